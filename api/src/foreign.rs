@@ -20,6 +20,7 @@ use crate::libwallet::{
 	BlockFees, CbData, Error, NodeClient, NodeVersionInfo, Slate, VersionInfo, WalletInst,
 	WalletLCProvider,
 };
+use crate::types::CoinbaseCache;
 use crate::util::secp::key::SecretKey;
 use crate::util::Mutex;
 use std::sync::Arc;
@@ -70,6 +71,8 @@ where
 	middleware: Option<ForeignCheckMiddleware>,
 	/// Stored keychain mask (in case the stored wallet seed is tokenized)
 	keychain_mask: Option<SecretKey>,
+	/// Coinbases recently built per height, shared between miner API requests
+	coinbase_cache: Option<Arc<Mutex<CoinbaseCache>>>,
 }
 
 impl<'a, L, C, K> Foreign<'a, L, C, K>
@@ -129,7 +132,12 @@ where
 	///
 	/// // A NodeClient must first be created to handle communication between
 	/// // the wallet and the node.
-	/// let node_client = HTTPNodeClient::new(&wallet_config.check_node_api_http_addr, None);
+	/// let node_client = HTTPNodeClient::new(
+	/// 	&wallet_config.check_node_api_http_addr,
+	/// 	None,
+	/// 	wallet_config.chain_type(),
+	/// )
+	/// .unwrap();
 	///
 	/// // impls::DefaultWalletImpl is provided for convenience in instantiating the wallet
 	/// // It contains the LMDBBackend, DefaultLCProvider (lifecycle) and ExtKeychain used
@@ -168,9 +176,17 @@ where
 			doctest_mode: false,
 			middleware,
 			keychain_mask,
+			coinbase_cache: None,
 		}
 	}
 
+	/// Sets the cache [`build_coinbase_at_height`](struct.Foreign.html#method.build_coinbase_at_height)
+	/// uses to answer repeated requests for the same block. The cache is usually shared
+	/// between all instances serving the miner API.
+	pub fn set_coinbase_cache(&mut self, cache: Arc<Mutex<CoinbaseCache>>) {
+		self.coinbase_cache = Some(cache);
+	}
+
 	/// Return the version capabilities of the running ForeignApi Node
 	/// # Arguments
 	/// None
@@ -268,6 +284,72 @@ where
 		)
 	}
 
+	/// Builds a coinbase output for a miner, deriving its key deterministically from the
+	/// block height rather than from the wallet's next available child index.
+	///
+	/// Requesting the same height twice always uses the same key, so a stratum server that
+	/// retries never leaves stray outputs behind. If a coinbase cache has been set with
+	/// [`set_coinbase_cache`](struct.Foreign.html#method.set_coinbase_cache), a repeated
+	/// request for the same height and fees returns exactly the same output and kernel.
+	///
+	/// # Arguments
+	///
+	/// * `block_fees` - A [`BlockFees`](../grin_wallet_libwallet/api_impl/types/struct.BlockFees.html)
+	/// struct. The `key_id` field is ignored.
+	///
+	/// # Returns
+	/// * `Ok`([`cb_data`](../grin_wallet_libwallet/api_impl/types/struct.CbData.html)`)` if successful.
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Foreign.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env_foreign!(wallet, wallet_config);
+	///
+	/// let mut api_foreign = Foreign::new(wallet.clone(), None, None);
+	///
+	/// let block_fees = BlockFees {
+	///		fees: 800000,
+	///		height: 234323,
+	///		key_id: None,
+	/// };
+	///
+	///	let res = api_foreign.build_coinbase_at_height(&block_fees);
+	///
+	/// if let Ok(cb_data) = res {
+	///		// cb_data.key_id is the same for every request at this height
+	///		// ...
+	/// }
+	/// ```
+
+	pub fn build_coinbase_at_height(&self, block_fees: &BlockFees) -> Result<CbData, Error> {
+		let mut cache = self.coinbase_cache.as_ref().map(|c| c.lock());
+		if let Some(cb) = cache.as_ref().and_then(|c| c.get(block_fees)) {
+			return Ok(cb);
+		}
+		let cb = {
+			let mut w_lock = self.wallet_inst.lock();
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			if let Some(m) = self.middleware.as_ref() {
+				m(
+					ForeignCheckMiddlewareFn::BuildCoinbase,
+					w.w2n_client().get_version_info(),
+					None,
+				)?;
+			}
+			foreign::build_coinbase_at_height(
+				&mut **w,
+				(&self.keychain_mask).as_ref(),
+				block_fees,
+				self.doctest_mode,
+			)?
+		};
+		if let Some(c) = cache.as_mut() {
+			c.insert(block_fees, cb.clone());
+		}
+		Ok(cb)
+	}
+
 	/// Verifies all messages in the slate match their public keys.
 	///
 	/// The option messages themselves are part of the `participant_data` field within the slate.
@@ -356,6 +438,8 @@ where
 	/// # Remarks
 	///
 	/// * This method will store a partially completed transaction in the wallet's transaction log.
+	/// * Slates that can't be received are logged, see
+	/// [`retrieve_failed_receives`](struct.Owner.html#method.retrieve_failed_receives).
 	///
 	/// # Example
 	/// Set up as in [new](struct.Foreign.html#method.new) method above.
@@ -383,21 +467,35 @@ where
 	) -> Result<Slate, Error> {
 		let mut w_lock = self.wallet_inst.lock();
 		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let mut res = Ok(());
 		if let Some(m) = self.middleware.as_ref() {
-			m(
+			res = m(
 				ForeignCheckMiddlewareFn::ReceiveTx,
 				w.w2n_client().get_version_info(),
 				Some(slate),
-			)?;
+			);
 		}
-		foreign::receive_tx(
-			&mut **w,
-			(&self.keychain_mask).as_ref(),
-			slate,
-			dest_acct_name,
-			message,
-			self.doctest_mode,
-		)
+		let res = res.and_then(|_| {
+			foreign::receive_tx(
+				&mut **w,
+				(&self.keychain_mask).as_ref(),
+				slate,
+				dest_acct_name,
+				message,
+				self.doctest_mode,
+			)
+		});
+		// Keep a trace of the slates that couldn't be received
+		if let Err(e) = &res {
+			let mask = (&self.keychain_mask).as_ref();
+			if let Err(log_err) = foreign::record_failed_receive(&mut **w, mask, slate, None, e) {
+				error!(
+					"Unable to log failed receive of slate {}: {}",
+					slate.id, log_err
+				);
+			}
+		}
+		res
 	}
 
 	/// Finalizes an invoice transaction initiated by this wallet's Owner api.
@@ -493,7 +591,12 @@ macro_rules! doctest_helper_setup_doc_env_foreign {
 		wallet_config.data_file_dir = dir.to_owned();
 		let pw = ZeroingString::from("");
 
-		let node_client = HTTPNodeClient::new(&wallet_config.check_node_api_http_addr, None);
+		let node_client = HTTPNodeClient::new(
+			&wallet_config.check_node_api_http_addr,
+			None,
+			wallet_config.chain_type(),
+		)
+		.unwrap();
 		let mut wallet = Box::new(
 			DefaultWalletImpl::<'static, HTTPNodeClient>::new(node_client.clone()).unwrap(),
 			)
@@ -39,8 +39,10 @@ extern crate log;
 
 mod foreign;
 mod foreign_rpc;
+mod miner_rpc;
 
 mod owner;
+mod owner_async;
 mod owner_rpc;
 mod owner_rpc_s;
 
@@ -48,7 +50,9 @@ mod types;
 
 pub use crate::foreign::{Foreign, ForeignCheckMiddleware, ForeignCheckMiddlewareFn};
 pub use crate::foreign_rpc::ForeignRpc;
-pub use crate::owner::Owner;
+pub use crate::miner_rpc::MinerRpc;
+pub use crate::owner::{Owner, OwnerContext};
+pub use crate::owner_async::{OwnerAsync, OwnerFuture};
 pub use crate::owner_rpc::OwnerRpc;
 pub use crate::owner_rpc_s::OwnerRpcS;
 
@@ -56,4 +60,7 @@ pub use crate::foreign_rpc::foreign_rpc as foreign_rpc_client;
 pub use crate::foreign_rpc::run_doctest_foreign;
 pub use crate::owner_rpc::run_doctest_owner;
 
-pub use types::{ECDHPubkey, EncryptedRequest, EncryptedResponse, EncryptionErrorResponse, Token};
+pub use types::{
+	response_mac_hex, verify_response_mac, CoinbaseCache, ECDHPubkey, EncryptedRequest,
+	EncryptedResponse, EncryptionErrorResponse, Token, RESPONSE_MAC_HEADER,
+};
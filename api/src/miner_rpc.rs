@@ -0,0 +1,51 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! JSON-RPC Stub generation for the Miner API
+
+use crate::keychain::Keychain;
+use crate::libwallet::{
+	BlockFees, CbData, ErrorKind, NodeClient, SlateVersion, VersionedCoinbase, WalletLCProvider,
+};
+use crate::Foreign;
+use easy_jsonrpc_mw;
+
+/// Public definition used to generate the Miner jsonrpc api.
+/// * When running `grin-wallet listen` with `miner_api_secret_path` set, the V2 api is
+/// available at `localhost:3415/v2/miner`, protected by basic auth with the username `miner`
+/// * The endpoint only supports POST operations, with the json-rpc request as the body
+#[easy_jsonrpc_mw::rpc]
+pub trait MinerRpc {
+	/**
+	Networked version of [Foreign::build_coinbase_at_height](struct.Foreign.html#method.build_coinbase_at_height).
+
+	# Json rpc example
+
+	*/
+
+	fn build_coinbase(&self, block_fees: &BlockFees) -> Result<VersionedCoinbase, ErrorKind>;
+}
+
+impl<'a, L, C, K> MinerRpc for Foreign<'a, L, C, K>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	fn build_coinbase(&self, block_fees: &BlockFees) -> Result<VersionedCoinbase, ErrorKind> {
+		let cb: CbData =
+			Foreign::build_coinbase_at_height(self, block_fees).map_err(|e| e.kind())?;
+		Ok(VersionedCoinbase::into_version(cb, SlateVersion::V2))
+	}
+}
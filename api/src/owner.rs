@@ -17,20 +17,44 @@
 use chrono::prelude::*;
 use uuid::Uuid;
 
-use crate::config::WalletConfig;
-use crate::core::core::Transaction;
+use crate::config::{WalletConfig, GRIN_WALLET_DIR};
+use crate::core::core::{amount_to_hr_string, Transaction};
 use crate::core::global;
-use crate::impls::create_sender;
+use crate::impls::{
+	available_space, create_sender, dir_size, last_backup_time, set_log_level, SlateDelivery,
+	SlateSender,
+};
 use crate::keychain::{Identifier, Keychain};
-use crate::libwallet::api_impl::owner;
+use crate::libwallet::api_impl::{foreign, owner};
 use crate::libwallet::{
-	AcctPathMapping, Error, ErrorKind, InitTxArgs, IssueInvoiceTxArgs, NodeClient,
-	NodeHeightResult, OutputCommitMapping, Slate, TxLogEntry, WalletInfo, WalletInst,
-	WalletLCProvider,
+	catalog_locale, AcctPathMapping, ApprovalToken, ArchivedSlate, BalanceHold, ChangeRecord,
+	ChildIndexScan, CoinbaseReport, DeliveryReceipt, DerivationScheme, Error, ErrorKind,
+	FailedReceive, FeeRateEstimate, ForeignApiKey, FreezeState, IndexRebuildReport, InitTxArgs,
+	InitTxResult, InitTxSendArgs, IssueInvoiceTxArgs, NodeClient, NodeClientStats,
+	NodeHeightResult, NodeStatus, OnetimeAddress, OperationStatus, OutputCommitMapping, OutputData,
+	OutputDerivationAudit, ParticipantAttachmentData, PaymentProof, PendingDispatch,
+	PendingOperations, PingResult, RecipientHandshake, ReconcileArgs, RefreshReport,
+	RefusedTxState, ReserveOutputsArgs, RestoreScan, SendHandle, SendMetrics, Slate,
+	SlateDescription, SourceFilter, SweepArgs, SweepPlan, SweepProgress, TotpSetup, TxCancelResult,
+	TxDetails, TxExportEntry, TxFilter, TxLogEntry, VaultSettings, ViewOnlyAttestation,
+	ViewOnlyBundle, WalletBackend, WalletEvent, WalletInfo, WalletInst, WalletLCProvider,
+	WalletStats, WatchOnlyKey,
 };
 use crate::util::secp::key::SecretKey;
-use crate::util::{from_hex, static_secp_instance, LoggingConfig, Mutex, ZeroingString};
+use crate::util::secp::pedersen;
+use crate::util::{from_hex, static_secp_instance, LogLevel, LoggingConfig, Mutex, ZeroingString};
+use std::cmp;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Seconds between checks of the confirmation of a sweep transaction
+const SWEEP_POLL_SECS: u64 = 10;
+
+/// Latest version of the owner API
+const OWNER_API_VERSION: u16 = 3;
 
 /// Main interface into all wallet API functions.
 /// Wallet APIs are split into two seperate blocks of functionality
@@ -58,6 +82,13 @@ where
 	pub doctest_mode: bool,
 	/// Share ECDH key
 	pub shared_key: Arc<Mutex<Option<SecretKey>>>,
+	/// Shuts down the listener serving this API, if any, for the `shutdown` method
+	pub shutdown_hook: Option<Arc<dyn Fn() + Send + Sync>>,
+	/// Code of the second factor calls are made with, see
+	/// [`set_totp_code`](struct.Owner.html#method.set_totp_code)
+	totp_code: Option<String>,
+	/// Whether the code was accepted, it's only checked once
+	totp_verified: Arc<AtomicBool>,
 }
 
 impl<'a, L, C, K> Owner<'a, L, C, K>
@@ -112,7 +143,12 @@ where
 	///
 	/// // A NodeClient must first be created to handle communication between
 	/// // the wallet and the node.
-	/// let node_client = HTTPNodeClient::new(&wallet_config.check_node_api_http_addr, None);
+	/// let node_client = HTTPNodeClient::new(
+	/// 	&wallet_config.check_node_api_http_addr,
+	/// 	None,
+	/// 	wallet_config.chain_type(),
+	/// )
+	/// .unwrap();
 	///
 	/// // impls::DefaultWalletImpl is provided for convenience in instantiating the wallet
 	/// // It contains the LMDBBackend, DefaultLCProvider (lifecycle) and ExtKeychain used
@@ -146,9 +182,106 @@ where
 			wallet_inst,
 			doctest_mode: false,
 			shared_key: Arc::new(Mutex::new(None)),
+			shutdown_hook: None,
+			totp_code: None,
+			totp_verified: Arc::new(AtomicBool::new(false)),
 		}
 	}
 
+	/// Sets the code of the second factor calls of this instance are made with, once
+	/// one is enabled with [`confirm_totp`](struct.Owner.html#method.confirm_totp). Sending,
+	/// finalizing, posting or cancelling transactions and changing the security settings
+	/// of the wallet are refused without a current code, or a recovery code. As each code
+	/// is accepted once, it's checked by the first call needing it, and later calls of
+	/// this instance go through.
+	///
+	/// # Arguments
+	/// * `code` - A current code of the authenticator app, or a recovery code.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// api_owner.set_totp_code(Some("123456".to_owned()));
+	/// ```
+
+	pub fn set_totp_code(&mut self, code: Option<String>) {
+		self.totp_code = code;
+		self.totp_verified = Arc::new(AtomicBool::new(false));
+	}
+
+	/// Checks the code of the second factor set with
+	/// [`set_totp_code`](struct.Owner.html#method.set_totp_code)
+	fn check_totp(
+		&self,
+		w: &mut (dyn WalletBackend<'a, C, K> + 'a),
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<(), Error> {
+		let code = self.totp_code.as_ref().map(|c| c.as_str());
+		check_totp(w, keychain_mask, code, &self.totp_verified)
+	}
+
+	/// Locks the wallet once and runs `f` with an [`OwnerContext`](struct.OwnerContext.html)
+	/// holding that lock. Operations made of several steps (e.g. creating a transaction, then
+	/// locking its outputs and finalizing it) should be composed through the context rather
+	/// than by calling other `Owner` methods, which would try to lock the wallet again and
+	/// deadlock if called from inside `f`.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `f` - The operation to run while the wallet is locked.
+	///
+	/// # Returns
+	/// * The result of `f`
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if the wallet can't
+	/// be opened.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 2,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: true,
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.with_context(None, |ctx| {
+	/// 	let slate = ctx.init_send_tx(args)?;
+	/// 	ctx.tx_lock_outputs(&slate, 0)?;
+	/// 	Ok(slate)
+	/// });
+	///
+	/// if let Ok(slate) = result {
+	/// 	// Send slate somehow
+	/// 	// ...
+	/// }
+	/// ```
+
+	pub fn with_context<F, R>(&self, keychain_mask: Option<&SecretKey>, f: F) -> Result<R, Error>
+	where
+		F: FnOnce(&mut OwnerContext<'_, 'a, C, K>) -> Result<R, Error>,
+	{
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let mut ctx = OwnerContext {
+			w: &mut **w,
+			keychain_mask: keychain_mask.cloned(),
+			doctest_mode: self.doctest_mode,
+			totp_code: self.totp_code.clone(),
+			totp_verified: self.totp_verified.clone(),
+		};
+		f(&mut ctx)
+	}
+
 	/// Returns a list of accounts stored in the wallet (i.e. mappings between
 	/// user-specified labels and BIP32 derivation paths.
 	/// # Arguments
@@ -238,7 +371,57 @@ where
 	) -> Result<Identifier, Error> {
 		let mut w_lock = self.wallet_inst.lock();
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::create_account_path(&mut **w, keychain_mask, label)
+		owner::create_account_path(&mut **w, keychain_mask, label, DerivationScheme::Standard)
+	}
+
+	/// Creates a new 'account' as [`create_account_path`](struct.Owner.html#method.create_account_path)
+	/// does, with the keys of the account derived with the given scheme.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `label` - A human readable label to which to map the new BIP32 Path
+	/// * `scheme` - The [`DerivationScheme`](../grin_wallet_libwallet/types/enum.DerivationScheme.html)
+	/// of the account's keys
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [Keychain Identifier](../grin_keychain/struct.Identifier.html) for the new path
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Remarks
+	///
+	/// * Hardened accounts are at paths `m/k'/0`. The account index `k` is shared by
+	/// both schemes, so the first account created after `m/1/0` is at `m/2/0` or `m/2'/0`.
+	/// * Existing accounts keep their scheme, and outputs of both schemes are found
+	/// by the updater and restored from the seed.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	/// use grin_wallet_libwallet::DerivationScheme;
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	///
+	/// let result =
+	/// 	api_owner.create_account_path_with_scheme(None, "savings", DerivationScheme::Hardened);
+	///
+	/// if let Ok(identifier) = result {
+	///		//...
+	/// }
+	/// ```
+
+	pub fn create_account_path_with_scheme(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		label: &str,
+		scheme: DerivationScheme,
+	) -> Result<Identifier, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::create_account_path(&mut **w, keychain_mask, label, scheme)
 	}
 
 	/// Sets the wallet's currently active account. This sets the
@@ -291,6 +474,132 @@ where
 		owner::set_active_account(&mut **w, label)
 	}
 
+	/// Returns the child index the next output created in the given account will be derived
+	/// with, i.e. the secret key of the next output will be at path `<account path>/<index>`.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `label` - The human readable label for the account.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * The next child index of the account if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Remarks
+	///
+	/// * This function does not need to use the root wallet seed or keychain.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	///
+	/// let result = api_owner.get_next_child_index(None, "default");
+	/// ```
+
+	pub fn get_next_child_index(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		label: &str,
+	) -> Result<u32, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::get_next_child_index(&mut **w, label)
+	}
+
+	/// Sets the child index the next output created in the given account will be derived with.
+	/// Intended for recovery tools repairing the index counter, e.g. after outputs were found
+	/// at indices beyond it (see [`scan_child_indices`](struct.Owner.html#method.scan_child_indices)).
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `label` - The human readable label for the account.
+	/// * `index` - The new next child index. Must be above the highest index used by an output
+	/// of the account in the wallet, so keys are never reused.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * `Ok(())` if the index was set
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	///
+	/// let result = api_owner.scan_child_indices(None, "default");
+	///
+	/// if let Ok(scan) = result {
+	///		// move the counter past any index already in use
+	///		if let Some(h) = scan.used_beyond_next.last() {
+	///			let result2 = api_owner.set_next_child_index(None, "default", h + 1);
+	///		}
+	/// }
+	/// ```
+
+	pub fn set_next_child_index(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		label: &str,
+		index: u32,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::set_next_child_index(&mut **w, keychain_mask, label, index)
+	}
+
+	/// Scans the outputs in the wallet for the child indices the given account has used,
+	/// reporting unused gaps below the next child index, and any indices used at or beyond it
+	/// which new outputs would otherwise reuse.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `label` - The human readable label for the account.
+	///
+	/// # Returns
+	/// * Result Containing:
+	/// * A [`ChildIndexScan`](../grin_wallet_libwallet/api_impl/types/struct.ChildIndexScan.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Remarks
+	///
+	/// * Only outputs known to the wallet are considered. Run
+	/// [`check_repair`](struct.Owner.html#method.check_repair) first to pick up outputs only
+	/// present on the chain.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	///
+	/// let result = api_owner.scan_child_indices(None, "default");
+	/// ```
+
+	pub fn scan_child_indices(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		label: &str,
+	) -> Result<ChildIndexScan, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::scan_child_indices(&mut **w, label)
+	}
+
 	/// Returns a list of outputs from the active account in the wallet.
 	///
 	/// # Arguments
@@ -425,120 +734,3486 @@ where
 		Ok(res)
 	}
 
-	/// Returns summary information from the active account in the wallet.
+	/// Returns the [Transaction Log Entries](../grin_wallet_libwallet/types/struct.TxLogEntry.html)
+	/// of the active account saved since a change cursor, so a poller only fetches what changed
+	/// since its last call. The wallet keeps a change counter, incremented whenever a
+	/// transaction log entry or an output is saved, which each entry records.
 	///
 	/// # Arguments
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
 	/// * `refresh_from_node` - If true, the wallet will attempt to contact
 	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
-	/// provided during wallet instantiation). If `false`, the results will
-	/// contain transaction information that may be out-of-date (from the last time
-	/// the wallet's output set was refreshed against the node).
-	/// * `minimum_confirmations` - The minimum number of confirmations an output
-	/// should have before it's included in the 'amount_currently_spendable' total
+	/// provided during wallet instantiation). Entries changed by the refresh are returned.
+	/// * `since` - The cursor returned by the previous call, 0 to get all the entries.
 	///
 	/// # Returns
-	/// * (`bool`, [`WalletInfo`](../grin_wallet_libwallet/types/struct.WalletInfo.html)) - A tuple:
+	/// * `(bool, u64, Vec<TxLogEntry>)` - A tuple:
 	/// * The first `bool` element indicates whether the data was successfully
-	/// refreshed from the node (note this may be false even if the `refresh_from_node`
-	/// argument was set to `true`.
-	/// * The second element contains the Summary [`WalletInfo`](../grin_wallet_libwallet/types/struct.WalletInfo.html)
+	/// refreshed from the node.
+	/// * The second element is the cursor to give to the next call.
+	/// * The third element contains the
+	/// [TxLogEntries](../grin_wallet_libwallet/types/struct.TxLogEntry.html) saved since `since`.
 	///
 	/// # Example
 	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
-	/// let mut api_owner = Owner::new(wallet.clone());
-	/// let update_from_node = true;
-	/// let minimum_confirmations=10;
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let mut cursor = 0;
 	///
-	/// // Return summary info for active account
-	/// let result = api_owner.retrieve_summary_info(None, update_from_node, minimum_confirmations);
+	/// // Poll for the changed TxLogEntries
+	/// let result = api_owner.retrieve_txs_since(None, true, cursor);
 	///
-	/// if let Ok((was_updated, summary_info)) = result {
+	/// if let Ok((was_updated, next_cursor, tx_log_entries)) = result {
+	///		cursor = next_cursor;
 	///		//...
 	/// }
 	/// ```
 
-	pub fn retrieve_summary_info(
+	pub fn retrieve_txs_since(
 		&self,
 		keychain_mask: Option<&SecretKey>,
 		refresh_from_node: bool,
-		minimum_confirmations: u64,
-	) -> Result<(bool, WalletInfo), Error> {
+		since: u64,
+	) -> Result<(bool, u64, Vec<TxLogEntry>), Error> {
 		let mut w_lock = self.wallet_inst.lock();
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::retrieve_summary_info(
-			&mut **w,
-			keychain_mask,
-			refresh_from_node,
-			minimum_confirmations,
-		)
+		owner::retrieve_txs_since(&mut **w, keychain_mask, refresh_from_node, since)
 	}
 
-	/// Initiates a new transaction as the sender, creating a new
-	/// [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html) object containing
-	/// the sender's inputs, change outputs, and public signature data. This slate can
-	/// then be sent to the recipient to continue the transaction via the
-	/// [Foreign API's `receive_tx`](struct.Foreign.html#method.receive_tx) method.
-	///
-	/// When a transaction is created, the wallet must also lock inputs (and create unconfirmed
-	/// outputs) corresponding to the transaction created in the slate, so that the wallet doesn't
-	/// attempt to re-spend outputs that are already included in a transaction before the transaction
-	/// is confirmed. This method also returns a function that will perform that locking, and it is
-	/// up to the caller to decide the best time to call the lock function
-	/// (via the [`tx_lock_outputs`](struct.Owner.html#method.tx_lock_outputs) method).
-	/// If the exchange method is intended to be synchronous (such as via a direct http call,)
-	/// then the lock call can wait until the response is confirmed. If it is asynchronous, (such
-	/// as via file transfer,) the lock call should happen immediately (before the file is sent
-	/// to the recipient).
-	///
-	/// If the `send_args` [`InitTxSendArgs`](../grin_wallet_libwallet/types/struct.InitTxSendArgs.html),
-	/// of the [`args`](../grin_wallet_libwallet/types/struct.InitTxArgs.html), field is Some, this
-	/// function will attempt to perform a synchronous send to the recipient specified in the `dest`
-	/// field according to the `method` field, and will also finalize and post the transaction if
-	/// the `finalize` field is set.
+	/// Returns the outputs of the active account saved since a change cursor, as
+	/// [`retrieve_txs_since`](struct.Owner.html#method.retrieve_txs_since) does for
+	/// transactions. Spent outputs are included, so a poller learns outputs were spent.
+	/// Outputs deleted by the cancellation of a transaction aren't returned, the cancelled
+	/// transaction is.
 	///
 	/// # Arguments
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `args` - [`InitTxArgs`](../grin_wallet_libwallet/types/struct.InitTxArgs.html),
-	/// transaction initialization arguments. See struct documentation for further detail.
+	/// * `refresh_from_node` - If true, the wallet will attempt to contact
+	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
+	/// provided during wallet instantiation). Outputs changed by the refresh are returned.
+	/// * `since` - The cursor returned by the previous call, 0 to get all the outputs.
 	///
 	/// # Returns
-	/// * a result containing:
-	/// * The transaction [Slate](../grin_wallet_libwallet/slate/struct.Slate.html),
-	/// which can be forwarded to the recieving party by any means. Once the caller is relatively
-	/// certain that the transaction has been sent to the recipient, the associated wallet
-	/// transaction outputs should be locked via a call to
-	/// [`tx_lock_outputs`](struct.Owner.html#method.tx_lock_outputs). This must be called before calling
-	/// [`finalize_tx`](struct.Owner.html#method.finalize_tx).
-	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
-	///
-	/// # Remarks
-	///
-	/// * This method requires an active connection to a node, and will fail with error if a node
-	/// cannot be contacted to refresh output statuses.
-	/// * This method will store a partially completed transaction in the wallet's transaction log,
-	/// which will be updated on the corresponding call to [`finalize_tx`](struct.Owner.html#method.finalize_tx).
+	/// * `(bool, u64, Vec<OutputCommitMapping>)` - A tuple:
+	/// * The first `bool` element indicates whether the data was successfully
+	/// refreshed from the node.
+	/// * The second element is the cursor to give to the next call.
+	/// * The third element contains the
+	/// [OutputCommitMapping](../grin_wallet_libwallet/types/struct.OutputCommitMapping.html)s
+	/// of the outputs saved since `since`.
 	///
 	/// # Example
-	/// Set up as in [new](struct.Owner.html#method.new) method above.
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
-	/// let mut api_owner = Owner::new(wallet.clone());
-	/// // Attempt to create a transaction using the 'default' account
-	/// let args = InitTxArgs {
-	/// 	src_acct_name: None,
+	/// let api_owner = Owner::new(wallet.clone());
+	///
+	/// let result = api_owner.retrieve_outputs_since(None, true, 0);
+	///
+	/// if let Ok((was_updated, cursor, output_mappings)) = result {
+	///		//...
+	/// }
+	/// ```
+
+	pub fn retrieve_outputs_since(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		refresh_from_node: bool,
+		since: u64,
+	) -> Result<(bool, u64, Vec<OutputCommitMapping>), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::retrieve_outputs_since(&mut **w, keychain_mask, refresh_from_node, since)
+	}
+
+	/// Returns everything the wallet knows about a transaction of the active account in a
+	/// single call: its [Transaction Log Entry](../grin_wallet_libwallet/types/struct.TxLogEntry.html),
+	/// the outputs it spends, creates and returns as change, the excess of its kernel,
+	/// whether it's stored in the wallet and its number of confirmations.
+	///
+	/// Outputs are those currently linked to the transaction log entry, an output created
+	/// by the transaction and spent since is listed with the spending transaction.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `refresh_from_node` - If true, the wallet will attempt to contact
+	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
+	/// provided during wallet instantiation). If `false`, the results will
+	/// contain transaction information that may be out-of-date (from the last time
+	/// the wallet's output set was refreshed against the node).
+	/// * `tx_slate_id` - The [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html) uuid
+	/// of the transaction. For a send to self, the sent entry is returned.
+	///
+	/// # Returns
+	/// * `(bool, TxDetails)` - A tuple:
+	/// * The first `bool` element indicates whether the data was successfully
+	/// refreshed from the node (note this may be false even if the `refresh_from_node`
+	/// argument was set to `true`.
+	/// * The second element contains the
+	/// [`TxDetails`](../grin_wallet_libwallet/api_impl/types/struct.TxDetails.html)
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if the
+	/// transaction doesn't exist.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let update_from_node = true;
+	///
+	/// let result = api_owner.retrieve_txs(None, false, None, None);
+	///
+	/// if let Ok((_, txs)) = result {
+	///		for t in txs.iter().filter(|t| t.tx_slate_id.is_some()) {
+	///			let slate_id = t.tx_slate_id.unwrap();
+	///			let details = api_owner.get_tx_details(None, update_from_node, slate_id);
+	///			//...
+	///		}
+	/// }
+	/// ```
+
+	pub fn get_tx_details(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		refresh_from_node: bool,
+		tx_slate_id: Uuid,
+	) -> Result<(bool, TxDetails), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let mut res =
+			owner::get_tx_details(&mut **w, keychain_mask, refresh_from_node, tx_slate_id)?;
+		if self.doctest_mode {
+			res.1.tx.confirmation_ts = Some(Utc.ymd(2019, 1, 15).and_hms(16, 1, 26));
+			res.1.tx.creation_ts = Utc.ymd(2019, 1, 15).and_hms(16, 1, 26);
+		}
+		Ok(res)
+	}
+
+	/// Exports the transactions of the active account for accounting, with the fiat value
+	/// of the amounts received and sent at the price of the coin when they were, and the
+	/// gains realized by the confirmed sends. A send realizes the difference between the
+	/// value of the amount and fee it sends and the cost basis of that share of the outputs
+	/// it spent, the prices of the coin when they were received. Fiat values are in
+	/// hundredths of the currency.
+	///
+	/// Prices are recorded as coins are received and sent when an `accounting_currency` and
+	/// a `price_provider_url` are configured, and can be set by hand with
+	/// [`set_tx_price`](struct.Owner.html#method.set_tx_price) and
+	/// [`set_output_cost_basis`](struct.Owner.html#method.set_output_cost_basis). No value
+	/// is given for a transaction without a price, and no gain for a send spending an output
+	/// without a cost basis.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `refresh_from_node` - If true, the wallet will attempt to contact
+	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
+	/// provided during wallet instantiation). If `false`, the results will
+	/// contain transaction information that may be out-of-date (from the last time
+	/// the wallet's output set was refreshed against the node).
+	///
+	/// # Returns
+	/// * `(bool, Vec<TxExportEntry>)` - A tuple:
+	/// * The first `bool` element indicates whether the data was successfully
+	/// refreshed from the node (note this may be false even if the `refresh_from_node`
+	/// argument was set to `true`.
+	/// * The second element contains a vector of
+	/// [`TxExportEntry`](../grin_wallet_libwallet/api_impl/types/struct.TxExportEntry.html)
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.export_txs(None, true);
+	///
+	/// if let Ok((_, entries)) = result {
+	///		let gains: i64 = entries.iter().filter_map(|e| e.realized_gain).sum();
+	///		//...
+	/// }
+	/// ```
+
+	pub fn export_txs(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		refresh_from_node: bool,
+	) -> Result<(bool, Vec<TxExportEntry>), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::export_txs(&mut **w, keychain_mask, refresh_from_node)
+	}
+
+	/// Sets by hand the price of the coin a transaction of the active account was sent or
+	/// received at, when no price provider was configured or it couldn't be reached. The
+	/// price of a received transaction becomes the cost basis of the outputs it received and
+	/// the wallet still holds. The price is dated at the confirmation of the transaction, or
+	/// its creation if it's not confirmed.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_slate_id` - The [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html) uuid
+	/// of the transaction.
+	/// * `currency` - Fiat currency of the price, e.g. `USD`.
+	/// * `unit_price` - Price of a whole coin, in hundredths of the currency.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if the
+	/// transaction doesn't exist.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_txs(None, false, None, None);
+	///
+	/// if let Ok((_, txs)) = result {
+	///		for t in txs.iter().filter(|t| t.tx_slate_id.is_some() && t.price.is_none()) {
+	///			let result = api_owner.set_tx_price(None, t.tx_slate_id.unwrap(), "USD", 1250);
+	///			//...
+	///		}
+	/// }
+	/// ```
+
+	pub fn set_tx_price(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_slate_id: Uuid,
+		currency: &str,
+		unit_price: u64,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::set_tx_price(&mut **w, keychain_mask, tx_slate_id, currency, unit_price)
+	}
+
+	/// Sets by hand the cost basis of an output of the active account, the price of the coin
+	/// when it was acquired, e.g. for an output restored from the seed, or mined. A spent
+	/// output can be given one too, for the gain of the send that spent it.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `commitment` - Commitment of the output, in hex.
+	/// * `currency` - Fiat currency of the price, e.g. `USD`.
+	/// * `unit_price` - Price of a whole coin, in hundredths of the currency.
+	/// * `acquired` - When the output was acquired.
+	///
+	/// # Returns
+	/// * The updated [`OutputData`](../grin_wallet_libwallet/types/struct.OutputData.html)
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if the active
+	/// account has no such output.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	/// use chrono::Utc;
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_outputs(None, false, false, None);
+	///
+	/// if let Ok((_, outputs)) = result {
+	///		for o in outputs.iter().filter(|o| o.output.cost_basis.is_none()) {
+	///			let commit = o.output.commit.clone().unwrap();
+	///			let result = api_owner.set_output_cost_basis(None, &commit, "USD", 1250, Utc::now());
+	///			//...
+	///		}
+	/// }
+	/// ```
+
+	pub fn set_output_cost_basis(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		commitment: &str,
+		currency: &str,
+		unit_price: u64,
+		acquired: DateTime<Utc>,
+	) -> Result<OutputData, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::set_output_cost_basis(
+			&mut **w,
+			keychain_mask,
+			commitment,
+			currency,
+			unit_price,
+			acquired,
+		)
+	}
+
+	/// Reconciles an externally kept ledger, such as an exchange's record of deposits
+	/// and withdrawals, against the transactions and balance of the active account.
+	/// Ledger entries are matched to wallet transactions by slate id. The report lists
+	/// expected deposits not received (or not confirmed yet), expected withdrawals not
+	/// sent, wallet transactions missing from the ledger and amounts that differ.
+	///
+	/// Ledgers kept as JSON or CSV can be parsed with
+	/// [`parse_ledger`](../grin_wallet_libwallet/api_impl/owner/fn.parse_ledger.html).
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `refresh_from_node` - If true, the wallet will attempt to contact
+	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
+	/// provided during wallet instantiation). If `false`, the results will
+	/// contain transaction information that may be out-of-date (from the last time
+	/// the wallet's output set was refreshed against the node).
+	/// * `args` - [`ReconcileArgs`](../grin_wallet_libwallet/api_impl/types/struct.ReconcileArgs.html),
+	/// the ledger entries and optionally the expected balance.
+	///
+	/// # Returns
+	/// * `(bool, ReconciliationReport)` - A tuple:
+	/// * The first `bool` element indicates whether the data was successfully
+	/// refreshed from the node (note this may be false even if the `refresh_from_node`
+	/// argument was set to `true`.
+	/// * The second element contains the
+	/// [`ReconciliationReport`](../grin_wallet_libwallet/api_impl/types/struct.ReconciliationReport.html)
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let args = ReconcileArgs {
+	///		entries: vec![],
+	///		expected_balance: Some(0),
+	///		since: None,
+	/// };
+	/// let result = api_owner.reconcile(None, true, args);
+	///
+	/// if let Ok((_, report)) = result {
+	///		if !report.is_reconciled() {
+	///			// investigate
+	///		}
+	/// }
+	/// ```
+
+	pub fn reconcile(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		refresh_from_node: bool,
+		args: ReconcileArgs,
+	) -> Result<(bool, ReconciliationReport), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::reconcile(&mut **w, keychain_mask, refresh_from_node, args)
+	}
+
+	/// Exports a view-only bundle of the wallet, for an auditor to check the balance the wallet
+	/// reports against a node. The bundle holds the commitments and values of the unspent
+	/// outputs of all the accounts, along with the rewind hash of the wallet, which lets the
+	/// range proofs of the outputs be rewound to recover their values. It holds no key to spend
+	/// the outputs, but reveals the values of all the outputs of the wallet to whoever has it.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `refresh_from_node` - If true, the wallet will attempt to contact
+	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
+	/// provided during wallet instantiation). If `false`, the bundle may be out-of-date.
+	///
+	/// # Returns
+	/// * (`bool`, [`ViewOnlyBundle`](../grin_wallet_libwallet/view_only/struct.ViewOnlyBundle.html)) - A tuple:
+	/// * The first `bool` element indicates whether the data was successfully
+	/// refreshed from the node (note this may be false even if the `refresh_from_node`
+	/// argument was set to `true`.
+	/// * The second element contains the bundle.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.export_view_only_bundle(None, true);
+	///
+	/// if let Ok((_, bundle)) = result {
+	/// 	println!("{} outputs worth {}", bundle.outputs.len(), bundle.total);
+	/// }
+	/// ```
+
+	pub fn export_view_only_bundle(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		refresh_from_node: bool,
+	) -> Result<(bool, ViewOnlyBundle), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::export_view_only_bundle(&mut **w, keychain_mask, refresh_from_node)
+	}
+
+	/// Audits a view-only bundle exported by
+	/// [`export_view_only_bundle`](struct.Owner.html#method.export_view_only_bundle), by this
+	/// or another wallet, against the node of this wallet. Each output of the bundle has to be
+	/// unspent, with a range proof that rewinds with the rewind hash of the bundle to the value
+	/// reported for it. The result is signed by this wallet, and can be checked by anyone with
+	/// [`ViewOnlyAttestation::verify`](../grin_wallet_libwallet/view_only/struct.ViewOnlyAttestation.html#method.verify).
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `bundle` - The view-only bundle to audit.
+	///
+	/// # Returns
+	/// * Ok with the signed [`ViewOnlyAttestation`](../grin_wallet_libwallet/view_only/struct.ViewOnlyAttestation.html)
+	/// if successful, listing the outputs found missing or of another value
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// if let Ok((_, bundle)) = api_owner.export_view_only_bundle(None, true) {
+	/// 	if let Ok(attestation) = api_owner.audit_view_only_bundle(None, &bundle) {
+	/// 		assert!(attestation.verify(Some(&bundle)).is_ok());
+	/// 	}
+	/// }
+	/// ```
+
+	pub fn audit_view_only_bundle(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		bundle: &ViewOnlyBundle,
+	) -> Result<ViewOnlyAttestation, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::audit_view_only_bundle(&mut **w, keychain_mask, bundle)
+	}
+
+	/// Returns the root public key of the wallet, which a watch-only wallet following the
+	/// outputs of this one can be created from with
+	/// [`create_watch_only_wallet`](struct.Owner.html#method.create_watch_only_wallet). It
+	/// holds no key to spend the outputs, but reveals all the outputs of the wallet, past
+	/// and future, to whoever has it.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with the [`WatchOnlyKey`](../grin_wallet_libwallet/view_only/struct.WatchOnlyKey.html)
+	/// of the wallet if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.watch_only_key(None);
+	///
+	/// if let Ok(key) = result {
+	/// 	println!("Root public key {}", key.root_public_key);
+	/// }
+	/// ```
+
+	pub fn watch_only_key(&self, keychain_mask: Option<&SecretKey>) -> Result<WatchOnlyKey, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::watch_only_key(&mut **w, keychain_mask)
+	}
+
+	/// Returns summary information from the active account in the wallet.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `refresh_from_node` - If true, the wallet will attempt to contact
+	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
+	/// provided during wallet instantiation). If `false`, the results will
+	/// contain transaction information that may be out-of-date (from the last time
+	/// the wallet's output set was refreshed against the node).
+	/// * `minimum_confirmations` - The minimum number of confirmations an output
+	/// should have before it's included in the 'amount_currently_spendable' total
+	///
+	/// # Returns
+	/// * (`bool`, [`WalletInfo`](../grin_wallet_libwallet/types/struct.WalletInfo.html)) - A tuple:
+	/// * The first `bool` element indicates whether the data was successfully
+	/// refreshed from the node (note this may be false even if the `refresh_from_node`
+	/// argument was set to `true`.
+	/// * The second element contains the Summary [`WalletInfo`](../grin_wallet_libwallet/types/struct.WalletInfo.html)
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let update_from_node = true;
+	/// let minimum_confirmations=10;
+	///
+	/// // Return summary info for active account
+	/// let result = api_owner.retrieve_summary_info(None, update_from_node, minimum_confirmations);
+	///
+	/// if let Ok((was_updated, summary_info)) = result {
+	///		//...
+	/// }
+	/// ```
+
+	pub fn retrieve_summary_info(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		refresh_from_node: bool,
+		minimum_confirmations: u64,
+	) -> Result<(bool, WalletInfo), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::retrieve_summary_info(
+			&mut **w,
+			keychain_mask,
+			refresh_from_node,
+			minimum_confirmations,
+		)
+	}
+
+	/// Updates the outputs and transactions of the wallet from the node, and reports how
+	/// it went. Outputs whose query to the node failed are left as they were and counted
+	/// in the report, unlike the `bool` the `retrieve_*` methods return, which only tells
+	/// whether the refresh was complete. Nothing is updated while the node is syncing.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with a [`RefreshReport`](../grin_wallet_libwallet/types/struct.RefreshReport.html)
+	/// if the node could be reached
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.update_wallet_state(None);
+	///
+	/// if let Ok(report) = result {
+	/// 	if !report.is_complete() {
+	/// 		println!("{} outputs not refreshed", report.outputs_failed);
+	/// 	}
+	/// }
+	/// ```
+
+	pub fn update_wallet_state(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<RefreshReport, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::update_wallet_state(&mut **w, keychain_mask, false)
+	}
+
+	/// Returns the coinbase outputs the active account built for mined blocks, with their
+	/// status. Confirmed coinbases that disappear from the chain before reaching maturity
+	/// belong to orphaned blocks; a refresh marks them unusable, so they no longer count
+	/// towards the wallet balance, and records their transaction as `OrphanedCoinbase`.
+	/// They keep being looked up until their height is past the finality depth, and
+	/// are confirmed again if their block is reorged back in.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `refresh_from_node` - If true, the wallet will attempt to contact
+	/// a node (via the [`NodeClient`](../grin_wallet_libwallet/types/trait.NodeClient.html)
+	/// provided during wallet instantiation), detecting orphaned coinbases. If `false`, the
+	/// report may be out-of-date.
+	///
+	/// # Returns
+	/// * (`bool`, [`CoinbaseReport`](../grin_wallet_libwallet/types/struct.CoinbaseReport.html)) - A tuple:
+	/// * The first `bool` element indicates whether the data was successfully
+	/// refreshed from the node (note this may be false even if the `refresh_from_node`
+	/// argument was set to `true`.
+	/// * The second element contains the [`CoinbaseReport`](../grin_wallet_libwallet/types/struct.CoinbaseReport.html)
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	///
+	/// let result = api_owner.retrieve_coinbase_report(None, true);
+	///
+	/// if let Ok((was_updated, report)) = result {
+	///		//...
+	/// }
+	/// ```
+
+	pub fn retrieve_coinbase_report(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		refresh_from_node: bool,
+	) -> Result<(bool, CoinbaseReport), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::retrieve_coinbase_report(&mut **w, keychain_mask, refresh_from_node)
+	}
+
+	/// Returns a stable, non-sensitive fingerprint of the wallet seed, derived by hashing
+	/// the root public key. It can be used to verify which seed a given data directory
+	/// belongs to without revealing the recovery phrase. The same fingerprint is written
+	/// to new seed files and logged whenever the wallet is opened.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with the fingerprint as a 16 character hex string if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	///
+	/// let result = api_owner.wallet_fingerprint(None);
+	///
+	/// if let Ok(fingerprint) = result {
+	///		//...
+	/// }
+	/// ```
+
+	pub fn wallet_fingerprint(&self, keychain_mask: Option<&SecretKey>) -> Result<String, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::wallet_fingerprint(&mut **w, keychain_mask)
+	}
+
+	/// Holds enough outputs of the active account to cover an upcoming scheduled payment of
+	/// `amount` and its fee. Until the hold expires, sends only select these outputs if their
+	/// [`InitTxArgs`](../grin_wallet_libwallet/types/struct.InitTxArgs.html) `reservation` is
+	/// the reservation's label. Calling this again with the same label adjusts the held
+	/// outputs to the new amount and extends the hold.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `args` - [`ReserveOutputsArgs`](../grin_wallet_libwallet/types/struct.ReserveOutputsArgs.html),
+	/// the label, amount and duration of the reservation.
+	///
+	/// # Returns
+	/// * Ok with the held [`OutputData`](../grin_wallet_libwallet/types/struct.OutputData.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// e.g. if the unreserved outputs can't cover the amount.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let args = ReserveOutputsArgs {
+	/// 	label: "rent".to_owned(),
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	expires_in_secs: 7 * 24 * 3600,
+	/// };
+	/// let result = api_owner.reserve_outputs(None, args);
+	///
+	/// if let Ok(_) = result {
+	/// 	// When the payment is due
+	/// 	let args = InitTxArgs {
+	/// 		amount: 2_000_000_000,
+	/// 		reservation: Some("rent".to_owned()),
+	/// 		..Default::default()
+	/// 	};
+	/// 	let result = api_owner.init_send_tx(None, args);
+	/// }
+	/// ```
+
+	pub fn reserve_outputs(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		args: ReserveOutputsArgs,
+	) -> Result<Vec<OutputData>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::reserve_outputs(&mut **w, keychain_mask, args)
+	}
+
+	/// Releases the outputs held by [`reserve_outputs`](struct.Owner.html#method.reserve_outputs)
+	/// for the given reservation, e.g. once the scheduled payment has been made or called off.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `label` - The label of the reservation.
+	///
+	/// # Returns
+	/// * Ok(()) if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.release_reservation(None, "rent");
+	/// ```
+
+	pub fn release_reservation(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		label: &str,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::release_reservation(&mut **w, keychain_mask, label)
+	}
+
+	/// Holds the given outputs of the active account for a reference, so that an external
+	/// coordinator, e.g. a swap daemon or a multisig orchestrator, can keep them across
+	/// several calls without the wallet selecting them for its own sends meanwhile. Until
+	/// the lock expires, sends only select these outputs if their
+	/// [`InitTxArgs`](../grin_wallet_libwallet/types/struct.InitTxArgs.html) `reservation` is
+	/// the reference. Either all the outputs are locked or none; locking them again with the
+	/// same reference extends the lock.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `commitments` - The commitments of the outputs, in hex.
+	/// * `ttl_secs` - How long the outputs are locked, in seconds.
+	/// * `reference` - The reference of the lock, to give to
+	/// [`unlock_by_ref`](struct.Owner.html#method.unlock_by_ref).
+	///
+	/// # Returns
+	/// * Ok with the locked [`OutputData`](../grin_wallet_libwallet/types/struct.OutputData.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// e.g. if an output is spent, locked by a transaction or held for another reference.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let commitments = vec![
+	/// 	"08e1da9e6dc4d6e808a718b2f110a991dd775d65ce5ae408a4e1f002a4961aa9e7".to_owned(),
+	/// ];
+	/// let result = api_owner.lock_outputs(None, &commitments, 3600, "swap-1");
+	/// ```
+
+	pub fn lock_outputs(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		commitments: &[String],
+		ttl_secs: u64,
+		reference: &str,
+	) -> Result<Vec<OutputData>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::lock_outputs(&mut **w, keychain_mask, commitments, ttl_secs, reference)
+	}
+
+	/// Releases the outputs locked by [`lock_outputs`](struct.Owner.html#method.lock_outputs)
+	/// for the given reference, e.g. once the coordinator has spent them or given up.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `reference` - The reference of the lock.
+	///
+	/// # Returns
+	/// * Ok with the released [`OutputData`](../grin_wallet_libwallet/types/struct.OutputData.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.unlock_by_ref(None, "swap-1");
+	/// ```
+
+	pub fn unlock_by_ref(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		reference: &str,
+	) -> Result<Vec<OutputData>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::unlock_by_ref(&mut **w, keychain_mask, reference)
+	}
+
+	/// Plans the transactions moving the whole spendable balance of an account to another
+	/// wallet, typically cold storage. Outputs are spent smallest first, as many per transaction
+	/// as the constraints allow, and no transaction creates change. Outputs worth less than the
+	/// fee to spend them are left behind as dust. However many outputs the wallet holds, each
+	/// transaction fits in a block: consolidating thousands of outputs takes several transactions,
+	/// which [`execute_sweep`](struct.Owner.html#method.execute_sweep) can wait on one by one.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `args` - [`SweepArgs`](../grin_wallet_libwallet/api_impl/types/struct.SweepArgs.html),
+	/// the constraints of the transactions.
+	///
+	/// # Returns
+	/// * Ok([`SweepPlan`](../grin_wallet_libwallet/api_impl/types/struct.SweepPlan.html)) if
+	/// successful, to run with [`execute_sweep`](struct.Owner.html#method.execute_sweep)
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as fees exceeding the budget.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let args = SweepArgs {
+	/// 	src_acct_name: None,
+	/// 	minimum_confirmations: 10,
+	/// 	max_outputs_per_tx: 100,
+	/// 	max_tx_weight: None,
+	/// 	fee_budget: Some(100_000_000),
+	/// };
+	/// let result = api_owner.plan_sweep(None, args);
+	///
+	/// if let Ok(plan) = result {
+	/// 	// Review the plan, then execute it
+	/// 	// ...
+	/// }
+	/// ```
+
+	pub fn plan_sweep(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		args: SweepArgs,
+	) -> Result<SweepPlan, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::plan_sweep(&mut **w, keychain_mask, args)
+	}
+
+	/// Executes a sweep planned by [`plan_sweep`](struct.Owner.html#method.plan_sweep), one
+	/// transaction after the other: each is sent through `sender`, finalized and posted, then,
+	/// if `confirmation_timeout_secs` is set, awaited until confirmed before the next one.
+	/// Fails without sending anything more if the wallet would no longer build the planned
+	/// transaction, e.g. because outputs were received or spent since the plan was made.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `plan` - The plan returned by [`plan_sweep`](struct.Owner.html#method.plan_sweep).
+	/// * `sender` - Sends the slates to the wallet receiving the balance, e.g. as created by
+	/// [`create_sender`](../grin_wallet_impls/fn.create_sender.html).
+	/// * `fluff` - Whether to skip the Dandelion phase when posting, as in
+	/// [`post_tx`](struct.Owner.html#method.post_tx).
+	/// * `confirmation_timeout_secs` - How long to wait for each transaction to be confirmed,
+	/// `None` not to wait.
+	/// * `progress` - Called as each transaction is posted and confirmed.
+	///
+	/// # Returns
+	/// * Ok(()) if all the transactions were posted, and confirmed if awaited
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	/// The transactions reported as posted until then stand.
+
+	pub fn execute_sweep<F>(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		plan: &SweepPlan,
+		sender: &dyn SlateSender,
+		fluff: bool,
+		confirmation_timeout_secs: Option<u64>,
+		mut progress: F,
+	) -> Result<(), Error>
+	where
+		F: FnMut(&SweepProgress),
+	{
+		let batches = plan.batches.len();
+		for (i, batch) in plan.batches.iter().enumerate() {
+			let args = InitTxArgs {
+				src_acct_name: plan.src_acct_name.clone(),
+				amount: batch.amount,
+				minimum_confirmations: plan.minimum_confirmations,
+				max_outputs: batch.inputs.len() as u32,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: true,
+				..Default::default()
+			};
+			let slate = self.with_context(keychain_mask, |ctx| {
+				// Outputs spent whole, without change, as planned
+				let estimate = ctx.init_send_tx(InitTxArgs {
+					estimate_only: Some(true),
+					..args.clone()
+				})?;
+				if estimate.amount != batch.amount + batch.fee || estimate.fee != batch.fee {
+					return Err(ErrorKind::Sweep(
+						"The wallet outputs changed since the sweep was planned".to_owned(),
+					))?;
+				}
+				ctx.init_send_tx(args)
+			})?;
+			info!(
+				"Sweep transaction {} of {}: sending {}, fee {}",
+				i + 1,
+				batches,
+				amount_to_hr_string(batch.amount, false),
+				amount_to_hr_string(batch.fee, false)
+			);
+			// The wallet isn't locked while waiting for the other party
+			let slate = sender.send_tx(&slate)?;
+			let slate = self.with_context(keychain_mask, |ctx| {
+				ctx.tx_lock_outputs(&slate, 0)?;
+				ctx.finalize_tx(&slate)
+			})?;
+			self.post_tx(keychain_mask, &slate.tx, fluff)?;
+			let mut status = SweepProgress {
+				batch: i,
+				batches,
+				tx_slate_id: slate.id,
+				confirmed: false,
+			};
+			progress(&status);
+
+			let timeout = match confirmation_timeout_secs {
+				Some(t) => Duration::from_secs(t),
+				None => continue,
+			};
+			let start = Instant::now();
+			loop {
+				let (_, txs) = self.retrieve_txs(keychain_mask, true, None, Some(slate.id))?;
+				if txs.iter().any(|t| t.confirmed) {
+					break;
+				}
+				if start.elapsed() >= timeout {
+					return Err(ErrorKind::Sweep(format!(
+						"Transaction {} wasn't confirmed within {} seconds",
+						slate.id,
+						timeout.as_secs()
+					)))?;
+				}
+				let remaining = timeout
+					.checked_sub(start.elapsed())
+					.unwrap_or(Duration::from_secs(0));
+				thread::sleep(cmp::min(Duration::from_secs(SWEEP_POLL_SECS), remaining));
+			}
+			status.confirmed = true;
+			progress(&status);
+		}
+		Ok(())
+	}
+
+	/// Holds `amount` of the spendable balance of the active account, e.g. while a quoted
+	/// checkout session is open. Unlike [`reserve_outputs`](struct.Owner.html#method.reserve_outputs)
+	/// no particular outputs are held: until the hold expires or is released, sends can't spend
+	/// the held amount unless their [`InitTxArgs`](../grin_wallet_libwallet/types/struct.InitTxArgs.html)
+	/// `hold` is the hold's reference. The held and available amounts are reported by
+	/// [`retrieve_summary_info`](struct.Owner.html#method.retrieve_summary_info).
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `amount` - The amount to hold, in nanogrins.
+	/// * `ttl_secs` - How long the amount is held, in seconds.
+	/// * `reference` - Reference of the hold, e.g. the checkout session id. It must not be in use
+	/// by another hold of the account.
+	///
+	/// # Returns
+	/// * Ok with the [`BalanceHold`](../grin_wallet_libwallet/types/struct.BalanceHold.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// e.g. if the available balance can't cover the amount.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.create_hold(None, 2_000_000_000, 15 * 60, "order-1234");
+	///
+	/// if let Ok(_) = result {
+	/// 	// When the customer checks out
+	/// 	let args = InitTxArgs {
+	/// 		amount: 2_000_000_000,
+	/// 		hold: Some("order-1234".to_owned()),
+	/// 		..Default::default()
+	/// 	};
+	/// 	let result = api_owner.init_send_tx(None, args);
+	/// }
+	/// ```
+
+	pub fn create_hold(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		amount: u64,
+		ttl_secs: u64,
+		reference: &str,
+	) -> Result<BalanceHold, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::create_hold(&mut **w, keychain_mask, amount, ttl_secs, reference)
+	}
+
+	/// Releases a balance hold made by [`create_hold`](struct.Owner.html#method.create_hold),
+	/// e.g. once the checkout session is abandoned. Holds are released automatically when a
+	/// send is made for them.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `reference` - The reference of the hold.
+	///
+	/// # Returns
+	/// * Ok(()) if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.release_hold(None, "order-1234");
+	/// ```
+
+	pub fn release_hold(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		reference: &str,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::release_hold(&mut **w, keychain_mask, reference)
+	}
+
+	/// Returns the filter listeners apply to the origins of incoming slates, along with
+	/// the number of slates rejected from each origin. Origins are of the form
+	/// `<method>:<address>`, e.g. `keybase:alice`. Only the keybase listener knows the
+	/// origin of the slates it receives at the moment.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with the [`SourceFilter`](../grin_wallet_libwallet/types/struct.SourceFilter.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_source_filter(None);
+	///
+	/// if let Ok(filter) = result {
+	/// 	for (origin, hits) in filter.hits.iter() {
+	/// 		println!("{} slates rejected from {}", hits, origin);
+	/// 	}
+	/// }
+	/// ```
+
+	pub fn retrieve_source_filter(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<SourceFilter, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::retrieve_source_filter(&mut **w)
+	}
+
+	/// Adds an origin to, or removes it from, the blocklist of incoming slates. Listeners
+	/// reject the slates of blocked origins, counting them in the filter's hits.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `origin` - The origin, e.g. `keybase:alice`.
+	/// * `blocked` - Whether the origin is blocked.
+	///
+	/// # Returns
+	/// * Ok(()) if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.set_source_blocked(None, "keybase:spammer", true);
+	/// ```
+
+	pub fn set_source_blocked(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		origin: &str,
+		blocked: bool,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::update_source_filter(&mut **w, keychain_mask, origin, false, blocked)
+	}
+
+	/// Adds an origin to, or removes it from, the allowlist of incoming slates. Once the
+	/// allowlist isn't empty, listeners only receive slates from the origins it holds.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `origin` - The origin, e.g. `keybase:alice`.
+	/// * `allowed` - Whether the origin is allowed.
+	///
+	/// # Returns
+	/// * Ok(()) if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.set_source_allowed(None, "keybase:alice", true);
+	/// ```
+
+	pub fn set_source_allowed(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		origin: &str,
+		allowed: bool,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::update_source_filter(&mut **w, keychain_mask, origin, true, allowed)
+	}
+
+	/// Returns the log of incoming slates that couldn't be received, whether they failed
+	/// validation or came from a rejected origin, to trace payments a sender claims to
+	/// have made. The log keeps the latest 1000 failures.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_slate_id` - If `Some(uuid)`, only return the failures of the slate with the given id.
+	///
+	/// # Returns
+	/// * Ok with a vector of [`FailedReceive`](../grin_wallet_libwallet/types/struct.FailedReceive.html),
+	/// oldest first, if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_failed_receives(None, None);
+	///
+	/// if let Ok(failures) = result {
+	/// 	for f in failures.iter() {
+	/// 		println!("{}: slate {} of {} rejected: {}", f.created, f.slate_id, f.amount, f.reason);
+	/// 	}
+	/// }
+	/// ```
+
+	pub fn retrieve_failed_receives(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<Vec<FailedReceive>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::retrieve_failed_receives(&mut **w, tx_slate_id)
+	}
+
+	/// Returns the versions of a slate kept in the slate archive, each slate the wallet
+	/// received for the transaction, as it was received, and each one it returned or sent.
+	/// Slates are only archived if the wallet is configured with a
+	/// `slate_archive_retention_days`, and are dropped once older than the retention period.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `slate_id` - Id of the slate.
+	///
+	/// # Returns
+	/// * Ok with a vector of [`ArchivedSlate`](../grin_wallet_libwallet/types/struct.ArchivedSlate.html),
+	/// oldest first, if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	/// use uuid::Uuid;
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let slate_id = Uuid::parse_str("0436430c-2b02-624c-2032-570501212b00").unwrap();
+	/// let result = api_owner.get_slate_history(None, &slate_id);
+	///
+	/// if let Ok(history) = result {
+	/// 	for s in history.iter() {
+	/// 		println!("{}: {:?} slate at {}", s.created, s.direction, s.step);
+	/// 	}
+	/// }
+	/// ```
+
+	pub fn get_slate_history(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		slate_id: &Uuid,
+	) -> Result<Vec<ArchivedSlate>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::get_slate_history(&mut **w, slate_id)
+	}
+
+	/// Polls the wallet event queue, for integrators to act on the wallet's transactions
+	/// being received, confirmed or cancelled. Events are recorded along with the change
+	/// they describe and delivered at least once: a consumer keeps getting the events it
+	/// hasn't acknowledged, across wallet restarts, until it passes the sequence number
+	/// of the last event it processed as `cursor`. Each consumer has its own offset. The
+	/// queue keeps the latest 10000 events.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `consumer` - Name of the consumer, its offset is kept under that name.
+	/// * `cursor` - If `Some(seq)`, acknowledge the events up to `seq` before polling.
+	/// * `max_events` - Most events to return, defaults to 100.
+	///
+	/// # Returns
+	/// * Ok with a vector of [`WalletEvent`](../grin_wallet_libwallet/types/struct.WalletEvent.html),
+	/// oldest first, if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let mut cursor = None;
+	/// while let Ok(events) = api_owner.poll_events(None, "shop", cursor, None) {
+	/// 	if events.is_empty() {
+	/// 		break;
+	/// 	}
+	/// 	for e in events.iter() {
+	/// 		println!("{}: {:?} tx {}", e.seq, e.event_type, e.tx.id);
+	/// 	}
+	/// 	cursor = events.last().map(|e| e.seq);
+	/// }
+	/// ```
+
+	pub fn poll_events(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		consumer: &str,
+		cursor: Option<u64>,
+		max_events: Option<u32>,
+	) -> Result<Vec<WalletEvent>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let max_events = max_events.unwrap_or(100) as usize;
+		owner::poll_events(&mut **w, keychain_mask, consumer, cursor, max_events)
+	}
+
+	/// Returns the change journal of the wallet, for debugging. Every change to the wallet
+	/// data (an output, a transaction log entry, an account...) is recorded in the journal
+	/// with the value the wallet's change counter took, which helps find out what changed a
+	/// balance. The journal keeps the latest 10000 changes.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `since` - If `Some(counter)`, only return the changes made after `counter`.
+	/// * `max_records` - Most changes to return, defaults to 1000.
+	///
+	/// # Returns
+	/// * Ok with a vector of [`ChangeRecord`](../grin_wallet_libwallet/types/struct.ChangeRecord.html),
+	/// oldest first, if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_change_journal(None, None, Some(50));
+	///
+	/// if let Ok(records) = result {
+	/// 	for r in records.iter() {
+	/// 		println!("{} {}: {:?} {:?} {}", r.counter, r.time, r.op, r.entity, r.id);
+	/// 	}
+	/// }
+	/// ```
+
+	pub fn retrieve_change_journal(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		since: Option<u64>,
+		max_records: Option<u32>,
+	) -> Result<Vec<ChangeRecord>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		let max_records = max_records.unwrap_or(1000) as usize;
+		owner::retrieve_change_journal(&mut **w, since.unwrap_or(0), max_records)
+	}
+
+	/// Makes an account a vault, or changes the lock of a vault. The kernels of the sends
+	/// from a vault are height locked, so the transactions can't be posted to the chain
+	/// before `lock_blocks` blocks have passed. Until then the owner can cancel a send by
+	/// spending its inputs elsewhere, e.g. should the online wallet be stolen. Both
+	/// [`init_send_tx`](struct.Owner.html#method.init_send_tx) and
+	/// [`process_invoice_tx`](struct.Owner.html#method.process_invoice_tx) apply the lock.
+	///
+	/// A longer lock takes effect immediately. A shorter one, 0 turning the vault off,
+	/// only takes effect once the current lock has passed.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `account` - The name of the account.
+	/// * `lock_blocks` - Number of blocks the sends are locked for, at most a week of blocks.
+	///
+	/// # Returns
+	/// * Ok with the [`VaultSettings`](../grin_wallet_libwallet/types/struct.VaultSettings.html)
+	/// of the account if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.create_account_path(None, "savings");
+	///
+	/// if let Ok(_) = result {
+	/// 	// Sends from the account can't be mined for a day
+	/// 	let result = api_owner.set_vault_lock(None, "savings", 1440);
+	/// }
+	/// ```
+
+	pub fn set_vault_lock(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		account: &str,
+		lock_blocks: u64,
+	) -> Result<VaultSettings, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::set_vault_lock(&mut **w, keychain_mask, account, lock_blocks)
+	}
+
+	/// Returns the lock of a vault account, see
+	/// [`set_vault_lock`](struct.Owner.html#method.set_vault_lock).
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `account` - The name of the account.
+	///
+	/// # Returns
+	/// * Ok with the [`VaultSettings`](../grin_wallet_libwallet/types/struct.VaultSettings.html)
+	/// of the account, or `None` if it has never been a vault, if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_vault_settings(None, "default");
+	/// ```
+
+	pub fn retrieve_vault_settings(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		account: &str,
+	) -> Result<Option<VaultSettings>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::retrieve_vault_settings(&mut **w, account)
+	}
+
+	/// Freezes the wallet, for operators responding to a suspected compromise. Until
+	/// unfrozen with the wallet password, the wallet receives no slates, whether through
+	/// the foreign API or the keybase listener, and the owner API only serves the
+	/// requests that don't change the wallet. The freeze is kept in the wallet database,
+	/// so it outlasts restarts.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with the [`FreezeState`](../grin_wallet_libwallet/types/struct.FreezeState.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.freeze_wallet(None);
+	/// ```
+
+	pub fn freeze_wallet(&self, keychain_mask: Option<&SecretKey>) -> Result<FreezeState, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::freeze_wallet(&mut **w, keychain_mask)
+	}
+
+	/// Unfreezes a wallet frozen by [`freeze_wallet`](struct.Owner.html#method.freeze_wallet).
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `password` - The wallet password, checked against the wallet seed file.
+	///
+	/// # Returns
+	/// * Ok(()) if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as a wrong password.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.unfreeze_wallet(None, ZeroingString::from("my_password"));
+	/// ```
+
+	pub fn unfreeze_wallet(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		password: ZeroingString,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let lc = w_lock.lc_provider()?;
+		// Decrypting the seed checks the password
+		lc.get_mnemonic(None, password)?;
+		let w = lc.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::unfreeze_wallet(&mut **w, keychain_mask)
+	}
+
+	/// Returns whether the wallet is frozen, see
+	/// [`freeze_wallet`](struct.Owner.html#method.freeze_wallet).
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with the [`FreezeState`](../grin_wallet_libwallet/types/struct.FreezeState.html),
+	/// or `None` if the wallet isn't frozen, if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_freeze_state(None);
+	///
+	/// if let Ok(Some(f)) = result {
+	/// 	println!("Wallet frozen since {}", f.since);
+	/// }
+	/// ```
+
+	pub fn retrieve_freeze_state(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<Option<FreezeState>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::retrieve_freeze_state(&mut **w)
+	}
+
+	/// Sets up a second factor for the owner API: a TOTP secret, to be entered into an
+	/// authenticator app, along with recovery codes usable once each in place of a code.
+	/// Once confirmed with [`confirm_totp`](struct.Owner.html#method.confirm_totp), sending,
+	/// finalizing, posting or cancelling transactions and changing the security settings of
+	/// the wallet are refused without a current code, set with
+	/// [`set_totp_code`](struct.Owner.html#method.set_totp_code). The owner API listener
+	/// takes it from the `totp` member of the JSON-RPC request. A secret not confirmed yet
+	/// is replaced. The secret is stored encrypted with the keychain.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with the [`TotpSetup`](../grin_wallet_libwallet/api_impl/types/struct.TotpSetup.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as a second factor being enabled already.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.setup_totp(None);
+	///
+	/// if let Ok(setup) = result {
+	/// 	println!("Scan {}, and keep {:?}", setup.uri, setup.recovery_codes);
+	/// }
+	/// ```
+
+	pub fn setup_totp(&self, keychain_mask: Option<&SecretKey>) -> Result<TotpSetup, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::setup_totp(&mut **w, keychain_mask)
+	}
+
+	/// Enables the second factor set up by [`setup_totp`](struct.Owner.html#method.setup_totp),
+	/// given a current code of the authenticator app it was entered into.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `code` - A current code of the authenticator app.
+	///
+	/// # Returns
+	/// * Ok(()) if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as a wrong code.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.confirm_totp(None, "123456");
+	/// ```
+
+	pub fn confirm_totp(&self, keychain_mask: Option<&SecretKey>, code: &str) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::confirm_totp(&mut **w, keychain_mask, code)
+	}
+
+	/// Checks a code of the second factor, or a recovery code, as is done before a spend or a
+	/// change of the security settings of the wallet. Each code is accepted once, and a recovery
+	/// code is used up. Any code passes if no second factor is enabled.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `code` - A current code of the authenticator app, or a recovery code.
+	///
+	/// # Returns
+	/// * Ok(()) if the code is accepted
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as a wrong or missing code.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.verify_totp(None, Some("123456"));
+	/// ```
+
+	pub fn verify_totp(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		code: Option<&str>,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::verify_totp(&mut **w, keychain_mask, code)
+	}
+
+	/// Removes the second factor, given one of its codes or a recovery code once it's
+	/// enabled.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `code` - A current code of the authenticator app, or a recovery code, if the second
+	/// factor is enabled.
+	///
+	/// # Returns
+	/// * Ok(()) if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as a wrong code.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.disable_totp(None, Some("123456"));
+	/// ```
+
+	pub fn disable_totp(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		code: Option<&str>,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::disable_totp(&mut **w, keychain_mask, code)
+	}
+
+	/// Returns whether the owner API requires a second factor for spends and changes of the
+	/// security settings of the wallet, see [`setup_totp`](struct.Owner.html#method.setup_totp).
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok(true) if a second factor is enabled, Ok(false) otherwise
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.totp_enabled(None);
+	/// ```
+
+	pub fn totp_enabled(&self, keychain_mask: Option<&SecretKey>) -> Result<bool, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::totp_enabled(&mut **w, keychain_mask)
+	}
+
+	/// Initiates a new transaction as the sender, creating a new
+	/// [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html) object containing
+	/// the sender's inputs, change outputs, and public signature data. This slate can
+	/// then be sent to the recipient to continue the transaction via the
+	/// [Foreign API's `receive_tx`](struct.Foreign.html#method.receive_tx) method.
+	///
+	/// When a transaction is created, the wallet must also lock inputs (and create unconfirmed
+	/// outputs) corresponding to the transaction created in the slate, so that the wallet doesn't
+	/// attempt to re-spend outputs that are already included in a transaction before the transaction
+	/// is confirmed. This method also returns a function that will perform that locking, and it is
+	/// up to the caller to decide the best time to call the lock function
+	/// (via the [`tx_lock_outputs`](struct.Owner.html#method.tx_lock_outputs) method).
+	/// If the exchange method is intended to be synchronous (such as via a direct http call,)
+	/// then the lock call can wait until the response is confirmed. If it is asynchronous, (such
+	/// as via file transfer,) the lock call should happen immediately (before the file is sent
+	/// to the recipient).
+	///
+	/// If the `send_args` [`InitTxSendArgs`](../grin_wallet_libwallet/types/struct.InitTxSendArgs.html),
+	/// of the [`args`](../grin_wallet_libwallet/types/struct.InitTxArgs.html), field is Some, this
+	/// function will attempt to perform a synchronous send to the recipient specified in the `dest`
+	/// field according to the `method` field, and will also finalize and post the transaction if
+	/// the `finalize` field is set. The send is prepared first: the outputs are locked and the
+	/// slate kept in the wallet, then it's dispatched as by
+	/// [`dispatch_tx`](struct.Owner.html#method.dispatch_tx), which can retry a dispatch that
+	/// failed. If finalizing or posting fails, the transaction is cancelled when
+	/// `cancel_on_failure` is set, or by default when the wallet's `cancel_tx_on_send_failure`
+	/// setting is, so the wallet isn't left with a transaction to cancel by hand. A recipient
+	/// not answering within the `timeout_secs` of the send arguments fails the send, which is
+	/// kept to be dispatched again.
+	/// [`init_send_tx_async`](struct.Owner.html#method.init_send_tx_async) sends without
+	/// waiting for the recipient.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `args` - [`InitTxArgs`](../grin_wallet_libwallet/types/struct.InitTxArgs.html),
+	/// transaction initialization arguments. See struct documentation for further detail.
+	///
+	/// # Returns
+	/// * a result containing:
+	/// * The transaction [Slate](../grin_wallet_libwallet/slate/struct.Slate.html),
+	/// which can be forwarded to the recieving party by any means. Once the caller is relatively
+	/// certain that the transaction has been sent to the recipient, the associated wallet
+	/// transaction outputs should be locked via a call to
+	/// [`tx_lock_outputs`](struct.Owner.html#method.tx_lock_outputs). This must be called before calling
+	/// [`finalize_tx`](struct.Owner.html#method.finalize_tx).
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Remarks
+	///
+	/// * This method requires an active connection to a node, and will fail with error if a node
+	/// cannot be contacted to refresh output statuses.
+	/// * This method will store a partially completed transaction in the wallet's transaction log,
+	/// which will be updated on the corresponding call to [`finalize_tx`](struct.Owner.html#method.finalize_tx).
+	///
+	/// # Example
+	/// Set up as in [new](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// // Attempt to create a transaction using the 'default' account
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 2,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: true,
+	/// 	message: Some("Have some Grins. Love, Yeastplume".to_owned()),
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.init_send_tx(
+	/// 	None,
+	/// 	args,
+	/// );
+	///
+	/// if let Ok(slate) = result {
+	/// 	// Send slate somehow
+	/// 	// ...
+	/// 	// Lock our outputs if we're happy the slate was (or is being) sent
+	/// 	api_owner.tx_lock_outputs(None, &slate, 0);
+	/// }
+	/// ```
+
+	pub fn init_send_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		args: InitTxArgs,
+	) -> Result<Slate, Error> {
+		self.init_send_tx_with_change(keychain_mask, args)
+			.map(|r| r.slate)
+	}
+
+	/// Initiates a new transaction as [`init_send_tx`](struct.Owner.html#method.init_send_tx)
+	/// does, also returning the change outputs the transaction creates, so they can be tracked
+	/// as belonging to the payment without comparing the outputs of the wallet before and after.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `args` - [`InitTxArgs`](../grin_wallet_libwallet/types/struct.InitTxArgs.html),
+	/// transaction initialization arguments. See struct documentation for further detail.
+	///
+	/// # Returns
+	/// * Ok with an [`InitTxResult`](../grin_wallet_libwallet/types/struct.InitTxResult.html),
+	/// the slate as `init_send_tx` returns it along with the key ids, commitments and values of
+	/// the change outputs, none for an estimate
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [new](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 2,
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.init_send_tx_with_change(None, args);
+	///
+	/// if let Ok(r) = result {
+	/// 	for c in r.change_outputs.iter() {
+	/// 		println!("Change of {}: {:?}", r.slate.id, c.commit);
+	/// 	}
+	/// }
+	/// ```
+
+	pub fn init_send_tx_with_change(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		args: InitTxArgs,
+	) -> Result<InitTxResult, Error> {
+		let send_args = args.send_args.clone();
+		// Helper functionality. If send arguments exist, prepare the send and dispatch it
+		let _operation = match send_args.as_ref() {
+			Some(sa) => {
+				check_send_method(&sa.method)?;
+				let operations = self.pending_operations()?;
+				Some(owner::begin_operation(&operations, "init_send_tx", None)?)
+			}
+			None => None,
+		};
+		let InitTxResult {
+			slate,
+			change_outputs,
+		} = self.prepare_send(keychain_mask, args)?;
+		let slate = match send_args {
+			Some(_) => self.dispatch_tx(keychain_mask, slate.id)?,
+			None => slate,
+		};
+		Ok(InitTxResult {
+			slate,
+			change_outputs,
+		})
+	}
+
+	/// Builds the transaction of a send, and keeps the send in the wallet to be dispatched
+	/// if there are send arguments
+	fn prepare_send(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		args: InitTxArgs,
+	) -> Result<InitTxResult, Error> {
+		self.with_context(keychain_mask, |ctx| ctx.prepare_send(args))
+	}
+
+	/// Checks a recipient can receive from this wallet before sending to it, so that no funds
+	/// get locked for a send bound to fail. The recipient is contacted as a send would, up to
+	/// the slate: its foreign API and slate versions are checked and a slate version negotiated,
+	/// and the receive challenge and resumable slate exchange it supports are looked up. No
+	/// transaction is built and nothing is sent to the recipient's wallet.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `method` - The method the recipient would be sent to, as in
+	/// [`InitTxSendArgs`](../grin_wallet_libwallet/types/struct.InitTxSendArgs.html). Only
+	/// recipients reached over `http`, `.onion` addresses included, can be checked.
+	/// * `dest` - The destination of the recipient, as in `InitTxSendArgs`.
+	///
+	/// # Returns
+	/// * Ok with the [`RecipientHandshake`](../grin_wallet_libwallet/api_impl/types/struct.RecipientHandshake.html)
+	/// if the recipient is reachable and compatible
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as the recipient not listening or not supporting any slate version of this wallet.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.check_recipient(None, "http", "http://192.168.0.10:3415");
+	///
+	/// if let Ok(handshake) = result {
+	/// 	// The recipient can be sent to
+	/// 	println!("Sending as slate V{}", handshake.slate_version);
+	/// }
+	/// ```
+
+	pub fn check_recipient(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		method: &str,
+		dest: &str,
+	) -> Result<RecipientHandshake, Error> {
+		check_send_method(method)?;
+		let (chain_type, tor_socks_proxy) = self.with_context(keychain_mask, |ctx| {
+			Ok((ctx.chain_type(), ctx.tor_socks_proxy()))
+		})?;
+		// The wallet isn't locked while waiting for the recipient
+		create_sender(method, dest, chain_type, tor_socks_proxy, None)?.handshake(method)
+	}
+
+	/// Dispatches a send prepared by [`init_send_tx`](struct.Owner.html#method.init_send_tx)
+	/// with `send_args`: sends the slate to the recipient, then finalizes and posts the
+	/// transaction as the send arguments say. `init_send_tx` dispatches the send itself; this
+	/// retries a dispatch that failed. Steps that succeeded aren't repeated, the recipient isn't
+	/// sent the slate again once they returned it.
+	///
+	/// A slate that failed to be delivered may still have reached the recipient, who would
+	/// reject it as a duplicate: its transaction is cancelled, unlocking its outputs, and the
+	/// send is retried with a new slate, built from the same arguments, which is returned. If
+	/// finalizing or posting fails, the transaction is cancelled when the `cancel_on_failure`
+	/// send argument, or by default the wallet's `cancel_tx_on_send_failure` setting, is set.
+	/// Otherwise the send is kept for another attempt until it's dispatched or the transaction
+	/// is cancelled. A send whose delivery failed is dropped rather than kept when the setting
+	/// is.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_slate_id` - The id of the slate of the send.
+	///
+	/// # Returns
+	/// * Ok with the transaction [Slate](../grin_wallet_libwallet/slate/struct.Slate.html) as it
+	/// was finalized, or returned by the recipient if not finalizing, if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as no send being prepared for the slate.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_pending_dispatches(None, None);
+	///
+	/// if let Ok(pending) = result {
+	/// 	for p in pending.iter() {
+	/// 		// The recipient may be back online
+	/// 		let res = api_owner.dispatch_tx(None, p.slate_id);
+	/// 	}
+	/// }
+	/// ```
+
+	pub fn dispatch_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_slate_id: Uuid,
+	) -> Result<Slate, Error> {
+		let (mut entry, chain_type, tor_socks_proxy, cancel_default) =
+			self.with_context(keychain_mask, |ctx| {
+				ctx.check_totp()?;
+				let cancel_default = ctx.w.config().cancel_tx_on_send_failure.unwrap_or(false);
+				match owner::retrieve_pending_dispatches(&mut *ctx.w, Some(tx_slate_id))?.pop() {
+					Some(e) => Ok((e, ctx.chain_type(), ctx.tor_socks_proxy(), cancel_default)),
+					None => Err(ErrorKind::NotPendingDispatch(tx_slate_id.to_string()).into()),
+				}
+			})?;
+		if entry.cancelled {
+			return self.redispatch_tx(keychain_mask, entry);
+		}
+		let sa = entry.send_args.clone();
+		check_send_method(&sa.method)?;
+		let cancel = sa.cancel_on_failure.unwrap_or(cancel_default);
+		// Closing the wallet waits for the send, rather than leave it half done
+		let operations = self.pending_operations()?;
+		let _operation = owner::begin_operation(&operations, "dispatch_tx", Some(tx_slate_id))?;
+		entry.attempts += 1;
+		let slate = match entry.response.clone() {
+			Some(s) => s,
+			None => {
+				// The wallet isn't locked while waiting for the other party
+				let res = deliver_slate(&sa, chain_type, tor_socks_proxy, &entry.slate);
+				let res = match res {
+					Ok(delivery) => self
+						.with_context(keychain_mask, |ctx| {
+							ctx.save_delivery_receipt(&tx_slate_id, &delivery.receipt)
+						})
+						.and_then(|_| delivery.into_slate()),
+					Err(e) => Err(e),
+				};
+				match res {
+					Ok(s) => s,
+					Err(e) => {
+						// The slate can't be sent again, a retry builds a new one
+						warn!("Delivering tx {} failed, cancelling it: {}", tx_slate_id, e);
+						self.with_context(keychain_mask, |ctx| {
+							ctx.cancel_tx(None, Some(tx_slate_id))?;
+							let mask = ctx.keychain_mask.clone();
+							if cancel || entry.args.is_none() {
+								return owner::delete_pending_dispatch(
+									&mut *ctx.w,
+									mask.as_ref(),
+									&tx_slate_id,
+								);
+							}
+							entry.cancelled = true;
+							entry.last_error = Some(e.to_string());
+							owner::save_pending_dispatch(&mut *ctx.w, mask.as_ref(), &entry)
+						})?;
+						return Err(e);
+					}
+				}
+			}
+		};
+		self.with_context(keychain_mask, |ctx| {
+			entry.response = Some(slate.clone());
+			let mut res = Ok(slate.clone());
+			if sa.finalize && !entry.finalized {
+				res = ctx.finalize_tx(&slate);
+				if let Ok(s) = res.as_ref() {
+					entry.response = Some(s.clone());
+					entry.finalized = true;
+				}
+			}
+			let res = res.and_then(|slate| {
+				if sa.post_tx {
+					ctx.post_tx(&slate.tx, sa.fluff)?;
+				}
+				Ok(slate)
+			});
+			let mask = ctx.keychain_mask.clone();
+			let e = match res {
+				Ok(slate) => {
+					owner::delete_pending_dispatch(&mut *ctx.w, mask.as_ref(), &tx_slate_id)?;
+					return Ok(slate);
+				}
+				Err(e) => e,
+			};
+			// Roll back rather than leave locked outputs behind, unless the tx is
+			// waiting for its lock height to be posted
+			if cancel && !owner::is_tx_deferred(&mut *ctx.w, &tx_slate_id)? {
+				warn!("Sending tx {} failed, cancelling it: {}", tx_slate_id, e);
+				match ctx.cancel_tx(None, Some(tx_slate_id)) {
+					Ok(()) => return Err(e),
+					Err(ce) => error!("Unable to cancel tx {}: {}", tx_slate_id, ce),
+				}
+			}
+			entry.last_error = Some(e.to_string());
+			owner::save_pending_dispatch(&mut *ctx.w, mask.as_ref(), &entry)?;
+			Err(e)
+		})
+	}
+
+	/// Operations in progress on the wallet, which closing it waits for
+	fn pending_operations(&self) -> Result<Arc<PendingOperations>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		Ok(w_lock.lc_provider()?.pending_operations())
+	}
+
+	/// Retries a send whose transaction was cancelled after a failed delivery, with a new
+	/// slate built from the arguments of the send
+	fn redispatch_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		old: PendingDispatch,
+	) -> Result<Slate, Error> {
+		let args = old
+			.args
+			.clone()
+			.ok_or_else(|| ErrorKind::NotPendingDispatch(old.slate_id.to_string()))?;
+		let slate = self.prepare_send(keychain_mask, args)?.slate;
+		self.with_context(keychain_mask, |ctx| {
+			let mask = ctx.keychain_mask.clone();
+			if let Some(mut entry) =
+				owner::retrieve_pending_dispatches(&mut *ctx.w, Some(slate.id))?.pop()
+			{
+				entry.attempts = old.attempts;
+				owner::save_pending_dispatch(&mut *ctx.w, mask.as_ref(), &entry)?;
+			}
+			owner::delete_pending_dispatch(&mut *ctx.w, mask.as_ref(), &old.slate_id)
+		})?;
+		self.dispatch_tx(keychain_mask, slate.id)
+	}
+
+	/// Returns the sends prepared by [`init_send_tx`](struct.Owner.html#method.init_send_tx)
+	/// with `send_args` that weren't dispatched yet, along with the number of attempts and the
+	/// last error of each, to retry them with [`dispatch_tx`](struct.Owner.html#method.dispatch_tx).
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_slate_id` - If `Some(uuid)`, only return the send of the slate with the given id.
+	///
+	/// # Returns
+	/// * Ok with a vector of [`PendingDispatch`](../grin_wallet_libwallet/types/struct.PendingDispatch.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_pending_dispatches(None, None);
+	///
+	/// if let Ok(pending) = result {
+	/// 	for p in pending.iter() {
+	/// 		println!("{}: {} attempts, {:?}", p.slate_id, p.attempts, p.last_error);
+	/// 	}
+	/// }
+	/// ```
+
+	pub fn retrieve_pending_dispatches(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<Vec<PendingDispatch>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::retrieve_pending_dispatches(&mut **w, tx_slate_id)
+	}
+
+	/// Hands out a one-time address for a single payment into the active account. Mimblewimble
+	/// doesn't let a sender create an output for the recipient alone, so the payment is still
+	/// made to the wallet's foreign listener, reachable at `listener_url`: the address is that
+	/// url with the id of the address as fragment, which the sender's wallet passes on to the
+	/// listener in a slate attachment when sending to the address.
+	///
+	/// The wallet refuses a second payment to the address, or one after it expired, and
+	/// receives the payment into the account the address was made for. Payments are detected
+	/// while refreshing the wallet: the address is marked confirmed once the payment confirms,
+	/// and can be paid to again if the payment was cancelled or abandoned.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `listener_url` - The http(s) url the wallet's foreign listener can be reached at by the
+	/// sender.
+	/// * `label` - Optional label for the address, e.g. an invoice number.
+	/// * `expires_in_hours` - If `Some(hours)`, payments to the address are refused after that
+	/// many hours.
+	///
+	/// # Returns
+	/// * Ok with the [`OnetimeAddress`](../grin_wallet_libwallet/types/struct.OnetimeAddress.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let url = "http://127.0.0.1:3415";
+	/// let result = api_owner.get_onetime_address(None, url, Some("order 42".to_owned()), Some(24));
+	///
+	/// if let Ok(address) = result {
+	/// 	println!("Pay to {}", address.address);
+	/// }
+	/// ```
+
+	pub fn get_onetime_address(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		listener_url: &str,
+		label: Option<String>,
+		expires_in_hours: Option<u64>,
+	) -> Result<OnetimeAddress, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::get_onetime_address(
+			&mut **w,
+			keychain_mask,
+			listener_url,
+			label,
+			expires_in_hours,
+		)
+	}
+
+	/// Returns the one-time addresses handed out by
+	/// [`get_onetime_address`](struct.Owner.html#method.get_onetime_address), oldest first, along
+	/// with the payment made to each, if any.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with a vector of [`OnetimeAddress`](../grin_wallet_libwallet/types/struct.OnetimeAddress.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_onetime_addresses(None);
+	///
+	/// if let Ok(addresses) = result {
+	/// 	for a in addresses.iter() {
+	/// 		println!("{}: {:?} confirmed {}", a.address, a.amount, a.confirmed);
+	/// 	}
+	/// }
+	/// ```
+
+	pub fn retrieve_onetime_addresses(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<Vec<OnetimeAddress>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::retrieve_onetime_addresses(&mut **w)
+	}
+
+	/// Creates a key for the foreign listener, so that one listener can receive payments for
+	/// several business units with their accounts kept apart. A sender given the key sends it
+	/// along with its slates, in the `X-Foreign-Api-Key` header or as the last segment of the
+	/// `/v2/foreign/key/<key>` path, and the slates are received into the account of the key
+	/// rather than the active account. Slates sent with an unknown or revoked key are refused.
+	///
+	/// The key is only returned by this call, the wallet keeps its hash only.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `account_label` - Label of the account slates sent with the key are received into.
+	/// * `label` - Optional label for the key, e.g. the business unit it's given to.
+	///
+	/// # Returns
+	/// * Ok with the key and its [`ForeignApiKey`](../grin_wallet_libwallet/types/struct.ForeignApiKey.html)
+	/// record if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.create_foreign_api_key(None, "default", Some("shop".to_owned()));
+	///
+	/// if let Ok((key, record)) = result {
+	/// 	println!("Key {} (id {}) receives into the default account", key, record.id);
+	/// }
+	/// ```
+
+	pub fn create_foreign_api_key(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		account_label: &str,
+		label: Option<String>,
+	) -> Result<(String, ForeignApiKey), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::create_foreign_api_key(&mut **w, keychain_mask, account_label, label)
+	}
+
+	/// Returns the keys of the foreign listener created by
+	/// [`create_foreign_api_key`](struct.Owner.html#method.create_foreign_api_key), oldest first.
+	/// Only the hashes of the keys are known to the wallet.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with a vector of [`ForeignApiKey`](../grin_wallet_libwallet/types/struct.ForeignApiKey.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_foreign_api_keys(None);
+	///
+	/// if let Ok(keys) = result {
+	/// 	for k in keys.iter() {
+	/// 		println!("{} {:?}: {}", k.id, k.label, k.parent_key_id.to_bip_32_string());
+	/// 	}
+	/// }
+	/// ```
+
+	pub fn retrieve_foreign_api_keys(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<Vec<ForeignApiKey>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::retrieve_foreign_api_keys(&mut **w)
+	}
+
+	/// Revokes a key of the foreign listener, slates sent with it being refused from then on.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `id` - Id of the key, as returned by
+	/// [`retrieve_foreign_api_keys`](struct.Owner.html#method.retrieve_foreign_api_keys).
+	///
+	/// # Returns
+	/// * Ok(()) if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// if let Ok((_, record)) = api_owner.create_foreign_api_key(None, "default", None) {
+	/// 	let result = api_owner.revoke_foreign_api_key(None, record.id);
+	/// }
+	/// ```
+
+	pub fn revoke_foreign_api_key(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		id: Uuid,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::revoke_foreign_api_key(&mut **w, keychain_mask, &id)
+	}
+
+	/// Issues a new invoice transaction slate, essentially a `request for payment`.
+	/// The slate created by this function will contain the amount, an output for the amount,
+	/// as well as round 1 of singature creation complete. The slate should then be send
+	/// to the payer, who should add their inputs and signature data and return the slate
+	/// via the [Foreign API's `finalize_invoice_tx`](struct.Foreign.html#method.finalize_invoice_tx) method.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `args` - [`IssueInvoiceTxArgs`](../grin_wallet_libwallet/types/struct.IssueInvoiceTxArgs.html),
+	/// invoice transaction initialization arguments. See struct documentation for further detail.
+	///
+	/// # Returns
+	/// * ``Ok([`slate`](../grin_wallet_libwallet/slate/struct.Slate.html))` if successful,
+	/// containing the updated slate.
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	///
+	/// let args = IssueInvoiceTxArgs {
+	/// 	amount: 60_000_000_000,
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.issue_invoice_tx(None, args);
+	///
+	/// if let Ok(slate) = result {
+	///		// if okay, send to the payer to add their inputs
+	///		// . . .
+	/// }
+	/// ```
+	pub fn issue_invoice_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		args: IssueInvoiceTxArgs,
+	) -> Result<Slate, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::issue_invoice_tx(&mut **w, keychain_mask, args, self.doctest_mode)
+	}
+
+	/// Processes an invoice tranaction created by another party, essentially
+	/// a `request for payment`. The incoming slate should contain a requested
+	/// amount, an output created by the invoicer convering the amount, and
+	/// part 1 of signature creation completed. This function will add inputs
+	/// equalling the amount + fees, as well as perform round 1 and 2 of signature
+	/// creation.
+	///
+	/// Callers should note that no prompting of the user will be done by this function
+	/// it is up to the caller to present the request for payment to the user
+	/// and verify that payment should go ahead.
+	///
+	/// A payment requiring approval, as every payment does when the wallet's
+	/// `require_tx_approval` setting is on, is returned without the partial signature.
+	/// Once approved via [`approve_tx`](struct.Owner.html#method.approve_tx), processing
+	/// the returned slate again signs it.
+	///
+	/// This function also stores the final transaction in the user's wallet files for retrieval
+	/// via the [`get_stored_tx`](struct.Owner.html#method.get_stored_tx) function.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `slate` - The transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html). The
+	/// payer should have filled in round 1 and 2.
+	/// * `args` - [`InitTxArgs`](../grin_wallet_libwallet/types/struct.InitTxArgs.html),
+	/// transaction initialization arguments. See struct documentation for further detail.
+	///
+	/// # Returns
+	/// * ``Ok([`slate`](../grin_wallet_libwallet/slate/struct.Slate.html))` if successful,
+	/// containing the updated slate.
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	///
+	/// // . . .
+	/// // The slate has been recieved from the invoicer, somehow
+	/// # let slate = Slate::blank(2);
+	/// let args = InitTxArgs {
+	///		src_acct_name: None,
+	///		amount: slate.amount,
+	///		minimum_confirmations: 2,
+	///		max_outputs: 500,
+	///		num_change_outputs: 1,
+	///		selection_strategy_is_use_all: true,
+	///		..Default::default()
+	///	};
+	///
+	/// let result = api_owner.process_invoice_tx(None, &slate, args);
+	///
+	/// if let Ok(slate) = result {
+	///	// If result okay, send back to the invoicer
+	///	// . . .
+	///	}
+	/// ```
+
+	pub fn process_invoice_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		slate: &Slate,
+		args: InitTxArgs,
+	) -> Result<Slate, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::process_invoice_tx(&mut **w, keychain_mask, slate, args, self.doctest_mode)
+	}
+
+	/// Locks the outputs associated with the inputs to the transaction in the given
+	/// [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html),
+	/// making them unavailable for use in further transactions. This function is called
+	/// by the sender, (or more generally, all parties who have put inputs into the transaction,)
+	/// and must be called before the corresponding call to [`finalize_tx`](struct.Owner.html#method.finalize_tx)
+	/// that completes the transaction.
+	///
+	/// Outputs will generally remain locked until they are removed from the chain,
+	/// at which point they will become `Spent`. It is commonplace for transactions not to complete
+	/// for various reasons over which a particular wallet has no control. For this reason,
+	/// [`cancel_tx`](struct.Owner.html#method.cancel_tx) can be used to manually unlock outputs
+	/// and return them to the `Unspent` state.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `slate` - The transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html). All
+	/// * `participant_id` - The participant id, generally 0 for the party putting in funds, 1 for the
+	/// party receiving.
+	/// elements in the `input` vector of the `tx` field that are found in the wallet's currently
+	/// active account will be set to status `Locked`
+	///
+	/// # Returns
+	/// * Ok(()) if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: true,
+	/// 	message: Some("Remember to lock this when we're happy this is sent".to_owned()),
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.init_send_tx(
+	/// 	None,
+	/// 	args,
+	/// );
+	///
+	/// if let Ok(slate) = result {
+	///		// Send slate somehow
+	///		// ...
+	///		// Lock our outputs if we're happy the slate was (or is being) sent
+	///		api_owner.tx_lock_outputs(None, &slate, 0);
+	/// }
+	/// ```
+
+	pub fn tx_lock_outputs(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		slate: &Slate,
+		participant_id: usize,
+	) -> Result<(), Error> {
+		self.with_context(keychain_mask, |ctx| {
+			ctx.tx_lock_outputs(slate, participant_id)
+		})
+	}
+
+	/// Finalizes a transaction, after all parties
+	/// have filled in both rounds of Slate generation. This step adds
+	/// all participants partial signatures to create the final signature,
+	/// resulting in a final transaction that is ready to post to a node.
+	///
+	/// Note that this function DOES NOT POST the transaction to a node
+	/// for validation. This is done in separately via the
+	/// [`post_tx`](struct.Owner.html#method.post_tx) function.
+	///
+	/// A transaction requiring approval, as every send does when the wallet's
+	/// `require_tx_approval` setting is on, is refused until approved via
+	/// [`approve_tx`](struct.Owner.html#method.approve_tx). So is posting it.
+	///
+	/// This function also stores the final transaction in the user's wallet files for retrieval
+	/// via the [`get_stored_tx`](struct.Owner.html#method.get_stored_tx) function.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `slate` - The transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html). All
+	/// participants must have filled in both rounds, and the sender should have locked their
+	/// outputs (via the [`tx_lock_outputs`](struct.Owner.html#method.tx_lock_outputs) function).
+	///
+	/// # Returns
+	/// * ``Ok([`slate`](../grin_wallet_libwallet/slate/struct.Slate.html))` if successful,
+	/// containing the new finalized slate.
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: true,
+	/// 	message: Some("Finalize this tx now".to_owned()),
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.init_send_tx(
+	/// 	None,
+	/// 	args,
+	/// );
+	///
+	/// if let Ok(slate) = result {
+	///		// Send slate somehow
+	///		// ...
+	///		// Lock our outputs if we're happy the slate was (or is being) sent
+	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
+	///		//
+	///		// Retrieve slate back from recipient
+	///		//
+	///		let res = api_owner.finalize_tx(None, &slate);
+	/// }
+	/// ```
+
+	pub fn finalize_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		slate: &Slate,
+	) -> Result<Slate, Error> {
+		self.with_context(keychain_mask, |ctx| ctx.finalize_tx(slate))
+	}
+
+	/// Finalizes invoices issued by this wallet once their payers have processed them, and
+	/// aggregates the resulting transactions into a single one. Merchants receiving many
+	/// small payments in a window can then post them all at once, as one transaction in which
+	/// the payments can't be told apart on chain.
+	///
+	/// Only invoice transactions can be aggregated by their receiver, as the receiver is the one
+	/// finalizing them; in the regular flow it's each sender. Each payment's kernel, and the fee
+	/// paid by its payer, remains in the aggregated transaction.
+	///
+	/// Every slate is checked before any is finalized. Each invoice is then finalized and stored
+	/// as [`finalize_invoice_tx`](struct.Foreign.html#method.finalize_invoice_tx) would, so
+	/// should the aggregation fail, the invoices can still be posted one by one.
+	///
+	/// Note that this function DOES NOT POST the transaction to a node, this is done separately
+	/// via the [`post_tx`](struct.Owner.html#method.post_tx) function.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `slates` - The invoice [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html)s, as
+	/// returned by their payers' [`process_invoice_tx`](struct.Owner.html#method.process_invoice_tx).
+	///
+	/// # Returns
+	/// * Ok with the aggregated [`Transaction`](../grin_core/core/transaction/struct.Transaction.html)
+	/// if successful.
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	///
+	/// // Invoices issued by this wallet, each processed by its payer
+	/// let slates: Vec<Slate> = vec![];
+	/// let result = api_owner.finalize_invoice_txs_aggregated(None, &slates);
+	///
+	/// if let Ok(tx) = result {
+	///		// Post the payments all at once
+	///		let res = api_owner.post_tx(None, &tx, true);
+	/// }
+	/// ```
+	pub fn finalize_invoice_txs_aggregated(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		slates: &[Slate],
+	) -> Result<Transaction, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::finalize_invoice_txs_aggregated(&mut **w, keychain_mask, slates)
+	}
+
+	/// Posts a completed transaction to the listening node for validation and inclusion in a block
+	/// for mining.
+	///
+	/// A send of this wallet whose kernel can't be mined at the next block yet, such as a send
+	/// from a vault account, would be refused by the node. It's held in the wallet instead, and
+	/// posted when the wallet is refreshed once the chain reaches its lock height.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx` - A completed [`Transaction`](../grin_core/core/transaction/struct.Transaction.html),
+	/// typically the `tx` field in the transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html).
+	/// * `fluff` - Instruct the node whether to use the Dandelion protocol when posting the
+	/// transaction. If `true`, the node should skip the Dandelion phase and broadcast the
+	/// transaction to all peers immediately. If `false`, the node will follow dandelion logic and
+	/// initiate the stem phase.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: true,
+	/// 	message: Some("Post this tx".to_owned()),
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.init_send_tx(
+	/// 	None,
+	/// 	args,
+	/// );
+	///
+	/// if let Ok(slate) = result {
+	///		// Send slate somehow
+	///		// ...
+	///		// Lock our outputs if we're happy the slate was (or is being) sent
+	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
+	///		//
+	///		// Retrieve slate back from recipient
+	///		//
+	///		let res = api_owner.finalize_tx(None, &slate);
+	///		let res = api_owner.post_tx(None, &slate.tx, true);
+	/// }
+	/// ```
+
+	pub fn post_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx: &Transaction,
+		fluff: bool,
+	) -> Result<(), Error> {
+		let client = {
+			let mut w_lock = self.wallet_inst.lock();
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			// Test keychain mask, to keep API consistent
+			let _ = w.keychain(keychain_mask)?;
+			self.check_totp(&mut **w, keychain_mask)?;
+			foreign::check_not_frozen(&mut **w)?;
+			owner::check_tx_approved(&mut **w, tx)?;
+			owner::check_node_not_stale(&mut **w)?;
+			if owner::defer_locked_tx(&mut **w, keychain_mask, tx)? {
+				return Ok(());
+			}
+			w.w2n_client().clone()
+		};
+		owner::post_tx(&client, tx, fluff)
+	}
+
+	/// Posts a transaction that didn't originate from this wallet, such as one produced by an
+	/// external signer or recovered from a slate file, to the listening node. The transaction
+	/// is validated before it's posted.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `raw_tx` - The transaction, either as the hex of its binary serialization or as JSON.
+	/// The JSON may be a transaction or a [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html)
+	/// whose `tx` is posted.
+	/// * `fluff` - Instruct the node whether to use the Dandelion protocol when posting the
+	/// transaction, as in [`post_tx`](struct.Owner.html#method.post_tx).
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if the transaction
+	/// can't be parsed, isn't valid or the node refuses it.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// // A transaction file written by an external signer
+	/// let raw_tx = "{}";
+	/// let result = api_owner.post_raw_tx(None, raw_tx, true);
+	/// assert!(result.is_err());
+	/// ```
+
+	pub fn post_raw_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		raw_tx: &str,
+		fluff: bool,
+	) -> Result<(), Error> {
+		let tx = owner::parse_raw_tx(raw_tx)?;
+		self.post_tx(keychain_mask, &tx, fluff)
+	}
+
+	/// Cancels a transaction. This entails:
+	/// * Setting the transaction status to either `TxSentCancelled` or `TxReceivedCancelled`
+	/// * Deleting all change outputs or recipient outputs associated with the transaction
+	/// * Setting the status of all assocatied inputs from `Locked` to `Spent` so they can be
+	/// used in new transactions.
+	///
+	/// Transactions can be cancelled by transaction log id or slate id (call with either set to
+	/// Some, not both)
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_id` - If present, cancel by the [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html) id
+	/// for the transaction.
+	///
+	/// * `tx_slate_id` - If present, cancel by the Slate id.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: true,
+	/// 	message: Some("Cancel this tx".to_owned()),
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.init_send_tx(
+	/// 	None,
+	/// 	args,
+	/// );
+	///
+	/// if let Ok(slate) = result {
+	///		// Send slate somehow
+	///		// ...
+	///		// Lock our outputs if we're happy the slate was (or is being) sent
+	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
+	///		//
+	///		// We didn't get the slate back, or something else went wrong
+	///		//
+	///		let res = api_owner.cancel_tx(None, None, Some(slate.id.clone()));
+	/// }
+	/// ```
+
+	pub fn cancel_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::cancel_tx(&mut **w, keychain_mask, tx_id, tx_slate_id)
+	}
+
+	/// Cancels all the transactions selected by a filter, as
+	/// [`cancel_tx`](struct.Owner.html#method.cancel_tx) would one by one, while taking the
+	/// wallet lock and contacting the node only once. Only unconfirmed sent or received
+	/// transactions are selected. A transaction that can't be cancelled doesn't stop the
+	/// others from being cancelled.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `filter` - The [`TxFilter`](../grin_wallet_libwallet/api_impl/types/struct.TxFilter.html)
+	/// selecting the transactions by type, age and account.
+	///
+	/// # Returns
+	/// * Ok with a [`TxCancelResult`](../grin_wallet_libwallet/api_impl/types/struct.TxCancelResult.html)
+	/// for each selected transaction, holding the error if it couldn't be cancelled.
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// // Cancel everything unconfirmed sent more than a day ago
+	/// let filter = TxFilter {
+	/// 	tx_type: Some(TxLogEntryType::TxSent),
+	/// 	min_age_secs: Some(24 * 3600),
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.cancel_txs(None, &filter);
+	///
+	/// if let Ok(results) = result {
+	/// 	for r in results.iter().filter(|r| r.error.is_some()) {
+	/// 		// Report the transactions left as they were
+	/// 	}
+	/// }
+	/// ```
+
+	pub fn cancel_txs(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		filter: &TxFilter,
+	) -> Result<Vec<TxCancelResult>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::cancel_txs(&mut **w, keychain_mask, filter)
+	}
+
+	/// Marks a received transaction as abandoned, for when its sender cancelled it or
+	/// otherwise never posted it. Unlike [`cancel_tx`](struct.Owner.html#method.cancel_tx),
+	/// the recipient outputs are kept, so the funds show up if the transaction is confirmed
+	/// after all, but the amount isn't counted as awaiting finalization or confirmation
+	/// anymore. Wallets can also do this automatically on refresh, for transactions neither
+	/// confirmed nor in the mempool after `abandon_received_after_hours` in the wallet
+	/// configuration.
+	///
+	/// Transactions can be marked by transaction log id or slate id (call with either set to
+	/// Some, not both)
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_id` - If present, mark by the [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html) id
+	/// for the transaction.
+	///
+	/// * `tx_slate_id` - If present, mark by the Slate id.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as the transaction not being an unconfirmed received one.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_txs(None, true, None, None);
+	///
+	/// if let Ok((_, txs)) = result {
+	/// 	for t in txs.iter().filter(|t| t.tx_type == TxLogEntryType::TxReceived && !t.confirmed) {
+	/// 		// The sender told us they won't post this one
+	/// 		let res = api_owner.abandon_tx(None, Some(t.id), None);
+	/// 	}
+	/// }
+	/// ```
+
+	pub fn abandon_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::abandon_tx(&mut **w, keychain_mask, tx_id, tx_slate_id)
+	}
+
+	/// Approves a transaction created with
+	/// [`InitTxArgs::requires_approval`](../grin_wallet_libwallet/api_impl/types/struct.InitTxArgs.html#structfield.requires_approval)
+	/// set, allowing it to be finalized and posted. This provides dual control over
+	/// payouts: the approval must come with a different approval token (see
+	/// [`create_approval_token`](struct.Owner.html#method.create_approval_token)) than the
+	/// one the transaction was created with. Approval tokens don't depend on the wallet
+	/// password or session, so reopening the wallet doesn't make its creator a second
+	/// operator. Approving an already approved transaction has no effect.
+	///
+	/// Transactions can be approved by transaction log id or slate id (call with either set to
+	/// Some, not both). Transactions whose outputs haven't been locked yet can only be approved
+	/// by slate id.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `approval_token` - Approval token of the approving operator. Approval is refused if
+	/// the token is unknown or revoked, or if this is the token the transaction was created with.
+	/// * `tx_id` - If present, approve by the [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html) id
+	/// for the transaction.
+	///
+	/// * `tx_slate_id` - If present, approve by the Slate id.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let (creator, _) = api_owner.create_approval_token(None, Some("alice".to_owned())).unwrap();
+	/// let (approver, _) = api_owner.create_approval_token(None, Some("bob".to_owned())).unwrap();
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: true,
+	/// 	requires_approval: Some(true),
+	/// 	approval_token: Some(creator),
+	/// 	..Default::default()
+	/// };
+	/// // Created with the first operator's token
+	/// let result = api_owner.init_send_tx(
+	/// 	None,
+	/// 	args,
+	/// );
+	///
+	/// if let Ok(slate) = result {
+	///		// Approved with a second operator's token
+	///		let res = api_owner.approve_tx(None, &approver, None, Some(slate.id.clone()));
+	/// }
+	/// ```
+
+	pub fn approve_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		approval_token: &str,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::approve_tx(&mut **w, keychain_mask, approval_token, tx_id, tx_slate_id)
+	}
+
+	/// Creates a token for an operator creating or approving transactions requiring approval
+	/// (see [`approve_tx`](struct.Owner.html#method.approve_tx)). Each operator should hold
+	/// their own token, which stays valid across wallet sessions until revoked.
+	///
+	/// The token is only returned by this call, the wallet keeps its hash only.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `label` - Optional label for the token, e.g. the operator it's given to.
+	///
+	/// # Returns
+	/// * Ok with the token and its [`ApprovalToken`](../grin_wallet_libwallet/types/struct.ApprovalToken.html)
+	/// record if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.create_approval_token(None, Some("treasurer".to_owned()));
+	///
+	/// if let Ok((token, record)) = result {
+	/// 	println!("Token {} has id {}", token, record.id);
+	/// }
+	/// ```
+
+	pub fn create_approval_token(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		label: Option<String>,
+	) -> Result<(String, ApprovalToken), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::create_approval_token(&mut **w, keychain_mask, label)
+	}
+
+	/// Returns the approval tokens created by
+	/// [`create_approval_token`](struct.Owner.html#method.create_approval_token), oldest first.
+	/// Only the hashes of the tokens are known to the wallet.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with a vector of [`ApprovalToken`](../grin_wallet_libwallet/types/struct.ApprovalToken.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_approval_tokens(None);
+	///
+	/// if let Ok(tokens) = result {
+	/// 	for t in tokens.iter() {
+	/// 		println!("{} {:?}", t.id, t.label);
+	/// 	}
+	/// }
+	/// ```
+
+	pub fn retrieve_approval_tokens(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<Vec<ApprovalToken>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::retrieve_approval_tokens(&mut **w)
+	}
+
+	/// Revokes an approval token, which can't create or approve transactions from then on.
+	/// Approvals already given with it are kept.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `id` - Id of the token, as returned by
+	/// [`retrieve_approval_tokens`](struct.Owner.html#method.retrieve_approval_tokens).
+	///
+	/// # Returns
+	/// * Ok(()) if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// if let Ok((_, record)) = api_owner.create_approval_token(None, None) {
+	/// 	let result = api_owner.revoke_approval_token(None, record.id);
+	/// }
+	/// ```
+
+	pub fn revoke_approval_token(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		id: Uuid,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		self.check_totp(&mut **w, keychain_mask)?;
+		owner::revoke_approval_token(&mut **w, keychain_mask, &id)
+	}
+
+	/// Refunds a received transaction to the refund address its payer attached to the slate
+	/// (see [`SlateAttachmentArgs::refund_address`](../grin_wallet_libwallet/api_impl/types/struct.SlateAttachmentArgs.html#method.refund_address)),
+	/// giving merchants a single call to pay back cancelled or over-paid invoices. A new
+	/// transaction is created, sent to the refund address, finalized and posted, as with
+	/// [`init_send_tx`](struct.Owner.html#method.init_send_tx) with `send_args` set.
+	///
+	/// Only confirmed received transactions can be refunded, and each only once; the refund's
+	/// slate id is recorded in the original
+	/// [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html) along with the
+	/// refund transaction, the wallet staying locked in between, so that two refunds of the
+	/// same transaction can't both be built. A refund that failed to be dispatched is retried
+	/// with [`dispatch_tx`](struct.Owner.html#method.dispatch_tx).
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_slate_id` - The slate id of the received transaction to refund.
+	/// * `amount` - The amount to refund, at most the amount received. If `None`, the full
+	/// amount received is refunded.
+	///
+	/// # Returns
+	/// * ``Ok([`slate`](../grin_wallet_libwallet/slate/struct.Slate.html))` if successful,
+	/// containing the finalized refund slate.
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	///
+	/// let result = api_owner.retrieve_txs(None, true, None, None);
+	///
+	/// if let Ok((_, txs)) = result {
+	///		for t in txs.iter().filter(|t| t.refund_address().is_some()) {
+	///			// Refund half of what was paid
+	///			let amount = t.amount_credited / 2;
+	///			let res = api_owner.refund_tx(None, t.tx_slate_id.unwrap(), Some(amount));
+	///		}
+	/// }
+	/// ```
+
+	pub fn refund_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_slate_id: Uuid,
+		amount: Option<u64>,
+	) -> Result<Slate, Error> {
+		let operations = self.pending_operations()?;
+		let _operation = owner::begin_operation(&operations, "refund_tx", None)?;
+		let slate = self.with_context(keychain_mask, |ctx| {
+			let args = ctx.refund_tx_args(tx_slate_id, amount)?;
+			if let Some(sa) = args.send_args.as_ref() {
+				check_send_method(&sa.method)?;
+			}
+			let slate = ctx.prepare_send(args)?.slate;
+			ctx.set_tx_refunded(tx_slate_id, slate.id)?;
+			Ok(slate)
+		})?;
+		self.dispatch_tx(keychain_mask, slate.id)
+	}
+
+	/// Retrieves the stored transaction associated with a TxLogEntry. Can be used even after the
+	/// transaction has completed.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_log_entry` - A [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html)
+	///
+	/// # Returns
+	/// * Ok with the stored  [`Transaction`](../grin_core/core/transaction/struct.Transaction.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let update_from_node = true;
+	/// let tx_id = None;
+	/// let tx_slate_id = None;
+	///
+	/// // Return all TxLogEntries
+	/// let result = api_owner.retrieve_txs(None, update_from_node, tx_id, tx_slate_id);
+	///
+	/// if let Ok((was_updated, tx_log_entries)) = result {
+	///		let stored_tx = api_owner.get_stored_tx(None, &tx_log_entries[0]).unwrap();
+	///		//...
+	/// }
+	/// ```
+
+	// TODO: Should be accepting an id, not an entire entry struct
+	pub fn get_stored_tx(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_log_entry: &TxLogEntry,
+	) -> Result<Option<Transaction>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::get_stored_tx(&**w, tx_log_entry)
+	}
+
+	/// Returns the proof address of the wallet, which payment proofs name the sender and
+	/// the recipient by. Merchants publish it so that their customers' payment proofs show
+	/// the payments were made to them.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok with the hex of the proof address if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.payment_proof_address(None);
+	/// ```
+
+	pub fn payment_proof_address(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<String, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::payment_proof_address(&mut **w, keychain_mask)
+	}
+
+	/// Returns the payment proof of a transaction sent with the `payment_proof` flag of
+	/// [`InitTxArgs`](../grin_wallet_libwallet/api_impl/types/struct.InitTxArgs.html) set. The
+	/// recipient signed the proof when receiving the slate, and the sender when finalizing it.
+	/// The proof holds once the transaction is confirmed, see
+	/// [`verify_payment_proof`](struct.Owner.html#method.verify_payment_proof).
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_id` - If present, the [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html)
+	/// id of the transaction.
+	/// * `tx_slate_id` - If present, the Slate id of the transaction.
+	///
+	/// # Returns
+	/// * Ok with the [`PaymentProof`](../grin_wallet_libwallet/payment_proof/struct.PaymentProof.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as the transaction having no proof.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_payment_proof(None, Some(3), None);
+	/// ```
+
+	pub fn retrieve_payment_proof(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<PaymentProof, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::retrieve_payment_proof(&mut **w, tx_id, tx_slate_id)
+	}
+
+	/// Verifies a payment proof, from this wallet or any other: it must be signed by both its
+	/// sender and its recipient, and its kernel must be on chain, as the node of the wallet
+	/// sees it.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `proof` - The [`PaymentProof`](../grin_wallet_libwallet/payment_proof/struct.PaymentProof.html)
+	/// to verify.
+	///
+	/// # Returns
+	/// * Ok with whether the sender is this wallet and whether the recipient is this wallet,
+	/// if the proof is valid
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if the proof is invalid
+	/// or another error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// if let Ok(proof) = api_owner.retrieve_payment_proof(None, Some(3), None) {
+	/// 	let result = api_owner.verify_payment_proof(None, &proof);
+	/// }
+	/// ```
+
+	pub fn verify_payment_proof(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		proof: &PaymentProof,
+	) -> Result<(bool, bool), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::verify_payment_proof(&mut **w, keychain_mask, proof)
+	}
+
+	/// Audits the outputs a transaction created (change outputs for a send, received outputs
+	/// otherwise), recomputing the derivation index each was created with and checking it can
+	/// be rebuilt from the wallet seed alone, so would be found again by a restore. The range
+	/// proof of an output on the chain is rewound with the seed, as a restore does. Outputs
+	/// whose commitment doesn't match the key derived from the seed (created with an
+	/// out-of-band key) or whose derivation index is shared with another output are flagged.
+	///
+	/// Transactions can be selected by transaction log id or slate id (call with either set to
+	/// Some, not both)
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_id` - If present, audit by the [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html) id
+	/// for the transaction.
+	///
+	/// * `tx_slate_id` - If present, audit by the Slate id.
+	///
+	/// # Returns
+	/// * Ok with a vector of [`OutputDerivationAudit`](../grin_wallet_libwallet/api_impl/types/struct.OutputDerivationAudit.html),
+	/// one per output created by the transaction, if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	///
+	/// let result = api_owner.audit_tx_outputs(None, Some(1), None);
+	///
+	/// if let Ok(audits) = result {
+	///		for a in audits.iter().filter(|a| !a.recoverable || a.index_reused) {
+	///			println!("{:?}", a.issues);
+	///		}
+	/// }
+	/// ```
+
+	pub fn audit_tx_outputs(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<Vec<OutputDerivationAudit>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::audit_tx_outputs(&mut **w, keychain_mask, tx_id, tx_slate_id)
+	}
+
+	/// Returns the structured attachments (such as order ids or refund addresses) recorded
+	/// for a transaction. Attachments are recorded when a slate is received, when a sender
+	/// locks their outputs and when a slate is finalized. Encrypted attachments exchanged with
+	/// the other participant are returned decrypted; any this wallet couldn't decrypt have
+	/// their `content` set to `None`.
+	///
+	/// Transactions can be selected by transaction log id or slate id (call with either set to
+	/// Some, not both)
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `tx_id` - If present, retrieve by the [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html) id
+	/// for the transaction.
+	///
+	/// * `tx_slate_id` - If present, retrieve by the Slate id.
+	///
+	/// # Returns
+	/// * Ok with a vector of [`ParticipantAttachmentData`](../grin_wallet_libwallet/slate/struct.ParticipantAttachmentData.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	///
+	/// let result = api_owner.retrieve_tx_attachments(None, Some(1), None);
+	///
+	/// if let Ok(attachments) = result {
+	///		for a in attachments.iter().filter(|a| a.kind == "order_id") {
+	///			println!("{:?}", a.content);
+	///		}
+	/// }
+	/// ```
+
+	pub fn retrieve_tx_attachments(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<Vec<ParticipantAttachmentData>, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::retrieve_tx_attachments(&mut **w, tx_id, tx_slate_id)
+	}
+
+	/// Verifies all messages in the slate match their public keys.
+	///
+	/// The optional messages themselves are part of the `participant_data` field within the slate.
+	/// Messages are signed with the same key used to sign for the paricipant's inputs, and can thus be
+	/// verified with the public key found in the `public_blind_excess` field. This function is a
+	/// simple helper to returns whether all signatures in the participant data match their public
+	/// keys.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `slate` - The transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html).
+	///
+	/// # Returns
+	/// * `Ok(())` if successful and the signatures validate
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
 	/// 	amount: 2_000_000_000,
-	/// 	minimum_confirmations: 2,
+	/// 	minimum_confirmations: 10,
 	/// 	max_outputs: 500,
 	/// 	num_change_outputs: 1,
 	/// 	selection_strategy_is_use_all: true,
-	/// 	message: Some("Have some Grins. Love, Yeastplume".to_owned()),
+	/// 	message: Some("Just verify messages".to_owned()),
 	/// 	..Default::default()
 	/// };
 	/// let result = api_owner.init_send_tx(
@@ -547,71 +4222,44 @@ where
 	/// );
 	///
 	/// if let Ok(slate) = result {
-	/// 	// Send slate somehow
-	/// 	// ...
-	/// 	// Lock our outputs if we're happy the slate was (or is being) sent
-	/// 	api_owner.tx_lock_outputs(None, &slate, 0);
+	///		// Send slate somehow
+	///		// ...
+	///		// Lock our outputs if we're happy the slate was (or is being) sent
+	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
+	///		//
+	///		// Retrieve slate back from recipient
+	///		//
+	///		let res = api_owner.verify_slate_messages(None, &slate);
 	/// }
 	/// ```
-
-	pub fn init_send_tx(
+	pub fn verify_slate_messages(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		args: InitTxArgs,
-	) -> Result<Slate, Error> {
-		let send_args = args.send_args.clone();
-		let mut slate = {
+		slate: &Slate,
+	) -> Result<(), Error> {
+		{
 			let mut w_lock = self.wallet_inst.lock();
 			let w = w_lock.lc_provider()?.wallet_inst()?;
-			owner::init_send_tx(&mut **w, keychain_mask, args, self.doctest_mode)?
-		};
-		// Helper functionality. If send arguments exist, attempt to send
-		match send_args {
-			Some(sa) => {
-				//TODO: in case of keybase, the response might take 60s and leave the service hanging
-				match sa.method.as_ref() {
-					"http" | "keybase" => {}
-					_ => {
-						error!("unsupported payment method: {}", sa.method);
-						return Err(ErrorKind::ClientCallback(
-							"unsupported payment method".to_owned(),
-						)
-						.into());
-					}
-				};
-				let comm_adapter = create_sender(&sa.method, &sa.dest)
-					.map_err(|e| ErrorKind::GenericError(format!("{}", e)))?;
-				slate = comm_adapter.send_tx(&slate)?;
-				self.tx_lock_outputs(keychain_mask, &slate, 0)?;
-				let slate = match sa.finalize {
-					true => self.finalize_tx(keychain_mask, &slate)?,
-					false => slate,
-				};
-
-				if sa.post_tx {
-					self.post_tx(keychain_mask, &slate.tx, sa.fluff)?;
-				}
-				Ok(slate)
-			}
-			None => Ok(slate),
+			// Test keychain mask, to keep API consistent
+			let _ = w.keychain(keychain_mask)?;
 		}
+		owner::verify_slate_messages(slate)
 	}
 
-	/// Issues a new invoice transaction slate, essentially a `request for payment`.
-	/// The slate created by this function will contain the amount, an output for the amount,
-	/// as well as round 1 of singature creation complete. The slate should then be send
-	/// to the payer, who should add their inputs and signature data and return the slate
-	/// via the [Foreign API's `finalize_invoice_tx`](struct.Foreign.html#method.finalize_invoice_tx) method.
+	/// Describes a slate in human readable form: its amount and fee, heights, the
+	/// participants who took part so far with their messages and attachments, and
+	/// which signing rounds are complete. Meant to show users what they're about
+	/// to sign, rather than the raw slate.
 	///
 	/// # Arguments
+	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `args` - [`IssueInvoiceTxArgs`](../grin_wallet_libwallet/types/struct.IssueInvoiceTxArgs.html),
-	/// invoice transaction initialization arguments. See struct documentation for further detail.
+	/// * `slate` - The transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html).
 	///
 	/// # Returns
-	/// * ``Ok([`slate`](../grin_wallet_libwallet/slate/struct.Slate.html))` if successful,
-	/// containing the updated slate.
+	/// * Ok with a [`SlateDescription`](../grin_wallet_libwallet/slate/struct.SlateDescription.html)
+	/// if successful. Messages whose signature doesn't verify are flagged, not an error.
 	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
 	///
 	/// # Example
@@ -620,242 +4268,368 @@ where
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
 	/// let mut api_owner = Owner::new(wallet.clone());
-	///
-	/// let args = IssueInvoiceTxArgs {
-	/// 	amount: 60_000_000_000,
+	/// let args = InitTxArgs {
+	/// 	src_acct_name: None,
+	/// 	amount: 2_000_000_000,
+	/// 	minimum_confirmations: 10,
+	/// 	max_outputs: 500,
+	/// 	num_change_outputs: 1,
+	/// 	selection_strategy_is_use_all: true,
+	/// 	message: Some("Describe me".to_owned()),
 	/// 	..Default::default()
 	/// };
-	/// let result = api_owner.issue_invoice_tx(None, args);
+	/// let result = api_owner.init_send_tx(
+	/// 	None,
+	/// 	args,
+	/// );
 	///
 	/// if let Ok(slate) = result {
-	///		// if okay, send to the payer to add their inputs
-	///		// . . .
+	///		let description = api_owner.describe_slate(None, &slate);
+	///		if let Ok(d) = description {
+	///			println!("{}", d);
+	///		}
 	/// }
 	/// ```
-	pub fn issue_invoice_tx(
+	pub fn describe_slate(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		args: IssueInvoiceTxArgs,
-	) -> Result<Slate, Error> {
+		slate: &Slate,
+	) -> Result<SlateDescription, Error> {
 		let mut w_lock = self.wallet_inst.lock();
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::issue_invoice_tx(&mut **w, keychain_mask, args, self.doctest_mode)
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		Ok(owner::describe_slate(&mut **w, slate))
 	}
 
-	/// Processes an invoice tranaction created by another party, essentially
-	/// a `request for payment`. The incoming slate should contain a requested
-	/// amount, an output created by the invoicer convering the amount, and
-	/// part 1 of signature creation completed. This function will add inputs
-	/// equalling the amount + fees, as well as perform round 1 and 2 of signature
-	/// creation.
+	/// Scans the entire UTXO set from the node, creating outputs for each scanned
+	/// output that matches the wallet's master seed. This function is intended to be called as part
+	/// of a recovery process (either from BIP32 phrase or backup seed files,) and will error if the
+	/// wallet is non-empty, i.e. contains any outputs at all.
 	///
-	/// Callers should note that no prompting of the user will be done by this function
-	/// it is up to the caller to present the request for payment to the user
-	/// and verify that payment should go ahead.
+	/// This operation scans the entire chain, and is expected to be time intensive. It is imperative
+	/// that no other processes should be trying to use the wallet at the same time this function is
+	/// running.
 	///
-	/// This function also stores the final transaction in the user's wallet files for retrieval
-	/// via the [`get_stored_tx`](struct.Owner.html#method.get_stored_tx) function.
+	/// A single [TxLogEntry](../grin_wallet_libwallet/types/struct.TxLogEntry.html) is created for
+	/// all non-coinbase outputs discovered and restored during this process. A separate entry
+	/// is created for each coinbase output.
 	///
 	/// # Arguments
+	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `slate` - The transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html). The
-	/// payer should have filled in round 1 and 2.
-	/// * `args` - [`InitTxArgs`](../grin_wallet_libwallet/types/struct.InitTxArgs.html),
-	/// transaction initialization arguments. See struct documentation for further detail.
 	///
 	/// # Returns
-	/// * ``Ok([`slate`](../grin_wallet_libwallet/slate/struct.Slate.html))` if successful,
-	/// containing the updated slate.
+	/// * `Ok(())` if successful
 	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
-	///
+
 	/// # Example
 	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
 	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.restore(None);
 	///
-	/// // . . .
-	/// // The slate has been recieved from the invoicer, somehow
-	/// # let slate = Slate::blank(2);
-	/// let args = InitTxArgs {
-	///		src_acct_name: None,
-	///		amount: slate.amount,
-	///		minimum_confirmations: 2,
-	///		max_outputs: 500,
-	///		num_change_outputs: 1,
-	///		selection_strategy_is_use_all: true,
-	///		..Default::default()
-	///	};
+	/// if let Ok(_) = result {
+	///		// Wallet outputs should be consistent with what's on chain
+	///		// ...
+	/// }
+	/// ```
+	pub fn restore(&self, keychain_mask: Option<&SecretKey>) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let res = owner::restore(&mut **w, keychain_mask, None);
+		res
+	}
+
+	/// Restores the wallet like [`restore`](struct.Owner.html#method.restore), but only from
+	/// the outputs created from its birthday on, the height of the first block that could hold
+	/// one of its outputs. Only the part of the UTXO set added since is downloaded from the node,
+	/// and only its rangeproofs are rewound, so that restoring a young wallet on a new device
+	/// takes minutes rather than a scan of the whole chain.
 	///
-	/// let result = api_owner.process_invoice_tx(None, &slate, args);
+	/// Outputs created before the birthday aren't found, so it must be no later than the
+	/// wallet's first transaction. If the node doesn't report the output MMR size in its
+	/// headers, the whole UTXO set is downloaded but older outputs still aren't rewound.
 	///
-	/// if let Ok(slate) = result {
-	///	// If result okay, send back to the invoicer
-	///	// . . .
-	///	}
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `birthday_height` - Height of the block the wallet was created at or before.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let mut api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.restore_from_birthday(None, 500_000);
+	///
+	/// if let Ok(_) = result {
+	///		// Wallet outputs created since block 500000 should be consistent with the chain
+	///		// ...
+	/// }
 	/// ```
+	pub fn restore_from_birthday(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		birthday_height: u64,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::restore(&mut **w, keychain_mask, Some(birthday_height))
+	}
 
-	pub fn process_invoice_tx(
+	/// Starts a step-wise restore, for a wizard that shows the progress of the scan of the
+	/// UTXO set and a preview of the recovered balance, and lets the user cancel the restore,
+	/// before anything is written to the wallet. Once the mnemonic has been checked with
+	/// [`validate_mnemonic`](struct.Owner.html#method.validate_mnemonic) and the wallet created
+	/// from it and opened, the returned scan is handed to
+	/// [`restore_scan`](struct.Owner.html#method.restore_scan) until complete, then to
+	/// [`restore_commit`](struct.Owner.html#method.restore_commit).
+	///
+	/// The scan covers the outputs on chain up to the last one at the time of the call, and
+	/// only the ones created from the birthday on if given, as in
+	/// [`restore_from_birthday`](struct.Owner.html#method.restore_from_birthday). Its
+	/// [`total`](../grin_wallet_libwallet/api_impl/types/struct.RestoreScan.html#method.total)
+	/// estimates the length of the scan.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `birthday_height` - Height of the block the wallet was created at or before, if known.
+	///
+	/// # Returns
+	/// * Ok([`RestoreScan`](../grin_wallet_libwallet/api_impl/types/struct.RestoreScan.html))
+	/// with nothing scanned yet, if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let mut scan = api_owner.restore_scan_start(None, None).unwrap();
+	///
+	/// while !scan.is_complete() {
+	///		scan = api_owner.restore_scan(None, scan, 1000).unwrap();
+	///		println!("{} of {} left, {} found", scan.remaining(), scan.total(), scan.amount_found());
+	/// }
+	/// let result = api_owner.restore_commit(None, &scan);
+	/// ```
+	pub fn restore_scan_start(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		slate: &Slate,
-		args: InitTxArgs,
-	) -> Result<Slate, Error> {
+		birthday_height: Option<u64>,
+	) -> Result<RestoreScan, Error> {
 		let mut w_lock = self.wallet_inst.lock();
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::process_invoice_tx(&mut **w, keychain_mask, slate, args, self.doctest_mode)
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::restore_scan_start(&mut **w, birthday_height)
 	}
 
-	/// Locks the outputs associated with the inputs to the transaction in the given
-	/// [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html),
-	/// making them unavailable for use in further transactions. This function is called
-	/// by the sender, (or more generally, all parties who have put inputs into the transaction,)
-	/// and must be called before the corresponding call to [`finalize_tx`](struct.Owner.html#method.finalize_tx)
-	/// that completes the transaction.
+	/// Scans the next chunk of outputs of a step-wise restore started with
+	/// [`restore_scan_start`](struct.Owner.html#method.restore_scan_start), adding the ones
+	/// belonging to the wallet to the outputs found. Nothing is written to the wallet, so the
+	/// restore is cancelled by no longer calling it, and resumed by passing the last scan
+	/// returned back in. The wallet is only locked for the duration of the call.
 	///
-	/// Outputs will generally remain locked until they are removed from the chain,
-	/// at which point they will become `Spent`. It is commonplace for transactions not to complete
-	/// for various reasons over which a particular wallet has no control. For this reason,
-	/// [`cancel_tx`](struct.Owner.html#method.cancel_tx) can be used to manually unlock outputs
-	/// and return them to the `Unspent` state.
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `scan` - The scan returned by the previous step.
+	/// * `max_outputs` - Most outputs to download and rewind in this step.
+	///
+	/// # Returns
+	/// * Ok([`RestoreScan`](../grin_wallet_libwallet/api_impl/types/struct.RestoreScan.html))
+	/// with the chunk scanned, if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`restore_scan_start`](struct.Owner.html#method.restore_scan_start).
+	pub fn restore_scan(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		scan: RestoreScan,
+		max_outputs: u64,
+	) -> Result<RestoreScan, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::restore_scan(&mut **w, keychain_mask, scan, max_outputs)
+	}
+
+	/// Saves the outputs found by a complete step-wise restore to the wallet, restoring their
+	/// transaction log entries and accounts as [`restore`](struct.Owner.html#method.restore)
+	/// does. The wallet must still be empty, and each output is checked against its key before
+	/// anything is saved.
 	///
 	/// # Arguments
+	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `slate` - The transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html). All
-	/// * `participant_id` - The participant id, generally 0 for the party putting in funds, 1 for the
-	/// party receiving.
-	/// elements in the `input` vector of the `tx` field that are found in the wallet's currently
-	/// active account will be set to status `Locked`
+	/// * `scan` - The scan returned by the last step, once complete.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`restore_scan_start`](struct.Owner.html#method.restore_scan_start).
+	pub fn restore_commit(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		scan: &RestoreScan,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::restore_commit(&mut **w, keychain_mask, scan)
+	}
+
+	/// Scans the entire UTXO set from the node, identify which outputs belong to the given wallet
+	/// update the wallet state to be consistent with what's currently in the UTXO set.
+	///
+	/// This function can be used to repair wallet state, particularly by restoring outputs that may
+	/// be missing if the wallet owner has cancelled transactions locally that were then successfully
+	/// posted to the chain.
+	///
+	/// This operation scans the entire chain, and is expected to be time intensive. It is imperative
+	/// that no other processes should be trying to use the wallet at the same time this function is
+	/// running.
+	///
+	/// When an output is found that doesn't exist in the wallet, a corresponding
+	/// [TxLogEntry](../grin_wallet_libwallet/types/struct.TxLogEntry.html) is created.
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `delete_unconfirmed` - if `false`, the check_repair process will be non-destructive, and
+	/// mostly limited to restoring missing outputs. It will leave unconfirmed transaction logs entries
+	/// and unconfirmed outputs intact. If `true`, the process will unlock all locked outputs,
+	/// restore all missing outputs, and mark any outputs that have been marked 'Spent' but are still
+	/// in the UTXO set as 'Unspent' (as can happen during a fork). It will also attempt to cancel any
+	/// transaction log entries associated with any locked outputs or outputs incorrectly marked 'Spent'.
+	/// Note this completely removes all outstanding transactions, so users should be very aware what
+	/// will happen if this flag is set. Note that if transactions/outputs are removed that later
+	/// confirm on the chain, another call to this function will restore them.
 	///
 	/// # Returns
-	/// * Ok(()) if successful
+	/// * `Ok(())` if successful
 	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
-	///
+
 	/// # Example
 	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
 	/// let mut api_owner = Owner::new(wallet.clone());
-	/// let args = InitTxArgs {
-	/// 	src_acct_name: None,
-	/// 	amount: 2_000_000_000,
-	/// 	minimum_confirmations: 10,
-	/// 	max_outputs: 500,
-	/// 	num_change_outputs: 1,
-	/// 	selection_strategy_is_use_all: true,
-	/// 	message: Some("Remember to lock this when we're happy this is sent".to_owned()),
-	/// 	..Default::default()
-	/// };
-	/// let result = api_owner.init_send_tx(
+	/// let result = api_owner.check_repair(
 	/// 	None,
-	/// 	args,
+	/// 	false,
 	/// );
 	///
-	/// if let Ok(slate) = result {
-	///		// Send slate somehow
+	/// if let Ok(_) = result {
+	///		// Wallet outputs should be consistent with what's on chain
 	///		// ...
-	///		// Lock our outputs if we're happy the slate was (or is being) sent
-	///		api_owner.tx_lock_outputs(None, &slate, 0);
 	/// }
 	/// ```
 
-	pub fn tx_lock_outputs(
+	pub fn check_repair(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		slate: &Slate,
-		participant_id: usize,
+		delete_unconfirmed: bool,
 	) -> Result<(), Error> {
 		let mut w_lock = self.wallet_inst.lock();
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::tx_lock_outputs(&mut **w, keychain_mask, slate, participant_id)
+		owner::check_repair(&mut **w, keychain_mask, delete_unconfirmed)
 	}
 
-	/// Finalizes a transaction, after all parties
-	/// have filled in both rounds of Slate generation. This step adds
-	/// all participants partial signatures to create the final signature,
-	/// resulting in a final transaction that is ready to post to a node.
-	///
-	/// Note that this function DOES NOT POST the transaction to a node
-	/// for validation. This is done in separately via the
-	/// [`post_tx`](struct.Owner.html#method.post_tx) function.
+	/// Imports an output the wallet can spend but doesn't know of, for recovering funds
+	/// when part of the wallet database was lost or the output was created by a custom
+	/// tool, without the full scan of [`check_repair`](struct.Owner.html#method.check_repair).
 	///
-	/// This function also stores the final transaction in the user's wallet files for retrieval
-	/// via the [`get_stored_tx`](struct.Owner.html#method.get_stored_tx) function.
+	/// The output is only accepted if its key is derived from one of the wallet's accounts,
+	/// its commitment is the one of `value` under that key, the node has it in the UTXO set
+	/// and, if `height` is given, it was created at that height. Outputs created with a raw
+	/// blinding factor rather than a key of the wallet can't be imported, as the wallet
+	/// couldn't spend them. A received transaction log entry is created for the output and
+	/// the account's next child index is moved past its key.
 	///
 	/// # Arguments
+	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `slate` - The transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html). All
-	/// participants must have filled in both rounds, and the sender should have locked their
-	/// outputs (via the [`tx_lock_outputs`](struct.Owner.html#method.tx_lock_outputs) function).
+	/// * `commit` - Commitment of the output.
+	/// * `value` - Value of the output, in nanogrins.
+	/// * `key_id` - Key the output's blinding factor is derived with.
+	/// * `height` - Height of the block that created the output, checked if given.
 	///
 	/// # Returns
-	/// * ``Ok([`slate`](../grin_wallet_libwallet/slate/struct.Slate.html))` if successful,
-	/// containing the new finalized slate.
-	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	/// * Ok([`OutputData`](../grin_wallet_libwallet/types/struct.OutputData.html)) of the
+	/// imported output, if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// and the output isn't imported.
 	///
 	/// # Example
 	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	/// use keychain::Keychain;
+	/// use util::secp::pedersen::Commitment;
 	///
-	/// let mut api_owner = Owner::new(wallet.clone());
-	/// let args = InitTxArgs {
-	/// 	src_acct_name: None,
-	/// 	amount: 2_000_000_000,
-	/// 	minimum_confirmations: 10,
-	/// 	max_outputs: 500,
-	/// 	num_change_outputs: 1,
-	/// 	selection_strategy_is_use_all: true,
-	/// 	message: Some("Finalize this tx now".to_owned()),
-	/// 	..Default::default()
-	/// };
-	/// let result = api_owner.init_send_tx(
-	/// 	None,
-	/// 	args,
-	/// );
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let commit = Commitment::from_vec(vec![8; 33]);
+	/// let key_id = ExtKeychain::derive_key_id(3, 0, 0, 12, 0);
+	/// let result = api_owner.import_output(None, &commit, 1_000_000_000, &key_id, None);
 	///
-	/// if let Ok(slate) = result {
-	///		// Send slate somehow
+	/// if let Ok(output) = result {
+	///		// The output can now be spent
 	///		// ...
-	///		// Lock our outputs if we're happy the slate was (or is being) sent
-	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
-	///		//
-	///		// Retrieve slate back from recipient
-	///		//
-	///		let res = api_owner.finalize_tx(None, &slate);
 	/// }
 	/// ```
-
-	pub fn finalize_tx(
+	pub fn import_output(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		slate: &Slate,
-	) -> Result<Slate, Error> {
+		commit: &pedersen::Commitment,
+		value: u64,
+		key_id: &Identifier,
+		height: Option<u64>,
+	) -> Result<OutputData, Error> {
 		let mut w_lock = self.wallet_inst.lock();
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::finalize_tx(&mut **w, keychain_mask, &slate)
+		owner::import_output(&mut **w, keychain_mask, commit, value, key_id, height)
 	}
 
-	/// Posts a completed transaction to the listening node for validation and inclusion in a block
-	/// for mining.
+	/// Rebuilds the data the wallet derives from its outputs and transaction log, for
+	/// when it has been corrupted and, for instance,
+	/// [`retrieve_outputs`](struct.Owner.html#method.retrieve_outputs) misses outputs the
+	/// transaction log shows. Cached output commitments are recomputed, links from outputs
+	/// to transaction log entries that don't exist are removed and sent transactions are
+	/// linked back to the transaction file stored under their slate id. Links to stored
+	/// transaction files that don't exist are removed.
+	///
+	/// Unlike [`check_repair`](struct.Owner.html#method.check_repair), this doesn't
+	/// contact the node.
 	///
 	/// # Arguments
+	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `tx` - A completed [`Transaction`](../grin_core/core/transaction/struct.Transaction.html),
-	/// typically the `tx` field in the transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html).
-	/// * `fluff` - Instruct the node whether to use the Dandelion protocol when posting the
-	/// transaction. If `true`, the node should skip the Dandelion phase and broadcast the
-	/// transaction to all peers immediately. If `false`, the node will follow dandelion logic and
-	/// initiate the stem phase.
 	///
 	/// # Returns
-	/// * `Ok(())` if successful
+	/// * Ok([`IndexRebuildReport`](../grin_wallet_libwallet/api_impl/types/struct.IndexRebuildReport.html))
+	/// counting the changes made, if successful
 	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
 	///
 	/// # Example
@@ -864,70 +4638,42 @@ where
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
 	/// let mut api_owner = Owner::new(wallet.clone());
-	/// let args = InitTxArgs {
-	/// 	src_acct_name: None,
-	/// 	amount: 2_000_000_000,
-	/// 	minimum_confirmations: 10,
-	/// 	max_outputs: 500,
-	/// 	num_change_outputs: 1,
-	/// 	selection_strategy_is_use_all: true,
-	/// 	message: Some("Post this tx".to_owned()),
-	/// 	..Default::default()
-	/// };
-	/// let result = api_owner.init_send_tx(
-	/// 	None,
-	/// 	args,
-	/// );
+	/// let result = api_owner.rebuild_indexes(None);
 	///
-	/// if let Ok(slate) = result {
-	///		// Send slate somehow
+	/// if let Ok(report) = result {
+	///		// Outputs and transactions are consistently linked again
 	///		// ...
-	///		// Lock our outputs if we're happy the slate was (or is being) sent
-	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
-	///		//
-	///		// Retrieve slate back from recipient
-	///		//
-	///		let res = api_owner.finalize_tx(None, &slate);
-	///		let res = api_owner.post_tx(None, &slate.tx, true);
 	/// }
 	/// ```
 
-	pub fn post_tx(
+	pub fn rebuild_indexes(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		tx: &Transaction,
-		fluff: bool,
-	) -> Result<(), Error> {
-		let client = {
-			let mut w_lock = self.wallet_inst.lock();
-			let w = w_lock.lc_provider()?.wallet_inst()?;
-			// Test keychain mask, to keep API consistent
-			let _ = w.keychain(keychain_mask)?;
-			w.w2n_client().clone()
-		};
-		owner::post_tx(&client, tx, fluff)
+	) -> Result<IndexRebuildReport, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		owner::rebuild_indexes(&mut **w, keychain_mask)
 	}
 
-	/// Cancels a transaction. This entails:
-	/// * Setting the transaction status to either `TxSentCancelled` or `TxReceivedCancelled`
-	/// * Deleting all change outputs or recipient outputs associated with the transaction
-	/// * Setting the status of all assocatied inputs from `Locked` to `Spent` so they can be
-	/// used in new transactions.
+	/// Retrieves the last known height known by the wallet. This is determined as follows:
+	/// * If the wallet can successfully contact its configured node, the reported node
+	/// height is returned, and the `updated_from_node` field in the response is `true`
+	/// * If the wallet cannot contact the node, this function returns the maximum height
+	/// of all outputs contained within the wallet, and the `updated_from_node` fields
+	/// in the response is set to false.
 	///
-	/// Transactions can be cancelled by transaction log id or slate id (call with either set to
-	/// Some, not both)
+	/// Clients should generally ensure the `updated_from_node` field is returned as
+	/// `true` before assuming the height for any operation.
 	///
 	/// # Arguments
 	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `tx_id` - If present, cancel by the [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html) id
-	/// for the transaction.
-	///
-	/// * `tx_slate_id` - If present, cancel by the Slate id.
 	///
 	/// # Returns
-	/// * `Ok(())` if successful
+	/// * Ok with a  [`NodeHeightResult`](../grin_wallet_libwallet/types/struct.NodeHeightResult.html)
+	/// if successful. If the height result was obtained from the configured node,
+	/// `updated_from_node` will be set to `true`
 	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
 	///
 	/// # Example
@@ -935,58 +4681,43 @@ where
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
-	/// let mut api_owner = Owner::new(wallet.clone());
-	/// let args = InitTxArgs {
-	/// 	src_acct_name: None,
-	/// 	amount: 2_000_000_000,
-	/// 	minimum_confirmations: 10,
-	/// 	max_outputs: 500,
-	/// 	num_change_outputs: 1,
-	/// 	selection_strategy_is_use_all: true,
-	/// 	message: Some("Cancel this tx".to_owned()),
-	/// 	..Default::default()
-	/// };
-	/// let result = api_owner.init_send_tx(
-	/// 	None,
-	/// 	args,
-	/// );
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.node_height(None);
 	///
-	/// if let Ok(slate) = result {
-	///		// Send slate somehow
-	///		// ...
-	///		// Lock our outputs if we're happy the slate was (or is being) sent
-	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
-	///		//
-	///		// We didn't get the slate back, or something else went wrong
-	///		//
-	///		let res = api_owner.cancel_tx(None, None, Some(slate.id.clone()));
+	/// if let Ok(node_height_result) = result {
+	///		if node_height_result.updated_from_node {
+	///			//we can assume node_height_result.height is relatively safe to use
+	///
+	///		}
+	///		//...
 	/// }
 	/// ```
 
-	pub fn cancel_tx(
+	pub fn node_height(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		tx_id: Option<u32>,
-		tx_slate_id: Option<Uuid>,
-	) -> Result<(), Error> {
+	) -> Result<NodeHeightResult, Error> {
 		let mut w_lock = self.wallet_inst.lock();
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::cancel_tx(&mut **w, keychain_mask, tx_id, tx_slate_id)
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::node_height(&mut **w, keychain_mask)
 	}
 
-	/// Retrieves the stored transaction associated with a TxLogEntry. Can be used even after the
-	/// transaction has completed.
+	/// Retrieves the connections, sync state and protocol version of the wallet's node.
+	/// While the node is syncing, calls that refresh the wallet from the node skip
+	/// the refresh and report that their results weren't validated.
 	///
 	/// # Arguments
 	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `tx_log_entry` - A [`TxLogEntry`](../grin_wallet_libwallet/types/struct.TxLogEntry.html)
 	///
 	/// # Returns
-	/// * Ok with the stored  [`Transaction`](../grin_core/core/transaction/struct.Transaction.html)
+	/// * Ok with a [`NodeStatus`](../grin_wallet_libwallet/types/struct.NodeStatus.html)
 	/// if successful
-	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// including when the node can't be contacted.
 	///
 	/// # Example
 	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
@@ -994,206 +4725,208 @@ where
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
 	/// let api_owner = Owner::new(wallet.clone());
-	/// let update_from_node = true;
-	/// let tx_id = None;
-	/// let tx_slate_id = None;
-	///
-	/// // Return all TxLogEntries
-	/// let result = api_owner.retrieve_txs(None, update_from_node, tx_id, tx_slate_id);
+	/// let result = api_owner.node_status(None);
 	///
-	/// if let Ok((was_updated, tx_log_entries)) = result {
-	///		let stored_tx = api_owner.get_stored_tx(None, &tx_log_entries[0]).unwrap();
+	/// if let Ok(status) = result {
+	///		if status.is_synced() {
+	///			// balances refreshed from the node can be relied on
+	///		}
 	///		//...
 	/// }
 	/// ```
 
-	// TODO: Should be accepting an id, not an entire entry struct
-	pub fn get_stored_tx(
-		&self,
-		keychain_mask: Option<&SecretKey>,
-		tx_log_entry: &TxLogEntry,
-	) -> Result<Option<Transaction>, Error> {
+	pub fn node_status(&self, keychain_mask: Option<&SecretKey>) -> Result<NodeStatus, Error> {
 		let mut w_lock = self.wallet_inst.lock();
 		let w = w_lock.lc_provider()?.wallet_inst()?;
 		// Test keychain mask, to keep API consistent
 		let _ = w.keychain(keychain_mask)?;
-		owner::get_stored_tx(&**w, tx_log_entry)
+		owner::node_status(&mut **w)
 	}
 
-	/// Verifies all messages in the slate match their public keys.
-	///
-	/// The optional messages themselves are part of the `participant_data` field within the slate.
-	/// Messages are signed with the same key used to sign for the paricipant's inputs, and can thus be
-	/// verified with the public key found in the `public_blind_excess` field. This function is a
-	/// simple helper to returns whether all signatures in the participant data match their public
-	/// keys.
+	/// Estimates the fee rate for a transaction to confirm quickly, from the fees paid
+	/// in recent blocks and the transactions waiting in the node's mempool. When the
+	/// mempool holds more than the next block can take, the rate outbids the best paying
+	/// transaction that would be left out. The rate is never less than the minimum nodes
+	/// accept, which the wallet pays by default.
 	///
 	/// # Arguments
 	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `slate` - The transaction [`Slate`](../grin_wallet_libwallet/slate/struct.Slate.html).
+	/// * `num_blocks` - Number of recent blocks to look at, 10 if none.
 	///
 	/// # Returns
-	/// * `Ok(())` if successful and the signatures validate
-	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	/// * Ok with a [`FeeRateEstimate`](../grin_wallet_libwallet/api_impl/types/struct.FeeRateEstimate.html)
+	/// if successful, the rates being per unit of transaction weight
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// including when the node can't be contacted.
 	///
 	/// # Example
 	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
-	/// let mut api_owner = Owner::new(wallet.clone());
-	/// let args = InitTxArgs {
-	/// 	src_acct_name: None,
-	/// 	amount: 2_000_000_000,
-	/// 	minimum_confirmations: 10,
-	/// 	max_outputs: 500,
-	/// 	num_change_outputs: 1,
-	/// 	selection_strategy_is_use_all: true,
-	/// 	message: Some("Just verify messages".to_owned()),
-	/// 	..Default::default()
-	/// };
-	/// let result = api_owner.init_send_tx(
-	/// 	None,
-	/// 	args,
-	/// );
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.recommended_fee_rate(None, Some(20));
 	///
-	/// if let Ok(slate) = result {
-	///		// Send slate somehow
-	///		// ...
-	///		// Lock our outputs if we're happy the slate was (or is being) sent
-	///		let res = api_owner.tx_lock_outputs(None, &slate, 0);
-	///		//
-	///		// Retrieve slate back from recipient
-	///		//
-	///		let res = api_owner.verify_slate_messages(None, &slate);
+	/// if let Ok(estimate) = result {
+	///		if estimate.pool_congested {
+	///			// paying estimate.recommended_fee_rate gets ahead of the queue
+	///		}
+	///		//...
 	/// }
 	/// ```
-	pub fn verify_slate_messages(
+
+	pub fn recommended_fee_rate(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		slate: &Slate,
-	) -> Result<(), Error> {
-		{
-			let mut w_lock = self.wallet_inst.lock();
-			let w = w_lock.lc_provider()?.wallet_inst()?;
-			// Test keychain mask, to keep API consistent
-			let _ = w.keychain(keychain_mask)?;
-		}
-		owner::verify_slate_messages(slate)
+		num_blocks: Option<u64>,
+	) -> Result<FeeRateEstimate, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		owner::recommended_fee_rate(&mut **w, num_blocks)
 	}
 
-	/// Scans the entire UTXO set from the node, creating outputs for each scanned
-	/// output that matches the wallet's master seed. This function is intended to be called as part
-	/// of a recovery process (either from BIP32 phrase or backup seed files,) and will error if the
-	/// wallet is non-empty, i.e. contains any outputs at all.
-	///
-	/// This operation scans the entire chain, and is expected to be time intensive. It is imperative
-	/// that no other processes should be trying to use the wallet at the same time this function is
-	/// running.
-	///
-	/// A single [TxLogEntry](../grin_wallet_libwallet/types/struct.TxLogEntry.html) is created for
-	/// all non-coinbase outputs discovered and restored during this process. A separate entry
-	/// is created for each coinbase output.
+	/// Retrieves the latency, error count and payload sizes of the calls the wallet made
+	/// to its node, per node API endpoint, to tell whether a slow wallet is waiting on the
+	/// node or on its own processing. Calls slower than 2 seconds are also logged.
 	///
 	/// # Arguments
 	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
+	/// * `reset` - Whether to start the statistics over once retrieved.
 	///
 	/// # Returns
-	/// * `Ok(())` if successful
+	/// * Ok with the [`NodeClientStats`](../grin_wallet_libwallet/types/struct.NodeClientStats.html)
+	/// since the wallet was opened or the last reset, if successful
 	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
-
+	///
 	/// # Example
 	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
-	/// let mut api_owner = Owner::new(wallet.clone());
-	/// let result = api_owner.restore(None);
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.node_client_stats(None, false);
 	///
-	/// if let Ok(_) = result {
-	///		// Wallet outputs should be consistent with what's on chain
-	///		// ...
+	/// if let Ok(stats) = result {
+	///		for e in stats.endpoints {
+	///			let avg_us = e.total_us / e.calls.max(1);
+	///			//...
+	///		}
 	/// }
 	/// ```
-	pub fn restore(&self, keychain_mask: Option<&SecretKey>) -> Result<(), Error> {
+
+	pub fn node_client_stats(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		reset: bool,
+	) -> Result<NodeClientStats, Error> {
 		let mut w_lock = self.wallet_inst.lock();
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		let res = owner::restore(&mut **w, keychain_mask);
-		res
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		Ok(owner::node_client_stats(&mut **w, reset))
 	}
 
-	/// Scans the entire UTXO set from the node, identify which outputs belong to the given wallet
-	/// update the wallet state to be consistent with what's currently in the UTXO set.
+	/// Answers right away with the server time, so clients can measure their latency to
+	/// the API and check their credentials, secure session or token, without the wallet
+	/// database being read. The keychain mask is only checked if a wallet is open.
 	///
-	/// This function can be used to repair wallet state, particularly by restoring outputs that may
-	/// be missing if the wallet owner has cancelled transactions locally that were then successfully
-	/// posted to the chain.
+	/// # Arguments
 	///
-	/// This operation scans the entire chain, and is expected to be time intensive. It is imperative
-	/// that no other processes should be trying to use the wallet at the same time this function is
-	/// running.
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
 	///
-	/// When an output is found that doesn't exist in the wallet, a corresponding
-	/// [TxLogEntry](../grin_wallet_libwallet/types/struct.TxLogEntry.html) is created.
+	/// # Returns
+	/// * Ok with a [`PingResult`](../grin_wallet_libwallet/api_impl/types/struct.PingResult.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as an invalid keychain mask.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.ping(None);
+	///
+	/// if let Ok(ping) = result {
+	///		if !ping.wallet_open {
+	///			// open the wallet before anything else
+	///		}
+	///		//...
+	/// }
+	/// ```
+
+	pub fn ping(&self, keychain_mask: Option<&SecretKey>) -> Result<PingResult, Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let wallet_open = match w_lock.lc_provider()?.wallet_inst() {
+			Ok(w) => {
+				// The keychain is held in memory, the mask is checked against its checksum
+				let _ = w.keychain(keychain_mask)?;
+				true
+			}
+			Err(_) => false,
+		};
+		Ok(PingResult {
+			server_time: Utc::now(),
+			owner_api_version: OWNER_API_VERSION,
+			wallet_open,
+		})
+	}
+
+	/// Retrieves the time spent in each step of building the sends of the wallet since it was
+	/// opened: refreshing the outputs from the node, selecting the inputs and building
+	/// the transaction elements, which includes generating the change rangeproofs. Meant to
+	/// tell which step dominates the latency of sends, e.g. before setting up a
+	/// `change_key_pool_size` for high-frequency sending.
 	///
 	/// # Arguments
 	///
 	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
 	/// being used.
-	/// * `delete_unconfirmed` - if `false`, the check_repair process will be non-destructive, and
-	/// mostly limited to restoring missing outputs. It will leave unconfirmed transaction logs entries
-	/// and unconfirmed outputs intact. If `true`, the process will unlock all locked outputs,
-	/// restore all missing outputs, and mark any outputs that have been marked 'Spent' but are still
-	/// in the UTXO set as 'Unspent' (as can happen during a fork). It will also attempt to cancel any
-	/// transaction log entries associated with any locked outputs or outputs incorrectly marked 'Spent'.
-	/// Note this completely removes all outstanding transactions, so users should be very aware what
-	/// will happen if this flag is set. Note that if transactions/outputs are removed that later
-	/// confirm on the chain, another call to this function will restore them.
 	///
 	/// # Returns
-	/// * `Ok(())` if successful
+	/// * Ok with the [`SendMetrics`](../grin_wallet_libwallet/api_impl/types/struct.SendMetrics.html)
+	/// if successful
 	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
-
+	///
 	/// # Example
 	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
 	/// ```
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
-	/// let mut api_owner = Owner::new(wallet.clone());
-	/// let result = api_owner.check_repair(
-	/// 	None,
-	/// 	false,
-	/// );
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_send_metrics(None);
 	///
-	/// if let Ok(_) = result {
-	///		// Wallet outputs should be consistent with what's on chain
-	///		// ...
+	/// if let Ok(metrics) = result {
+	///		if metrics.total_us > 0 {
+	///			let rangeproof_share = metrics.build_us as f64 / metrics.total_us as f64;
+	///		}
 	/// }
 	/// ```
 
-	pub fn check_repair(
+	pub fn retrieve_send_metrics(
 		&self,
 		keychain_mask: Option<&SecretKey>,
-		delete_unconfirmed: bool,
-	) -> Result<(), Error> {
+	) -> Result<SendMetrics, Error> {
 		let mut w_lock = self.wallet_inst.lock();
 		let w = w_lock.lc_provider()?.wallet_inst()?;
-		owner::check_repair(&mut **w, keychain_mask, delete_unconfirmed)
+		// Test keychain mask, to keep API consistent
+		let _ = w.keychain(keychain_mask)?;
+		Ok(owner::retrieve_send_metrics(&**w))
 	}
 
-	/// Retrieves the last known height known by the wallet. This is determined as follows:
-	/// * If the wallet can successfully contact its configured node, the reported node
-	/// height is returned, and the `updated_from_node` field in the response is `true`
-	/// * If the wallet cannot contact the node, this function returns the maximum height
-	/// of all outputs contained within the wallet, and the `updated_from_node` fields
-	/// in the response is set to false.
-	///
-	/// Clients should generally ensure the `updated_from_node` field is returned as
-	/// `true` before assuming the height for any operation.
+	/// Retrieves usage statistics of the wallet, giving operators a single call for capacity
+	/// planning and health checks: the number of outputs of each status, of transaction log
+	/// entries of each type and of accounts, the size of the wallet data directory and the
+	/// space left on its disk, the oldest transaction still waiting for confirmation and when
+	/// the wallet was last backed up with the `backup` command.
 	///
 	/// # Arguments
 	///
@@ -1201,9 +4934,8 @@ where
 	/// being used.
 	///
 	/// # Returns
-	/// * Ok with a  [`NodeHeightResult`](../grin_wallet_libwallet/types/struct.NodeHeightResult.html)
-	/// if successful. If the height result was obtained from the configured node,
-	/// `updated_from_node` will be set to `true`
+	/// * Ok with the [`WalletStats`](../grin_wallet_libwallet/api_impl/types/struct.WalletStats.html)
+	/// if successful
 	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
 	///
 	/// # Example
@@ -1212,26 +4944,69 @@ where
 	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
 	///
 	/// let api_owner = Owner::new(wallet.clone());
-	/// let result = api_owner.node_height(None);
-	///
-	/// if let Ok(node_height_result) = result {
-	///		if node_height_result.updated_from_node {
-	///			//we can assume node_height_result.height is relatively safe to use
+	/// let result = api_owner.wallet_stats(None);
 	///
+	/// if let Ok(stats) = result {
+	///		if let Some(tx) = stats.oldest_unconfirmed_tx {
+	///			// check on the transaction
 	///		}
 	///		//...
 	/// }
 	/// ```
 
-	pub fn node_height(
-		&self,
-		keychain_mask: Option<&SecretKey>,
-	) -> Result<NodeHeightResult, Error> {
+	pub fn wallet_stats(&self, keychain_mask: Option<&SecretKey>) -> Result<WalletStats, Error> {
 		let mut w_lock = self.wallet_inst.lock();
-		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let lc = w_lock.lc_provider()?;
+		let top_level_dir = lc.get_top_level_directory()?;
+		let w = lc.wallet_inst()?;
 		// Test keychain mask, to keep API consistent
 		let _ = w.keychain(keychain_mask)?;
-		owner::node_height(&mut **w, keychain_mask)
+		let mut stats = owner::wallet_stats(&mut **w)?;
+		// The data directory of doctests changes on every run
+		if !self.doctest_mode {
+			let data_dir = Path::new(&top_level_dir).join(GRIN_WALLET_DIR);
+			stats.data_dir_size = dir_size(&data_dir).ok();
+			stats.disk_free_space = available_space(&data_dir);
+			stats.last_backup = last_backup_time(&data_dir);
+		}
+		Ok(stats)
+	}
+
+	/// Changes, while the wallet runs, the level at which a module and its submodules are
+	/// logged, e.g. to debug the node client without restarting and losing the wallet's
+	/// in-memory state. Records of the module are written to the configured log outputs
+	/// whatever their configured levels.
+	///
+	/// # Arguments
+	///
+	/// * `target` - The module path, e.g. `grin_wallet_impls::node_clients`. Modules of other
+	/// crates, such as `hyper`, can be logged too.
+	/// * `level` - The level to log the module at, or `None` to log it at the configured
+	/// levels again.
+	///
+	/// # Returns
+	/// * `Ok(())` if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	/// use grin_wallet_util::grin_util::LogLevel;
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let target = "grin_wallet_impls::node_clients";
+	/// let result = api_owner.set_log_level(target, Some(LogLevel::Debug));
+	///
+	/// if let Ok(_) = result {
+	///		// diagnose, then go back to the configured levels
+	///		let _ = api_owner.set_log_level(target, None);
+	/// }
+	/// ```
+
+	pub fn set_log_level(&self, target: &str, level: Option<LogLevel>) -> Result<(), Error> {
+		set_log_level(target, level)
+			.map_err(|e| ErrorKind::GenericError(format!("Setting log level: {}", e)).into())
 	}
 
 	// LIFECYCLE FUNCTIONS
@@ -1322,6 +5097,31 @@ where
 		lc.set_top_level_directory(dir)
 	}
 
+	/// Returns the locale the messages of the wallet, such as the errors returned by the
+	/// JSON-RPC APIs, are given in: the `locale` of its configuration, if the wallet has a
+	/// catalog of messages for it other than the English one.
+	///
+	/// # Returns
+	/// * `Some(locale)` - The language of the catalog, e.g. `de`
+	/// * `None` - Messages are given in English
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// if let Some(l) = api_owner.locale() {
+	/// 	println!("Errors are given in {}", l);
+	/// }
+	/// ```
+
+	pub fn locale(&self) -> Option<&'static str> {
+		let mut w_lock = self.wallet_inst.lock();
+		let lc = w_lock.lc_provider().ok()?;
+		catalog_locale(lc.wallet_config().locale.as_ref().map(|l| l.as_str()))
+	}
+
 	/// Create a `mwc-wallet.toml` configuration file in the top-level directory for the
 	/// specified chain type.
 	/// A custom [`WalletConfig`](../grin_wallet_config/types/struct.WalletConfig.html)
@@ -1337,6 +5137,8 @@ where
 	///     * `UserTesting`
 	///     * `Floonet`
 	///     * `Mainnet`
+	/// * `wallet_config`: Optional wallet configuration. If its `custom_chain` is set, e.g. for
+	/// a private testnet, the chain type, listener ports and node address are taken from it.
 	///
 	/// # Returns
 	/// * Ok if successful
@@ -1379,24 +5181,125 @@ where
 		lc.create_config(chain_type, "mwc-wallet.toml", wallet_config, logging_config)
 	}
 
-	/// Creates a new wallet seed and empty wallet database in the `wallet_data` directory of
-	/// the top level directory.
-	///
-	/// Paths in the configuration file will be updated to reflect the top level directory, so
-	/// path-related values in the optional configuration structs will be ignored.
-	///
-	/// The wallet files must not already exist, and ~The `mwc-wallet.toml` file must exist
+	/// Checks a mnemonic is a valid recovery phrase, the words and their checksum, before
+	/// creating a wallet from it with [`create_wallet`](struct.Owner.html#method.create_wallet).
+	/// Nothing is written.
+	///
+	/// # Arguments
+	///
+	/// * `mnemonic`: The recovery phrase to check.
+	///
+	/// # Returns
+	/// * Ok if the mnemonic is valid
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if it isn't.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let mnemonic = ZeroingString::from("not a valid recovery phrase");
+	/// let result = api_owner.validate_mnemonic(mnemonic);
+	///
+	/// assert!(result.is_err());
+	/// ```
+	pub fn validate_mnemonic(&self, mnemonic: ZeroingString) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let lc = w_lock.lc_provider()?;
+		lc.validate_mnemonic(mnemonic)
+	}
+
+	/// Creates a new wallet seed and empty wallet database in the `wallet_data` directory of
+	/// the top level directory.
+	///
+	/// Paths in the configuration file will be updated to reflect the top level directory, so
+	/// path-related values in the optional configuration structs will be ignored.
+	///
+	/// The wallet files must not already exist, and ~The `mwc-wallet.toml` file must exist
+	/// in the top level directory (can be created via a call to
+	/// [`create_config`](struct.Owner.html#method.create_config))
+	///
+	/// # Arguments
+	///
+	/// * `name`: Reserved for future use, use `None` for the time being.
+	/// * `mnemonic`: If present, restore the wallet seed from the given mnemonic instead of creating
+	/// a new random seed.
+	/// * `mnemonic_length`: Desired length of mnemonic in bytes (16 or 32, either 12 or 24 words).
+	/// Use 0 if mnemonic isn't being used.
+	/// * `password`: The password used to encrypt/decrypt the `wallet.seed` file
+	///
+	/// # Returns
+	/// * Ok if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// use grin_core::global::ChainTypes;
+	///
+	///	// note that the WalletInst struct does not necessarily need to contain an
+	///	// instantiated wallet
+	///
+	/// let dir = "path/to/wallet/dir";
+	///
+	/// # let dir = tempdir().map_err(|e| format!("{:#?}", e)).unwrap();
+	/// # let dir = dir
+	/// # 	.path()
+	/// # 	.to_str()
+	/// # 	.ok_or("Failed to convert tmpdir path to string.".to_owned())
+	/// # 	.unwrap();
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let _ = api_owner.set_top_level_directory(dir);
+	///
+	/// // Create configuration
+	/// let result = api_owner.create_config(&ChainTypes::Mainnet, None, None);
+	///
+	///	// create new wallet wirh random seed
+	///	let pw = ZeroingString::from("my_password");
+	/// let result = api_owner.create_wallet(None, None, 0, pw);
+	///
+	/// if let Ok(r) = result {
+	///		//...
+	/// }
+	/// ```
+
+	pub fn create_wallet(
+		&self,
+		name: Option<&str>,
+		mnemonic: Option<ZeroingString>,
+		mnemonic_length: u32,
+		password: ZeroingString,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let lc = w_lock.lc_provider()?;
+		lc.create_wallet(
+			name,
+			mnemonic,
+			mnemonic_length as usize,
+			password,
+			self.doctest_mode,
+		)
+	}
+
+	/// Creates a watch-only wallet and its empty database in the `wallet_data` directory of
+	/// the top level directory, from the root public key of another wallet as returned by
+	/// [`watch_only_key`](struct.Owner.html#method.watch_only_key). It holds no seed, so
+	/// it can find and follow the outputs of that wallet by a scan, and report its balance
+	/// and history, but can't spend or receive. Outputs created before the legacy range
+	/// proofs were retired aren't found.
+	///
+	/// The wallet files must not already exist, and the `mwc-wallet.toml` file must exist
 	/// in the top level directory (can be created via a call to
 	/// [`create_config`](struct.Owner.html#method.create_config))
 	///
 	/// # Arguments
 	///
 	/// * `name`: Reserved for future use, use `None` for the time being.
-	/// * `mnemonic`: If present, restore the wallet seed from the given mnemonic instead of creating
-	/// a new random seed.
-	/// * `mnemonic_length`: Desired length of mnemonic in bytes (16 or 32, either 12 or 24 words).
-	/// Use 0 if mnemonic isn't being used.
-	/// * `password`: The password used to encrypt/decrypt the `wallet.seed` file
+	/// * `root_public_key`: Hex of the root public key of the wallet to follow.
+	/// * `password`: The password used to encrypt/decrypt the `wallet.watch` file
 	///
 	/// # Returns
 	/// * Ok if successful
@@ -1409,9 +5312,6 @@ where
 	///
 	/// use grin_core::global::ChainTypes;
 	///
-	///	// note that the WalletInst struct does not necessarily need to contain an
-	///	// instantiated wallet
-	///
 	/// let dir = "path/to/wallet/dir";
 	///
 	/// # let dir = tempdir().map_err(|e| format!("{:#?}", e)).unwrap();
@@ -1426,31 +5326,25 @@ where
 	/// // Create configuration
 	/// let result = api_owner.create_config(&ChainTypes::Mainnet, None, None);
 	///
-	///	// create new wallet wirh random seed
+	///	// create a wallet following the outputs of another
+	/// let key = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
 	///	let pw = ZeroingString::from("my_password");
-	/// let result = api_owner.create_wallet(None, None, 0, pw);
+	/// let result = api_owner.create_watch_only_wallet(None, key, pw);
 	///
 	/// if let Ok(r) = result {
 	///		//...
 	/// }
 	/// ```
 
-	pub fn create_wallet(
+	pub fn create_watch_only_wallet(
 		&self,
 		name: Option<&str>,
-		mnemonic: Option<ZeroingString>,
-		mnemonic_length: u32,
+		root_public_key: &str,
 		password: ZeroingString,
 	) -> Result<(), Error> {
 		let mut w_lock = self.wallet_inst.lock();
 		let lc = w_lock.lc_provider()?;
-		lc.create_wallet(
-			name,
-			mnemonic,
-			mnemonic_length as usize,
-			password,
-			self.doctest_mode,
-		)
+		lc.create_watch_only_wallet(name, root_public_key, password)
 	}
 
 	/// `Opens` a wallet, populating the internal keychain with the encrypted seed, and optionally
@@ -1530,13 +5424,23 @@ where
 
 	/// `Close` a wallet, removing the master seed from memory.
 	///
+	/// Operations of this process still using the wallet, such as sends waiting for their
+	/// recipient, would fail once the seed is gone. The wallet is only closed once none is in
+	/// progress: new operations are refused while closing, and those in progress are given up
+	/// to `wait_secs` to end.
+	///
 	/// # Arguments
 	///
 	/// * `name`: Reserved for future use, use `None` for the time being.
+	/// * `wait_secs`: How long to wait for the operations in progress to end, if any. The
+	/// wallet isn't closed if any is in progress when `None`.
 	///
 	/// # Returns
 	/// * Ok if successful
-	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// of kind `OperationsPending` with the
+	/// [`PendingOperation`](../grin_wallet_libwallet/api_impl/types/struct.PendingOperation.html)s
+	/// still in progress if those kept the wallet from closing.
 	///
 	/// # Example
 	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
@@ -1548,17 +5452,529 @@ where
 	///	// Set up as above
 	/// # let api_owner = Owner::new(wallet.clone());
 	///
-	/// let res = api_owner.close_wallet(None);
+	/// let res = api_owner.close_wallet(None, Some(10));
 	///
 	/// if let Ok(_) = res {
 	///		// ...
 	/// }
 	/// ```
 
-	pub fn close_wallet(&self, name: Option<&str>) -> Result<(), Error> {
+	pub fn close_wallet(&self, name: Option<&str>, wait_secs: Option<u64>) -> Result<(), Error> {
+		// The wallet isn't locked while waiting, operations in progress need it to end
+		let operations = self.pending_operations()?;
+		owner::close_after_operations(&operations, wait_secs, || {
+			let mut w_lock = self.wallet_inst.lock();
+			let lc = w_lock.lc_provider()?;
+			lc.close_wallet(name)
+		})
+	}
+
+	/// Shuts down the listener serving this API. New requests are refused, those in flight
+	/// are given time to complete, and the wallet is closed before the listener stops.
+	///
+	/// # Arguments
+	///
+	/// * None
+	///
+	/// # Returns
+	/// * Ok if the shutdown was requested
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as the API not being served by a listener.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// // Not served by a listener, there's nothing to shut down
+	/// assert!(api_owner.shutdown().is_err());
+	/// ```
+
+	pub fn shutdown(&self) -> Result<(), Error> {
+		match self.shutdown_hook.as_ref() {
+			Some(hook) => {
+				hook();
+				Ok(())
+			}
+			None => Err(ErrorKind::GenericError("No listener to shut down".to_owned()).into()),
+		}
+	}
+
+	/// Status of an operation of this process using the wallet across several calls, such as
+	/// a send dispatched in the background by
+	/// [`init_send_tx_async`](struct.Owner.html#method.init_send_tx_async). Operations are
+	/// reported while running, and the last hundred ended along with how they ended.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `operation_id` - The id of the operation, e.g. the `operation_id` of a
+	/// [`SendHandle`](../grin_wallet_libwallet/api_impl/types/struct.SendHandle.html).
+	///
+	/// # Returns
+	/// * Ok with the [`OperationStatus`](../grin_wallet_libwallet/api_impl/types/struct.OperationStatus.html)
+	/// if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as no operation being known by that id.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let result = api_owner.retrieve_operation_status(None, 1);
+	///
+	/// if let Ok(status) = result {
+	///		println!("{:?}: {:?}", status.state, status.error);
+	/// }
+	/// ```
+
+	pub fn retrieve_operation_status(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		operation_id: u64,
+	) -> Result<OperationStatus, Error> {
+		{
+			let mut w_lock = self.wallet_inst.lock();
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			// Test keychain mask, to keep API consistent
+			let _ = w.keychain(keychain_mask)?;
+		}
+		owner::retrieve_operation_status(&self.pending_operations()?, operation_id)
+			.ok_or_else(|| ErrorKind::GenericError(format!("No operation {}", operation_id)).into())
+	}
+
+	/// Change the password the wallet seed is encrypted with. The seed is encrypted anew with
+	/// the key derivation parameters the wallet is configured with (see `seed_kdf_algorithm`
+	/// and `seed_kdf_iterations` in the configuration file), which are recorded in the seed
+	/// file along with it, so that seeds encrypted before the parameters were strengthened are
+	/// upgraded. While the wallet is open, a code of its second factor is required once one is
+	/// enabled, see [`set_totp_code`](struct.Owner.html#method.set_totp_code).
+	///
+	/// # Arguments
+	///
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `name`: Reserved for future use, use `None` for the time being.
+	/// * `old`: The current password of the wallet.
+	/// * `new`: The new password of the wallet.
+	///
+	/// # Returns
+	/// * Ok if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered,
+	/// such as the current password being wrong.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let old = ZeroingString::from("my_password");
+	/// let new = ZeroingString::from("my_new_password");
+	/// let res = api_owner.change_password(None, None, old, new);
+	///
+	/// if let Ok(_) = res {
+	///		// The wallet is opened with the new password from now on
+	/// }
+	/// ```
+
+	pub fn change_password(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		name: Option<&str>,
+		old: ZeroingString,
+		new: ZeroingString,
+	) -> Result<(), Error> {
+		let mut w_lock = self.wallet_inst.lock();
+		let lc = w_lock.lc_provider()?;
+		// The second factor of a closed wallet can't be read, its password stands for it
+		if let Ok(w) = lc.wallet_inst() {
+			self.check_totp(&mut **w, keychain_mask)?;
+		}
+		lc.change_password(name, old, new)
+	}
+
+	/// `Lock` the open wallet, removing the master seed from memory, so that it has to be
+	/// opened again with [`open_wallet`](struct.Owner.html#method.open_wallet) before any
+	/// further call. Unlike [`close_wallet`](struct.Owner.html#method.close_wallet), only
+	/// a caller holding the keychain mask of the wallet can lock it. The owner API listener
+	/// also locks the wallet by itself after the `owner_api_lock_after_mins` set in the
+	/// configuration file without requests.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	///
+	/// # Returns
+	/// * Ok if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let res = api_owner.lock_wallet(None);
+	///
+	/// if let Ok(_) = res {
+	///		// The wallet has to be opened again
+	/// }
+	/// ```
+
+	pub fn lock_wallet(&self, keychain_mask: Option<&SecretKey>) -> Result<(), Error> {
 		let mut w_lock = self.wallet_inst.lock();
 		let lc = w_lock.lc_provider()?;
-		lc.close_wallet(name)
+		// Only a caller holding the mask can lock the wallet
+		let _ = lc.wallet_inst()?.keychain(keychain_mask)?;
+		lc.close_wallet(None)
+	}
+}
+
+impl<L, C, K> Owner<'static, L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	/// Initiates a send as [`init_send_tx`](struct.Owner.html#method.init_send_tx) does with
+	/// `send_args`, returning once the transaction is built and its outputs locked, without
+	/// waiting for the recipient. The send is dispatched in the background, as by
+	/// [`dispatch_tx`](struct.Owner.html#method.dispatch_tx); its progress is queried with
+	/// [`retrieve_operation_status`](struct.Owner.html#method.retrieve_operation_status). A
+	/// send that failed is kept in the wallet, to be dispatched again.
+	///
+	/// # Arguments
+	/// * `keychain_mask` - Wallet secret mask to XOR against the stored wallet seed before using, if
+	/// being used.
+	/// * `args` - [`InitTxArgs`](../grin_wallet_libwallet/types/struct.InitTxArgs.html),
+	/// transaction initialization arguments, whose `send_args` must be set.
+	///
+	/// # Returns
+	/// * Ok with the [`SendHandle`](../grin_wallet_libwallet/api_impl/types/struct.SendHandle.html)
+	/// of the send if successful
+	/// * or [`libwallet::Error`](../grin_wallet_libwallet/struct.Error.html) if an error is encountered
+	/// building the transaction. Errors of the dispatch are reported in the status of its operation.
+	///
+	/// # Example
+	/// Set up as in [`new`](struct.Owner.html#method.new) method above.
+	/// ```
+	/// # grin_wallet_api::doctest_helper_setup_doc_env!(wallet, wallet_config);
+	///
+	/// let api_owner = Owner::new(wallet.clone());
+	/// let args = InitTxArgs {
+	/// 	amount: 2_000_000_000,
+	/// 	send_args: Some(InitTxSendArgs {
+	/// 		method: "http".to_owned(),
+	/// 		dest: "http://192.168.0.10:3415".to_owned(),
+	/// 		finalize: true,
+	/// 		post_tx: true,
+	/// 		fluff: false,
+	/// 		cancel_on_failure: None,
+	/// 		timeout_secs: Some(60),
+	/// 	}),
+	/// 	..Default::default()
+	/// };
+	/// let result = api_owner.init_send_tx_async(None, args);
+	///
+	/// if let Ok(handle) = result {
+	/// 	let status = api_owner.retrieve_operation_status(None, handle.operation_id);
+	/// }
+	/// ```
+
+	pub fn init_send_tx_async(
+		&self,
+		keychain_mask: Option<&SecretKey>,
+		args: InitTxArgs,
+	) -> Result<SendHandle, Error> {
+		match args.send_args.as_ref() {
+			Some(sa) => check_send_method(&sa.method)?,
+			None => {
+				return Err(ErrorKind::GenericError(
+					"Sends in the background need send arguments".to_owned(),
+				))?;
+			}
+		}
+		if args.estimate_only == Some(true) {
+			return Err(ErrorKind::GenericError(
+				"Estimates aren't sent in the background".to_owned(),
+			))?;
+		}
+		let slate_id = self.prepare_send(keychain_mask, args)?.slate.id;
+		let operations = self.pending_operations()?;
+		let mut operation =
+			owner::begin_operation(&operations, "init_send_tx_async", Some(slate_id))?;
+		let handle = SendHandle {
+			operation_id: operation.id(),
+			tx_slate_id: slate_id,
+		};
+		let api = Owner {
+			wallet_inst: self.wallet_inst.clone(),
+			doctest_mode: self.doctest_mode,
+			shared_key: self.shared_key.clone(),
+			shutdown_hook: self.shutdown_hook.clone(),
+			totp_code: self.totp_code.clone(),
+			totp_verified: self.totp_verified.clone(),
+		};
+		let keychain_mask = keychain_mask.cloned();
+		thread::Builder::new()
+			.name("send".to_owned())
+			.spawn(move || {
+				if let Err(e) = api.dispatch_tx(keychain_mask.as_ref(), slate_id) {
+					error!("Send of {} failed: {}", slate_id, e);
+					operation.set_error(e.to_string());
+				}
+			})
+			.map_err(|e| ErrorKind::GenericError(format!("Unable to start the send: {}", e)))?;
+		Ok(handle)
+	}
+}
+
+/// Check the method of synchronous send arguments is one the wallet can send with
+fn check_send_method(method: &str) -> Result<(), Error> {
+	match method {
+		"http" | "tor" | "keybase" => Ok(()),
+		_ => {
+			error!("unsupported payment method: {}", method);
+			Err(ErrorKind::ClientCallback("unsupported payment method".to_owned()).into())
+		}
+	}
+}
+
+/// Check the code of the second factor calls are made with, if one is enabled. As
+/// each code is accepted once, it's only checked by the first call needing it.
+fn check_totp<'a, C, K>(
+	w: &mut (dyn WalletBackend<'a, C, K> + 'a),
+	keychain_mask: Option<&SecretKey>,
+	code: Option<&str>,
+	verified: &AtomicBool,
+) -> Result<(), Error>
+where
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if verified.load(Ordering::SeqCst) || !owner::totp_enabled(w, keychain_mask)? {
+		return Ok(());
+	}
+	owner::verify_totp(w, keychain_mask, code)?;
+	verified.store(true, Ordering::SeqCst);
+	Ok(())
+}
+
+/// Deliver a slate as the send arguments say. Each request to the recipient fails
+/// unless answered within their `timeout_secs`.
+fn deliver_slate(
+	sa: &InitTxSendArgs,
+	chain_type: global::ChainTypes,
+	tor_socks_proxy: Option<String>,
+	slate: &Slate,
+) -> Result<SlateDelivery, Error> {
+	let timeout = sa.timeout_secs.map(Duration::from_secs);
+	let sender = create_sender(&sa.method, &sa.dest, chain_type, tor_socks_proxy, timeout)
+		.map_err(|e| ErrorKind::GenericError(format!("{}", e)))?;
+	sender.deliver_tx(slate, &sa.method)
+}
+
+/// The wallet, locked for the duration of a single logical operation. Obtained through
+/// [`Owner::with_context`](struct.Owner.html#method.with_context); its methods behave as
+/// their `Owner` counterparts, without locking the wallet again.
+pub struct OwnerContext<'w, 'a, C, K>
+where
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	w: &'w mut (dyn WalletBackend<'a, C, K> + 'a),
+	keychain_mask: Option<SecretKey>,
+	doctest_mode: bool,
+	totp_code: Option<String>,
+	totp_verified: Arc<AtomicBool>,
+}
+
+impl<'w, 'a, C, K> OwnerContext<'w, 'a, C, K>
+where
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	/// Chain type of the node the wallet is connected to
+	pub fn chain_type(&self) -> global::ChainTypes {
+		self.w.w2n_client().chain_type()
+	}
+
+	/// Address of the Tor SOCKS proxy the wallet reaches .onion recipients through
+	pub fn tor_socks_proxy(&self) -> Option<String> {
+		self.w.config().tor_socks_proxy_addr.clone()
+	}
+
+	/// See [`Owner::set_totp_code`](struct.Owner.html#method.set_totp_code)
+	fn check_totp(&mut self) -> Result<(), Error> {
+		let mask = self.keychain_mask.clone();
+		let code = self.totp_code.as_ref().map(|c| c.as_str());
+		check_totp(&mut *self.w, mask.as_ref(), code, &self.totp_verified)
+	}
+
+	/// See [`Owner::retrieve_outputs`](struct.Owner.html#method.retrieve_outputs)
+	pub fn retrieve_outputs(
+		&mut self,
+		include_spent: bool,
+		refresh_from_node: bool,
+		tx_id: Option<u32>,
+	) -> Result<(bool, Vec<OutputCommitMapping>), Error> {
+		let mask = self.keychain_mask.as_ref();
+		owner::retrieve_outputs(&mut *self.w, mask, include_spent, refresh_from_node, tx_id)
+	}
+
+	/// See [`Owner::retrieve_txs`](struct.Owner.html#method.retrieve_txs)
+	pub fn retrieve_txs(
+		&mut self,
+		refresh_from_node: bool,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<(bool, Vec<TxLogEntry>), Error> {
+		let mask = self.keychain_mask.as_ref();
+		owner::retrieve_txs(&mut *self.w, mask, refresh_from_node, tx_id, tx_slate_id)
+	}
+
+	/// See [`Owner::retrieve_summary_info`](struct.Owner.html#method.retrieve_summary_info)
+	pub fn retrieve_summary_info(
+		&mut self,
+		refresh_from_node: bool,
+		minimum_confirmations: u64,
+	) -> Result<(bool, WalletInfo), Error> {
+		let mask = self.keychain_mask.as_ref();
+		owner::retrieve_summary_info(&mut *self.w, mask, refresh_from_node, minimum_confirmations)
+	}
+
+	/// See [`Owner::init_send_tx`](struct.Owner.html#method.init_send_tx). The `send_args`
+	/// of `args` are ignored, sending the slate is up to the caller.
+	pub fn init_send_tx(&mut self, args: InitTxArgs) -> Result<Slate, Error> {
+		// Estimates don't spend
+		if args.estimate_only != Some(true) {
+			self.check_totp()?;
+		}
+		let mask = self.keychain_mask.as_ref();
+		owner::init_send_tx(&mut *self.w, mask, args, self.doctest_mode)
+	}
+
+	/// Builds the transaction of a send, and keeps the send in the wallet to be dispatched
+	/// if there are send arguments
+	fn prepare_send(&mut self, args: InitTxArgs) -> Result<InitTxResult, Error> {
+		let send_args = args.send_args.clone();
+		let estimate_only = args.estimate_only == Some(true);
+		let init_args = args.clone();
+		let slate = self.init_send_tx(args)?;
+		// No transaction context is kept for an estimate
+		let change_outputs = match estimate_only {
+			true => vec![],
+			false => {
+				let mask = self.keychain_mask.clone();
+				owner::retrieve_change_outputs(&mut *self.w, mask.as_ref(), &slate.id)?
+			}
+		};
+		if let Some(sa) = send_args {
+			// Outputs are locked once, a dispatch failing after the delivery is retried
+			// with the same slate
+			self.tx_lock_outputs(&slate, 0)?;
+			let entry = PendingDispatch {
+				slate_id: slate.id,
+				slate: slate.clone(),
+				send_args: sa,
+				response: None,
+				finalized: false,
+				attempts: 0,
+				last_error: None,
+				created: Utc::now(),
+				args: Some(init_args),
+				cancelled: false,
+			};
+			owner::save_pending_dispatch(&mut *self.w, self.keychain_mask.as_ref(), &entry)?;
+		}
+		Ok(InitTxResult {
+			slate,
+			change_outputs,
+		})
+	}
+
+	/// See [`Owner::tx_lock_outputs`](struct.Owner.html#method.tx_lock_outputs)
+	pub fn tx_lock_outputs(&mut self, slate: &Slate, participant_id: usize) -> Result<(), Error> {
+		let mask = self.keychain_mask.as_ref();
+		owner::tx_lock_outputs(&mut *self.w, mask, slate, participant_id)
+	}
+
+	/// Records the acknowledgement of a slate by the wallet it was sent to on the
+	/// transaction log entries of the slate
+	pub fn save_delivery_receipt(
+		&mut self,
+		tx_slate_id: &Uuid,
+		receipt: &DeliveryReceipt,
+	) -> Result<(), Error> {
+		let mask = self.keychain_mask.as_ref();
+		owner::save_delivery_receipt(&mut *self.w, mask, tx_slate_id, receipt)
+	}
+
+	/// See [`Owner::finalize_tx`](struct.Owner.html#method.finalize_tx)
+	pub fn finalize_tx(&mut self, slate: &Slate) -> Result<Slate, Error> {
+		self.check_totp()?;
+		let mask = self.keychain_mask.as_ref();
+		owner::finalize_tx(&mut *self.w, mask, slate)
+	}
+
+	/// See [`Owner::post_tx`](struct.Owner.html#method.post_tx)
+	pub fn post_tx(&mut self, tx: &Transaction, fluff: bool) -> Result<(), Error> {
+		// Test keychain mask, to keep API consistent
+		let _ = self.w.keychain(self.keychain_mask.as_ref())?;
+		self.check_totp()?;
+		foreign::check_not_frozen(&mut *self.w)?;
+		owner::check_tx_approved(&mut *self.w, tx)?;
+		owner::check_node_not_stale(&mut *self.w)?;
+		if owner::defer_locked_tx(&mut *self.w, self.keychain_mask.as_ref(), tx)? {
+			return Ok(());
+		}
+		owner::post_tx(self.w.w2n_client(), tx, fluff)
+	}
+
+	/// After the node refused a transaction, finds whether it went through all the
+	/// same, or else marks spent the wallet outputs of its inputs the node shows
+	/// spent
+	pub fn refresh_spent_inputs(&mut self, tx: &Transaction) -> Result<RefusedTxState, Error> {
+		let mask = self.keychain_mask.as_ref();
+		owner::refresh_spent_inputs(&mut *self.w, mask, tx)
+	}
+
+	/// See [`Owner::cancel_tx`](struct.Owner.html#method.cancel_tx)
+	pub fn cancel_tx(
+		&mut self,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<(), Error> {
+		self.check_totp()?;
+		let mask = self.keychain_mask.as_ref();
+		owner::cancel_tx(&mut *self.w, mask, tx_id, tx_slate_id)
+	}
+
+	/// Arguments for a refund of the given transaction, see
+	/// [`Owner::refund_tx`](struct.Owner.html#method.refund_tx)
+	pub fn refund_tx_args(
+		&mut self,
+		tx_slate_id: Uuid,
+		amount: Option<u64>,
+	) -> Result<InitTxArgs, Error> {
+		// Test keychain mask, to keep API consistent
+		let _ = self.w.keychain(self.keychain_mask.as_ref())?;
+		owner::refund_tx_args(&mut *self.w, tx_slate_id, amount)
+	}
+
+	/// Records the refund of a transaction, see
+	/// [`Owner::refund_tx`](struct.Owner.html#method.refund_tx)
+	pub fn set_tx_refunded(
+		&mut self,
+		tx_slate_id: Uuid,
+		refund_slate_id: Uuid,
+	) -> Result<(), Error> {
+		let mask = self.keychain_mask.as_ref();
+		owner::set_tx_refunded(&mut *self.w, mask, tx_slate_id, refund_slate_id)
 	}
 }
 
@@ -1583,7 +5999,10 @@ macro_rules! doctest_helper_setup_doc_env {
 		use api::{Foreign, Owner};
 		use config::WalletConfig;
 		use impls::{DefaultLCProvider, DefaultWalletImpl, HTTPNodeClient};
-		use libwallet::{BlockFees, InitTxArgs, IssueInvoiceTxArgs, Slate, WalletInst};
+		use libwallet::{
+			BlockFees, InitTxArgs, IssueInvoiceTxArgs, ReconcileArgs, ReserveOutputsArgs, Slate,
+			SweepArgs, TxFilter, TxLogEntryType, WalletInst,
+		};
 
 		let dir = tempdir().map_err(|e| format!("{:#?}", e)).unwrap();
 		let dir = dir
@@ -1595,7 +6014,12 @@ macro_rules! doctest_helper_setup_doc_env {
 		wallet_config.data_file_dir = dir.to_owned();
 		let pw = ZeroingString::from("");
 
-		let node_client = HTTPNodeClient::new(&wallet_config.check_node_api_http_addr, None);
+		let node_client = HTTPNodeClient::new(
+			&wallet_config.check_node_api_http_addr,
+			None,
+			wallet_config.chain_type(),
+		)
+		.unwrap();
 		let mut wallet = Box::new(
 			DefaultWalletImpl::<'static, HTTPNodeClient>::new(node_client.clone()).unwrap(),
 			)
@@ -0,0 +1,260 @@
+// Copyright 2018 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Owner API for async services. Its calls are futures, run on the threads of
+//! the tokio runtime polling them rather than on threads spawned for each call.
+
+use futures::future;
+use futures::{Async, Future};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::core::core::Transaction;
+use crate::keychain::Keychain;
+use crate::libwallet::{
+	AcctPathMapping, Error, InitTxArgs, IssueInvoiceTxArgs, NodeClient, NodeHeightResult,
+	OutputCommitMapping, Slate, TxLogEntry, WalletInfo, WalletInst, WalletLCProvider, WalletStats,
+};
+use crate::util::secp::key::SecretKey;
+use crate::util::Mutex;
+use crate::Owner;
+
+/// Future of an Owner API call
+pub type OwnerFuture<T> = Box<dyn Future<Item = T, Error = Error> + Send>;
+
+/// Owner API returning futures, for services built on tokio. The wallet is still locked
+/// for the duration of each call, which is run through
+/// [`tokio_threadpool::blocking`](https://docs.rs/tokio-threadpool/0.1/tokio_threadpool/fn.blocking.html)
+/// so the other tasks of the runtime keep being polled while it waits for the lock, the
+/// database or the node. Polled outside of a tokio threadpool, e.g. with `wait`, a call
+/// blocks the caller.
+///
+/// The usual calls have a method of their own, taking the keychain mask by value. Any
+/// other [`Owner`](struct.Owner.html) method is made async with
+/// [`call`](struct.OwnerAsync.html#method.call).
+pub struct OwnerAsync<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K>,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	owner: Arc<Owner<'static, L, C, K>>,
+}
+
+impl<L, C, K> Clone for OwnerAsync<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K>,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	fn clone(&self) -> Self {
+		OwnerAsync {
+			owner: self.owner.clone(),
+		}
+	}
+}
+
+impl<L, C, K> OwnerAsync<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K>,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	/// Create a new async API instance with the given wallet instance, see
+	/// [`Owner::new`](struct.Owner.html#method.new)
+	pub fn new(wallet_inst: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>) -> Self {
+		OwnerAsync::from_owner(Owner::new(wallet_inst))
+	}
+
+	/// Make the calls of a blocking API instance async
+	pub fn from_owner(owner: Owner<'static, L, C, K>) -> Self {
+		OwnerAsync {
+			owner: Arc::new(owner),
+		}
+	}
+
+	/// The blocking API instance the calls are made on
+	pub fn owner(&self) -> &Owner<'static, L, C, K> {
+		&self.owner
+	}
+
+	/// Make a call of the blocking API, as a future
+	///
+	/// # Example
+	/// ```ignore
+	/// let owner = OwnerAsync::new(wallet.clone());
+	/// let fut = owner.call(move |api| api.get_stored_tx(mask.as_ref(), &tx_log_entry));
+	/// ```
+	pub fn call<F, T>(&self, f: F) -> OwnerFuture<T>
+	where
+		F: FnOnce(&Owner<'static, L, C, K>) -> Result<T, Error> + Send + 'static,
+		T: Send + 'static,
+	{
+		let owner = self.owner.clone();
+		let mut f = Some(f);
+		Box::new(future::poll_fn(move || {
+			// The threadpool may be out of blocking capacity, the call is then made
+			// when polled again
+			let res = tokio_threadpool::blocking(|| {
+				let f = f.take().expect("Owner API call polled after completion");
+				f(&owner)
+			});
+			match res {
+				Ok(Async::Ready(r)) => r.map(Async::Ready),
+				Ok(Async::NotReady) => Ok(Async::NotReady),
+				Err(_) => {
+					// Not on a tokio threadpool, blocking the caller is all there is to do
+					let f = f.take().expect("Owner API call polled after completion");
+					f(&owner).map(Async::Ready)
+				}
+			}
+		}))
+	}
+
+	/// Async version of [`Owner::accounts`](struct.Owner.html#method.accounts)
+	pub fn accounts(&self, keychain_mask: Option<SecretKey>) -> OwnerFuture<Vec<AcctPathMapping>> {
+		self.call(move |api| api.accounts(keychain_mask.as_ref()))
+	}
+
+	/// Async version of [`Owner::retrieve_outputs`](struct.Owner.html#method.retrieve_outputs)
+	pub fn retrieve_outputs(
+		&self,
+		keychain_mask: Option<SecretKey>,
+		include_spent: bool,
+		refresh_from_node: bool,
+		tx_id: Option<u32>,
+	) -> OwnerFuture<(bool, Vec<OutputCommitMapping>)> {
+		self.call(move |api| {
+			api.retrieve_outputs(
+				keychain_mask.as_ref(),
+				include_spent,
+				refresh_from_node,
+				tx_id,
+			)
+		})
+	}
+
+	/// Async version of [`Owner::retrieve_txs`](struct.Owner.html#method.retrieve_txs)
+	pub fn retrieve_txs(
+		&self,
+		keychain_mask: Option<SecretKey>,
+		refresh_from_node: bool,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> OwnerFuture<(bool, Vec<TxLogEntry>)> {
+		self.call(move |api| {
+			api.retrieve_txs(
+				keychain_mask.as_ref(),
+				refresh_from_node,
+				tx_id,
+				tx_slate_id,
+			)
+		})
+	}
+
+	/// Async version of
+	/// [`Owner::retrieve_summary_info`](struct.Owner.html#method.retrieve_summary_info)
+	pub fn retrieve_summary_info(
+		&self,
+		keychain_mask: Option<SecretKey>,
+		refresh_from_node: bool,
+		minimum_confirmations: u64,
+	) -> OwnerFuture<(bool, WalletInfo)> {
+		self.call(move |api| {
+			api.retrieve_summary_info(
+				keychain_mask.as_ref(),
+				refresh_from_node,
+				minimum_confirmations,
+			)
+		})
+	}
+
+	/// Async version of [`Owner::init_send_tx`](struct.Owner.html#method.init_send_tx)
+	pub fn init_send_tx(
+		&self,
+		keychain_mask: Option<SecretKey>,
+		args: InitTxArgs,
+	) -> OwnerFuture<Slate> {
+		self.call(move |api| api.init_send_tx(keychain_mask.as_ref(), args))
+	}
+
+	/// Async version of [`Owner::issue_invoice_tx`](struct.Owner.html#method.issue_invoice_tx)
+	pub fn issue_invoice_tx(
+		&self,
+		keychain_mask: Option<SecretKey>,
+		args: IssueInvoiceTxArgs,
+	) -> OwnerFuture<Slate> {
+		self.call(move |api| api.issue_invoice_tx(keychain_mask.as_ref(), args))
+	}
+
+	/// Async version of
+	/// [`Owner::process_invoice_tx`](struct.Owner.html#method.process_invoice_tx)
+	pub fn process_invoice_tx(
+		&self,
+		keychain_mask: Option<SecretKey>,
+		slate: Slate,
+		args: InitTxArgs,
+	) -> OwnerFuture<Slate> {
+		self.call(move |api| api.process_invoice_tx(keychain_mask.as_ref(), &slate, args))
+	}
+
+	/// Async version of [`Owner::tx_lock_outputs`](struct.Owner.html#method.tx_lock_outputs)
+	pub fn tx_lock_outputs(
+		&self,
+		keychain_mask: Option<SecretKey>,
+		slate: Slate,
+		participant_id: usize,
+	) -> OwnerFuture<()> {
+		self.call(move |api| api.tx_lock_outputs(keychain_mask.as_ref(), &slate, participant_id))
+	}
+
+	/// Async version of [`Owner::finalize_tx`](struct.Owner.html#method.finalize_tx)
+	pub fn finalize_tx(
+		&self,
+		keychain_mask: Option<SecretKey>,
+		slate: Slate,
+	) -> OwnerFuture<Slate> {
+		self.call(move |api| api.finalize_tx(keychain_mask.as_ref(), &slate))
+	}
+
+	/// Async version of [`Owner::post_tx`](struct.Owner.html#method.post_tx)
+	pub fn post_tx(
+		&self,
+		keychain_mask: Option<SecretKey>,
+		tx: Transaction,
+		fluff: bool,
+	) -> OwnerFuture<()> {
+		self.call(move |api| api.post_tx(keychain_mask.as_ref(), &tx, fluff))
+	}
+
+	/// Async version of [`Owner::cancel_tx`](struct.Owner.html#method.cancel_tx)
+	pub fn cancel_tx(
+		&self,
+		keychain_mask: Option<SecretKey>,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> OwnerFuture<()> {
+		self.call(move |api| api.cancel_tx(keychain_mask.as_ref(), tx_id, tx_slate_id))
+	}
+
+	/// Async version of [`Owner::node_height`](struct.Owner.html#method.node_height)
+	pub fn node_height(&self, keychain_mask: Option<SecretKey>) -> OwnerFuture<NodeHeightResult> {
+		self.call(move |api| api.node_height(keychain_mask.as_ref()))
+	}
+
+	/// Async version of [`Owner::wallet_stats`](struct.Owner.html#method.wallet_stats)
+	pub fn wallet_stats(&self, keychain_mask: Option<SecretKey>) -> OwnerFuture<WalletStats> {
+		self.call(move |api| api.wallet_stats(keychain_mask.as_ref()))
+	}
+}
@@ -13,17 +13,26 @@
 // limitations under the License.
 
 //! JSON-RPC Stub generation for the Owner API
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 use crate::core::core::Transaction;
 use crate::keychain::{Identifier, Keychain};
 use crate::libwallet::slate_versions::v2::TransactionV2;
 use crate::libwallet::{
-	AcctPathMapping, ErrorKind, InitTxArgs, IssueInvoiceTxArgs, NodeClient, NodeHeightResult,
-	OutputCommitMapping, Slate, SlateVersion, TxLogEntry, VersionedSlate, WalletInfo,
-	WalletLCProvider,
+	AcctPathMapping, ArchivedSlate, BalanceHold, ChangeRecord, ChildIndexScan, CoinbaseReport,
+	DerivationScheme, ErrorKind, FailedReceive, FeeRateEstimate, ForeignApiKey, FreezeState,
+	IndexRebuildReport, InitTxArgs, InitTxResult, IssueInvoiceTxArgs, NodeClient, NodeClientStats,
+	NodeHeightResult, NodeStatus, OnetimeAddress, OperationStatus, OutputCommitMapping, OutputData,
+	OutputDerivationAudit, ParticipantAttachmentData, PaymentProof, PendingDispatch, PingResult,
+	RecipientHandshake, ReconcileArgs, RefreshReport, ReserveOutputsArgs, RestoreScan, SendHandle,
+	SendMetrics, Slate, SlateDescription, SlateVersion, SourceFilter, SweepArgs, SweepPlan,
+	TotpSetup, TxCancelResult, TxDetails, TxExportEntry, TxFilter, TxLogEntry, VaultSettings,
+	VersionedSlate, ViewOnlyAttestation, ViewOnlyBundle, WalletEvent, WalletInfo, WalletLCProvider,
+	WalletStats, WatchOnlyKey,
 };
-use crate::util::Mutex;
+use crate::util::secp::pedersen;
+use crate::util::{from_hex, LogLevel, Mutex, ZeroingString};
 use crate::{Owner, OwnerRpcS};
 use easy_jsonrpc_mw;
 use std::sync::Arc;
@@ -99,6 +108,17 @@ pub trait OwnerRpc: Sync + Send {
 	 */
 	fn create_account_path(&self, label: &String) -> Result<Identifier, ErrorKind>;
 
+	/**
+	Networked version of [Owner::create_account_path_with_scheme](struct.Owner.html#method.create_account_path_with_scheme).
+
+	*/
+
+	fn create_account_path_with_scheme(
+		&self,
+		label: &String,
+		scheme: DerivationScheme,
+	) -> Result<Identifier, ErrorKind>;
+
 	/**
 	Networked version of [Owner::set_active_account](struct.Owner.html#method.set_active_account).
 
@@ -129,6 +149,24 @@ pub trait OwnerRpc: Sync + Send {
 	 */
 	fn set_active_account(&self, label: &String) -> Result<(), ErrorKind>;
 
+	/**
+	Networked version of [Owner::get_next_child_index](struct.Owner.html#method.get_next_child_index).
+
+	 */
+	fn get_next_child_index(&self, label: &String) -> Result<u32, ErrorKind>;
+
+	/**
+	Networked version of [Owner::set_next_child_index](struct.Owner.html#method.set_next_child_index).
+
+	 */
+	fn set_next_child_index(&self, label: &String, index: u32) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::scan_child_indices](struct.Owner.html#method.scan_child_indices).
+
+	 */
+	fn scan_child_indices(&self, label: &String) -> Result<ChildIndexScan, ErrorKind>;
+
 	/**
 	Networked version of [Owner::retrieve_outputs](struct.Owner.html#method.retrieve_outputs).
 
@@ -156,6 +194,102 @@ pub trait OwnerRpc: Sync + Send {
 		tx_slate_id: Option<Uuid>,
 	) -> Result<(bool, Vec<TxLogEntry>), ErrorKind>;
 
+	/**
+	Networked version of [Owner::retrieve_txs_since](struct.Owner.html#method.retrieve_txs_since).
+
+	 */
+	fn retrieve_txs_since(
+		&self,
+		refresh_from_node: bool,
+		since: u64,
+	) -> Result<(bool, u64, Vec<TxLogEntry>), ErrorKind>;
+
+	/**
+	Networked version of [Owner::retrieve_outputs_since](struct.Owner.html#method.retrieve_outputs_since).
+
+	 */
+	fn retrieve_outputs_since(
+		&self,
+		refresh_from_node: bool,
+		since: u64,
+	) -> Result<(bool, u64, Vec<OutputCommitMapping>), ErrorKind>;
+
+	/**
+	Networked version of [Owner::get_tx_details](struct.Owner.html#method.get_tx_details).
+
+	 */
+	fn get_tx_details(
+		&self,
+		refresh_from_node: bool,
+		tx_slate_id: Uuid,
+	) -> Result<(bool, TxDetails), ErrorKind>;
+
+	/**
+	Networked version of [Owner::export_txs](struct.Owner.html#method.export_txs).
+
+	*/
+	fn export_txs(&self, refresh_from_node: bool) -> Result<(bool, Vec<TxExportEntry>), ErrorKind>;
+
+	/**
+	Networked version of [Owner::set_tx_price](struct.Owner.html#method.set_tx_price).
+
+	*/
+	fn set_tx_price(
+		&self,
+		tx_slate_id: Uuid,
+		currency: String,
+		unit_price: u64,
+	) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::set_output_cost_basis](struct.Owner.html#method.set_output_cost_basis).
+
+	*/
+	fn set_output_cost_basis(
+		&self,
+		commitment: String,
+		currency: String,
+		unit_price: u64,
+		acquired: DateTime<Utc>,
+	) -> Result<OutputData, ErrorKind>;
+
+	/**
+	Networked version of [Owner::reconcile](struct.Owner.html#method.reconcile).
+
+	 */
+	fn reconcile(
+		&self,
+		refresh_from_node: bool,
+		args: ReconcileArgs,
+	) -> Result<(bool, ReconciliationReport), ErrorKind>;
+
+	/**
+	Networked version of [Owner::export_view_only_bundle](struct.Owner.html#method.export_view_only_bundle).
+
+	*/
+
+	fn export_view_only_bundle(
+		&self,
+		refresh_from_node: bool,
+	) -> Result<(bool, ViewOnlyBundle), ErrorKind>;
+
+	/**
+	Networked version of [Owner::audit_view_only_bundle](struct.Owner.html#method.audit_view_only_bundle).
+
+	*/
+
+	fn audit_view_only_bundle(
+		&self,
+		bundle: ViewOnlyBundle,
+	) -> Result<ViewOnlyAttestation, ErrorKind>;
+
+	/**
+	Networked version of [Owner::watch_only_key](struct.Owner.html#method.watch_only_key).
+
+	*/
+
+	fn watch_only_key(&self) -> Result<WatchOnlyKey, ErrorKind>;
+
 	/**
 	Networked version of [Owner::retrieve_summary_info](struct.Owner.html#method.retrieve_summary_info).
 
@@ -167,6 +301,218 @@ pub trait OwnerRpc: Sync + Send {
 		minimum_confirmations: u64,
 	) -> Result<(bool, WalletInfo), ErrorKind>;
 
+	/**
+	Networked version of [Owner::update_wallet_state](struct.Owner.html#method.update_wallet_state).
+
+	*/
+
+	fn update_wallet_state(&self) -> Result<RefreshReport, ErrorKind>;
+
+	/**
+	Networked version of [Owner::retrieve_coinbase_report](struct.Owner.html#method.retrieve_coinbase_report).
+
+	*/
+
+	fn retrieve_coinbase_report(
+		&self,
+		refresh_from_node: bool,
+	) -> Result<(bool, CoinbaseReport), ErrorKind>;
+
+	/**
+	Networked version of [Owner::wallet_fingerprint](struct.Owner.html#method.wallet_fingerprint).
+
+	*/
+
+	fn wallet_fingerprint(&self) -> Result<String, ErrorKind>;
+
+	/**
+	Networked version of [Owner::reserve_outputs](struct.Owner.html#method.reserve_outputs).
+
+	*/
+
+	fn reserve_outputs(&self, args: ReserveOutputsArgs) -> Result<Vec<OutputData>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::release_reservation](struct.Owner.html#method.release_reservation).
+
+	*/
+
+	fn release_reservation(&self, label: &String) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::lock_outputs](struct.Owner.html#method.lock_outputs).
+
+	*/
+
+	fn lock_outputs(
+		&self,
+		commitments: Vec<String>,
+		ttl_secs: u64,
+		reference: &String,
+	) -> Result<Vec<OutputData>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::unlock_by_ref](struct.Owner.html#method.unlock_by_ref).
+
+	*/
+
+	fn unlock_by_ref(&self, reference: &String) -> Result<Vec<OutputData>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::plan_sweep](struct.Owner.html#method.plan_sweep).
+
+	*/
+
+	fn plan_sweep(&self, args: SweepArgs) -> Result<SweepPlan, ErrorKind>;
+
+	/**
+	Networked version of [Owner::create_hold](struct.Owner.html#method.create_hold).
+
+	*/
+
+	fn create_hold(
+		&self,
+		amount: u64,
+		ttl_secs: u64,
+		reference: &String,
+	) -> Result<BalanceHold, ErrorKind>;
+
+	/**
+	Networked version of [Owner::release_hold](struct.Owner.html#method.release_hold).
+
+	*/
+
+	fn release_hold(&self, reference: &String) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::retrieve_source_filter](struct.Owner.html#method.retrieve_source_filter).
+
+	*/
+
+	fn retrieve_source_filter(&self) -> Result<SourceFilter, ErrorKind>;
+
+	/**
+	Networked version of [Owner::set_source_blocked](struct.Owner.html#method.set_source_blocked).
+
+	*/
+
+	fn set_source_blocked(&self, origin: &String, blocked: bool) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::set_source_allowed](struct.Owner.html#method.set_source_allowed).
+
+	*/
+
+	fn set_source_allowed(&self, origin: &String, allowed: bool) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::retrieve_failed_receives](struct.Owner.html#method.retrieve_failed_receives).
+
+	*/
+
+	fn retrieve_failed_receives(
+		&self,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<Vec<FailedReceive>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::get_slate_history](struct.Owner.html#method.get_slate_history).
+
+	*/
+
+	fn get_slate_history(&self, slate_id: Uuid) -> Result<Vec<ArchivedSlate>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::retrieve_change_journal](struct.Owner.html#method.retrieve_change_journal).
+
+	*/
+
+	fn retrieve_change_journal(
+		&self,
+		since: Option<u64>,
+		max_records: Option<u32>,
+	) -> Result<Vec<ChangeRecord>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::poll_events](struct.Owner.html#method.poll_events).
+
+	*/
+
+	fn poll_events(
+		&self,
+		consumer: &String,
+		cursor: Option<u64>,
+		max_events: Option<u32>,
+	) -> Result<Vec<WalletEvent>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::set_vault_lock](struct.Owner.html#method.set_vault_lock).
+
+	*/
+
+	fn set_vault_lock(
+		&self,
+		account: &String,
+		lock_blocks: u64,
+	) -> Result<VaultSettings, ErrorKind>;
+
+	/**
+	Networked version of [Owner::retrieve_vault_settings](struct.Owner.html#method.retrieve_vault_settings).
+
+	*/
+
+	fn retrieve_vault_settings(&self, account: &String)
+		-> Result<Option<VaultSettings>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::freeze_wallet](struct.Owner.html#method.freeze_wallet).
+
+	*/
+
+	fn freeze_wallet(&self) -> Result<FreezeState, ErrorKind>;
+
+	/**
+	Networked version of [Owner::unfreeze_wallet](struct.Owner.html#method.unfreeze_wallet).
+
+	*/
+
+	fn unfreeze_wallet(&self, password: String) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::retrieve_freeze_state](struct.Owner.html#method.retrieve_freeze_state).
+
+	*/
+
+	fn retrieve_freeze_state(&self) -> Result<Option<FreezeState>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::setup_totp](struct.Owner.html#method.setup_totp).
+
+	*/
+
+	fn setup_totp(&self) -> Result<TotpSetup, ErrorKind>;
+
+	/**
+	Networked version of [Owner::confirm_totp](struct.Owner.html#method.confirm_totp).
+
+	*/
+
+	fn confirm_totp(&self, code: String) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::disable_totp](struct.Owner.html#method.disable_totp).
+
+	*/
+
+	fn disable_totp(&self, code: Option<String>) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::totp_enabled](struct.Owner.html#method.totp_enabled).
+
+	*/
+
+	fn totp_enabled(&self) -> Result<bool, ErrorKind>;
+
 	/**
 		Networked version of [Owner::init_send_tx](struct.Owner.html#method.init_send_tx).
 
@@ -174,6 +520,13 @@ pub trait OwnerRpc: Sync + Send {
 
 	fn init_send_tx(&self, args: InitTxArgs) -> Result<VersionedSlate, ErrorKind>;
 
+	/**
+	Networked version of [Owner::init_send_tx_with_change](struct.Owner.html#method.init_send_tx_with_change).
+
+	*/
+
+	fn init_send_tx_with_change(&self, args: InitTxArgs) -> Result<InitTxResult, ErrorKind>;
+
 	/**
 		Networked version of [Owner::issue_invoice_tx](struct.Owner.html#method.issue_invoice_tx).
 
@@ -281,6 +634,15 @@ pub trait OwnerRpc: Sync + Send {
 	 */
 	fn finalize_tx(&self, slate: VersionedSlate) -> Result<VersionedSlate, ErrorKind>;
 
+	/**
+	Networked version of [Owner::finalize_invoice_txs_aggregated](struct.Owner.html#method.finalize_invoice_txs_aggregated).
+
+	 */
+	fn finalize_invoice_txs_aggregated(
+		&self,
+		slates: Vec<VersionedSlate>,
+	) -> Result<TransactionV2, ErrorKind>;
+
 	/**
 	Networked version of [Owner::post_tx](struct.Owner.html#method.post_tx).
 
@@ -288,6 +650,13 @@ pub trait OwnerRpc: Sync + Send {
 
 	fn post_tx(&self, tx: TransactionV2, fluff: bool) -> Result<(), ErrorKind>;
 
+	/**
+	Networked version of [Owner::post_raw_tx](struct.Owner.html#method.post_raw_tx).
+
+	 */
+
+	fn post_raw_tx(&self, raw_tx: String, fluff: bool) -> Result<(), ErrorKind>;
+
 	/**
 	Networked version of [Owner::cancel_tx](struct.Owner.html#method.cancel_tx).
 
@@ -296,35 +665,187 @@ pub trait OwnerRpc: Sync + Send {
 	fn cancel_tx(&self, tx_id: Option<u32>, tx_slate_id: Option<Uuid>) -> Result<(), ErrorKind>;
 
 	/**
-	Networked version of [Owner::get_stored_tx](struct.Owner.html#method.get_stored_tx).
+	Networked version of [Owner::cancel_txs](struct.Owner.html#method.cancel_txs).
 
-	 */
-	fn get_stored_tx(&self, tx: &TxLogEntry) -> Result<Option<TransactionV2>, ErrorKind>;
+	*/
+
+	fn cancel_txs(&self, filter: TxFilter) -> Result<Vec<TxCancelResult>, ErrorKind>;
 
 	/**
-	Networked version of [Owner::verify_slate_messages](struct.Owner.html#method.verify_slate_messages).
+	Networked version of [Owner::abandon_tx](struct.Owner.html#method.abandon_tx).
 
-	```
-	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
-	# r#"
-	{
-		"jsonrpc": "2.0",
-		"method": "verify_slate_messages",
-		"id": 1,
-		"params": [ {
-				"amount": "6000000000",
-				"fee": "8000000",
-				"height": "4",
-				"id": "0436430c-2b02-624c-2032-570501212b00",
-				"lock_height": "4",
-				"num_participants": 2,
-				"participant_data": [
-				{
-					"id": "0",
-					"message": "my message",
-					"message_sig": "8f07ddd5e9f5179cff19486034181ed76505baaad53e5d994064127b56c5841b1d4c1358be398f801eb90d933774b5218fa7e769b11c4c640402253353656f75",
-					"part_sig": null,
-					"public_blind_excess": "034b4df2f0558b73ea72a1ca5c4ab20217c66bbe0829056fca7abe76888e9349ee",
+	*/
+
+	fn abandon_tx(&self, tx_id: Option<u32>, tx_slate_id: Option<Uuid>) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::check_recipient](struct.Owner.html#method.check_recipient).
+
+	*/
+
+	fn check_recipient(
+		&self,
+		method: String,
+		dest: String,
+	) -> Result<RecipientHandshake, ErrorKind>;
+
+	/**
+	Networked version of [Owner::dispatch_tx](struct.Owner.html#method.dispatch_tx).
+
+	*/
+
+	fn dispatch_tx(&self, tx_slate_id: Uuid) -> Result<VersionedSlate, ErrorKind>;
+
+	/**
+	Networked version of [Owner::init_send_tx_async](struct.Owner.html#method.init_send_tx_async).
+
+	*/
+
+	fn init_send_tx_async(&self, args: InitTxArgs) -> Result<SendHandle, ErrorKind>;
+
+	/**
+	Networked version of [Owner::retrieve_operation_status](struct.Owner.html#method.retrieve_operation_status).
+
+	*/
+
+	fn retrieve_operation_status(&self, operation_id: u64) -> Result<OperationStatus, ErrorKind>;
+
+	/**
+	Networked version of [Owner::retrieve_pending_dispatches](struct.Owner.html#method.retrieve_pending_dispatches).
+
+	*/
+
+	fn retrieve_pending_dispatches(
+		&self,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<Vec<PendingDispatch>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::get_onetime_address](struct.Owner.html#method.get_onetime_address).
+
+	*/
+
+	fn get_onetime_address(
+		&self,
+		listener_url: String,
+		label: Option<String>,
+		expires_in_hours: Option<u64>,
+	) -> Result<OnetimeAddress, ErrorKind>;
+
+	/**
+	Networked version of [Owner::retrieve_onetime_addresses](struct.Owner.html#method.retrieve_onetime_addresses).
+
+	*/
+
+	fn retrieve_onetime_addresses(&self) -> Result<Vec<OnetimeAddress>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::create_foreign_api_key](struct.Owner.html#method.create_foreign_api_key).
+
+	*/
+
+	fn create_foreign_api_key(
+		&self,
+		account_label: String,
+		label: Option<String>,
+	) -> Result<(String, ForeignApiKey), ErrorKind>;
+
+	/**
+	Networked version of [Owner::retrieve_foreign_api_keys](struct.Owner.html#method.retrieve_foreign_api_keys).
+
+	*/
+
+	fn retrieve_foreign_api_keys(&self) -> Result<Vec<ForeignApiKey>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::revoke_foreign_api_key](struct.Owner.html#method.revoke_foreign_api_key).
+
+	*/
+
+	fn revoke_foreign_api_key(&self, id: Uuid) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::refund_tx](struct.Owner.html#method.refund_tx).
+
+	 */
+	fn refund_tx(
+		&self,
+		tx_slate_id: Uuid,
+		amount: Option<u64>,
+	) -> Result<VersionedSlate, ErrorKind>;
+
+	/**
+	Networked version of [Owner::get_stored_tx](struct.Owner.html#method.get_stored_tx).
+
+	 */
+	fn get_stored_tx(&self, tx: &TxLogEntry) -> Result<Option<TransactionV2>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::payment_proof_address](struct.Owner.html#method.payment_proof_address).
+
+	 */
+	fn payment_proof_address(&self) -> Result<String, ErrorKind>;
+
+	/**
+	Networked version of [Owner::retrieve_payment_proof](struct.Owner.html#method.retrieve_payment_proof).
+
+	 */
+	fn retrieve_payment_proof(
+		&self,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<PaymentProof, ErrorKind>;
+
+	/**
+	Networked version of [Owner::verify_payment_proof](struct.Owner.html#method.verify_payment_proof).
+
+	 */
+	fn verify_payment_proof(&self, proof: PaymentProof) -> Result<(bool, bool), ErrorKind>;
+
+	/**
+	Networked version of [Owner::audit_tx_outputs](struct.Owner.html#method.audit_tx_outputs).
+
+	 */
+	fn audit_tx_outputs(
+		&self,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<Vec<OutputDerivationAudit>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::retrieve_tx_attachments](struct.Owner.html#method.retrieve_tx_attachments).
+
+	 */
+	fn retrieve_tx_attachments(
+		&self,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<Vec<ParticipantAttachmentData>, ErrorKind>;
+
+	/**
+	Networked version of [Owner::verify_slate_messages](struct.Owner.html#method.verify_slate_messages).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "verify_slate_messages",
+		"id": 1,
+		"params": [ {
+				"amount": "6000000000",
+				"fee": "8000000",
+				"height": "4",
+				"id": "0436430c-2b02-624c-2032-570501212b00",
+				"lock_height": "4",
+				"num_participants": 2,
+				"participant_data": [
+				{
+					"id": "0",
+					"message": "my message",
+					"message_sig": "8f07ddd5e9f5179cff19486034181ed76505baaad53e5d994064127b56c5841b1d4c1358be398f801eb90d933774b5218fa7e769b11c4c640402253353656f75",
+					"part_sig": null,
+					"public_blind_excess": "034b4df2f0558b73ea72a1ca5c4ab20217c66bbe0829056fca7abe76888e9349ee",
 					"public_nonce": "031b84c5567b126440995d3ed5aaba0565d71e1834604819ff9c17f5e9d5dd078f"
 				}
 				],
@@ -367,28 +888,371 @@ pub trait OwnerRpc: Sync + Send {
 	# ,
 	# r#"
 	{
-		"jsonrpc": "2.0",
+		"jsonrpc": "2.0",
+		"id": 1,
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# ,false, 0 ,false, false, false);
+	```
+	*/
+	fn verify_slate_messages(&self, slate: VersionedSlate) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::describe_slate](struct.Owner.html#method.describe_slate).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "describe_slate",
+		"id": 1,
+		"params": [ {
+				"amount": "6000000000",
+				"fee": "8000000",
+				"height": "4",
+				"id": "0436430c-2b02-624c-2032-570501212b00",
+				"lock_height": "4",
+				"num_participants": 2,
+				"participant_data": [
+				{
+					"id": "0",
+					"message": "my message",
+					"message_sig": "8f07ddd5e9f5179cff19486034181ed76505baaad53e5d994064127b56c5841b1d4c1358be398f801eb90d933774b5218fa7e769b11c4c640402253353656f75",
+					"part_sig": null,
+					"public_blind_excess": "034b4df2f0558b73ea72a1ca5c4ab20217c66bbe0829056fca7abe76888e9349ee",
+					"public_nonce": "031b84c5567b126440995d3ed5aaba0565d71e1834604819ff9c17f5e9d5dd078f"
+				}
+				],
+				"tx": {
+					"body": {
+						"inputs": [
+						{
+							"commit": "08e1da9e6dc4d6e808a718b2f110a991dd775d65ce5ae408a4e1f002a4961aa9e7",
+							"features": "Coinbase"
+						}
+						],
+						"kernels": [
+						{
+							"excess": "000000000000000000000000000000000000000000000000000000000000000000",
+							"excess_sig": "00000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000",
+							"features": "HeightLocked",
+							"fee": "8000000",
+							"lock_height": "4"
+						}
+						],
+						"outputs": [
+						{
+							"commit": "094be57c91787fc2033d5d97fae099f1a6ddb37ea48370f1a138f09524c767fdd3",
+							"features": "Plain",
+							"proof": "2a42e9e902b70ce44e1fccb14de87ee0a97100bddf12c6bead1b9c5f4eb60300f29c13094fa12ffeee238fb4532b18f6b61cf51b23c1c7e1ad2e41560dc27edc0a2b9e647a0b3e4e806fced5b65e61d0f1f5197d3e2285c632d359e27b6b9206b2caffea4f67e0c7a2812e7a22c134b98cf89bd43d9f28b8bec25cce037a0ac5b1ae8f667e54e1250813a5263004486b4465ad4e641ab2b535736ea26535a11013564f08f483b7dab1c2bcc3ee38eadf2f7850eff7e3459a4bbabf9f0cf6c50d0c0a4120565cd4a2ce3e354c11721cd695760a24c70e0d5a0dfc3c5dcd51dfad6de2c237a682f36dc0b271f21bb3655e5333016aaa42c2efa1446e5f3c0a79ec417c4d30f77556951cb0f05dbfafb82d9f95951a9ea241fda2a6388f73ace036b98acce079f0e4feebccc96290a86dcc89118a901210b245f2d114cf94396e4dbb461e82aa26a0581389707957968c7cdc466213bb1cd417db207ef40c05842ab67a01a9b96eb1430ebc26e795bb491258d326d5174ad549401059e41782121e506744af8af9d8e493644a87d613600888541cbbe538c625883f3eb4aa3102c5cfcc25de8e97af8927619ce6a731b3b8462d51d993066b935b0648d2344ad72e4fd70f347fbd81041042e5ea31cc7b2e3156a920b80ecba487b950ca32ca95fae85b759c936246ecf441a9fdd95e8fee932d6782cdec686064018c857efc47fb4b2a122600d5fdd79af2486f44df7e629184e1c573bc0a9b3feb40b190ef2861a1ab45e2ac2201b9cd42e495deea247269820ed32389a2810ad6c0f9a296d2a2d9c54089fed50b7f5ecfcd33ab9954360e1d7f5598c32128cfcf2a1d8bf14616818da8a5343bfa88f0eedf392e9d4ab1ace1b60324129cd4852c2e27813a9cf71a6ae6229a4fcecc1a756b3e664c5f50af333082616815a3bec8fc0b75b8e4e767d719"
+						}
+						]
+					},
+					"offset": "d202964900000000d302964900000000d402964900000000d502964900000000"
+				},
+				"version_info": {
+					"orig_version": 2,
+					"version": 2,
+					"block_header_version": 2
+				}
+			}
+		]
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"id": 1,
+		"result": {
+			"Ok": {
+				"amount": "6000000000",
+				"amount_display": "6.000000000 MWC",
+				"fee": "8000000",
+				"fee_display": "0.008000000 MWC",
+				"finalized": false,
+				"height": "4",
+				"id": "0436430c-2b02-624c-2032-570501212b00",
+				"lock_height": "4",
+				"num_inputs": 1,
+				"num_outputs": 1,
+				"num_participants": 2,
+				"participants": [
+					{
+						"attachment_kinds": [],
+						"id": "0",
+						"message": "my message",
+						"message_sig_valid": true,
+						"role": "Sender",
+						"signed": false
+					}
+				],
+				"round1_complete": false,
+				"round2_complete": false,
+				"version": 2
+			}
+		}
+	}
+	# "#
+	# ,false, 0 ,false, false, false);
+	```
+	*/
+	fn describe_slate(&self, slate: VersionedSlate) -> Result<SlateDescription, ErrorKind>;
+
+	/**
+	Networked version of [Owner::restore](struct.Owner.html#method.restore).
+
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "restore",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# , false, 1, false, false, false);
+	```
+	 */
+	fn restore(&self) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::restore_from_birthday](struct.Owner.html#method.restore_from_birthday).
+
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "restore_from_birthday",
+		"params": [4],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# , false, 1, false, false, false);
+	```
+	 */
+	fn restore_from_birthday(&self, birthday_height: u64) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::restore_scan_start](struct.Owner.html#method.restore_scan_start).
+	*/
+	fn restore_scan_start(&self, birthday_height: Option<u64>) -> Result<RestoreScan, ErrorKind>;
+
+	/**
+	Networked version of [Owner::restore_scan](struct.Owner.html#method.restore_scan).
+	*/
+	fn restore_scan(&self, scan: RestoreScan, max_outputs: u64) -> Result<RestoreScan, ErrorKind>;
+
+	/**
+	Networked version of [Owner::restore_commit](struct.Owner.html#method.restore_commit).
+	*/
+	fn restore_commit(&self, scan: RestoreScan) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::check_repair](struct.Owner.html#method.check_repair).
+
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "check_repair",
+		"params": [false],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": null
+		}
+	}
+	# "#
+	# , false, 1, false, false, false);
+	```
+	 */
+	fn check_repair(&self, delete_unconfirmed: bool) -> Result<(), ErrorKind>;
+
+	/**
+	Networked version of [Owner::import_output](struct.Owner.html#method.import_output), with
+	the commitment given in hex.
+	*/
+	fn import_output(
+		&self,
+		commit: String,
+		value: u64,
+		key_id: Identifier,
+		height: Option<u64>,
+	) -> Result<OutputData, ErrorKind>;
+
+	/**
+	Networked version of [Owner::rebuild_indexes](struct.Owner.html#method.rebuild_indexes).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "rebuild_indexes",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"commits_fixed": 0,
+				"stored_txs_pruned": 0,
+				"stored_txs_relinked": 0,
+				"tx_links_pruned": 0
+			}
+		}
+	}
+	# "#
+	# , false, 1, false, false, false);
+	```
+	 */
+	fn rebuild_indexes(&self) -> Result<IndexRebuildReport, ErrorKind>;
+
+	/**
+	Networked version of [Owner::node_height](struct.Owner.html#method.node_height).
+
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "node_height",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
+		"id": 1,
+		"jsonrpc": "2.0",
+		"result": {
+			"Ok": {
+				"height": "5",
+				"updated_from_node": true
+			}
+		}
+	}
+	# "#
+	# , false, 5, false, false, false);
+	```
+	 */
+	fn node_height(&self) -> Result<NodeHeightResult, ErrorKind>;
+
+	/**
+	Networked version of [Owner::node_status](struct.Owner.html#method.node_status).
+
+	*/
+
+	fn node_status(&self) -> Result<NodeStatus, ErrorKind>;
+
+	/**
+	Networked version of [Owner::recommended_fee_rate](struct.Owner.html#method.recommended_fee_rate).
+
+	*/
+
+	fn recommended_fee_rate(&self, num_blocks: Option<u64>) -> Result<FeeRateEstimate, ErrorKind>;
+
+	/**
+	Networked version of [Owner::ping](struct.Owner.html#method.ping).
+
+	*/
+
+	fn ping(&self) -> Result<PingResult, ErrorKind>;
+
+	/**
+	Networked version of [Owner::node_client_stats](struct.Owner.html#method.node_client_stats).
+
+	*/
+
+	fn node_client_stats(&self, reset: bool) -> Result<NodeClientStats, ErrorKind>;
+
+	/**
+	Networked version of [Owner::retrieve_send_metrics](struct.Owner.html#method.retrieve_send_metrics).
+
+	```
+	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
+	# r#"
+	{
+		"jsonrpc": "2.0",
+		"method": "retrieve_send_metrics",
+		"params": [],
+		"id": 1
+	}
+	# "#
+	# ,
+	# r#"
+	{
 		"id": 1,
+		"jsonrpc": "2.0",
 		"result": {
-			"Ok": null
+			"Ok": {
+				"build_us": "0",
+				"refresh_us": "0",
+				"selection_us": "0",
+				"sends": "0",
+				"total_us": "0"
+			}
 		}
 	}
 	# "#
-	# ,false, 0 ,false, false, false);
+	# , false, 0, false, false, false);
 	```
-	*/
-	fn verify_slate_messages(&self, slate: VersionedSlate) -> Result<(), ErrorKind>;
+	 */
+	fn retrieve_send_metrics(&self) -> Result<SendMetrics, ErrorKind>;
 
 	/**
-	Networked version of [Owner::restore](struct.Owner.html#method.restore).
-
+	Networked version of [Owner::wallet_stats](struct.Owner.html#method.wallet_stats).
 
 	```
 	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
 	# r#"
 	{
 		"jsonrpc": "2.0",
-		"method": "restore",
+		"method": "wallet_stats",
 		"params": [],
 		"id": 1
 	}
@@ -399,26 +1263,33 @@ pub trait OwnerRpc: Sync + Send {
 		"id": 1,
 		"jsonrpc": "2.0",
 		"result": {
-			"Ok": null
+			"Ok": {
+				"accounts": 1,
+				"data_dir_size": null,
+				"disk_free_space": null,
+				"last_backup": null,
+				"oldest_unconfirmed_tx": null,
+				"outputs": {},
+				"tx_entries": {}
+			}
 		}
 	}
 	# "#
-	# , false, 1, false, false, false);
+	# , false, 0, false, false, false);
 	```
 	 */
-	fn restore(&self) -> Result<(), ErrorKind>;
+	fn wallet_stats(&self) -> Result<WalletStats, ErrorKind>;
 
 	/**
-	Networked version of [Owner::check_repair](struct.Owner.html#method.check_repair).
-
+	Networked version of [Owner::set_log_level](struct.Owner.html#method.set_log_level).
 
 	```
 	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
 	# r#"
 	{
 		"jsonrpc": "2.0",
-		"method": "check_repair",
-		"params": [false],
+		"method": "set_log_level",
+		"params": ["grin_wallet_impls::node_clients", "Debug"],
 		"id": 1
 	}
 	# "#
@@ -432,21 +1303,21 @@ pub trait OwnerRpc: Sync + Send {
 		}
 	}
 	# "#
-	# , false, 1, false, false, false);
+	# , false, 0, false, false, false);
 	```
-	 */
-	fn check_repair(&self, delete_unconfirmed: bool) -> Result<(), ErrorKind>;
+	*/
 
-	/**
-	Networked version of [Owner::node_height](struct.Owner.html#method.node_height).
+	fn set_log_level(&self, target: String, level: Option<LogLevel>) -> Result<(), ErrorKind>;
 
+	/**
+	Networked version of [Owner::shutdown](struct.Owner.html#method.shutdown).
 
 	```
 	# grin_wallet_api::doctest_helper_json_rpc_owner_assert_response!(
 	# r#"
 	{
 		"jsonrpc": "2.0",
-		"method": "node_height",
+		"method": "shutdown",
 		"params": [],
 		"id": 1
 	}
@@ -457,24 +1328,24 @@ pub trait OwnerRpc: Sync + Send {
 		"id": 1,
 		"jsonrpc": "2.0",
 		"result": {
-			"Ok": {
-				"height": "5",
-				"updated_from_node": true
+			"Err": {
+				"GenericError": "No listener to shut down"
 			}
 		}
 	}
 	# "#
-	# , false, 5, false, false, false);
+	# , false, 0, false, false, false);
 	```
-	 */
-	fn node_height(&self) -> Result<NodeHeightResult, ErrorKind>;
+	*/
+
+	fn shutdown(&self) -> Result<(), ErrorKind>;
 }
 
-impl<'a, L, C, K> OwnerRpc for Owner<'a, L, C, K>
+impl<L, C, K> OwnerRpc for Owner<'static, L, C, K>
 where
-	L: WalletLCProvider<'a, C, K>,
-	C: NodeClient + 'a,
-	K: Keychain + 'a,
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
 {
 	fn accounts(&self) -> Result<Vec<AcctPathMapping>, ErrorKind> {
 		Owner::accounts(self, None).map_err(|e| e.kind())
@@ -484,10 +1355,30 @@ where
 		Owner::create_account_path(self, None, label).map_err(|e| e.kind())
 	}
 
+	fn create_account_path_with_scheme(
+		&self,
+		label: &String,
+		scheme: DerivationScheme,
+	) -> Result<Identifier, ErrorKind> {
+		Owner::create_account_path_with_scheme(self, None, label, scheme).map_err(|e| e.kind())
+	}
+
 	fn set_active_account(&self, label: &String) -> Result<(), ErrorKind> {
 		Owner::set_active_account(self, None, label).map_err(|e| e.kind())
 	}
 
+	fn get_next_child_index(&self, label: &String) -> Result<u32, ErrorKind> {
+		Owner::get_next_child_index(self, None, label).map_err(|e| e.kind())
+	}
+
+	fn set_next_child_index(&self, label: &String, index: u32) -> Result<(), ErrorKind> {
+		Owner::set_next_child_index(self, None, label, index).map_err(|e| e.kind())
+	}
+
+	fn scan_child_indices(&self, label: &String) -> Result<ChildIndexScan, ErrorKind> {
+		Owner::scan_child_indices(self, None, label).map_err(|e| e.kind())
+	}
+
 	fn retrieve_outputs(
 		&self,
 		include_spent: bool,
@@ -507,6 +1398,80 @@ where
 		Owner::retrieve_txs(self, None, refresh_from_node, tx_id, tx_slate_id).map_err(|e| e.kind())
 	}
 
+	fn retrieve_txs_since(
+		&self,
+		refresh_from_node: bool,
+		since: u64,
+	) -> Result<(bool, u64, Vec<TxLogEntry>), ErrorKind> {
+		Owner::retrieve_txs_since(self, None, refresh_from_node, since).map_err(|e| e.kind())
+	}
+
+	fn retrieve_outputs_since(
+		&self,
+		refresh_from_node: bool,
+		since: u64,
+	) -> Result<(bool, u64, Vec<OutputCommitMapping>), ErrorKind> {
+		Owner::retrieve_outputs_since(self, None, refresh_from_node, since).map_err(|e| e.kind())
+	}
+
+	fn get_tx_details(
+		&self,
+		refresh_from_node: bool,
+		tx_slate_id: Uuid,
+	) -> Result<(bool, TxDetails), ErrorKind> {
+		Owner::get_tx_details(self, None, refresh_from_node, tx_slate_id).map_err(|e| e.kind())
+	}
+
+	fn export_txs(&self, refresh_from_node: bool) -> Result<(bool, Vec<TxExportEntry>), ErrorKind> {
+		Owner::export_txs(self, None, refresh_from_node).map_err(|e| e.kind())
+	}
+
+	fn set_tx_price(
+		&self,
+		tx_slate_id: Uuid,
+		currency: String,
+		unit_price: u64,
+	) -> Result<(), ErrorKind> {
+		Owner::set_tx_price(self, None, tx_slate_id, &currency, unit_price).map_err(|e| e.kind())
+	}
+
+	fn set_output_cost_basis(
+		&self,
+		commitment: String,
+		currency: String,
+		unit_price: u64,
+		acquired: DateTime<Utc>,
+	) -> Result<OutputData, ErrorKind> {
+		Owner::set_output_cost_basis(self, None, &commitment, &currency, unit_price, acquired)
+			.map_err(|e| e.kind())
+	}
+
+	fn reconcile(
+		&self,
+		refresh_from_node: bool,
+		args: ReconcileArgs,
+	) -> Result<(bool, ReconciliationReport), ErrorKind> {
+		Owner::reconcile(self, None, refresh_from_node, args).map_err(|e| e.kind())
+	}
+
+	fn export_view_only_bundle(
+		&self,
+		refresh_from_node: bool,
+	) -> Result<(bool, ViewOnlyBundle), ErrorKind> {
+		Owner::export_view_only_bundle(self, None, refresh_from_node).map_err(|e| e.kind())
+	}
+
+	fn audit_view_only_bundle(
+		&self,
+		bundle: ViewOnlyBundle,
+	) -> Result<ViewOnlyAttestation, ErrorKind> {
+		Owner::audit_view_only_bundle(self, None, &bundle).map_err(|e| e.kind())
+	}
+
+	fn watch_only_key(&self) -> Result<WatchOnlyKey, ErrorKind> {
+		Owner::watch_only_key(self, None).map_err(|e| e.kind())
+	}
+
 	fn retrieve_summary_info(
 		&self,
 		refresh_from_node: bool,
@@ -516,12 +1481,152 @@ where
 			.map_err(|e| e.kind())
 	}
 
+	fn update_wallet_state(&self) -> Result<RefreshReport, ErrorKind> {
+		Owner::update_wallet_state(self, None).map_err(|e| e.kind())
+	}
+
+	fn retrieve_coinbase_report(
+		&self,
+		refresh_from_node: bool,
+	) -> Result<(bool, CoinbaseReport), ErrorKind> {
+		Owner::retrieve_coinbase_report(self, None, refresh_from_node).map_err(|e| e.kind())
+	}
+
+	fn wallet_fingerprint(&self) -> Result<String, ErrorKind> {
+		Owner::wallet_fingerprint(self, None).map_err(|e| e.kind())
+	}
+
+	fn reserve_outputs(&self, args: ReserveOutputsArgs) -> Result<Vec<OutputData>, ErrorKind> {
+		Owner::reserve_outputs(self, None, args).map_err(|e| e.kind())
+	}
+
+	fn release_reservation(&self, label: &String) -> Result<(), ErrorKind> {
+		Owner::release_reservation(self, None, label).map_err(|e| e.kind())
+	}
+
+	fn lock_outputs(
+		&self,
+		commitments: Vec<String>,
+		ttl_secs: u64,
+		reference: &String,
+	) -> Result<Vec<OutputData>, ErrorKind> {
+		Owner::lock_outputs(self, None, &commitments, ttl_secs, reference).map_err(|e| e.kind())
+	}
+
+	fn unlock_by_ref(&self, reference: &String) -> Result<Vec<OutputData>, ErrorKind> {
+		Owner::unlock_by_ref(self, None, reference).map_err(|e| e.kind())
+	}
+
+	fn plan_sweep(&self, args: SweepArgs) -> Result<SweepPlan, ErrorKind> {
+		Owner::plan_sweep(self, None, args).map_err(|e| e.kind())
+	}
+
+	fn create_hold(
+		&self,
+		amount: u64,
+		ttl_secs: u64,
+		reference: &String,
+	) -> Result<BalanceHold, ErrorKind> {
+		Owner::create_hold(self, None, amount, ttl_secs, reference).map_err(|e| e.kind())
+	}
+
+	fn release_hold(&self, reference: &String) -> Result<(), ErrorKind> {
+		Owner::release_hold(self, None, reference).map_err(|e| e.kind())
+	}
+
+	fn retrieve_source_filter(&self) -> Result<SourceFilter, ErrorKind> {
+		Owner::retrieve_source_filter(self, None).map_err(|e| e.kind())
+	}
+
+	fn set_source_blocked(&self, origin: &String, blocked: bool) -> Result<(), ErrorKind> {
+		Owner::set_source_blocked(self, None, origin, blocked).map_err(|e| e.kind())
+	}
+
+	fn set_source_allowed(&self, origin: &String, allowed: bool) -> Result<(), ErrorKind> {
+		Owner::set_source_allowed(self, None, origin, allowed).map_err(|e| e.kind())
+	}
+
+	fn retrieve_failed_receives(
+		&self,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<Vec<FailedReceive>, ErrorKind> {
+		Owner::retrieve_failed_receives(self, None, tx_slate_id).map_err(|e| e.kind())
+	}
+
+	fn get_slate_history(&self, slate_id: Uuid) -> Result<Vec<ArchivedSlate>, ErrorKind> {
+		Owner::get_slate_history(self, None, &slate_id).map_err(|e| e.kind())
+	}
+
+	fn retrieve_change_journal(
+		&self,
+		since: Option<u64>,
+		max_records: Option<u32>,
+	) -> Result<Vec<ChangeRecord>, ErrorKind> {
+		Owner::retrieve_change_journal(self, None, since, max_records).map_err(|e| e.kind())
+	}
+
+	fn poll_events(
+		&self,
+		consumer: &String,
+		cursor: Option<u64>,
+		max_events: Option<u32>,
+	) -> Result<Vec<WalletEvent>, ErrorKind> {
+		Owner::poll_events(self, None, consumer, cursor, max_events).map_err(|e| e.kind())
+	}
+
+	fn set_vault_lock(
+		&self,
+		account: &String,
+		lock_blocks: u64,
+	) -> Result<VaultSettings, ErrorKind> {
+		Owner::set_vault_lock(self, None, account, lock_blocks).map_err(|e| e.kind())
+	}
+
+	fn retrieve_vault_settings(
+		&self,
+		account: &String,
+	) -> Result<Option<VaultSettings>, ErrorKind> {
+		Owner::retrieve_vault_settings(self, None, account).map_err(|e| e.kind())
+	}
+
+	fn freeze_wallet(&self) -> Result<FreezeState, ErrorKind> {
+		Owner::freeze_wallet(self, None).map_err(|e| e.kind())
+	}
+
+	fn unfreeze_wallet(&self, password: String) -> Result<(), ErrorKind> {
+		Owner::unfreeze_wallet(self, None, ZeroingString::from(password)).map_err(|e| e.kind())
+	}
+
+	fn retrieve_freeze_state(&self) -> Result<Option<FreezeState>, ErrorKind> {
+		Owner::retrieve_freeze_state(self, None).map_err(|e| e.kind())
+	}
+
+	fn setup_totp(&self) -> Result<TotpSetup, ErrorKind> {
+		Owner::setup_totp(self, None).map_err(|e| e.kind())
+	}
+
+	fn confirm_totp(&self, code: String) -> Result<(), ErrorKind> {
+		Owner::confirm_totp(self, None, &code).map_err(|e| e.kind())
+	}
+
+	fn disable_totp(&self, code: Option<String>) -> Result<(), ErrorKind> {
+		Owner::disable_totp(self, None, code.as_ref().map(|c| c.as_str())).map_err(|e| e.kind())
+	}
+
+	fn totp_enabled(&self) -> Result<bool, ErrorKind> {
+		Owner::totp_enabled(self, None).map_err(|e| e.kind())
+	}
+
 	fn init_send_tx(&self, args: InitTxArgs) -> Result<VersionedSlate, ErrorKind> {
 		let slate = Owner::init_send_tx(self, None, args).map_err(|e| e.kind())?;
 		let version = SlateVersion::V2;
 		Ok(VersionedSlate::into_version(slate, version))
 	}
 
+	fn init_send_tx_with_change(&self, args: InitTxArgs) -> Result<InitTxResult, ErrorKind> {
+		Owner::init_send_tx_with_change(self, None, args).map_err(|e| e.kind())
+	}
+
 	fn issue_invoice_tx(&self, args: IssueInvoiceTxArgs) -> Result<VersionedSlate, ErrorKind> {
 		let slate = Owner::issue_invoice_tx(self, None, args).map_err(|e| e.kind())?;
 		let version = SlateVersion::V2;
@@ -546,6 +1651,16 @@ where
 		Ok(VersionedSlate::into_version(out_slate, version))
 	}
 
+	fn finalize_invoice_txs_aggregated(
+		&self,
+		slates: Vec<VersionedSlate>,
+	) -> Result<TransactionV2, ErrorKind> {
+		let slates: Vec<Slate> = slates.into_iter().map(|s| Slate::from(s)).collect();
+		Owner::finalize_invoice_txs_aggregated(self, None, &slates)
+			.map(|tx| TransactionV2::from(tx))
+			.map_err(|e| e.kind())
+	}
+
 	fn tx_lock_outputs(
 		&self,
 		slate: VersionedSlate,
@@ -559,31 +1674,225 @@ where
 		Owner::cancel_tx(self, None, tx_id, tx_slate_id).map_err(|e| e.kind())
 	}
 
+	fn cancel_txs(&self, filter: TxFilter) -> Result<Vec<TxCancelResult>, ErrorKind> {
+		Owner::cancel_txs(self, None, &filter).map_err(|e| e.kind())
+	}
+
+	fn abandon_tx(&self, tx_id: Option<u32>, tx_slate_id: Option<Uuid>) -> Result<(), ErrorKind> {
+		Owner::abandon_tx(self, None, tx_id, tx_slate_id).map_err(|e| e.kind())
+	}
+
+	fn check_recipient(
+		&self,
+		method: String,
+		dest: String,
+	) -> Result<RecipientHandshake, ErrorKind> {
+		Owner::check_recipient(self, None, &method, &dest).map_err(|e| e.kind())
+	}
+
+	fn dispatch_tx(&self, tx_slate_id: Uuid) -> Result<VersionedSlate, ErrorKind> {
+		let slate = Owner::dispatch_tx(self, None, tx_slate_id).map_err(|e| e.kind())?;
+		let version = SlateVersion::V2;
+		Ok(VersionedSlate::into_version(slate, version))
+	}
+
+	fn init_send_tx_async(&self, args: InitTxArgs) -> Result<SendHandle, ErrorKind> {
+		Owner::init_send_tx_async(self, None, args).map_err(|e| e.kind())
+	}
+
+	fn retrieve_operation_status(&self, operation_id: u64) -> Result<OperationStatus, ErrorKind> {
+		Owner::retrieve_operation_status(self, None, operation_id).map_err(|e| e.kind())
+	}
+
+	fn retrieve_pending_dispatches(
+		&self,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<Vec<PendingDispatch>, ErrorKind> {
+		Owner::retrieve_pending_dispatches(self, None, tx_slate_id).map_err(|e| e.kind())
+	}
+
+	fn get_onetime_address(
+		&self,
+		listener_url: String,
+		label: Option<String>,
+		expires_in_hours: Option<u64>,
+	) -> Result<OnetimeAddress, ErrorKind> {
+		Owner::get_onetime_address(self, None, &listener_url, label, expires_in_hours)
+			.map_err(|e| e.kind())
+	}
+
+	fn retrieve_onetime_addresses(&self) -> Result<Vec<OnetimeAddress>, ErrorKind> {
+		Owner::retrieve_onetime_addresses(self, None).map_err(|e| e.kind())
+	}
+
+	fn create_foreign_api_key(
+		&self,
+		account_label: String,
+		label: Option<String>,
+	) -> Result<(String, ForeignApiKey), ErrorKind> {
+		Owner::create_foreign_api_key(self, None, &account_label, label).map_err(|e| e.kind())
+	}
+
+	fn retrieve_foreign_api_keys(&self) -> Result<Vec<ForeignApiKey>, ErrorKind> {
+		Owner::retrieve_foreign_api_keys(self, None).map_err(|e| e.kind())
+	}
+
+	fn revoke_foreign_api_key(&self, id: Uuid) -> Result<(), ErrorKind> {
+		Owner::revoke_foreign_api_key(self, None, id).map_err(|e| e.kind())
+	}
+
+	fn refund_tx(
+		&self,
+		tx_slate_id: Uuid,
+		amount: Option<u64>,
+	) -> Result<VersionedSlate, ErrorKind> {
+		let slate = Owner::refund_tx(self, None, tx_slate_id, amount).map_err(|e| e.kind())?;
+		let version = SlateVersion::V2;
+		Ok(VersionedSlate::into_version(slate, version))
+	}
+
 	fn get_stored_tx(&self, tx: &TxLogEntry) -> Result<Option<TransactionV2>, ErrorKind> {
 		Owner::get_stored_tx(self, None, tx)
 			.map(|x| x.map(|y| TransactionV2::from(y)))
 			.map_err(|e| e.kind())
 	}
 
+	fn payment_proof_address(&self) -> Result<String, ErrorKind> {
+		Owner::payment_proof_address(self, None).map_err(|e| e.kind())
+	}
+
+	fn retrieve_payment_proof(
+		&self,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<PaymentProof, ErrorKind> {
+		Owner::retrieve_payment_proof(self, None, tx_id, tx_slate_id).map_err(|e| e.kind())
+	}
+
+	fn verify_payment_proof(&self, proof: PaymentProof) -> Result<(bool, bool), ErrorKind> {
+		Owner::verify_payment_proof(self, None, &proof).map_err(|e| e.kind())
+	}
+
+	fn audit_tx_outputs(
+		&self,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<Vec<OutputDerivationAudit>, ErrorKind> {
+		Owner::audit_tx_outputs(self, None, tx_id, tx_slate_id).map_err(|e| e.kind())
+	}
+
+	fn retrieve_tx_attachments(
+		&self,
+		tx_id: Option<u32>,
+		tx_slate_id: Option<Uuid>,
+	) -> Result<Vec<ParticipantAttachmentData>, ErrorKind> {
+		Owner::retrieve_tx_attachments(self, None, tx_id, tx_slate_id).map_err(|e| e.kind())
+	}
+
 	fn post_tx(&self, tx: TransactionV2, fluff: bool) -> Result<(), ErrorKind> {
 		Owner::post_tx(self, None, &Transaction::from(tx), fluff).map_err(|e| e.kind())
 	}
 
+	fn post_raw_tx(&self, raw_tx: String, fluff: bool) -> Result<(), ErrorKind> {
+		Owner::post_raw_tx(self, None, &raw_tx, fluff).map_err(|e| e.kind())
+	}
+
 	fn verify_slate_messages(&self, slate: VersionedSlate) -> Result<(), ErrorKind> {
 		Owner::verify_slate_messages(self, None, &Slate::from(slate)).map_err(|e| e.kind())
 	}
 
+	fn describe_slate(&self, slate: VersionedSlate) -> Result<SlateDescription, ErrorKind> {
+		Owner::describe_slate(self, None, &Slate::from(slate)).map_err(|e| e.kind())
+	}
+
 	fn restore(&self) -> Result<(), ErrorKind> {
 		Owner::restore(self, None).map_err(|e| e.kind())
 	}
 
+	fn restore_from_birthday(&self, birthday_height: u64) -> Result<(), ErrorKind> {
+		Owner::restore_from_birthday(self, None, birthday_height).map_err(|e| e.kind())
+	}
+
+	fn restore_scan_start(&self, birthday_height: Option<u64>) -> Result<RestoreScan, ErrorKind> {
+		Owner::restore_scan_start(self, None, birthday_height).map_err(|e| e.kind())
+	}
+
+	fn restore_scan(&self, scan: RestoreScan, max_outputs: u64) -> Result<RestoreScan, ErrorKind> {
+		Owner::restore_scan(self, None, scan, max_outputs).map_err(|e| e.kind())
+	}
+
+	fn restore_commit(&self, scan: RestoreScan) -> Result<(), ErrorKind> {
+		Owner::restore_commit(self, None, &scan).map_err(|e| e.kind())
+	}
+
 	fn check_repair(&self, delete_unconfirmed: bool) -> Result<(), ErrorKind> {
 		Owner::check_repair(self, None, delete_unconfirmed).map_err(|e| e.kind())
 	}
 
+	fn import_output(
+		&self,
+		commit: String,
+		value: u64,
+		key_id: Identifier,
+		height: Option<u64>,
+	) -> Result<OutputData, ErrorKind> {
+		let commit = from_hex(commit.clone())
+			.map_err(|_| ErrorKind::ImportOutput(format!("Invalid commitment {}", commit)))?;
+		Owner::import_output(
+			self,
+			None,
+			&pedersen::Commitment::from_vec(commit),
+			value,
+			&key_id,
+			height,
+		)
+		.map_err(|e| e.kind())
+	}
+
+	fn rebuild_indexes(&self) -> Result<IndexRebuildReport, ErrorKind> {
+		Owner::rebuild_indexes(self, None).map_err(|e| e.kind())
+	}
+
 	fn node_height(&self) -> Result<NodeHeightResult, ErrorKind> {
 		Owner::node_height(self, None).map_err(|e| e.kind())
 	}
+
+	fn node_status(&self) -> Result<NodeStatus, ErrorKind> {
+		Owner::node_status(self, None).map_err(|e| e.kind())
+	}
+
+	fn recommended_fee_rate(&self, num_blocks: Option<u64>) -> Result<FeeRateEstimate, ErrorKind> {
+		Owner::recommended_fee_rate(self, None, num_blocks).map_err(|e| e.kind())
+	}
+
+	fn ping(&self) -> Result<PingResult, ErrorKind> {
+		Owner::ping(self, None)
+			.map(|p| PingResult {
+				owner_api_version: 2,
+				..p
+			})
+			.map_err(|e| e.kind())
+	}
+
+	fn node_client_stats(&self, reset: bool) -> Result<NodeClientStats, ErrorKind> {
+		Owner::node_client_stats(self, None, reset).map_err(|e| e.kind())
+	}
+
+	fn retrieve_send_metrics(&self) -> Result<SendMetrics, ErrorKind> {
+		Owner::retrieve_send_metrics(self, None).map_err(|e| e.kind())
+	}
+
+	fn wallet_stats(&self) -> Result<WalletStats, ErrorKind> {
+		Owner::wallet_stats(self, None).map_err(|e| e.kind())
+	}
+
+	fn set_log_level(&self, target: String, level: Option<LogLevel>) -> Result<(), ErrorKind> {
+		Owner::set_log_level(self, &target, level).map_err(|e| e.kind())
+	}
+
+	fn shutdown(&self) -> Result<(), ErrorKind> {
+		Owner::shutdown(self).map_err(|e| e.kind())
+	}
 }
 
 /// helper to set up a real environment to run integrated doctests
@@ -12,16 +12,16 @@
 // limitations under the License.
 
 use crate::core::libtx::secp_ser;
-use crate::libwallet::{Error, ErrorKind};
+use crate::libwallet::{BlockFees, CbData, Error, ErrorKind};
 use crate::util::secp::key::{PublicKey, SecretKey};
 use crate::util::{from_hex, to_hex};
 use failure::ResultExt;
 
 use base64;
 use rand::{thread_rng, Rng};
-use ring::aead;
+use ring::{aead, digest, hmac};
 use serde_json::{self, Value};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 
 /// Wrapper for API Tokens
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -32,6 +32,43 @@ pub struct Token {
 	pub keychain_mask: Option<SecretKey>,
 }
 
+/// Coinbases recently built for the miner API, keyed by block height and fees,
+/// so a repeated request for the same block returns the same output and kernel
+pub struct CoinbaseCache {
+	capacity: usize,
+	entries: VecDeque<(u64, u64, CbData)>,
+}
+
+impl CoinbaseCache {
+	/// Create a cache holding at most `capacity` coinbases
+	pub fn new(capacity: usize) -> Self {
+		CoinbaseCache {
+			capacity,
+			entries: VecDeque::with_capacity(capacity),
+		}
+	}
+
+	/// Coinbase previously built for this height and fees, if still cached
+	pub fn get(&self, block_fees: &BlockFees) -> Option<CbData> {
+		self.entries
+			.iter()
+			.find(|(h, f, _)| *h == block_fees.height && *f == block_fees.fees)
+			.map(|(_, _, cb)| cb.clone())
+	}
+
+	/// Cache a coinbase, evicting the oldest entry once full
+	pub fn insert(&mut self, block_fees: &BlockFees, cb: CbData) {
+		if self.capacity == 0 {
+			return;
+		}
+		while self.entries.len() >= self.capacity {
+			self.entries.pop_front();
+		}
+		self.entries
+			.push_back((block_fees.height, block_fees.fees, cb));
+	}
+}
+
 /// Wrapper for ECDH Public keys
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(transparent)]
@@ -273,6 +310,40 @@ impl EncryptionErrorResponse {
 	}
 }
 
+/// HTTP header carrying the MAC of the owner API V3 responses, when enabled
+pub const RESPONSE_MAC_HEADER: &'static str = "mwc-response-mac";
+
+/// Signing key of the response MACs, derived from the shared key
+fn response_mac_key(key: &SecretKey) -> hmac::SigningKey {
+	hmac::SigningKey::new(&digest::SHA256, &key.0)
+}
+
+/// MAC of an owner API V3 response, the hex HMAC-SHA256 keyed with the shared key of
+/// the nonce of the encrypted request, empty if there is none, followed by the body
+pub fn response_mac_hex(key: &SecretKey, req_nonce: &str, body: &[u8]) -> String {
+	let mut ctx = hmac::SigningContext::with_key(&response_mac_key(key));
+	ctx.update(req_nonce.as_bytes());
+	ctx.update(body);
+	to_hex(ctx.sign().as_ref().to_vec())
+}
+
+/// Check the MAC of an owner API V3 response, made by
+/// [`response_mac_hex`](fn.response_mac_hex.html)
+pub fn verify_response_mac(
+	key: &SecretKey,
+	req_nonce: &str,
+	body: &[u8],
+	mac: &str,
+) -> Result<(), Error> {
+	let tag = from_hex(mac.to_owned())
+		.map_err(|_| ErrorKind::APIEncryption("Invalid response MAC".to_owned()))?;
+	let mut msg = req_nonce.as_bytes().to_vec();
+	msg.extend_from_slice(body);
+	hmac::verify_with_own_key(&response_mac_key(key), &msg, &tag)
+		.map_err(|_| ErrorKind::APIEncryption("Response MAC mismatch".to_owned()))?;
+	Ok(())
+}
+
 #[test]
 fn encrypted_request() -> Result<(), Error> {
 	use crate::util::{from_hex, static_secp_instance};
@@ -306,3 +377,23 @@ fn encrypted_request() -> Result<(), Error> {
 	assert_eq!(req, dec_res);
 	Ok(())
 }
+
+#[test]
+fn response_mac() -> Result<(), Error> {
+	use crate::util::{from_hex, static_secp_instance};
+
+	let key_str = "e00dcc4a009e3427c6b1e1a550c538179d46f3827a13ed74c759c860761caf1e";
+	let shared_key = {
+		let secp_inst = static_secp_instance();
+		let secp = secp_inst.lock();
+		SecretKey::from_slice(&secp, &from_hex(key_str.to_owned()).unwrap())?
+	};
+	let body = br#"{"jsonrpc": "2.0", "id": 1, "result": {"Ok": "100"}}"#;
+	let mac = response_mac_hex(&shared_key, "a1b2", body);
+	verify_response_mac(&shared_key, "a1b2", body, &mac)?;
+	// Tampered bodies and responses replayed to other requests are detected
+	let tampered = br#"{"jsonrpc": "2.0", "id": 1, "result": {"Ok": "900"}}"#;
+	assert!(verify_response_mac(&shared_key, "a1b2", tampered, &mac).is_err());
+	assert!(verify_response_mac(&shared_key, "c3d4", body, &mac).is_err());
+	Ok(())
+}
@@ -70,6 +70,259 @@ fn comments() -> HashMap<String, String> {
 		"check_node_api_http_addr".to_string(),
 		"
 #where the wallet should find a running node
+"
+		.to_string(),
+	);
+	retval.insert(
+		"node_api_http2".to_string(),
+		"
+#if true, only use HTTP/2 when talking to the node (the node must support it)
+#connections to the node are kept alive and reused either way
+"
+		.to_string(),
+	);
+	retval.insert(
+		"node_outputs_query_parallelism".to_string(),
+		"
+#maximum number of output queries (200 outputs each) sent to the node at once
+"
+		.to_string(),
+	);
+	retval.insert(
+		"node_outputs_query_retries".to_string(),
+		"
+#how many times a failed output query is retried before giving up on it
+"
+		.to_string(),
+	);
+	retval.insert(
+		"node_requests_per_second".to_string(),
+		"
+#if set, limits the requests sent to the node to this many a second on average,
+#to spare a shared node during refreshes and restores
+"
+		.to_string(),
+	);
+	retval.insert(
+		"node_request_burst".to_string(),
+		"
+#number of requests sent at once before node_requests_per_second applies
+#defaults to a second's worth of requests
+"
+		.to_string(),
+	);
+	retval.insert(
+		"quorum_node_api_http_addrs".to_string(),
+		"
+#other nodes asked for their chain height when node_quorum_max_deviation is set
+#e.g. quorum_node_api_http_addrs = [\"http://10.0.0.2:3413\", \"http://10.0.0.3:3413\"]
+"
+		.to_string(),
+	);
+	retval.insert(
+		"node_quorum_max_deviation".to_string(),
+		"
+#if set, the wallet asks all the configured nodes for their chain height and
+#refuses to use a node more than this many blocks away from the majority, to
+#guard against a malicious or eclipsed node feeding it stale outputs
+"
+		.to_string(),
+	);
+	retval.insert(
+		"finality_depth".to_string(),
+		"
+#number of confirmations after which outputs and transactions are considered
+#final and no longer checked for reorgs (1440 blocks is roughly one day)
+"
+		.to_string(),
+	);
+	retval.insert(
+		"currency".to_string(),
+		"
+#ticker of the currency held, shown with amounts and returned by the owner API.
+#Defaults to MWC, or tMWC on floonet
+"
+		.to_string(),
+	);
+	retval.insert(
+		"locale".to_string(),
+		"
+#locale of the messages returned with owner API errors, e.g. \"de\" or \"ru\".
+#Error codes aren't affected. Messages missing from a catalog stay in English
+"
+		.to_string(),
+	);
+	retval.insert(
+		"accounting_currency".to_string(),
+		"
+#fiat currency (e.g. \"USD\") the coins are valued in as they're received and sent,
+#for the cost basis of the outputs and the gains reported by export_txs. Needs
+#price_provider_url to be set as well
+"
+		.to_string(),
+	);
+	retval.insert(
+		"price_provider_url".to_string(),
+		"
+#url answering a GET with a json object whose \"price\" field is the price of a
+#coin in the accounting currency. {currency} in the url is replaced by the currency
+"
+		.to_string(),
+	);
+	retval.insert(
+		"change_key_pool_size".to_string(),
+		"
+#number of change output keys reserved at once, ahead of the sends using them,
+#so that sends don't each write the wallet's key index. Keys still reserved when
+#the wallet closes are skipped. Rangeproofs are made for each change amount and
+#can't be prepared ahead. The owner API's retrieve_send_metrics tells how long
+#each step of sending takes
+"
+		.to_string(),
+	);
+	retval.insert(
+		"display_minimum_confirmations".to_string(),
+		"
+#confirmations an output needs to be counted as spendable in the balance the
+#info command shows, when not given with --min_conf
+"
+		.to_string(),
+	);
+	retval.insert(
+		"spend_minimum_confirmations".to_string(),
+		"
+#confirmations an output needs to be spent by the send and pay commands, when
+#not given with --min_conf. Can be higher than display_minimum_confirmations, to
+#show funds early but only spend them once they're unlikely to be reorganized
+"
+		.to_string(),
+	);
+	retval.insert(
+		"payjoin_contribute_input".to_string(),
+		"
+#if true, when a sender asks for it (send --payjoin), one of the wallet's outputs
+#with 10 confirmations is spent in the transaction received, into the output
+#receiving the amount. Not all the inputs of the transaction are then the sender's
+"
+		.to_string(),
+	);
+	retval.insert(
+		"payjoin_release_after_mins".to_string(),
+		"
+#minutes after which a payjoin received that was neither posted nor confirmed is
+#cancelled, making the output it spends spendable again (60 if not set)
+"
+		.to_string(),
+	);
+	retval.insert(
+		"payjoin_max_contributions_per_hour".to_string(),
+		"
+#outputs spent in the payjoins received in an hour at most. Payjoins asked for
+#beyond it are received without one of the wallet's outputs (10 if not set)
+"
+		.to_string(),
+	);
+	retval.insert(
+		"tor_socks_proxy_addr".to_string(),
+		"
+#address of the Tor SOCKS proxy (e.g. \"127.0.0.1:9050\") http sends to .onion
+#destinations are routed through. Without it, sends to .onion addresses fail
+"
+		.to_string(),
+	);
+	retval.insert(
+		"tor_binary".to_string(),
+		"
+#path of the tor executable `listen --method tor` runs to publish the listener
+#as an onion service, \"tor\" from the PATH if not set. Unless
+#tor_socks_proxy_addr is set, sends go through that tor process as well
+"
+		.to_string(),
+	);
+	retval.insert(
+		"slate_archive_retention_days".to_string(),
+		"
+#if set, every version of the slates the wallet receives and sends is kept for
+#that many days in the slate archive, to look into exchanges that failed
+"
+		.to_string(),
+	);
+	retval.insert(
+		"abandon_received_after_hours".to_string(),
+		"
+#if set, received transactions neither confirmed nor seen in the mempool after
+#that many hours are marked abandoned on refresh, their sender having most likely
+#cancelled them. Their amount is then no longer shown as awaiting finalization
+"
+		.to_string(),
+	);
+	retval.insert(
+		"cancel_tx_on_send_failure".to_string(),
+		"
+#whether a transaction sent in one go through the owner API is cancelled, and its
+#outputs unlocked, when finalizing or posting it fails. A send that couldn't be
+#delivered is then dropped rather than kept to be retried with a new slate
+"
+		.to_string(),
+	);
+	retval.insert(
+		"retry_send_on_spent_inputs".to_string(),
+		"
+#whether a transaction the node refuses because some of its inputs were already
+#spent, as when the wallet wasn't refreshed, is cancelled and sent once more with
+#inputs selected anew, after the wallet outputs of the spent inputs are refreshed
+"
+		.to_string(),
+	);
+	retval.insert(
+		"require_tx_approval".to_string(),
+		"
+#whether every transaction sent, or invoice paid, must be created with an approval
+#token and approved by a second one before it's finalized, signed or posted
+"
+		.to_string(),
+	);
+	retval.insert(
+		"stale_node_after_mins".to_string(),
+		"
+#strict mode: if set, transactions aren't built, finalized or posted while the
+#node's chain tip is older than this many minutes, or its height hasn't changed
+#for as long, so a stalled or forked node isn't trusted with them
+"
+		.to_string(),
+	);
+	retval.insert(
+		"verify_outputs_on_open".to_string(),
+		"
+#whether the outputs stored in the wallet are checked against the wallet seed
+#when it's opened, building a rangeproof for each spendable one, to find
+#corrupt entries before a send uses them
+"
+		.to_string(),
+	);
+	retval.insert(
+		"seed_kdf_algorithm".to_string(),
+		"
+#function deriving the key the wallet seed is encrypted with from the password,
+#pbkdf2_sha512 if not set, or argon2id. The wallet.seed file records the
+#parameters it was encrypted with, so changing them only affects seeds encrypted
+#afterwards: when a wallet is created or recovered, or its password changed
+"
+		.to_string(),
+	);
+	retval.insert(
+		"seed_kdf_iterations".to_string(),
+		"
+#number of PBKDF2-SHA512 iterations (100000 if not set, at most 10000000), or of
+#Argon2id passes (3 if not set, at most 100), of the key derivation
+"
+		.to_string(),
+	);
+	retval.insert(
+		"seed_kdf_memory_kib".to_string(),
+		"
+#memory used by the Argon2id key derivation in KiB, 65536 if not set, at most
+#4194304
 "
 		.to_string(),
 	);
@@ -86,6 +339,121 @@ fn comments() -> HashMap<String, String> {
 #include the foreign API endpoints on the same port as the owner
 #API. Useful for networking environments like AWS ECS that make
 #it difficult to access multiple ports on a single service.
+"
+		.to_string(),
+	);
+	retval.insert(
+		"owner_api_response_mac".to_string(),
+		"
+#if true, owner API V3 responses carry an mwc-response-mac header, the hex
+#HMAC-SHA256 of the encrypted request nonce followed by the response body,
+#keyed with the secure API shared key. Clients checking it detect responses
+#tampered with, or replayed, by proxies between them and the wallet.
+"
+		.to_string(),
+	);
+	retval.insert(
+		"owner_api_lock_after_mins".to_string(),
+		"
+#if set, the wallet is locked after this many minutes without owner API V3
+#requests: it's closed, dropping its seed from memory, and has to be opened
+#again with open_wallet. Meant for kiosks and shared machines
+"
+		.to_string(),
+	);
+	retval.insert(
+		"miner_api_secret_path".to_string(),
+		"
+#path of the secret used by mining/stratum servers to authenticate on the
+#miner API (/v2/miner, username 'miner'). When set, build_coinbase is only
+#available there and refused on the Foreign API
+"
+		.to_string(),
+	);
+	retval.insert(
+		"miner_api_max_requests_per_minute".to_string(),
+		"
+#maximum number of requests per minute accepted on the miner API
+"
+		.to_string(),
+	);
+	retval.insert(
+		"api_max_body_size".to_string(),
+		"
+#largest request body, in bytes, accepted by the foreign and owner API listeners
+"
+		.to_string(),
+	);
+	retval.insert(
+		"api_read_timeout_secs".to_string(),
+		"
+#seconds a client is given to send its request body before the request is dropped
+"
+		.to_string(),
+	);
+	retval.insert(
+		"api_max_concurrent_requests".to_string(),
+		"
+#maximum number of requests each listener handles at the same time, further
+#requests are refused until one completes
+"
+		.to_string(),
+	);
+	retval.insert(
+		"receive_challenge_difficulty".to_string(),
+		"
+#if set, senders must solve a proof of work challenge before the foreign listener
+#receives their slate, deterring spam on publicly exposed wallets. The difficulty is
+#in leading zero bits (at most 28), 20 takes a sender about a second
+"
+		.to_string(),
+	);
+	retval.insert(
+		"foreign_api_receive_tx".to_string(),
+		"
+#whether the foreign listener receives slates sent to the wallet (receive_tx), on
+#/v2/foreign and on the dedicated /v2/foreign/receive route. Defaults to true
+"
+		.to_string(),
+	);
+	retval.insert(
+		"foreign_api_finalize_invoice_tx".to_string(),
+		"
+#whether the foreign listener finalizes the invoices it is sent back (finalize_invoice_tx),
+#on /v2/foreign and on the dedicated /v2/foreign/invoice route. Defaults to true
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[wallet.backup]".to_string(),
+		"
+#S3-compatible object storage the backup command, and the wallet daemon every
+#interval_hours if set, push encrypted wallet backups to. Backups are encrypted
+#with a key derived from the wallet seed.
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[wallet.custom_chain]".to_string(),
+		"
+#custom chain, e.g. a private testnet, following the consensus rules of
+#base_chain_type. If genesis_hash is set, nodes on another chain, or that can't
+#be reached to tell, are refused.
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[wallet.slate_versions]".to_string(),
+		"
+#slate version sent to each destination (http URL or keybase user). Slates to
+#other http listeners are downgraded to a version they support if needed.
+"
+		.to_string(),
+	);
+	retval.insert(
+		"[wallet.account_confirmations]".to_string(),
+		"
+#confirmations required by some accounts in place of the wallet's
 "
 		.to_string(),
 	);
@@ -126,6 +494,18 @@ fn comments() -> HashMap<String, String> {
 	retval.insert(
 		"[logging]".to_string(),
 		"
+#to mirror the owner API audit log to a syslog server, e.g. for a SIEM, uncomment
+#and edit. The format is Cef or Syslog, at most max_records_per_minute records
+#are sent, and the values of redact_params are left out along with passwords,
+#mnemonics and tokens:
+#[logging.audit]
+#syslog_addr = \"127.0.0.1:514\"
+#format = \"Cef\"
+#facility = 13
+#max_records_per_minute = 600
+#include_params = false
+#redact_params = [\"dest\"]
+
 #########################################
 ### LOGGING CONFIGURATION             ###
 #########################################
@@ -193,6 +573,65 @@ fn comments() -> HashMap<String, String> {
 	retval
 }
 
+/// Commented out examples of the optional tables of the wallet section, which
+/// aren't written to the file until set. They're added at the end of the wallet
+/// section, by the header of the table they're examples of.
+fn wallet_table_examples() -> Vec<(String, String)> {
+	vec![
+		(
+			"[wallet.backup]".to_string(),
+			"
+#to push encrypted backups to object storage, uncomment and edit:
+#[wallet.backup]
+#s3_endpoint = \"https://s3.eu-west-1.amazonaws.com\"
+#s3_bucket = \"my-wallet-backups\"
+#s3_region = \"eu-west-1\"
+#s3_access_key = \"AKIA...\"
+#s3_secret_key_path = \".s3_secret\"
+#prefix = \"main/\"
+#retention = 7
+#interval_hours = 24
+"
+			.to_string(),
+		),
+		(
+			"[wallet.custom_chain]".to_string(),
+			"
+#to run on a custom chain, such as a private testnet, uncomment and edit. The
+#node address keeps its host, on node_port:
+#[wallet.custom_chain]
+#chain_name = \"my_testnet\"
+#base_chain_type = \"UserTesting\"
+#genesis_hash = \"\"
+#node_port = 33413
+#foreign_api_port = 33415
+#owner_api_port = 33420
+"
+			.to_string(),
+		),
+		(
+			"[wallet.slate_versions]".to_string(),
+			"
+#to pin the slate version sent to a destination, e.g. a wallet that can't read
+#newer slates, uncomment and edit:
+#[wallet.slate_versions]
+#\"http://192.168.0.10:3415\" = 2
+"
+			.to_string(),
+		),
+		(
+			"[wallet.account_confirmations]".to_string(),
+			"
+#to require other confirmations for an account, uncomment and edit:
+#[wallet.account_confirmations.savings]
+#display = 1
+#spend = 20
+"
+			.to_string(),
+		),
+	]
+}
+
 fn get_key(line: &str) -> String {
 	if line.contains("[") && line.contains("]") {
 		return line.to_owned();
@@ -207,8 +646,24 @@ pub fn insert_comments(orig: String) -> String {
 	let comments = comments();
 	let lines: Vec<&str> = orig.split("\n").collect();
 	let mut out_lines = vec![];
-	for l in lines {
+	let mut in_wallet = false;
+	for l in lines.iter() {
 		let key = get_key(l);
+		// the wallet section ends where the next top level table starts
+		if in_wallet && key.starts_with("[") && !key.starts_with("[wallet") {
+			for (table, example) in wallet_table_examples() {
+				if !lines
+					.iter()
+					.any(|l| l.starts_with(table.trim_end_matches("]")))
+				{
+					out_lines.push(example);
+				}
+			}
+			in_wallet = false;
+		}
+		if key == "[wallet]" {
+			in_wallet = true;
+		}
 		if let Some(v) = comments.get(&key) {
 			out_lines.push(v.to_owned());
 		}
@@ -28,7 +28,7 @@ use toml;
 use crate::comments::insert_comments;
 use crate::core::global;
 use crate::types::WalletConfig;
-use crate::types::{ConfigError, GlobalWalletConfig, GlobalWalletConfigMembers};
+use crate::types::{AuditLogConfig, ConfigError, GlobalWalletConfig, GlobalWalletConfigMembers};
 use crate::util::LoggingConfig;
 
 /// Wallet configuration file name
@@ -40,6 +40,18 @@ pub const GRIN_WALLET_DIR: &'static str = "wallet_data";
 /// API secret
 pub const API_SECRET_FILE_NAME: &'static str = ".api_secret";
 
+/// The audit log mirror is configured in the logging section, whose other
+/// entries make up the logging config shared with the node
+#[derive(Deserialize)]
+struct AuditLogSection {
+	logging: Option<AuditLogLogging>,
+}
+
+#[derive(Deserialize)]
+struct AuditLogLogging {
+	audit: Option<AuditLogConfig>,
+}
+
 fn get_grin_path(chain_type: &global::ChainTypes) -> Result<PathBuf, ConfigError> {
 	// Check if grin dir exists
 	let mut grin_path = match dirs::home_dir() {
@@ -150,6 +162,7 @@ impl Default for GlobalWalletConfigMembers {
 		GlobalWalletConfigMembers {
 			logging: Some(LoggingConfig::default()),
 			wallet: WalletConfig::default(),
+			audit_log: None,
 		}
 	}
 }
@@ -208,9 +221,15 @@ impl GlobalWalletConfig {
 		let mut file = File::open(self.config_file_path.as_mut().unwrap())?;
 		let mut contents = String::new();
 		file.read_to_string(&mut contents)?;
-		let decoded: Result<GlobalWalletConfigMembers, toml::de::Error> = toml::from_str(&contents);
+		let decoded: Result<GlobalWalletConfigMembers, toml::de::Error> = toml::from_str(&contents)
+			.and_then(|mut gc: GlobalWalletConfigMembers| {
+				let section: AuditLogSection = toml::from_str(&contents)?;
+				gc.audit_log = section.logging.and_then(|l| l.audit);
+				Ok(gc)
+			});
 		match decoded {
-			Ok(gc) => {
+			Ok(mut gc) => {
+				gc.wallet.apply_custom_chain();
 				self.members = Some(gc);
 				return Ok(self);
 			}
@@ -31,4 +31,7 @@ pub mod config;
 pub mod types;
 
 pub use crate::config::{initial_setup_wallet, GRIN_WALLET_DIR, WALLET_CONFIG_FILE_NAME};
-pub use crate::types::{ConfigError, GlobalWalletConfig, GlobalWalletConfigMembers, WalletConfig};
+pub use crate::types::{
+	AccountConfirmations, AuditLogConfig, AuditLogFormat, BackupConfig, ConfigError,
+	CustomChainConfig, GlobalWalletConfig, GlobalWalletConfigMembers, WalletConfig,
+};
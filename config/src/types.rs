@@ -14,6 +14,7 @@
 
 //! Public types for config modules
 
+use std::collections::BTreeMap;
 use std::fmt;
 use std::io;
 use std::path::PathBuf;
@@ -21,6 +22,17 @@ use std::path::PathBuf;
 use crate::core::global::ChainTypes;
 use crate::util::LoggingConfig;
 
+/// Default number of confirmations after which outputs and transactions are
+/// treated as final, i.e. deeper than any reorg the wallet expects to see
+pub const DEFAULT_FINALITY_DEPTH: u64 = 1440;
+
+/// Default number of minutes after which an input contributed to a payjoin that
+/// was neither posted nor confirmed is released
+pub const DEFAULT_PAYJOIN_RELEASE_AFTER_MINS: u64 = 60;
+
+/// Default number of inputs contributed to payjoins in an hour, at most
+pub const DEFAULT_PAYJOIN_MAX_CONTRIBUTIONS_PER_HOUR: u32 = 10;
+
 /// Command-line wallet configuration
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct WalletConfig {
@@ -40,8 +52,110 @@ pub struct WalletConfig {
 	/// The api address of a running server node against which transaction inputs
 	/// will be checked during send
 	pub check_node_api_http_addr: String,
+	/// If Some(true), talk to the node over HTTP/2 only, multiplexing
+	/// all requests over a single connection
+	pub node_api_http2: Option<bool>,
+	/// Maximum number of output queries sent to the node at once
+	pub node_outputs_query_parallelism: Option<usize>,
+	/// Number of times a failed output query is retried
+	pub node_outputs_query_retries: Option<u32>,
+	/// Average number of requests a second sent to the node, unlimited if missing
+	pub node_requests_per_second: Option<f64>,
+	/// Number of requests that may be sent to the node at once before the rate limit applies
+	pub node_request_burst: Option<u32>,
+	/// Other nodes asked for their chain height along with check_node_api_http_addr
+	/// when picking a node by quorum
+	pub quorum_node_api_http_addrs: Option<Vec<String>>,
+	/// If set, pick the node to use among the configured ones, refusing nodes whose
+	/// height is further than this many blocks from the quorum
+	pub node_quorum_max_deviation: Option<u64>,
+	/// Number of confirmations after which outputs and txs are final
+	pub finality_depth: Option<u64>,
+	/// Ticker of the currency held, labelling the amounts the wallet reports
+	pub currency: Option<String>,
+	/// Locale of the messages in owner API errors, e.g. `de`. English if unset
+	pub locale: Option<String>,
+	/// Fiat currency, e.g. `USD`, the coins received and sent are valued in for
+	/// their cost basis, not valued if none
+	pub accounting_currency: Option<String>,
+	/// Url the price of the coin in the accounting currency is fetched from,
+	/// `{currency}` being replaced by the currency
+	pub price_provider_url: Option<String>,
+	/// Number of change keys reserved at once, ahead of the sends using them
+	pub change_key_pool_size: Option<usize>,
+	/// Confirmations of the outputs counted as spendable in the balance displayed
+	pub display_minimum_confirmations: Option<u64>,
+	/// Confirmations of the outputs transactions may spend
+	pub spend_minimum_confirmations: Option<u64>,
+	/// Whether to contribute one of the wallet's inputs to the transactions it
+	/// receives, when the sender asks for it
+	pub payjoin_contribute_input: Option<bool>,
+	/// Minutes after which a contributed input is released, cancelling the
+	/// payjoin received, if the payjoin wasn't posted or confirmed
+	pub payjoin_release_after_mins: Option<u64>,
+	/// Inputs contributed to payjoins in an hour at most, further payjoins being
+	/// received without one
+	pub payjoin_max_contributions_per_hour: Option<u32>,
+	/// Address of the Tor SOCKS proxy sends to .onion destinations go through
+	pub tor_socks_proxy_addr: Option<String>,
+	/// Path of the Tor executable run by `listen --method tor`
+	pub tor_binary: Option<String>,
+	/// Days the slates received and sent are kept in the slate archive, none
+	/// to not archive slates
+	pub slate_archive_retention_days: Option<u64>,
+	/// Hours after which received txs neither confirmed nor in the mempool are
+	/// marked abandoned, none to only mark them when asked to
+	pub abandon_received_after_hours: Option<u64>,
+	/// Whether txs sent synchronously through the owner API are cancelled when
+	/// finalizing or posting them fails
+	pub cancel_tx_on_send_failure: Option<bool>,
+	/// Whether a send the node refused for spending already spent inputs is
+	/// cancelled and sent once more with inputs selected anew
+	pub retry_send_on_spent_inputs: Option<bool>,
+	/// Whether every send, and every invoice paid, must be approved by a second
+	/// approval token before it's finalized, signed or posted
+	pub require_tx_approval: Option<bool>,
+	/// Minutes without a new block on the node after which sends aren't built,
+	/// finalized or posted, never refused if none
+	pub stale_node_after_mins: Option<u64>,
+	/// Whether the stored outputs are checked against the wallet seed when the
+	/// wallet is opened, not checked if none
+	pub verify_outputs_on_open: Option<bool>,
+	/// Key derivation function the wallet seed is encrypted with when the wallet
+	/// is created or recovered, or its password changed: `pbkdf2_sha512` if none,
+	/// or `argon2id`
+	pub seed_kdf_algorithm: Option<String>,
+	/// Number of PBKDF2 iterations, or Argon2id passes, of the key derivation
+	pub seed_kdf_iterations: Option<u32>,
+	/// Memory used by the Argon2id key derivation, in KiB
+	pub seed_kdf_memory_kib: Option<u32>,
 	/// Whether to include foreign API endpoints on the Owner API
 	pub owner_api_include_foreign: Option<bool>,
+	/// Whether owner API V3 responses carry a MAC made with the secure API shared key
+	pub owner_api_response_mac: Option<bool>,
+	/// Minutes without owner API V3 requests after which the wallet is locked,
+	/// never if none
+	pub owner_api_lock_after_mins: Option<u64>,
+	/// Location of the secret for basic auth on the miner API. If set, coinbases
+	/// are only served on the miner API and no longer on the Foreign API
+	pub miner_api_secret_path: Option<String>,
+	/// Maximum number of requests per minute accepted on the miner API
+	pub miner_api_max_requests_per_minute: Option<u32>,
+	/// Largest request body, in bytes, accepted by the foreign and owner API listeners
+	pub api_max_body_size: Option<usize>,
+	/// Time, in seconds, a client is given to send a request body to the listeners
+	pub api_read_timeout_secs: Option<u64>,
+	/// Maximum number of requests each listener handles at the same time
+	pub api_max_concurrent_requests: Option<usize>,
+	/// If set, senders must solve a proof of work challenge of this difficulty, in
+	/// leading zero bits, before the foreign listener receives their slate
+	pub receive_challenge_difficulty: Option<u8>,
+	/// Whether the foreign listener receives slates sent to the wallet, on
+	/// `/v2/foreign` and `/v2/foreign/receive`. Enabled if none
+	pub foreign_api_receive_tx: Option<bool>,
+	/// Whether the foreign listener finalizes invoices paid to the wallet, on
+	/// `/v2/foreign` and `/v2/foreign/invoice`. Enabled if none
+	pub foreign_api_finalize_invoice_tx: Option<bool>,
 	/// The directory in which wallet files are stored
 	pub data_file_dir: String,
 	/// If Some(true), don't cache commits alongside output data
@@ -56,6 +170,106 @@ pub struct WalletConfig {
 	pub dark_background_color_scheme: Option<bool>,
 	/// The exploding lifetime (minutes) for keybase notification on coins received
 	pub keybase_notify_ttl: Option<u16>,
+	/// Remote storage encrypted wallet backups are pushed to
+	pub backup: Option<BackupConfig>,
+	/// Parameters of a custom chain (e.g. a private testnet) this wallet runs on
+	pub custom_chain: Option<CustomChainConfig>,
+	/// Slate versions pinned per destination (http URL or keybase user), for
+	/// counterparties running older wallets
+	pub slate_versions: Option<BTreeMap<String, u16>>,
+	/// Confirmations required by some accounts, by account name, in place of
+	/// the wallet's
+	pub account_confirmations: Option<BTreeMap<String, AccountConfirmations>>,
+}
+
+/// Confirmations required by an account, those not set being the wallet's
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AccountConfirmations {
+	/// Confirmations of the outputs counted as spendable in the balance displayed
+	pub display: Option<u64>,
+	/// Confirmations of the outputs transactions may spend
+	pub spend: Option<u64>,
+}
+
+/// Parameters of a custom chain, such as a private testnet used for integration
+/// testing. The chain follows the consensus rules of `base_chain_type`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CustomChainConfig {
+	/// Name of the chain, used in messages
+	pub chain_name: String,
+	/// Chain whose consensus rules the custom chain follows, usually UserTesting
+	pub base_chain_type: ChainTypes,
+	/// Hash of the genesis block. If set, the wallet refuses to use a node on another chain
+	pub genesis_hash: Option<String>,
+	/// Port of the node API
+	pub node_port: u16,
+	/// Port of the wallet's foreign API listener
+	pub foreign_api_port: u16,
+	/// Port of the wallet's owner API listener
+	pub owner_api_port: u16,
+}
+
+/// S3-compatible object storage wallet backups are pushed to. Backups are
+/// encrypted before leaving the wallet, with a key derived from its seed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BackupConfig {
+	/// Base URL of the storage service, e.g. https://s3.eu-west-1.amazonaws.com
+	pub s3_endpoint: String,
+	/// Bucket the backups are stored in
+	pub s3_bucket: String,
+	/// Region of the bucket, us-east-1 for most services other than AWS
+	pub s3_region: String,
+	/// Access key id
+	pub s3_access_key: String,
+	/// File holding the secret access key
+	pub s3_secret_key_path: String,
+	/// Prefix of the backup names, to share a bucket between wallets
+	pub prefix: Option<String>,
+	/// Number of backups kept, older ones are deleted once a new one is pushed.
+	/// Defaults to 7, 0 keeps them all.
+	pub retention: Option<u32>,
+	/// Hours between the backups the wallet daemon pushes. Not set, backups are
+	/// only pushed by the backup command.
+	pub interval_hours: Option<u32>,
+}
+
+/// Mirror of the owner API audit log to a syslog server, e.g. to feed a SIEM.
+/// Read from the `[logging.audit]` section of the configuration file.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditLogConfig {
+	/// Address of the syslog server the records are sent to over UDP, e.g.
+	/// 127.0.0.1:514
+	pub syslog_addr: String,
+	/// Format of the records, plain syslog messages or CEF. Defaults to CEF.
+	#[serde(default)]
+	pub format: AuditLogFormat,
+	/// Syslog facility of the records. Defaults to 13, log audit.
+	pub facility: Option<u8>,
+	/// Records sent per minute at most, the ones over are dropped and counted.
+	/// Defaults to 600, 0 doesn't limit them.
+	pub max_records_per_minute: Option<u32>,
+	/// Whether the records include the parameters of the requests
+	#[serde(default)]
+	pub include_params: bool,
+	/// Parameters whose values are redacted from the records, on top of the
+	/// passwords, mnemonics and tokens that always are
+	#[serde(default)]
+	pub redact_params: Vec<String>,
+}
+
+/// Format of the records of the audit log mirror
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum AuditLogFormat {
+	/// Syslog message of key=value pairs
+	Syslog,
+	/// ArcSight Common Event Format, in a syslog message
+	Cef,
+}
+
+impl Default for AuditLogFormat {
+	fn default() -> AuditLogFormat {
+		AuditLogFormat::Cef
+	}
 }
 
 impl Default for WalletConfig {
@@ -68,13 +282,57 @@ impl Default for WalletConfig {
 			api_secret_path: Some(".api_secret".to_string()),
 			node_api_secret_path: Some(".api_secret".to_string()),
 			check_node_api_http_addr: "http://127.0.0.1:3413".to_string(),
+			node_api_http2: Some(false),
+			node_outputs_query_parallelism: Some(4),
+			node_outputs_query_retries: Some(2),
+			node_requests_per_second: None,
+			node_request_burst: None,
+			quorum_node_api_http_addrs: None,
+			node_quorum_max_deviation: None,
+			finality_depth: Some(DEFAULT_FINALITY_DEPTH),
+			currency: None,
+			locale: None,
+			accounting_currency: None,
+			price_provider_url: None,
+			change_key_pool_size: None,
+			display_minimum_confirmations: Some(10),
+			spend_minimum_confirmations: Some(10),
+			payjoin_contribute_input: Some(false),
+			payjoin_release_after_mins: None,
+			payjoin_max_contributions_per_hour: None,
+			tor_socks_proxy_addr: None,
+			tor_binary: None,
+			slate_archive_retention_days: None,
+			abandon_received_after_hours: None,
+			cancel_tx_on_send_failure: Some(false),
+			retry_send_on_spent_inputs: Some(false),
+			require_tx_approval: Some(false),
+			stale_node_after_mins: None,
+			verify_outputs_on_open: None,
+			seed_kdf_algorithm: None,
+			seed_kdf_iterations: None,
+			seed_kdf_memory_kib: None,
 			owner_api_include_foreign: Some(false),
+			owner_api_response_mac: None,
+			owner_api_lock_after_mins: None,
+			miner_api_secret_path: None,
+			miner_api_max_requests_per_minute: Some(120),
+			api_max_body_size: Some(1_048_576),
+			api_read_timeout_secs: Some(30),
+			api_max_concurrent_requests: Some(32),
+			receive_challenge_difficulty: None,
+			foreign_api_receive_tx: None,
+			foreign_api_finalize_invoice_tx: None,
 			data_file_dir: ".".to_string(),
 			no_commit_cache: Some(false),
 			tls_certificate_file: None,
 			tls_certificate_key: None,
 			dark_background_color_scheme: Some(true),
 			keybase_notify_ttl: Some(1440),
+			backup: None,
+			custom_chain: None,
+			slate_versions: None,
+			account_confirmations: None,
 		}
 	}
 }
@@ -100,7 +358,110 @@ impl WalletConfig {
 	pub fn owner_api_listen_addr(&self) -> String {
 		format!("127.0.0.1:{}", self.owner_api_listen_port())
 	}
+
+	/// Chain this wallet runs on, defaulting to mainnet if missing. On a custom
+	/// chain, this is the chain whose consensus rules it follows.
+	pub fn chain_type(&self) -> ChainTypes {
+		match self.custom_chain.as_ref() {
+			Some(c) => c.base_chain_type.clone(),
+			None => self.chain_type.clone().unwrap_or(ChainTypes::Mainnet),
+		}
+	}
+
+	/// Confirmations after which outputs and transactions are treated as final,
+	/// defaulting to a day of blocks
+	pub fn finality_depth(&self) -> u64 {
+		self.finality_depth.unwrap_or(DEFAULT_FINALITY_DEPTH)
+	}
+
+	/// Minutes after which an input contributed to a payjoin that wasn't posted
+	/// or confirmed is released, defaulting to an hour
+	pub fn payjoin_release_after_mins(&self) -> u64 {
+		self.payjoin_release_after_mins
+			.unwrap_or(DEFAULT_PAYJOIN_RELEASE_AFTER_MINS)
+	}
+
+	/// Inputs contributed to payjoins in an hour at most, defaulting to 10
+	pub fn payjoin_max_contributions_per_hour(&self) -> u32 {
+		self.payjoin_max_contributions_per_hour
+			.unwrap_or(DEFAULT_PAYJOIN_MAX_CONTRIBUTIONS_PER_HOUR)
+	}
+
+	/// Ticker of the currency held, defaulting to MWC, or tMWC for floonet coins
+	pub fn currency(&self) -> String {
+		match self.currency.as_ref() {
+			Some(c) => c.clone(),
+			None => match self.chain_type() {
+				ChainTypes::Floonet => "tMWC".to_owned(),
+				_ => "MWC".to_owned(),
+			},
+		}
+	}
+
+	/// Point the chain type, listener ports and node address at the custom
+	/// chain, if one is set. The node address keeps its scheme and host, only
+	/// its port is the custom chain's
+	pub fn apply_custom_chain(&mut self) {
+		if let Some(c) = self.custom_chain.clone() {
+			self.chain_type = Some(c.base_chain_type);
+			self.api_listen_port = c.foreign_api_port;
+			self.owner_api_listen_port = Some(c.owner_api_port);
+			self.check_node_api_http_addr = with_port(&self.check_node_api_http_addr, c.node_port);
+		}
+	}
+
+	/// Slate version pinned for a destination, if any. URLs match with or
+	/// without a trailing slash.
+	pub fn pinned_slate_version(&self, dest: &str) -> Option<u16> {
+		let versions = self.slate_versions.as_ref()?;
+		versions
+			.get(dest)
+			.or_else(|| versions.get(dest.trim_end_matches('/')))
+			.or_else(|| versions.get(&format!("{}/", dest)))
+			.cloned()
+	}
+
+	/// Confirmations of the outputs of an account counted as spendable in the
+	/// balance displayed, 10 if not configured
+	pub fn display_minimum_confirmations(&self, account: &str) -> u64 {
+		self.account_confirmations
+			.as_ref()
+			.and_then(|a| a.get(account))
+			.and_then(|c| c.display)
+			.or(self.display_minimum_confirmations)
+			.unwrap_or(10)
+	}
+
+	/// Confirmations of the outputs of an account transactions may spend, 10 if
+	/// not configured
+	pub fn spend_minimum_confirmations(&self, account: &str) -> u64 {
+		self.account_confirmations
+			.as_ref()
+			.and_then(|a| a.get(account))
+			.and_then(|c| c.spend)
+			.or(self.spend_minimum_confirmations)
+			.unwrap_or(10)
+	}
 }
+
+/// The address with its port replaced, keeping its scheme, host and path
+fn with_port(addr: &str, port: u16) -> String {
+	let (scheme, rest) = match addr.find("://") {
+		Some(i) => addr.split_at(i + 3),
+		None => ("http://", addr),
+	};
+	let (authority, path) = match rest.find('/') {
+		Some(i) => rest.split_at(i),
+		None => (rest, ""),
+	};
+	// the colons of a bracketed IPv6 host aren't a port separator
+	let host = match authority.rfind(':') {
+		Some(i) if !authority[i..].contains(']') => &authority[..i],
+		_ => authority,
+	};
+	format!("{}{}:{}{}", scheme, host, port, path)
+}
+
 /// Error type wrapping config errors.
 #[derive(Debug)]
 pub enum ConfigError {
@@ -164,4 +525,8 @@ pub struct GlobalWalletConfigMembers {
 	pub wallet: WalletConfig,
 	/// Logging config
 	pub logging: Option<LoggingConfig>,
+	/// Audit log mirror, from the `audit` table of the logging section, which
+	/// otherwise holds the logging config shared with the node
+	#[serde(skip)]
+	pub audit_log: Option<AuditLogConfig>,
 }
@@ -12,35 +12,43 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-//! Grin wallet command-line function implementations
+//! Grin wallet command-line function implementations. The commands that are
+//! part of a wallet's life cycle, from `init` to `finalize`, print nothing and
+//! return what the command line displays, so that they can be embedded as a
+//! library with the same behavior as the binary.
 
 use crate::api::TLSConfig;
-use crate::config::{WalletConfig, WALLET_CONFIG_FILE_NAME};
-use crate::core::{core, global};
+use crate::config::{
+	AuditLogConfig, BackupConfig, WalletConfig, GRIN_WALLET_DIR, WALLET_CONFIG_FILE_NAME,
+};
+use crate::core::core::Transaction;
+use crate::core::global;
 use crate::error::{Error, ErrorKind};
+use crate::impls::{
+	archive_backup, list_backups, restore_backup, verify_backup, BackupInfo, S3Target,
+};
 use crate::impls::{create_sender, KeybaseAllChannels, SlateGetter as _, SlateReceiver as _};
-use crate::impls::{PathToSlate, SlatePutter};
+use crate::impls::{PathToSlate, ReceiveChallenges, SlatePutter};
+use crate::impls::{TorProcess, DEFAULT_TOR_SOCKS_ADDR};
 use crate::keychain;
-use crate::libwallet::{InitTxArgs, IssueInvoiceTxArgs, NodeClient, WalletInst, WalletLCProvider};
+use crate::libwallet::api_impl::owner;
+use crate::libwallet::{
+	self, DerivationScheme, InitTxArgs, IssueInvoiceTxArgs, NodeClient, RefusedTxState, Slate,
+	ViewOnlyBundle, WalletInst, WalletLCProvider,
+};
+use crate::util::file::get_first_line;
 use crate::util::secp::key::SecretKey;
 use crate::util::{Mutex, ZeroingString};
 use crate::{controller, display};
 use serde_json as json;
 use std::fs::File;
-use std::io::Write;
+use std::io::{Read, Write};
+use std::path::Path;
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 use uuid::Uuid;
 
-fn show_recovery_phrase(phrase: ZeroingString) {
-	println!("Your recovery phrase is:");
-	println!();
-	println!("{}", &*phrase);
-	println!();
-	println!("Please back-up these words in a non-digital format.");
-}
-
 /// Arguments common to all wallet commands
 #[derive(Clone)]
 pub struct GlobalArgs {
@@ -50,23 +58,89 @@ pub struct GlobalArgs {
 	pub chain_type: global::ChainTypes,
 	pub password: Option<ZeroingString>,
 	pub tls_conf: Option<TLSConfig>,
+	/// Syslog server the owner API audit log is mirrored to, if any
+	pub audit_log: Option<AuditLogConfig>,
+}
+
+/// Make sure the node runs the custom chain the wallet is configured for, if any,
+/// by comparing genesis block hashes. A node that can't be reached is refused
+/// too, as the chain it runs can't be told.
+pub fn check_custom_chain<C>(node_client: &C, config: &WalletConfig) -> Result<(), Error>
+where
+	C: NodeClient,
+{
+	let custom_chain = match config.custom_chain.as_ref() {
+		Some(c) => c,
+		None => return Ok(()),
+	};
+	let expected = match custom_chain.genesis_hash.as_ref() {
+		Some(h) if !h.is_empty() => h,
+		_ => return Ok(()),
+	};
+	match node_client.get_header_by_height(0) {
+		Ok(genesis) => {
+			if genesis.hash != *expected {
+				return Err(ErrorKind::ArgumentError(format!(
+					"Node at {} is not on chain \"{}\": genesis block is {}, expected {}",
+					node_client.node_url(),
+					custom_chain.chain_name,
+					genesis.hash,
+					expected
+				))
+				.into());
+			}
+		}
+		Err(e) => {
+			return Err(ErrorKind::ArgumentError(format!(
+				"Unable to check the node at {} is on chain \"{}\": {}",
+				node_client.node_url(),
+				custom_chain.chain_name,
+				e
+			))
+			.into());
+		}
+	}
+	Ok(())
+}
+
+/// Pick the node to start with by quorum, see `owner::select_node_by_quorum`. Every
+/// refresh of the wallet picks it again.
+pub fn select_node_by_quorum<C>(node_client: &mut C, config: &WalletConfig) -> Result<(), Error>
+where
+	C: NodeClient,
+{
+	owner::select_node_by_quorum(node_client, config)?;
+	Ok(())
 }
 
 /// Arguments for init command
 pub struct InitArgs {
 	/// BIP39 recovery phrase length
 	pub list_length: usize,
+	/// Password the wallet seed is encrypted with
 	pub password: ZeroingString,
+	/// Configuration the wallet is created with
 	pub config: WalletConfig,
+	/// Recovery phrase to create the wallet from, rather than a new one
 	pub recovery_phrase: Option<ZeroingString>,
+	/// Root public key of the wallet to follow, for a watch-only wallet
+	pub watch_only: Option<String>,
+	/// Whether the wallet is to be restored from the chain once created
 	pub restore: bool,
 }
 
+/// Result of the init command
+pub struct InitResult {
+	/// Recovery phrase of the new wallet, to be backed up by its owner. None for a
+	/// watch-only wallet, which has no seed.
+	pub recovery_phrase: Option<ZeroingString>,
+}
+
 pub fn init<'a, L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
 	g_args: &GlobalArgs,
 	args: InitArgs,
-) -> Result<(), Error>
+) -> Result<InitResult, Error>
 where
 	L: WalletLCProvider<'a, C, K>,
 	C: NodeClient + 'a,
@@ -75,6 +149,13 @@ where
 	let mut w_lock = wallet.lock();
 	let p = w_lock.lc_provider()?;
 	p.create_config(&g_args.chain_type, WALLET_CONFIG_FILE_NAME, None, None)?;
+	if let Some(key) = args.watch_only.as_ref() {
+		p.create_watch_only_wallet(None, key, args.password)?;
+		warn!("Watch-only wallet created, run 'check' to find the outputs it follows");
+		return Ok(InitResult {
+			recovery_phrase: None,
+		});
+	}
 	p.create_wallet(
 		None,
 		args.recovery_phrase,
@@ -83,21 +164,31 @@ where
 		false,
 	)?;
 
-	let m = p.get_mnemonic(None, args.password)?;
-	show_recovery_phrase(m);
-	Ok(())
+	let recovery_phrase = p.get_mnemonic(None, args.password)?;
+	Ok(InitResult {
+		recovery_phrase: Some(recovery_phrase),
+	})
 }
 
 /// Argument for recover
 pub struct RecoverArgs {
+	/// Recovery phrase to recover the wallet seed from, or None to get the
+	/// phrase of the existing wallet
 	pub recovery_phrase: Option<ZeroingString>,
+	/// Password of the wallet
 	pub passphrase: ZeroingString,
 }
 
+/// Result of the recover command
+pub struct RecoverResult {
+	/// Recovery phrase of the existing wallet, if none was given to recover from
+	pub recovery_phrase: Option<ZeroingString>,
+}
+
 pub fn recover<'a, L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
 	args: RecoverArgs,
-) -> Result<(), Error>
+) -> Result<RecoverResult, Error>
 where
 	L: WalletLCProvider<'a, C, K>,
 	C: NodeClient + 'a,
@@ -105,18 +196,34 @@ where
 {
 	let mut w_lock = wallet.lock();
 	let p = w_lock.lc_provider()?;
-	match args.recovery_phrase {
-		None => {
-			let m = p.get_mnemonic(None, args.passphrase)?;
-			show_recovery_phrase(m);
+	let recovery_phrase = match args.recovery_phrase {
+		None => Some(p.get_mnemonic(None, args.passphrase)?),
+		Some(phrase) => {
+			p.recover_from_mnemonic(phrase, args.passphrase)?;
+			None
 		}
-		Some(phrase) => p.recover_from_mnemonic(phrase, args.passphrase)?,
+	};
+	Ok(RecoverResult { recovery_phrase })
+}
+
+/// Limits on the requests accepted by the API listeners
+pub(crate) fn listener_limits(config: &WalletConfig) -> controller::ListenerLimits {
+	let default = controller::ListenerLimits::default();
+	controller::ListenerLimits {
+		max_body_size: config.api_max_body_size.unwrap_or(default.max_body_size),
+		read_timeout: config
+			.api_read_timeout_secs
+			.map(Duration::from_secs)
+			.unwrap_or(default.read_timeout),
+		max_concurrent_requests: config
+			.api_max_concurrent_requests
+			.unwrap_or(default.max_concurrent_requests),
 	}
-	Ok(())
 }
 
 /// Arguments for listen command
 pub struct ListenArgs {
+	/// Listener to run, `http` or `keybase`
 	pub method: String,
 }
 
@@ -132,13 +239,74 @@ where
 	C: NodeClient + 'static,
 	K: keychain::Keychain + 'static,
 {
-	let res = match args.method.as_str() {
-		"http" => controller::foreign_listener(
+	let miner_api = match config.miner_api_secret_path.clone() {
+		Some(path) => match get_first_line(Some(path.clone())) {
+			Some(api_secret) => Some(controller::MinerApiConfig {
+				api_secret,
+				max_requests_per_minute: config.miner_api_max_requests_per_minute.unwrap_or(120),
+			}),
+			None => {
+				return Err(ErrorKind::ArgumentError(format!(
+					"Unable to read miner API secret from \"{}\".",
+					path
+				))
+				.into());
+			}
+		},
+		None => None,
+	};
+	let receive_challenges = match config.receive_challenge_difficulty {
+		Some(d) => Some(
+			ReceiveChallenges::new(d)
+				.map_err(|e| ErrorKind::LibWallet(e.kind(), e.cause_string()))?,
+		),
+		None => None,
+	};
+	let foreign_listener = |tls_conf| {
+		controller::foreign_listener(
 			wallet.clone(),
 			keychain_mask,
 			&config.api_listen_addr(),
-			g_args.tls_conf.clone(),
-		),
+			tls_conf,
+			miner_api,
+			listener_limits(config),
+			receive_challenges,
+			controller::ForeignApiRoles {
+				receive_tx: config.foreign_api_receive_tx.unwrap_or(true),
+				finalize_invoice_tx: config.foreign_api_finalize_invoice_tx.unwrap_or(true),
+			},
+		)
+	};
+	let res = match args.method.as_str() {
+		"http" => foreign_listener(g_args.tls_conf.clone()),
+		"tor" => {
+			// Sends go through the tor process too, unless another proxy is set
+			let socks_addr = match config.tor_socks_proxy_addr {
+				Some(_) => None,
+				None => Some(DEFAULT_TOR_SOCKS_ADDR),
+			};
+			let tor = TorProcess::start(
+				config
+					.tor_binary
+					.as_ref()
+					.map(|b| b.as_str())
+					.unwrap_or("tor"),
+				Path::new(&config.data_file_dir),
+				&config.api_listen_addr(),
+				socks_addr,
+			)
+			.map_err(|e| ErrorKind::LibWallet(e.kind(), e.cause_string()))?;
+			if let Some(addr) = socks_addr {
+				let mut w_lock = wallet.lock();
+				w_lock.lc_provider()?.set_wallet_config(WalletConfig {
+					tor_socks_proxy_addr: Some(addr.to_owned()),
+					..config.clone()
+				});
+			}
+			warn!("Listening as onion service {}", tor.onion_address());
+			// Onion services are already encrypted, tor forwards plain http
+			foreign_listener(None)
+		}
 		"keybase" => KeybaseAllChannels::new()?.listen(
 			config.clone(),
 			g_args.password.clone().unwrap(),
@@ -160,11 +328,13 @@ where
 	Ok(())
 }
 
+/// Run the owner API listener until the shutdown handle is triggered
 pub fn owner_api<L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
 	keychain_mask: Option<SecretKey>,
 	config: &WalletConfig,
 	g_args: &GlobalArgs,
+	shutdown: controller::ListenerShutdown,
 ) -> Result<(), Error>
 where
 	L: WalletLCProvider<'static, C, K> + Send + Sync + 'static,
@@ -181,6 +351,12 @@ where
 		g_args.node_api_secret.clone(),
 		g_args.tls_conf.clone(),
 		config.owner_api_include_foreign.clone(),
+		config.owner_api_response_mac.clone(),
+		config.owner_api_lock_after_mins.clone(),
+		listener_limits(config),
+		g_args.audit_log.clone(),
+		shutdown,
+		None,
 	);
 	if let Err(e) = res {
 		return Err(ErrorKind::LibWallet(e.kind(), e.cause_string()).into());
@@ -191,6 +367,7 @@ where
 /// Arguments for account command
 pub struct AccountArgs {
 	pub create: Option<String>,
+	pub scheme: DerivationScheme,
 }
 
 pub fn account<'a, L, C, K>(
@@ -218,7 +395,7 @@ where
 	} else {
 		let label = args.create.unwrap();
 		let res = controller::owner_single_use(wallet, keychain_mask, |api, m| {
-			api.create_account_path(m, &label)?;
+			api.create_account_path_with_scheme(m, &label, args.scheme)?;
 			thread::sleep(Duration::from_millis(200));
 			info!("Account: '{}' Created!", label);
 			Ok(())
@@ -232,140 +409,265 @@ where
 	Ok(())
 }
 
+/// Chain the wallet's node client is set up for, the Tor SOCKS proxy the
+/// wallet sends to .onion destinations through, and the ticker of its currency
+fn sender_settings<'a, L, C, K>(
+	wallet: &Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+) -> Result<(global::ChainTypes, Option<String>, String), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	let mut w_lock = wallet.lock();
+	let w = w_lock.lc_provider()?.wallet_inst()?;
+	Ok((
+		w.w2n_client().chain_type(),
+		w.config().tor_socks_proxy_addr.clone(),
+		w.config().currency(),
+	))
+}
+
 /// Arguments for the send command
 pub struct SendArgs {
+	/// Amount to send, in nanogrins
 	pub amount: u64,
+	/// Message for the recipient
 	pub message: Option<String>,
+	/// Confirmations the spent outputs must have
 	pub minimum_confirmations: u64,
+	/// Input selection strategy, `smallest` or `all`
 	pub selection_strategy: String,
+	/// Only estimate the fee and locked amount of each selection strategy
 	pub estimate_selection_strategies: bool,
+	/// How the slate is sent, `http`, `file`, `keybase` or `self`
 	pub method: String,
+	/// Destination of the slate for the method, or the account for `self`
 	pub dest: String,
+	/// Number of change outputs
 	pub change_outputs: usize,
+	/// Whether the transaction is fluffed rather than stemmed when posted
 	pub fluff: bool,
+	/// Maximum number of inputs
 	pub max_outputs: usize,
+	/// Slate version to send, rather than the latest
 	pub target_slate_version: Option<u16>,
+	/// Whether to ask the recipient to contribute one of its inputs
+	pub payjoin: bool,
+	/// Whether the fee is deducted from the amount rather than added on top
+	pub fee_from_amount: bool,
+	/// Code of the second factor of the wallet, once one is enabled
+	pub totp: Option<String>,
+}
+
+/// Estimated cost of a send with one selection strategy
+pub struct SendEstimate {
+	/// Input selection strategy
+	pub strategy: String,
+	/// Amount that would be locked
+	pub total: u64,
+	/// Fee
+	pub fee: u64,
+}
+
+/// Result of the send command
+pub struct SendResult {
+	/// Slate of the transaction, finalized and posted unless sent by file.
+	/// None if only estimating.
+	pub slate: Option<Slate>,
+	/// Estimated cost of each selection strategy, if only estimating
+	pub estimates: Vec<SendEstimate>,
 }
 
 pub fn send<'a, L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
 	keychain_mask: Option<&SecretKey>,
 	args: SendArgs,
-	dark_scheme: bool,
-) -> Result<(), Error>
+) -> Result<SendResult, Error>
 where
 	L: WalletLCProvider<'a, C, K>,
 	C: NodeClient + 'a,
 	K: keychain::Keychain + 'a,
 {
+	let (chain_type, tor_socks_proxy, currency) = sender_settings(&wallet)?;
+	let mut send_result = SendResult {
+		slate: None,
+		estimates: vec![],
+	};
 	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+		api.set_totp_code(args.totp.clone());
 		if args.estimate_selection_strategies {
-			let strategies = vec!["smallest", "all"]
-				.into_iter()
-				.map(|strategy| {
-					let init_args = InitTxArgs {
-						src_acct_name: None,
-						amount: args.amount,
-						minimum_confirmations: args.minimum_confirmations,
-						max_outputs: args.max_outputs as u32,
-						num_change_outputs: args.change_outputs as u32,
-						selection_strategy_is_use_all: strategy == "all",
-						estimate_only: Some(true),
-						..Default::default()
-					};
-					let slate = api.init_send_tx(m, init_args).unwrap();
-					(strategy, slate.amount, slate.fee)
-				})
-				.collect();
-			display::estimate(args.amount, strategies, dark_scheme);
+			for strategy in vec!["smallest", "all"] {
+				let init_args = InitTxArgs {
+					src_acct_name: None,
+					amount: args.amount,
+					minimum_confirmations: args.minimum_confirmations,
+					max_outputs: args.max_outputs as u32,
+					num_change_outputs: args.change_outputs as u32,
+					selection_strategy_is_use_all: strategy == "all",
+					estimate_only: Some(true),
+					fee_from_amount: Some(args.fee_from_amount),
+					..Default::default()
+				};
+				let slate = api.init_send_tx(m, init_args)?;
+				send_result.estimates.push(SendEstimate {
+					strategy: strategy.to_owned(),
+					total: slate.amount,
+					fee: slate.fee,
+				});
+			}
 		} else {
-			let init_args = InitTxArgs {
-				src_acct_name: None,
-				amount: args.amount,
-				minimum_confirmations: args.minimum_confirmations,
-				max_outputs: args.max_outputs as u32,
-				num_change_outputs: args.change_outputs as u32,
-				selection_strategy_is_use_all: args.selection_strategy == "all",
-				message: args.message.clone(),
-				target_slate_version: args.target_slate_version,
-				send_args: None,
-				..Default::default()
-			};
-			let result = api.init_send_tx(m, init_args);
-			let mut slate = match result {
-				Ok(s) => {
-					info!(
-						"Tx created: {} grin to {} (strategy '{}')",
-						core::amount_to_hr_string(args.amount, false),
-						args.dest,
-						args.selection_strategy,
-					);
-					s
-				}
-				Err(e) => {
-					info!("Tx not created: {}", e);
-					return Err(e);
-				}
+			// Closing the wallet waits for the send to end
+			let operations = wallet.lock().lc_provider()?.pending_operations();
+			let _operation = owner::begin_operation(&operations, "send", None)?;
+			// A send refused for spending inputs already spent is retried once, if the
+			// wallet is configured to
+			let retry_on_spent = {
+				let mut w_lock = wallet.lock();
+				let w = w_lock.lc_provider()?.wallet_inst()?;
+				w.config().retry_send_on_spent_inputs.unwrap_or(false)
 			};
+			let mut retried = false;
+			loop {
+				let init_args = InitTxArgs {
+					src_acct_name: None,
+					amount: args.amount,
+					minimum_confirmations: args.minimum_confirmations,
+					max_outputs: args.max_outputs as u32,
+					num_change_outputs: args.change_outputs as u32,
+					selection_strategy_is_use_all: args.selection_strategy == "all",
+					message: args.message.clone(),
+					target_slate_version: args.target_slate_version,
+					send_args: None,
+					payjoin: Some(args.payjoin),
+					fee_from_amount: Some(args.fee_from_amount),
+					..Default::default()
+				};
+				let result = api.init_send_tx(m, init_args);
+				let mut slate = match result {
+					Ok(s) => {
+						info!(
+							"Tx created: {} to {} (strategy '{}')",
+							libwallet::amount_to_currency_string(args.amount, false, &currency),
+							args.dest,
+							args.selection_strategy,
+						);
+						s
+					}
+					Err(e) => {
+						info!("Tx not created: {}", e);
+						return Err(e);
+					}
+				};
 
-			match args.method.as_str() {
-				"file" => {
-					PathToSlate((&args.dest).into()).put_tx(&slate)?;
-					api.tx_lock_outputs(m, &slate, 0)?;
-					return Ok(());
+				match args.method.as_str() {
+					"file" => {
+						PathToSlate((&args.dest).into()).put_tx(&slate)?;
+						api.tx_lock_outputs(m, &slate, 0)?;
+						send_result.slate = Some(slate);
+						return Ok(());
+					}
+					"self" => {
+						api.tx_lock_outputs(m, &slate, 0)?;
+						let km = match keychain_mask.as_ref() {
+							None => None,
+							Some(&m) => Some(m.to_owned()),
+						};
+						controller::foreign_single_use(wallet.clone(), km, |api| {
+							slate = api.receive_tx(&slate, Some(&args.dest), None)?;
+							Ok(())
+						})?;
+					}
+					method => {
+						let sender = create_sender(
+							method,
+							&args.dest,
+							chain_type.clone(),
+							tor_socks_proxy.clone(),
+							None,
+						)?;
+						let delivery = sender.deliver_tx(&slate, method)?;
+						let receipt = delivery.receipt.clone();
+						slate = delivery.into_slate()?;
+						api.tx_lock_outputs(m, &slate, 0)?;
+						api.with_context(m, |ctx| ctx.save_delivery_receipt(&slate.id, &receipt))?;
+					}
 				}
-				"self" => {
-					api.tx_lock_outputs(m, &slate, 0)?;
-					let km = match keychain_mask.as_ref() {
-						None => None,
-						Some(&m) => Some(m.to_owned()),
-					};
-					controller::foreign_single_use(wallet, km, |api| {
-						slate = api.receive_tx(&slate, Some(&args.dest), None)?;
-						Ok(())
-					})?;
-				}
-				method => {
-					let sender = create_sender(method, &args.dest)?;
-					slate = sender.send_tx(&slate)?;
-					api.tx_lock_outputs(m, &slate, 0)?;
-				}
-			}
 
-			api.verify_slate_messages(m, &slate).map_err(|e| {
-				error!("Error validating participant messages: {}", e);
-				e
-			})?;
-			slate = api.finalize_tx(m, &slate)?;
-			let result = api.post_tx(m, &slate.tx, args.fluff);
-			match result {
-				Ok(_) => {
-					info!("Tx sent ok",);
-					return Ok(());
-				}
-				Err(e) => {
-					error!("Tx sent fail: {}", e);
-					return Err(e);
+				api.verify_slate_messages(m, &slate).map_err(|e| {
+					error!("Error validating participant messages: {}", e);
+					e
+				})?;
+				slate = api.finalize_tx(m, &slate)?;
+				let result = api.post_tx(m, &slate.tx, args.fluff);
+				match result {
+					Ok(_) => {
+						info!("Tx sent ok",);
+						send_result.slate = Some(slate);
+						return Ok(());
+					}
+					Err(e) => {
+						error!("Tx sent fail: {}", e);
+						if !retry_on_spent {
+							return Err(e);
+						}
+						// Stale outputs of the wallet are refreshed, and the send built anew
+						let state =
+							api.with_context(m, |ctx| ctx.refresh_spent_inputs(&slate.tx))?;
+						let spent = match state {
+							RefusedTxState::Landed => {
+								info!("Tx {} went through after all", slate.id);
+								send_result.slate = Some(slate);
+								return Ok(());
+							}
+							RefusedTxState::InputsSpent(spent) => spent,
+						};
+						if spent.is_empty() {
+							return Err(e);
+						}
+						if retried {
+							api.cancel_tx(m, None, Some(slate.id))?;
+							let kind = libwallet::ErrorKind::InputsAlreadySpent(spent.len());
+							return Err(kind.into());
+						}
+						warn!(
+							"{} inputs of tx {} were already spent, sending it again",
+							spent.len(),
+							slate.id
+						);
+						api.cancel_tx(m, None, Some(slate.id))?;
+						retried = true;
+					}
 				}
 			}
 		}
 		Ok(())
 	})?;
-	Ok(())
+	Ok(send_result)
 }
 
 /// Receive command argument
 pub struct ReceiveArgs {
+	/// File of the slate to receive
 	pub input: String,
+	/// Message for the sender
 	pub message: Option<String>,
 }
 
+/// Result of the receive command
+pub struct ReceiveResult {
+	/// Slate signed by the wallet
+	pub slate: Slate,
+	/// File the signed slate was written to, to be sent back to the sender
+	pub response_file: String,
+}
+
 pub fn receive<'a, L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
 	keychain_mask: Option<&SecretKey>,
 	g_args: &GlobalArgs,
 	args: ReceiveArgs,
-) -> Result<(), Error>
+) -> Result<ReceiveResult, Error>
 where
 	L: WalletLCProvider<'a, C, K>,
 	C: NodeClient + 'a,
@@ -384,31 +686,51 @@ where
 		slate = api.receive_tx(&slate, Some(&g_args.account), args.message.clone())?;
 		Ok(())
 	})?;
-	PathToSlate(format!("{}.response", args.input).into()).put_tx(&slate)?;
+	let response_file = format!("{}.response", args.input);
+	PathToSlate((&response_file).into()).put_tx(&slate)?;
 	info!(
-		"Response file {}.response generated, and can be sent back to the transaction originator.",
-		args.input
+		"Response file {} generated, and can be sent back to the transaction originator.",
+		response_file
 	);
-	Ok(())
+	Ok(ReceiveResult {
+		slate,
+		response_file,
+	})
 }
 
 /// Finalize command args
 pub struct FinalizeArgs {
-	pub input: String,
+	/// Files of the slates to finalize, several only for paid invoices
+	/// finalized as one transaction
+	pub inputs: Vec<String>,
+	/// Whether the transaction is fluffed rather than stemmed when posted
 	pub fluff: bool,
+	/// Code of the second factor of the wallet, once one is enabled
+	pub totp: Option<String>,
+}
+
+/// Result of the finalize command
+pub struct FinalizeResult {
+	/// Ids of the finalized slates
+	pub slate_ids: Vec<Uuid>,
+	/// Transaction posted to the node
+	pub tx: Transaction,
 }
 
 pub fn finalize<'a, L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
 	keychain_mask: Option<&SecretKey>,
 	args: FinalizeArgs,
-) -> Result<(), Error>
+) -> Result<FinalizeResult, Error>
 where
 	L: WalletLCProvider<'a, C, K>,
 	C: NodeClient + 'a,
 	K: keychain::Keychain + 'a,
 {
-	let mut slate = PathToSlate((&args.input).into()).get_tx()?;
+	if args.inputs.len() > 1 {
+		return finalize_aggregated(wallet, keychain_mask, args);
+	}
+	let mut slate = PathToSlate((&args.inputs[0]).into()).get_tx()?;
 
 	// Rather than duplicating the entire command, we'll just
 	// try to determine what kind of finalization this is
@@ -441,18 +763,19 @@ where
 			slate = api.finalize_invoice_tx(&mut slate)?;
 			Ok(())
 		})?;
-	} else {
-		controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+	}
+
+	// Finalized and posted by the same instance, which checks the code of the
+	// second factor once
+	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+		api.set_totp_code(args.totp.clone());
+		if !is_invoice {
 			if let Err(e) = api.verify_slate_messages(m, &slate) {
 				error!("Error validating participant messages: {}", e);
 				return Err(e);
 			}
 			slate = api.finalize_tx(m, &mut slate)?;
-			Ok(())
-		})?;
-	}
-
-	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+		}
 		let result = api.post_tx(m, &slate.tx, args.fluff);
 		match result {
 			Ok(_) => {
@@ -466,7 +789,52 @@ where
 		}
 	})?;
 
-	Ok(())
+	Ok(FinalizeResult {
+		slate_ids: vec![slate.id],
+		tx: slate.tx,
+	})
+}
+
+/// Finalize several paid invoices, posting them as a single aggregated transaction
+fn finalize_aggregated<'a, L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	keychain_mask: Option<&SecretKey>,
+	args: FinalizeArgs,
+) -> Result<FinalizeResult, Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	let mut slates = vec![];
+	for input in args.inputs.iter() {
+		slates.push(PathToSlate(input.into()).get_tx()?);
+	}
+	let mut agg_tx = None;
+	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+		api.set_totp_code(args.totp.clone());
+		let tx = api.finalize_invoice_txs_aggregated(m, &slates)?;
+		let result = api.post_tx(m, &tx, args.fluff);
+		agg_tx = Some(tx);
+		match result {
+			Ok(_) => {
+				info!(
+					"{} invoices sent successfully in one transaction, check the wallet again \
+					 for confirmation.",
+					slates.len()
+				);
+				Ok(())
+			}
+			Err(e) => {
+				error!("Tx not sent: {}", e);
+				Err(e)
+			}
+		}
+	})?;
+	Ok(FinalizeResult {
+		slate_ids: slates.iter().map(|s| s.id).collect(),
+		tx: agg_tx.unwrap(),
+	})
 }
 
 /// Issue Invoice Args
@@ -507,6 +875,7 @@ pub struct ProcessInvoiceArgs {
 	pub max_outputs: usize,
 	pub input: String,
 	pub estimate_selection_strategies: bool,
+	pub totp: Option<String>,
 }
 
 /// Process invoice
@@ -522,7 +891,9 @@ where
 	K: keychain::Keychain + 'a,
 {
 	let slate = PathToSlate((&args.input).into()).get_tx()?;
+	let (chain_type, tor_socks_proxy, currency) = sender_settings(&wallet)?;
 	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+		api.set_totp_code(args.totp.clone());
 		if args.estimate_selection_strategies {
 			let strategies = vec!["smallest", "all"]
 				.into_iter()
@@ -541,7 +912,7 @@ where
 					(strategy, slate.amount, slate.fee)
 				})
 				.collect();
-			display::estimate(slate.amount, strategies, dark_scheme);
+			display::estimate(slate.amount, strategies, &currency, dark_scheme);
 		} else {
 			let init_args = InitTxArgs {
 				src_acct_name: None,
@@ -562,8 +933,8 @@ where
 			let mut slate = match result {
 				Ok(s) => {
 					info!(
-						"Invoice processed: {} grin to {} (strategy '{}')",
-						core::amount_to_hr_string(slate.amount, false),
+						"Invoice processed: {} to {} (strategy '{}')",
+						libwallet::amount_to_currency_string(slate.amount, false, &currency),
 						args.dest,
 						args.selection_strategy,
 					);
@@ -593,9 +964,18 @@ where
 					})?;
 				}
 				method => {
-					let sender = create_sender(method, &args.dest)?;
-					slate = sender.send_tx(&slate)?;
+					let sender = create_sender(
+						method,
+						&args.dest,
+						chain_type.clone(),
+						tor_socks_proxy.clone(),
+						None,
+					)?;
+					let delivery = sender.deliver_tx(&slate, method)?;
+					let receipt = delivery.receipt.clone();
+					slate = delivery.into_slate()?;
 					api.tx_lock_outputs(m, &slate, 0)?;
+					api.with_context(m, |ctx| ctx.save_delivery_receipt(&slate.id, &receipt))?;
 				}
 			}
 		}
@@ -629,6 +1009,23 @@ where
 	Ok(())
 }
 
+pub fn stats<'a, L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	keychain_mask: Option<&SecretKey>,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+		let stats = api.wallet_stats(m)?;
+		display::wallet_stats(&stats);
+		Ok(())
+	})?;
+	Ok(())
+}
+
 pub fn outputs<'a, L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
 	keychain_mask: Option<&SecretKey>,
@@ -709,11 +1106,241 @@ where
 	Ok(())
 }
 
+/// Reconcile command args
+pub struct ReconcileArgs {
+	pub input: String,
+	pub expected_balance: Option<u64>,
+}
+
+pub fn reconcile<'a, L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	keychain_mask: Option<&SecretKey>,
+	g_args: &GlobalArgs,
+	args: ReconcileArgs,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	let mut ledger = String::new();
+	File::open(&args.input)
+		.and_then(|mut f| f.read_to_string(&mut ledger))
+		.map_err(|e| {
+			ErrorKind::ArgumentError(format!("Unable to read ledger {}: {}", args.input, e))
+		})?;
+	let reconcile_args = libwallet::ReconcileArgs {
+		entries: owner::parse_ledger(&ledger)?,
+		expected_balance: args.expected_balance,
+		since: None,
+	};
+	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+		let (validated, report) = api.reconcile(m, true, reconcile_args)?;
+		display::reconciliation(&g_args.account, &report, validated);
+		Ok(())
+	})?;
+	Ok(())
+}
+
+/// Check recipient command args
+pub struct CheckRecipientArgs {
+	/// Method the recipient would be sent to with
+	pub method: String,
+	/// Destination of the recipient
+	pub dest: String,
+}
+
+pub fn check_recipient<'a, L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	keychain_mask: Option<&SecretKey>,
+	args: CheckRecipientArgs,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+		let handshake = api.check_recipient(m, &args.method, &args.dest)?;
+		display::recipient_handshake(&handshake);
+		Ok(())
+	})?;
+	Ok(())
+}
+
+/// View-only bundle command args
+pub struct ViewBundleArgs {
+	/// Bundle to audit, a bundle of the wallet is exported if not given
+	pub audit: Option<String>,
+	/// Whether to write the root public key of the wallet instead of a bundle
+	pub root_key: bool,
+	/// File the bundle, the attestation or the root public key is written to
+	pub dest: String,
+}
+
+pub fn view_bundle<'a, L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	keychain_mask: Option<&SecretKey>,
+	args: ViewBundleArgs,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	let bundle = match args.audit.as_ref() {
+		None => None,
+		Some(input) => {
+			let mut content = String::new();
+			File::open(input)
+				.and_then(|mut f| f.read_to_string(&mut content))
+				.map_err(|e| {
+					ErrorKind::ArgumentError(format!("Unable to read bundle {}: {}", input, e))
+				})?;
+			let bundle: ViewOnlyBundle = json::from_str(&content).map_err(|e| {
+				ErrorKind::ArgumentError(format!("Invalid bundle {}: {}", input, e))
+			})?;
+			Some(bundle)
+		}
+	};
+	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+		let content = match bundle.as_ref() {
+			None if args.root_key => api.watch_only_key(m)?.root_public_key,
+			None => {
+				let (validated, bundle) = api.export_view_only_bundle(m, true)?;
+				if !validated {
+					warn!("Wallet failed to refresh against the node, the bundle may be outdated");
+				}
+				json::to_string_pretty(&bundle).unwrap()
+			}
+			Some(b) => {
+				let attestation = api.audit_view_only_bundle(m, b)?;
+				display::view_only_attestation(&attestation);
+				json::to_string_pretty(&attestation).unwrap()
+			}
+		};
+		let mut file = File::create(&args.dest)?;
+		file.write_all(content.as_bytes())?;
+		info!("View-only file written to {}", args.dest);
+		Ok(())
+	})?;
+	Ok(())
+}
+
+/// Backup command args
+pub struct BackupArgs {
+	pub list: bool,
+	pub verify: Option<String>,
+	/// Backup to restore and directory to restore it into
+	pub restore: Option<(String, String)>,
+}
+
+/// Storage set in the backup config, along with the config
+fn backup_target(config: &WalletConfig) -> Result<(S3Target, &BackupConfig), Error> {
+	let backup_config = config.backup.as_ref().ok_or_else(|| {
+		ErrorKind::ArgumentError("No backup storage set in [wallet.backup]".to_owned())
+	})?;
+	let secret_key =
+		get_first_line(Some(backup_config.s3_secret_key_path.clone())).ok_or_else(|| {
+			ErrorKind::ArgumentError(format!(
+				"Unable to read the storage secret key from {}",
+				backup_config.s3_secret_key_path
+			))
+		})?;
+	let target = S3Target::new(
+		&backup_config.s3_endpoint,
+		&backup_config.s3_bucket,
+		&backup_config.s3_region,
+		&backup_config.s3_access_key,
+		&secret_key,
+	)?;
+	Ok((target, backup_config))
+}
+
+/// Push a backup of the wallet to the storage set in the config, as the backup
+/// command and the wallet daemon do
+pub fn push_wallet_backup<'a, L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	keychain_mask: Option<&SecretKey>,
+	config: &WalletConfig,
+) -> Result<BackupInfo, Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	let (target, backup_config) = backup_target(config)?;
+	let prefix = backup_config.prefix.clone().unwrap_or_default();
+	let wallet_dir = Path::new(&config.data_file_dir).join(GRIN_WALLET_DIR);
+	let retention = backup_config.retention.unwrap_or(7) as usize;
+	// The wallet stays locked while its files are archived, so they're consistent,
+	// but not while the archive is uploaded, checked and old backups pruned
+	let archive = {
+		let mut w_lock = wallet.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		let keychain = w.keychain(keychain_mask)?;
+		archive_backup(&prefix, &wallet_dir, &keychain)?
+	};
+	let info = archive.push(&target, retention)?;
+	warn!(
+		"Backup {} of {} wallet files pushed and verified, {} bytes, sha256 {}",
+		info.name, info.files, info.size, info.sha256
+	);
+	for name in info.pruned.iter() {
+		warn!("Deleted backup {}, beyond the retention", name);
+	}
+	Ok(info)
+}
+
+pub fn backup<'a, L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+	keychain_mask: Option<&SecretKey>,
+	config: &WalletConfig,
+	args: BackupArgs,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: keychain::Keychain + 'a,
+{
+	if args.verify.is_none() && args.restore.is_none() && !args.list {
+		push_wallet_backup(wallet, keychain_mask, config)?;
+		return Ok(());
+	}
+	let (target, backup_config) = backup_target(config)?;
+	let prefix = backup_config.prefix.clone().unwrap_or_default();
+	let keychain = {
+		let mut w_lock = wallet.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		w.keychain(keychain_mask)?
+	};
+	if args.list {
+		for name in list_backups(&target, &prefix, &keychain)? {
+			println!("{}", name);
+		}
+	} else if let Some(name) = args.verify {
+		let files = verify_backup(&target, &name, &keychain)?;
+		warn!(
+			"Backup {} verified, holding {} wallet files",
+			name,
+			files.len()
+		);
+	} else if let Some((name, dir)) = args.restore {
+		let files = restore_backup(&target, &name, &keychain, Path::new(&dir))?;
+		warn!(
+			"Restored {} wallet files of backup {} into {}",
+			files, name, dir
+		);
+	}
+	Ok(())
+}
+
 /// Repost
 pub struct RepostArgs {
 	pub id: u32,
 	pub dump_file: Option<String>,
 	pub fluff: bool,
+	pub totp: Option<String>,
 }
 
 pub fn repost<'a, L, C, K>(
@@ -727,6 +1354,7 @@ where
 	K: keychain::Keychain + 'a,
 {
 	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+		api.set_totp_code(args.totp.clone());
 		let (_, txs) = api.retrieve_txs(m, true, Some(args.id), None)?;
 		let stored_tx = api.get_stored_tx(m, &txs[0])?;
 		if stored_tx.is_none() {
@@ -766,6 +1394,7 @@ pub struct CancelArgs {
 	pub tx_id: Option<u32>,
 	pub tx_slate_id: Option<Uuid>,
 	pub tx_id_string: String,
+	pub totp: Option<String>,
 }
 
 pub fn cancel<'a, L, C, K>(
@@ -779,6 +1408,7 @@ where
 	K: keychain::Keychain + 'a,
 {
 	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
+		api.set_totp_code(args.totp.clone());
 		let result = api.cancel_tx(m, args.tx_id, args.tx_slate_id);
 		match result {
 			Ok(_) => {
@@ -794,9 +1424,15 @@ where
 	Ok(())
 }
 
+/// Arguments for the restore command
+pub struct RestoreArgs {
+	pub birthday: Option<u64>,
+}
+
 pub fn restore<'a, L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
 	keychain_mask: Option<&SecretKey>,
+	args: RestoreArgs,
 ) -> Result<(), Error>
 where
 	L: WalletLCProvider<'a, C, K>,
@@ -804,7 +1440,10 @@ where
 	K: keychain::Keychain + 'a,
 {
 	controller::owner_single_use(wallet.clone(), keychain_mask, |api, m| {
-		let result = api.restore(m);
+		let result = match args.birthday {
+			Some(b) => api.restore_from_birthday(m, b),
+			None => api.restore(m),
+		};
 		match result {
 			Ok(_) => {
 				warn!("Wallet restore complete",);
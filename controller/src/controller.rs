@@ -14,11 +14,22 @@
 
 //! Controller for wallet.. instantiates and handles listeners (or single-run
 //! invocations) as needed.
-use crate::api::{self, ApiServer, BasicAuthMiddleware, ResponseFuture, Router, TLSConfig};
+use crate::api::{
+	self, ApiServer, BasicAuthMiddleware, HandlerObj, ResponseFuture, Router, TLSConfig,
+};
+use crate::config::{AuditLogConfig, GRIN_WALLET_DIR};
+use crate::daemon::{DaemonHandle, DAEMON_STATUS_PATH};
+use crate::impls::{
+	AuditLog, AuditOutcome, ReceiveChallenges, SlateExchangeChunk, SlateExchanges,
+	FOREIGN_API_KEY_HEADER, FOREIGN_API_KEY_PATH, RECEIVE_CHALLENGE_HEADER, RECEIVE_CHALLENGE_PATH,
+	RECEIVE_NONCE_HEADER, SLATE_EXCHANGE_PATH,
+};
+use crate::journal::RequestJournal;
 use crate::keychain::Keychain;
+use crate::libwallet::api_impl::foreign;
 use crate::libwallet::{
-	Error, ErrorKind, NodeClient, NodeVersionInfo, Slate, WalletInst, WalletLCProvider,
-	CURRENT_SLATE_VERSION, GRIN_BLOCK_HEADER_VERSION,
+	check_json_depth, Error, ErrorKind, NodeClient, NodeVersionInfo, Slate, WalletInst,
+	WalletLCProvider, CURRENT_SLATE_VERSION, GRIN_BLOCK_HEADER_VERSION, MAX_JSON_DEPTH,
 };
 use crate::util::secp::key::SecretKey;
 use crate::util::{from_hex, static_secp_instance, to_base64, Mutex};
@@ -27,15 +38,23 @@ use futures::future::{err, ok};
 use futures::{Future, Stream};
 use hyper::header::HeaderValue;
 use hyper::{Body, Request, Response, StatusCode};
+use ring::constant_time::verify_slices_are_equal;
 use serde::{Deserialize, Serialize};
 use serde_json;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex as StdMutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::timer::Timeout;
+use uuid::Uuid;
 
 use crate::apiwallet::{
-	EncryptedRequest, EncryptedResponse, EncryptionErrorResponse, Foreign,
-	ForeignCheckMiddlewareFn, ForeignRpc, Owner, OwnerRpc, OwnerRpcS,
+	response_mac_hex, CoinbaseCache, EncryptedRequest, EncryptedResponse, EncryptionErrorResponse,
+	Foreign, ForeignCheckMiddleware, ForeignCheckMiddlewareFn, ForeignRpc, MinerRpc, Owner,
+	OwnerRpc, OwnerRpcS, RESPONSE_MAC_HEADER,
 };
 use easy_jsonrpc_mw;
 use easy_jsonrpc_mw::{Handler, MaybeReply};
@@ -43,8 +62,13 @@ use easy_jsonrpc_mw::{Handler, MaybeReply};
 lazy_static! {
 	pub static ref GRIN_OWNER_BASIC_REALM: HeaderValue =
 		HeaderValue::from_str("Basic realm=GrinOwnerAPI").unwrap();
+	pub static ref GRIN_MINER_BASIC_REALM: HeaderValue =
+		HeaderValue::from_str("Basic realm=GrinMinerAPI").unwrap();
 }
 
+/// Number of recently built coinbases the miner API keeps to answer repeated requests
+const MINER_COINBASE_CACHE_SIZE: usize = 16;
+
 fn check_middleware(
 	name: ForeignCheckMiddlewareFn,
 	node_version_info: Option<NodeVersionInfo>,
@@ -75,6 +99,21 @@ fn check_middleware(
 	}
 }
 
+// Foreign API middleware while the miner API is running: coinbases are only
+// served to authenticated miners
+fn check_middleware_miner_mode(
+	name: ForeignCheckMiddlewareFn,
+	node_version_info: Option<NodeVersionInfo>,
+	slate: Option<&Slate>,
+) -> Result<(), Error> {
+	match name {
+		ForeignCheckMiddlewareFn::BuildCoinbase => Err(ErrorKind::MinerApi(
+			"build_coinbase is only available on the authenticated miner API".into(),
+		))?,
+		_ => check_middleware(name, node_version_info, slate),
+	}
+}
+
 /// Instantiate wallet Owner API for a single-use (command line) call
 /// Return a function containing a loaded API context to call
 pub fn owner_single_use<'a, L, F, C, K>(
@@ -117,6 +156,9 @@ where
 /// port and wrapping the calls
 /// Note keychain mask is only provided here in case the foreign listener is also being used
 /// in the same wallet instance
+/// If `daemon` is provided, the status of the daemon is served on `/v2/daemon/status`
+/// Requests are recorded in the audit log of the listener, mirrored to the syslog
+/// server of `audit_log` if given
 pub fn owner_listener<L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
 	keychain_mask: Arc<Mutex<Option<SecretKey>>>,
@@ -124,6 +166,12 @@ pub fn owner_listener<L, C, K>(
 	api_secret: Option<String>,
 	tls_config: Option<TLSConfig>,
 	owner_api_include_foreign: Option<bool>,
+	owner_api_response_mac: Option<bool>,
+	owner_api_lock_after_mins: Option<u64>,
+	limits: ListenerLimits,
+	audit_log: Option<AuditLogConfig>,
+	shutdown: ListenerShutdown,
+	daemon: Option<DaemonHandle>,
 ) -> Result<(), Error>
 where
 	L: WalletLCProvider<'static, C, K> + 'static,
@@ -131,6 +179,8 @@ where
 	K: Keychain + 'static,
 {
 	let mut router = Router::new();
+	router.add_middleware(Arc::new(ShutdownMiddleware::new(shutdown.clone())));
+	router.add_middleware(Arc::new(ListenerLimitsMiddleware::new(limits)));
 	if api_secret.is_some() {
 		let api_basic_auth =
 			"Basic ".to_string() + &to_base64(&("grin:".to_string() + &api_secret.unwrap()));
@@ -145,9 +195,40 @@ where
 		running_foreign = true;
 	}
 
-	let api_handler_v2 = OwnerAPIHandlerV2::new(wallet.clone());
-	let api_handler_v3 =
-		OwnerAPIHandlerV3::new(wallet.clone(), keychain_mask.clone(), running_foreign);
+	let audit_log = AuditLog::new(audit_log)
+		.map_err(|e| ErrorKind::GenericError(format!("Error initializing the audit log: {}", e)))?;
+	let audit_log = Arc::new(audit_log);
+
+	// Mutating requests are journaled, recover the ones a previous run left incomplete
+	let journal = Arc::new(open_request_journal(&wallet)?);
+	recover_requests(&journal, wallet.clone(), keychain_mask.lock().clone());
+
+	// If so configured, the wallet is locked after a while without requests
+	let idle_lock = match owner_api_lock_after_mins {
+		Some(m) if m > 0 => {
+			let lock = IdleLock::new(Duration::from_secs(m * 60));
+			lock.watch(wallet.clone(), keychain_mask.clone(), shutdown.clone())?;
+			Some(lock)
+		}
+		_ => None,
+	};
+
+	let api_handler_v2 = OwnerAPIHandlerV2::new(
+		wallet.clone(),
+		journal.clone(),
+		audit_log.clone(),
+		shutdown.clone(),
+	);
+	let api_handler_v3 = OwnerAPIHandlerV3::new(
+		wallet.clone(),
+		keychain_mask.clone(),
+		running_foreign,
+		journal,
+		audit_log,
+		owner_api_response_mac.unwrap_or(false),
+		shutdown.clone(),
+		idle_lock,
+	);
 
 	router
 		.add_route("/v2/owner", Arc::new(api_handler_v2))
@@ -157,10 +238,18 @@ where
 		.add_route("/v3/owner", Arc::new(api_handler_v3))
 		.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
 
+	if let Some(daemon) = daemon {
+		router
+			.add_route(DAEMON_STATUS_PATH, Arc::new(DaemonStatusHandler { daemon }))
+			.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
+	}
+
 	// If so configured, add the foreign API to the same port
 	if running_foreign {
 		warn!("Starting HTTP Foreign API on Owner server at {}.", addr);
-		let foreign_api_handler_v2 = ForeignAPIHandlerV2::new(wallet, keychain_mask);
+		// The owner port has its own basic auth, so coinbases stay available here
+		let foreign_api_handler_v2 =
+			ForeignAPIHandlerV2::new(wallet.clone(), keychain_mask, false, None);
 		router
 			.add_route("/v2/foreign", Arc::new(foreign_api_handler_v2))
 			.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
@@ -169,37 +258,678 @@ where
 	let mut apis = ApiServer::new();
 	warn!("Starting HTTP Owner API server at {}.", addr);
 	let socket_addr: SocketAddr = addr.parse().expect("unable to parse socket address");
-	let api_thread =
-		apis.start(socket_addr, router, tls_config)
-			.context(ErrorKind::GenericError(
-				"API thread failed to start".to_string(),
-			))?;
+	apis.start(socket_addr, router, tls_config)
+		.context(ErrorKind::GenericError(
+			"API thread failed to start".to_string(),
+		))?;
 	warn!("HTTP Owner listener started.");
-	api_thread
-		.join()
-		.map_err(|e| ErrorKind::GenericError(format!("API thread panicked :{:?}", e)).into())
+	shutdown.wait();
+
+	warn!("Shutting down HTTP Owner API server, waiting for requests in flight.");
+	if !shutdown.drain(SHUTDOWN_DRAIN_TIMEOUT) {
+		warn!("Requests still in flight, those journaled will be recovered on the next start.");
+	}
+	// Closing the wallet closes its database, once the last request is done with it
+	match wallet.try_lock_for(SHUTDOWN_DRAIN_TIMEOUT) {
+		Some(mut w_lock) => {
+			if let Err(e) = w_lock.lc_provider().and_then(|lc| lc.close_wallet(None)) {
+				error!("Unable to close the wallet: {}", e);
+			}
+		}
+		None => error!("Wallet still in use, exiting without closing it."),
+	}
+	apis.stop();
+	warn!("HTTP Owner listener stopped.");
+	Ok(())
+}
+
+/// Open the owner API request journal in the wallet data directory
+fn open_request_journal<L, C, K>(
+	wallet: &Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+) -> Result<RequestJournal, Error>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	let top_level_dir = {
+		let mut w_lock = wallet.lock();
+		w_lock.lc_provider()?.get_top_level_directory()?
+	};
+	let data_dir = Path::new(&top_level_dir).join(GRIN_WALLET_DIR);
+	RequestJournal::new(&data_dir.to_string_lossy())
+}
+
+/// Recover the requests left incomplete in the journal, if the wallet is open.
+/// Otherwise they're recovered once the wallet is opened through the V3 API.
+fn recover_requests<L, C, K>(
+	journal: &RequestJournal,
+	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	keychain_mask: Option<SecretKey>,
+) where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	let is_open = {
+		let mut w_lock = wallet.lock();
+		match w_lock.lc_provider() {
+			Ok(lc) => lc.wallet_inst().is_ok(),
+			Err(_) => false,
+		}
+	};
+	if !is_open {
+		return;
+	}
+	let api = Owner::new(wallet);
+	if let Err(e) = journal.recover(&api, keychain_mask.as_ref()) {
+		error!("Unable to recover the owner API journal: {}", e);
+	}
+}
+
+/// Run an owner API request, journaling it while it runs if it changes the wallet
+fn handle_journaled<F>(
+	journal: &RequestJournal,
+	audit_log: &AuditLog,
+	val: &serde_json::Value,
+	has_token: bool,
+	handle: F,
+) -> Result<MaybeReply, Error>
+where
+	F: FnOnce(serde_json::Value) -> MaybeReply,
+{
+	let mut req = val.clone();
+	let entry = journal.begin(&mut req, has_token)?;
+	let reply = handle(req);
+	if let Some(e) = entry {
+		if let Err(e) = journal.complete(&e) {
+			error!("Unable to complete owner API journal entry: {}", e);
+		}
+	}
+	audit_request(audit_log, val, has_token, &reply);
+	Ok(reply)
+}
+
+/// Record a request in the owner API audit log, failed if its reply holds an
+/// error or an `Err` result
+fn audit_request(
+	audit_log: &AuditLog,
+	val: &serde_json::Value,
+	has_token: bool,
+	reply: &MaybeReply,
+) {
+	let method = match val["method"].as_str() {
+		Some(m) => m,
+		None => return,
+	};
+	let failed = match reply {
+		MaybeReply::Reply(r) => !r["error"].is_null() || !r["result"]["Err"].is_null(),
+		MaybeReply::DontReply => false,
+	};
+	let outcome = match failed {
+		true => AuditOutcome::Failure,
+		false => AuditOutcome::Success,
+	};
+	let api = match has_token {
+		true => "v3",
+		false => "v2",
+	};
+	audit_log.record(method, api, outcome, &val["params"]);
+}
+
+/// Owner API methods still served while the wallet is frozen, none of them
+/// changes the wallet
+const FROZEN_WALLET_METHODS: &[&str] = &[
+	"accounts",
+	"get_next_child_index",
+	"retrieve_outputs",
+	"retrieve_txs",
+	"retrieve_outputs_since",
+	"retrieve_txs_since",
+	"retrieve_summary_info",
+	"retrieve_coinbase_report",
+	"update_wallet_state",
+	"wallet_fingerprint",
+	"retrieve_source_filter",
+	"retrieve_failed_receives",
+	"get_slate_history",
+	"retrieve_change_journal",
+	"retrieve_pending_dispatches",
+	"retrieve_onetime_addresses",
+	"retrieve_foreign_api_keys",
+	"retrieve_approval_tokens",
+	"audit_view_only_bundle",
+	"watch_only_key",
+	"check_recipient",
+	"lock_wallet",
+	"retrieve_vault_settings",
+	"get_stored_tx",
+	"payment_proof_address",
+	"retrieve_payment_proof",
+	"verify_payment_proof",
+	"retrieve_tx_attachments",
+	"verify_slate_messages",
+	"describe_slate",
+	"node_height",
+	"retrieve_send_metrics",
+	"retrieve_operation_status",
+	"export_txs",
+	"init_secure_api",
+	"get_top_level_directory",
+	"set_log_level",
+	"open_wallet",
+	"close_wallet",
+	"freeze_wallet",
+	"unfreeze_wallet",
+	"retrieve_freeze_state",
+	"totp_enabled",
+	"ping",
+];
+
+/// Error reply to a request changing the wallet while it's frozen, `None` if the
+/// request can run
+fn frozen_reply<L, C, K>(
+	api: &Owner<'static, L, C, K>,
+	val: &serde_json::Value,
+) -> Option<serde_json::Value>
+where
+	L: WalletLCProvider<'static, C, K>,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	let calls: Vec<&serde_json::Value> = match val {
+		serde_json::Value::Array(a) => a.iter().collect(),
+		v => vec![v],
+	};
+	let read_only = calls.iter().all(|c| match c["method"].as_str() {
+		Some(m) => FROZEN_WALLET_METHODS.contains(&m),
+		None => false,
+	});
+	if read_only {
+		return None;
+	}
+	let state = {
+		let mut w_lock = api.wallet_inst.lock();
+		let w = w_lock.lc_provider().ok()?.wallet_inst().ok()?;
+		w.freeze_state().ok()??
+	};
+	let e = ErrorKind::WalletFrozen(state.since.to_rfc3339());
+	warn!("Refusing owner API request: {}", e);
+	Some(serde_json::json!({
+		"jsonrpc": "2.0",
+		"id": val["id"],
+		"error": {"code": -32000, "message": e.localized(api.locale())},
+	}))
+}
+
+/// Code of the second factor a request is made with, given in its `totp` member,
+/// once for a whole batch
+fn totp_code(val: &serde_json::Value) -> Option<String> {
+	let calls: Vec<&serde_json::Value> = match val {
+		serde_json::Value::Array(a) => a.iter().collect(),
+		v => vec![v],
+	};
+	calls
+		.iter()
+		.filter_map(|c| c["totp"].as_str())
+		.next()
+		.map(|c| c.to_owned())
+}
+
+/// Settings for the miner API served alongside the foreign API
+#[derive(Clone, Debug)]
+pub struct MinerApiConfig {
+	/// Secret miners authenticate with (basic auth, username `miner`)
+	pub api_secret: String,
+	/// Maximum number of requests accepted per minute
+	pub max_requests_per_minute: u32,
+}
+
+/// Limits on the requests accepted by the HTTP listeners, so a peer can't exhaust
+/// the wallet by posting oversized bodies or holding requests open
+#[derive(Clone, Debug)]
+pub struct ListenerLimits {
+	/// Largest request body accepted, in bytes
+	pub max_body_size: usize,
+	/// Time a client is given to send the request body
+	pub read_timeout: Duration,
+	/// Maximum number of requests handled at the same time
+	pub max_concurrent_requests: usize,
+}
+
+impl Default for ListenerLimits {
+	fn default() -> ListenerLimits {
+		ListenerLimits {
+			max_body_size: 1_048_576,
+			read_timeout: Duration::from_secs(30),
+			max_concurrent_requests: 32,
+		}
+	}
+}
+
+/// Middleware enforcing the listener limits. Request bodies are read here, so the
+/// handlers only ever see complete requests within the size limit.
+pub struct ListenerLimitsMiddleware {
+	limits: ListenerLimits,
+	in_flight: Arc<AtomicUsize>,
+}
+
+impl ListenerLimitsMiddleware {
+	/// Create a new middleware enforcing the given limits
+	pub fn new(limits: ListenerLimits) -> ListenerLimitsMiddleware {
+		ListenerLimitsMiddleware {
+			limits,
+			in_flight: Arc::new(AtomicUsize::new(0)),
+		}
+	}
+}
+
+/// Slot of a request being handled, released when the request completes or is dropped
+struct RequestSlot(Arc<AtomicUsize>);
+
+impl Drop for RequestSlot {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::SeqCst);
+	}
+}
+
+impl api::Handler for ListenerLimitsMiddleware {
+	fn call(
+		&self,
+		req: Request<Body>,
+		mut handlers: Box<dyn Iterator<Item = HandlerObj>>,
+	) -> ResponseFuture {
+		let next_handler = match handlers.next() {
+			Some(h) => h,
+			None => {
+				return Box::new(ok(response(
+					StatusCode::INTERNAL_SERVER_ERROR,
+					"no handler found",
+				)));
+			}
+		};
+		let slot = RequestSlot(self.in_flight.clone());
+		if self.in_flight.fetch_add(1, Ordering::SeqCst) >= self.limits.max_concurrent_requests {
+			warn!("Too many concurrent API requests, refusing request");
+			return Box::new(ok(response(StatusCode::SERVICE_UNAVAILABLE, "")));
+		}
+
+		let max_body_size = self.limits.max_body_size;
+		let content_length = req
+			.headers()
+			.get(hyper::header::CONTENT_LENGTH)
+			.and_then(|v| v.to_str().ok())
+			.and_then(|v| v.parse::<usize>().ok());
+		if content_length.map(|l| l > max_body_size).unwrap_or(false) {
+			return Box::new(ok(response(StatusCode::PAYLOAD_TOO_LARGE, "")));
+		}
+
+		// Bodies sent without a length are cut off as soon as they exceed the limit
+		let (parts, body) = req.into_parts();
+		let read_body =
+			body.map_err(|_| StatusCode::BAD_REQUEST)
+				.fold(vec![], move |mut acc, chunk| {
+					if acc.len() + chunk.len() > max_body_size {
+						return Err(StatusCode::PAYLOAD_TOO_LARGE);
+					}
+					acc.extend_from_slice(&chunk);
+					Ok(acc)
+				});
+		// The iterator isn't Send, the remaining handlers are collected to be called later
+		let handlers: Vec<HandlerObj> = handlers.collect();
+		Box::new(
+			Timeout::new(read_body, self.limits.read_timeout)
+				.then(move |res| -> ResponseFuture {
+					match res {
+						Ok(body) => next_handler.call(
+							Request::from_parts(parts, Body::from(body)),
+							Box::new(handlers.into_iter()),
+						),
+						Err(e) => {
+							let status = if e.is_elapsed() {
+								StatusCode::REQUEST_TIMEOUT
+							} else {
+								e.into_inner().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+							};
+							Box::new(ok(response(status, "")))
+						}
+					}
+				})
+				.then(move |res| {
+					drop(slot);
+					res
+				}),
+		)
+	}
+}
+
+/// Longest wait for the requests in flight, and then for the wallet, when the
+/// owner API listener shuts down
+const SHUTDOWN_DRAIN_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Handle to shut the owner API listener down gracefully, from a signal handler
+/// or the `shutdown` method of the API. New requests are then refused, those in
+/// flight are given time to complete and the wallet is closed before the listener
+/// returns. Mutating requests are journaled, so any that don't complete in time
+/// are recovered on the next start.
+#[derive(Clone)]
+pub struct ListenerShutdown {
+	inner: Arc<(StdMutex<ShutdownState>, Condvar)>,
+}
+
+/// Whether a shutdown was requested, and the number of requests in flight.
+/// Waiters on the condition variable are notified of any change
+#[derive(Default)]
+struct ShutdownState {
+	requested: bool,
+	in_flight: usize,
+}
+
+impl ListenerShutdown {
+	/// Create a new handle, no shutdown being requested
+	pub fn new() -> ListenerShutdown {
+		ListenerShutdown {
+			inner: Arc::new((StdMutex::new(ShutdownState::default()), Condvar::new())),
+		}
+	}
+
+	/// The state is only ever updated in whole, so a panic while it's locked
+	/// can't leave it inconsistent
+	fn lock_state(&self) -> MutexGuard<'_, ShutdownState> {
+		self.inner.0.lock().unwrap_or_else(|e| e.into_inner())
+	}
+
+	/// Request the listener to shut down
+	pub fn request(&self) {
+		self.lock_state().requested = true;
+		self.inner.1.notify_all();
+	}
+
+	/// Whether the listener was requested to shut down
+	pub fn is_requested(&self) -> bool {
+		self.lock_state().requested
+	}
+
+	/// Block until the listener is requested to shut down
+	pub fn wait(&self) {
+		let mut state = self.lock_state();
+		while !state.requested {
+			state = self.inner.1.wait(state).unwrap_or_else(|e| e.into_inner());
+		}
+	}
+
+	/// Block until the listener is requested to shut down, or the timeout
+	/// elapses, returning whether it was requested to
+	pub fn wait_timeout(&self, timeout: Duration) -> bool {
+		let deadline = Instant::now() + timeout;
+		let mut state = self.lock_state();
+		while !state.requested {
+			let now = Instant::now();
+			if now >= deadline {
+				return false;
+			}
+			state = match self.inner.1.wait_timeout(state, deadline - now) {
+				Ok((s, _)) => s,
+				Err(e) => e.into_inner().0,
+			};
+		}
+		true
+	}
+
+	/// Count a request as in flight until the returned slot is dropped, unless
+	/// a shutdown was requested
+	fn enter(&self) -> Option<ShutdownSlot> {
+		let mut state = self.lock_state();
+		if state.requested {
+			return None;
+		}
+		state.in_flight += 1;
+		Some(ShutdownSlot(self.clone()))
+	}
+
+	/// Wait for the requests in flight to complete, returning whether they
+	/// all did within the timeout
+	fn drain(&self, timeout: Duration) -> bool {
+		let deadline = Instant::now() + timeout;
+		let mut state = self.lock_state();
+		while state.in_flight > 0 {
+			let now = Instant::now();
+			if now >= deadline {
+				return false;
+			}
+			state = match self.inner.1.wait_timeout(state, deadline - now) {
+				Ok((s, _)) => s,
+				Err(e) => e.into_inner().0,
+			};
+		}
+		true
+	}
+}
+
+/// A request in flight, until dropped
+struct ShutdownSlot(ListenerShutdown);
+
+impl Drop for ShutdownSlot {
+	fn drop(&mut self) {
+		self.0.lock_state().in_flight -= 1;
+		(self.0).inner.1.notify_all();
+	}
+}
+
+/// Lock of the wallet after a period without owner API V3 requests, for kiosk
+/// and shared machine deployments. The wallet is closed, dropping its seed and
+/// keychain mask, so it has to be opened again before the next requests.
+#[derive(Clone)]
+pub struct IdleLock {
+	timeout: Duration,
+	last_request: Arc<Mutex<Instant>>,
+}
+
+impl IdleLock {
+	/// Create a new lock, locking the wallet after the given time without requests
+	pub fn new(timeout: Duration) -> IdleLock {
+		IdleLock {
+			timeout,
+			last_request: Arc::new(Mutex::new(Instant::now())),
+		}
+	}
+
+	/// Record a request, restarting the timer
+	pub fn touch(&self) {
+		*self.last_request.lock() = Instant::now();
+	}
+
+	/// Whether no request was made for the whole timeout
+	pub fn is_idle(&self) -> bool {
+		self.last_request.lock().elapsed() >= self.timeout
+	}
+
+	/// Lock the wallet whenever it's left idle, until the listener shuts down
+	fn watch<L, C, K>(
+		&self,
+		wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+		keychain_mask: Arc<Mutex<Option<SecretKey>>>,
+		shutdown: ListenerShutdown,
+	) -> Result<(), Error>
+	where
+		L: WalletLCProvider<'static, C, K> + 'static,
+		C: NodeClient + 'static,
+		K: Keychain + 'static,
+	{
+		let lock = self.clone();
+		thread::Builder::new()
+			.name("owner-idle-lock".to_owned())
+			.spawn(move || {
+				while !shutdown.wait_timeout(Duration::from_secs(1)) {
+					if !lock.is_idle() {
+						continue;
+					}
+					let mut w_lock = wallet.lock();
+					let lc = match w_lock.lc_provider() {
+						Ok(lc) => lc,
+						Err(_) => continue,
+					};
+					if lc.wallet_inst().is_err() {
+						continue;
+					}
+					warn!(
+						"No owner API request for {:?}, locking the wallet.",
+						lock.timeout
+					);
+					if let Err(e) = lc.close_wallet(None) {
+						error!("Unable to lock the wallet: {}", e);
+					}
+					*keychain_mask.lock() = None;
+				}
+			})
+			.map_err(|e| ErrorKind::GenericError(format!("Unable to start idle lock: {}", e)))?;
+		Ok(())
+	}
+}
+
+/// Middleware refusing requests once a shutdown is requested, and keeping
+/// count of those in flight
+pub struct ShutdownMiddleware {
+	shutdown: ListenerShutdown,
+}
+
+impl ShutdownMiddleware {
+	/// Create a new middleware for the given shutdown handle
+	pub fn new(shutdown: ListenerShutdown) -> ShutdownMiddleware {
+		ShutdownMiddleware { shutdown }
+	}
+}
+
+impl api::Handler for ShutdownMiddleware {
+	fn call(
+		&self,
+		req: Request<Body>,
+		mut handlers: Box<dyn Iterator<Item = HandlerObj>>,
+	) -> ResponseFuture {
+		let slot = match self.shutdown.enter() {
+			Some(s) => s,
+			None => {
+				return Box::new(ok(response(
+					StatusCode::SERVICE_UNAVAILABLE,
+					"shutting down",
+				)));
+			}
+		};
+		let next_handler = match handlers.next() {
+			Some(h) => h,
+			None => {
+				return Box::new(ok(response(
+					StatusCode::INTERNAL_SERVER_ERROR,
+					"no handler found",
+				)));
+			}
+		};
+		Box::new(next_handler.call(req, handlers).then(move |res| {
+			drop(slot);
+			res
+		}))
+	}
+}
+
+/// What the `shutdown` method of the owner API does when served by a listener
+fn shutdown_hook(shutdown: &ListenerShutdown) -> Arc<dyn Fn() + Send + Sync> {
+	let shutdown = shutdown.clone();
+	Arc::new(move || {
+		warn!("Owner API shutdown requested");
+		shutdown.request();
+	})
 }
 
 /// Listener version, providing same API but listening for requests on a
 /// port and wrapping the calls
+/// If `miner_api` is provided, coinbases are served to stratum servers on `/v2/miner`
+/// and refused on `/v2/foreign`
+/// If `receive_challenges` is provided, senders must solve a challenge issued on
+/// `/v2/receive_challenge` before a slate is received
+/// `roles` sets which of `receive_tx` and `finalize_invoice_tx` are served, on
+/// `/v2/foreign` and on their own routes `/v2/foreign/receive` and `/v2/foreign/invoice`
+/// Slates sent with a foreign API key, in a header or on `/v2/foreign/key/<key>`, are
+/// received into the account of the key
 pub fn foreign_listener<L, C, K>(
 	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
 	keychain_mask: Arc<Mutex<Option<SecretKey>>>,
 	addr: &str,
 	tls_config: Option<TLSConfig>,
+	miner_api: Option<MinerApiConfig>,
+	limits: ListenerLimits,
+	receive_challenges: Option<ReceiveChallenges>,
+	roles: ForeignApiRoles,
 ) -> Result<(), Error>
 where
 	L: WalletLCProvider<'static, C, K> + 'static,
 	C: NodeClient + 'static,
 	K: Keychain + 'static,
 {
-	let api_handler_v2 = ForeignAPIHandlerV2::new(wallet, keychain_mask);
+	let receive_challenges = receive_challenges.map(|c| Arc::new(c));
+	let api_handler_v2 = ForeignAPIHandlerV2::new(
+		wallet.clone(),
+		keychain_mask.clone(),
+		miner_api.is_some(),
+		receive_challenges.clone(),
+		roles,
+	);
 
 	let mut router = Router::new();
+	router.add_middleware(Arc::new(ListenerLimitsMiddleware::new(limits)));
 
 	router
-		.add_route("/v2/foreign", Arc::new(api_handler_v2))
+		.add_route("/v2/foreign", Arc::new(api_handler_v2.clone()))
 		.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
+	router
+		.add_route(
+			&format!("{}/*", FOREIGN_API_KEY_PATH),
+			Arc::new(api_handler_v2.clone()),
+		)
+		.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
+	if roles.receive_tx {
+		let receive_handler = ForeignAPIHandlerV2 {
+			roles: ForeignApiRoles::receive_only(),
+			..api_handler_v2.clone()
+		};
+		let slate_exchange_handler = SlateExchangeHandler::new(receive_handler.clone());
+		router
+			.add_route("/v2/foreign/receive", Arc::new(receive_handler))
+			.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
+		router
+			.add_route(SLATE_EXCHANGE_PATH, Arc::new(slate_exchange_handler))
+			.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
+	} else {
+		warn!("Slates sent to the wallet are not received on the Foreign API.");
+	}
+	if roles.finalize_invoice_tx {
+		let invoice_handler = ForeignAPIHandlerV2 {
+			roles: ForeignApiRoles::invoice_only(),
+			..api_handler_v2.clone()
+		};
+		router
+			.add_route("/v2/foreign/invoice", Arc::new(invoice_handler))
+			.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
+	} else {
+		warn!("Invoices are not finalized on the Foreign API.");
+	}
+
+	if let Some(challenges) = receive_challenges {
+		warn!("Slates are only received with a solved challenge.");
+		router
+			.add_route(
+				RECEIVE_CHALLENGE_PATH,
+				Arc::new(ReceiveChallengeHandler { challenges }),
+			)
+			.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
+	}
+
+	if let Some(c) = miner_api {
+		warn!("Starting HTTP Miner API on Foreign server at {}.", addr);
+		let miner_api_handler_v2 = MinerAPIHandlerV2::new(wallet, keychain_mask, &c);
+		router
+			.add_route("/v2/miner", Arc::new(miner_api_handler_v2))
+			.map_err(|_| ErrorKind::GenericError("Router failed to add route".to_string()))?;
+	}
 
 	let mut apis = ApiServer::new();
 	warn!("Starting HTTP Foreign listener API server at {}.", addr);
@@ -227,6 +957,12 @@ where
 {
 	/// Wallet instance
 	pub wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	/// Journal of the mutating requests
+	pub journal: Arc<RequestJournal>,
+	/// Audit log of the requests
+	pub audit_log: Arc<AuditLog>,
+	/// Shutdown handle of the listener
+	pub shutdown: ListenerShutdown,
 }
 
 impl<L, C, K> OwnerAPIHandlerV2<L, C, K>
@@ -238,8 +974,16 @@ where
 	/// Create a new owner API handler for GET methods
 	pub fn new(
 		wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+		journal: Arc<RequestJournal>,
+		audit_log: Arc<AuditLog>,
+		shutdown: ListenerShutdown,
 	) -> OwnerAPIHandlerV2<L, C, K> {
-		OwnerAPIHandlerV2 { wallet }
+		OwnerAPIHandlerV2 {
+			wallet,
+			journal,
+			audit_log,
+			shutdown,
+		}
 	}
 
 	fn call_api(
@@ -247,9 +991,22 @@ where
 		req: Request<Body>,
 		api: Owner<'static, L, C, K>,
 	) -> Box<dyn Future<Item = serde_json::Value, Error = Error> + Send> {
+		let journal = self.journal.clone();
+		let audit_log = self.audit_log.clone();
 		Box::new(parse_body(req).and_then(move |val: serde_json::Value| {
+			let mut api = api;
+			api.set_totp_code(totp_code(&val));
 			let owner_api = &api as &dyn OwnerRpc;
-			match owner_api.handle_request(val) {
+			if let Some(r) = frozen_reply(&api, &val) {
+				return ok(r);
+			}
+			let reply = match handle_journaled(&journal, &audit_log, &val, false, |req| {
+				owner_api.handle_request(req)
+			}) {
+				Ok(r) => r,
+				Err(e) => return err(e),
+			};
+			match reply {
 				MaybeReply::Reply(r) => ok(r),
 				MaybeReply::DontReply => {
 					// Since it's http, we need to return something. We return [] because jsonrpc
@@ -261,7 +1018,8 @@ where
 	}
 
 	fn handle_post_request(&self, req: Request<Body>) -> WalletResponseFuture {
-		let api = Owner::new(self.wallet.clone());
+		let mut api = Owner::new(self.wallet.clone());
+		api.shutdown_hook = Some(shutdown_hook(&self.shutdown));
 		Box::new(
 			self.call_api(req, api)
 				.and_then(|resp| ok(json_response_pretty(&resp))),
@@ -311,6 +1069,22 @@ where
 	/// Whether we're running the foreign API on the same port, and therefore
 	/// have to store the mask in-process
 	pub running_foreign: bool,
+
+	/// Journal of the mutating requests
+	pub journal: Arc<RequestJournal>,
+
+	/// Audit log of the requests
+	pub audit_log: Arc<AuditLog>,
+
+	/// Whether responses carry a MAC made with the shared key, once the secure
+	/// API is initialized
+	pub response_mac: bool,
+
+	/// Shutdown handle of the listener
+	pub shutdown: ListenerShutdown,
+
+	/// Lock of the wallet once idle, if configured
+	pub idle_lock: Option<IdleLock>,
 }
 
 pub struct OwnerV3Helpers;
@@ -340,6 +1114,18 @@ impl OwnerV3Helpers {
 		}
 	}
 
+	/// Checks whether a request is to lock the wallet
+	pub fn is_lock_wallet(val: &serde_json::Value) -> bool {
+		if let Some(m) = val["method"].as_str() {
+			match m {
+				"lock_wallet" => true,
+				_ => false,
+			}
+		} else {
+			false
+		}
+	}
+
 	/// Checks whether a request is an encrypted request
 	pub fn is_encrypted_request(val: &serde_json::Value) -> bool {
 		if let Some(m) = val["method"].as_str() {
@@ -386,20 +1172,18 @@ impl OwnerV3Helpers {
 		}
 	}
 
+	/// Keychain mask returned by a successful open_wallet call
+	pub fn response_mask(val: &serde_json::Value) -> Option<SecretKey> {
+		let key = val["result"]["Ok"].as_str()?;
+		let key_bytes = from_hex(key.to_owned()).ok()?;
+		let secp_inst = static_secp_instance();
+		let secp = secp_inst.lock();
+		SecretKey::from_slice(&secp, &key_bytes).ok()
+	}
+
 	/// Update the shared mask, in case of foreign API being run
 	pub fn update_mask(mask: Arc<Mutex<Option<SecretKey>>>, val: &serde_json::Value) {
-		if let Some(key) = val["result"]["Ok"].as_str() {
-			let key_bytes = match from_hex(key.to_owned()) {
-				Ok(k) => k,
-				Err(_) => return,
-			};
-			let secp_inst = static_secp_instance();
-			let secp = secp_inst.lock();
-			let sk = match SecretKey::from_slice(&secp, &key_bytes) {
-				Ok(s) => s,
-				Err(_) => return,
-			};
-
+		if let Some(sk) = OwnerV3Helpers::response_mask(val) {
 			let mut shared_mask_ref = mask.lock();
 			*shared_mask_ref = Some(sk);
 		}
@@ -451,11 +1235,23 @@ impl OwnerV3Helpers {
 		Ok(res)
 	}
 
-	/// convert an internal error (if exists) as proper JSON-RPC
-	pub fn check_error_response(val: &serde_json::Value) -> (bool, serde_json::Value) {
+	/// convert an internal error (if exists) as proper JSON-RPC, wallet errors
+	/// getting their message in `locale`, if any
+	pub fn check_error_response(
+		val: &serde_json::Value,
+		locale: Option<&str>,
+	) -> (bool, serde_json::Value) {
+		let localized = match locale {
+			Some(_) => serde_json::from_value::<ErrorKind>(val["result"]["Err"].clone())
+				.ok()
+				.map(|e| e.localized(locale)),
+			None => None,
+		};
 		// check for string first. This ensures that error messages
 		// that are just strings aren't given weird formatting
-		let err_string = if val["result"]["Err"].is_object() {
+		let err_string = if localized.is_some() {
+			localized
+		} else if val["result"]["Err"].is_object() {
 			let mut retval;
 			let hashed: Result<HashMap<String, String>, serde_json::Error> =
 				serde_json::from_value(val["result"]["Err"].clone());
@@ -531,12 +1327,22 @@ where
 		wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
 		keychain_mask: Arc<Mutex<Option<SecretKey>>>,
 		running_foreign: bool,
+		journal: Arc<RequestJournal>,
+		audit_log: Arc<AuditLog>,
+		response_mac: bool,
+		shutdown: ListenerShutdown,
+		idle_lock: Option<IdleLock>,
 	) -> OwnerAPIHandlerV3<L, C, K> {
 		OwnerAPIHandlerV3 {
 			wallet,
 			shared_key: Arc::new(Mutex::new(None)),
 			keychain_mask: keychain_mask,
 			running_foreign,
+			journal,
+			audit_log,
+			response_mac,
+			shutdown,
+			idle_lock,
 		}
 	}
 
@@ -544,23 +1350,30 @@ where
 		&self,
 		req: Request<Body>,
 		api: Owner<'static, L, C, K>,
-	) -> Box<dyn Future<Item = serde_json::Value, Error = Error> + Send> {
+	) -> Box<dyn Future<Item = (serde_json::Value, Option<String>), Error = Error> + Send> {
 		let key = self.shared_key.clone();
 		let mask = self.keychain_mask.clone();
 		let running_foreign = self.running_foreign;
+		let journal = self.journal.clone();
+		let audit_log = self.audit_log.clone();
+		if let Some(l) = self.idle_lock.as_ref() {
+			l.touch();
+		}
 		Box::new(parse_body(req).and_then(move |val: serde_json::Value| {
+			// nonce of an encrypted request, the response MAC covers it
+			let req_nonce = val["params"]["nonce"].as_str().map(|n| n.to_owned());
 			let mut val = val;
-			let owner_api_s = &api as &dyn OwnerRpcS;
+			let mut api = api;
 			let mut is_init_secure_api = OwnerV3Helpers::is_init_secure_api(&val);
 			let mut was_encrypted = false;
 			let mut encrypted_req_id = 0;
 			if !is_init_secure_api {
 				if let Err(v) = OwnerV3Helpers::check_encryption_started(key.clone()) {
-					return ok(v);
+					return ok((v, req_nonce));
 				}
 				let res = OwnerV3Helpers::decrypt_request(key.clone(), &val);
 				match res {
-					Err(e) => return ok(e),
+					Err(e) => return ok((e, req_nonce)),
 					Ok(v) => {
 						encrypted_req_id = v.0;
 						val = v.1;
@@ -572,12 +1385,32 @@ where
 			is_init_secure_api = OwnerV3Helpers::is_init_secure_api(&val);
 			// also need to intercept open/close wallet requests
 			let is_open_wallet = OwnerV3Helpers::is_open_wallet(&val);
-			match owner_api_s.handle_request(val) {
+			let is_lock_wallet = OwnerV3Helpers::is_lock_wallet(&val);
+			api.set_totp_code(totp_code(&val));
+			let owner_api_s = &api as &dyn OwnerRpcS;
+			let reply = match frozen_reply(&api, &val) {
+				Some(r) => MaybeReply::Reply(r),
+				None => match handle_journaled(&journal, &audit_log, &val, true, |req| {
+					owner_api_s.handle_request(req)
+				}) {
+					Ok(r) => r,
+					Err(e) => return err(e),
+				},
+			};
+			match reply {
 				MaybeReply::Reply(mut r) => {
-					let (_was_error, unencrypted_intercept) =
-						OwnerV3Helpers::check_error_response(&r.clone());
+					let (was_error, unencrypted_intercept) =
+						OwnerV3Helpers::check_error_response(&r.clone(), api.locale());
 					if is_open_wallet && running_foreign {
-						OwnerV3Helpers::update_mask(mask, &r.clone());
+						OwnerV3Helpers::update_mask(mask.clone(), &r.clone());
+					}
+					// the foreign API can't use the mask of a locked wallet anymore
+					if is_lock_wallet && !was_error {
+						*mask.lock() = None;
+					}
+					if is_open_wallet && !was_error {
+						let opened_mask = OwnerV3Helpers::response_mask(&r);
+						recover_requests(&journal, api.wallet_inst.clone(), opened_mask);
 					}
 					if was_encrypted {
 						let res = OwnerV3Helpers::encrypt_response(
@@ -587,7 +1420,7 @@ where
 						);
 						r = match res {
 							Ok(v) => v,
-							Err(v) => return ok(v),
+							Err(v) => return ok((v, req_nonce)),
 						}
 					}
 					// intercept init_secure_api response (after encryption,
@@ -599,23 +1432,41 @@ where
 							api.shared_key.lock().clone(),
 						);
 					}
-					ok(r)
+					ok((r, req_nonce))
 				}
 				MaybeReply::DontReply => {
 					// Since it's http, we need to return something. We return [] because jsonrpc
 					// clients will parse it as an empty batch response.
-					ok(serde_json::json!([]))
+					ok((serde_json::json!([]), req_nonce))
 				}
 			}
 		}))
 	}
 
 	fn handle_post_request(&self, req: Request<Body>) -> WalletResponseFuture {
-		let api = Owner::new(self.wallet.clone());
-		Box::new(
-			self.call_api(req, api)
-				.and_then(|resp| ok(json_response_pretty(&resp))),
-		)
+		let mut api = Owner::new(self.wallet.clone());
+		api.shutdown_hook = Some(shutdown_hook(&self.shutdown));
+		let key = self.shared_key.clone();
+		let response_mac = self.response_mac;
+		Box::new(self.call_api(req, api).and_then(move |(resp, req_nonce)| {
+			if !response_mac {
+				return ok(json_response_pretty(&resp));
+			}
+			let json = match serde_json::to_string_pretty(&resp) {
+				Ok(j) => j,
+				Err(_) => return ok(response(StatusCode::INTERNAL_SERVER_ERROR, "")),
+			};
+			// No MAC can be made before the secure API is initialized
+			let mac = key.lock().as_ref().map(|k| {
+				let nonce = req_nonce.unwrap_or_default();
+				response_mac_hex(k, &nonce, json.as_bytes())
+			});
+			let mut res = response(StatusCode::OK, json);
+			if let Some(m) = mac.and_then(|m| HeaderValue::from_str(&m).ok()) {
+				res.headers_mut().insert(RESPONSE_MAC_HEADER, m);
+			}
+			ok(res)
+		}))
 	}
 }
 
@@ -651,6 +1502,12 @@ where
 	pub wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
 	/// Keychain mask
 	pub keychain_mask: Arc<Mutex<Option<SecretKey>>>,
+	/// Whether coinbases are served by the miner API instead
+	pub miner_mode: bool,
+	/// Challenges senders must solve before a slate is received, if required
+	pub receive_challenges: Option<Arc<ReceiveChallenges>>,
+	/// Slate methods served by the handler
+	pub roles: ForeignApiRoles,
 }
 
 impl<L, C, K> ForeignAPIHandlerV2<L, C, K>
@@ -663,10 +1520,528 @@ where
 	pub fn new(
 		wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
 		keychain_mask: Arc<Mutex<Option<SecretKey>>>,
+		miner_mode: bool,
+		receive_challenges: Option<Arc<ReceiveChallenges>>,
+		roles: ForeignApiRoles,
 	) -> ForeignAPIHandlerV2<L, C, K> {
 		ForeignAPIHandlerV2 {
 			wallet,
 			keychain_mask,
+			miner_mode,
+			receive_challenges,
+			roles,
+		}
+	}
+
+	/// Run a foreign API request, refusing it if it calls a method the handler
+	/// doesn't serve, if a receive challenge is required and wasn't solved, or if
+	/// it was sent with an unknown foreign API key
+	fn handle_request(
+		&self,
+		val: serde_json::Value,
+		proof: Option<(String, u64)>,
+		api_key: Option<String>,
+	) -> serde_json::Value {
+		let val = match api_key {
+			Some(k) => match self.api_key_account(&k) {
+				Ok(account) => set_receive_account(val, &account),
+				Err(e) => {
+					warn!("Refusing foreign API request: {}", e);
+					return serde_json::json!({
+						"jsonrpc": "2.0",
+						"id": val["id"],
+						"error": {"code": -32000, "message": format!("{}", e)},
+					});
+				}
+			},
+			None => val,
+		};
+		if let Err(e) = check_roles(&self.roles, &val) {
+			warn!("Refusing foreign API request: {}", e);
+			return serde_json::json!({
+				"jsonrpc": "2.0",
+				"id": val["id"],
+				"error": {"code": -32601, "message": format!("{}", e)},
+			});
+		}
+		if let Some(c) = self.receive_challenges.as_ref() {
+			if let Err(e) = check_receive_proof(c, &val, proof) {
+				warn!("Refusing receive_tx request: {}", e);
+				return serde_json::json!({
+					"jsonrpc": "2.0",
+					"id": val["id"],
+					"error": {"code": -32000, "message": format!("{}", e)},
+				});
+			}
+		}
+		let mask = self.keychain_mask.lock();
+		let middleware: ForeignCheckMiddleware = if self.miner_mode {
+			check_middleware_miner_mode
+		} else {
+			check_middleware
+		};
+		let api = Foreign::new(self.wallet.clone(), mask.clone(), Some(middleware));
+		let foreign_api = &api as &dyn ForeignRpc;
+		match foreign_api.handle_request(val) {
+			MaybeReply::Reply(r) => r,
+			MaybeReply::DontReply => {
+				// Since it's http, we need to return something. We return [] because jsonrpc
+				// clients will parse it as an empty batch response.
+				serde_json::json!([])
+			}
+		}
+	}
+
+	/// Check the sender of a slate exchange may send the slate, before any of it is
+	/// taken: as for a receive_tx call, the foreign API key it's sent with must be
+	/// known, and a receive challenge solved for the slate if the listener requires it
+	fn check_exchange_sender(
+		&self,
+		slate_id: &Uuid,
+		proof: Option<(String, u64)>,
+		api_key: Option<&str>,
+	) -> Result<(), Error> {
+		if let Some(k) = api_key {
+			self.api_key_account(k)?;
+		}
+		match self.receive_challenges.as_ref() {
+			Some(c) => check_slate_proof(c, slate_id, proof),
+			None => Ok(()),
+		}
+	}
+
+	/// Account slates sent with the foreign API key are received into
+	fn api_key_account(&self, key: &str) -> Result<String, Error> {
+		let mut w_lock = self.wallet.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		foreign::foreign_api_key_account(&mut **w, key)
+	}
+
+	fn handle_post_request(&self, req: Request<Body>) -> WalletResponseFuture {
+		let handler = self.clone();
+		let proof = receive_proof(&req);
+		let api_key = foreign_api_key(&req);
+		Box::new(parse_body(req).and_then(move |val: serde_json::Value| {
+			ok(json_response_pretty(
+				&handler.handle_request(val, proof, api_key),
+			))
+		}))
+	}
+}
+
+/// Foreign API key a request was sent with, in its header or as the last
+/// segment of its path
+fn foreign_api_key(req: &Request<Body>) -> Option<String> {
+	if let Some(k) = req.headers().get(FOREIGN_API_KEY_HEADER) {
+		return k.to_str().ok().map(|k| k.to_owned());
+	}
+	let path = req.uri().path().trim_end_matches('/');
+	let prefix = format!("{}/", FOREIGN_API_KEY_PATH);
+	match path.starts_with(&prefix) {
+		true => Some(path[prefix.len()..].to_owned()),
+		false => None,
+	}
+}
+
+/// Make every receive_tx call of a request, batched or not, receive into the
+/// given account
+fn set_receive_account(mut val: serde_json::Value, account: &str) -> serde_json::Value {
+	let mut calls: Vec<&mut serde_json::Value> = match val {
+		serde_json::Value::Array(ref mut a) => a.iter_mut().collect(),
+		ref mut v => vec![v],
+	};
+	for call in calls {
+		if call["method"] != "receive_tx" {
+			continue;
+		}
+		match call["params"] {
+			serde_json::Value::Array(ref mut a) => {
+				while a.len() < 2 {
+					a.push(serde_json::Value::Null);
+				}
+				a[1] = serde_json::json!(account);
+			}
+			ref mut p => p["dest_acct_name"] = serde_json::json!(account),
+		}
+	}
+	val
+}
+
+impl<L, C, K> Clone for ForeignAPIHandlerV2<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	fn clone(&self) -> Self {
+		ForeignAPIHandlerV2 {
+			wallet: self.wallet.clone(),
+			keychain_mask: self.keychain_mask.clone(),
+			miner_mode: self.miner_mode,
+			receive_challenges: self.receive_challenges.clone(),
+			roles: self.roles,
+		}
+	}
+}
+
+impl<L, C, K> api::Handler for ForeignAPIHandlerV2<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	fn post(&self, req: Request<Body>) -> ResponseFuture {
+		Box::new(
+			self.handle_post_request(req)
+				.and_then(|r| ok(r))
+				.or_else(|e| {
+					error!("Request Error: {:?}", e);
+					ok(create_error_response(e))
+				}),
+		)
+	}
+
+	fn options(&self, _req: Request<Body>) -> ResponseFuture {
+		Box::new(ok(create_ok_response("{}")))
+	}
+}
+
+/// Slate methods of the foreign API served by a listener. Merchants accepting
+/// payments may not want to finalize invoices, and the other way around.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ForeignApiRoles {
+	/// Whether slates sent to the wallet are received, through `receive_tx`
+	pub receive_tx: bool,
+	/// Whether invoices paid to the wallet are finalized, through `finalize_invoice_tx`
+	pub finalize_invoice_tx: bool,
+}
+
+impl ForeignApiRoles {
+	/// Only receive slates
+	pub fn receive_only() -> ForeignApiRoles {
+		ForeignApiRoles {
+			receive_tx: true,
+			finalize_invoice_tx: false,
+		}
+	}
+
+	/// Only finalize invoices
+	pub fn invoice_only() -> ForeignApiRoles {
+		ForeignApiRoles {
+			receive_tx: false,
+			finalize_invoice_tx: true,
+		}
+	}
+}
+
+impl Default for ForeignApiRoles {
+	fn default() -> ForeignApiRoles {
+		ForeignApiRoles {
+			receive_tx: true,
+			finalize_invoice_tx: true,
+		}
+	}
+}
+
+/// Check that no call of a request, batched or not, is to a slate method the
+/// roles don't allow
+fn check_roles(roles: &ForeignApiRoles, val: &serde_json::Value) -> Result<(), Error> {
+	let calls: Vec<&serde_json::Value> = match val {
+		serde_json::Value::Array(a) => a.iter().collect(),
+		v => vec![v],
+	};
+	for call in calls {
+		let allowed = match call["method"].as_str() {
+			Some("receive_tx") => roles.receive_tx,
+			Some("finalize_invoice_tx") => roles.finalize_invoice_tx,
+			_ => true,
+		};
+		if !allowed {
+			let method = call["method"].as_str().unwrap_or_default().to_owned();
+			return Err(ErrorKind::MethodDisabled(method))?;
+		}
+	}
+	Ok(())
+}
+
+/// Challenge and nonce sent along a request, if any
+fn receive_proof(req: &Request<Body>) -> Option<(String, u64)> {
+	let header = |name: &str| {
+		req.headers()
+			.get(name)
+			.and_then(|v| v.to_str().ok())
+			.map(|v| v.to_owned())
+	};
+	let challenge = header(RECEIVE_CHALLENGE_HEADER)?;
+	let nonce = header(RECEIVE_NONCE_HEADER)?.parse().ok()?;
+	Some((challenge, nonce))
+}
+
+/// Check that every receive_tx call of a request, batched or not, comes with a
+/// challenge solved for its slate
+fn check_receive_proof(
+	challenges: &ReceiveChallenges,
+	val: &serde_json::Value,
+	proof: Option<(String, u64)>,
+) -> Result<(), Error> {
+	let calls: Vec<&serde_json::Value> = match val {
+		serde_json::Value::Array(a) => a.iter().collect(),
+		v => vec![v],
+	};
+	for call in calls {
+		if call["method"] != "receive_tx" {
+			continue;
+		}
+		let slate = match &call["params"] {
+			serde_json::Value::Array(a) => a.get(0).cloned().unwrap_or_default(),
+			p => p["slate"].clone(),
+		};
+		let slate_id = serde_json::from_value(slate["id"].clone())
+			.map_err(|_| ErrorKind::ReceiveChallenge("Missing slate id".to_owned()))?;
+		check_slate_proof(challenges, &slate_id, proof.clone())?;
+	}
+	Ok(())
+}
+
+/// Check the challenge and nonce sent along a request are solved for the slate
+fn check_slate_proof(
+	challenges: &ReceiveChallenges,
+	slate_id: &Uuid,
+	proof: Option<(String, u64)>,
+) -> Result<(), Error> {
+	let (challenge, nonce) = proof.ok_or_else(|| {
+		ErrorKind::ReceiveChallenge(format!(
+			"A challenge from {} must be solved before sending",
+			RECEIVE_CHALLENGE_PATH
+		))
+	})?;
+	challenges.verify(&challenge, slate_id, nonce)
+}
+
+/// Handler receiving slates in chunks through slate exchanges. A complete slate is
+/// received as by a receive_tx call to the foreign API, and the reply is kept with the
+/// exchange for senders that lost their connection.
+pub struct SlateExchangeHandler<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	/// Foreign API handler receiving the complete slates
+	pub foreign: ForeignAPIHandlerV2<L, C, K>,
+	/// Exchanges in progress or recently completed
+	exchanges: Arc<Mutex<SlateExchanges>>,
+}
+
+impl<L, C, K> SlateExchangeHandler<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	/// Create a new slate exchange handler
+	pub fn new(foreign: ForeignAPIHandlerV2<L, C, K>) -> SlateExchangeHandler<L, C, K> {
+		SlateExchangeHandler {
+			foreign,
+			exchanges: Arc::new(Mutex::new(SlateExchanges::new())),
+		}
+	}
+
+	fn handle_post_request(&self, req: Request<Body>) -> WalletResponseFuture {
+		let foreign = self.foreign.clone();
+		let exchanges = self.exchanges.clone();
+		let proof = receive_proof(&req);
+		let api_key = foreign_api_key(&req);
+		Box::new(parse_body(req).and_then(move |chunk: SlateExchangeChunk| {
+			// Nothing is kept from senders the slate wouldn't be received from
+			let sender = foreign.check_exchange_sender(
+				&chunk.slate_id,
+				proof.clone(),
+				api_key.as_ref().map(|k| k.as_str()),
+			);
+			if let Err(e) = sender {
+				warn!("Refusing slate exchange chunk: {}", e);
+				return err(e);
+			}
+			let token = chunk.token.clone();
+			let data = match exchanges.lock().add_chunk(chunk) {
+				Ok(d) => d,
+				Err(e) => return err(e),
+			};
+			// The slate is taken out of the exchanges, so they aren't locked while
+			// it's received. Until then, senders asking are told to wait for the reply.
+			if let Some(data) = data {
+				let slate = check_json_depth(data.as_bytes(), MAX_JSON_DEPTH)
+					.map_err(|e| e.to_string())
+					.and_then(|_| {
+						serde_json::from_str::<serde_json::Value>(&data).map_err(|e| e.to_string())
+					});
+				let reply = match slate {
+					Ok(slate) => foreign.handle_request(
+						serde_json::json!({
+							"jsonrpc": "2.0",
+							"method": "receive_tx",
+							"id": 1,
+							"params": [slate, null, null],
+						}),
+						proof,
+						api_key,
+					),
+					Err(e) => serde_json::json!({
+						"jsonrpc": "2.0",
+						"id": 1,
+						"error": {"code": -32700, "message": format!("Invalid slate: {}", e)},
+					}),
+				};
+				exchanges.lock().complete(&token, reply);
+			}
+			let status = exchanges.lock().status(&token);
+			ok(json_response_pretty(&status))
+		}))
+	}
+}
+
+impl<L, C, K> api::Handler for SlateExchangeHandler<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	fn get(&self, req: Request<Body>) -> ResponseFuture {
+		let token = req.uri().query().and_then(|q| {
+			q.split('&')
+				.find(|p| p.starts_with("token="))
+				.map(|p| p["token=".len()..].to_owned())
+		});
+		match token {
+			Some(t) => Box::new(ok(json_response_pretty(&self.exchanges.lock().status(&t)))),
+			None => Box::new(ok(response(StatusCode::BAD_REQUEST, "Missing token"))),
+		}
+	}
+
+	fn post(&self, req: Request<Body>) -> ResponseFuture {
+		Box::new(
+			self.handle_post_request(req)
+				.and_then(|r| ok(r))
+				.or_else(|e| {
+					error!("Request Error: {:?}", e);
+					ok(create_error_response(e))
+				}),
+		)
+	}
+
+	fn options(&self, _req: Request<Body>) -> ResponseFuture {
+		Box::new(ok(create_ok_response("{}")))
+	}
+}
+
+/// Handler issuing the receive challenges of the foreign listener
+pub struct ReceiveChallengeHandler {
+	/// Challenge issuer
+	pub challenges: Arc<ReceiveChallenges>,
+}
+
+impl api::Handler for ReceiveChallengeHandler {
+	fn get(&self, _req: Request<Body>) -> ResponseFuture {
+		Box::new(ok(json_response_pretty(&self.challenges.issue())))
+	}
+
+	fn options(&self, _req: Request<Body>) -> ResponseFuture {
+		Box::new(ok(create_ok_response("{}")))
+	}
+}
+
+/// Handler reporting the status of the daemon the owner listener is part of
+pub struct DaemonStatusHandler {
+	/// Handle of the daemon
+	pub daemon: DaemonHandle,
+}
+
+impl api::Handler for DaemonStatusHandler {
+	fn get(&self, _req: Request<Body>) -> ResponseFuture {
+		Box::new(ok(json_response_pretty(&self.daemon.status())))
+	}
+}
+
+/// Sliding one minute window limiting the requests accepted by a handler
+struct RateLimiter {
+	max_requests: u32,
+	requests: VecDeque<Instant>,
+}
+
+impl RateLimiter {
+	fn new(max_requests: u32) -> Self {
+		RateLimiter {
+			max_requests,
+			requests: VecDeque::new(),
+		}
+	}
+
+	/// Record a request, returning false if it goes over the limit
+	fn check(&mut self) -> bool {
+		let now = Instant::now();
+		while let Some(&t) = self.requests.front() {
+			if now.duration_since(t) < Duration::from_secs(60) {
+				break;
+			}
+			self.requests.pop_front();
+		}
+		if self.requests.len() >= self.max_requests as usize {
+			return false;
+		}
+		self.requests.push_back(now);
+		true
+	}
+}
+
+/// V2 API Handler/Wrapper for the miner API, serving coinbases to stratum servers
+/// behind their own basic auth secret and a rate limit
+pub struct MinerAPIHandlerV2<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	/// Wallet instance
+	pub wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+	/// Keychain mask
+	pub keychain_mask: Arc<Mutex<Option<SecretKey>>>,
+	/// Expected Authorization header
+	api_basic_auth: String,
+	/// Requests accepted over the last minute
+	rate_limiter: Mutex<RateLimiter>,
+	/// Coinbases recently built, so repeated requests get the same answer
+	coinbase_cache: Arc<Mutex<CoinbaseCache>>,
+}
+
+impl<L, C, K> MinerAPIHandlerV2<L, C, K>
+where
+	L: WalletLCProvider<'static, C, K> + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	/// Create a new miner API handler
+	pub fn new(
+		wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K> + 'static>>>,
+		keychain_mask: Arc<Mutex<Option<SecretKey>>>,
+		config: &MinerApiConfig,
+	) -> MinerAPIHandlerV2<L, C, K> {
+		MinerAPIHandlerV2 {
+			wallet,
+			keychain_mask,
+			api_basic_auth: "Basic ".to_string()
+				+ &to_base64(&("miner:".to_string() + &config.api_secret)),
+			rate_limiter: Mutex::new(RateLimiter::new(config.max_requests_per_minute)),
+			coinbase_cache: Arc::new(Mutex::new(CoinbaseCache::new(MINER_COINBASE_CACHE_SIZE))),
+		}
+	}
+
+	fn is_authorized(&self, req: &Request<Body>) -> bool {
+		match req.headers().get(hyper::header::AUTHORIZATION) {
+			Some(h) => {
+				verify_slices_are_equal(h.as_bytes(), self.api_basic_auth.as_bytes()).is_ok()
+			}
+			None => false,
 		}
 	}
 
@@ -676,8 +2051,8 @@ where
 		api: Foreign<'static, L, C, K>,
 	) -> Box<dyn Future<Item = serde_json::Value, Error = Error> + Send> {
 		Box::new(parse_body(req).and_then(move |val: serde_json::Value| {
-			let foreign_api = &api as &dyn ForeignRpc;
-			match foreign_api.handle_request(val) {
+			let miner_api = &api as &dyn MinerRpc;
+			match miner_api.handle_request(val) {
 				MaybeReply::Reply(r) => ok(r),
 				MaybeReply::DontReply => {
 					// Since it's http, we need to return something. We return [] because jsonrpc
@@ -690,7 +2065,8 @@ where
 
 	fn handle_post_request(&self, req: Request<Body>) -> WalletResponseFuture {
 		let mask = self.keychain_mask.lock();
-		let api = Foreign::new(self.wallet.clone(), mask.clone(), Some(check_middleware));
+		let mut api = Foreign::new(self.wallet.clone(), mask.clone(), Some(check_middleware));
+		api.set_coinbase_cache(self.coinbase_cache.clone());
 		Box::new(
 			self.call_api(req, api)
 				.and_then(|resp| ok(json_response_pretty(&resp))),
@@ -698,13 +2074,25 @@ where
 	}
 }
 
-impl<L, C, K> api::Handler for ForeignAPIHandlerV2<L, C, K>
+impl<L, C, K> api::Handler for MinerAPIHandlerV2<L, C, K>
 where
 	L: WalletLCProvider<'static, C, K> + 'static,
 	C: NodeClient + 'static,
 	K: Keychain + 'static,
 {
 	fn post(&self, req: Request<Body>) -> ResponseFuture {
+		if !self.is_authorized(&req) {
+			let mut resp = response(StatusCode::UNAUTHORIZED, "");
+			resp.headers_mut().insert(
+				hyper::header::WWW_AUTHENTICATE,
+				GRIN_MINER_BASIC_REALM.clone(),
+			);
+			return Box::new(ok(resp));
+		}
+		if !self.rate_limiter.lock().check() {
+			warn!("Miner API rate limit exceeded, rejecting request");
+			return Box::new(ok(response(StatusCode::TOO_MANY_REQUESTS, "")));
+		}
 		Box::new(
 			self.handle_post_request(req)
 				.and_then(|r| ok(r))
@@ -798,11 +2186,144 @@ where
 		req.into_body()
 			.concat2()
 			.map_err(|_| ErrorKind::GenericError("Failed to read request".to_owned()).into())
-			.and_then(|body| match serde_json::from_reader(&body.to_vec()[..]) {
-				Ok(obj) => ok(obj),
-				Err(e) => {
-					err(ErrorKind::GenericError(format!("Invalid request body: {}", e)).into())
+			.and_then(|body| {
+				// Refused before parsing, rather than left to the parser's recursion limit
+				if let Err(e) = check_json_depth(&body, MAX_JSON_DEPTH) {
+					return err(e);
+				}
+				match serde_json::from_reader(&body.to_vec()[..]) {
+					Ok(obj) => ok(obj),
+					Err(e) => {
+						err(ErrorKind::GenericError(format!("Invalid request body: {}", e)).into())
+					}
 				}
 			}),
 	)
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::stream;
+	use hyper::header::CONTENT_LENGTH;
+	use tokio::runtime::Runtime;
+
+	/// Handler answering a POST request with the length of its body
+	struct BodyLength;
+
+	impl api::Handler for BodyLength {
+		fn post(&self, req: Request<Body>) -> ResponseFuture {
+			Box::new(
+				req.into_body()
+					.concat2()
+					.map(|b| response(StatusCode::OK, b.len().to_string())),
+			)
+		}
+	}
+
+	fn through_limits(mw: &ListenerLimitsMiddleware, body: Body) -> ResponseFuture {
+		let req = Request::post("/v2/foreign").body(body).unwrap();
+		let handlers: Vec<HandlerObj> = vec![Arc::new(BodyLength)];
+		mw.call(req, Box::new(handlers.into_iter()))
+	}
+
+	fn answer(rt: &mut Runtime, res: ResponseFuture) -> (StatusCode, String) {
+		let res = res.and_then(|r| {
+			let status = r.status();
+			r.into_body()
+				.concat2()
+				.map(move |b| (status, String::from_utf8(b.to_vec()).unwrap()))
+		});
+		rt.block_on(res).unwrap()
+	}
+
+	#[test]
+	fn listener_limits_are_enforced() {
+		let mut rt = Runtime::new().unwrap();
+		let mw = ListenerLimitsMiddleware::new(ListenerLimits {
+			max_body_size: 1000,
+			read_timeout: Duration::from_millis(100),
+			max_concurrent_requests: 1,
+		});
+
+		// bodies within the limit are handed on whole
+		let res = through_limits(&mw, Body::from(vec![1u8; 1000]));
+		assert_eq!(answer(&mut rt, res), (StatusCode::OK, "1000".to_owned()));
+
+		// larger ones are refused, whether their length is announced or not
+		let req = Request::post("/v2/foreign")
+			.header(CONTENT_LENGTH, "1001")
+			.body(Body::from(vec![1u8; 1001]))
+			.unwrap();
+		let handlers: Vec<HandlerObj> = vec![Arc::new(BodyLength)];
+		let res = mw.call(req, Box::new(handlers.into_iter()));
+		assert_eq!(answer(&mut rt, res).0, StatusCode::PAYLOAD_TOO_LARGE);
+		let chunks = stream::iter_ok::<_, std::io::Error>(vec![vec![1u8; 600], vec![1u8; 600]]);
+		let res = through_limits(&mw, Body::wrap_stream(chunks));
+		assert_eq!(answer(&mut rt, res).0, StatusCode::PAYLOAD_TOO_LARGE);
+
+		// a body that doesn't come in time is given up on
+		let (sender, body) = Body::channel();
+		let res = through_limits(&mw, body);
+		assert_eq!(answer(&mut rt, res).0, StatusCode::REQUEST_TIMEOUT);
+		drop(sender);
+
+		// a request waiting for its body holds its slot, until it's dropped
+		let (_sender, body) = Body::channel();
+		let waiting = through_limits(&mw, body);
+		let res = through_limits(&mw, Body::from("refused"));
+		assert_eq!(answer(&mut rt, res).0, StatusCode::SERVICE_UNAVAILABLE);
+		drop(waiting);
+		let res = through_limits(&mw, Body::from("handled"));
+		assert_eq!(answer(&mut rt, res), (StatusCode::OK, "7".to_owned()));
+	}
+
+	#[test]
+	fn shutdown_drains_requests_in_flight() {
+		let shutdown = ListenerShutdown::new();
+		assert!(shutdown.drain(Duration::from_millis(10)));
+		let slot = shutdown.enter().unwrap();
+		let other = shutdown.enter().unwrap();
+		drop(other);
+
+		// No new request is let in once a shutdown is requested, and the one
+		// in flight is waited for
+		shutdown.request();
+		assert!(shutdown.enter().is_none());
+		assert!(!shutdown.drain(Duration::from_millis(100)));
+		let release = thread::spawn(move || {
+			thread::sleep(Duration::from_millis(200));
+			drop(slot);
+		});
+		let start = Instant::now();
+		assert!(shutdown.drain(Duration::from_secs(5)));
+		assert!(start.elapsed() < Duration::from_secs(1));
+		release.join().unwrap();
+	}
+
+	#[test]
+	fn foreign_roles_are_checked() {
+		let receive = serde_json::json!({"jsonrpc": "2.0", "id": 1, "method": "receive_tx"});
+		let invoice =
+			serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "finalize_invoice_tx"});
+		let version = serde_json::json!({"jsonrpc": "2.0", "id": 3, "method": "check_version"});
+
+		let roles = ForeignApiRoles::default();
+		assert!(check_roles(&roles, &receive).is_ok());
+		assert!(check_roles(&roles, &invoice).is_ok());
+
+		let roles = ForeignApiRoles::receive_only();
+		assert!(check_roles(&roles, &receive).is_ok());
+		assert!(check_roles(&roles, &invoice).is_err());
+		assert!(check_roles(&roles, &version).is_ok());
+
+		// A call to a disabled method can't be slipped in a batch
+		let roles = ForeignApiRoles::invoice_only();
+		assert!(check_roles(&roles, &invoice).is_ok());
+		assert!(check_roles(&roles, &receive).is_err());
+		let batch = serde_json::json!([version.clone(), receive]);
+		assert!(check_roles(&roles, &batch).is_err());
+		let batch = serde_json::json!([version, invoice]);
+		assert!(check_roles(&roles, &batch).is_ok());
+	}
+}
@@ -0,0 +1,393 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Wallet daemon, running the services of a wallet in a single supervised
+//! process: the owner API listener, the foreign API listener, an updater
+//! refreshing the wallet from the node, which also confirms the transactions
+//! the wallet broadcast, and the scheduled backups if set in the config. A
+//! service that fails is restarted after a backoff, and the state of all of
+//! them is reported on the owner API at `/v2/daemon/status`.
+
+use crate::command::{self, GlobalArgs, ListenArgs};
+use crate::config::{WalletConfig, GRIN_WALLET_DIR};
+use crate::controller::{self, ListenerShutdown};
+use crate::error::{Error, ErrorKind};
+use crate::impls::next_backup_in;
+use crate::keychain::Keychain;
+use crate::libwallet::{NodeClient, WalletInst, WalletLCProvider};
+use crate::util::secp::key::SecretKey;
+use crate::util::{Mutex, RwLock};
+use chrono::{DateTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+use std::cmp;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Path of the owner API endpoint reporting the status of the daemon
+pub const DAEMON_STATUS_PATH: &'static str = "/v2/daemon/status";
+/// Longest wait before a failed service is restarted
+const MAX_RESTART_BACKOFF_SECS: u64 = 300;
+/// Wait before a scheduled backup that couldn't be pushed is tried again
+const BACKUP_RETRY_SECS: u64 = 600;
+
+/// State of a service run by the daemon
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum ServiceState {
+	/// Started and not known to have failed
+	Running,
+	/// Failed, waiting to be started again
+	Restarting,
+	/// Failed more times than allowed, not restarted anymore
+	Failed,
+	/// Stopped with the daemon
+	Stopped,
+}
+
+/// Status of a service run by the daemon
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ServiceStatus {
+	/// Name of the service
+	pub name: String,
+	/// Current state
+	pub state: ServiceState,
+	/// Times the service was restarted
+	pub restarts: u32,
+	/// Last error of the service, if any
+	pub last_error: Option<String>,
+	/// When the state last changed
+	pub since: DateTime<Utc>,
+}
+
+/// Status of the daemon and its services
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DaemonStatus {
+	/// When the daemon was started
+	pub started: DateTime<Utc>,
+	/// Services run by the daemon
+	pub services: Vec<ServiceStatus>,
+}
+
+/// Arguments for the daemon
+#[derive(Clone)]
+pub struct DaemonArgs {
+	/// Whether to run the owner API listener
+	pub owner_api: bool,
+	/// Whether to run the foreign API listener
+	pub foreign_api: bool,
+	/// Seconds between refreshes of the wallet, none to not run the updater
+	pub update_interval_secs: Option<u64>,
+	/// Times a failed service is restarted before the daemon gives up on it
+	pub max_restarts: u32,
+}
+
+impl Default for DaemonArgs {
+	fn default() -> DaemonArgs {
+		DaemonArgs {
+			owner_api: true,
+			foreign_api: true,
+			update_interval_secs: Some(60),
+			max_restarts: 10,
+		}
+	}
+}
+
+/// Handle to a running daemon, to get its status or stop it
+#[derive(Clone)]
+pub struct DaemonHandle {
+	shutdown: ListenerShutdown,
+	started: DateTime<Utc>,
+	services: Arc<RwLock<Vec<ServiceStatus>>>,
+}
+
+impl DaemonHandle {
+	/// Create a handle stopping the daemon along with the given shutdown handle
+	pub fn new(shutdown: ListenerShutdown) -> DaemonHandle {
+		DaemonHandle {
+			shutdown,
+			started: Utc::now(),
+			services: Arc::new(RwLock::new(vec![])),
+		}
+	}
+
+	/// Status of the daemon and its services
+	pub fn status(&self) -> DaemonStatus {
+		DaemonStatus {
+			started: self.started,
+			services: self.services.read().clone(),
+		}
+	}
+
+	/// Stop the daemon and its services
+	pub fn stop(&self) {
+		self.shutdown.request();
+	}
+
+	/// Whether the daemon was asked to stop
+	pub fn is_stopping(&self) -> bool {
+		self.shutdown.is_requested()
+	}
+
+	fn update<F>(&self, name: &str, f: F)
+	where
+		F: FnOnce(&mut ServiceStatus),
+	{
+		let mut services = self.services.write();
+		match services.iter_mut().find(|s| s.name == name) {
+			Some(s) => f(s),
+			None => {
+				let mut s = ServiceStatus {
+					name: name.to_owned(),
+					state: ServiceState::Running,
+					restarts: 0,
+					last_error: None,
+					since: Utc::now(),
+				};
+				f(&mut s);
+				services.push(s);
+			}
+		}
+	}
+
+	fn set_state(&self, name: &str, state: ServiceState) {
+		self.update(name, |s| {
+			if s.state != state {
+				s.state = state;
+				s.since = Utc::now();
+			}
+		});
+	}
+
+	fn set_error(&self, name: &str, error: String) {
+		self.update(name, |s| s.last_error = Some(error));
+	}
+
+	/// Sleep for the duration, or until the daemon is asked to stop
+	fn sleep(&self, duration: Duration) {
+		self.shutdown.wait_timeout(duration);
+	}
+}
+
+/// Run the services of the wallet until the daemon is stopped, through its
+/// handle or the `shutdown` method of the owner API
+pub fn daemon<L, C, K>(
+	wallet: Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+	keychain_mask: Option<SecretKey>,
+	config: &WalletConfig,
+	g_args: &GlobalArgs,
+	args: &DaemonArgs,
+	handle: DaemonHandle,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'static, C, K> + Send + Sync + 'static,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	let backup_interval_hours = config.backup.as_ref().and_then(|b| b.interval_hours);
+	if !args.owner_api
+		&& !args.foreign_api
+		&& args.update_interval_secs.is_none()
+		&& backup_interval_hours.is_none()
+	{
+		let msg = "No service for the daemon to run.".to_owned();
+		return Err(ErrorKind::ArgumentError(msg).into());
+	}
+	// A single keychain mask, shared by all the services
+	let km = Arc::new(Mutex::new(keychain_mask));
+	warn!("Starting wallet daemon.");
+
+	let mut owner_thread = None;
+	if args.owner_api {
+		let (wallet, km, config) = (wallet.clone(), km.clone(), config.clone());
+		let (api_secret, handle_s) = (g_args.node_api_secret.clone(), handle.clone());
+		let audit_log = g_args.audit_log.clone();
+		owner_thread = Some(supervise(
+			&handle,
+			"owner_api",
+			args.max_restarts,
+			move || {
+				controller::owner_listener(
+					wallet.clone(),
+					km.clone(),
+					config.owner_api_listen_addr().as_str(),
+					api_secret.clone(),
+					None,
+					config.owner_api_include_foreign.clone(),
+					config.owner_api_response_mac.clone(),
+					config.owner_api_lock_after_mins.clone(),
+					command::listener_limits(&config),
+					audit_log.clone(),
+					handle_s.shutdown.clone(),
+					Some(handle_s.clone()),
+				)
+				.map_err(|e| ErrorKind::LibWallet(e.kind(), e.cause_string()).into())
+			},
+		)?);
+	}
+	if args.foreign_api {
+		let (wallet, km) = (wallet.clone(), km.clone());
+		let (config, g_args) = (config.clone(), g_args.clone());
+		let listen_args = ListenArgs {
+			method: "http".to_owned(),
+		};
+		supervise(&handle, "foreign_api", args.max_restarts, move || {
+			command::listen(wallet.clone(), km.clone(), &config, &listen_args, &g_args)
+		})?;
+	}
+	if let Some(secs) = args.update_interval_secs {
+		let (wallet, km, handle_u) = (wallet.clone(), km.clone(), handle.clone());
+		supervise(&handle, "updater", args.max_restarts, move || {
+			while !handle_u.is_stopping() {
+				// A node that can't be reached isn't a failure of the updater
+				let mask = km.lock().clone();
+				let res = controller::owner_single_use(wallet.clone(), mask.as_ref(), |api, m| {
+					api.retrieve_summary_info(m, true, 1)?;
+					Ok(())
+				});
+				if let Err(e) = res {
+					warn!("Unable to update the wallet: {}", e);
+					handle_u.set_error("updater", e.to_string());
+				}
+				handle_u.sleep(Duration::from_secs(secs));
+			}
+			Ok(())
+		})?;
+	}
+
+	if let Some(hours) = backup_interval_hours {
+		let (wallet, km, config, handle_b) =
+			(wallet.clone(), km.clone(), config.clone(), handle.clone());
+		let interval = Duration::from_secs(hours as u64 * 3600);
+		let wallet_dir = Path::new(&config.data_file_dir).join(GRIN_WALLET_DIR);
+		supervise(&handle, "backup", args.max_restarts, move || {
+			while !handle_b.is_stopping() {
+				// Backups pushed by the backup command in the meantime count too
+				let wait = next_backup_in(&wallet_dir, interval);
+				if wait > Duration::from_secs(0) {
+					handle_b.sleep(wait);
+					continue;
+				}
+				// Storage that can't be reached isn't a failure of the service
+				let mask = km.lock().clone();
+				if let Err(e) = command::push_wallet_backup(wallet.clone(), mask.as_ref(), &config)
+				{
+					warn!("Unable to back up the wallet: {}", e);
+					handle_b.set_error("backup", e.to_string());
+					handle_b.sleep(Duration::from_secs(BACKUP_RETRY_SECS));
+				}
+			}
+			Ok(())
+		})?;
+	}
+
+	handle.shutdown.wait();
+	warn!("Stopping wallet daemon.");
+	match owner_thread {
+		// The owner listener waits for its requests in flight and closes the wallet
+		Some(t) => {
+			let _ = t.join();
+		}
+		None => {
+			let mut w_lock = wallet.lock();
+			if let Err(e) = w_lock.lc_provider().and_then(|lc| lc.close_wallet(None)) {
+				error!("Unable to close the wallet: {}", e);
+			}
+		}
+	}
+	warn!("Wallet daemon stopped.");
+	Ok(())
+}
+
+/// Run a service on a thread of its own, restarting it when it fails, with
+/// a backoff doubling on every restart
+fn supervise<F>(
+	handle: &DaemonHandle,
+	name: &'static str,
+	max_restarts: u32,
+	run: F,
+) -> Result<thread::JoinHandle<()>, Error>
+where
+	F: Fn() -> Result<(), Error> + Send + 'static,
+{
+	let handle = handle.clone();
+	handle.set_state(name, ServiceState::Running);
+	let thread = thread::Builder::new()
+		.name(format!("daemon-{}", name))
+		.spawn(move || loop {
+			let error = match panic::catch_unwind(AssertUnwindSafe(|| run())) {
+				Ok(Ok(())) => "stopped unexpectedly".to_owned(),
+				Ok(Err(e)) => e.to_string(),
+				Err(_) => "panicked".to_owned(),
+			};
+			if handle.is_stopping() {
+				handle.set_state(name, ServiceState::Stopped);
+				return;
+			}
+			error!("Daemon service {} failed: {}", name, error);
+			handle.set_error(name, error);
+			let mut restarts = 0;
+			handle.update(name, |s| restarts = s.restarts);
+			if restarts >= max_restarts {
+				let failures = restarts + 1;
+				error!(
+					"Daemon service {} failed {} times, not restarting it.",
+					name, failures
+				);
+				handle.set_state(name, ServiceState::Failed);
+				return;
+			}
+			handle.set_state(name, ServiceState::Restarting);
+			handle.sleep(Duration::from_secs(cmp::min(
+				1 << cmp::min(restarts, 16),
+				MAX_RESTART_BACKOFF_SECS,
+			)));
+			if handle.is_stopping() {
+				handle.set_state(name, ServiceState::Stopped);
+				return;
+			}
+			warn!("Restarting daemon service {}.", name);
+			handle.update(name, |s| s.restarts += 1);
+			handle.set_state(name, ServiceState::Running);
+		})
+		.map_err(|e| ErrorKind::GenericError(format!("Unable to start {}: {}", name, e)))?;
+	Ok(thread)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn supervised_service_status() {
+		let handle = DaemonHandle::new(ListenerShutdown::new());
+		let failing = supervise(&handle, "failing", 1, || {
+			Err(ErrorKind::GenericError("down".to_owned()).into())
+		})
+		.unwrap();
+		failing.join().unwrap();
+		let status = handle.status();
+		assert_eq!(status.services[0].state, ServiceState::Failed);
+		assert_eq!(status.services[0].restarts, 1);
+		assert!(status.services[0].last_error.is_some());
+
+		// Services stopped with the daemon aren't restarted
+		let stopped = supervise(&handle, "stopped", 1, || Ok(())).unwrap();
+		handle.stop();
+		stopped.join().unwrap();
+		let status = handle.status();
+		assert_eq!(status.services[1].state, ServiceState::Stopped);
+	}
+}
@@ -15,13 +15,24 @@
 use crate::core::core::{self, amount_to_hr_string};
 use crate::core::global;
 use crate::libwallet::{
-	AcctPathMapping, Error, OutputCommitMapping, OutputStatus, TxLogEntry, WalletInfo,
+	amount_to_currency_string, AcctPathMapping, Error, LedgerEntry, OutputCommitMapping,
+	OutputStatus, RecipientHandshake, ReconciliationReport, TxLogEntry, ViewOnlyAttestation,
+	WalletInfo, WalletStats,
 };
 use crate::util;
 use prettytable;
 use std::io::prelude::Write;
 use term;
 
+/// Display the recovery phrase of a wallet
+pub fn recovery_phrase(phrase: &str) {
+	println!("Your recovery phrase is:");
+	println!();
+	println!("{}", phrase);
+	println!();
+	println!("Please back-up these words in a non-digital format.");
+}
+
 /// Display outputs in a pretty way
 pub fn outputs(
 	account: &str,
@@ -175,7 +186,13 @@ pub fn txs(
 			Some(m) => format!("{}", m.format("%Y-%m-%d %H:%M:%S")),
 			None => "None".to_owned(),
 		};
-		let confirmed = format!("{}", t.confirmed);
+		let confirmed = if !t.confirmed && t.in_mempool {
+			"InMempool".to_owned()
+		} else if !t.confirmed && t.abandoned {
+			"Abandoned".to_owned()
+		} else {
+			format!("{}", t.confirmed)
+		};
 		let num_inputs = format!("{}", t.num_inputs);
 		let num_outputs = format!("{}", t.num_outputs);
 		let amount_debited_str = core::amount_to_hr_string(t.amount_debited, true);
@@ -270,8 +287,8 @@ pub fn info(
 	dark_background_color_scheme: bool,
 ) {
 	println!(
-		"\n____ Wallet Summary Info - Account '{}' as of height {} ____\n",
-		account, wallet_info.last_confirmed_height,
+		"\n____ Wallet Summary Info - Account '{}' as of height {}, amounts in {} ____\n",
+		account, wallet_info.last_confirmed_height, wallet_info.currency,
 	);
 
 	let mut table = table!();
@@ -297,6 +314,20 @@ pub fn info(
 			bFB->format!("Awaiting Finalization"),
 			FB->amount_to_hr_string(wallet_info.amount_awaiting_finalization, false)
 		]);
+		// Only display abandoned receives if the wallet gave up on some
+		if wallet_info.amount_abandoned > 0 {
+			table.add_row(row![
+				bFw->"Abandoned by Sender",
+				Fw->amount_to_hr_string(wallet_info.amount_abandoned, false)
+			]);
+		}
+		// Only display reverted amounts if a reorg took some away
+		if wallet_info.amount_reverted > 0 {
+			table.add_row(row![
+				bFr->"Reverted by Reorg",
+				Fr->amount_to_hr_string(wallet_info.amount_reverted, false)
+			]);
+		}
 		table.add_row(row![
 			Fr->"Locked by previous transaction",
 			Fr->amount_to_hr_string(wallet_info.amount_locked, false)
@@ -309,6 +340,21 @@ pub fn info(
 			bFG->"Currently Spendable",
 			FG->amount_to_hr_string(wallet_info.amount_currently_spendable, false)
 		]);
+		// Only display holds if the balance is being held for upcoming payments
+		if wallet_info.amount_held > 0 {
+			table.add_row(row![
+				bFY->"Held for Upcoming Payments",
+				FY->amount_to_hr_string(wallet_info.amount_held, false)
+			]);
+			table.add_row(row![
+				bFG->"Available",
+				FG->amount_to_hr_string(wallet_info.amount_available, false)
+			]);
+		}
+		table.add_row(row![
+			bFw->format!("Finalized (>= {})", wallet_info.finality_depth),
+			Fw->amount_to_hr_string(wallet_info.amount_finalized, false)
+		]);
 	} else {
 		table.add_row(row![
 			bFG->"Total",
@@ -326,6 +372,20 @@ pub fn info(
 			bFB->format!("Awaiting Confirmation (< {})", wallet_info.minimum_confirmations),
 			FB->amount_to_hr_string(wallet_info.amount_awaiting_confirmation, false)
 		]);
+		// Only display abandoned receives if the wallet gave up on some
+		if wallet_info.amount_abandoned > 0 {
+			table.add_row(row![
+				bFw->"Abandoned by Sender",
+				Fw->amount_to_hr_string(wallet_info.amount_abandoned, false)
+			]);
+		}
+		// Only display reverted amounts if a reorg took some away
+		if wallet_info.amount_reverted > 0 {
+			table.add_row(row![
+				bFr->"Reverted by Reorg",
+				Fr->amount_to_hr_string(wallet_info.amount_reverted, false)
+			]);
+		}
 		table.add_row(row![
 			Fr->"Locked by previous transaction",
 			Fr->amount_to_hr_string(wallet_info.amount_locked, false)
@@ -338,6 +398,21 @@ pub fn info(
 			bFG->"Currently Spendable",
 			FG->amount_to_hr_string(wallet_info.amount_currently_spendable, false)
 		]);
+		// Only display holds if the balance is being held for upcoming payments
+		if wallet_info.amount_held > 0 {
+			table.add_row(row![
+				bFB->"Held for Upcoming Payments",
+				FB->amount_to_hr_string(wallet_info.amount_held, false)
+			]);
+			table.add_row(row![
+				bFG->"Available",
+				FG->amount_to_hr_string(wallet_info.amount_available, false)
+			]);
+		}
+		table.add_row(row![
+			bFB->format!("Finalized (>= {})", wallet_info.finality_depth),
+			FB->amount_to_hr_string(wallet_info.amount_finalized, false)
+		]);
 	};
 	table.set_format(*prettytable::format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
 	table.printstd();
@@ -359,11 +434,12 @@ pub fn estimate(
 		u64,  // total amount to be locked
 		u64,  // fee
 	)>,
+	currency: &str,
 	dark_background_color_scheme: bool,
 ) {
 	println!(
 		"\nEstimation for sending {}:\n",
-		amount_to_hr_string(amount, false)
+		amount_to_currency_string(amount, false, currency)
 	);
 
 	let mut table = table!();
@@ -393,6 +469,225 @@ pub fn estimate(
 	println!();
 }
 
+/// Display the differences between a ledger and the wallet
+pub fn reconciliation(account: &str, report: &ReconciliationReport, validated: bool) {
+	println!(
+		"\n____ Ledger Reconciliation - Account '{}' ____\n",
+		account
+	);
+
+	let slate_id = |t: &TxLogEntry| match t.tx_slate_id {
+		Some(id) => id.to_string(),
+		None => "None".to_owned(),
+	};
+	let reference = |e: &LedgerEntry| e.reference.clone().unwrap_or_default();
+	let mut table = table!();
+	table.set_titles(row![
+		bMG->"Issue",
+		bMG->"Slate ID",
+		bMG->"Ledger Amount",
+		bMG->"Wallet Amount",
+		bMG->"Reference",
+	]);
+	for e in report.missing_deposits.iter() {
+		table.add_row(row![
+			bFR->"Missing deposit",
+			e.tx_slate_id,
+			amount_to_hr_string(e.amount, true),
+			"",
+			reference(e),
+		]);
+	}
+	for e in report.missing_withdrawals.iter() {
+		table.add_row(row![
+			bFR->"Missing withdrawal",
+			e.tx_slate_id,
+			amount_to_hr_string(e.amount, true),
+			"",
+			reference(e),
+		]);
+	}
+	for m in report.amount_mismatches.iter() {
+		table.add_row(row![
+			bFR->"Amount mismatch",
+			m.entry.tx_slate_id,
+			amount_to_hr_string(m.entry.amount, true),
+			amount_to_hr_string(m.actual_amount, true),
+			reference(&m.entry),
+		]);
+	}
+	for t in report.unexpected_deposits.iter() {
+		table.add_row(row![
+			bFY->"Unexpected deposit",
+			slate_id(t),
+			"",
+			amount_to_hr_string(t.amount_credited, true),
+			"",
+		]);
+	}
+	for t in report.unexpected_sends.iter() {
+		let sent = t
+			.amount_debited
+			.saturating_sub(t.amount_credited)
+			.saturating_sub(t.fee.unwrap_or(0));
+		table.add_row(row![
+			bFR->"Unexpected send",
+			slate_id(t),
+			"",
+			amount_to_hr_string(sent, true),
+			"",
+		]);
+	}
+	let has_issues = !report.missing_deposits.is_empty()
+		|| !report.missing_withdrawals.is_empty()
+		|| !report.amount_mismatches.is_empty()
+		|| !report.unexpected_deposits.is_empty()
+		|| !report.unexpected_sends.is_empty();
+	if has_issues {
+		table.set_format(*prettytable::format::consts::FORMAT_NO_COLSEP);
+		table.printstd();
+		println!();
+	}
+
+	println!("Matched ledger entries: {}", report.matched);
+	println!(
+		"Wallet balance: {}",
+		amount_to_hr_string(report.actual_balance, false)
+	);
+	if let Some(b) = report.expected_balance {
+		println!("Expected balance: {}", amount_to_hr_string(b, false));
+	}
+	println!();
+	if report.is_reconciled() {
+		println!("The wallet matches the ledger.");
+	} else {
+		println!("WARNING: The wallet doesn't match the ledger.");
+	}
+	if !validated {
+		println!(
+			"\nWARNING: Wallet failed to verify data against a live chain. \
+			 The above is from local cache and only valid up to the given height! \
+			 (is your `grin server` offline or broken?)"
+		);
+	}
+}
+
+/// Display what a recipient reported when checked
+pub fn recipient_handshake(handshake: &RecipientHandshake) {
+	println!("\n____ Recipient {} ____\n", handshake.dest);
+	println!("Foreign API version: {}", handshake.foreign_api_version);
+	println!(
+		"Supported slate versions: {}",
+		handshake.supported_slate_versions.join(", ")
+	);
+	println!("Slates sent as: V{}", handshake.slate_version);
+	match handshake.receive_challenge_difficulty {
+		Some(d) => println!("Receive challenge difficulty: {} bits", d),
+		None => println!("Receive challenge: none"),
+	}
+	println!(
+		"Resumable slate exchange: {}",
+		if handshake.slate_exchange {
+			"yes"
+		} else {
+			"no"
+		}
+	);
+	println!("Version check round trip: {} ms", handshake.latency_ms);
+	println!();
+	println!("The recipient can receive from this wallet.");
+}
+
+/// Display the usage statistics of the wallet
+pub fn wallet_stats(stats: &WalletStats) {
+	println!("\n____ Wallet Statistics ____\n");
+	println!("Accounts: {}", stats.accounts);
+	println!("Outputs:");
+	for (status, count) in stats.outputs.iter() {
+		println!("  {}: {}", status, count);
+	}
+	println!("Transaction log entries:");
+	for (tx_type, count) in stats.tx_entries.iter() {
+		println!("  {}: {}", tx_type, count);
+	}
+	match stats.oldest_unconfirmed_tx.as_ref() {
+		Some(t) => println!(
+			"Oldest unconfirmed transaction: {}, created {}",
+			t.id,
+			t.creation_ts.format("%Y-%m-%d %H:%M:%S")
+		),
+		None => println!("Oldest unconfirmed transaction: none"),
+	}
+	let bytes = |b: Option<u64>| match b {
+		Some(b) => format!("{:.1} MiB", b as f64 / 1_048_576.0),
+		None => "unknown".to_owned(),
+	};
+	println!("Data directory size: {}", bytes(stats.data_dir_size));
+	println!("Free disk space: {}", bytes(stats.disk_free_space));
+	match stats.last_backup {
+		Some(t) => println!("Last backup: {}", t.format("%Y-%m-%d %H:%M:%S")),
+		None => println!("Last backup: never"),
+	}
+	println!();
+}
+
+/// Display the audit of a view-only bundle
+pub fn view_only_attestation(attestation: &ViewOnlyAttestation) {
+	println!(
+		"\n____ View-Only Bundle Audit - Wallet {} ____\n",
+		attestation.fingerprint
+	);
+
+	let mut table = table!();
+	table.set_titles(row![
+		bMG->"Commitment",
+		bMG->"Reported Value",
+		bMG->"Verified Value",
+		bMG->"Issue",
+	]);
+	for o in attestation.outputs.iter() {
+		let verified = match o.verified_value {
+			Some(v) => amount_to_hr_string(v, true),
+			None => "".to_owned(),
+		};
+		let issue = o.issue.clone().unwrap_or_default();
+		if o.issue.is_some() {
+			table.add_row(row![
+				bFR->util::to_hex(o.commit.as_ref().to_vec()),
+				amount_to_hr_string(o.reported_value, true),
+				verified,
+				bFR->issue,
+			]);
+		} else {
+			table.add_row(row![
+				bFD->util::to_hex(o.commit.as_ref().to_vec()),
+				amount_to_hr_string(o.reported_value, true),
+				verified,
+				issue,
+			]);
+		}
+	}
+	table.set_format(*prettytable::format::consts::FORMAT_NO_COLSEP);
+	table.printstd();
+	println!();
+
+	println!("Audited at height: {}", attestation.height);
+	println!(
+		"Reported total: {}",
+		amount_to_hr_string(attestation.reported_total, false)
+	);
+	println!(
+		"Verified total: {}",
+		amount_to_hr_string(attestation.verified_total, false)
+	);
+	println!();
+	if attestation.is_clean() {
+		println!("The node confirms the balance of the bundle.");
+	} else {
+		println!("WARNING: The node doesn't confirm the balance of the bundle.");
+	}
+}
+
 /// Display list of wallet accounts in a pretty way
 pub fn accounts(acct_mappings: Vec<AcctPathMapping>) {
 	println!("\n____ Wallet Accounts ____\n",);
@@ -401,11 +696,13 @@ pub fn accounts(acct_mappings: Vec<AcctPathMapping>) {
 	table.set_titles(row![
 		mMG->"Name",
 		bMG->"Parent BIP-32 Derivation Path",
+		bMG->"Derivation",
 	]);
 	for m in acct_mappings {
 		table.add_row(row![
 			bFC->m.label,
 			bGC->m.path.to_bip_32_string(),
+			bFC->m.derivation_scheme(),
 		]);
 	}
 	table.set_format(*prettytable::format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
@@ -0,0 +1,334 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Journal of the mutating owner API requests. Each request is written to disk
+//! before it runs and removed once it returns, so requests interrupted by a crash
+//! can be rolled back or replayed the next time the owner API starts.
+
+use crate::apiwallet::{Owner, OwnerContext};
+use crate::core::core::Transaction;
+use crate::keychain::Keychain;
+use crate::libwallet::slate_versions::v2::TransactionV2;
+use crate::libwallet::{
+	Error, ErrorKind, NodeClient, Slate, TxLogEntryType, VersionedSlate, WalletLCProvider,
+};
+use crate::util::secp::key::SecretKey;
+use chrono::{DateTime, Utc};
+use failure::ResultExt;
+use serde_derive::{Deserialize, Serialize};
+use serde_json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+/// Directory, in the wallet data directory, holding the journal
+pub const JOURNAL_DIR: &'static str = "rpc_journal";
+
+/// Extension of journal entries
+const ENTRY_EXT: &'static str = "json";
+
+/// Extension of entries that couldn't be recovered, kept for inspection
+const FAILED_EXT: &'static str = "failed";
+
+/// Names of the parameters of the journaled methods, in order
+fn journaled_params(method: &str) -> Option<&'static [&'static str]> {
+	match method {
+		"init_send_tx" => Some(&["args"]),
+		"process_invoice_tx" => Some(&["slate", "args"]),
+		"refund_tx" => Some(&["tx_slate_id", "amount"]),
+		"finalize_tx" => Some(&["slate"]),
+		"post_tx" => Some(&["tx", "fluff"]),
+		"post_raw_tx" => Some(&["raw_tx", "fluff"]),
+		"cancel_tx" => Some(&["tx_id", "tx_slate_id"]),
+		_ => None,
+	}
+}
+
+/// An owner API request that was started but isn't known to have completed
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JournalEntry {
+	/// Entry id
+	pub id: Uuid,
+	/// Owner API method
+	pub method: String,
+	/// Parameters of the request by name, without the API token
+	pub params: serde_json::Value,
+	/// When the request was received
+	pub created: DateTime<Utc>,
+	/// Id of the slate of the tx the request creates, if known before it runs
+	#[serde(default)]
+	pub slate_id: Option<Uuid>,
+}
+
+impl JournalEntry {
+	fn param(&self, name: &str) -> serde_json::Value {
+		self.params[name].clone()
+	}
+
+	fn slate(&self) -> Result<Slate, Error> {
+		let slate: VersionedSlate = serde_json::from_value(self.param("slate"))
+			.map_err(|e| ErrorKind::Format(format!("Journaled slate: {}", e)))?;
+		Ok(Slate::from(slate))
+	}
+}
+
+/// Journal of the mutating owner API requests, one file per request
+pub struct RequestJournal {
+	dir: PathBuf,
+}
+
+impl RequestJournal {
+	/// Open the journal in the given wallet data directory, creating it if needed
+	pub fn new(data_dir: &str) -> Result<RequestJournal, Error> {
+		let dir = Path::new(data_dir).join(JOURNAL_DIR);
+		fs::create_dir_all(&dir).context(ErrorKind::IO)?;
+		Ok(RequestJournal { dir })
+	}
+
+	fn entry_path(&self, id: &Uuid, ext: &str) -> PathBuf {
+		self.dir.join(format!("{}.{}", id, ext))
+	}
+
+	/// Record a request before it runs. Returns the entry if the request changes the
+	/// wallet and has been journaled, `None` otherwise. `has_token` tells whether the
+	/// request comes through the secure (V3) API, which takes a token first.
+	///
+	/// A send given no slate id is given one in `req`, so the tx it creates is known
+	/// to the journal even if the request never returns.
+	pub fn begin(
+		&self,
+		req: &mut serde_json::Value,
+		has_token: bool,
+	) -> Result<Option<JournalEntry>, Error> {
+		let method = match req["method"].as_str() {
+			Some(m) => m.to_owned(),
+			None => return Ok(None),
+		};
+		let names = match journaled_params(&method) {
+			Some(n) => n,
+			None => return Ok(None),
+		};
+		let offset = if has_token { 1 } else { 0 };
+		if method == "init_send_tx" {
+			let args = match req.get_mut("params") {
+				Some(serde_json::Value::Array(a)) => a.get_mut(offset),
+				Some(p) => p.get_mut("args"),
+				None => None,
+			};
+			if let Some(serde_json::Value::Object(a)) = args {
+				if a.get("slate_id").map(|v| v.is_null()).unwrap_or(true) {
+					let id = Uuid::new_v4().to_string();
+					a.insert("slate_id".to_owned(), serde_json::Value::String(id));
+				}
+			}
+		}
+		let mut params = serde_json::Map::new();
+		match &req["params"] {
+			serde_json::Value::Object(o) => {
+				for n in names.iter() {
+					if let Some(v) = o.get(*n) {
+						params.insert(n.to_string(), v.clone());
+					}
+				}
+			}
+			serde_json::Value::Array(a) => {
+				for (n, v) in names.iter().zip(a.iter().skip(offset)) {
+					params.insert(n.to_string(), v.clone());
+				}
+			}
+			_ => {}
+		}
+		// operator secrets aren't needed to recover and aren't kept on disk
+		if let Some(serde_json::Value::Object(a)) = params.get_mut("args") {
+			a.remove("approval_token");
+		}
+		let mut entry = JournalEntry {
+			id: Uuid::new_v4(),
+			method,
+			params: serde_json::Value::Object(params),
+			created: Utc::now(),
+			slate_id: None,
+		};
+		entry.slate_id = match entry.method.as_str() {
+			"init_send_tx" => {
+				serde_json::from_value(entry.param("args")["slate_id"].clone()).unwrap_or(None)
+			}
+			"process_invoice_tx" => entry.slate().ok().map(|s| s.id),
+			_ => None,
+		};
+		let json = serde_json::to_string_pretty(&entry)
+			.map_err(|e| ErrorKind::Format(format!("Journal entry: {}", e)))?;
+		fs::write(self.entry_path(&entry.id, ENTRY_EXT), json).context(ErrorKind::IO)?;
+		Ok(Some(entry))
+	}
+
+	/// Remove the entry of a request that has returned
+	pub fn complete(&self, entry: &JournalEntry) -> Result<(), Error> {
+		fs::remove_file(self.entry_path(&entry.id, ENTRY_EXT)).context(ErrorKind::IO)?;
+		Ok(())
+	}
+
+	/// Keep the entry of a request that couldn't be recovered, without retrying it
+	fn fail(&self, entry: &JournalEntry) -> Result<(), Error> {
+		fs::rename(
+			self.entry_path(&entry.id, ENTRY_EXT),
+			self.entry_path(&entry.id, FAILED_EXT),
+		)
+		.context(ErrorKind::IO)?;
+		Ok(())
+	}
+
+	/// Requests that were started but never completed, oldest first
+	pub fn pending(&self) -> Result<Vec<JournalEntry>, Error> {
+		let mut entries = vec![];
+		for f in fs::read_dir(&self.dir).context(ErrorKind::IO)? {
+			let path = f.context(ErrorKind::IO)?.path();
+			if path.extension().and_then(|e| e.to_str()) != Some(ENTRY_EXT) {
+				continue;
+			}
+			let content = fs::read_to_string(&path).context(ErrorKind::IO)?;
+			match serde_json::from_str::<JournalEntry>(&content) {
+				Ok(e) => entries.push(e),
+				Err(e) => error!("Unreadable journal entry {}: {}", path.display(), e),
+			}
+		}
+		entries.sort_by_key(|e| e.created);
+		Ok(entries)
+	}
+
+	/// Roll back or replay the requests left incomplete by a previous run.
+	/// Entries that can't be recovered are kept in the journal directory
+	/// with a `.failed` extension.
+	pub fn recover<'a, L, C, K>(
+		&self,
+		api: &Owner<'a, L, C, K>,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<(), Error>
+	where
+		L: WalletLCProvider<'a, C, K>,
+		C: NodeClient + 'a,
+		K: Keychain + 'a,
+	{
+		for entry in self.pending()? {
+			warn!(
+				"Recovering interrupted owner API request {} ({}), received at {}",
+				entry.method, entry.id, entry.created
+			);
+			match recover_entry(api, keychain_mask, &entry) {
+				Ok(()) => self.complete(&entry)?,
+				Err(e) => {
+					error!(
+						"Unable to recover owner API request {}: {}. Its record is kept in {}",
+						entry.id,
+						e,
+						self.dir.display()
+					);
+					self.fail(&entry)?;
+				}
+			}
+		}
+		Ok(())
+	}
+}
+
+fn recover_entry<'a, L, C, K>(
+	api: &Owner<'a, L, C, K>,
+	keychain_mask: Option<&SecretKey>,
+	entry: &JournalEntry,
+) -> Result<(), Error>
+where
+	L: WalletLCProvider<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	match entry.method.as_str() {
+		// The slate never made it back to the caller, so cancel the transaction
+		// the request created if it was never finalized, unlocking its outputs
+		"init_send_tx" | "process_invoice_tx" => {
+			let slate_id = entry.slate_id.ok_or_else(|| {
+				ErrorKind::GenericError("No slate id recorded for the request".to_owned())
+			})?;
+			api.with_context(keychain_mask, |ctx| cancel_unfinalized(ctx, slate_id))
+		}
+		// The refund is recorded on the tx it refunds
+		"refund_tx" => {
+			let tx_slate_id: Uuid = serde_json::from_value(entry.param("tx_slate_id"))
+				.map_err(|e| ErrorKind::Format(format!("Journaled slate id: {}", e)))?;
+			api.with_context(keychain_mask, |ctx| {
+				let (_, txs) = ctx.retrieve_txs(false, None, Some(tx_slate_id))?;
+				match txs.iter().filter_map(|t| t.refund_slate_id).next() {
+					Some(id) => cancel_unfinalized(ctx, id),
+					None => Ok(()),
+				}
+			})
+		}
+		"finalize_tx" => {
+			let slate = entry.slate()?;
+			let (_, txs) = api.retrieve_txs(keychain_mask, false, None, Some(slate.id))?;
+			if txs.iter().any(|t| t.stored_tx.is_some()) {
+				return Ok(());
+			}
+			api.finalize_tx(keychain_mask, &slate)?;
+			Ok(())
+		}
+		"post_tx" => {
+			let tx: TransactionV2 = serde_json::from_value(entry.param("tx"))
+				.map_err(|e| ErrorKind::Format(format!("Journaled transaction: {}", e)))?;
+			let fluff = entry.param("fluff").as_bool().unwrap_or(false);
+			api.post_tx(keychain_mask, &Transaction::from(tx), fluff)
+		}
+		"post_raw_tx" => {
+			let raw_tx = entry.param("raw_tx");
+			let raw_tx = raw_tx
+				.as_str()
+				.ok_or_else(|| ErrorKind::Format("Journaled raw transaction".to_owned()))?;
+			let fluff = entry.param("fluff").as_bool().unwrap_or(false);
+			api.post_raw_tx(keychain_mask, raw_tx, fluff)
+		}
+		"cancel_tx" => {
+			let tx_id = entry.param("tx_id").as_u64().map(|i| i as u32);
+			let tx_slate_id = serde_json::from_value(entry.param("tx_slate_id")).unwrap_or(None);
+			let (_, txs) = api.retrieve_txs(keychain_mask, false, tx_id, tx_slate_id)?;
+			let cancelled = txs.iter().all(|t| {
+				t.tx_type == TxLogEntryType::TxSentCancelled
+					|| t.tx_type == TxLogEntryType::TxReceivedCancelled
+			});
+			if cancelled {
+				return Ok(());
+			}
+			api.cancel_tx(keychain_mask, tx_id, tx_slate_id)
+		}
+		_ => Ok(()),
+	}
+}
+
+/// Cancel the sent tx of the given slate if it was never finalized
+fn cancel_unfinalized<'a, C, K>(
+	ctx: &mut OwnerContext<'_, 'a, C, K>,
+	slate_id: Uuid,
+) -> Result<(), Error>
+where
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let (_, txs) = ctx.retrieve_txs(false, None, Some(slate_id))?;
+	let interrupted = txs
+		.iter()
+		.find(|t| t.tx_type == TxLogEntryType::TxSent && !t.confirmed && t.stored_tx.is_none());
+	if let Some(t) = interrupted {
+		warn!("Cancelling transaction {} of the interrupted request", t.id);
+		ctx.cancel_tx(Some(t.id), None)?;
+	}
+	Ok(())
+}
@@ -33,7 +33,9 @@ use grin_wallet_util::grin_util as util;
 
 pub mod command;
 pub mod controller;
+pub mod daemon;
 pub mod display;
 mod error;
+pub mod journal;
 
 pub use crate::error::{Error, ErrorKind};
@@ -0,0 +1,139 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test received transactions the sender never posted being abandoned
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_config as config;
+use grin_wallet_libwallet as libwallet;
+
+use config::WalletConfig;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, Slate};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// abandon impl
+fn abandon_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	// Wallet 1 sends twice and never posts
+	let amount = 1_000_000_000;
+	let mut slates = vec![];
+	for _ in 0..2 {
+		let mut slate = Slate::blank(1);
+		wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: false,
+				..Default::default()
+			};
+			slate = api.init_send_tx(m, args)?;
+			slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+			api.tx_lock_outputs(m, &slate, 0)?;
+			Ok(())
+		})?;
+		slates.push(slate);
+	}
+
+	// The first is given up on by hand, the recipient output kept
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.amount_awaiting_finalization, 2 * amount);
+		assert_eq!(info.amount_abandoned, 0);
+		api.abandon_tx(m, None, Some(slates[0].id))?;
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.amount_awaiting_finalization, amount);
+		assert_eq!(info.amount_abandoned, amount);
+		let (_, outputs) = api.retrieve_outputs(m, false, false, None)?;
+		assert_eq!(outputs.len(), 2);
+		Ok(())
+	})?;
+
+	// The second once the wallet abandons what isn't posted right away
+	set_wallet_config!(
+		wallet2,
+		WalletConfig {
+			abandon_received_after_hours: Some(0),
+			..WalletConfig::default()
+		}
+	);
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.amount_awaiting_finalization, 0);
+		assert_eq!(info.amount_abandoned, 2 * amount);
+		let (_, txs) = api.retrieve_txs(m, false, None, Some(slates[1].id))?;
+		assert!(txs[0].abandoned);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_abandon() {
+	let test_dir = "test_output/abandon";
+	setup(test_dir);
+	if let Err(e) = abandon_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
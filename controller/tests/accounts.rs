@@ -24,7 +24,7 @@ use self::core::global;
 use self::keychain::{ExtKeychain, Keychain};
 use grin_wallet_libwallet as libwallet;
 use impls::test_framework::{self, LocalWalletClient};
-use libwallet::InitTxArgs;
+use libwallet::{DerivationScheme, InitTxArgs};
 use std::thread;
 use std::time::Duration;
 
@@ -91,6 +91,14 @@ fn accounts_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		assert_eq!(new_path, ExtKeychain::derive_key_id(2, 2, 0, 0, 0));
 		let new_path = api.create_account_path(m, "account3").unwrap();
 		assert_eq!(new_path, ExtKeychain::derive_key_id(2, 3, 0, 0, 0));
+		// hardened accounts share the account index with standard ones
+		let scheme = DerivationScheme::Hardened;
+		let new_path = api.create_account_path_with_scheme(m, "hardened", scheme)?;
+		assert_eq!(
+			new_path,
+			ExtKeychain::derive_key_id(2, 4 | 1 << 31, 0, 0, 0)
+		);
+		assert_eq!(DerivationScheme::of(&new_path), scheme);
 		// trying to add same label again should fail
 		let res = api.create_account_path(m, "account1");
 		assert!(res.is_err());
@@ -257,6 +265,23 @@ fn accounts_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
 		Ok(())
 	})?;
 
+	// derivation index counter of account2 on wallet 1
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let scan = api.scan_child_indices(m, "account2")?;
+		let next = api.get_next_child_index(m, "account2")?;
+		assert_eq!(scan.next_child_index, next);
+		assert_eq!(scan.highest_used_index, Some(next - 1));
+		assert!(scan.unused_ranges.is_empty());
+		assert!(scan.used_beyond_next.is_empty());
+		// can't move the counter back over used keys
+		assert!(api.set_next_child_index(m, "account2", next - 1).is_err());
+		api.set_next_child_index(m, "account2", next + 10)?;
+		let scan = api.scan_child_indices(m, "account2")?;
+		assert_eq!(scan.next_child_index, next + 10);
+		assert_eq!(scan.unused_ranges, vec![(next, next + 9)]);
+		Ok(())
+	})?;
+
 	// let logging finish
 	thread::sleep(Duration::from_millis(200));
 	Ok(())
@@ -0,0 +1,149 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test finalizing several invoices as one aggregated transaction
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, IssueInvoiceTxArgs, Slate};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// aggregate invoice impl
+fn aggregate_invoice_tx_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	// Wallet 2 issues three invoices, wallet 1 pays them all
+	let mut slates = vec![];
+	for amount in vec![reward / 2, reward / 4, reward / 8] {
+		let mut slate = Slate::blank(2);
+		wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+			let args = IssueInvoiceTxArgs {
+				amount,
+				..Default::default()
+			};
+			slate = api.issue_invoice_tx(m, args)?;
+			Ok(())
+		})?;
+		wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount: slate.amount,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: false,
+				..Default::default()
+			};
+			slate = api.process_invoice_tx(m, &slate, args)?;
+			api.tx_lock_outputs(m, &slate, 0)?;
+			Ok(())
+		})?;
+		slates.push(slate);
+	}
+
+	// No slates, or a slate given twice, are refused before any is finalized
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let twice = vec![slates[0].clone(), slates[0].clone()];
+		assert!(api.finalize_invoice_txs_aggregated(m, &twice).is_err());
+		assert!(api.finalize_invoice_txs_aggregated(m, &[]).is_err());
+		Ok(())
+	})?;
+
+	// Wallet 2 finalizes them as one transaction, posted by wallet 1 so that
+	// wallet 2 doesn't get the mined amount
+	let mut tx = None;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let agg = api.finalize_invoice_txs_aggregated(m, &slates)?;
+		assert_eq!(agg.kernels().len(), 3);
+		tx = Some(agg);
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.post_tx(m, tx.as_ref().unwrap(), false)?;
+		Ok(())
+	})?;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+
+	// All three payments were received
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		assert_eq!(txs.len(), 3);
+		assert!(txs.iter().all(|t| t.confirmed));
+		assert_eq!(
+			info.amount_currently_spendable,
+			reward / 2 + reward / 4 + reward / 8
+		);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_aggregate_invoice_tx() -> Result<(), libwallet::Error> {
+	let test_dir = "test_output/aggregate_invoice_tx";
+	setup(test_dir);
+	aggregate_invoice_tx_impl(test_dir)?;
+	clean_output_dir(test_dir);
+	Ok(())
+}
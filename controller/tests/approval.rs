@@ -0,0 +1,378 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test dual-control approval of sent transactions
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_config as config;
+use grin_wallet_util::grin_core as core;
+use grin_wallet_util::grin_util as util;
+
+use config::WalletConfig;
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{ApprovalToken, ErrorKind, InitTxArgs, IssueInvoiceTxArgs, Slate};
+use std::thread;
+use std::time::Duration;
+use util::ZeroingString;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// approval impl
+fn approval_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	// Each operator gets their own approval token
+	let mut tokens = vec![];
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		tokens.push(api.create_approval_token(m, Some("alice".to_owned()))?);
+		tokens.push(api.create_approval_token(m, Some("bob".to_owned()))?);
+		assert_eq!(api.retrieve_approval_tokens(m)?.len(), 2);
+		Ok(())
+	})?;
+	let (alice, alice_record) = tokens[0].clone();
+	let (bob, bob_record) = tokens[1].clone();
+	assert_ne!(alice, bob);
+	assert_eq!(alice_record.token_hash, ApprovalToken::hash(&alice));
+
+	let args = |token: Option<String>| InitTxArgs {
+		src_acct_name: None,
+		amount: reward,
+		minimum_confirmations: 2,
+		max_outputs: 500,
+		num_change_outputs: 1,
+		selection_strategy_is_use_all: true,
+		requires_approval: Some(true),
+		approval_token: token,
+		..Default::default()
+	};
+
+	// First operator creates a tx requiring approval, and can't push it through alone
+	let mut slate = Slate::blank(2);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		for token in vec![None, Some("unknown".to_owned())] {
+			match api.init_send_tx(m, args(token)).unwrap_err().kind() {
+				ErrorKind::TransactionApprovalError(_) => {}
+				e => panic!("Unexpected error: {}", e),
+			}
+		}
+		slate = api.init_send_tx(m, args(Some(alice.clone())))?;
+		slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+
+		let res = api.finalize_tx(m, &slate);
+		match res.unwrap_err().kind() {
+			ErrorKind::TransactionApprovalRequired(_) => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+		for token in vec![alice.as_str(), "unknown"] {
+			match api
+				.approve_tx(m, token, None, Some(slate.id))
+				.unwrap_err()
+				.kind()
+			{
+				ErrorKind::TransactionApprovalError(_) => {}
+				e => panic!("Unexpected error: {}", e),
+			}
+		}
+		let (_, txs) = api.retrieve_txs(m, true, None, Some(slate.id))?;
+		let approval = txs[0].approval.as_ref().unwrap();
+		assert_eq!(approval.created_by, alice_record.id.to_string());
+		assert!(!approval.is_approved());
+		Ok(())
+	})?;
+
+	// Reopening the wallet, with a new Owner API token, doesn't make the first
+	// operator a second one
+	let mask3_i = {
+		let mut w_lock = wallet1.lock();
+		let lc = w_lock.lc_provider()?;
+		lc.close_wallet(None)?;
+		lc.open_wallet(None, ZeroingString::from(""), true, false)?
+	};
+	let mask3 = (&mask3_i).as_ref();
+	wallet::controller::owner_single_use(wallet1.clone(), mask3, |api, m| {
+		assert_eq!(api.retrieve_approval_tokens(m)?.len(), 2);
+		let res = api.approve_tx(m, &alice, None, Some(slate.id));
+		match res.unwrap_err().kind() {
+			ErrorKind::TransactionApprovalError(_) => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+		Ok(())
+	})?;
+
+	// Second operator approves with their own token and completes the tx
+	wallet::controller::owner_single_use(wallet1.clone(), mask3, |api, m| {
+		api.approve_tx(m, &bob, None, Some(slate.id))?;
+		let (_, txs) = api.retrieve_txs(m, true, None, Some(slate.id))?;
+		let approval = txs[0].approval.as_ref().unwrap();
+		assert_eq!(approval.approved_by, Some(bob_record.id.to_string()));
+		slate = api.finalize_tx(m, &slate)?;
+		api.post_tx(m, &slate.tx, false)?;
+		Ok(())
+	})?;
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask3, 3, false);
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask3, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, true, None, Some(slate.id))?;
+		assert!(txs[0].confirmed);
+
+		// A revoked token can't create txs anymore
+		api.revoke_approval_token(m, bob_record.id)?;
+		assert_eq!(api.retrieve_approval_tokens(m)?.len(), 1);
+		match api
+			.init_send_tx(m, args(Some(bob.clone())))
+			.unwrap_err()
+			.kind()
+		{
+			ErrorKind::TransactionApprovalError(_) => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+/// approval policy impl
+fn approval_policy_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	let args = |token: Option<String>| InitTxArgs {
+		src_acct_name: None,
+		amount: reward / 2,
+		minimum_confirmations: 2,
+		max_outputs: 500,
+		num_change_outputs: 1,
+		selection_strategy_is_use_all: false,
+		approval_token: token,
+		..Default::default()
+	};
+
+	// Two sends made before approval is required, one of them finalized
+	let mut finalized = Slate::blank(2);
+	let mut unfinalized = Slate::blank(2);
+	let mut tokens = vec![];
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let slate = api.init_send_tx(m, args(None))?;
+		finalized = client1.send_tx_slate_direct("wallet2", &slate)?;
+		api.tx_lock_outputs(m, &finalized, 0)?;
+		finalized = api.finalize_tx(m, &finalized)?;
+
+		let slate = api.init_send_tx(m, args(None))?;
+		unfinalized = client1.send_tx_slate_direct("wallet2", &slate)?;
+		api.tx_lock_outputs(m, &unfinalized, 0)?;
+
+		tokens.push(api.create_approval_token(m, Some("alice".to_owned()))?.0);
+		tokens.push(api.create_approval_token(m, Some("bob".to_owned()))?.0);
+		Ok(())
+	})?;
+	let (alice, bob) = (tokens[0].clone(), tokens[1].clone());
+
+	set_wallet_config!(
+		wallet1,
+		WalletConfig {
+			require_tx_approval: Some(true),
+			..WalletConfig::default()
+		}
+	);
+
+	// Once it is, sends that weren't approved can't be finalized nor posted,
+	// whether they were asked to be approved or not
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		match api.finalize_tx(m, &unfinalized).unwrap_err().kind() {
+			ErrorKind::TransactionApprovalRequired(_) => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+		match api.post_tx(m, &finalized.tx, false).unwrap_err().kind() {
+			ErrorKind::TransactionApprovalRequired(_) => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+		api.cancel_tx(m, None, Some(finalized.id))?;
+		api.cancel_tx(m, None, Some(unfinalized.id))?;
+
+		// New sends need an approval token, and a second one to approve them
+		match api.init_send_tx(m, args(None)).unwrap_err().kind() {
+			ErrorKind::TransactionApprovalError(_) => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+		let slate = api.init_send_tx(m, args(Some(alice.clone())))?;
+		let mut slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		match api.finalize_tx(m, &slate).unwrap_err().kind() {
+			ErrorKind::TransactionApprovalRequired(_) => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+		api.approve_tx(m, &bob, None, Some(slate.id))?;
+		slate = api.finalize_tx(m, &slate)?;
+		api.post_tx(m, &slate.tx, false)?;
+		Ok(())
+	})?;
+
+	// Paying an invoice needs approval as well, the payment only being signed
+	// once approved
+	let mut slate = Slate::blank(2);
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let args = IssueInvoiceTxArgs {
+			amount: reward / 2,
+			..Default::default()
+		};
+		slate = api.issue_invoice_tx(m, args)?;
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		match api
+			.process_invoice_tx(m, &slate, args(None))
+			.unwrap_err()
+			.kind()
+		{
+			ErrorKind::TransactionApprovalError(_) => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+		slate = api.process_invoice_tx(m, &slate, args(Some(alice.clone())))?;
+		match api
+			.process_invoice_tx(m, &slate, args(Some(alice.clone())))
+			.unwrap_err()
+			.kind()
+		{
+			ErrorKind::TransactionApprovalRequired(_) => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+		Ok(())
+	})?;
+	assert!(!slate.participant_with_id(0).unwrap().is_complete());
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.approve_tx(m, &bob, None, Some(slate.id))?;
+		slate = api.process_invoice_tx(m, &slate, args(Some(alice.clone())))?;
+		assert!(slate.participant_with_id(0).unwrap().is_complete());
+		api.tx_lock_outputs(m, &slate, 0)?;
+		Ok(())
+	})?;
+	wallet::controller::foreign_single_use(wallet2.clone(), mask2_i.clone(), |api| {
+		slate = api.finalize_invoice_tx(&slate)?;
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.post_tx(m, &slate.tx, false)?;
+		Ok(())
+	})?;
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, true, None, Some(slate.id))?;
+		assert!(txs[0].confirmed);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_tx_approval() {
+	let test_dir = "test_output/approval";
+	setup(test_dir);
+	if let Err(e) = approval_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
+
+#[test]
+fn wallet_tx_approval_policy() {
+	let test_dir = "test_output/approval_policy";
+	setup(test_dir);
+	if let Err(e) = approval_policy_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
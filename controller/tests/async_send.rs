@@ -0,0 +1,146 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test sends dispatched in the background, timing out
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, InitTxSendArgs, OperationState, TxLogEntryType};
+use std::net::TcpListener;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// async send impl
+fn async_send_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+
+	// A recipient taking connections but never replying
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let dest = format!("http://{}", listener.local_addr().unwrap());
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let mut args = InitTxArgs {
+			src_acct_name: None,
+			amount: core::consensus::MWC_FIRST_GROUP_REWARD / 2,
+			minimum_confirmations: 1,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		assert!(api.init_send_tx_async(m, args.clone()).is_err());
+
+		args.send_args = Some(InitTxSendArgs {
+			method: "http".to_owned(),
+			dest: dest.clone(),
+			finalize: true,
+			post_tx: true,
+			fluff: false,
+			cancel_on_failure: None,
+			timeout_secs: Some(1),
+		});
+		let handle = api.init_send_tx_async(m, args)?;
+		let status = api.retrieve_operation_status(m, handle.operation_id)?;
+		assert_eq!(status.state, OperationState::Running);
+		assert_eq!(status.operation.tx_slate_id, Some(handle.tx_slate_id));
+
+		// The send times out, and is kept to be dispatched again
+		let start = Instant::now();
+		let status = loop {
+			let status = api.retrieve_operation_status(m, handle.operation_id)?;
+			if status.state != OperationState::Running {
+				break status;
+			}
+			assert!(start.elapsed() < Duration::from_secs(30));
+			thread::sleep(Duration::from_millis(100));
+		};
+		assert_eq!(status.state, OperationState::Failed);
+		assert!(status.error.unwrap().contains("within 1 seconds"));
+		assert!(status.finished.is_some());
+		let pending = api.retrieve_pending_dispatches(m, Some(handle.tx_slate_id))?;
+		assert_eq!(pending.len(), 1);
+		assert_eq!(pending[0].attempts, 1);
+
+		// The slate may have reached the recipient: its tx is cancelled rather than
+		// left with locked outputs
+		assert!(pending[0].cancelled);
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.amount_locked, 0);
+		let (_, txs) = api.retrieve_txs(m, true, None, Some(handle.tx_slate_id))?;
+		assert_eq!(txs[0].tx_type, TxLogEntryType::TxSentCancelled);
+
+		// A retry sends a new slate, the recipient wouldn't take the same one twice
+		assert!(api.dispatch_tx(m, handle.tx_slate_id).is_err());
+		assert!(api
+			.retrieve_pending_dispatches(m, Some(handle.tx_slate_id))?
+			.is_empty());
+		let pending = api.retrieve_pending_dispatches(m, None)?;
+		assert_eq!(pending.len(), 1);
+		assert_ne!(pending[0].slate_id, handle.tx_slate_id);
+		assert_eq!(pending[0].attempts, 2);
+		assert!(pending[0].cancelled);
+
+		assert!(api
+			.retrieve_operation_status(m, handle.operation_id + 100)
+			.is_err());
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_async_send() {
+	let test_dir = "test_output/async_send";
+	setup(test_dir);
+	if let Err(e) = async_send_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,251 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test slate attachments
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{ErrorKind, InitTxArgs, IssueInvoiceTxArgs, Slate, SlateAttachmentArgs};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+fn attachment(kind: &str, content: &str, encrypt: bool) -> SlateAttachmentArgs {
+	SlateAttachmentArgs {
+		kind: kind.to_owned(),
+		content: content.to_owned(),
+		encrypt,
+	}
+}
+
+/// attachments impl
+fn attachments_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	let mut slate = Slate::blank(2);
+
+	// Standard send, with a plain order id. Nothing can be encrypted yet.
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let mut args = InitTxArgs {
+			src_acct_name: None,
+			amount: reward,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: true,
+			attachments: Some(vec![attachment("refund_address", "http://wallet1", true)]),
+			..Default::default()
+		};
+		match api.init_send_tx(m, args.clone()).unwrap_err().kind() {
+			ErrorKind::SlateAttachment(_) => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+		args.attachments = Some(vec![attachment("order id", "1234", false)]);
+		match api.init_send_tx(m, args.clone()).unwrap_err().kind() {
+			ErrorKind::SlateAttachment(_) => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+		args.attachments = Some(vec![attachment("order_id", "1234", false)]);
+		slate = api.init_send_tx(m, args)?;
+		Ok(())
+	})?;
+
+	// Attachments are signed by the participant who added them, altered or unsigned
+	// ones are refused
+	let p = slate.participant_with_id(0).unwrap();
+	assert!(p.attachments[0].sig.is_some());
+	let mut altered = slate.clone();
+	altered.participant_data[0].attachments[0].content = "4321".to_owned();
+	let mut unsigned = slate.clone();
+	unsigned.participant_data[0].attachments[0].sig = None;
+	wallet::controller::foreign_single_use(wallet2.clone(), mask2_i.clone(), |api| {
+		for s in vec![altered, unsigned] {
+			match api.receive_tx(&s, None, None).unwrap_err().kind() {
+				ErrorKind::SlateAttachment(_) => {}
+				e => panic!("Unexpected error: {}", e),
+			}
+		}
+		Ok(())
+	})?;
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		slate = api.finalize_tx(m, &slate)?;
+		let attachments = api.retrieve_tx_attachments(m, None, Some(slate.id))?;
+		assert_eq!(attachments.len(), 1);
+		assert_eq!(attachments[0].content, Some("1234".to_owned()));
+		Ok(())
+	})?;
+
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let attachments = api.retrieve_tx_attachments(m, None, Some(slate.id))?;
+		assert_eq!(attachments.len(), 1);
+		assert_eq!(attachments[0].id, 0);
+		assert_eq!(attachments[0].kind, "order_id");
+		assert_eq!(attachments[0].content, Some("1234".to_owned()));
+		assert!(!attachments[0].encrypted);
+		Ok(())
+	})?;
+
+	// Invoice flow, the payer encrypts a refund address to the invoicer, which gives
+	// its own to pay it back in turn
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let args = IssueInvoiceTxArgs {
+			amount: reward,
+			attachments: Some(vec![
+				attachment("order_id", "5678", false),
+				SlateAttachmentArgs::refund_address("http://wallet2", false),
+			]),
+			..Default::default()
+		};
+		slate = api.issue_invoice_tx(m, args)?;
+		Ok(())
+	})?;
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: slate.amount,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: true,
+			attachments: Some(vec![SlateAttachmentArgs::refund_address(
+				"http://wallet1",
+				true,
+			)]),
+			..Default::default()
+		};
+		slate = api.process_invoice_tx(m, &slate, args)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		let attachments = api.retrieve_tx_attachments(m, None, Some(slate.id))?;
+		assert_eq!(attachments.len(), 3);
+		assert_eq!(attachments[2].content, Some("http://wallet1".to_owned()));
+		Ok(())
+	})?;
+
+	// Only the invoicer can read the encrypted content
+	let p = slate.participant_with_id(0).unwrap();
+	assert!(p.attachments[0].encrypted);
+	assert!(p.attachments[0].content != "http://wallet1");
+
+	wallet::controller::foreign_single_use(wallet2.clone(), mask2_i.clone(), |api| {
+		slate = api.finalize_invoice_tx(&slate)?;
+		Ok(())
+	})?;
+
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let attachments = api.retrieve_tx_attachments(m, None, Some(slate.id))?;
+		assert_eq!(attachments.len(), 3);
+		assert_eq!(attachments[0].kind, "order_id");
+		assert_eq!(attachments[0].content, Some("5678".to_owned()));
+		assert_eq!(attachments[1].kind, "refund_address");
+		assert_eq!(attachments[1].content, Some("http://wallet2".to_owned()));
+		assert_eq!(attachments[2].kind, "refund_address");
+		assert_eq!(attachments[2].content, Some("http://wallet1".to_owned()));
+		assert!(attachments[2].encrypted);
+		Ok(())
+	})?;
+
+	// Refunds need a confirmed tx, a refund address and a valid amount
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		match api.refund_tx(m, slate.id, None).unwrap_err().kind() {
+			ErrorKind::RefundError(_) => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+		Ok(())
+	})?;
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.post_tx(m, &slate.tx, false)?;
+		Ok(())
+	})?;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, true, None, Some(slate.id))?;
+		assert!(txs[0].confirmed);
+		// The refund goes to the payer, not to the invoicer's own address
+		assert_eq!(txs[0].participant_id, Some(1));
+		assert_eq!(txs[0].refund_address(), Some("http://wallet1"));
+		match api
+			.refund_tx(m, slate.id, Some(reward + 1))
+			.unwrap_err()
+			.kind()
+		{
+			ErrorKind::RefundError(_) => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_slate_attachments() {
+	let test_dir = "test_output/attachments";
+	setup(test_dir);
+	if let Err(e) = attachments_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,184 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test auditing the derivation of the outputs created by a transaction
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_api::{Owner, OwnerRpc, OwnerRpcS, Token};
+use grin_wallet_libwallet as libwallet;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, OutputStatus, Slate};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// audit outputs impl
+fn audit_outputs_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	let amount = 1_000_000_000;
+	let mut slate = Slate::blank(1);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: amount,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		let slate_i = api.init_send_tx(m, args)?;
+		slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		slate = api.finalize_tx(m, &slate)?;
+		Ok(())
+	})?;
+
+	// Before the tx is on the chain, only the commitments can be rebuilt
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let audits = api.audit_tx_outputs(m, None, Some(slate.id))?;
+		assert_eq!(audits.len(), 1);
+		assert!(audits[0].is_change);
+		assert!(audits[0].recoverable);
+		assert!(!audits[0].rangeproof_checked);
+		assert!(audits[0].issues.is_empty());
+		api.post_tx(m, &slate.tx, false)?;
+		Ok(())
+	})?;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+
+	// Once it is, their range proofs rewind with the wallet seed, on either side
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.retrieve_summary_info(m, true, 1)?;
+		let audits = api.audit_tx_outputs(m, None, Some(slate.id))?;
+		assert_eq!(audits.len(), 1);
+		assert_eq!(audits[0].output.status, OutputStatus::Unspent);
+		assert!(audits[0].recoverable);
+		assert!(audits[0].rangeproof_checked);
+		assert!(!audits[0].index_reused);
+		assert_eq!(audits[0].derivation_index, audits[0].output.n_child);
+		assert!(audits[0].issues.is_empty());
+		Ok(())
+	})?;
+	let mut received = None;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		api.retrieve_summary_info(m, true, 1)?;
+		let audits = api.audit_tx_outputs(m, None, Some(slate.id))?;
+		assert_eq!(audits.len(), 1);
+		assert!(!audits[0].is_change);
+		assert_eq!(audits[0].output.value, amount);
+		assert!(audits[0].recoverable);
+		assert!(audits[0].rangeproof_checked);
+		assert!(audits[0].issues.is_empty());
+		received = Some(audits[0].output.clone());
+		Ok(())
+	})?;
+
+	// An output whose record doesn't match its commitment and range proof isn't
+	// recoverable, and one sharing its key with another is flagged
+	let received = received.unwrap();
+	{
+		wallet_inst!(wallet2, w);
+		let mut batch = w.batch(mask2)?;
+		let mut tampered = received.clone();
+		tampered.value = amount + 1;
+		batch.save(tampered)?;
+		let mut copy = received.clone();
+		copy.commit = None;
+		copy.mmr_index = Some(1);
+		copy.tx_log_entry = None;
+		batch.save(copy)?;
+		batch.commit()?;
+	}
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let audits = api.audit_tx_outputs(m, None, Some(slate.id))?;
+		assert_eq!(audits.len(), 1);
+		assert!(!audits[0].recoverable);
+		assert!(audits[0].rangeproof_checked);
+		assert!(audits[0].index_reused);
+		assert_eq!(audits[0].issues.len(), 3);
+		Ok(())
+	})?;
+
+	// The audit is available through both versions of the owner RPC API
+	let api = Owner::new(wallet1.clone());
+	let token = Token {
+		keychain_mask: mask1_i.clone(),
+	};
+	let audits = OwnerRpcS::audit_tx_outputs(&api, token, None, Some(slate.id)).unwrap();
+	assert_eq!(audits.len(), 1);
+	assert!(audits[0].recoverable);
+	let audits = OwnerRpc::audit_tx_outputs(&api, None, Some(slate.id)).unwrap();
+	assert_eq!(audits.len(), 1);
+	let token = Token {
+		keychain_mask: mask1_i.clone(),
+	};
+	assert!(OwnerRpcS::audit_tx_outputs(&api, token, None, None).is_err());
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_audit_outputs() {
+	let test_dir = "test_output/audit_outputs";
+	setup(test_dir);
+	if let Err(e) = audit_outputs_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,134 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test balance holds for checkout sessions
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{ErrorKind, InitTxArgs};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// balance hold impl
+fn balance_hold_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		let spendable = info.amount_currently_spendable;
+		assert_eq!(info.amount_held, 0);
+		assert_eq!(info.amount_available, spendable);
+
+		// Hold one reward for a checkout session
+		let hold = api.create_hold(m, reward, 3600, "order-1")?;
+		assert_eq!(hold.amount, reward);
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.amount_currently_spendable, spendable);
+		assert_eq!(info.amount_held, reward);
+		assert_eq!(info.amount_available, spendable - reward);
+
+		// References are unique and holds can't exceed the available balance
+		assert!(api.create_hold(m, reward, 3600, "order-1").is_err());
+		match api
+			.create_hold(m, spendable, 3600, "order-2")
+			.unwrap_err()
+			.kind()
+		{
+			ErrorKind::NotEnoughFunds { .. } => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+
+		// Ad-hoc sends can't spend the held amount
+		let mut args = InitTxArgs {
+			src_acct_name: None,
+			amount: spendable - reward,
+			minimum_confirmations: 1,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: true,
+			estimate_only: Some(true),
+			..Default::default()
+		};
+		match api.init_send_tx(m, args.clone()).unwrap_err().kind() {
+			ErrorKind::NotEnoughFunds { .. } => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+
+		// The checkout can, using up the hold
+		args.hold = Some("order-1".to_owned());
+		api.init_send_tx(m, args.clone())?;
+		args.amount = reward;
+		args.estimate_only = None;
+		api.init_send_tx(m, args.clone())?;
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.amount_held, 0);
+		assert!(api.init_send_tx(m, args).is_err());
+
+		// Released holds no longer count against the balance
+		api.create_hold(m, reward, 3600, "order-2")?;
+		api.release_hold(m, "order-2")?;
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.amount_held, 0);
+		assert_eq!(info.amount_available, info.amount_currently_spendable);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_balance_hold() {
+	let test_dir = "test_output/balance_hold";
+	setup(test_dir);
+	if let Err(e) = balance_hold_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,148 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test restoring a wallet from its birthday
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_util::ZeroingString;
+
+use impls::test_framework::{self, LocalWalletClient};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// birthday restore impl
+fn birthday_restore_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	let seed_phrase = "affair pistol cancel crush garment candy ancient flag work \
+	                   market crush dry stand focus mutual weapon offer ceiling rival turn team spring \
+	                   where swift";
+	let seed_phrase = Some(ZeroingString::from(seed_phrase));
+
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		seed_phrase,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		seed_phrase,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+	create_wallet_and_add!(
+		client3,
+		wallet3,
+		mask3_i,
+		test_dir,
+		"wallet3",
+		seed_phrase,
+		&mut wallet_proxy,
+		false
+	);
+	let mask3 = (&mask3_i).as_ref();
+	create_wallet_and_add!(
+		m_client,
+		miner,
+		miner_mask_i,
+		test_dir,
+		"miner",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let miner_mask = (&miner_mask_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	// Outputs before and after the birthday, with someone else's in between
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 2, false);
+	let _ = test_framework::award_blocks_to_wallet(&chain, miner.clone(), miner_mask, 3, false);
+	let birthday = chain.head().unwrap().height + 1;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+
+	// Only the outputs created since the birthday are restored
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		api.restore_from_birthday(m, birthday)?;
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		assert_eq!(outputs.len(), 3);
+		assert!(outputs.iter().all(|o| o.output.height >= birthday));
+		Ok(())
+	})?;
+
+	// A step-wise restore from the birthday starts past the last position of the
+	// output MMR as of the block before, and finds the same outputs
+	wallet::controller::owner_single_use(wallet3.clone(), mask3, |api, m| {
+		let header = chain.get_header_by_height(birthday - 1).unwrap();
+		let mut scan = api.restore_scan_start(m, Some(birthday))?;
+		assert_eq!(scan.start_index, header.output_mmr_size + 1);
+		while !scan.is_complete() {
+			scan = api.restore_scan(m, scan, 1000)?;
+		}
+		assert_eq!(scan.outputs.len(), 3);
+		assert!(scan
+			.outputs
+			.iter()
+			.all(|o| o.mmr_index > header.output_mmr_size));
+		Ok(())
+	})?;
+
+	// While a full restore finds them all
+	wallet::controller::owner_single_use(wallet3.clone(), mask3, |api, m| {
+		api.restore(m)?;
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		assert_eq!(outputs.len(), 5);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_birthday_restore() {
+	let test_dir = "test_output/birthday_restore";
+	setup(test_dir);
+	if let Err(e) = birthday_restore_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
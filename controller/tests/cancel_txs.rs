@@ -0,0 +1,172 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test cancelling the transactions selected by a filter
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use core::global;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, Slate, TxFilter, TxLogEntryType};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// cancel txs impl
+fn cancel_txs_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let cm = global::coinbase_maturity();
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	// Two sends wallet 1 never posts
+	let amount = 1_000_000_000;
+	let mut slates = vec![];
+	for _ in 0..2 {
+		wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount: amount,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: false,
+				..Default::default()
+			};
+			let slate_i = api.init_send_tx(m, args)?;
+			let mut slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
+			api.tx_lock_outputs(m, &slate, 0)?;
+			slate = api.finalize_tx(m, &slate)?;
+			slates.push(slate);
+			Ok(())
+		})?;
+	}
+	let slate_ids: Vec<_> = slates.iter().map(|s: &Slate| Some(s.id)).collect();
+
+	// Wallet 2 cancels what it received
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		// nothing sent
+		let mut filter = TxFilter {
+			tx_type: Some(TxLogEntryType::TxSent),
+			..Default::default()
+		};
+		assert!(api.cancel_txs(m, &filter)?.is_empty());
+		// nothing that old
+		filter.tx_type = Some(TxLogEntryType::TxReceived);
+		filter.min_age_secs = Some(3600);
+		assert!(api.cancel_txs(m, &filter)?.is_empty());
+		// nothing in another account, and no account that doesn't exist
+		api.create_account_path(m, "other")?;
+		filter.min_age_secs = None;
+		filter.account = Some("other".to_owned());
+		assert!(api.cancel_txs(m, &filter)?.is_empty());
+		filter.account = Some("missing".to_owned());
+		assert!(api.cancel_txs(m, &filter).is_err());
+
+		filter.account = Some("default".to_owned());
+		let results = api.cancel_txs(m, &filter)?;
+		assert_eq!(results.len(), 2);
+		for r in &results {
+			assert!(slate_ids.contains(&r.tx_slate_id));
+			assert!(r.error.is_none());
+		}
+		let (_, txs) = api.retrieve_txs(m, false, None, None)?;
+		assert_eq!(txs.len(), 2);
+		assert!(txs
+			.iter()
+			.all(|t| t.tx_type == TxLogEntryType::TxReceivedCancelled));
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.amount_awaiting_finalization, 0);
+		assert_eq!(info.total, 0);
+
+		// cancelled txs aren't selected again
+		assert!(api.cancel_txs(m, &TxFilter::default())?.is_empty());
+		Ok(())
+	})?;
+
+	// Wallet 1 cancels everything unconfirmed, which leaves the coinbases alone
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let results = api.cancel_txs(m, &TxFilter::default())?;
+		assert_eq!(results.len(), 2);
+		assert!(results.iter().all(|r| r.error.is_none()));
+		let (_, txs) = api.retrieve_txs(m, false, None, None)?;
+		let sent_cancelled = txs
+			.iter()
+			.filter(|t| t.tx_type == TxLogEntryType::TxSentCancelled)
+			.count();
+		assert_eq!(sent_cancelled, 2);
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(
+			info.amount_currently_spendable,
+			(info.last_confirmed_height - cm) * reward
+		);
+		assert!(api.cancel_txs(m, &TxFilter::default())?.is_empty());
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_cancel_txs() {
+	let test_dir = "test_output/cancel_txs";
+	setup(test_dir);
+	if let Err(e) = cancel_txs_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,123 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test wallets of different chains in the same process
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use core::consensus;
+use core::global::{self, ChainTypes};
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::NodeClient;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// chain types impl
+fn chain_types_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Wallet 2 believes its node runs floonet, while the process runs the
+	// automated testing chain
+	{
+		wallet_inst!(wallet2, w);
+		w.w2n_client().set_chain_type(ChainTypes::Floonet);
+	}
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 1, false);
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet2.clone(), mask2, 1, false);
+
+	// Coinbase outputs are locked for the maturity of each wallet's own chain
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		assert_eq!(outputs.len(), 1);
+		let o = &outputs[0].output;
+		assert_eq!(
+			o.lock_height - o.height,
+			global::AUTOMATED_TESTING_COINBASE_MATURITY
+		);
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		assert_eq!(outputs.len(), 1);
+		let o = &outputs[0].output;
+		assert_eq!(o.lock_height - o.height, consensus::COINBASE_MATURITY);
+		Ok(())
+	})?;
+
+	// and so is the weight of their blocks
+	assert_eq!(client1.max_block_weight(), global::TESTING_MAX_BLOCK_WEIGHT);
+	{
+		wallet_inst!(wallet2, w);
+		assert_eq!(
+			w.w2n_client().max_block_weight(),
+			consensus::MAX_BLOCK_WEIGHT
+		);
+	}
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_chain_types() {
+	let test_dir = "test_output/chain_types";
+	setup(test_dir);
+	if let Err(e) = chain_types_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,182 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the change journal of the wallet data
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{ChangeEntity, ChangeOp, InitTxArgs, OutputStatus, Slate};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// change journal impl
+fn change_journal_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	// Every change is recorded in order, and the records match the change
+	// cursors of the data
+	let mut cursor = 0;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let records = api.retrieve_change_journal(m, None, None)?;
+		assert!(!records.is_empty());
+		assert!(records.windows(2).all(|r| r[0].counter < r[1].counter));
+		assert_eq!(
+			records.last().unwrap().counter,
+			api.retrieve_txs_since(m, false, 0)?.1
+		);
+		let (_, outputs) = api.retrieve_outputs(m, false, false, None)?;
+		assert_eq!(outputs.len(), 5);
+		for o in outputs {
+			let record = records
+				.iter()
+				.find(|r| r.counter == o.output.change_seq)
+				.unwrap();
+			assert_eq!(record.op, ChangeOp::Save);
+			assert_eq!(record.entity, ChangeEntity::Output);
+			assert_eq!(record.id, o.output.key_id.to_string());
+		}
+
+		// only the records after the given counter, up to the given number
+		let since = records[records.len() / 2].counter;
+		let later = api.retrieve_change_journal(m, Some(since), None)?;
+		assert!(!later.is_empty());
+		assert!(later.iter().all(|r| r.counter > since));
+		assert_eq!(
+			later.len(),
+			records.iter().filter(|r| r.counter > since).count()
+		);
+		let first = api.retrieve_change_journal(m, None, Some(2))?;
+		assert_eq!(first.len(), 2);
+		assert_eq!(first[0].counter, records[0].counter);
+		assert_eq!(first[1].counter, records[1].counter);
+
+		cursor = records.last().unwrap().counter;
+		assert!(api
+			.retrieve_change_journal(m, Some(cursor), None)?
+			.is_empty());
+
+		// a new account is a change too
+		api.create_account_path(m, "savings")?;
+		let records = api.retrieve_change_journal(m, Some(cursor), None)?;
+		assert!(records
+			.iter()
+			.any(|r| r.entity == ChangeEntity::Account && r.id == "savings"));
+		cursor = records.last().unwrap().counter;
+		Ok(())
+	})?;
+
+	let mut slate = Slate::blank(1);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: 1_000_000_000,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		let slate_i = api.init_send_tx(m, args)?;
+		slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		slate = api.finalize_tx(m, &slate)?;
+		Ok(())
+	})?;
+
+	// The cancellation of the send is in the journal, its change output deleted
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, false, None, Some(slate.id))?;
+		let tx = txs[0].clone();
+		let (_, outputs) = api.retrieve_outputs(m, false, false, Some(tx.id))?;
+		let change = outputs
+			.iter()
+			.find(|o| o.output.status == OutputStatus::Unconfirmed)
+			.unwrap();
+		let change_id = change.output.key_id.to_string();
+		// the send saved its outputs and the tx
+		let saved = api.retrieve_change_journal(m, Some(cursor), None)?;
+		assert!(saved
+			.iter()
+			.any(|r| r.op == ChangeOp::Save && r.id == change_id));
+		cursor = saved.last().unwrap().counter;
+
+		api.cancel_tx(m, Some(tx.id), None)?;
+		let records = api.retrieve_change_journal(m, Some(cursor), None)?;
+		assert!(records.windows(2).all(|r| r[0].counter < r[1].counter));
+		assert!(records.iter().any(|r| r.op == ChangeOp::Delete
+			&& r.entity == ChangeEntity::Output
+			&& r.id == change_id));
+		let tx_id = format!("{}/{}", tx.parent_key_id, tx.id);
+		assert!(records
+			.iter()
+			.any(|r| r.entity == ChangeEntity::TxLogEntry && r.id == tx_id));
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_change_journal() {
+	let test_dir = "test_output/change_journal";
+	setup(test_dir);
+	if let Err(e) = change_journal_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
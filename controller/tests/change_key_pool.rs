@@ -0,0 +1,133 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test sends taking their change keys from a pool reserved ahead
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_config as config;
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use config::WalletConfig;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, OutputStatus};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// change key pool impl
+fn change_key_pool_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+	{
+		let mut w_lock = wallet1.lock();
+		let lc = w_lock.lc_provider()?;
+		lc.set_wallet_config(WalletConfig {
+			change_key_pool_size: Some(5),
+			..WalletConfig::default()
+		});
+	}
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		for _ in 0..2 {
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount: reward / 2,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: false,
+				..Default::default()
+			};
+			let slate = api.init_send_tx(m, args)?;
+			api.tx_lock_outputs(m, &slate, 0)?;
+		}
+
+		// Both change keys came from the five reserved by the first send
+		assert_eq!(api.get_next_child_index(m, "default")?, 5);
+		let (_, outputs) = api.retrieve_outputs(m, false, false, None)?;
+		let mut change: Vec<u32> = outputs
+			.iter()
+			.filter(|o| o.output.status == OutputStatus::Unconfirmed)
+			.map(|o| o.output.n_child)
+			.collect();
+		change.sort();
+		assert_eq!(change, vec![0, 1]);
+
+		let metrics = api.retrieve_send_metrics(m)?;
+		assert_eq!(metrics.sends, 2);
+		assert!(metrics.total_us >= metrics.refresh_us + metrics.selection_us + metrics.build_us);
+		Ok(())
+	})?;
+
+	// The metrics are each wallet's own
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		assert_eq!(api.retrieve_send_metrics(m)?.sends, 0);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_change_key_pool() {
+	let test_dir = "test_output/change_key_pool";
+	setup(test_dir);
+	if let Err(e) = change_key_pool_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,174 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test retrieving the txs and outputs changed since a cursor
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, OutputStatus, Slate};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// changes since impl
+fn changes_since_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	// Nothing changed since the cursor of a full fetch
+	let mut cursor = 0;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (refreshed, next, txs) = api.retrieve_txs_since(m, true, cursor)?;
+		assert!(refreshed);
+		assert_eq!(txs.len(), 5);
+		let (_, _, outputs) = api.retrieve_outputs_since(m, false, cursor)?;
+		assert_eq!(outputs.len(), 5);
+		assert!(next > cursor);
+		cursor = next;
+		let (_, next, txs) = api.retrieve_txs_since(m, false, cursor)?;
+		assert!(txs.is_empty());
+		assert!(api.retrieve_outputs_since(m, false, cursor)?.2.is_empty());
+		assert_eq!(next, cursor);
+		Ok(())
+	})?;
+
+	let amount = 1_000_000_000;
+	let mut slate = Slate::blank(1);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: amount,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		let slate_i = api.init_send_tx(m, args)?;
+		slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		slate = api.finalize_tx(m, &slate)?;
+		Ok(())
+	})?;
+
+	// Only the send and the outputs it locked and created changed
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, next, txs) = api.retrieve_txs_since(m, false, cursor)?;
+		assert_eq!(txs.len(), 1);
+		assert_eq!(txs[0].tx_slate_id, Some(slate.id));
+		assert!(txs[0].change_seq > cursor);
+		let (_, _, outputs) = api.retrieve_outputs_since(m, false, cursor)?;
+		assert!(outputs.iter().all(|o| o.output.change_seq > cursor));
+		let locked = outputs
+			.iter()
+			.filter(|o| o.output.status == OutputStatus::Locked)
+			.count();
+		let unconfirmed = outputs
+			.iter()
+			.filter(|o| o.output.status == OutputStatus::Unconfirmed)
+			.count();
+		assert_eq!(locked, 1);
+		assert_eq!(unconfirmed, 1);
+		assert!(next > cursor);
+		cursor = next;
+		Ok(())
+	})?;
+
+	// The recipient's changes are its own
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, next, txs) = api.retrieve_txs_since(m, false, 0)?;
+		assert_eq!(txs.len(), 1);
+		assert_eq!(txs[0].tx_slate_id, Some(slate.id));
+		let (_, _, outputs) = api.retrieve_outputs_since(m, false, 0)?;
+		assert_eq!(outputs.len(), 1);
+		assert_eq!(outputs[0].output.value, amount);
+		assert!(api.retrieve_txs_since(m, false, next)?.2.is_empty());
+		Ok(())
+	})?;
+
+	// Cancelling the send changes it again, along with its outputs
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.cancel_tx(m, None, Some(slate.id))?;
+		let (_, _, txs) = api.retrieve_txs_since(m, false, cursor)?;
+		let tx = txs
+			.iter()
+			.find(|t| t.tx_slate_id == Some(slate.id))
+			.unwrap();
+		assert!(tx.change_seq > cursor);
+		// the unlocked input is back, the deleted change output gone
+		let (_, _, outputs) = api.retrieve_outputs_since(m, false, cursor)?;
+		assert!(outputs
+			.iter()
+			.any(|o| o.output.status == OutputStatus::Unspent));
+		assert!(outputs
+			.iter()
+			.all(|o| o.output.status == OutputStatus::Unspent));
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_changes_since() {
+	let test_dir = "test_output/changes_since";
+	setup(test_dir);
+	if let Err(e) = changes_since_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
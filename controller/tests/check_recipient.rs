@@ -0,0 +1,151 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test checking a recipient can receive before sending to it
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_api::{Owner, OwnerRpc};
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_util as util;
+
+use impls::test_framework::LocalWalletClient;
+use impls::ReceiveChallenges;
+use libwallet::CURRENT_SLATE_VERSION;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use util::Mutex;
+use wallet::controller::{ForeignApiRoles, ListenerLimits};
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// check recipient impl
+fn check_recipient_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	// Wallet 2 receives on a listener, and on another requiring a challenge solved
+	let open_addr = "127.0.0.1:34419";
+	let challenge_addr = "127.0.0.1:34420";
+	for (addr, challenges) in vec![
+		(open_addr, None),
+		(challenge_addr, Some(ReceiveChallenges::new(8)?)),
+	] {
+		let listener_wallet = wallet2.clone();
+		let listener_mask = Arc::new(Mutex::new(mask2_i.clone()));
+		thread::spawn(move || {
+			if let Err(e) = wallet::controller::foreign_listener(
+				listener_wallet,
+				listener_mask,
+				addr,
+				None,
+				None,
+				ListenerLimits::default(),
+				challenges,
+				ForeignApiRoles::default(),
+			) {
+				error!("Foreign listener error: {}", e);
+			}
+		});
+	}
+	thread::sleep(Duration::from_millis(500));
+
+	let open_url = format!("http://{}", open_addr);
+	let challenge_url = format!("http://{}", challenge_addr);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		// A listening recipient reports what a send to it would go through
+		let handshake = api.check_recipient(m, "http", &open_url)?;
+		assert_eq!(handshake.method, "http");
+		assert!(handshake.dest.starts_with(&open_url));
+		assert!(handshake.foreign_api_version >= 2);
+		assert!(handshake
+			.supported_slate_versions
+			.contains(&format!("V{}", CURRENT_SLATE_VERSION)));
+		assert_eq!(handshake.slate_version, CURRENT_SLATE_VERSION);
+		assert!(handshake.receive_challenge_difficulty.is_none());
+		assert!(handshake.slate_exchange);
+
+		let handshake = api.check_recipient(m, "http", &challenge_url)?;
+		assert_eq!(handshake.receive_challenge_difficulty, Some(8));
+
+		// One that isn't listening, or a method without recipient, fails the check
+		assert!(api
+			.check_recipient(m, "http", "http://127.0.0.1:34421")
+			.is_err());
+		assert!(api.check_recipient(m, "file", "tx.part").is_err());
+
+		// and nothing is built or sent by the checks
+		let (_, txs) = api.retrieve_txs(m, false, None, None)?;
+		assert!(txs.is_empty());
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet2.clone(), (&mask2_i).as_ref(), |api, m| {
+		let (_, txs) = api.retrieve_txs(m, false, None, None)?;
+		assert!(txs.is_empty());
+		Ok(())
+	})?;
+
+	// The check is available through the owner RPC API
+	let api = Owner::new(wallet1.clone());
+	let handshake = OwnerRpc::check_recipient(&api, "http".to_owned(), open_url).unwrap();
+	assert!(handshake.slate_exchange);
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_check_recipient() {
+	let test_dir = "test_output/check_recipient";
+	setup(test_dir);
+	if let Err(e) = check_recipient_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
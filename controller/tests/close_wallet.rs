@@ -0,0 +1,126 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test closing the wallet while operations are in progress
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::api_impl::owner;
+use libwallet::ErrorKind;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// close wallet impl
+fn close_wallet_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+
+	// A send in progress keeps the wallet from closing
+	let operations1 = wallet1.lock().lc_provider()?.pending_operations();
+	let operation = owner::begin_operation(&operations1, "send", None)?;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		match api.close_wallet(None, None).unwrap_err().kind() {
+			ErrorKind::OperationsPending(ops) => {
+				assert_eq!(ops.len(), 1);
+				assert_eq!(ops[0].kind, "send");
+			}
+			e => panic!("Unexpected error: {}", e),
+		}
+		// Still open, and taking new operations
+		api.retrieve_summary_info(m, true, 1)?;
+		let _other = owner::begin_operation(&operations1, "dispatch_tx", None)?;
+		assert_eq!(owner::retrieve_pending_operations(&operations1).len(), 2);
+		Ok(())
+	})?;
+
+	// The operations of wallet1 don't keep wallet2 from closing
+	let operations2 = wallet2.lock().lc_provider()?.pending_operations();
+	assert!(owner::retrieve_pending_operations(&operations2).is_empty());
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, _| {
+		api.close_wallet(None, None)
+	})?;
+	assert_eq!(owner::retrieve_pending_operations(&operations1).len(), 1);
+
+	// Closing waits for the send to end
+	let send = thread::spawn(move || {
+		thread::sleep(Duration::from_millis(500));
+		drop(operation);
+	});
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, _| {
+		api.close_wallet(None, Some(10))
+	})?;
+	send.join().unwrap();
+	assert!(owner::retrieve_pending_operations(&operations1).is_empty());
+	{
+		let mut w_lock = wallet1.lock();
+		let lc = w_lock.lc_provider()?;
+		assert!(lc.wallet_inst().is_err());
+	}
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_close_with_pending_operations() {
+	let test_dir = "test_output/close_wallet";
+	setup(test_dir);
+	if let Err(e) = close_wallet_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,164 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the results returned by the wallet life cycle commands
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_util::grin_core as core;
+use grin_wallet_util::grin_util::ZeroingString;
+
+use core::global::ChainTypes;
+use impls::test_framework::{self, LocalWalletClient};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use wallet::command::{self, FinalizeArgs, GlobalArgs, ReceiveArgs, RecoverArgs, SendArgs};
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+fn send_args(method: &str, dest: &str, estimate_selection_strategies: bool) -> SendArgs {
+	SendArgs {
+		amount: 1_000_000_000,
+		message: None,
+		minimum_confirmations: 2,
+		selection_strategy: "smallest".to_owned(),
+		estimate_selection_strategies,
+		method: method.to_owned(),
+		dest: dest.to_owned(),
+		change_outputs: 1,
+		fluff: false,
+		max_outputs: 500,
+		target_slate_version: None,
+		payjoin: false,
+		fee_from_amount: false,
+		totp: None,
+	}
+}
+
+/// command results impl
+fn command_results_test_impl(test_dir: &'static str) -> Result<(), wallet::Error> {
+	let seed_phrase = "affair pistol cancel crush garment candy ancient flag work \
+	                   market crush dry stand focus mutual weapon offer ceiling rival turn team spring \
+	                   where swift";
+
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		Some(ZeroingString::from(seed_phrase)),
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	// Without a phrase to recover from, the phrase of the wallet is returned
+	let result = command::recover(
+		wallet1.clone(),
+		RecoverArgs {
+			recovery_phrase: None,
+			passphrase: ZeroingString::from(""),
+		},
+	)?;
+	assert_eq!(&*result.recovery_phrase.unwrap(), seed_phrase);
+
+	// Estimating returns the cost of each strategy, and no slate
+	let result = command::send(wallet1.clone(), mask1, send_args("file", "", true))?;
+	assert!(result.slate.is_none());
+	assert_eq!(result.estimates.len(), 2);
+	assert_eq!(result.estimates[0].strategy, "smallest");
+	assert_eq!(result.estimates[1].strategy, "all");
+	assert!(result.estimates.iter().all(|e| e.fee > 0));
+	assert!(result.estimates[1].total >= result.estimates[0].total);
+
+	// A send by file returns the slate written, before it's signed by the recipient
+	let send_file = format!("{}/send.tx", test_dir);
+	let result = command::send(wallet1.clone(), mask1, send_args("file", &send_file, false))?;
+	assert!(result.estimates.is_empty());
+	let slate = result.slate.unwrap();
+	assert_eq!(slate.amount, 1_000_000_000);
+
+	// The receive returns the signed slate and the file it's written to
+	let g_args = GlobalArgs {
+		account: "default".to_owned(),
+		node_api_secret: None,
+		show_spent: false,
+		chain_type: ChainTypes::AutomatedTesting,
+		password: None,
+		tls_conf: None,
+		audit_log: None,
+	};
+	let args = ReceiveArgs {
+		input: send_file.clone(),
+		message: None,
+	};
+	let result = command::receive(wallet2.clone(), mask2, &g_args, args)?;
+	assert_eq!(result.slate.id, slate.id);
+	assert_eq!(result.response_file, format!("{}.response", send_file));
+	assert!(Path::new(&result.response_file).exists());
+
+	// The finalize returns the id of the slate and the transaction posted
+	let args = FinalizeArgs {
+		inputs: vec![result.response_file.clone()],
+		fluff: false,
+		totp: None,
+	};
+	let result = command::finalize(wallet1.clone(), mask1, args)?;
+	assert_eq!(result.slate_ids, vec![slate.id]);
+	assert!(!result.tx.kernels().is_empty());
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_command_results() {
+	let test_dir = "test_output/command_results";
+	setup(test_dir);
+	if let Err(e) = command_results_test_impl(test_dir) {
+		panic!("Wallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
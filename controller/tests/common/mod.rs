@@ -40,6 +40,14 @@ macro_rules! wallet_inst {
 	};
 }
 
+#[macro_export]
+macro_rules! set_wallet_config {
+	($wallet:ident, $config: expr) => {{
+		let mut w_lock = $wallet.lock();
+		w_lock.lc_provider()?.set_wallet_config($config);
+	}};
+}
+
 #[macro_export]
 macro_rules! create_wallet_and_add {
 	($client:ident, $wallet: ident, $mask: ident, $test_dir: expr, $name: expr, $seed_phrase: expr, $proxy: expr, $create_mask: expr) => {
@@ -0,0 +1,189 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the confirming block recorded on tx log entries
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_config as config;
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use config::WalletConfig;
+use core::core::hash::Hashed;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, Slate, TxLogEntry, TxLogEntryType};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// confirming block impl
+fn confirming_block_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+	set_wallet_config!(
+		wallet1,
+		WalletConfig {
+			finality_depth: Some(3),
+			..WalletConfig::default()
+		}
+	);
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	let args = InitTxArgs {
+		src_acct_name: None,
+		amount: reward / 2,
+		minimum_confirmations: 2,
+		max_outputs: 500,
+		num_change_outputs: 1,
+		selection_strategy_is_use_all: false,
+		..Default::default()
+	};
+	let mut slate = Slate::blank(1);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let slate_i = api.init_send_tx(m, args.clone())?;
+		slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		slate = api.finalize_tx(m, &slate)?;
+		api.post_tx(m, &slate.tx, false)?;
+		Ok(())
+	})?;
+
+	// Confirmed txs get the block they're in
+	let mut sent: Option<TxLogEntry> = None;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let tip = chain.head().unwrap().height;
+		for t in txs.iter().filter(|t| t.confirmed) {
+			let height = t.confirmed_height.unwrap();
+			let header = chain.get_header_by_height(height).unwrap();
+			assert_eq!(t.confirmed_block_hash, Some(header.hash().to_hex()));
+			assert_eq!(t.num_confirmations(tip), Some(tip - height + 1));
+		}
+		sent = txs.into_iter().find(|t| t.tx_slate_id == Some(slate.id));
+		Ok(())
+	})?;
+	let sent = sent.unwrap();
+	assert!(sent.confirmed);
+	let sent_height = sent.confirmed_height.unwrap();
+
+	// Without a recorded height, the block is found from the tx kernel, from the
+	// height of the tx change output on
+	{
+		wallet_inst!(wallet1, w);
+		let mut t = sent.clone();
+		t.confirmed_height = None;
+		t.confirmed_block_hash = None;
+		let mut batch = w.batch(mask1)?;
+		batch.save_tx_log_entry(t, &sent.parent_key_id)?;
+		batch.commit()?;
+	}
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, true, Some(sent.id), None)?;
+		assert_eq!(txs[0].confirmed_height, Some(sent_height));
+		assert_eq!(txs[0].confirmed_block_hash, sent.confirmed_block_hash);
+		assert_eq!(txs[0].kernel_lookup_min_height, None);
+		Ok(())
+	})?;
+
+	// A kernel that isn't found isn't looked up again in the blocks already
+	// searched, but for the last ones a reorg could still change
+	let mut unposted = Slate::blank(1);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let slate_i = api.init_send_tx(m, args.clone())?;
+		unposted = client1.send_tx_slate_direct("wallet2", &slate_i)?;
+		api.tx_lock_outputs(m, &unposted, 0)?;
+		unposted = api.finalize_tx(m, &unposted)?;
+		Ok(())
+	})?;
+	let created_at = chain.head().unwrap().height;
+	{
+		wallet_inst!(wallet1, w);
+		let mut t = w
+			.tx_log_iter()
+			.find(|t| t.tx_slate_id == Some(unposted.id))
+			.unwrap();
+		assert_eq!(t.tx_type, TxLogEntryType::TxSent);
+		t.confirmed = true;
+		let parent_key_id = t.parent_key_id.clone();
+		let mut batch = w.batch(mask1)?;
+		batch.save_tx_log_entry(t, &parent_key_id)?;
+		batch.commit()?;
+	}
+	let lookup_min_height = |min_height: Option<u64>| -> Result<(), libwallet::Error> {
+		wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+			let (_, txs) = api.retrieve_txs(m, true, None, Some(unposted.id))?;
+			assert!(txs[0].confirmed_block_hash.is_none());
+			assert_eq!(txs[0].kernel_lookup_min_height, min_height);
+			Ok(())
+		})
+	};
+	lookup_min_height(Some(created_at))?;
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet2.clone(), mask2, 6, false);
+	let tip = chain.head().unwrap().height;
+	lookup_min_height(Some(tip - 3))?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_confirming_block() {
+	let test_dir = "test_output/confirming_block";
+	setup(test_dir);
+	if let Err(e) = confirming_block_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,204 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the cost basis of outputs and the gains realized by sends
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+use grin_wallet_util::grin_keychain as keychain;
+use grin_wallet_util::grin_util as util;
+
+use impls::test_framework::{self, LocalWalletClient};
+use keychain::Keychain;
+use libwallet::{
+	InitTxArgs, NodeClient, PriceProvider, PriceSource, TxLogEntryType, WalletInst,
+	WalletLCProvider,
+};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use util::Mutex;
+use uuid::Uuid;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// Price provider quoting a fixed price
+struct FixedPrice(u64);
+
+impl PriceProvider for FixedPrice {
+	fn name(&self) -> String {
+		"fixed".to_owned()
+	}
+
+	fn price(&self, _currency: &str) -> Result<u64, libwallet::Error> {
+		Ok(self.0)
+	}
+}
+
+/// Value the coins of a wallet in USD at a fixed price, `None` to not value them
+fn set_price<L, C, K>(
+	wallet: &Arc<Mutex<Box<dyn WalletInst<'static, L, C, K>>>>,
+	price: Option<u64>,
+) -> Result<(), libwallet::Error>
+where
+	L: WalletLCProvider<'static, C, K>,
+	C: NodeClient + 'static,
+	K: Keychain + 'static,
+{
+	let mut w_lock = wallet.lock();
+	let lc = w_lock.lc_provider()?;
+	let source = price.map(|p| PriceSource::new("USD", Arc::new(FixedPrice(p))));
+	lc.set_price_source(source.map(Arc::new));
+	Ok(())
+}
+
+/// cost basis impl
+fn cost_basis_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	// Each wallet values its coins with its own price provider. Coins mined at
+	// 1.00 USD
+	set_price(&wallet1, Some(100))?;
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	// And sent at 3.00 USD, while wallet2 receives them at 5.00 USD
+	set_price(&wallet1, Some(300))?;
+	set_price(&wallet2, Some(500))?;
+	let mut slate_id = None;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: reward / 2,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		let mut slate = api.init_send_tx(m, args)?;
+		slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		slate = api.finalize_tx(m, &slate)?;
+		api.post_tx(m, &slate.tx, false)?;
+		slate_id = Some(slate.id);
+		Ok(())
+	})?;
+	let slate_id = slate_id.unwrap();
+	set_price(&wallet1, None)?;
+	set_price(&wallet2, None)?;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, entries) = api.export_txs(m, true)?;
+		let sent = entries
+			.iter()
+			.find(|e| e.tx.tx_type == TxLogEntryType::TxSent)
+			.unwrap();
+		assert!(sent.tx.confirmed);
+		assert_eq!(sent.lots.len(), 1);
+		assert_eq!(sent.lots[0].cost_basis.as_ref().unwrap().unit_price, 100);
+		let amount = sent.tx.amount_debited - sent.tx.amount_credited;
+		let base = core::consensus::GRIN_BASE;
+		assert_eq!(sent.currency, Some("USD".to_owned()));
+		assert_eq!(sent.fiat_value, Some(amount * 300 / base));
+		assert_eq!(sent.cost_basis, Some(amount * 100 / base));
+		assert_eq!(
+			sent.realized_gain,
+			Some((amount * 300 / base) as i64 - (amount * 100 / base) as i64)
+		);
+
+		// The change carries over the cost basis of the input
+		let (_, outputs) = api.retrieve_outputs(m, false, false, Some(sent.tx.id))?;
+		assert!(!outputs.is_empty());
+		for o in outputs {
+			assert_eq!(o.output.cost_basis.unwrap().unit_price, 100);
+		}
+		Ok(())
+	})?;
+
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, entries) = api.export_txs(m, true)?;
+		assert_eq!(entries.len(), 1);
+		assert_eq!(
+			entries[0].fiat_value,
+			Some(reward / 2 * 500 / core::consensus::GRIN_BASE)
+		);
+
+		// A price set by hand becomes the cost basis of the outputs received
+		api.set_tx_price(m, slate_id, "USD", 250)?;
+		let (_, outputs) = api.retrieve_outputs(m, false, false, None)?;
+		let basis = outputs[0].output.cost_basis.clone().unwrap();
+		assert_eq!((basis.unit_price, basis.source.as_str()), (250, "manual"));
+		let (_, entries) = api.export_txs(m, false)?;
+		assert_eq!(
+			entries[0].fiat_value,
+			Some(reward / 2 * 250 / core::consensus::GRIN_BASE)
+		);
+		assert!(api.set_tx_price(m, Uuid::new_v4(), "USD", 250).is_err());
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_cost_basis() {
+	let test_dir = "test_output/cost_basis";
+	setup(test_dir);
+	if let Err(e) = cost_basis_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
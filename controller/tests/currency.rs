@@ -0,0 +1,141 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the currency each wallet labels its amounts with
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_config as config;
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use config::WalletConfig;
+use core::core::amount_to_hr_string;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{amount_to_currency_string, InitTxArgs, DEFAULT_CURRENCY};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// currency impl
+fn currency_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Only wallet1 holds another currency
+	set_wallet_config!(
+		wallet1,
+		WalletConfig {
+			currency: Some("XMW".to_owned()),
+			..WalletConfig::default()
+		}
+	);
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	assert_eq!(
+		amount_to_currency_string(reward, false, "XMW"),
+		format!("{} XMW", amount_to_hr_string(reward, false))
+	);
+	assert_eq!(
+		amount_to_currency_string(reward, true, DEFAULT_CURRENCY),
+		format!("{} MWC", amount_to_hr_string(reward, true))
+	);
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet2.clone(), mask2, 3, false);
+
+	let args = InitTxArgs {
+		src_acct_name: None,
+		amount: reward / 2,
+		minimum_confirmations: 1,
+		max_outputs: 500,
+		num_change_outputs: 1,
+		selection_strategy_is_use_all: false,
+		..Default::default()
+	};
+	for (wallet, mask, ticker) in vec![
+		(wallet1.clone(), mask1, "XMW"),
+		(wallet2.clone(), mask2, DEFAULT_CURRENCY),
+	] {
+		wallet::controller::owner_single_use(wallet, mask, |api, m| {
+			let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+			assert_eq!(info.currency, ticker);
+
+			let slate = api.init_send_tx(m, args.clone())?;
+			api.tx_lock_outputs(m, &slate, 0)?;
+			let description = api.describe_slate(m, &slate)?;
+			assert_eq!(
+				description.amount_display,
+				amount_to_currency_string(reward / 2, false, ticker)
+			);
+			assert_eq!(
+				description.fee_display,
+				amount_to_currency_string(slate.fee, false, ticker)
+			);
+			let (_, details) = api.get_tx_details(m, false, slate.id)?;
+			assert_eq!(details.currency, ticker);
+			Ok(())
+		})?;
+	}
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_currency() {
+	let test_dir = "test_output/currency";
+	setup(test_dir);
+	if let Err(e) = currency_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
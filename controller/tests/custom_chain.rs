@@ -0,0 +1,158 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test wallets configured for a custom chain
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_config as config;
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use config::{CustomChainConfig, GlobalWalletConfig, WalletConfig};
+use core::core::hash::Hashed;
+use core::global::ChainTypes;
+use impls::test_framework::{self, LocalWalletClient};
+use impls::HTTPNodeClient;
+use std::fs;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+fn custom_chain(genesis_hash: Option<String>) -> CustomChainConfig {
+	CustomChainConfig {
+		chain_name: "my_testnet".to_owned(),
+		base_chain_type: ChainTypes::UserTesting,
+		genesis_hash,
+		node_port: 33413,
+		foreign_api_port: 33415,
+		owner_api_port: 33420,
+	}
+}
+
+/// custom chain impl
+fn custom_chain_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 1, false);
+
+	// The custom chain is applied as the config file is loaded, keeping the host
+	// of the node address
+	let path = format!("{}/grin-wallet.toml", test_dir);
+	let mut global = GlobalWalletConfig::for_chain(&ChainTypes::AutomatedTesting);
+	{
+		let config = &mut global.members.as_mut().unwrap().wallet;
+		config.check_node_api_http_addr = "http://10.0.0.5:3413".to_owned();
+		config.custom_chain = Some(custom_chain(None));
+	}
+	global.write_to_file(&path).unwrap();
+	let loaded = GlobalWalletConfig::new(&path).unwrap();
+	let loaded = loaded.members.unwrap().wallet;
+	assert_eq!(loaded.chain_type, Some(ChainTypes::UserTesting));
+	assert_eq!(loaded.api_listen_port, 33415);
+	assert_eq!(loaded.owner_api_listen_port, Some(33420));
+	assert_eq!(loaded.check_node_api_http_addr, "http://10.0.0.5:33413");
+
+	for (addr, applied) in &[
+		("https://node.example.com", "https://node.example.com:33413"),
+		(
+			"https://node.example.com:443/",
+			"https://node.example.com:33413/",
+		),
+		("http://[::1]:3413", "http://[::1]:33413"),
+		("[::1]", "http://[::1]:33413"),
+	] {
+		let mut config = WalletConfig {
+			check_node_api_http_addr: addr.to_string(),
+			custom_chain: Some(custom_chain(None)),
+			..WalletConfig::default()
+		};
+		config.apply_custom_chain();
+		assert_eq!(config.check_node_api_http_addr, *applied);
+	}
+
+	// The examples of the tables not set are written at the end of the wallet
+	// section, not in the logging one
+	let contents = fs::read_to_string(&path).unwrap();
+	let logging = contents.find("[logging]").unwrap();
+	let backup = contents.find("#[wallet.backup]").unwrap();
+	assert!(backup > contents.find("keybase_notify_ttl").unwrap());
+	assert!(backup < contents.find("### LOGGING CONFIGURATION").unwrap());
+	assert!(backup < logging);
+	assert!(!contents.contains("#[wallet.custom_chain]"));
+	assert!(contents.contains("\n[wallet.custom_chain]"));
+
+	// A node is only used if it's on the custom chain, which an unreachable one
+	// can't be told to be
+	let genesis = chain.get_header_by_height(0).unwrap().hash().to_hex();
+	let config = WalletConfig {
+		custom_chain: Some(custom_chain(Some(genesis))),
+		..WalletConfig::default()
+	};
+	assert!(wallet::command::check_custom_chain(&client1, &config).is_ok());
+	let other = WalletConfig {
+		custom_chain: Some(custom_chain(Some("00".repeat(32)))),
+		..WalletConfig::default()
+	};
+	assert!(wallet::command::check_custom_chain(&client1, &other).is_err());
+	let unreachable =
+		HTTPNodeClient::new("http://127.0.0.1:1", None, ChainTypes::AutomatedTesting).unwrap();
+	assert!(wallet::command::check_custom_chain(&unreachable, &config).is_err());
+	// without a genesis hash, there's nothing to check
+	let unchecked = WalletConfig {
+		custom_chain: Some(custom_chain(None)),
+		..WalletConfig::default()
+	};
+	assert!(wallet::command::check_custom_chain(&unreachable, &unchecked).is_ok());
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_custom_chain() {
+	let test_dir = "test_output/custom_chain";
+	setup(test_dir);
+	if let Err(e) = custom_chain_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,170 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the receipts of slates delivered to a foreign listener
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+use grin_wallet_util::grin_util as util;
+
+use core::global::ChainTypes;
+use impls::test_framework::{self, LocalWalletClient};
+use impls::{HttpSlateSender, SlateSender};
+use libwallet::{DeliveryStatus, InitTxArgs, Slate};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use url::Url;
+use util::Mutex;
+use uuid::Uuid;
+use wallet::controller::{ForeignApiRoles, ListenerLimits};
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// delivery receipt impl
+fn delivery_receipt_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	// Wallet 2 receives on its foreign listener
+	let addr = "127.0.0.1:34416";
+	let listener_wallet = wallet2.clone();
+	let listener_mask = Arc::new(Mutex::new(mask2_i.clone()));
+	thread::spawn(move || {
+		if let Err(e) = wallet::controller::foreign_listener(
+			listener_wallet,
+			listener_mask,
+			addr,
+			None,
+			None,
+			ListenerLimits::default(),
+			None,
+			ForeignApiRoles::default(),
+		) {
+			error!("Foreign listener error: {}", e);
+		}
+	});
+	thread::sleep(Duration::from_millis(500));
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	let url = Url::parse(&format!("http://{}", addr)).unwrap();
+	let sender = HttpSlateSender::new(url, ChainTypes::AutomatedTesting, None, None).unwrap();
+	let mut slate_i = Slate::blank(1);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: 1_000_000_000,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		slate_i = api.init_send_tx(m, args)?;
+		Ok(())
+	})?;
+
+	// A slate the listener receives is acknowledged as accepted, with the slate
+	// it signed
+	let delivery = sender.deliver_tx(&slate_i, "http")?;
+	assert_eq!(delivery.receipt.status, DeliveryStatus::Accepted);
+	assert_eq!(delivery.receipt.method, "http");
+	assert!(delivery.receipt.reason.is_none());
+	let receipt = delivery.receipt.clone();
+	let slate = delivery.into_slate()?;
+	assert_eq!(slate.id, slate_i.id);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.tx_lock_outputs(m, &slate, 0)?;
+		api.with_context(m, |ctx| ctx.save_delivery_receipt(&slate.id, &receipt))?;
+		let (_, txs) = api.retrieve_txs(m, false, None, Some(slate.id))?;
+		assert_eq!(txs.len(), 1);
+		assert_eq!(txs[0].delivery_receipt, Some(receipt.clone()));
+		Ok(())
+	})?;
+
+	// One it refuses, here for having received it already, is acknowledged as
+	// rejected, with the reason
+	let delivery = sender.deliver_tx(&slate_i, "http")?;
+	assert_eq!(delivery.receipt.status, DeliveryStatus::Rejected);
+	assert!(delivery.receipt.reason.is_some());
+	assert!(delivery.slate.is_none());
+	let receipt = delivery.receipt.clone();
+	assert!(delivery.into_slate().is_err());
+	assert!(sender.send_tx(&slate_i).is_err());
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.with_context(m, |ctx| ctx.save_delivery_receipt(&slate.id, &receipt))?;
+		let (_, txs) = api.retrieve_txs(m, false, None, Some(slate.id))?;
+		let saved = txs[0].delivery_receipt.clone().unwrap();
+		assert_eq!(saved.status, DeliveryStatus::Rejected);
+		assert_eq!(saved.reason, receipt.reason);
+
+		// A receipt for a slate the wallet has no tx for is dropped
+		let other = Uuid::new_v4();
+		api.with_context(m, |ctx| ctx.save_delivery_receipt(&other, &receipt))?;
+		let (_, txs) = api.retrieve_txs(m, false, None, Some(other))?;
+		assert!(txs.is_empty());
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_delivery_receipt() {
+	let test_dir = "test_output/delivery_receipt";
+	setup(test_dir);
+	if let Err(e) = delivery_receipt_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,143 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test describing a slate as it goes through a transaction
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::InitTxArgs;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// describe slate impl
+fn describe_slate_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	let amount = 1_000_000_000;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: amount,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			message: Some("for the coffee".to_owned()),
+			..Default::default()
+		};
+		let slate = api.init_send_tx(m, args)?;
+
+		// Only the sender took part, and signed its message
+		let d = api.describe_slate(m, &slate)?;
+		assert_eq!(d.id, slate.id);
+		assert_eq!(d.amount, amount);
+		assert_eq!(d.fee, slate.fee);
+		assert!(d.num_inputs > 0);
+		assert_eq!(d.num_outputs, 1);
+		assert_eq!(d.num_participants, 2);
+		assert_eq!(d.participants.len(), 1);
+		assert_eq!(d.participants[0].role, "Sender");
+		assert!(!d.participants[0].signed);
+		assert_eq!(d.participants[0].message, Some("for the coffee".to_owned()));
+		assert_eq!(d.participants[0].message_sig_valid, Some(true));
+		assert!(!d.round1_complete);
+		assert!(!d.round2_complete);
+		assert!(!d.finalized);
+
+		// The recipient added its data and signed, the sender hasn't yet
+		let mut slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		let d = api.describe_slate(m, &slate)?;
+		assert_eq!(d.participants.len(), 2);
+		assert_eq!(d.participants[1].role, "Recipient");
+		assert!(d.participants[1].signed);
+		assert_eq!(d.participants[1].message_sig_valid, None);
+		assert_eq!(d.num_outputs, 2);
+		assert!(d.round1_complete);
+		assert!(!d.round2_complete);
+		assert!(!d.finalized);
+
+		api.tx_lock_outputs(m, &slate, 0)?;
+		slate = api.finalize_tx(m, &slate)?;
+		let d = api.describe_slate(m, &slate)?;
+		assert!(d.round2_complete);
+		assert!(d.finalized);
+		let text = format!("{}", d);
+		assert!(text.contains("Participants: 2 of 2"));
+		assert!(text.contains("Finalized: true"));
+		assert!(text.contains("Message: for the coffee\n"));
+
+		// A message changed after it was signed is shown as such
+		slate.participant_data[0].message = Some("for the car".to_owned());
+		let d = api.describe_slate(m, &slate)?;
+		assert_eq!(d.participants[0].message_sig_valid, Some(false));
+		assert!(format!("{}", d).contains("Message: for the car (INVALID SIGNATURE)"));
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_describe_slate() {
+	let test_dir = "test_output/describe_slate";
+	setup(test_dir);
+	if let Err(e) = describe_slate_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
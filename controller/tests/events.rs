@@ -0,0 +1,139 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the wallet event queue
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, IssueInvoiceTxArgs, TxLogEntryType, WalletEventType};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// event queue impl
+fn events_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		// Each coinbase is confirmed on refresh
+		api.retrieve_summary_info(m, true, 1)?;
+		let events = api.poll_events(m, "shop", None, None)?;
+		assert_eq!(events.len(), 5);
+		assert!(events
+			.iter()
+			.all(|e| e.event_type == WalletEventType::Confirmed
+				&& e.tx.tx_type == TxLogEntryType::ConfirmedCoinbase));
+		assert_eq!(events[0].seq, 1);
+
+		// Events are delivered again until acknowledged
+		let events = api.poll_events(m, "shop", None, Some(2))?;
+		assert_eq!(events.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![1, 2]);
+		let events = api.poll_events(m, "shop", None, Some(2))?;
+		assert_eq!(events[0].seq, 1);
+		let events = api.poll_events(m, "shop", Some(2), None)?;
+		assert_eq!(events.len(), 3);
+		assert_eq!(events[0].seq, 3);
+		let events = api.poll_events(m, "shop", Some(5), None)?;
+		assert!(events.is_empty());
+
+		// Consumers have their own offsets
+		assert_eq!(api.poll_events(m, "audit", None, None)?.len(), 5);
+
+		// Cancelled send
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: reward,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: true,
+			..Default::default()
+		};
+		let slate = api.init_send_tx(m, args)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		api.cancel_tx(m, None, Some(slate.id))?;
+
+		// Invoice, receiving funds
+		let args = IssueInvoiceTxArgs {
+			amount: reward,
+			..Default::default()
+		};
+		let invoice = api.issue_invoice_tx(m, args)?;
+
+		let events = api.poll_events(m, "shop", None, None)?;
+		assert_eq!(events.len(), 2);
+		assert_eq!(events[0].event_type, WalletEventType::Cancelled);
+		assert_eq!(events[0].tx.tx_slate_id, Some(slate.id));
+		assert_eq!(events[0].tx.tx_type, TxLogEntryType::TxSentCancelled);
+		assert_eq!(events[1].event_type, WalletEventType::Received);
+		assert_eq!(events[1].tx.tx_slate_id, Some(invoice.id));
+		assert_eq!(events[1].tx.amount_credited, reward);
+		Ok(())
+	})?;
+
+	// Offsets are stored with the wallet
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let events = api.poll_events(m, "shop", None, None)?;
+		assert_eq!(events[0].seq, 6);
+		assert!(api.poll_events(m, "", None, None).is_err());
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_event_queue() {
+	let test_dir = "test_output/events";
+	setup(test_dir);
+	if let Err(e) = events_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
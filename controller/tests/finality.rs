@@ -0,0 +1,135 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the finality depth each wallet is configured with
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_config as config;
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+use grin_wallet_util::grin_keychain as keychain;
+
+use config::WalletConfig;
+use impls::test_framework::{self, LocalWalletClient};
+use keychain::{ExtKeychain, Keychain};
+use libwallet::{TxLogEntry, TxLogEntryType, DEFAULT_FINALITY_DEPTH};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// finality impl
+fn finality_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Only wallet1 treats its outputs as final after 3 confirmations
+	{
+		let mut w_lock = wallet1.lock();
+		let lc = w_lock.lc_provider()?;
+		lc.set_wallet_config(WalletConfig {
+			finality_depth: Some(3),
+			..WalletConfig::default()
+		});
+	}
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet2.clone(), mask2, 1, false);
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.last_confirmed_height, 6);
+		assert_eq!(info.finality_depth, 3);
+		// The outputs of blocks 1 to 4 have at least 3 confirmations at height 6,
+		// the one of block 5 has 2
+		assert_eq!(info.amount_finalized, 4 * reward);
+
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		for o in outputs.iter().map(|o| &o.output) {
+			let final_at = o.height + 2;
+			assert!(!o.is_final(final_at - 1, 3));
+			assert!(o.is_final(final_at, 3));
+		}
+		Ok(())
+	})?;
+
+	// The depth of wallet1 doesn't change the one of wallet2
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.finality_depth, DEFAULT_FINALITY_DEPTH);
+		assert_eq!(info.amount_finalized, 0);
+		Ok(())
+	})?;
+
+	// A tx stays subject to reorg checks until it's exactly the finality depth
+	// deep
+	let mut tx = TxLogEntry::new(ExtKeychain::root_key_id(), TxLogEntryType::TxSent, 0);
+	assert!(!tx.is_final(100, 3));
+	tx.confirmed = true;
+	tx.confirmed_height = Some(100);
+	assert!(!tx.is_final(101, 3));
+	assert!(tx.is_final(102, 3));
+	assert!(!tx.is_final(99, 3));
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_finality() {
+	let test_dir = "test_output/finality";
+	setup(test_dir);
+	if let Err(e) = finality_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,96 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the foreign API keys picking the account slates are received into
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+
+use impls::test_framework::LocalWalletClient;
+use libwallet::api_impl::{foreign, owner};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// foreign api keys impl
+fn foreign_api_keys_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	{
+		wallet_inst!(wallet1, w);
+		let scheme = libwallet::DerivationScheme::Standard;
+		owner::create_account_path(&mut **w, mask1, "shop", scheme)?;
+		// Keys are only for existing accounts
+		assert!(owner::create_foreign_api_key(&mut **w, mask1, "nope", None).is_err());
+
+		let label = Some("online shop".to_owned());
+		let (key, record) = owner::create_foreign_api_key(&mut **w, mask1, "shop", label)?;
+		let (other_key, _) = owner::create_foreign_api_key(&mut **w, mask1, "default", None)?;
+		assert_ne!(key, other_key);
+		assert_eq!(owner::retrieve_foreign_api_keys(&mut **w)?.len(), 2);
+		// Only the hash of the key is kept
+		assert_ne!(record.key_hash, key);
+
+		assert_eq!(foreign::foreign_api_key_account(&mut **w, &key)?, "shop");
+		assert_eq!(
+			foreign::foreign_api_key_account(&mut **w, &other_key)?,
+			"default"
+		);
+		assert!(foreign::foreign_api_key_account(&mut **w, "unknown").is_err());
+
+		owner::revoke_foreign_api_key(&mut **w, mask1, &record.id)?;
+		assert!(foreign::foreign_api_key_account(&mut **w, &key).is_err());
+		assert_eq!(owner::retrieve_foreign_api_keys(&mut **w)?.len(), 1);
+	}
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_foreign_api_keys() {
+	let test_dir = "test_output/foreign_api_keys";
+	setup(test_dir);
+	if let Err(e) = foreign_api_keys_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,164 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test foreign listeners serving only some of the slate methods
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+use grin_wallet_util::grin_util as util;
+
+use core::global::ChainTypes;
+use impls::test_framework::{self, LocalWalletClient};
+use impls::{Client, HttpSlateSender, SlateSender};
+use libwallet::{InitTxArgs, Slate};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use url::Url;
+use util::Mutex;
+use wallet::controller::{ForeignApiRoles, ListenerLimits};
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// foreign roles impl
+fn foreign_roles_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	// Wallet 2 only receives on one listener, and only finalizes invoices on
+	// the other
+	let receive_addr = "127.0.0.1:34417";
+	let invoice_addr = "127.0.0.1:34418";
+	for (addr, roles) in vec![
+		(receive_addr, ForeignApiRoles::receive_only()),
+		(invoice_addr, ForeignApiRoles::invoice_only()),
+	] {
+		let listener_wallet = wallet2.clone();
+		let listener_mask = Arc::new(Mutex::new(mask2_i.clone()));
+		thread::spawn(move || {
+			if let Err(e) = wallet::controller::foreign_listener(
+				listener_wallet,
+				listener_mask,
+				addr,
+				None,
+				None,
+				ListenerLimits::default(),
+				None,
+				roles,
+			) {
+				error!("Foreign listener error: {}", e);
+			}
+		});
+	}
+	thread::sleep(Duration::from_millis(500));
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	let mut slate_i = Slate::blank(1);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: 1_000_000_000,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		slate_i = api.init_send_tx(m, args)?;
+		Ok(())
+	})?;
+
+	// A slate is only received by the listener receiving slates
+	let sender = |addr: &str| {
+		let url = Url::parse(&format!("http://{}", addr)).unwrap();
+		HttpSlateSender::new(url, ChainTypes::AutomatedTesting, None, None).unwrap()
+	};
+	assert!(sender(invoice_addr).send_tx(&slate_i).is_err());
+	let slate = sender(receive_addr).send_tx(&slate_i)?;
+	assert_eq!(slate.id, slate_i.id);
+
+	// Calls to a disabled method are refused on every route, the others served
+	let client = Client::new(false).unwrap();
+	let call = |url: String, method: &str| {
+		let req = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": []});
+		client.post::<_, Value>(&url, None, &req, ChainTypes::AutomatedTesting)
+	};
+	let disabled = |res: Result<Value, _>| res.unwrap()["error"]["code"] == json!(-32601);
+	let foreign = |addr: &str| format!("http://{}/v2/foreign", addr);
+	assert!(disabled(call(foreign(receive_addr), "finalize_invoice_tx")));
+	assert!(disabled(call(foreign(invoice_addr), "receive_tx")));
+	assert!(!disabled(call(foreign(invoice_addr), "check_version")));
+	let receive = format!("{}/receive", foreign(receive_addr));
+	assert!(disabled(call(receive, "finalize_invoice_tx")));
+	let invoice = format!("{}/invoice", foreign(invoice_addr));
+	assert!(disabled(call(invoice, "receive_tx")));
+
+	// and the routes of a disabled method aren't there
+	let receive = format!("{}/receive", foreign(invoice_addr));
+	assert!(call(receive, "receive_tx").is_err());
+	let invoice = format!("{}/invoice", foreign(receive_addr));
+	assert!(call(invoice, "finalize_invoice_tx").is_err());
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_foreign_roles() {
+	let test_dir = "test_output/foreign_roles";
+	setup(test_dir);
+	if let Err(e) = foreign_roles_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,169 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test freezing a wallet
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+use grin_wallet_util::grin_util as util;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{ErrorKind, InitTxArgs, Slate};
+use std::thread;
+use std::time::Duration;
+use util::ZeroingString;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// freeze impl
+fn freeze_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	let mut slate = Slate::blank(2);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: reward,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: true,
+			..Default::default()
+		};
+		slate = api.init_send_tx(m, args)?;
+		Ok(())
+	})?;
+
+	// Spends are refused by the owner API itself, whatever serves it
+	let mut sent = Slate::blank(2);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: reward,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: true,
+			..Default::default()
+		};
+		sent = api.init_send_tx(m, args.clone())?;
+		api.tx_lock_outputs(m, &sent, 0)?;
+		sent = client1.send_tx_slate_direct("wallet2", &sent)?;
+		api.freeze_wallet(m)?;
+		let frozen = |e: libwallet::Error| match e.kind() {
+			ErrorKind::WalletFrozen(_) => {}
+			k => panic!("Unexpected error: {}", k),
+		};
+		frozen(api.init_send_tx(m, args).unwrap_err());
+		frozen(api.finalize_tx(m, &sent).unwrap_err());
+		api.unfreeze_wallet(m, ZeroingString::from(""))?;
+		sent = api.finalize_tx(m, &sent)?;
+		api.freeze_wallet(m)?;
+		frozen(api.post_tx(m, &sent.tx, false).unwrap_err());
+		frozen(
+			api.with_context(m, |ctx| ctx.post_tx(&sent.tx, false))
+				.unwrap_err(),
+		);
+		api.unfreeze_wallet(m, ZeroingString::from(""))?;
+		api.cancel_tx(m, None, Some(sent.id))?;
+		Ok(())
+	})?;
+
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let state = api.freeze_wallet(m)?;
+		assert_eq!(api.freeze_wallet(m)?, state);
+		assert_eq!(api.retrieve_freeze_state(m)?, Some(state));
+		Ok(())
+	})?;
+
+	// A frozen wallet doesn't receive
+	wallet::controller::foreign_single_use(wallet2.clone(), mask2_i.clone(), |api| {
+		match api.receive_tx(&slate, None, None).unwrap_err().kind() {
+			ErrorKind::WalletFrozen(_) => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+		Ok(())
+	})?;
+
+	// Unfreezing takes the wallet password
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		assert!(api
+			.unfreeze_wallet(m, ZeroingString::from("wrong"))
+			.is_err());
+		assert!(api.retrieve_freeze_state(m)?.is_some());
+		api.unfreeze_wallet(m, ZeroingString::from(""))?;
+		assert!(api.retrieve_freeze_state(m)?.is_none());
+		Ok(())
+	})?;
+
+	wallet::controller::foreign_single_use(wallet2.clone(), mask2_i.clone(), |api| {
+		api.receive_tx(&slate, None, None)?;
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_freeze() {
+	let test_dir = "test_output/freeze";
+	setup(test_dir);
+	if let Err(e) = freeze_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
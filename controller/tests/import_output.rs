@@ -0,0 +1,122 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test importing an output the wallet doesn't know of
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_util::ZeroingString;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::OutputStatus;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// import output impl
+fn import_output_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	let seed_phrase = "affair pistol cancel crush garment candy ancient flag work \
+	                   market crush dry stand focus mutual weapon offer ceiling rival turn team spring \
+	                   where swift";
+	let seed_phrase = Some(ZeroingString::from(seed_phrase));
+
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		seed_phrase,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		seed_phrase,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+	let mut outputs = vec![];
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, o) = api.retrieve_outputs(m, false, true, None)?;
+		outputs = o;
+		Ok(())
+	})?;
+	let out = outputs[0].clone();
+
+	// Wallet 2, from the same seed, only accepts the output as it is on chain
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (key_id, value, height) = (&out.output.key_id, out.output.value, out.output.height);
+		assert!(api
+			.import_output(m, &out.commit, value + 1, key_id, None)
+			.is_err());
+		let wrong_height = Some(height + 1);
+		assert!(api
+			.import_output(m, &out.commit, value, key_id, wrong_height)
+			.is_err());
+
+		let imported = api.import_output(m, &out.commit, value, key_id, Some(height))?;
+		assert_eq!(imported.status, OutputStatus::Unspent);
+		assert_eq!(imported.mmr_index, out.output.mmr_index);
+		assert!(imported.is_coinbase);
+		assert!(api
+			.import_output(m, &out.commit, value, key_id, None)
+			.is_err());
+
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		assert_eq!(outputs.len(), 1);
+		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		assert_eq!(txs.len(), 1);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_import_output() {
+	let test_dir = "test_output/import_output";
+	setup(test_dir);
+	if let Err(e) = import_output_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,142 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test recovery of interrupted owner API requests
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, TxLogEntryType};
+use serde_json::json;
+use std::thread;
+use std::time::Duration;
+use wallet::journal::RequestJournal;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// journal recovery impl
+fn journal_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	let journal = RequestJournal::new(&format!("{}/wallet1", test_dir))?;
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: reward,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		let mut req = json!({
+			"jsonrpc": "2.0",
+			"method": "init_send_tx",
+			"id": 1,
+			"params": [args.clone()],
+		});
+
+		// Requests that don't change the wallet aren't journaled
+		let mut query = json!({"jsonrpc": "2.0", "method": "retrieve_txs", "id": 1, "params": []});
+		assert!(journal.begin(&mut query, false)?.is_none());
+
+		// A completed request leaves nothing behind
+		let entry = journal.begin(&mut req.clone(), false)?.unwrap();
+		assert_eq!(entry.params["args"]["amount"], reward);
+		assert_eq!(journal.pending()?.len(), 1);
+		journal.complete(&entry)?;
+		assert!(journal.pending()?.is_empty());
+
+		// The wallet stops after locking the outputs, the request never completes.
+		// The journal picked the slate id of the tx the request creates.
+		let entry = journal.begin(&mut req, false)?.unwrap();
+		let journaled_args: InitTxArgs = serde_json::from_value(req["params"][0].clone()).unwrap();
+		let slate = api.init_send_tx(m, journaled_args.clone())?;
+		assert!(entry.slate_id.is_some());
+		assert_eq!(Some(slate.id), entry.slate_id);
+		api.tx_lock_outputs(m, &slate, 0)?;
+
+		// The same slate id can't be used twice
+		assert!(api.init_send_tx(m, journaled_args).is_err());
+
+		// Another send made meanwhile isn't part of the request
+		let other = api.init_send_tx(
+			m,
+			InitTxArgs {
+				amount: reward / 2,
+				..args
+			},
+		)?;
+		api.tx_lock_outputs(m, &other, 0)?;
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		let other_locked = info.amount_locked;
+
+		journal.recover(api, m)?;
+		assert!(journal.pending()?.is_empty());
+		let (_, txs) = api.retrieve_txs(m, true, None, Some(slate.id))?;
+		assert_eq!(txs[0].tx_type, TxLogEntryType::TxSentCancelled);
+		let (_, txs) = api.retrieve_txs(m, true, None, Some(other.id))?;
+		assert_eq!(txs[0].tx_type, TxLogEntryType::TxSent);
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert!(info.amount_locked > 0);
+		assert!(info.amount_locked < other_locked);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_request_journal() {
+	let test_dir = "test_output/journal";
+	setup(test_dir);
+	if let Err(e) = journal_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,137 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the outputs a node lies about aren't taken as confirmed
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_keychain as keychain;
+use grin_wallet_util::grin_util as util;
+
+use impls::test_framework::{self, LocalWalletClient, LyingNodeClient};
+use impls::{DefaultLCProvider, DefaultWalletImpl};
+use keychain::ExtKeychain;
+use libwallet::api_impl::foreign;
+use libwallet::{BlockFees, OutputStatus, WalletInst};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use util::{Mutex, ZeroingString};
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// lying node impl
+fn lying_node_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		_wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let _mask1 = (&mask1_i).as_ref();
+
+	// A wallet whose node lies to it, asking the simulated node through client1
+	let liar = LyingNodeClient::new(client1.clone());
+	let wallet2 = DefaultWalletImpl::<LyingNodeClient>::new(liar.clone()).unwrap();
+	let mut wallet2 = Box::new(wallet2)
+		as Box<
+			dyn WalletInst<
+				DefaultLCProvider<'static, LyingNodeClient, ExtKeychain>,
+				LyingNodeClient,
+				ExtKeychain,
+			>,
+		>;
+	let lc = wallet2.lc_provider()?;
+	lc.set_top_level_directory(&format!("{}/wallet2", test_dir))?;
+	lc.create_wallet(None, None, 32, ZeroingString::from(""), false)?;
+	let mask2_i = lc.open_wallet(None, ZeroingString::from(""), false, false)?;
+	let mask2 = (&mask2_i).as_ref();
+	let wallet2 = Arc::new(Mutex::new(wallet2));
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet2.clone(), mask2, 3, false);
+
+	// Outputs whose rangeproofs don't verify aren't confirmed
+	liar.lies.write().bad_proofs = true;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		assert_eq!(outputs.len(), 3);
+		assert!(outputs
+			.iter()
+			.all(|o| o.output.status == OutputStatus::Unconfirmed));
+		Ok(())
+	})?;
+
+	// Until the node stops lying
+	liar.lies.write().bad_proofs = false;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		assert!(outputs
+			.iter()
+			.all(|o| o.output.status == OutputStatus::Unspent));
+		Ok(())
+	})?;
+
+	// An output the node places in a block it isn't in isn't confirmed
+	let commit = {
+		wallet_inst!(wallet2, w);
+		let block_fees = BlockFees {
+			fees: 0,
+			key_id: None,
+			height: 10,
+		};
+		let cb = foreign::build_coinbase(&mut **w, mask2, &block_fees, false)?;
+		cb.output.commitment()
+	};
+	liar.lies.write().outputs.insert(commit, (2, 3));
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		let output = outputs.iter().find(|o| o.commit == commit).unwrap();
+		assert_eq!(output.output.status, OutputStatus::Unconfirmed);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_lying_node() {
+	let test_dir = "test_output/lying_node";
+	setup(test_dir);
+	if let Err(e) = lying_node_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,154 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the status of posted transactions waiting in the node's mempool
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use core::core::Transaction;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, NodeClient, RefusedTxState, Slate, TxLogEntryType};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// mempool impl
+fn mempool_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+	let hold_posted = wallet_proxy.hold_posted.clone();
+	let pool = wallet_proxy.pool.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let _mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	// Two sends are posted, and held in the mempool rather than mined
+	hold_posted.store(true, Ordering::Relaxed);
+	let args = InitTxArgs {
+		src_acct_name: None,
+		amount: reward / 2,
+		minimum_confirmations: 2,
+		max_outputs: 500,
+		num_change_outputs: 1,
+		selection_strategy_is_use_all: false,
+		..Default::default()
+	};
+	let mut slates = vec![];
+	for _ in 0..2 {
+		let mut slate = Slate::blank(1);
+		wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+			let slate_i = api.init_send_tx(m, args.clone())?;
+			slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
+			api.tx_lock_outputs(m, &slate, 0)?;
+			slate = api.finalize_tx(m, &slate)?;
+			api.post_tx(m, &slate.tx, false)?;
+			Ok(())
+		})?;
+		slates.push(slate);
+	}
+	assert_eq!(pool.lock().len(), 2);
+
+	// The pool is indexed by kernel excess
+	let entries = client2.get_pool_entries()?;
+	assert_eq!(entries.len(), 2);
+	for slate in slates.iter() {
+		let entry = entries.get(&slate.tx.kernels()[0].excess).unwrap();
+		assert!(entry.tx_at.is_some());
+	}
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let sent = txs
+			.iter()
+			.filter(|t| t.tx_type == TxLogEntryType::TxSent)
+			.collect::<Vec<_>>();
+		assert_eq!(sent.len(), 2);
+		assert!(sent.iter().all(|t| t.in_mempool && !t.confirmed));
+
+		// A tx refused because it's already in the pool has landed, its inputs
+		// aren't touched
+		let state = api.with_context(m, |ctx| ctx.refresh_spent_inputs(&slates[0].tx))?;
+		assert_eq!(state, RefusedTxState::Landed);
+		Ok(())
+	})?;
+
+	// Once mined, the txs are confirmed and out of the pool
+	hold_posted.store(false, Ordering::Relaxed);
+	let mined: Vec<Transaction> = pool.lock().iter().map(|(tx, _)| tx.clone()).collect();
+	test_framework::award_block_to_wallet(&chain, mined.iter().collect(), wallet1.clone(), mask1)?;
+	assert!(client1.get_pool_entries()?.is_empty());
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let sent = txs
+			.iter()
+			.filter(|t| t.tx_type == TxLogEntryType::TxSent)
+			.collect::<Vec<_>>();
+		assert!(sent.iter().all(|t| !t.in_mempool && t.confirmed));
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_mempool() {
+	let test_dir = "test_output/mempool";
+	setup(test_dir);
+	if let Err(e) = mempool_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
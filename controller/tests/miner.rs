@@ -0,0 +1,113 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test coinbases built for miners
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_api::CoinbaseCache;
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_util::Mutex;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::BlockFees;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// miner coinbase impl
+fn miner_coinbase_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 2, false);
+
+	let mut next_index = 0;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		next_index = api.get_next_child_index(m, "default")?;
+		Ok(())
+	})?;
+
+	let block_fees = BlockFees {
+		fees: 0,
+		height: 10,
+		key_id: None,
+	};
+	let cache = Arc::new(Mutex::new(CoinbaseCache::new(4)));
+	wallet::controller::foreign_single_use(wallet1.clone(), mask1_i.clone(), |api| {
+		// The key only depends on the height
+		let cb1 = api.build_coinbase_at_height(&block_fees)?;
+		let cb2 = api.build_coinbase_at_height(&block_fees)?;
+		assert_eq!(cb1.key_id, cb2.key_id);
+		assert_eq!(cb1.output.commit, cb2.output.commit);
+		let other = api.build_coinbase_at_height(&BlockFees {
+			height: 11,
+			..block_fees.clone()
+		})?;
+		assert!(other.key_id != cb1.key_id);
+
+		// With a cache, repeated requests get the very same kernel
+		api.set_coinbase_cache(cache.clone());
+		let cb3 = api.build_coinbase_at_height(&block_fees)?;
+		let cb4 = api.build_coinbase_at_height(&block_fees)?;
+		assert_eq!(cb3.kernel, cb4.kernel);
+		Ok(())
+	})?;
+
+	// Coinbase keys never touch the child index counter
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		assert_eq!(api.get_next_child_index(m, "default")?, next_index);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_miner_coinbase() {
+	let test_dir = "test_output/miner_coinbase";
+	setup(test_dir);
+	if let Err(e) = miner_coinbase_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
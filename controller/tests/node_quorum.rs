@@ -0,0 +1,179 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the node is picked by quorum, again on every refresh
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_config as config;
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_keychain as keychain;
+use grin_wallet_util::grin_util as util;
+
+use config::WalletConfig;
+use impls::test_framework::{self, LocalWalletClient, LyingNodeClient, NodeLies};
+use impls::{DefaultLCProvider, DefaultWalletImpl};
+use keychain::ExtKeychain;
+use libwallet::api_impl::owner;
+use libwallet::{ErrorKind, NodeClient, WalletInst};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use util::{Mutex, ZeroingString};
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+macro_rules! node_url {
+	($wallet:ident) => {{
+		wallet_inst!($wallet, w);
+		w.w2n_client().node_url().to_owned()
+	}};
+}
+
+/// node quorum impl
+fn node_quorum_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// A wallet asking three nodes, all answered by the simulated node through client1
+	let nodes = LyingNodeClient::new(client1.clone());
+	let wallet2 = DefaultWalletImpl::<LyingNodeClient>::new(nodes.clone()).unwrap();
+	let mut wallet2 = Box::new(wallet2)
+		as Box<
+			dyn WalletInst<
+				DefaultLCProvider<'static, LyingNodeClient, ExtKeychain>,
+				LyingNodeClient,
+				ExtKeychain,
+			>,
+		>;
+	let lc = wallet2.lc_provider()?;
+	lc.set_top_level_directory(&format!("{}/wallet2", test_dir))?;
+	lc.create_wallet(None, None, 32, ZeroingString::from(""), false)?;
+	let mask2_i = lc.open_wallet(None, ZeroingString::from(""), false, false)?;
+	let mask2 = (&mask2_i).as_ref();
+	let wallet2 = Arc::new(Mutex::new(wallet2));
+
+	let config = WalletConfig {
+		check_node_api_http_addr: "node_a".to_owned(),
+		quorum_node_api_http_addrs: Some(vec!["node_b".to_owned(), "node_c".to_owned()]),
+		node_quorum_max_deviation: Some(2),
+		..WalletConfig::default()
+	};
+	set_wallet_config!(wallet2, config.clone());
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	// All the nodes agree, the configured one is used
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		assert!(api.retrieve_summary_info(m, true, 1)?.0);
+		Ok(())
+	})?;
+	assert_eq!(node_url!(wallet2), "node_a");
+
+	// A node on another chain at the same height isn't trusted, the next refresh
+	// moves away from it
+	nodes.lies.write().forked = vec!["node_a".to_owned()];
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		assert!(api.retrieve_summary_info(m, true, 1)?.0);
+		Ok(())
+	})?;
+	assert_eq!(node_url!(wallet2), "node_b");
+
+	// Neither is a node lagging behind the others, and the configured node is used
+	// again once it agrees with them
+	*nodes.lies.write() = NodeLies {
+		lagging: vec![("node_c".to_owned(), 5)].into_iter().collect(),
+		..NodeLies::default()
+	};
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		assert!(api.retrieve_summary_info(m, true, 1)?.0);
+		Ok(())
+	})?;
+	assert_eq!(node_url!(wallet2), "node_a");
+
+	// A node that doesn't answer is left out
+	*nodes.lies.write() = NodeLies {
+		down: vec!["node_a".to_owned()],
+		..NodeLies::default()
+	};
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		assert!(api.retrieve_summary_info(m, true, 1)?.0);
+		Ok(())
+	})?;
+	assert_eq!(node_url!(wallet2), "node_b");
+
+	// Without a majority of the nodes agreeing, none is trusted and the wallet
+	// isn't refreshed
+	*nodes.lies.write() = NodeLies {
+		lagging: vec![("node_c".to_owned(), 5)].into_iter().collect(),
+		forked: vec!["node_a".to_owned()],
+		..NodeLies::default()
+	};
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		assert!(!api.retrieve_summary_info(m, true, 1)?.0);
+		Ok(())
+	})?;
+	let mut client = nodes.clone();
+	match owner::select_node_by_quorum(&mut client, &config) {
+		Err(e) => match e.kind() {
+			ErrorKind::NodeQuorum(_) => (),
+			k => panic!("unexpected error {}", k),
+		},
+		Ok(_) => panic!("node picked without a quorum"),
+	}
+
+	// Without a quorum configured, the node is left as is
+	*nodes.lies.write() = NodeLies::default();
+	let mut client = nodes.clone();
+	owner::select_node_by_quorum(&mut client, &WalletConfig::default())?;
+	assert_eq!(client.node_url(), nodes.node_url());
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_node_quorum() {
+	let test_dir = "test_output/node_quorum";
+	setup(test_dir);
+	if let Err(e) = node_quorum_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,155 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the node status is reported, and outputs aren't refreshed while it syncs
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_api::{Owner, OwnerRpcS, Token};
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_keychain as keychain;
+use grin_wallet_util::grin_util as util;
+
+use impls::test_framework::{self, LocalWalletClient, LyingNodeClient};
+use impls::{DefaultLCProvider, DefaultWalletImpl};
+use keychain::ExtKeychain;
+use libwallet::{OutputStatus, WalletInst};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use util::{Mutex, ZeroingString};
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// node status impl
+fn node_status_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		_wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let _mask1 = (&mask1_i).as_ref();
+
+	// A wallet whose node can be told to be syncing
+	let node = LyingNodeClient::new(client1.clone());
+	let wallet2 = DefaultWalletImpl::<LyingNodeClient>::new(node.clone()).unwrap();
+	let mut wallet2 = Box::new(wallet2)
+		as Box<
+			dyn WalletInst<
+				DefaultLCProvider<'static, LyingNodeClient, ExtKeychain>,
+				LyingNodeClient,
+				ExtKeychain,
+			>,
+		>;
+	let lc = wallet2.lc_provider()?;
+	lc.set_top_level_directory(&format!("{}/wallet2", test_dir))?;
+	lc.create_wallet(None, None, 32, ZeroingString::from(""), false)?;
+	let mask2_i = lc.open_wallet(None, ZeroingString::from(""), false, false)?;
+	let mask2 = (&mask2_i).as_ref();
+	let wallet2 = Arc::new(Mutex::new(wallet2));
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet2.clone(), mask2, 3, false);
+
+	// A synced node is refreshed from
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let status = api.node_status(m)?;
+		assert!(status.is_synced());
+		assert_eq!(status.height, chain.head().unwrap().height);
+		let report = api.update_wallet_state(m)?;
+		assert!(report.node_synced);
+		assert!(report.is_complete());
+		let (refreshed, outputs) = api.retrieve_outputs(m, false, false, None)?;
+		assert!(refreshed);
+		assert_eq!(outputs.len(), 3);
+		assert!(outputs
+			.iter()
+			.all(|o| o.output.status == OutputStatus::Unspent));
+		Ok(())
+	})?;
+
+	// Not a syncing one, whose outputs would look missing
+	node.lies.write().sync_status = Some("body_sync".to_owned());
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet2.clone(), mask2, 2, false);
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let status = api.node_status(m)?;
+		assert!(!status.is_synced());
+		assert_eq!(status.sync_status, "body_sync");
+		let report = api.update_wallet_state(m)?;
+		assert!(!report.node_synced);
+		assert!(!report.is_complete());
+		assert_eq!(report.outputs_total, 0);
+		let (refreshed, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		assert!(!refreshed);
+		let unspent = outputs
+			.iter()
+			.filter(|o| o.output.status == OutputStatus::Unspent)
+			.count();
+		assert_eq!(unspent, 3);
+		Ok(())
+	})?;
+
+	// The status is available through the owner RPC API
+	let api = Owner::new(wallet2.clone());
+	let token = Token {
+		keychain_mask: mask2_i.clone(),
+	};
+	let status = OwnerRpcS::node_status(&api, token).unwrap();
+	assert_eq!(status.sync_status, "body_sync");
+
+	// Until it's synced again
+	node.lies.write().sync_status = None;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (refreshed, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		assert!(refreshed);
+		assert_eq!(outputs.len(), 5);
+		assert!(outputs
+			.iter()
+			.all(|o| o.output.status == OutputStatus::Unspent));
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_node_status() {
+	let test_dir = "test_output/node_status";
+	setup(test_dir);
+	if let Err(e) = node_status_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
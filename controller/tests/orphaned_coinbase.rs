@@ -0,0 +1,176 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test coinbases whose block is orphaned
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_config as config;
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_keychain as keychain;
+use grin_wallet_util::grin_util as util;
+
+use config::WalletConfig;
+use impls::test_framework::{self, LocalWalletClient, LyingNodeClient};
+use impls::{DefaultLCProvider, DefaultWalletImpl};
+use keychain::ExtKeychain;
+use libwallet::{CoinbaseStatus, OutputStatus, TxLogEntryType, WalletInst};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use util::{Mutex, ZeroingString};
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// orphaned coinbase impl
+fn orphaned_coinbase_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// A miner whose node can be told a block was orphaned
+	let node = LyingNodeClient::new(client1.clone());
+	let wallet2 = DefaultWalletImpl::<LyingNodeClient>::new(node.clone()).unwrap();
+	let mut wallet2 = Box::new(wallet2)
+		as Box<
+			dyn WalletInst<
+				DefaultLCProvider<'static, LyingNodeClient, ExtKeychain>,
+				LyingNodeClient,
+				ExtKeychain,
+			>,
+		>;
+	let lc = wallet2.lc_provider()?;
+	lc.set_top_level_directory(&format!("{}/wallet2", test_dir))?;
+	lc.create_wallet(None, None, 32, ZeroingString::from(""), false)?;
+	let mask2_i = lc.open_wallet(None, ZeroingString::from(""), false, false)?;
+	let mask2 = (&mask2_i).as_ref();
+	let wallet2 = Arc::new(Mutex::new(wallet2));
+	set_wallet_config!(
+		wallet2,
+		WalletConfig {
+			finality_depth: Some(3),
+			..WalletConfig::default()
+		}
+	);
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet2.clone(), mask2, 3, false);
+
+	let mut commit = None;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		let newest = outputs.iter().max_by_key(|o| o.output.height).unwrap();
+		assert_eq!(newest.output.height, 3);
+		commit = Some(newest.commit);
+		Ok(())
+	})?;
+	let commit = commit.unwrap();
+
+	let coinbase = |tx_type: TxLogEntryType,
+	                output_status: OutputStatus,
+	                status: CoinbaseStatus|
+	 -> Result<(), libwallet::Error> {
+		wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+			let (_, outputs) = api.retrieve_outputs(m, true, true, None)?;
+			let output = outputs.iter().find(|o| o.commit == commit).unwrap();
+			assert_eq!(output.output.status, output_status);
+			let (_, txs) = api.retrieve_txs(m, false, output.output.tx_log_entry, None)?;
+			assert_eq!(txs[0].tx_type, tx_type);
+			assert_eq!(
+				txs[0].confirmed,
+				tx_type == TxLogEntryType::ConfirmedCoinbase
+			);
+			let (_, report) = api.retrieve_coinbase_report(m, false)?;
+			let info = report.coinbases.iter().find(|c| c.height == 3).unwrap();
+			assert_eq!(info.status, status);
+			Ok(())
+		})
+	};
+
+	// The immature coinbase of an orphaned block is unusable
+	node.lies.write().orphaned.push(commit);
+	coinbase(
+		TxLogEntryType::OrphanedCoinbase,
+		OutputStatus::Reverted,
+		CoinbaseStatus::Orphaned,
+	)?;
+	assert_eq!(
+		format!("{}", TxLogEntryType::OrphanedCoinbase),
+		"Orphaned\nCoinbase"
+	);
+
+	// but it's still looked up, and confirmed again if its block is back
+	node.lies.write().orphaned.clear();
+	coinbase(
+		TxLogEntryType::ConfirmedCoinbase,
+		OutputStatus::Unspent,
+		CoinbaseStatus::Immature,
+	)?;
+
+	// until it's past the finality depth
+	node.lies.write().orphaned.push(commit);
+	coinbase(
+		TxLogEntryType::OrphanedCoinbase,
+		OutputStatus::Reverted,
+		CoinbaseStatus::Orphaned,
+	)?;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+	coinbase(
+		TxLogEntryType::OrphanedCoinbase,
+		OutputStatus::Spent,
+		CoinbaseStatus::Orphaned,
+	)?;
+	node.lies.write().orphaned.clear();
+	coinbase(
+		TxLogEntryType::OrphanedCoinbase,
+		OutputStatus::Spent,
+		CoinbaseStatus::Orphaned,
+	)?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_orphaned_coinbase() {
+	let test_dir = "test_output/orphaned_coinbase";
+	setup(test_dir);
+	if let Err(e) = orphaned_coinbase_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,92 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the async owner API
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_api::OwnerAsync;
+use grin_wallet_libwallet as libwallet;
+
+use futures::Future;
+use impls::test_framework::{self, LocalWalletClient};
+use std::thread;
+use std::time::Duration;
+use tokio::runtime::Runtime;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// owner async impl
+fn owner_async_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	// Calls run on the runtime's threads, and give the same results as blocking ones
+	let owner = OwnerAsync::new(wallet1.clone());
+	let (_, expected_info) = owner.owner().retrieve_summary_info(mask1, true, 1)?;
+	let mut rt = Runtime::new().unwrap();
+	let calls = owner
+		.retrieve_summary_info(mask1_i.clone(), true, 1)
+		.join(owner.retrieve_txs(mask1_i.clone(), false, None, None));
+	let ((_, info), (_, txs)) = rt.block_on(calls)?;
+	assert_eq!(info.total, expected_info.total);
+	assert_eq!(txs.len(), 5);
+
+	// Any other call, polled outside of the runtime
+	let accounts = owner
+		.call(move |api| api.accounts(mask1_i.as_ref()))
+		.wait()?;
+	assert_eq!(accounts.len(), 1);
+	rt.shutdown_now().wait().unwrap();
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn owner_async() {
+	let test_dir = "test_output/owner_async";
+	setup(test_dir);
+	if let Err(e) = owner_async_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,104 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test composing owner operations under a single wallet lock
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_util::grin_core as core;
+
+use grin_wallet_libwallet as libwallet;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::InitTxArgs;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// owner context impl
+fn owner_context_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	// few values to keep things shorter
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+
+	// Get some mining done
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	// Compose several operations under a single wallet lock
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.with_context(m, |ctx| {
+			let (_, info) = ctx.retrieve_summary_info(true, 1)?;
+			let spendable = info.amount_currently_spendable;
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount: reward,
+				minimum_confirmations: 1,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: true,
+				..Default::default()
+			};
+			let slate = ctx.init_send_tx(args)?;
+			ctx.tx_lock_outputs(&slate, 0)?;
+			let (_, txs) = ctx.retrieve_txs(false, None, Some(slate.id))?;
+			assert_eq!(txs.len(), 1);
+			let (_, info) = ctx.retrieve_summary_info(false, 1)?;
+			assert_eq!(info.amount_currently_spendable, 0);
+			ctx.cancel_tx(None, Some(slate.id))?;
+			let (_, info) = ctx.retrieve_summary_info(false, 1)?;
+			assert_eq!(info.amount_currently_spendable, spendable);
+			Ok(())
+		})
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_owner_context() {
+	let test_dir = "test_output/owner_context";
+	setup(test_dir);
+	if let Err(e) = owner_context_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,109 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test shutting down the owner listener through the owner API
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_api::{Owner, OwnerRpc, OwnerRpcS};
+use grin_wallet_libwallet as libwallet;
+
+use impls::test_framework::LocalWalletClient;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use wallet::controller::ListenerShutdown;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// owner shutdown impl
+fn owner_shutdown_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	// Without a listener, there's nothing to shut down
+	let api = Owner::new(wallet1.clone());
+	assert!(api.shutdown().is_err());
+	assert!(OwnerRpc::shutdown(&api).is_err());
+	assert!(OwnerRpcS::shutdown(&api).is_err());
+
+	// Waiting for a shutdown times out until one is requested
+	let shutdown = ListenerShutdown::new();
+	let start = Instant::now();
+	assert!(!shutdown.wait_timeout(Duration::from_millis(200)));
+	assert!(start.elapsed() >= Duration::from_millis(200));
+	assert!(!shutdown.is_requested());
+
+	// The listener's hook is what the method calls, through either API version
+	let mut api = Owner::new(wallet1.clone());
+	let hooked = shutdown.clone();
+	api.shutdown_hook = Some(Arc::new(move || hooked.request()));
+	let waiter = {
+		let shutdown = shutdown.clone();
+		thread::spawn(move || {
+			let start = Instant::now();
+			shutdown.wait();
+			start.elapsed()
+		})
+	};
+	thread::sleep(Duration::from_millis(200));
+	assert!(!shutdown.is_requested());
+	OwnerRpcS::shutdown(&api).unwrap();
+	assert!(shutdown.is_requested());
+
+	// which wakes the listener up right away, rather than on its next poll
+	let waited = waiter.join().unwrap();
+	assert!(waited >= Duration::from_millis(200));
+	assert!(waited < Duration::from_secs(5));
+	let start = Instant::now();
+	assert!(shutdown.wait_timeout(Duration::from_secs(5)));
+	assert!(start.elapsed() < Duration::from_secs(1));
+	assert!(OwnerRpc::shutdown(&api).is_ok());
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_owner_shutdown() {
+	let test_dir = "test_output/owner_shutdown";
+	setup(test_dir);
+	if let Err(e) = owner_shutdown_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
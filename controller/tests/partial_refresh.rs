@@ -0,0 +1,156 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the report of a refresh some outputs couldn't be queried for
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_keychain as keychain;
+use grin_wallet_util::grin_util as util;
+
+use impls::test_framework::{self, LocalWalletClient, LyingNodeClient};
+use impls::{DefaultLCProvider, DefaultWalletImpl};
+use keychain::ExtKeychain;
+use libwallet::{InitTxArgs, OutputStatus, WalletInst};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use util::{Mutex, ZeroingString};
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// partial refresh impl
+fn partial_refresh_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		_wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let _mask1 = (&mask1_i).as_ref();
+
+	// A wallet whose node doesn't answer some queries
+	let node = LyingNodeClient::new(client1.clone());
+	let wallet2 = DefaultWalletImpl::<LyingNodeClient>::new(node.clone()).unwrap();
+	let mut wallet2 = Box::new(wallet2)
+		as Box<
+			dyn WalletInst<
+				DefaultLCProvider<'static, LyingNodeClient, ExtKeychain>,
+				LyingNodeClient,
+				ExtKeychain,
+			>,
+		>;
+	let lc = wallet2.lc_provider()?;
+	lc.set_top_level_directory(&format!("{}/wallet2", test_dir))?;
+	lc.create_wallet(None, None, 32, ZeroingString::from(""), false)?;
+	let mask2_i = lc.open_wallet(None, ZeroingString::from(""), false, false)?;
+	let mask2 = (&mask2_i).as_ref();
+	let wallet2 = Arc::new(Mutex::new(wallet2));
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet2.clone(), mask2, 5, false);
+
+	let mut unanswered = None;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let report = api.update_wallet_state(m)?;
+		assert!(report.is_complete());
+		assert_eq!(report.outputs_total, 5);
+		assert_eq!(report.outputs_failed, 0);
+		let (_, outputs) = api.retrieve_outputs(m, false, false, None)?;
+		unanswered = Some(outputs[0].commit);
+		Ok(())
+	})?;
+	let unanswered = unanswered.unwrap();
+
+	// The output the node doesn't answer for is reported and left as it was,
+	// rather than taken as gone from the chain
+	node.lies.write().unanswered.push(unanswered);
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let report = api.update_wallet_state(m)?;
+		assert!(!report.is_complete());
+		assert!(report.node_synced);
+		assert_eq!(report.outputs_total, 5);
+		assert_eq!(report.outputs_failed, 1);
+		assert_eq!(report.errors.len(), 1);
+
+		let (validated, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		assert!(!validated);
+		let output = outputs.iter().find(|o| o.commit == unanswered).unwrap();
+		assert_eq!(output.output.status, OutputStatus::Unspent);
+		let (validated, _) = api.retrieve_summary_info(m, true, 1)?;
+		assert!(!validated);
+
+		// Sends still need every output refreshed
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: 1_000_000,
+			minimum_confirmations: 1,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		match api.init_send_tx(m, args) {
+			Err(e) => match e.kind() {
+				libwallet::ErrorKind::PartialRefresh(1, 5, _) => {}
+				k => panic!("Unexpected error {}", k),
+			},
+			Ok(_) => panic!("Sent from a partial refresh"),
+		}
+		Ok(())
+	})?;
+
+	// Until the node answers again
+	node.lies.write().unanswered.clear();
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let report = api.update_wallet_state(m)?;
+		assert!(report.is_complete());
+		let (validated, _) = api.retrieve_outputs(m, false, true, None)?;
+		assert!(validated);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_partial_refresh() {
+	let test_dir = "test_output/partial_refresh";
+	setup(test_dir);
+	if let Err(e) = partial_refresh_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,254 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the recipient contributing an input to a payjoin send
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_config as config;
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use config::WalletConfig;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, Slate, TxLogEntryType};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// payjoin impl
+fn payjoin_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	// Wallet 2 has a single output old enough to be contributed
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet2.clone(), mask2, 1, false);
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.amount_currently_spendable, reward);
+		Ok(())
+	})?;
+	{
+		let mut w_lock = wallet2.lock();
+		let lc = w_lock.lc_provider()?;
+		lc.set_wallet_config(WalletConfig {
+			payjoin_contribute_input: Some(true),
+			..WalletConfig::default()
+		});
+	}
+
+	let mut slate = Slate::blank(2);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: reward / 2,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			payjoin: Some(true),
+			..Default::default()
+		};
+		slate = api.init_send_tx(m, args)?;
+		assert!(slate.payjoin_requested());
+		let sender_inputs = slate.tx.inputs().len();
+		slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		assert_eq!(slate.tx.inputs().len(), sender_inputs + 1);
+		api.tx_lock_outputs(m, &slate, 0)?;
+		slate = api.finalize_tx(m, &slate)?;
+		api.post_tx(m, &slate.tx, false)?;
+		Ok(())
+	})?;
+
+	// The contributed input is locked until the transaction confirms
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, false, 1)?;
+		assert_eq!(info.amount_locked, reward);
+		let (_, txs) = api.retrieve_txs(m, false, None, Some(slate.id))?;
+		let tx = txs.iter().find(|t| t.tx_type == TxLogEntryType::TxReceived);
+		let tx = tx.unwrap();
+		assert_eq!(tx.num_inputs, 1);
+		assert_eq!(tx.amount_debited, reward);
+		assert_eq!(tx.amount_credited, reward + reward / 2);
+		Ok(())
+	})?;
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.amount_locked, 0);
+		assert_eq!(info.total, reward + reward / 2);
+		let (_, outputs) = api.retrieve_outputs(m, false, false, None)?;
+		assert_eq!(outputs.len(), 1);
+		assert_eq!(outputs[0].output.value, reward + reward / 2);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+/// payjoin release impl
+fn payjoin_release_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Wallet 2 releases the inputs of payjoins not posted right away, and
+	// contributes to one payjoin an hour
+	{
+		let mut w_lock = wallet2.lock();
+		let lc = w_lock.lc_provider()?;
+		lc.set_wallet_config(WalletConfig {
+			payjoin_contribute_input: Some(true),
+			payjoin_release_after_mins: Some(0),
+			payjoin_max_contributions_per_hour: Some(1),
+			..WalletConfig::default()
+		});
+	}
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet2.clone(), mask2, 1, false);
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	let mut slates = vec![];
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		for _ in 0..2 {
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount: reward / 2,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: false,
+				payjoin: Some(true),
+				..Default::default()
+			};
+			let slate = api.init_send_tx(m, args)?;
+			let sender_inputs = slate.tx.inputs().len();
+			let slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+			slates.push((slate, sender_inputs));
+		}
+		Ok(())
+	})?;
+
+	// Only the first payjoin got an input, the second was over the limit
+	assert_eq!(slates[0].0.tx.inputs().len(), slates[0].1 + 1);
+	assert_eq!(slates[1].0.tx.inputs().len(), slates[1].1);
+
+	// The first payjoin is never posted, so its input is released
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, false, 1)?;
+		assert_eq!(info.amount_locked, reward);
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.amount_locked, 0);
+		assert_eq!(info.amount_currently_spendable, reward);
+		let (_, txs) = api.retrieve_txs(m, false, None, Some(slates[0].0.id))?;
+		assert_eq!(txs[0].tx_type, TxLogEntryType::TxReceivedCancelled);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_payjoin() {
+	let test_dir = "test_output/payjoin";
+	setup(test_dir);
+	if let Err(e) = payjoin_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
+
+#[test]
+fn wallet_payjoin_release() {
+	let test_dir = "test_output/payjoin_release";
+	setup(test_dir);
+	if let Err(e) = payjoin_release_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
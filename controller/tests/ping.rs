@@ -0,0 +1,121 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test pinging the owner API
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_api::{Owner, OwnerRpcS, Token};
+use grin_wallet_libwallet as libwallet;
+
+use chrono::{Duration as ChronoDuration, Utc};
+use impls::test_framework::LocalWalletClient;
+use libwallet::ErrorKind;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// ping impl
+fn ping_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	// An open wallet answers with its time, with the right mask only
+	let api = Owner::new(wallet1.clone());
+	let before = Utc::now();
+	let ping = api.ping(mask1)?;
+	assert!(ping.wallet_open);
+	assert_eq!(ping.owner_api_version, 3);
+	assert!(ping.server_time >= before);
+	assert!(ping.server_time <= Utc::now() + ChronoDuration::seconds(1));
+	match api.ping(mask2).unwrap_err().kind() {
+		ErrorKind::InvalidKeychainMask => {}
+		e => panic!("Unexpected error: {}", e),
+	}
+
+	// The same through the secure owner RPC API
+	let token = Token {
+		keychain_mask: mask1_i.clone(),
+	};
+	let ping = OwnerRpcS::ping(&api, token).unwrap();
+	assert!(ping.wallet_open);
+	let token = Token {
+		keychain_mask: mask2_i.clone(),
+	};
+	assert!(OwnerRpcS::ping(&api, token).is_err());
+
+	// A closed wallet still answers, without a mask to check
+	{
+		let mut w_lock = wallet1.lock();
+		w_lock.lc_provider()?.close_wallet(None)?;
+	}
+	let ping = api.ping(None)?;
+	assert!(!ping.wallet_open);
+	assert_eq!(ping.owner_api_version, 3);
+	let ping = api.ping(mask2)?;
+	assert!(!ping.wallet_open);
+
+	// The other wallet is unaffected
+	let api = Owner::new(wallet2.clone());
+	assert!(api.ping(mask2)?.wallet_open);
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_ping() {
+	let test_dir = "test_output/ping";
+	setup(test_dir);
+	if let Err(e) = ping_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
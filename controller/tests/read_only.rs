@@ -0,0 +1,147 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test a wallet opened read-only can't be changed
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_keychain as keychain;
+use grin_wallet_util::grin_util as util;
+
+use impls::test_framework::{self, LocalWalletClient};
+use impls::{DefaultLCProvider, DefaultWalletImpl};
+use keychain::ExtKeychain;
+use libwallet::{InitTxArgs, WalletInst};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use util::{Mutex, ZeroingString};
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// read only impl
+fn read_only_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+	let mut total = 0;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		total = api.retrieve_summary_info(m, true, 1)?.1.total;
+		Ok(())
+	})?;
+	{
+		let mut w_lock = wallet1.lock();
+		w_lock.lc_provider()?.close_wallet(None)?;
+	}
+
+	// Open the same wallet read-only
+	let reader = DefaultWalletImpl::<LocalWalletClient>::new(client1.clone()).unwrap();
+	let mut reader = Box::new(reader)
+		as Box<
+			dyn WalletInst<
+				DefaultLCProvider<'static, LocalWalletClient, ExtKeychain>,
+				LocalWalletClient,
+				ExtKeychain,
+			>,
+		>;
+	let lc = reader.lc_provider()?;
+	lc.set_top_level_directory(&format!("{}/wallet1", test_dir))?;
+	lc.set_read_only(true);
+	let mask2_i = lc.open_wallet(None, ZeroingString::from(""), false, false)?;
+	let mask2 = (&mask2_i).as_ref();
+	let reader = Arc::new(Mutex::new(reader));
+
+	// Everything can be read
+	wallet::controller::owner_single_use(reader.clone(), mask2, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, false, 1)?;
+		assert_eq!(info.total, total);
+		let (_, outputs) = api.retrieve_outputs(m, false, false, None)?;
+		assert_eq!(outputs.len(), 5);
+		let (_, txs) = api.retrieve_txs(m, false, None, None)?;
+		assert_eq!(txs.len(), 5);
+		assert_eq!(api.accounts(m)?.len(), 1);
+		Ok(())
+	})?;
+
+	// Nothing can be written, even by what doesn't go through a batch
+	{
+		wallet_inst!(reader, w);
+		assert!(w.batch(mask2).is_err());
+		assert!(w.next_child(mask2).is_err());
+	}
+	wallet::controller::owner_single_use(reader.clone(), mask2, |api, m| {
+		assert!(api.create_account_path(m, "other").is_err());
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: 1_000_000_000,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		assert!(api.init_send_tx(m, args).is_err());
+		Ok(())
+	})?;
+
+	// And nothing was
+	wallet::controller::owner_single_use(reader.clone(), mask2, |api, m| {
+		assert_eq!(api.accounts(m)?.len(), 1);
+		let (_, outputs) = api.retrieve_outputs(m, true, false, None)?;
+		assert_eq!(outputs.len(), 5);
+		let (_, info) = api.retrieve_summary_info(m, false, 1)?;
+		assert_eq!(info.total, total);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_read_only() {
+	let test_dir = "test_output/read_only";
+	setup(test_dir);
+	if let Err(e) = read_only_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,142 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test rebuilding the indexes derived from outputs and the tx log
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{IndexRebuildReport, InitTxArgs, OutputStatus, TxLogEntryType};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// rebuild indexes impl
+fn rebuild_indexes_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	let mut slate_id = None;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: reward / 2,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		let slate = api.init_send_tx(m, args)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		slate_id = Some(slate.id);
+
+		// Nothing to rebuild in a consistent wallet
+		assert_eq!(api.rebuild_indexes(m)?, IndexRebuildReport::default());
+		Ok(())
+	})?;
+
+	// Corrupt the derived data
+	{
+		wallet_inst!(wallet1, w);
+		let mut output = w
+			.iter()
+			.find(|o| o.status == OutputStatus::Unspent)
+			.unwrap();
+		output.commit = None;
+		output.tx_log_entry = Some(1000);
+		let txs = w.tx_log_iter().collect::<Vec<_>>();
+		let mut sent = txs
+			.iter()
+			.find(|t| t.tx_slate_id == slate_id)
+			.unwrap()
+			.clone();
+		sent.stored_tx = None;
+		let mut coinbase = txs
+			.iter()
+			.find(|t| t.tx_type == TxLogEntryType::ConfirmedCoinbase)
+			.unwrap()
+			.clone();
+		coinbase.stored_tx = Some("missing.grintx".to_owned());
+
+		let mut batch = w.batch(mask1)?;
+		batch.save(output)?;
+		let parent_key_id = sent.parent_key_id.clone();
+		batch.save_tx_log_entry(sent, &parent_key_id)?;
+		batch.save_tx_log_entry(coinbase, &parent_key_id)?;
+		batch.commit()?;
+	}
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let report = api.rebuild_indexes(m)?;
+		assert_eq!(report.commits_fixed, 1);
+		assert_eq!(report.tx_links_pruned, 1);
+		assert_eq!(report.stored_txs_relinked, 1);
+		assert_eq!(report.stored_txs_pruned, 1);
+
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		assert!(outputs.iter().all(|o| o.output.commit.is_some()));
+		let (_, txs) = api.retrieve_txs(m, false, None, slate_id)?;
+		assert!(api.get_stored_tx(m, &txs[0])?.is_some());
+
+		// A second run has nothing left to do
+		assert_eq!(api.rebuild_indexes(m)?, IndexRebuildReport::default());
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_rebuild_indexes() {
+	let test_dir = "test_output/rebuild_indexes";
+	setup(test_dir);
+	if let Err(e) = rebuild_indexes_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,180 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test reconciling an external ledger against the wallet
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use core::core::amount_to_hr_string;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::api_impl::owner::parse_ledger;
+use libwallet::{InitTxArgs, LedgerDirection, ReconcileArgs};
+use std::thread;
+use std::time::Duration;
+use uuid::Uuid;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// reconcile impl
+fn reconcile_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	// Two deposits to wallet 2, the exchange
+	let amounts = [reward / 4, reward / 8];
+	let mut slate_ids = vec![];
+	for amount in amounts.iter() {
+		wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount: *amount,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: false,
+				..Default::default()
+			};
+			let slate = api.init_send_tx(m, args)?;
+			let slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+			api.tx_lock_outputs(m, &slate, 0)?;
+			let slate = api.finalize_tx(m, &slate)?;
+			api.post_tx(m, &slate.tx, false)?;
+			slate_ids.push(slate.id);
+			Ok(())
+		})?;
+	}
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+
+	// The ledger gets the second amount wrong, and expects a third deposit
+	let missing_id = Uuid::new_v4();
+	let ledger = format!(
+		"tx_slate_id,direction,amount,reference\n\
+		 {},deposit,{},a1\n\
+		 {},deposit,{},a2\n\
+		 {},deposit,1,a3\n",
+		slate_ids[0],
+		amount_to_hr_string(amounts[0], false),
+		slate_ids[1],
+		amount_to_hr_string(amounts[1] + 1, false),
+		missing_id,
+	);
+	let entries = parse_ledger(&ledger)?;
+	assert_eq!(entries.len(), 3);
+	assert_eq!(entries[0].direction, LedgerDirection::Deposit);
+	assert_eq!(entries[0].amount, amounts[0]);
+	assert!(parse_ledger("not,a,ledger").is_err());
+
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let args = ReconcileArgs {
+			entries: entries.clone(),
+			expected_balance: Some(amounts[0] + amounts[1]),
+			since: None,
+		};
+		let (_, report) = api.reconcile(m, true, args)?;
+		assert_eq!(report.matched, 1);
+		assert_eq!(report.amount_mismatches.len(), 1);
+		assert_eq!(report.amount_mismatches[0].actual_amount, amounts[1]);
+		assert_eq!(report.missing_deposits.len(), 1);
+		assert_eq!(report.missing_deposits[0].tx_slate_id, missing_id);
+		assert!(report.unexpected_deposits.is_empty());
+		assert!(!report.is_reconciled());
+
+		// A deposit left out of the ledger is unexpected
+		let args = ReconcileArgs {
+			entries: entries[..1].to_vec(),
+			..Default::default()
+		};
+		let (_, report) = api.reconcile(m, true, args)?;
+		assert_eq!(report.matched, 1);
+		assert_eq!(report.unexpected_deposits.len(), 1);
+		assert_eq!(
+			report.unexpected_deposits[0].tx_slate_id,
+			Some(slate_ids[1])
+		);
+
+		// The full and correct ledger matches
+		let mut entries = entries[..2].to_vec();
+		entries[1].amount = amounts[1];
+		let args = ReconcileArgs {
+			entries,
+			expected_balance: Some(amounts[0] + amounts[1]),
+			since: None,
+		};
+		let (_, report) = api.reconcile(m, true, args)?;
+		assert!(report.is_reconciled());
+		Ok(())
+	})?;
+
+	// The sends show up as unexpected on the sending side
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, report) = api.reconcile(m, true, ReconcileArgs::default())?;
+		assert_eq!(report.unexpected_sends.len(), 2);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_reconcile() {
+	let test_dir = "test_output/reconcile";
+	setup(test_dir);
+	if let Err(e) = reconcile_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,153 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test output reservations for scheduled payments
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{ErrorKind, InitTxArgs, OutputStatus, ReserveOutputsArgs};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// reservation impl
+fn reservation_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		let spendable = info.amount_currently_spendable;
+
+		// Hold outputs for a payment of one reward, two outputs are needed with the fee
+		let args = ReserveOutputsArgs {
+			label: "rent".to_owned(),
+			amount: reward,
+			minimum_confirmations: 1,
+			expires_in_secs: 3600,
+		};
+		let held = api.reserve_outputs(m, args)?;
+		assert_eq!(held.len(), 2);
+
+		// Ad-hoc sends can't use the held outputs
+		let mut args = InitTxArgs {
+			src_acct_name: None,
+			amount: spendable - reward,
+			minimum_confirmations: 1,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: true,
+			estimate_only: Some(true),
+			..Default::default()
+		};
+		match api.init_send_tx(m, args.clone()).unwrap_err().kind() {
+			ErrorKind::NotEnoughFunds { .. } => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+
+		// The scheduled payment can
+		args.reservation = Some("rent".to_owned());
+		api.init_send_tx(m, args.clone())?;
+
+		args.reservation = None;
+		args.amount = reward;
+		args.estimate_only = None;
+		let slate = api.init_send_tx(m, args.clone())?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		let (_, outputs) = api.retrieve_outputs(m, false, false, None)?;
+		for h in held.iter() {
+			let o = outputs
+				.iter()
+				.find(|o| o.output.key_id == h.key_id)
+				.unwrap();
+			assert_eq!(o.output.status, OutputStatus::Unspent);
+			assert_eq!(o.output.reservation, h.reservation);
+		}
+		api.cancel_tx(m, None, Some(slate.id))?;
+
+		// Once released, the outputs are available to any send again
+		api.release_reservation(m, "rent")?;
+		args.amount = spendable - reward;
+		args.estimate_only = Some(true);
+		api.init_send_tx(m, args.clone())?;
+
+		// Outputs can also be locked by commitment for an external coordinator
+		let commits: Vec<String> = outputs
+			.iter()
+			.filter(|o| o.output.status == OutputStatus::Unspent)
+			.take(2)
+			.map(|o| o.output.commit.clone().unwrap())
+			.collect();
+		let locked = api.lock_outputs(m, &commits, 3600, "swap-1")?;
+		assert_eq!(locked.len(), 2);
+		assert!(api.lock_outputs(m, &commits[..1], 3600, "swap-2").is_err());
+		let unknown = vec![commits[0].clone(), "08".repeat(33)];
+		assert!(api.lock_outputs(m, &unknown, 3600, "swap-1").is_err());
+		assert!(api.init_send_tx(m, args.clone()).is_err());
+		args.reservation = Some("swap-1".to_owned());
+		api.init_send_tx(m, args.clone())?;
+
+		assert_eq!(api.unlock_by_ref(m, "swap-1")?.len(), 2);
+		assert!(api.unlock_by_ref(m, "swap-1")?.is_empty());
+		args.reservation = None;
+		api.init_send_tx(m, args)?;
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_output_reservation() {
+	let test_dir = "test_output/reservation";
+	setup(test_dir);
+	if let Err(e) = reservation_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
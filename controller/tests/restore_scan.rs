@@ -0,0 +1,138 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test a step-wise restore of a wallet
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_util::ZeroingString;
+
+use impls::test_framework::{self, LocalWalletClient};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// step-wise restore impl
+fn restore_scan_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	let seed_phrase = "affair pistol cancel crush garment candy ancient flag work \
+	                   market crush dry stand focus mutual weapon offer ceiling rival turn team spring \
+	                   where swift";
+	let seed_phrase = Some(ZeroingString::from(seed_phrase));
+
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		seed_phrase,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		seed_phrase,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+	create_wallet_and_add!(
+		m_client,
+		miner,
+		miner_mask_i,
+		test_dir,
+		"miner",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let miner_mask = (&miner_mask_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 2, false);
+	let _ = test_framework::award_blocks_to_wallet(&chain, miner.clone(), miner_mask, 3, false);
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 2, false);
+
+	let mut balance = 0;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		balance = outputs.iter().map(|o| o.output.value).sum();
+		Ok(())
+	})?;
+
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		// A few outputs at a time, with nothing written to the wallet meanwhile
+		let mut scan = api.restore_scan_start(m, None)?;
+		assert!(scan.total() > 2);
+		let mut steps = 0;
+		while !scan.is_complete() {
+			scan = api.restore_scan(m, scan, 2)?;
+			steps += 1;
+			let (_, outputs) = api.retrieve_outputs(m, false, false, None)?;
+			assert!(outputs.is_empty());
+		}
+		assert!(steps > 1);
+		assert_eq!(scan.remaining(), 0);
+		assert_eq!(scan.outputs.len(), 4);
+		assert_eq!(scan.amount_found(), balance);
+
+		// Outputs that aren't the commitment of their value under their key are refused
+		let mut tampered = scan.clone();
+		tampered.outputs[0].value += 1;
+		assert!(api.restore_commit(m, &tampered).is_err());
+
+		api.restore_commit(m, &scan)?;
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		assert_eq!(outputs.len(), 4);
+
+		// Only an empty wallet is restored
+		assert!(api.restore_commit(m, &scan).is_err());
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_restore_scan() {
+	let test_dir = "test_output/restore_scan";
+	setup(test_dir);
+	if let Err(e) = restore_scan_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,166 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test received transactions reverted by a reorg
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_config as config;
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_keychain as keychain;
+use grin_wallet_util::grin_util as util;
+
+use config::WalletConfig;
+use impls::test_framework::{self, LocalWalletClient, LyingNodeClient};
+use impls::{DefaultLCProvider, DefaultWalletImpl};
+use keychain::ExtKeychain;
+use libwallet::{InitTxArgs, OutputStatus, Slate, TxLogEntryType, WalletInst};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use util::{Mutex, ZeroingString};
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// reverted tx impl
+fn reverted_tx_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// A recipient whose node can be told a block was reorged out
+	let node = LyingNodeClient::new(client1.clone());
+	let wallet2 = DefaultWalletImpl::<LyingNodeClient>::new(node.clone()).unwrap();
+	let mut wallet2 = Box::new(wallet2)
+		as Box<
+			dyn WalletInst<
+				DefaultLCProvider<'static, LyingNodeClient, ExtKeychain>,
+				LyingNodeClient,
+				ExtKeychain,
+			>,
+		>;
+	let lc = wallet2.lc_provider()?;
+	lc.set_top_level_directory(&format!("{}/wallet2", test_dir))?;
+	lc.create_wallet(None, None, 32, ZeroingString::from(""), false)?;
+	let mask2_i = lc.open_wallet(None, ZeroingString::from(""), false, false)?;
+	let mask2 = (&mask2_i).as_ref();
+	let wallet2 = Arc::new(Mutex::new(wallet2));
+	set_wallet_config!(
+		wallet2,
+		WalletConfig {
+			finality_depth: Some(10),
+			..WalletConfig::default()
+		}
+	);
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	// Wallet 1 sends to wallet 2, and the tx is confirmed
+	let amount = 1_000_000_000;
+	let mut slate = Slate::blank(1);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: amount,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		slate = api.init_send_tx(m, args)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		Ok(())
+	})?;
+	wallet::controller::foreign_single_use(wallet2.clone(), mask2_i.clone(), |api| {
+		slate = api.receive_tx(&slate, None, None)?;
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		slate = api.finalize_tx(m, &slate)?;
+		api.post_tx(m, &slate.tx, false)?;
+		Ok(())
+	})?;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+
+	let received = |tx_type: TxLogEntryType,
+	                output_status: OutputStatus,
+	                reverted: u64|
+	 -> Result<(), libwallet::Error> {
+		wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+			let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+			assert_eq!(info.amount_reverted, reverted);
+			assert_eq!(info.total, amount - reverted);
+			let (_, outputs) = api.retrieve_outputs(m, true, false, None)?;
+			assert_eq!(outputs.len(), 1);
+			assert_eq!(outputs[0].output.status, output_status);
+			let (_, txs) = api.retrieve_txs(m, false, None, Some(slate.id))?;
+			assert_eq!(txs[0].tx_type, tx_type);
+			assert_eq!(txs[0].confirmed, tx_type == TxLogEntryType::TxReceived);
+			Ok(())
+		})
+	};
+	received(TxLogEntryType::TxReceived, OutputStatus::Unspent, 0)?;
+
+	// Once the block of the tx is reorged out, its amount is reported apart
+	let mut commit = None;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, outputs) = api.retrieve_outputs(m, false, false, None)?;
+		commit = Some(outputs[0].commit);
+		Ok(())
+	})?;
+	node.lies.write().orphaned.push(commit.unwrap());
+	received(TxLogEntryType::TxReverted, OutputStatus::Reverted, amount)?;
+
+	// and it's received again if the tx is confirmed again
+	node.lies.write().orphaned.clear();
+	received(TxLogEntryType::TxReceived, OutputStatus::Unspent, 0)?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_reverted_tx() {
+	let test_dir = "test_output/reverted_tx";
+	setup(test_dir);
+	if let Err(e) = reverted_tx_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
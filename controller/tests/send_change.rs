@@ -0,0 +1,126 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the change outputs returned when a send is initiated
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, OutputStatus};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// send change impl
+fn send_change_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	let amount = 1_000_000_000;
+	for num_change_outputs in &[1, 3] {
+		wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount: amount,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: *num_change_outputs,
+				selection_strategy_is_use_all: false,
+				..Default::default()
+			};
+			let result = api.init_send_tx_with_change(m, args)?;
+			let change_outputs = result.change_outputs;
+			assert_eq!(change_outputs.len(), *num_change_outputs as usize);
+			let mut slate = client1.send_tx_slate_direct("wallet2", &result.slate)?;
+			api.tx_lock_outputs(m, &slate, 0)?;
+			slate = api.finalize_tx(m, &slate)?;
+
+			// the change outputs are those the wallet saved for the send
+			let (_, txs) = api.retrieve_txs(m, false, None, Some(slate.id))?;
+			let (_, outputs) = api.retrieve_outputs(m, true, false, Some(txs[0].id))?;
+			let mut spent = 0;
+			for o in outputs {
+				match o.output.status {
+					OutputStatus::Locked => spent += o.output.value,
+					OutputStatus::Unconfirmed => {
+						let change = change_outputs
+							.iter()
+							.find(|c| c.key_id == o.output.key_id)
+							.unwrap();
+						assert_eq!(change.commit, o.commit);
+						assert_eq!(change.value, o.output.value);
+					}
+					_ => panic!("unexpected output status {}", o.output.status),
+				}
+			}
+			// all that's spent is sent, paid as fee or back as change
+			let change: u64 = change_outputs.iter().map(|c| c.value).sum();
+			assert_eq!(spent, amount + slate.fee + change);
+			Ok(())
+		})?;
+	}
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_send_change() {
+	let test_dir = "test_output/send_change";
+	setup(test_dir);
+	if let Err(e) = send_change_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
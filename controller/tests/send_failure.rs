@@ -0,0 +1,159 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the cancellation of failed sends each wallet is configured with
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_config as config;
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use config::WalletConfig;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, InitTxSendArgs, TxLogEntryType};
+use std::net::TcpListener;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// send failure impl
+fn send_failure_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Only wallet1 drops the sends it fails to deliver
+	set_wallet_config!(
+		wallet1,
+		WalletConfig {
+			cancel_tx_on_send_failure: Some(true),
+			..WalletConfig::default()
+		}
+	);
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet2.clone(), mask2, 3, false);
+
+	// A recipient not listening anymore
+	let dest = {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		format!("http://{}", listener.local_addr().unwrap())
+	};
+	let args = InitTxArgs {
+		src_acct_name: None,
+		amount: core::consensus::MWC_FIRST_GROUP_REWARD / 2,
+		minimum_confirmations: 1,
+		max_outputs: 500,
+		num_change_outputs: 1,
+		selection_strategy_is_use_all: false,
+		send_args: Some(InitTxSendArgs {
+			method: "http".to_owned(),
+			dest: dest.clone(),
+			finalize: true,
+			post_tx: true,
+			fluff: false,
+			cancel_on_failure: None,
+			timeout_secs: Some(5),
+		}),
+		..Default::default()
+	};
+
+	// Both wallets cancel the tx, unlocking its outputs
+	let mut slate_ids = vec![];
+	for (wallet, mask) in vec![(wallet1.clone(), mask1), (wallet2.clone(), mask2)] {
+		wallet::controller::owner_single_use(wallet, mask, |api, m| {
+			assert!(api.init_send_tx(m, args.clone()).is_err());
+			let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+			let tx = txs.iter().find(|t| t.tx_slate_id.is_some()).unwrap();
+			assert_eq!(tx.tx_type, TxLogEntryType::TxSentCancelled);
+			let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+			assert_eq!(info.amount_locked, 0);
+			slate_ids.push(tx.tx_slate_id.unwrap());
+			Ok(())
+		})?;
+	}
+
+	// wallet1 dropped the send, wallet2 keeps it to be retried
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		assert!(api.retrieve_pending_dispatches(m, None)?.is_empty());
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let pending = api.retrieve_pending_dispatches(m, None)?;
+		assert_eq!(pending.len(), 1);
+		assert_eq!(pending[0].slate_id, slate_ids[1]);
+		assert!(pending[0].cancelled);
+		assert_eq!(pending[0].attempts, 1);
+		Ok(())
+	})?;
+
+	// The send arguments take precedence over the configuration
+	let mut keep_args = args.clone();
+	keep_args.send_args.as_mut().unwrap().cancel_on_failure = Some(false);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		assert!(api.init_send_tx(m, keep_args).is_err());
+		assert_eq!(api.retrieve_pending_dispatches(m, None)?.len(), 1);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_send_failure() {
+	let test_dir = "test_output/send_failure";
+	setup(test_dir);
+	if let Err(e) = send_failure_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
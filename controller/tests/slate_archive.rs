@@ -0,0 +1,157 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the archive of the slates received and sent
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_config as config;
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use config::WalletConfig;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, Slate, SlateDirection};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// slate archive impl
+fn slate_archive_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	// Slates aren't archived unless asked for
+	let mut slate = Slate::blank(2);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: reward / 2,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: true,
+			..Default::default()
+		};
+		slate = api.init_send_tx(m, args)?;
+		assert!(api.get_slate_history(m, &slate.id)?.is_empty());
+		Ok(())
+	})?;
+
+	let archiving = WalletConfig {
+		slate_archive_retention_days: Some(30),
+		..WalletConfig::default()
+	};
+	set_wallet_config!(wallet1, archiving.clone());
+	set_wallet_config!(wallet2, archiving);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: reward / 2,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: true,
+			..Default::default()
+		};
+		slate = api.init_send_tx(m, args)?;
+		slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		slate = api.finalize_tx(m, &slate)?;
+		Ok(())
+	})?;
+
+	// The sender keeps what it sent and what it got back, at each round
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let history = api.get_slate_history(m, &slate.id)?;
+		let steps: Vec<(&str, SlateDirection)> = history
+			.iter()
+			.map(|s| (s.step.as_str(), s.direction))
+			.collect();
+		assert_eq!(
+			steps,
+			vec![
+				("init_send_tx", SlateDirection::Outbound),
+				("finalize_tx", SlateDirection::Inbound),
+				("finalize_tx", SlateDirection::Outbound),
+			]
+		);
+		assert_eq!(history[0].slate.participant_data.len(), 1);
+		assert_eq!(history[1].slate.participant_data.len(), 2);
+		Ok(())
+	})?;
+
+	// And so does the recipient
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let history = api.get_slate_history(m, &slate.id)?;
+		assert_eq!(history.len(), 2);
+		assert_eq!(history[0].direction, SlateDirection::Inbound);
+		assert_eq!(history[1].direction, SlateDirection::Outbound);
+		assert!(history.iter().all(|s| s.step == "receive_tx"));
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_slate_archive() {
+	let test_dir = "test_output/slate_archive";
+	setup(test_dir);
+	if let Err(e) = slate_archive_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,232 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test slates sent to a foreign listener through slate exchanges
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+use grin_wallet_util::grin_util as util;
+
+use core::global::ChainTypes;
+use impls::test_framework::{self, LocalWalletClient};
+use impls::{
+	Client, HttpSlateSender, ReceiveChallenges, SlateExchangeChunk, SlateExchangeStatus,
+	SlateSender, SLATE_EXCHANGE_PATH,
+};
+use libwallet::{InitTxArgs, Slate, TxLogEntryType};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use url::Url;
+use util::Mutex;
+use wallet::controller::{ForeignApiRoles, ListenerLimits};
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// slate exchange impl
+fn slate_exchange_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	// Wallet 2 receives on its foreign listener, and on another requiring a
+	// challenge solved
+	let addr = "127.0.0.1:34415";
+	let challenge_addr = "127.0.0.1:34422";
+	for (addr, challenges) in vec![
+		(addr, None),
+		(challenge_addr, Some(ReceiveChallenges::new(8)?)),
+	] {
+		let listener_wallet = wallet2.clone();
+		let listener_mask = Arc::new(Mutex::new(mask2_i.clone()));
+		thread::spawn(move || {
+			if let Err(e) = wallet::controller::foreign_listener(
+				listener_wallet,
+				listener_mask,
+				addr,
+				None,
+				None,
+				ListenerLimits::default(),
+				challenges,
+				ForeignApiRoles::default(),
+			) {
+				error!("Foreign listener error: {}", e);
+			}
+		});
+	}
+	thread::sleep(Duration::from_millis(500));
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	let args = InitTxArgs {
+		src_acct_name: None,
+		amount: reward / 2,
+		minimum_confirmations: 2,
+		max_outputs: 500,
+		num_change_outputs: 1,
+		selection_strategy_is_use_all: false,
+		..Default::default()
+	};
+
+	// A slate sent over HTTP goes through an exchange
+	let url = Url::parse(&format!("http://{}", addr)).unwrap();
+	let sender = HttpSlateSender::new(url, ChainTypes::AutomatedTesting, None, None).unwrap();
+	let mut slate = Slate::blank(1);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let slate_i = api.init_send_tx(m, args.clone())?;
+		slate = sender.send_tx(&slate_i)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		slate = api.finalize_tx(m, &slate)?;
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet2.clone(), (&mask2_i).as_ref(), |api, m| {
+		let (_, txs) = api.retrieve_txs(m, false, None, Some(slate.id))?;
+		assert_eq!(txs.len(), 1);
+		assert_eq!(txs[0].tx_type, TxLogEntryType::TxReceived);
+		Ok(())
+	})?;
+
+	// The exchanges aren't locked while a slate is received, so a sender asking
+	// for the status of its exchange isn't held up by another slate
+	let mut slate_i = Slate::blank(1);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		slate_i = api.init_send_tx(m, args.clone())?;
+		Ok(())
+	})?;
+	let data = serde_json::to_string(&slate_i).unwrap();
+	let chunk = SlateExchangeChunk {
+		token: "held".to_owned(),
+		slate_id: slate_i.id,
+		offset: 0,
+		total: data.len() as u64,
+		data,
+	};
+	let exchange_url = format!("http://{}{}", addr, SLATE_EXCHANGE_PATH);
+	let client = Client::new(false).unwrap();
+
+	// Holding the recipient wallet keeps its slate from being received
+	let held = wallet2.lock();
+	let post = {
+		let (client, exchange_url) = (client.clone(), exchange_url.clone());
+		thread::spawn(move || {
+			client
+				.post::<_, SlateExchangeStatus>(
+					&exchange_url,
+					None,
+					&chunk,
+					ChainTypes::AutomatedTesting,
+				)
+				.unwrap()
+		})
+	};
+	thread::sleep(Duration::from_millis(500));
+	let (tx, rx) = mpsc::channel();
+	{
+		let (client, exchange_url) = (client.clone(), exchange_url.clone());
+		thread::spawn(move || {
+			let status_url = format!("{}?token=held", exchange_url);
+			let status =
+				client.get::<SlateExchangeStatus>(&status_url, None, ChainTypes::AutomatedTesting);
+			let _ = tx.send(status.unwrap());
+		});
+	}
+	let status = rx
+		.recv_timeout(Duration::from_secs(5))
+		.expect("status held up by the slate being received");
+	// the whole slate is in, and the reply is still to come
+	assert!(status.total > 0);
+	assert_eq!(status.received, status.total);
+	assert!(status.response.is_none());
+
+	drop(held);
+	let status = post.join().unwrap();
+	let response = status.response.unwrap();
+	assert!(response["result"]["Ok"] != serde_json::Value::Null);
+	let status_url = format!("{}?token=held", exchange_url);
+	let status = client
+		.get::<SlateExchangeStatus>(&status_url, None, ChainTypes::AutomatedTesting)
+		.unwrap();
+	assert_eq!(status.response, Some(response));
+
+	// A listener requiring a receive challenge keeps nothing of a slate sent
+	// without one solved for it
+	let data = serde_json::to_string(&slate_i).unwrap();
+	let chunk = SlateExchangeChunk {
+		token: "unsolved".to_owned(),
+		slate_id: slate_i.id,
+		offset: 0,
+		total: data.len() as u64,
+		data,
+	};
+	let exchange_url = format!("http://{}{}", challenge_addr, SLATE_EXCHANGE_PATH);
+	assert!(client
+		.post::<_, SlateExchangeStatus>(&exchange_url, None, &chunk, ChainTypes::AutomatedTesting)
+		.is_err());
+	let status_url = format!("{}?token=unsolved", exchange_url);
+	let status = client
+		.get::<SlateExchangeStatus>(&status_url, None, ChainTypes::AutomatedTesting)
+		.unwrap();
+	assert_eq!(status.received, 0);
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_slate_exchange() {
+	let test_dir = "test_output/slate_exchange";
+	setup(test_dir);
+	if let Err(e) = slate_exchange_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,107 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test filtering of incoming slates by origin
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+
+use impls::test_framework::LocalWalletClient;
+use libwallet::api_impl::foreign;
+use libwallet::ErrorKind;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// source filter impl
+fn source_filter_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let check = |origin: &str| -> Result<(), libwallet::Error> {
+		let mut w_lock = wallet1.lock();
+		let w = w_lock.lc_provider()?.wallet_inst()?;
+		foreign::check_slate_source(&mut **w, mask1, origin)
+	};
+
+	// Everything is accepted by default
+	check("keybase:bob")?;
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.set_source_blocked(m, "Keybase:Bob ", true)?;
+		Ok(())
+	})?;
+	match check("keybase:bob").unwrap_err().kind() {
+		ErrorKind::SourceRejected(o) => assert_eq!(o, "keybase:bob"),
+		e => panic!("Unexpected error: {}", e),
+	}
+	check("keybase:carol")?;
+
+	// Once there is an allowlist, only its origins are accepted
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.set_source_blocked(m, "keybase:bob", false)?;
+		api.set_source_allowed(m, "keybase:bob", true)?;
+		Ok(())
+	})?;
+	check("keybase:bob")?;
+	assert!(check("keybase:carol").is_err());
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let filter = api.retrieve_source_filter(m)?;
+		assert!(filter.blocklist.is_empty());
+		assert_eq!(filter.allowlist, vec!["keybase:bob".to_owned()]);
+		assert_eq!(filter.hits.get("keybase:bob"), Some(&1));
+		assert_eq!(filter.hits.get("keybase:carol"), Some(&1));
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_source_filter() {
+	let test_dir = "test_output/source_filter";
+	setup(test_dir);
+	if let Err(e) = source_filter_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,171 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the refresh of the inputs of a transaction refused by the node
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+use grin_wallet_util::grin_keychain as keychain;
+use grin_wallet_util::grin_util as util;
+
+use self::core::core::{Input, OutputFeatures, Transaction};
+use impls::test_framework::{self, LocalWalletClient};
+use keychain::{Keychain, SwitchCommitmentType};
+use libwallet::api_impl::owner;
+use libwallet::{InitTxArgs, OutputStatus, RefusedTxState, Slate};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// spent inputs impl
+fn spent_inputs_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let _mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	// The node takes the tx, though the wallet is told the post failed
+	let mut slate = Slate::blank(1);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |sender_api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: core::consensus::MWC_FIRST_GROUP_REWARD / 2,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		let slate_i = sender_api.init_send_tx(m, args)?;
+		slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
+		sender_api.tx_lock_outputs(m, &slate, 0)?;
+		slate = sender_api.finalize_tx(m, &slate)?;
+		sender_api.post_tx(m, &slate.tx, false)?;
+		Ok(())
+	})?;
+
+	{
+		// The kernel is found on chain, and the inputs are left to the next refresh
+		wallet_inst!(wallet1, w);
+		assert_eq!(
+			owner::refresh_spent_inputs(&mut **w, mask1, &slate.tx)?,
+			RefusedTxState::Landed
+		);
+		assert!(w.iter().all(|o| o.status != OutputStatus::Spent));
+	}
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (refreshed, _) = api.retrieve_summary_info(m, true, 1)?;
+		assert!(refreshed);
+		Ok(())
+	})?;
+
+	{
+		wallet_inst!(wallet1, w);
+		let mut output = w
+			.iter()
+			.find(|o| o.status == OutputStatus::Unspent)
+			.unwrap();
+		let key_id = output.key_id.clone();
+		let keychain = w.keychain(mask1)?;
+		let commit = keychain.commit(output.value, &key_id, &SwitchCommitmentType::Regular)?;
+
+		// Inputs still unspent on the node are left alone
+		let tx = Transaction::empty().with_input(Input::new(OutputFeatures::Coinbase, commit));
+		assert_eq!(
+			owner::refresh_spent_inputs(&mut **w, mask1, &tx)?,
+			RefusedTxState::InputsSpent(vec![])
+		);
+
+		// An output the node doesn't have isn't taken as spent if it was confirmed
+		// above the node's tip
+		let height = output.height;
+		let spent = keychain.commit(output.value + 1, &key_id, &SwitchCommitmentType::Regular)?;
+		output.commit = Some(util::to_hex(spent.0.to_vec()));
+		output.height = w.w2n_client().get_chain_height()? + 10;
+		let mut batch = w.batch(mask1)?;
+		batch.save(output.clone())?;
+		batch.commit()?;
+		let tx = Transaction::empty().with_input(Input::new(OutputFeatures::Plain, spent));
+		assert_eq!(
+			owner::refresh_spent_inputs(&mut **w, mask1, &tx)?,
+			RefusedTxState::InputsSpent(vec![])
+		);
+		let o = w.iter().find(|o| o.key_id == key_id).unwrap();
+		assert_eq!(o.status, OutputStatus::Unspent);
+
+		// But is once the node has the block it was confirmed in
+		output.height = height;
+		let mut batch = w.batch(mask1)?;
+		batch.save(output)?;
+		batch.commit()?;
+		assert_eq!(
+			owner::refresh_spent_inputs(&mut **w, mask1, &tx)?,
+			RefusedTxState::InputsSpent(vec![spent])
+		);
+		let output = w.iter().find(|o| o.key_id == key_id).unwrap();
+		assert_eq!(output.status, OutputStatus::Spent);
+	}
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_spent_inputs() {
+	let test_dir = "test_output/spent_inputs";
+	setup(test_dir);
+	if let Err(e) = spent_inputs_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
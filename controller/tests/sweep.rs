@@ -0,0 +1,242 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test sweeping the whole balance to another wallet
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use core::{consensus, global};
+use impls::test_framework::{self, LocalWalletClient};
+use impls::SlateSender;
+use libwallet::{InitTxArgs, Slate, SweepArgs};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// Sends slates straight to another wallet of the proxy
+struct DirectSender(LocalWalletClient, &'static str);
+
+impl SlateSender for DirectSender {
+	fn send_tx(&self, slate: &Slate) -> Result<Slate, libwallet::Error> {
+		self.0.send_tx_slate_direct(self.1, slate)
+	}
+}
+
+/// sweep impl
+fn sweep_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+	create_wallet_and_add!(
+		client3,
+		wallet3,
+		mask3_i,
+		test_dir,
+		"wallet3",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask3 = (&mask3_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	// The hot wallet receives a few payments
+	for amount in &[reward / 4, reward / 3, reward / 2] {
+		wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+			let args = InitTxArgs {
+				src_acct_name: None,
+				amount: *amount,
+				minimum_confirmations: 2,
+				max_outputs: 500,
+				num_change_outputs: 1,
+				selection_strategy_is_use_all: false,
+				..Default::default()
+			};
+			let slate = api.init_send_tx(m, args)?;
+			let slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+			api.tx_lock_outputs(m, &slate, 0)?;
+			let slate = api.finalize_tx(m, &slate)?;
+			api.post_tx(m, &slate.tx, false)?;
+			Ok(())
+		})?;
+	}
+
+	let mut args = SweepArgs {
+		src_acct_name: None,
+		minimum_confirmations: 1,
+		max_outputs_per_tx: 2,
+		max_tx_weight: None,
+		fee_budget: None,
+	};
+	let mut swept = 0;
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		let plan = api.plan_sweep(m, args.clone())?;
+		assert_eq!(plan.batches.len(), 2);
+		assert_eq!(plan.batches[0].inputs.len(), 2);
+		assert_eq!(plan.dust, 0);
+		assert_eq!(plan.amount + plan.fee, info.amount_currently_spendable);
+		swept = plan.amount;
+
+		// The weight limit can force a transaction per output
+		args.max_tx_weight = Some(25);
+		assert_eq!(api.plan_sweep(m, args.clone())?.batches.len(), 3);
+		args.max_tx_weight = None;
+
+		args.fee_budget = Some(plan.fee - 1);
+		assert!(api.plan_sweep(m, args.clone()).is_err());
+
+		let sender = DirectSender(client2.clone(), "wallet3");
+		let mut progress = vec![];
+		api.execute_sweep(m, &plan, &sender, false, Some(60), |p| {
+			progress.push(p.clone())
+		})?;
+		assert_eq!(progress.len(), 4);
+		assert!(progress[3].confirmed);
+
+		// Only the coinbase of the blocks mined by the test node is left
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.amount_currently_spendable, 0);
+		Ok(())
+	})?;
+
+	wallet::controller::owner_single_use(wallet3.clone(), mask3, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.total, swept);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+/// sweep of more outputs than fit in a block impl
+fn sweep_block_weight_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	// Inputs fitting in a block along with the output and kernel of the sweep, and
+	// those of the coinbase
+	let fixed = 2 * (consensus::BLOCK_OUTPUT_WEIGHT + consensus::BLOCK_KERNEL_WEIGHT);
+	let per_block = (global::max_block_weight() - fixed) / consensus::BLOCK_INPUT_WEIGHT;
+	let blocks = per_block + global::coinbase_maturity() as usize + 5;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, blocks, false);
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		let mut args = SweepArgs {
+			src_acct_name: None,
+			minimum_confirmations: 1,
+			max_outputs_per_tx: 10_000,
+			max_tx_weight: None,
+			fee_budget: None,
+		};
+		let plan = api.plan_sweep(m, args.clone())?;
+		assert!(plan.batches.len() >= 2);
+		assert_eq!(plan.batches[0].inputs.len(), per_block);
+		assert!(plan.batches.iter().all(|b| b.inputs.len() <= per_block));
+		assert_eq!(plan.amount + plan.fee, info.amount_currently_spendable);
+
+		// nor does a weight limit above that of a block let more in
+		args.max_tx_weight = Some(10 * global::max_block_weight() as u64);
+		let capped = api.plan_sweep(m, args.clone())?;
+		assert_eq!(capped.batches, plan.batches);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_sweep() {
+	let test_dir = "test_output/sweep";
+	setup(test_dir);
+	if let Err(e) = sweep_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
+
+#[test]
+fn wallet_sweep_block_weight() {
+	let test_dir = "test_output/sweep_block_weight";
+	setup(test_dir);
+	if let Err(e) = sweep_block_weight_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
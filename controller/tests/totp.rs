@@ -0,0 +1,203 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the second factor of the owner API
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+use grin_wallet_util::grin_util::ZeroingString;
+
+use chrono::Utc;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::api_impl::owner;
+use libwallet::totp::{totp_code, TOTP_STEP_SECS};
+use libwallet::{ErrorKind, InitTxArgs};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// Whether a call was refused for want of a valid code of the second factor
+fn refused<T>(res: Result<T, libwallet::Error>) -> bool {
+	match res {
+		Err(e) => match e.kind() {
+			ErrorKind::Totp(_) => true,
+			_ => false,
+		},
+		Ok(_) => false,
+	}
+}
+
+/// totp impl
+fn totp_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	{
+		wallet_inst!(wallet1, w);
+		// No code is needed until a secret is set up and confirmed
+		owner::verify_totp(&mut **w, mask1, None)?;
+		let setup = owner::setup_totp(&mut **w, mask1)?;
+		assert!(setup.uri.contains(&setup.secret));
+		assert_eq!(setup.recovery_codes.len(), 10);
+		assert!(!owner::totp_enabled(&mut **w, mask1)?);
+		owner::verify_totp(&mut **w, mask1, None)?;
+
+		// The secret is stored encrypted with the keychain
+		let settings = w.totp_settings(mask1)?.unwrap();
+		assert_eq!(settings.secret_base32(), setup.secret);
+		let secret = settings.secret;
+		let step = Utc::now().timestamp() as u64 / TOTP_STEP_SECS;
+		assert!(owner::confirm_totp(&mut **w, mask1, "12345x").is_err());
+		let code = format!("{:06}", totp_code(&secret, step));
+		owner::confirm_totp(&mut **w, mask1, &code)?;
+		assert!(owner::totp_enabled(&mut **w, mask1)?);
+
+		// Codes are required and accepted once
+		assert!(owner::verify_totp(&mut **w, mask1, None).is_err());
+		assert!(owner::verify_totp(&mut **w, mask1, Some(&code)).is_err());
+		let next_code = format!("{:06}", totp_code(&secret, step + 1));
+		owner::verify_totp(&mut **w, mask1, Some(&next_code))?;
+		assert!(owner::verify_totp(&mut **w, mask1, Some(&next_code)).is_err());
+
+		// So are recovery codes
+		let recovery_code = setup.recovery_codes[0].clone();
+		owner::verify_totp(&mut **w, mask1, Some(&recovery_code))?;
+		assert!(owner::verify_totp(&mut **w, mask1, Some(&recovery_code)).is_err());
+
+		// An enabled secret can't be replaced, only disabled with a code
+		assert!(owner::setup_totp(&mut **w, mask1).is_err());
+		assert!(owner::disable_totp(&mut **w, mask1, None).is_err());
+		owner::disable_totp(&mut **w, mask1, Some(&setup.recovery_codes[1]))?;
+		assert!(!owner::totp_enabled(&mut **w, mask1)?);
+		owner::verify_totp(&mut **w, mask1, None)?;
+	}
+
+	let secret = {
+		wallet_inst!(wallet1, w);
+		owner::setup_totp(&mut **w, mask1)?;
+		w.totp_settings(mask1)?.unwrap().secret
+	};
+	let step = Utc::now().timestamp() as u64 / TOTP_STEP_SECS;
+	let args = InitTxArgs {
+		src_acct_name: None,
+		amount: reward,
+		minimum_confirmations: 1,
+		max_outputs: 500,
+		num_change_outputs: 1,
+		selection_strategy_is_use_all: true,
+		..Default::default()
+	};
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		// Nothing is refused until the secret is confirmed
+		let slate = api.init_send_tx(m, args.clone())?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		api.cancel_tx(m, None, Some(slate.id))?;
+		api.confirm_totp(m, &format!("{:06}", totp_code(&secret, step)))?;
+		assert!(api.totp_enabled(m)?);
+		Ok(())
+	})?;
+
+	// Then the owner API refuses spends and changes of the security settings of
+	// the wallet without a code, whoever calls it
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		assert!(refused(api.init_send_tx(m, args.clone())));
+		assert!(refused(api.cancel_tx(m, Some(1), None)));
+		assert!(refused(api.set_vault_lock(m, "default", 10)));
+		assert!(refused(api.create_approval_token(m, None)));
+		assert!(refused(api.create_foreign_api_key(m, "default", None)));
+		assert!(refused(api.lock_outputs(m, &[], 60, "ref")));
+		assert!(refused(api.unlock_by_ref(m, "ref")));
+		assert!(refused(api.release_hold(m, "ref")));
+		assert!(refused(api.change_password(
+			m,
+			None,
+			ZeroingString::from(""),
+			ZeroingString::from("new")
+		)));
+		api.with_context(m, |ctx| {
+			assert!(refused(ctx.init_send_tx(args.clone())));
+			Ok(())
+		})?;
+		// An estimate spends nothing
+		let estimate = InitTxArgs {
+			estimate_only: Some(true),
+			..args.clone()
+		};
+		api.init_send_tx(m, estimate)?;
+
+		// Nor are they made with a wrong code
+		api.set_totp_code(Some("12345x".to_owned()));
+		assert!(refused(api.init_send_tx(m, args.clone())));
+		Ok(())
+	})?;
+
+	// A code goes for every call of the instance it's set on, once
+	let code = format!("{:06}", totp_code(&secret, step + 1));
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.set_totp_code(Some(code.clone()));
+		let slate = api.init_send_tx(m, args.clone())?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		api.cancel_tx(m, None, Some(slate.id))?;
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.set_totp_code(Some(code.clone()));
+		assert!(refused(api.init_send_tx(m, args.clone())));
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_totp() {
+	let test_dir = "test_output/totp";
+	setup(test_dir);
+	if let Err(e) = totp_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
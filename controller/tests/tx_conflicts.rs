@@ -0,0 +1,162 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test sends spending the same inputs
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{InitTxArgs, Slate, TxLogEntryType};
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// tx conflicts impl
+fn tx_conflicts_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	// Two sends are built from all the same outputs before either is locked
+	let args = InitTxArgs {
+		src_acct_name: None,
+		amount: reward / 2,
+		minimum_confirmations: 2,
+		max_outputs: 500,
+		num_change_outputs: 1,
+		selection_strategy_is_use_all: true,
+		..Default::default()
+	};
+	let mut slate_a = Slate::blank(1);
+	let mut slate_b = Slate::blank(1);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let slate_i = api.init_send_tx(m, args.clone())?;
+		slate_a = client1.send_tx_slate_direct("wallet2", &slate_i)?;
+		let slate_i = api.init_send_tx(m, args.clone())?;
+		slate_b = client1.send_tx_slate_direct("wallet2", &slate_i)?;
+		api.tx_lock_outputs(m, &slate_a, 0)?;
+		slate_a = api.finalize_tx(m, &slate_a)?;
+		api.tx_lock_outputs(m, &slate_b, 0)?;
+		slate_b = api.finalize_tx(m, &slate_b)?;
+		Ok(())
+	})?;
+
+	// While neither is confirmed, each records the other
+	let (mut id_a, mut id_b) = (0, 0);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let a = txs
+			.iter()
+			.find(|t| t.tx_slate_id == Some(slate_a.id))
+			.unwrap();
+		let b = txs
+			.iter()
+			.find(|t| t.tx_slate_id == Some(slate_b.id))
+			.unwrap();
+		assert_eq!(a.conflicts_with, vec![b.id]);
+		assert_eq!(b.conflicts_with, vec![a.id]);
+		id_a = a.id;
+		id_b = b.id;
+		Ok(())
+	})?;
+
+	// Once one is confirmed, the other is cancelled
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.post_tx(m, &slate_a.tx, false)?;
+		Ok(())
+	})?;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let a = txs.iter().find(|t| t.id == id_a).unwrap();
+		let b = txs.iter().find(|t| t.id == id_b).unwrap();
+		assert!(a.confirmed);
+		assert_eq!(a.tx_type, TxLogEntryType::TxSent);
+		assert_eq!(b.tx_type, TxLogEntryType::TxSentCancelled);
+		assert_eq!(b.conflicts_with, vec![id_a]);
+		Ok(())
+	})?;
+
+	// The recipient's side of the losing send isn't cancelled for it
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, true, None, None)?;
+		let a = txs
+			.iter()
+			.find(|t| t.tx_slate_id == Some(slate_a.id))
+			.unwrap();
+		let b = txs
+			.iter()
+			.find(|t| t.tx_slate_id == Some(slate_b.id))
+			.unwrap();
+		assert!(a.confirmed);
+		assert!(!b.confirmed);
+		assert_eq!(b.tx_type, TxLogEntryType::TxReceived);
+		assert!(b.conflicts_with.is_empty());
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_tx_conflicts() {
+	let test_dir = "test_output/tx_conflicts";
+	setup(test_dir);
+	if let Err(e) = tx_conflicts_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,147 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the aggregated transaction details view
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{ErrorKind, InitTxArgs, Slate, TxLogEntryType};
+use std::thread;
+use std::time::Duration;
+use uuid::Uuid;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// tx details impl
+fn tx_details_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	let mut slate = Slate::blank(2);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: reward / 2,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		slate = api.init_send_tx(m, args)?;
+		slate = client1.send_tx_slate_direct("wallet2", &slate)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+
+		// Not finalized yet, no kernel excess
+		let (_, details) = api.get_tx_details(m, false, slate.id)?;
+		assert_eq!(details.tx.tx_type, TxLogEntryType::TxSent);
+		assert_eq!(details.inputs.len(), 1);
+		assert_eq!(details.change.len(), 1);
+		assert!(details.outputs.is_empty());
+		assert!(details.kernel_excess.is_none());
+
+		slate = api.finalize_tx(m, &slate)?;
+		api.post_tx(m, &slate.tx, false)?;
+
+		let (_, details) = api.get_tx_details(m, false, slate.id)?;
+		assert!(details.has_stored_tx);
+		assert!(details.kernel_excess.is_some());
+		assert_eq!(details.confirmations, 0);
+
+		match api
+			.get_tx_details(m, false, Uuid::new_v4())
+			.unwrap_err()
+			.kind()
+		{
+			ErrorKind::TransactionDoesntExist(_) => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+		Ok(())
+	})?;
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 3, false);
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, details) = api.get_tx_details(m, true, slate.id)?;
+		assert!(details.tx.confirmed);
+		assert_eq!(details.confirmations, 3);
+		Ok(())
+	})?;
+
+	wallet::controller::owner_single_use(wallet2.clone(), mask2, |api, m| {
+		let (_, details) = api.get_tx_details(m, true, slate.id)?;
+		assert_eq!(details.tx.tx_type, TxLogEntryType::TxReceived);
+		assert_eq!(details.outputs.len(), 1);
+		assert_eq!(details.outputs[0].output.value, reward / 2);
+		assert!(details.inputs.is_empty() && details.change.is_empty());
+		assert!(details.confirmations > 0);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_tx_details() {
+	let test_dir = "test_output/tx_details";
+	setup(test_dir);
+	if let Err(e) = tx_details_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,218 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test time-locked vault accounts
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+
+use core::core::Transaction;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::{ErrorKind, InitTxArgs, Slate};
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// vault impl
+fn vault_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		match api.set_vault_lock(m, "savings", 100).unwrap_err().kind() {
+			ErrorKind::UnknownAccountLabel(_) => {}
+			e => panic!("Unexpected error: {}", e),
+		}
+		assert!(api.retrieve_vault_settings(m, "default")?.is_none());
+		api.set_vault_lock(m, "default", 100)?;
+
+		// The kernels of the account's sends are height locked
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: reward,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: true,
+			..Default::default()
+		};
+		let slate = api.init_send_tx(m, args)?;
+		assert_eq!(slate.lock_height, slate.height + 100);
+		api.cancel_tx(m, None, Some(slate.id))?;
+
+		// Turning the vault off waits for the current lock to pass
+		let vault = api.set_vault_lock(m, "default", 0)?;
+		assert_eq!(vault.lock_blocks, 100);
+		assert_eq!(vault.pending_lock_blocks, Some(0));
+		assert_eq!(vault.lock_blocks_at(vault.pending_height - 1), 100);
+		assert_eq!(vault.lock_blocks_at(vault.pending_height), 0);
+		assert_eq!(api.retrieve_vault_settings(m, "default")?, Some(vault));
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+/// vault post impl
+fn vault_post_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+	let hold_posted = wallet_proxy.hold_posted.clone();
+	let pool = wallet_proxy.pool.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let _mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let reward = core::consensus::MWC_FIRST_GROUP_REWARD;
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	// A send from the vault is finalized and posted right away
+	hold_posted.store(true, Ordering::Relaxed);
+	let mut slate = Slate::blank(1);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		api.set_vault_lock(m, "default", 5)?;
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: reward,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: true,
+			..Default::default()
+		};
+		let slate_i = api.init_send_tx(m, args)?;
+		slate = client1.send_tx_slate_direct("wallet2", &slate_i)?;
+		api.tx_lock_outputs(m, &slate, 0)?;
+		slate = api.finalize_tx(m, &slate)?;
+		api.post_tx(m, &slate.tx, false)?;
+		Ok(())
+	})?;
+
+	// but its kernel can't be mined yet, so it's held by the wallet rather than
+	// refused by the node
+	assert!(pool.lock().is_empty());
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, true, None, Some(slate.id))?;
+		assert_eq!(txs.len(), 1);
+		assert_eq!(txs[0].post_at_height, Some(slate.lock_height));
+		assert!(!txs[0].confirmed);
+		Ok(())
+	})?;
+	assert!(pool.lock().is_empty());
+
+	// Once the lock height is reached, it's posted on refresh
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, true, None, Some(slate.id))?;
+		assert_eq!(txs[0].post_at_height, None);
+		Ok(())
+	})?;
+	assert_eq!(pool.lock().len(), 1);
+
+	// and confirmed once mined
+	hold_posted.store(false, Ordering::Relaxed);
+	let mined: Vec<Transaction> = pool.lock().iter().map(|(tx, _)| tx.clone()).collect();
+	assert_eq!(mined[0].kernels()[0].excess, slate.tx.kernels()[0].excess);
+	test_framework::award_block_to_wallet(&chain, mined.iter().collect(), wallet1.clone(), mask1)?;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		let (_, txs) = api.retrieve_txs(m, true, None, Some(slate.id))?;
+		assert!(txs[0].confirmed);
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_vault_account() {
+	let test_dir = "test_output/vault";
+	setup(test_dir);
+	if let Err(e) = vault_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
+
+#[test]
+fn wallet_vault_post() {
+	let test_dir = "test_output/vault_post";
+	setup(test_dir);
+	if let Err(e) = vault_post_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -0,0 +1,121 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the integrity check of the stored outputs
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_config as config;
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_util as util;
+
+use config::WalletConfig;
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::api_impl::owner;
+use libwallet::OutputStatus;
+use std::thread;
+use std::time::Duration;
+use util::ZeroingString;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// verify outputs impl
+fn verify_outputs_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	{
+		wallet_inst!(wallet1, w);
+		assert!(owner::verify_outputs(&mut **w, mask1)?.is_empty());
+
+		// Corrupt the value of an output
+		let mut output = w
+			.iter()
+			.find(|o| o.status == OutputStatus::Unspent)
+			.unwrap();
+		output.value += 1;
+		let key_id = output.key_id.clone();
+		let mut batch = w.batch(mask1)?;
+		batch.save(output)?;
+		batch.commit()?;
+
+		let issues = owner::verify_outputs(&mut **w, mask1)?;
+		assert_eq!(issues.len(), 1);
+		assert_eq!(issues[0].output.key_id, key_id);
+	}
+
+	// A wallet configured to check its outputs when opened still opens with a
+	// corrupt one, only warning about it
+	set_wallet_config!(
+		wallet1,
+		WalletConfig {
+			verify_outputs_on_open: Some(true),
+			..WalletConfig::default()
+		}
+	);
+	let mask2_i = {
+		let mut w_lock = wallet1.lock();
+		let lc = w_lock.lc_provider()?;
+		lc.close_wallet(None)?;
+		lc.open_wallet(None, ZeroingString::from(""), true, false)?
+	};
+	{
+		wallet_inst!(wallet1, w);
+		assert_eq!(w.config().verify_outputs_on_open, Some(true));
+		assert_eq!(
+			owner::verify_outputs(&mut **w, (&mask2_i).as_ref())?.len(),
+			1
+		);
+	}
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_verify_outputs() {
+	let test_dir = "test_output/verify_outputs";
+	setup(test_dir);
+	if let Err(e) = verify_outputs_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
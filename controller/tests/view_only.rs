@@ -0,0 +1,121 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the export and audit of view-only bundles
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+
+use impls::test_framework::{self, LocalWalletClient};
+use libwallet::api_impl::owner;
+use std::thread;
+use std::time::Duration;
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// view-only bundle impl
+fn view_only_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask1 = (&mask1_i).as_ref();
+	create_wallet_and_add!(
+		client2,
+		wallet2,
+		mask2_i,
+		test_dir,
+		"wallet2",
+		None,
+		&mut wallet_proxy,
+		true
+	);
+	let mask2 = (&mask2_i).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	let bundle = {
+		wallet_inst!(wallet1, w);
+		let (validated, bundle) = owner::export_view_only_bundle(&mut **w, mask1, true)?;
+		assert!(validated);
+		assert_eq!(bundle.outputs.len(), 5);
+		assert_eq!(
+			bundle.total,
+			bundle.outputs.iter().map(|o| o.value).sum::<u64>()
+		);
+		bundle
+	};
+
+	// Another wallet audits the bundle, without the keys of wallet1
+	wallet_inst!(wallet2, w);
+	let attestation = owner::audit_view_only_bundle(&mut **w, mask2, &bundle)?;
+	assert!(attestation.is_clean());
+	assert_eq!(attestation.verified_total, bundle.total);
+	attestation.verify(Some(&bundle))?;
+
+	// A value reported higher than the one committed to is caught
+	let mut inflated = bundle.clone();
+	inflated.outputs[0].value += 1;
+	inflated.total += 1;
+	let attestation = owner::audit_view_only_bundle(&mut **w, mask2, &inflated)?;
+	assert!(!attestation.is_clean());
+	assert!(attestation.outputs[0].issue.is_some());
+	assert_eq!(
+		attestation.verified_total,
+		bundle.total - bundle.outputs[0].value
+	);
+	attestation.verify(Some(&inflated))?;
+	assert!(attestation.verify(Some(&bundle)).is_err());
+
+	// Tampering with the attestation breaks its signature
+	let mut forged = attestation.clone();
+	forged.verified_total = inflated.total;
+	assert!(forged.verify(None).is_err());
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_view_only_bundle() {
+	let test_dir = "test_output/view_only";
+	setup(test_dir);
+	if let Err(e) = view_only_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
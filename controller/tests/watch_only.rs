@@ -0,0 +1,162 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test a watch-only wallet following the outputs of another from its root public key
+#[macro_use]
+extern crate log;
+extern crate grin_wallet_controller as wallet;
+extern crate grin_wallet_impls as impls;
+
+use grin_wallet_libwallet as libwallet;
+use grin_wallet_util::grin_core as core;
+use grin_wallet_util::grin_keychain as keychain;
+use grin_wallet_util::grin_util as util;
+
+use impls::test_framework::{self, LocalWalletClient};
+use impls::{DefaultLCProvider, DefaultWalletImpl};
+use keychain::ExtKeychain;
+use libwallet::{InitTxArgs, WalletInst};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use util::{Mutex, ZeroingString};
+
+#[macro_use]
+mod common;
+use common::{clean_output_dir, create_wallet_proxy, setup};
+
+/// watch-only impl
+fn watch_only_test_impl(test_dir: &'static str) -> Result<(), libwallet::Error> {
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy = create_wallet_proxy(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	create_wallet_and_add!(
+		client1,
+		wallet1,
+		mask1_i,
+		test_dir,
+		"wallet1",
+		None,
+		&mut wallet_proxy,
+		false
+	);
+	let mask1 = (&mask1_i).as_ref();
+
+	let mut key = None;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		key = Some(api.watch_only_key(m)?);
+		Ok(())
+	})?;
+	let key = key.unwrap();
+
+	// A watch-only wallet following wallet1
+	let client_watch = LocalWalletClient::new("watch", wallet_proxy.tx.clone());
+	let watch = DefaultWalletImpl::<LocalWalletClient>::new(client_watch.clone()).unwrap();
+	let mut watch = Box::new(watch)
+		as Box<
+			dyn WalletInst<
+				DefaultLCProvider<'static, LocalWalletClient, ExtKeychain>,
+				LocalWalletClient,
+				ExtKeychain,
+			>,
+		>;
+	let lc = watch.lc_provider()?;
+	lc.set_top_level_directory(&format!("{}/watch", test_dir))?;
+	assert!(lc
+		.create_watch_only_wallet(None, "not a key", ZeroingString::from(""))
+		.is_err());
+	lc.create_watch_only_wallet(None, &key.root_public_key, ZeroingString::from(""))?;
+	let mask_watch = lc.open_wallet(None, ZeroingString::from(""), false, false)?;
+	let watch = Arc::new(Mutex::new(watch));
+	wallet_proxy.add_wallet(
+		"watch",
+		client_watch.get_send_instance(),
+		watch.clone(),
+		mask_watch.clone(),
+	);
+	let mask_watch = (&mask_watch).as_ref();
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 5, false);
+
+	let mut info1 = None;
+	let mut outputs1 = vec![];
+	let mut fingerprint1 = None;
+	wallet::controller::owner_single_use(wallet1.clone(), mask1, |api, m| {
+		info1 = Some(api.retrieve_summary_info(m, true, 1)?.1);
+		outputs1 = api.retrieve_outputs(m, false, false, None)?.1;
+		fingerprint1 = Some(api.wallet_fingerprint(m)?);
+		Ok(())
+	})?;
+	let info1 = info1.unwrap();
+
+	wallet::controller::owner_single_use(watch.clone(), mask_watch, |api, m| {
+		assert_eq!(api.watch_only_key(m)?, key);
+		assert_eq!(Some(api.wallet_fingerprint(m)?), fingerprint1);
+
+		// The outputs of wallet1 are found by a scan
+		api.check_repair(m, false)?;
+		let (_, info) = api.retrieve_summary_info(m, true, 1)?;
+		assert_eq!(info.total, info1.total);
+		assert_eq!(
+			info.amount_currently_spendable,
+			info1.amount_currently_spendable
+		);
+		let (_, outputs) = api.retrieve_outputs(m, false, true, None)?;
+		assert_eq!(outputs.len(), outputs1.len());
+		for (o, o1) in outputs.iter().zip(outputs1.iter()) {
+			assert_eq!(o.commit, o1.commit);
+		}
+
+		// But can't be spent
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: core::consensus::MWC_FIRST_GROUP_REWARD / 2,
+			minimum_confirmations: 1,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: false,
+			..Default::default()
+		};
+		match api.init_send_tx(m, args) {
+			Err(e) => match e.kind() {
+				libwallet::ErrorKind::WatchOnly(_) => {}
+				k => panic!("Unexpected error {}", k),
+			},
+			Ok(_) => panic!("Watch-only wallet sent"),
+		}
+		Ok(())
+	})?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	Ok(())
+}
+
+#[test]
+fn wallet_watch_only() {
+	let test_dir = "test_output/watch_only";
+	setup(test_dir);
+	if let Err(e) = watch_only_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+	clean_output_dir(test_dir);
+}
@@ -12,32 +12,418 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::api;
 /// HTTP Wallet 'plugin' implementation
+use super::http_transport::HttpTransport;
+use crate::api;
+use crate::blake2::blake2b::blake2b;
 use crate::core::global;
-use crate::libwallet::{Error, ErrorKind, Slate};
-use crate::SlateSender;
-use serde::Serialize;
+use crate::libwallet::{Error, ErrorKind, RecipientHandshake, Slate, CURRENT_SLATE_VERSION};
+use crate::util;
+use crate::{SlateDelivery, SlateSender};
+use chrono::Utc;
+use hyper::header::{HeaderValue, ACCEPT, USER_AGENT};
+use hyper::{Body, Request};
+use rand::{thread_rng, Rng};
+use ring::constant_time::verify_slices_are_equal;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::cmp;
+use std::collections::HashMap;
+use std::mem;
+use std::thread;
+use std::time::{Duration, Instant};
 use url::Url;
+use uuid::Uuid;
+
+/// Path of the foreign listener endpoint issuing receive challenges
+pub const RECEIVE_CHALLENGE_PATH: &'static str = "/v2/receive_challenge";
+/// Header carrying the challenge a receive_tx request was solved for
+pub const RECEIVE_CHALLENGE_HEADER: &'static str = "mwc-receive-challenge";
+/// Header carrying the nonce solving the challenge
+pub const RECEIVE_NONCE_HEADER: &'static str = "mwc-receive-nonce";
+/// Highest difficulty, in leading zero bits, a listener can require and a sender will solve
+pub const MAX_RECEIVE_CHALLENGE_DIFFICULTY: u8 = 28;
+
+/// Header carrying the foreign API key a slate is sent with
+pub const FOREIGN_API_KEY_HEADER: &'static str = "mwc-foreign-api-key";
+/// Path of the foreign API taking the foreign API key as last segment
+pub const FOREIGN_API_KEY_PATH: &'static str = "/v2/foreign/key";
+
+/// Path of the foreign listener endpoint receiving slates in resumable chunks
+pub const SLATE_EXCHANGE_PATH: &'static str = "/v2/slate_exchange";
+/// Largest chunk of a slate a sender uploads at once, in bytes
+pub const SLATE_EXCHANGE_CHUNK_SIZE: usize = 16 * 1024;
+/// Largest serialized slate a listener accepts through an exchange, in bytes
+pub const MAX_SLATE_EXCHANGE_SIZE: u64 = 4 * 1024 * 1024;
+/// Most bytes a listener buffers across all its exchanges, slates being received
+/// and replies kept
+pub const MAX_SLATE_EXCHANGES_BUFFERED: u64 = 32 * 1024 * 1024;
+
+/// Minutes a receive challenge can be solved and used for
+const RECEIVE_CHALLENGE_TTL_MINUTES: i64 = 10;
+/// Seconds a slate exchange is kept after its reply, for the sender to fetch it
+const SLATE_EXCHANGE_TTL_SECS: u64 = 3600;
+/// Seconds a slate exchange still being uploaded is kept without a new chunk
+const SLATE_EXCHANGE_IDLE_SECS: u64 = 120;
+/// Most slate exchanges a listener keeps at the same time
+const MAX_SLATE_EXCHANGES: usize = 256;
+/// Times a sender resumes an interrupted slate exchange before giving up
+const SLATE_EXCHANGE_RETRIES: u64 = 5;
+/// Milliseconds between the checks of a sender waiting for its slate to be received
+const SLATE_EXCHANGE_POLL_MILLIS: u64 = 200;
+
+/// Client puzzle a public foreign listener can require before receiving a slate, so
+/// spam slates can't cheaply fill the wallet's transaction log. The sender looks for
+/// a nonce such that `blake2b(challenge | slate id | nonce)` starts with `difficulty`
+/// zero bits.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ReceiveChallenge {
+	/// Challenge issued by the listener, hex encoded
+	pub challenge: String,
+	/// Number of leading zero bits the proof hash needs
+	pub difficulty: u8,
+}
+
+impl ReceiveChallenge {
+	/// Whether the nonce solves the challenge for the slate
+	pub fn is_solved_by(&self, slate_id: &Uuid, nonce: u64) -> bool {
+		let mut data = self.challenge.as_bytes().to_vec();
+		data.extend_from_slice(slate_id.as_bytes());
+		data.extend_from_slice(&nonce.to_le_bytes());
+		let hash = blake2b(32, &[], &data);
+		let mut zeros = 0;
+		for b in hash.as_bytes() {
+			zeros += b.leading_zeros();
+			if *b != 0 {
+				break;
+			}
+		}
+		zeros >= self.difficulty as u32
+	}
+
+	/// Find a nonce solving the challenge for the slate
+	pub fn solve(&self, slate_id: &Uuid) -> u64 {
+		(0..).find(|n| self.is_solved_by(slate_id, *n)).unwrap()
+	}
+}
+
+/// Issues and checks the receive challenges of a foreign listener. Challenges are
+/// authenticated with a secret of the listener, so none need to be stored.
+pub struct ReceiveChallenges {
+	secret: [u8; 32],
+	difficulty: u8,
+}
+
+impl ReceiveChallenges {
+	/// Create a new issuer for challenges of the given difficulty
+	pub fn new(difficulty: u8) -> Result<ReceiveChallenges, Error> {
+		if difficulty > MAX_RECEIVE_CHALLENGE_DIFFICULTY {
+			return Err(ErrorKind::ReceiveChallenge(format!(
+				"Difficulty can't be more than {}",
+				MAX_RECEIVE_CHALLENGE_DIFFICULTY
+			)))?;
+		}
+		Ok(ReceiveChallenges {
+			secret: thread_rng().gen(),
+			difficulty,
+		})
+	}
+
+	fn tag(&self, issued: i64) -> Vec<u8> {
+		blake2b(16, &self.secret, &issued.to_be_bytes())
+			.as_bytes()
+			.to_vec()
+	}
+
+	/// Issue a new challenge
+	pub fn issue(&self) -> ReceiveChallenge {
+		let issued = Utc::now().timestamp();
+		let mut challenge = issued.to_be_bytes().to_vec();
+		challenge.extend(self.tag(issued));
+		ReceiveChallenge {
+			challenge: util::to_hex(challenge),
+			difficulty: self.difficulty,
+		}
+	}
+
+	/// Check the proof of a receive_tx request for the slate
+	pub fn verify(&self, challenge: &str, slate_id: &Uuid, nonce: u64) -> Result<(), Error> {
+		let invalid = || ErrorKind::ReceiveChallenge("Invalid challenge".to_owned());
+		let bytes = util::from_hex(challenge.to_owned()).map_err(|_| invalid())?;
+		if bytes.len() != 24 {
+			return Err(invalid())?;
+		}
+		let mut issued = [0u8; 8];
+		issued.copy_from_slice(&bytes[..8]);
+		let issued = i64::from_be_bytes(issued);
+		verify_slices_are_equal(&bytes[8..], &self.tag(issued)).map_err(|_| invalid())?;
+		if Utc::now().timestamp() > issued + RECEIVE_CHALLENGE_TTL_MINUTES * 60 {
+			return Err(ErrorKind::ReceiveChallenge("Challenge expired".to_owned()))?;
+		}
+		let c = ReceiveChallenge {
+			challenge: challenge.to_owned(),
+			difficulty: self.difficulty,
+		};
+		if !c.is_solved_by(slate_id, nonce) {
+			return Err(ErrorKind::ReceiveChallenge(
+				"Challenge not solved".to_owned(),
+			))?;
+		}
+		Ok(())
+	}
+}
+
+/// Part of a slate uploaded to a foreign listener through a slate exchange
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SlateExchangeChunk {
+	/// Token of the exchange, chosen by the sender
+	pub token: String,
+	/// Id of the slate, which the receive challenge of the sender is solved for
+	pub slate_id: Uuid,
+	/// Offset of the chunk in the serialized slate, in bytes
+	pub offset: u64,
+	/// Size of the serialized slate, in bytes
+	pub total: u64,
+	/// Chunk of the serialized slate
+	pub data: String,
+}
+
+/// State of a slate exchange on the listener. A sender that lost its connection
+/// resumes the upload from `received`, or gets the listener's reply if the slate
+/// was already processed. A whole slate without a reply is still being received.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SlateExchangeStatus {
+	/// Token of the exchange
+	pub token: String,
+	/// Bytes of the serialized slate received so far
+	pub received: u64,
+	/// Size of the serialized slate, 0 until the first chunk is received
+	pub total: u64,
+	/// Reply to the receive_tx call, once the slate was processed
+	pub response: Option<Value>,
+}
+
+struct SlateExchange {
+	slate_id: Uuid,
+	data: String,
+	received: u64,
+	total: u64,
+	response: Option<Value>,
+	/// Size of the serialized reply
+	response_len: u64,
+	updated: Instant,
+}
+
+impl SlateExchange {
+	/// Bytes the exchange holds
+	fn buffered(&self) -> u64 {
+		self.data.len() as u64 + self.response_len
+	}
+
+	/// Whether the exchange can be dropped: its upload stalled for `idle`, or its
+	/// reply was kept for `ttl`. A whole slate waiting for its reply is kept as long.
+	fn is_expired(&self, idle: Duration, ttl: Duration) -> bool {
+		match self.received < self.total {
+			true => self.updated.elapsed() >= idle,
+			false => self.updated.elapsed() >= ttl,
+		}
+	}
+}
+
+/// Slates a foreign listener is receiving in chunks, by exchange token. Completed
+/// exchanges keep the listener's reply for a while, so a sender whose connection
+/// dropped can fetch it rather than guess whether its slate was received. Uploads
+/// left idle are dropped, and the bytes held across all exchanges are capped.
+pub struct SlateExchanges {
+	exchanges: HashMap<String, SlateExchange>,
+	max_buffered: u64,
+	idle_timeout: Duration,
+	reply_ttl: Duration,
+}
+
+impl SlateExchanges {
+	/// Create an empty set of exchanges
+	pub fn new() -> SlateExchanges {
+		SlateExchanges {
+			exchanges: HashMap::new(),
+			max_buffered: MAX_SLATE_EXCHANGES_BUFFERED,
+			idle_timeout: Duration::from_secs(SLATE_EXCHANGE_IDLE_SECS),
+			reply_ttl: Duration::from_secs(SLATE_EXCHANGE_TTL_SECS),
+		}
+	}
+
+	/// Bytes held across all exchanges
+	fn buffered(&self) -> u64 {
+		self.exchanges.values().map(|e| e.buffered()).sum()
+	}
+
+	/// Drop expired exchanges
+	fn expire(&mut self) {
+		let (idle, ttl) = (self.idle_timeout, self.reply_ttl);
+		self.exchanges.retain(|_, e| !e.is_expired(idle, ttl));
+	}
+
+	/// Drop expired exchanges, and the oldest one if there's no room for another
+	fn prune(&mut self) {
+		self.expire();
+		if self.exchanges.len() >= MAX_SLATE_EXCHANGES {
+			let oldest = self
+				.exchanges
+				.iter()
+				.min_by_key(|(_, e)| e.updated)
+				.map(|(t, _)| t.clone());
+			if let Some(t) = oldest {
+				self.exchanges.remove(&t);
+			}
+		}
+	}
+
+	/// Status of an exchange, a fresh one if the token is unknown or the exchange
+	/// expired
+	pub fn status(&mut self, token: &str) -> SlateExchangeStatus {
+		self.expire();
+		let (received, total, response) = match self.exchanges.get(token) {
+			Some(e) => (e.received, e.total, e.response.clone()),
+			None => (0, 0, None),
+		};
+		SlateExchangeStatus {
+			token: token.to_owned(),
+			received,
+			total,
+			response,
+		}
+	}
+
+	/// Add a chunk to its exchange. Returns the serialized slate once its last chunk
+	/// is in, for the caller to process and [`complete`](#method.complete) the
+	/// exchange. Chunks that were already received are ignored. The caller checks
+	/// the sender may send the slate before handing its chunks over.
+	pub fn add_chunk(&mut self, chunk: SlateExchangeChunk) -> Result<Option<String>, Error> {
+		if chunk.total > MAX_SLATE_EXCHANGE_SIZE {
+			return Err(ErrorKind::SlateExchange(format!(
+				"Slate of {} bytes is larger than the {} accepted",
+				chunk.total, MAX_SLATE_EXCHANGE_SIZE
+			)))?;
+		}
+		if !self.exchanges.contains_key(&chunk.token) {
+			self.prune();
+		} else {
+			self.expire();
+		}
+		let len = chunk.data.len() as u64;
+		let buffered = self.buffered();
+		let exchange = self
+			.exchanges
+			.entry(chunk.token.clone())
+			.or_insert_with(|| SlateExchange {
+				slate_id: chunk.slate_id,
+				data: String::new(),
+				received: 0,
+				total: chunk.total,
+				response: None,
+				response_len: 0,
+				updated: Instant::now(),
+			});
+		if exchange.total != chunk.total || exchange.slate_id != chunk.slate_id {
+			return Err(ErrorKind::SlateExchange(format!(
+				"Exchange {} is for slate {} of {} bytes",
+				chunk.token, exchange.slate_id, exchange.total
+			)))?;
+		}
+		if exchange.response.is_some() || chunk.offset + len <= exchange.received {
+			return Ok(None);
+		}
+		if chunk.offset != exchange.received {
+			return Err(ErrorKind::SlateExchange(format!(
+				"Exchange {} expects a chunk at offset {}",
+				chunk.token, exchange.received
+			)))?;
+		}
+		if exchange.received + len > exchange.total {
+			return Err(ErrorKind::SlateExchange(format!(
+				"Chunk goes past the end of the slate of exchange {}",
+				chunk.token
+			)))?;
+		}
+		if buffered + len > self.max_buffered {
+			return Err(ErrorKind::SlateExchange(format!(
+				"Listener already holds {} bytes of slates, retry later",
+				buffered
+			)))?;
+		}
+		exchange.data.push_str(&chunk.data);
+		exchange.received += len;
+		exchange.updated = Instant::now();
+		if exchange.received == exchange.total {
+			// Taken out, so the slate is received without holding the exchanges. Until
+			// it's completed, the exchange reports the whole slate received and no reply.
+			return Ok(Some(mem::replace(&mut exchange.data, String::new())));
+		}
+		Ok(None)
+	}
+
+	/// Record the reply to the receive_tx call of an exchange whose slate is complete
+	pub fn complete(&mut self, token: &str, response: Value) {
+		if let Some(e) = self.exchanges.get_mut(token) {
+			e.response_len = response.to_string().len() as u64;
+			e.response = Some(response);
+			e.updated = Instant::now();
+		}
+	}
+}
 
 #[derive(Clone)]
 pub struct HttpSlateSender {
 	base_url: Url,
+	chain_type: global::ChainTypes,
+	/// Foreign API key given by the recipient, sent along with the slate
+	api_key: Option<String>,
+	transport: HttpTransport,
 }
 
 impl HttpSlateSender {
-	/// Create, return Err if scheme is not "http"
-	pub fn new(base_url: Url) -> Result<HttpSlateSender, SchemeNotHttp> {
+	/// Create, return Err if scheme is not "http". A foreign API key given by
+	/// the recipient is passed as user of the url, as in `http://<key>@host:port`.
+	/// .onion destinations are reached through the Tor SOCKS proxy at
+	/// `tor_socks_proxy`, and each request fails unless answered within `timeout`.
+	pub fn new(
+		mut base_url: Url,
+		chain_type: global::ChainTypes,
+		tor_socks_proxy: Option<String>,
+		timeout: Option<Duration>,
+	) -> Result<HttpSlateSender, SchemeNotHttp> {
 		if base_url.scheme() != "http" && base_url.scheme() != "https" {
 			Err(SchemeNotHttp)
 		} else {
-			Ok(HttpSlateSender { base_url })
+			let api_key = match base_url.username() {
+				"" => None,
+				k => Some(k.to_owned()),
+			};
+			let _ = base_url.set_username("");
+			Ok(HttpSlateSender {
+				base_url,
+				chain_type,
+				api_key,
+				transport: HttpTransport::new(tor_socks_proxy, timeout),
+			})
+		}
+	}
+
+	/// Add the foreign API key to a request, if any
+	fn add_api_key(&self, req: &mut Request<Body>) -> Result<(), Error> {
+		if let Some(k) = self.api_key.as_ref() {
+			req.headers_mut().insert(
+				FOREIGN_API_KEY_HEADER,
+				HeaderValue::from_str(k)
+					.map_err(|_| ErrorKind::ClientCallback("Invalid foreign API key".to_owned()))?,
+			);
 		}
+		Ok(())
 	}
 
-	/// Check version of the listening wallet
-	fn check_other_version(&self, url: &Url) -> Result<(), Error> {
+	/// Check version of the listening wallet, returning its foreign API version and the
+	/// slate versions it supports
+	fn check_other_version(&self, url: &Url) -> Result<(u16, Vec<String>), Error> {
 		let req = json!({
 			"jsonrpc": "2.0",
 			"method": "check_version",
@@ -45,18 +431,18 @@ impl HttpSlateSender {
 			"params": []
 		});
 
-		let res: String = post(url, None, &req).map_err(|e| {
-			let mut report = format!("Performing version check (is recipient listening?): {}", e);
-			let err_string = format!("{}", e);
-			if err_string.contains("404") {
+		let res: String = self.post(url, &req).map_err(|e| {
+			if let ErrorKind::DestinationHttp(404, _) = e.kind() {
 				// Report that the other version of the wallet is out of date
-				report = format!(
+				let report = format!(
 					"Other wallet is incompatible and requires an upgrade. \
 					 Please urge the other wallet owner to upgrade and try the transaction again."
 				);
+				error!("{}", report);
+				return Error::from(ErrorKind::ClientCallback(report));
 			}
-			error!("{}", report);
-			ErrorKind::ClientCallback(report)
+			error!("Performing version check (is recipient listening?): {}", e);
+			e
 		})?;
 
 		let res: Value = serde_json::from_str(&res).unwrap();
@@ -84,54 +470,235 @@ impl HttpSlateSender {
 			return Err(ErrorKind::ClientCallback(report).into());
 		}
 
-		if !supported_slate_versions.contains(&"V2".to_owned()) {
-			let report = format!("Unable to negotiate slate format with other wallet.");
+		Ok((foreign_api_version, supported_slate_versions))
+	}
+
+	/// Challenge the listening wallet requires before receiving, if any
+	fn receive_challenge(&self) -> Result<Option<ReceiveChallenge>, Error> {
+		let url: Url = self
+			.base_url
+			.join(RECEIVE_CHALLENGE_PATH)
+			.expect("invalid receive challenge url path");
+		// Listeners without the challenge endpoint receive slates freely
+		let challenge: ReceiveChallenge = match self.get(&url) {
+			Ok(c) => c,
+			Err(_) => return Ok(None),
+		};
+		if challenge.difficulty > MAX_RECEIVE_CHALLENGE_DIFFICULTY {
+			let report = format!(
+				"Other wallet requires a receive challenge of difficulty {}, more than {}.",
+				challenge.difficulty, MAX_RECEIVE_CHALLENGE_DIFFICULTY
+			);
 			error!("{}", report);
 			return Err(ErrorKind::ClientCallback(report).into());
 		}
+		Ok(Some(challenge))
+	}
 
-		Ok(())
+	/// Send the slate in chunks through a slate exchange, resuming the upload after
+	/// dropped connections. Returns the listener's reply to the receive_tx call, or
+	/// `None` if the listener doesn't support slate exchanges.
+	fn exchange_slate(
+		&self,
+		slate: &Slate,
+		proof: &Option<(String, u64)>,
+	) -> Result<Option<Value>, Error> {
+		let url: Url = self
+			.base_url
+			.join(SLATE_EXCHANGE_PATH)
+			.expect("invalid slate exchange url path");
+		let token = Uuid::new_v4().to_string();
+		let status_url = self.slate_exchange_status_url(&token);
+		let get_status = || self.get::<SlateExchangeStatus>(&status_url);
+		// Older listeners receive the slate in a single request
+		if get_status().is_err() {
+			return Ok(None);
+		}
+		debug!("Exchanging slate {} with token {}", slate.id, token);
+
+		let data = serde_json::to_string(slate).map_err(|_| ErrorKind::SlateDeser)?;
+		let mut offset = 0;
+		let mut failures = 0;
+		loop {
+			let status = if offset < data.len() {
+				let mut end = cmp::min(offset + SLATE_EXCHANGE_CHUNK_SIZE, data.len());
+				while !data.is_char_boundary(end) {
+					end -= 1;
+				}
+				let chunk = SlateExchangeChunk {
+					token: token.clone(),
+					slate_id: slate.id,
+					offset: offset as u64,
+					total: data.len() as u64,
+					data: data[offset..end].to_owned(),
+				};
+				self.post_chunk(&url, &chunk, proof)
+			} else {
+				// The listener has the whole slate and is still receiving it
+				thread::sleep(Duration::from_millis(SLATE_EXCHANGE_POLL_MILLIS));
+				get_status()
+			};
+			let status = match status {
+				Ok(s) => s,
+				Err(e) => {
+					failures += 1;
+					if failures > SLATE_EXCHANGE_RETRIES {
+						error!("Posting transaction slate (is recipient listening?): {}", e);
+						return Err(e);
+					}
+					warn!("Slate exchange {} interrupted: {}, resuming", token, e);
+					thread::sleep(Duration::from_secs(failures));
+					// Carry on from what the listener got, which may be everything
+					match get_status() {
+						Ok(s) => s,
+						Err(_) => continue,
+					}
+				}
+			};
+			if let Some(r) = status.response {
+				return Ok(Some(r));
+			}
+			offset = status.received as usize;
+		}
+	}
+
+	/// Url of the status of a slate exchange
+	fn slate_exchange_status_url(&self, token: &str) -> Url {
+		let mut url: Url = self
+			.base_url
+			.join(SLATE_EXCHANGE_PATH)
+			.expect("invalid slate exchange url path");
+		url.set_query(Some(&format!("token={}", token)));
+		url
+	}
+
+	/// Upload a chunk of a slate exchange, returning the status of the exchange
+	fn post_chunk(
+		&self,
+		url: &Url,
+		chunk: &SlateExchangeChunk,
+		proof: &Option<(String, u64)>,
+	) -> Result<SlateExchangeStatus, Error> {
+		let mut req =
+			api::client::create_post_request(url.as_str(), None, chunk, self.chain_type.clone())
+				.map_err(|e| {
+					ErrorKind::ClientCallback(format!("Building slate exchange request: {}", e))
+				})?;
+		add_receive_proof(&mut req, proof)?;
+		self.add_api_key(&mut req)?;
+		let res = self.transport.send_request(url, req)?;
+		let status = serde_json::from_str(&res).map_err(|e| {
+			ErrorKind::ClientCallback(format!("Invalid slate exchange status: {}", e))
+		})?;
+		Ok(status)
+	}
+
+	/// Get a JSON reply from the destination
+	fn get<T>(&self, url: &Url) -> Result<T, Error>
+	where
+		for<'de> T: Deserialize<'de>,
+	{
+		let req = Request::get(url.as_str())
+			.header(USER_AGENT, "mwc-client")
+			.header(ACCEPT, "application/json")
+			.body(Body::empty())
+			.map_err(|e| ErrorKind::ClientCallback(format!("Building request: {}", e)))?;
+		let res = self.transport.send_request(url, req)?;
+		let res = serde_json::from_str(&res)
+			.map_err(|e| ErrorKind::ClientCallback(format!("Invalid reply from {}: {}", url, e)))?;
+		Ok(res)
+	}
+
+	/// Post a JSON request to the destination, returning the reply
+	fn post<IN>(&self, url: &Url, input: &IN) -> Result<String, Error>
+	where
+		IN: Serialize,
+	{
+		let mut req =
+			api::client::create_post_request(url.as_str(), None, input, self.chain_type.clone())
+				.map_err(|e| ErrorKind::ClientCallback(format!("Building request: {}", e)))?;
+		self.add_api_key(&mut req)?;
+		self.transport.send_request(url, req)
 	}
 }
 
 impl SlateSender for HttpSlateSender {
 	fn send_tx(&self, slate: &Slate) -> Result<Slate, Error> {
+		self.deliver_tx(slate, "http")?.into_slate()
+	}
+
+	/// The listener acknowledges the slate in its reply to the receive_tx call,
+	/// returning the slate or the reason it rejected it
+	fn deliver_tx(&self, slate: &Slate, method: &str) -> Result<SlateDelivery, Error> {
 		let url: Url = self
 			.base_url
 			.join("/v2/foreign")
 			.expect("/v2/foreign is an invalid url path");
 		debug!("Posting transaction slate to {}", url);
 
-		self.check_other_version(&url)?;
+		let (_, supported_slate_versions) = self.check_other_version(&url)?;
+		let slate = &negotiate_slate_version(slate, &supported_slate_versions)?;
 
-		// Note: not using easy-jsonrpc as don't want the dependencies in this crate
-		let req = json!({
-			"jsonrpc": "2.0",
-			"method": "receive_tx",
-			"id": 1,
-			"params": [
-						slate,
-						null,
-						null
-					]
-		});
-		trace!("Sending receive_tx request: {}", req);
+		let proof = match self.receive_challenge()? {
+			Some(c) => {
+				debug!("Solving receive challenge of difficulty {}", c.difficulty);
+				Some((c.challenge.clone(), c.solve(&slate.id)))
+			}
+			None => None,
+		};
 
-		let res: String = post(&url, None, &req).map_err(|e| {
-			let report = format!("Posting transaction slate (is recipient listening?): {}", e);
-			error!("{}", report);
-			ErrorKind::ClientCallback(report)
-		})?;
+		let res = match self.exchange_slate(slate, &proof)? {
+			Some(r) => r,
+			None => {
+				// Note: not using easy-jsonrpc as don't want the dependencies in this crate
+				let req = json!({
+					"jsonrpc": "2.0",
+					"method": "receive_tx",
+					"id": 1,
+					"params": [
+								slate,
+								null,
+								null
+							]
+				});
+				trace!("Sending receive_tx request: {}", req);
+
+				let mut http_req = api::client::create_post_request(
+					url.as_str(),
+					None,
+					&req,
+					self.chain_type.clone(),
+				)
+				.map_err(|e| {
+					ErrorKind::ClientCallback(format!("Building receive_tx request: {}", e))
+				})?;
+				add_receive_proof(&mut http_req, &proof)?;
+				self.add_api_key(&mut http_req)?;
+
+				let res: String = self.transport.send_request(&url, http_req).map_err(|e| {
+					error!("Posting transaction slate (is recipient listening?): {}", e);
+					e
+				})?;
+				serde_json::from_str(&res).unwrap()
+			}
+		};
 
-		let res: Value = serde_json::from_str(&res).unwrap();
 		trace!("Response: {}", res);
 		if res["error"] != json!(null) {
 			let report = format!(
-				"Posting transaction slate: Error: {}, Message: {}",
+				"Error: {}, Message: {}",
 				res["error"]["code"], res["error"]["message"]
 			);
-			error!("{}", report);
-			return Err(ErrorKind::ClientCallback(report).into());
+			error!("Posting transaction slate: {}", report);
+			return Ok(SlateDelivery::rejected(report, method));
+		}
+		if res["result"]["Err"] != json!(null) {
+			let report = match res["result"]["Err"].as_str() {
+				Some(e) => e.to_owned(),
+				None => res["result"]["Err"].to_string(),
+			};
+			error!("Transaction slate rejected by the recipient: {}", report);
+			return Ok(SlateDelivery::rejected(report, method));
 		}
 
 		let slate_value = res["result"]["Ok"].clone();
@@ -139,10 +706,90 @@ impl SlateSender for HttpSlateSender {
 		let slate = Slate::deserialize_upgrade(&serde_json::to_string(&slate_value).unwrap())
 			.map_err(|_| ErrorKind::SlateDeser)?;
 
-		Ok(slate)
+		Ok(SlateDelivery::accepted(slate, method))
+	}
+
+	/// Goes through the steps of a send up to the slate: the version check and
+	/// negotiation, then the receive challenge and the slate exchange discovery
+	fn handshake(&self, method: &str) -> Result<RecipientHandshake, Error> {
+		let url: Url = self
+			.base_url
+			.join("/v2/foreign")
+			.expect("/v2/foreign is an invalid url path");
+		debug!("Checking recipient {}", url);
+
+		let start = Instant::now();
+		let (foreign_api_version, supported_slate_versions) = self.check_other_version(&url)?;
+		let latency = start.elapsed();
+		let slate = negotiate_slate_version(&Slate::blank(2), &supported_slate_versions)?;
+		let receive_challenge = self.receive_challenge()?;
+		let status_url = self.slate_exchange_status_url(&Uuid::new_v4().to_string());
+		let slate_exchange = self.get::<SlateExchangeStatus>(&status_url).is_ok();
+
+		// The foreign API key was taken out of the url
+		Ok(RecipientHandshake {
+			method: method.to_owned(),
+			dest: self.base_url.to_string(),
+			foreign_api_version,
+			supported_slate_versions,
+			slate_version: slate.version_info.orig_version,
+			receive_challenge_difficulty: receive_challenge.map(|c| c.difficulty),
+			slate_exchange,
+			latency_ms: latency.as_secs() * 1000 + latency.subsec_millis() as u64,
+		})
 	}
 }
 
+/// Convert the slate to a version the other wallet supports. A slate pinned to an
+/// older version is sent as is, otherwise it's downgraded to the newest version
+/// both wallets support.
+fn negotiate_slate_version(slate: &Slate, supported: &[String]) -> Result<Slate, Error> {
+	let mut slate = slate.clone();
+	let wanted = slate.version_info.orig_version;
+	if supported.contains(&format!("V{}", wanted)) {
+		return Ok(slate);
+	}
+	let report = if wanted != CURRENT_SLATE_VERSION {
+		format!(
+			"Other wallet doesn't support slate version V{} the slate is pinned to, only {:?}.",
+			wanted, supported
+		)
+	} else {
+		let version = supported
+			.iter()
+			.filter_map(|v| v.trim_start_matches('V').parse::<u16>().ok())
+			.filter(|v| *v < CURRENT_SLATE_VERSION)
+			.max();
+		match version {
+			Some(v) => {
+				debug!("Downgrading slate {} to version V{}", slate.id, v);
+				slate.set_serialization_version(v)?;
+				return Ok(slate);
+			}
+			None => format!(
+				"Unable to negotiate slate format with other wallet, it supports {:?}.",
+				supported
+			),
+		}
+	};
+	error!("{}", report);
+	Err(ErrorKind::ClientCallback(report).into())
+}
+
+/// Add the solution of a receive challenge to a request
+fn add_receive_proof(req: &mut Request<Body>, proof: &Option<(String, u64)>) -> Result<(), Error> {
+	if let Some((challenge, nonce)) = proof {
+		let headers = req.headers_mut();
+		headers.insert(
+			RECEIVE_CHALLENGE_HEADER,
+			HeaderValue::from_str(challenge)
+				.map_err(|_| ErrorKind::ClientCallback("Invalid receive challenge".to_owned()))?,
+		);
+		headers.insert(RECEIVE_NONCE_HEADER, HeaderValue::from(*nonce));
+	}
+	Ok(())
+}
+
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct SchemeNotHttp;
 
@@ -153,20 +800,110 @@ impl Into<Error> for SchemeNotHttp {
 	}
 }
 
-pub fn post<IN>(url: &Url, api_secret: Option<String>, input: &IN) -> Result<String, api::Error>
-where
-	IN: Serialize,
-{
-	// TODO: change create_post_request to accept a url instead of a &str
-	let chain_type = if global::is_main() {
-		global::ChainTypes::Mainnet
-	} else if global::is_floo() {
-		global::ChainTypes::Floonet
-	} else {
-		global::ChainTypes::UserTesting
-	};
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn receive_challenge_proof() {
+		let challenges = ReceiveChallenges::new(8).unwrap();
+		let c = challenges.issue();
+		let slate_id = Uuid::new_v4();
+		let nonce = c.solve(&slate_id);
+		challenges.verify(&c.challenge, &slate_id, nonce).unwrap();
+
+		// The proof only holds for the slate it was solved for
+		let other_id = Uuid::new_v4();
+		if !c.is_solved_by(&other_id, nonce) {
+			assert!(challenges.verify(&c.challenge, &other_id, nonce).is_err());
+		}
+
+		// Challenges are authenticated by the listener that issued them
+		let other = ReceiveChallenges::new(8).unwrap();
+		assert!(other.verify(&c.challenge, &slate_id, nonce).is_err());
+		assert!(ReceiveChallenges::new(MAX_RECEIVE_CHALLENGE_DIFFICULTY + 1).is_err());
+	}
 
-	let req = api::client::create_post_request(url.as_str(), api_secret, input, chain_type)?;
-	let res = api::client::send_request(req)?;
-	Ok(res)
+	#[test]
+	fn slate_version_negotiation() {
+		let slate = Slate::blank(2);
+		let s = negotiate_slate_version(&slate, &["V2".to_owned()]).unwrap();
+		assert_eq!(s.version_info.orig_version, CURRENT_SLATE_VERSION);
+
+		// Without a common version, or one this wallet knows, the send fails
+		assert!(negotiate_slate_version(&slate, &[]).is_err());
+		assert!(negotiate_slate_version(&slate, &["V3".to_owned()]).is_err());
+		assert!(negotiate_slate_version(&slate, &["V1".to_owned()]).is_err());
+	}
+
+	#[test]
+	fn slate_exchange_resume() {
+		let mut exchanges = SlateExchanges::new();
+		let data = "{\"id\":\"slate\"}";
+		let slate_id = Uuid::new_v4();
+		let chunk = |offset: usize, end: usize| SlateExchangeChunk {
+			token: "t".to_owned(),
+			slate_id,
+			offset: offset as u64,
+			total: data.len() as u64,
+			data: data[offset..end].to_owned(),
+		};
+		assert_eq!(exchanges.status("t").received, 0);
+		assert_eq!(exchanges.add_chunk(chunk(0, 5)).unwrap(), None);
+		// The exchange is for that slate only
+		let other = SlateExchangeChunk {
+			slate_id: Uuid::new_v4(),
+			..chunk(5, 8)
+		};
+		assert!(exchanges.add_chunk(other).is_err());
+
+		// A resent chunk is ignored, one past what was received is refused
+		assert_eq!(exchanges.add_chunk(chunk(0, 5)).unwrap(), None);
+		assert!(exchanges.add_chunk(chunk(8, 10)).is_err());
+		assert_eq!(exchanges.status("t").received, 5);
+
+		let complete = exchanges.add_chunk(chunk(5, data.len())).unwrap();
+		assert_eq!(complete.as_ref().map(|d| d.as_str()), Some(data));
+		exchanges.complete("t", json!({"result": {"Ok": null}}));
+
+		// The reply is kept, and the slate isn't handed out again
+		assert_eq!(exchanges.add_chunk(chunk(5, data.len())).unwrap(), None);
+		let status = exchanges.status("t");
+		assert_eq!(status.received, status.total);
+		assert_eq!(status.response, Some(json!({"result": {"Ok": null}})));
+	}
+	#[test]
+	fn slate_exchange_limits() {
+		let mut exchanges = SlateExchanges::new();
+		exchanges.max_buffered = 10;
+		exchanges.idle_timeout = Duration::from_millis(100);
+		let chunk = |token: &str, data: &str, total: usize| SlateExchangeChunk {
+			token: token.to_owned(),
+			slate_id: Uuid::nil(),
+			offset: 0,
+			total: total as u64,
+			data: data.to_owned(),
+		};
+
+		// Bytes held across all exchanges are capped
+		assert_eq!(exchanges.add_chunk(chunk("a", "012345", 8)).unwrap(), None);
+		assert!(exchanges.add_chunk(chunk("b", "01234", 8)).is_err());
+		assert_eq!(exchanges.add_chunk(chunk("b", "0123", 8)).unwrap(), None);
+		assert_eq!(exchanges.buffered(), 10);
+
+		// Uploads left idle are dropped, freeing their bytes
+		thread::sleep(Duration::from_millis(150));
+		assert_eq!(exchanges.status("a").received, 0);
+		assert!(exchanges.add_chunk(chunk("c", "01234567", 8)).is_ok());
+		assert_eq!(exchanges.status("b").received, 0);
+
+		// while a reply is kept for longer
+		let complete = exchanges.add_chunk(chunk("d", "01", 2)).unwrap();
+		assert!(complete.is_some());
+		exchanges.complete("d", json!(1));
+		thread::sleep(Duration::from_millis(150));
+		assert_eq!(exchanges.status("d").response, Some(json!(1)));
+		exchanges.reply_ttl = Duration::from_millis(100);
+		assert_eq!(exchanges.status("d").response, None);
+	}
 }
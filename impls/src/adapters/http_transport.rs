@@ -0,0 +1,454 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transport of the http slate sender. Destinations are resolved through the
+//! cache of the sender, .onion destinations are reached through its Tor SOCKS
+//! proxy, and failures to resolve, to connect and HTTP errors are reported
+//! apart, so a failed send tells what went wrong.
+
+use crate::client_utils::Client;
+use crate::libwallet::{Error, ErrorKind};
+use crate::util::Mutex;
+use futures::{Future, Stream};
+use hyper::header::{CONNECTION, CONTENT_LENGTH, HOST};
+use hyper::{Body, Request};
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// Seconds the addresses a destination resolved to are reused for
+pub const DNS_CACHE_TTL_SECS: u64 = 300;
+/// Seconds allowed to open a connection, directly or through the Tor proxy
+const CONNECT_TIMEOUT_SECS: u64 = 30;
+/// Seconds allowed for the destination to reply, receiving a slate can take a while
+const READ_TIMEOUT_SECS: u64 = 120;
+/// Largest reply read from a destination, in bytes
+const MAX_REPLY_SIZE: u64 = 16 * 1024 * 1024;
+
+lazy_static! {
+	/// Client whose pooled connections the https requests of all transports share,
+	/// created on the first of them
+	static ref HTTPS_CLIENT: Mutex<Option<Client>> = Mutex::new(None);
+}
+
+/// The client https requests are sent with, created if there's none yet
+fn https_client() -> Result<Client, Error> {
+	let mut shared = HTTPS_CLIENT.lock();
+	if let Some(c) = shared.as_ref() {
+		return Ok(c.clone());
+	}
+	let client = Client::new(false)
+		.map_err(|e| ErrorKind::ClientCallback(format!("Unable to create HTTP client: {}", e)))?;
+	*shared = Some(client.clone());
+	Ok(client)
+}
+
+/// Connections of a sender to its destinations: the Tor SOCKS proxy .onion
+/// destinations are reached through, if any, how long a destination is given to
+/// reply, and the addresses the destinations resolved to
+pub struct HttpTransport {
+	tor_socks_proxy: Option<String>,
+	timeout: Option<Duration>,
+	dns_cache: Mutex<HashMap<(String, u16), (Vec<SocketAddr>, Instant)>>,
+}
+
+/// Whether the url points to a Tor onion service
+pub fn is_onion(url: &Url) -> bool {
+	url.host_str()
+		.map(|h| h.to_lowercase().ends_with(".onion"))
+		.unwrap_or(false)
+}
+
+impl HttpTransport {
+	/// Transport reaching .onion destinations through the Tor SOCKS proxy at
+	/// `tor_socks_proxy`, if set, and failing to otherwise. Each request fails
+	/// unless answered within `timeout`, by default within `READ_TIMEOUT_SECS` of
+	/// the last data received.
+	pub fn new(tor_socks_proxy: Option<String>, timeout: Option<Duration>) -> HttpTransport {
+		HttpTransport {
+			tor_socks_proxy,
+			timeout,
+			dns_cache: Mutex::new(HashMap::new()),
+		}
+	}
+
+	/// Address of the Tor SOCKS proxy, if one is set
+	pub fn tor_socks_proxy(&self) -> Option<&str> {
+		self.tor_socks_proxy.as_ref().map(|p| p.as_str())
+	}
+
+	/// Resolve a host, reusing what it resolved to in the last `DNS_CACHE_TTL_SECS`
+	pub fn resolve(&self, host: &str, port: u16) -> Result<Vec<SocketAddr>, Error> {
+		let key = (host.to_lowercase(), port);
+		if let Some((addrs, expiry)) = self.dns_cache.lock().get(&key) {
+			if *expiry > Instant::now() {
+				return Ok(addrs.clone());
+			}
+		}
+		let addrs: Vec<SocketAddr> = (host, port)
+			.to_socket_addrs()
+			.map_err(|e| ErrorKind::DestinationResolution(format!("{}: {}", host, e)))?
+			.collect();
+		if addrs.is_empty() {
+			return Err(ErrorKind::DestinationResolution(format!(
+				"{}: no address found",
+				host
+			)))?;
+		}
+		let now = Instant::now();
+		let mut cache = self.dns_cache.lock();
+		cache.retain(|_, (_, expiry)| *expiry > now);
+		cache.insert(
+			key,
+			(addrs.clone(), now + Duration::from_secs(DNS_CACHE_TTL_SECS)),
+		);
+		Ok(addrs)
+	}
+
+	/// Send a request to the destination and return the body of its reply. Plain
+	/// http requests, the only ones onion services get, are made over a connection
+	/// of their own, https ones over the pooled connections of a `Client` shared by
+	/// all transports.
+	pub fn send_request(&self, url: &Url, req: Request<Body>) -> Result<String, Error> {
+		let (status, body) = if url.scheme() == "https" {
+			if is_onion(url) {
+				return Err(ErrorKind::DestinationConnection(format!(
+					"{}: use http for Tor addresses, onion services are already encrypted",
+					url
+				)))?;
+			}
+			// Resolved here to fill the cache and fail early, hyper then connects
+			self.resolve(host(url)?, url.port_or_known_default().unwrap_or(443))?;
+			https_client()?
+				.send_request_status(req, self.timeout)
+				.map_err(|e| ErrorKind::DestinationConnection(format!("{}: {}", url, e)))?
+		} else {
+			self.send_plain(url, req)?
+		};
+		let body = String::from_utf8_lossy(&body).to_string();
+		if status < 200 || status >= 300 {
+			return Err(ErrorKind::DestinationHttp(status, body))?;
+		}
+		Ok(body)
+	}
+
+	/// Send a request over a new connection, returning the status and body of the reply
+	fn send_plain(&self, url: &Url, req: Request<Body>) -> Result<(u16, Vec<u8>), Error> {
+		let (parts, body) = req.into_parts();
+		let body = body
+			.concat2()
+			.wait()
+			.map_err(|e| ErrorKind::ClientCallback(format!("Reading request body: {}", e)))?;
+		let host = host(url)?;
+		let mut path = url.path().to_owned();
+		if let Some(q) = url.query() {
+			path.push('?');
+			path.push_str(q);
+		}
+		let mut head = format!("{} {} HTTP/1.1\r\n", parts.method, path);
+		match url.port() {
+			Some(p) => head.push_str(&format!("Host: {}:{}\r\n", host, p)),
+			None => head.push_str(&format!("Host: {}\r\n", host)),
+		}
+		head.push_str(&format!("Content-Length: {}\r\n", body.len()));
+		head.push_str("Connection: close\r\n");
+		for (name, value) in parts.headers.iter() {
+			if name == HOST || name == CONTENT_LENGTH || name == CONNECTION {
+				continue;
+			}
+			let value = value
+				.to_str()
+				.map_err(|_| ErrorKind::ClientCallback(format!("Invalid header {}", name)))?;
+			head.push_str(&format!("{}: {}\r\n", name, value));
+		}
+		head.push_str("\r\n");
+
+		let mut stream = self.connect(url)?;
+		let timeout = self.timeout;
+		let failed = |e: io::Error| match (e.kind(), timeout) {
+			(io::ErrorKind::WouldBlock, Some(t)) | (io::ErrorKind::TimedOut, Some(t)) => {
+				ErrorKind::DestinationConnection(format!(
+					"{}: no reply within {} seconds",
+					url,
+					t.as_secs()
+				))
+			}
+			_ => ErrorKind::DestinationConnection(format!("{}: {}", url, e)),
+		};
+		stream.write_all(head.as_bytes()).map_err(failed)?;
+		stream.write_all(&body).map_err(failed)?;
+		Ok(read_reply(stream).map_err(failed)?)
+	}
+
+	/// Open a connection to the host of the url, through the Tor proxy for onion services
+	fn connect(&self, url: &Url) -> Result<TcpStream, Error> {
+		let host = host(url)?;
+		let port = url.port_or_known_default().unwrap_or(80);
+		let stream = if is_onion(url) {
+			let proxy = self.tor_socks_proxy().ok_or_else(|| {
+				ErrorKind::DestinationConnection(format!(
+					"{} is a Tor address, but no Tor SOCKS proxy is set (tor_socks_proxy_addr)",
+					host
+				))
+			})?;
+			debug!("Connecting to {} through Tor proxy {}", host, proxy);
+			socks5_connect(proxy, host, port, self.connect_timeout())?
+		} else {
+			connect_any(&self.resolve(host, port)?, host, self.connect_timeout())?
+		};
+		let timeout = Some(
+			self.timeout
+				.unwrap_or_else(|| Duration::from_secs(READ_TIMEOUT_SECS)),
+		);
+		stream
+			.set_read_timeout(timeout)
+			.and_then(|_| stream.set_write_timeout(timeout))
+			.map_err(|e| ErrorKind::DestinationConnection(format!("{}: {}", host, e)))?;
+		Ok(stream)
+	}
+
+	/// Time allowed to open a connection, no more than the whole request is given
+	fn connect_timeout(&self) -> Duration {
+		let timeout = Duration::from_secs(CONNECT_TIMEOUT_SECS);
+		match self.timeout {
+			Some(t) if t < timeout => t,
+			_ => timeout,
+		}
+	}
+}
+
+fn host(url: &Url) -> Result<&str, Error> {
+	url.host_str()
+		.ok_or_else(|| ErrorKind::DestinationResolution(format!("{} has no host", url)).into())
+}
+
+/// Connect to the first of the addresses that accepts
+fn connect_any(addrs: &[SocketAddr], name: &str, timeout: Duration) -> Result<TcpStream, Error> {
+	let mut failures = vec![];
+	for addr in addrs {
+		match TcpStream::connect_timeout(addr, timeout) {
+			Ok(s) => return Ok(s),
+			Err(e) => failures.push(format!("{}: {}", addr, e)),
+		}
+	}
+	Err(ErrorKind::DestinationConnection(format!("{} ({})", name, failures.join(", "))).into())
+}
+
+/// Open a connection to host:port through a SOCKS5 proxy. The proxy resolves the
+/// host, so onion addresses never reach the local resolver.
+fn socks5_connect(
+	proxy: &str,
+	host: &str,
+	port: u16,
+	timeout: Duration,
+) -> Result<TcpStream, Error> {
+	let failed = |e: String| {
+		ErrorKind::DestinationConnection(format!("{} through Tor proxy {}: {}", host, proxy, e))
+	};
+	let addrs: Vec<SocketAddr> = proxy
+		.to_socket_addrs()
+		.map_err(|e| failed(format!("invalid proxy address: {}", e)))?
+		.collect();
+	let mut stream = connect_any(&addrs, proxy, timeout)?;
+	if host.len() > 255 {
+		return Err(failed("host name too long".to_owned()))?;
+	}
+	let io_failed = |e: io::Error| failed(e.to_string());
+	stream.set_read_timeout(Some(timeout)).map_err(io_failed)?;
+
+	// Version 5, offering no authentication only
+	stream.write_all(&[5, 1, 0]).map_err(io_failed)?;
+	let mut reply = [0u8; 2];
+	stream.read_exact(&mut reply).map_err(io_failed)?;
+	if reply != [5, 0] {
+		return Err(failed("proxy refused the SOCKS5 handshake".to_owned()))?;
+	}
+
+	// Connect to a domain name
+	let mut req = vec![5, 1, 0, 3, host.len() as u8];
+	req.extend_from_slice(host.as_bytes());
+	req.extend_from_slice(&port.to_be_bytes());
+	stream.write_all(&req).map_err(io_failed)?;
+	let mut reply = [0u8; 4];
+	stream.read_exact(&mut reply).map_err(io_failed)?;
+	if reply[1] != 0 {
+		return Err(failed(socks5_reply_error(reply[1]).to_owned()))?;
+	}
+	// Skip the address the proxy bound
+	let len = match reply[3] {
+		1 => 4,
+		4 => 16,
+		3 => {
+			let mut len = [0u8; 1];
+			stream.read_exact(&mut len).map_err(io_failed)?;
+			len[0] as usize
+		}
+		_ => return Err(failed("invalid proxy reply".to_owned()))?,
+	};
+	let mut bound = vec![0u8; len + 2];
+	stream.read_exact(&mut bound).map_err(io_failed)?;
+	Ok(stream)
+}
+
+fn socks5_reply_error(code: u8) -> &'static str {
+	match code {
+		1 => "general proxy failure",
+		2 => "connection not allowed by the proxy",
+		3 => "network unreachable",
+		// What Tor replies for an onion service it can't reach
+		4 => "host unreachable",
+		5 => "connection refused",
+		6 => "TTL expired",
+		7 => "command not supported by the proxy",
+		8 => "address type not supported by the proxy",
+		_ => "unknown proxy error",
+	}
+}
+
+/// Read an HTTP/1.1 reply, returning its status and body
+fn read_reply(stream: TcpStream) -> io::Result<(u16, Vec<u8>)> {
+	let invalid = |m: &str| io::Error::new(io::ErrorKind::InvalidData, m.to_owned());
+	let mut reader = BufReader::new(stream.take(MAX_REPLY_SIZE));
+	let mut line = String::new();
+	reader.read_line(&mut line)?;
+	let status = line
+		.split_whitespace()
+		.nth(1)
+		.and_then(|s| s.parse::<u16>().ok())
+		.ok_or_else(|| invalid("invalid HTTP reply"))?;
+
+	let mut length = None;
+	let mut chunked = false;
+	loop {
+		line.clear();
+		if reader.read_line(&mut line)? == 0 {
+			return Err(invalid("reply ended in its headers"));
+		}
+		let header = line.trim_end();
+		if header.is_empty() {
+			break;
+		}
+		if let Some(i) = header.find(':') {
+			let name = header[..i].trim().to_lowercase();
+			let value = header[i + 1..].trim();
+			if name == "content-length" {
+				length = value.parse::<usize>().ok();
+			} else if name == "transfer-encoding" {
+				chunked = value.to_lowercase().contains("chunked");
+			}
+		}
+	}
+
+	let mut body = vec![];
+	if chunked {
+		loop {
+			line.clear();
+			reader.read_line(&mut line)?;
+			let size = line.trim().split(';').next().unwrap_or("");
+			let size =
+				usize::from_str_radix(size, 16).map_err(|_| invalid("invalid chunk size"))?;
+			if size == 0 {
+				break;
+			}
+			let start = body.len();
+			body.resize(start + size, 0);
+			reader.read_exact(&mut body[start..])?;
+			line.clear();
+			reader.read_line(&mut line)?;
+		}
+	} else if let Some(n) = length {
+		body.resize(n, 0);
+		reader.read_exact(&mut body)?;
+	} else {
+		reader.read_to_end(&mut body)?;
+	}
+	Ok((status, body))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn onion_destinations() {
+		let onion: Url = "http://2gzyxa5ihm7nsggfxnu52rck2vv4rvmdlkiu3zzui5du4xyclen53wid.onion"
+			.parse()
+			.unwrap();
+		assert!(is_onion(&onion));
+		assert!(is_onion(&"http://Example.ONION:3415".parse().unwrap()));
+		assert!(!is_onion(&"http://127.0.0.1:3415".parse().unwrap()));
+		assert!(!is_onion(&"https://onion.example.com".parse().unwrap()));
+
+		// Without a proxy, onion destinations fail to connect rather than resolve,
+		// whatever proxy another transport has
+		let tor = HttpTransport::new(Some("127.0.0.1:9050".to_owned()), None);
+		assert_eq!(tor.tor_socks_proxy(), Some("127.0.0.1:9050"));
+		let transport = HttpTransport::new(None, None);
+		assert_eq!(transport.tor_socks_proxy(), None);
+		match transport.connect(&onion).unwrap_err().kind() {
+			ErrorKind::DestinationConnection(m) => assert!(m.contains("no Tor SOCKS proxy")),
+			k => panic!("unexpected error {:?}", k),
+		}
+	}
+
+	#[test]
+	fn shared_https_client() {
+		let client = https_client().unwrap();
+		assert!(https_client().unwrap().shares_pool(&client));
+		assert!(!Client::new(false).unwrap().shares_pool(&client));
+	}
+
+	#[test]
+	fn resolution_cache() {
+		let transport = HttpTransport::new(None, None);
+		let addrs = transport.resolve("127.0.0.1", 3415).unwrap();
+		assert_eq!(addrs, vec!["127.0.0.1:3415".parse::<SocketAddr>().unwrap()]);
+		let key = ("127.0.0.1".to_owned(), 3415);
+		assert!(transport.dns_cache.lock().contains_key(&key));
+		// Each transport has a cache of its own
+		assert!(!HttpTransport::new(None, None)
+			.dns_cache
+			.lock()
+			.contains_key(&key));
+		match transport
+			.resolve("no-such-host.invalid", 3415)
+			.unwrap_err()
+			.kind()
+		{
+			ErrorKind::DestinationResolution(_) => {}
+			k => panic!("unexpected error {:?}", k),
+		}
+	}
+
+	#[test]
+	fn request_timeout() {
+		// A destination taking connections but never replying
+		let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+		let url: Url = format!("http://{}/v2/foreign", listener.local_addr().unwrap())
+			.parse()
+			.unwrap();
+		let transport = HttpTransport::new(None, Some(Duration::from_secs(1)));
+		assert_eq!(transport.connect_timeout(), Duration::from_secs(1));
+		let start = Instant::now();
+		let req = Request::get(url.as_str()).body(Body::empty()).unwrap();
+		match transport.send_request(&url, req).unwrap_err().kind() {
+			ErrorKind::DestinationConnection(m) => assert!(m.contains("within 1 seconds")),
+			k => panic!("unexpected error {:?}", k),
+		}
+		// The request itself gave up, nothing is left waiting for the reply
+		assert!(start.elapsed() < Duration::from_secs(10));
+	}
+}
@@ -14,13 +14,17 @@
 
 // Keybase Wallet Plugin
 
-use crate::adapters::{SlateReceiver, SlateSender};
+use crate::adapters::{SlateDelivery, SlateReceiver, SlateSender};
 use crate::config::WalletConfig;
 use crate::keychain::ExtKeychain;
 use crate::libwallet::api_impl::foreign;
-use crate::libwallet::{Error, ErrorKind, Slate, WalletInst};
+use crate::libwallet::{Error, ErrorKind, NodeClient, Slate, WalletBackend, WalletInst};
+use crate::util::secp::key::SecretKey;
 use crate::util::ZeroingString;
-use crate::{DefaultLCProvider, DefaultWalletImpl, HTTPNodeClient};
+use crate::{
+	DefaultLCProvider, DefaultWalletImpl, HTTPNodeClient, DEFAULT_OUTPUTS_QUERY_PARALLELISM,
+	DEFAULT_OUTPUTS_QUERY_RETRIES,
+};
 use serde::Serialize;
 use serde_json::{from_str, json, to_string, Value};
 use std::collections::{HashMap, HashSet};
@@ -28,6 +32,7 @@ use std::process::{Command, Stdio};
 use std::str::from_utf8;
 use std::thread::sleep;
 use std::time::{Duration, Instant};
+use uuid::Uuid;
 
 const TTL: u16 = 60; // TODO: Pass this as a parameter
 const LISTEN_SLEEP_DURATION: Duration = Duration::from_millis(5000);
@@ -36,13 +41,29 @@ const POLL_SLEEP_DURATION: Duration = Duration::from_millis(1000);
 // Which topic names to use for communication
 const SLATE_NEW: &str = "grin_slate_new";
 const SLATE_SIGNED: &str = "grin_slate_signed";
+const SLATE_REJECTED: &str = "grin_slate_rejected";
+
+/// Acknowledgement of a slate the recipient refused, sent back with the
+/// SLATE_REJECTED topic
+#[derive(Serialize, Deserialize)]
+struct SlateRejection {
+	/// Id of the slate
+	id: Uuid,
+	/// Why the slate was refused
+	reason: String,
+}
 
 #[derive(Clone)]
-pub struct KeybaseChannel(String);
+pub struct KeybaseChannel {
+	channel: String,
+	/// Seconds a response is waited for
+	ttl: u16,
+}
 
 impl KeybaseChannel {
-	/// Check if keybase is installed and return an adapter object.
-	pub fn new(channel: String) -> Result<KeybaseChannel, Error> {
+	/// Check if keybase is installed and return an adapter object. Responses are
+	/// waited for `TTL` seconds, or `timeout` if shorter.
+	pub fn new(channel: String, timeout: Option<Duration>) -> Result<KeybaseChannel, Error> {
 		// Limit only one recipient
 		if channel.matches(",").count() > 0 {
 			return Err(
@@ -58,7 +79,11 @@ impl KeybaseChannel {
 			.into());
 		}
 
-		Ok(KeybaseChannel(channel))
+		let ttl = match timeout {
+			Some(t) if t.as_secs() < TTL as u64 => t.as_secs().max(1) as u16,
+			_ => TTL,
+		};
+		Ok(KeybaseChannel { channel, ttl })
 	}
 }
 
@@ -268,11 +293,23 @@ fn notify(message: &str, channel: &str, ttl: u16) -> bool {
 	}
 }
 
-/// Listen for a message from a specific channel with topic SLATE_SIGNED for nseconds and return the first valid slate.
-fn poll(nseconds: u64, channel: &str) -> Option<Slate> {
+/// Listen for a message from a specific channel with topic SLATE_SIGNED for nseconds and return the first valid slate,
+/// or the reason the slate was rejected if a SLATE_REJECTED message came for it instead.
+fn poll(nseconds: u64, channel: &str, slate_id: &Uuid) -> Option<Result<Slate, String>> {
 	let start = Instant::now();
 	info!("Waiting for response message from @{}...", channel);
 	while start.elapsed().as_secs() < nseconds {
+		if let Ok(rejections) = read_from_channel(channel, SLATE_REJECTED) {
+			for msg in rejections.iter() {
+				match from_str::<SlateRejection>(&msg) {
+					Ok(r) if r.id == *slate_id => {
+						warn!("tx {} was rejected by @{}: {}", r.id, channel, r.reason);
+						return Some(Err(r.reason));
+					}
+					_ => (),
+				}
+			}
+		}
 		let unread = read_from_channel(channel, SLATE_SIGNED);
 		for msg in unread.unwrap().iter() {
 			let blob = Slate::deserialize_upgrade(&msg);
@@ -283,7 +320,7 @@ fn poll(nseconds: u64, channel: &str) -> Option<Slate> {
 						"keybase response message received from @{}, tx uuid: {}",
 						channel, slate.id,
 					);
-					return Some(slate);
+					return Some(Ok(slate));
 				}
 				Err(_) => (),
 			}
@@ -291,19 +328,25 @@ fn poll(nseconds: u64, channel: &str) -> Option<Slate> {
 		sleep(POLL_SLEEP_DURATION);
 	}
 	error!(
-		"No response from @{} in {} seconds. Grin send failed!",
+		"No response from @{} in {} seconds, the slate is left queued.",
 		channel, nseconds
 	);
 	None
 }
 
 impl SlateSender for KeybaseChannel {
-	/// Send a slate to a keybase username then wait for a response for TTL seconds.
+	/// Send a slate to a keybase username then wait for a response for `ttl` seconds.
 	fn send_tx(&self, slate: &Slate) -> Result<Slate, Error> {
+		self.deliver_tx(slate, "keybase")?.into_slate()
+	}
+
+	/// Send a slate to a keybase username then wait for a response for `ttl` seconds. A slate
+	/// without response in that time is left queued in the recipient's channel.
+	fn deliver_tx(&self, slate: &Slate, method: &str) -> Result<SlateDelivery, Error> {
 		let id = slate.id;
 
 		// Send original slate to recipient with the SLATE_NEW topic
-		match send(&slate, &self.0, SLATE_NEW, TTL) {
+		match send(&slate, &self.channel, SLATE_NEW, self.ttl) {
 			true => (),
 			false => {
 				return Err(ErrorKind::ClientCallback(
@@ -311,16 +354,16 @@ impl SlateSender for KeybaseChannel {
 				))?;
 			}
 		}
-		info!("tx request has been sent to @{}, tx uuid: {}", &self.0, id);
+		info!(
+			"tx request has been sent to @{}, tx uuid: {}",
+			&self.channel, id
+		);
 		// Wait for response from recipient with SLATE_SIGNED topic
-		match poll(TTL as u64, &self.0) {
-			Some(slate) => return Ok(slate),
-			None => {
-				return Err(ErrorKind::ClientCallback(
-					"Receiving reply from recipient".to_owned(),
-				))?;
-			}
-		}
+		Ok(match poll(self.ttl as u64, &self.channel, &id) {
+			Some(Ok(slate)) => SlateDelivery::accepted(slate, method),
+			Some(Err(reason)) => SlateDelivery::rejected(reason, method),
+			None => SlateDelivery::queued(method),
+		})
 	}
 }
 
@@ -354,7 +397,22 @@ impl SlateReceiver for KeybaseAllChannels {
 		account: &str,
 		node_api_secret: Option<String>,
 	) -> Result<(), Error> {
-		let node_client = HTTPNodeClient::new(&config.check_node_api_http_addr, node_api_secret);
+		let mut node_client = HTTPNodeClient::new_with_http2(
+			&config.check_node_api_http_addr,
+			node_api_secret,
+			config.chain_type(),
+			config.node_api_http2.unwrap_or(false),
+		)?;
+		node_client.set_outputs_query_limits(
+			config
+				.node_outputs_query_parallelism
+				.unwrap_or(DEFAULT_OUTPUTS_QUERY_PARALLELISM),
+			config
+				.node_outputs_query_retries
+				.unwrap_or(DEFAULT_OUTPUTS_QUERY_RETRIES),
+		);
+		node_client
+			.set_request_rate_limit(config.node_requests_per_second, config.node_request_burst);
 		let mut wallet = Box::new(
 			DefaultWalletImpl::<'static, HTTPNodeClient>::new(node_client.clone()).unwrap(),
 		)
@@ -368,12 +426,25 @@ impl SlateReceiver for KeybaseAllChannels {
 			>;
 		let lc = wallet.lc_provider().unwrap();
 		lc.set_top_level_directory(&config.data_file_dir)?;
+		lc.set_wallet_config(config.clone());
 		let mask = lc.open_wallet(None, passphrase, true, false)?;
 		let wallet_inst = lc.wallet_inst()?;
 		wallet_inst.set_parent_key_id_by_name(account)?;
+		let username = whoami()?;
 
 		info!("Listening for transactions on keybase ...");
+		let mut frozen = false;
 		loop {
+			// leave the messages unread while the wallet is frozen
+			if let Ok(Some(_)) = wallet_inst.freeze_state() {
+				if !frozen {
+					warn!("Wallet frozen, not receiving transactions on keybase");
+					frozen = true;
+				}
+				sleep(LISTEN_SLEEP_DURATION);
+				continue;
+			}
+			frozen = false;
 			// listen for messages from all channels with topic SLATE_NEW
 			let unread = get_unread(SLATE_NEW);
 			if unread.is_err() {
@@ -400,6 +471,25 @@ impl SlateReceiver for KeybaseAllChannels {
 							}
 						}
 
+						let sender = channel_sender(channel, &username);
+						let origin = format!("keybase:{}", sender);
+						if let Err(e) = foreign::check_slate_source(
+							&mut **wallet_inst,
+							Some(mask.as_ref().unwrap()),
+							&origin,
+						) {
+							warn!("Incoming tx from @{} is rejected: {}", sender, e);
+							reject(&slate, channel, &e);
+							log_failed_receive(
+								&mut **wallet_inst,
+								mask.as_ref(),
+								&slate,
+								&origin,
+								&e,
+							);
+							continue;
+						}
+
 						info!(
 							"tx initiated on channel \"{}\", to send you {}(g). tx uuid: {}",
 							channel,
@@ -429,6 +519,7 @@ impl SlateReceiver for KeybaseAllChannels {
 								if success {
 									notify_on_receive(
 										config.keybase_notify_ttl.unwrap_or(1440),
+										&config.currency(),
 										channel.to_string(),
 										tx_uuid.to_string(),
 									);
@@ -443,6 +534,14 @@ impl SlateReceiver for KeybaseAllChannels {
 									"Error on receiving tx via keybase: {}. Incoming tx failed",
 									e
 								);
+								reject(&slate, channel, &e);
+								log_failed_receive(
+									&mut **wallet_inst,
+									mask.as_ref(),
+									&slate,
+									&origin,
+									&e,
+								);
 							}
 						}
 					}
@@ -455,36 +554,52 @@ impl SlateReceiver for KeybaseAllChannels {
 	}
 }
 
+/// Let the sender of a slate know it was rejected, with the SLATE_REJECTED topic
+fn reject(slate: &Slate, channel: &str, reason: &Error) {
+	let rejection = SlateRejection {
+		id: slate.id,
+		reason: reason.to_string(),
+	};
+	if !send(rejection, channel, SLATE_REJECTED, TTL) {
+		error!(
+			"Failed to send the rejection of tx {} to @{}",
+			slate.id, channel
+		);
+	}
+}
+
+/// Keep a trace of an incoming slate that couldn't be received
+fn log_failed_receive(
+	w: &mut dyn WalletBackend<'static, HTTPNodeClient, ExtKeychain>,
+	mask: Option<&SecretKey>,
+	slate: &Slate,
+	origin: &str,
+	reason: &Error,
+) {
+	if let Err(e) = foreign::record_failed_receive(w, mask, slate, Some(origin), reason) {
+		error!("Unable to log failed receive of slate {}: {}", slate.id, e);
+	}
+}
+
 /// Notify in keybase on receiving a transaction
-fn notify_on_receive(keybase_notify_ttl: u16, channel: String, tx_uuid: String) {
+fn notify_on_receive(keybase_notify_ttl: u16, currency: &str, channel: String, tx_uuid: String) {
 	if keybase_notify_ttl > 0 {
 		let my_username = whoami();
 		if let Ok(username) = my_username {
-			let split = channel.split(",");
-			let vec: Vec<&str> = split.collect();
-			if vec.len() > 1 {
+			if channel.contains(",") {
 				let receiver = username;
-				let sender = if vec[0] == receiver {
-					vec[1]
-				} else {
-					if vec[1] != receiver {
-						error!("keybase - channel doesn't include my username! channel: {}, username: {}",
-							   channel, receiver
-						);
-					}
-					vec[0]
-				};
+				let sender = channel_sender(&channel, &receiver);
 
 				let msg = format!(
-					"[grin wallet notice]: \
+					"[{} wallet notice]: \
 					 you could have some coins received from @{}\n\
 					 Transaction Id: {}",
-					sender, tx_uuid
+					currency, sender, tx_uuid
 				);
 				notify(&msg, &receiver, keybase_notify_ttl);
 				info!(
-					"tx from @{} is done, please check on grin wallet. tx uuid: {}",
-					sender, tx_uuid,
+					"tx from @{} is done, please check on {} wallet. tx uuid: {}",
+					sender, currency, tx_uuid,
 				);
 			}
 		} else {
@@ -492,3 +607,22 @@ fn notify_on_receive(keybase_notify_ttl: u16, channel: String, tx_uuid: String)
 		}
 	}
 }
+
+/// The other participant of a two members channel, or the channel itself
+fn channel_sender<'a>(channel: &'a str, username: &str) -> &'a str {
+	let vec: Vec<&str> = channel.split(",").collect();
+	if vec.len() < 2 {
+		return channel;
+	}
+	if vec[0] == username {
+		vec[1]
+	} else {
+		if vec[1] != username {
+			error!(
+				"keybase - channel doesn't include my username! channel: {}, username: {}",
+				channel, username
+			);
+		}
+		vec[0]
+	}
+}
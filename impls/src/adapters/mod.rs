@@ -14,21 +14,101 @@
 
 mod file;
 mod http;
+mod http_transport;
 mod keybase;
 
 pub use self::file::PathToSlate;
-pub use self::http::HttpSlateSender;
+pub use self::http::{
+	HttpSlateSender, ReceiveChallenge, ReceiveChallenges, SlateExchangeChunk, SlateExchangeStatus,
+	SlateExchanges, FOREIGN_API_KEY_HEADER, FOREIGN_API_KEY_PATH, MAX_RECEIVE_CHALLENGE_DIFFICULTY,
+	RECEIVE_CHALLENGE_HEADER, RECEIVE_CHALLENGE_PATH, RECEIVE_NONCE_HEADER, SLATE_EXCHANGE_PATH,
+};
+pub use self::http_transport::{is_onion, HttpTransport, DNS_CACHE_TTL_SECS};
 pub use self::keybase::{KeybaseAllChannels, KeybaseChannel};
 
 use crate::config::WalletConfig;
-use crate::libwallet::{Error, ErrorKind, Slate};
+use crate::core::global;
+use crate::libwallet::{
+	DeliveryReceipt, DeliveryStatus, Error, ErrorKind, RecipientHandshake, Slate,
+};
+use crate::tor::onion_url;
 use crate::util::ZeroingString;
+use std::time::Duration;
 
 /// Sends transactions to a corresponding SlateReceiver
 pub trait SlateSender {
 	/// Send a transaction slate to another listening wallet and return result
 	/// TODO: Probably need a slate wrapper type
 	fn send_tx(&self, slate: &Slate) -> Result<Slate, Error>;
+
+	/// Send a transaction slate to another listening wallet, returning how that
+	/// wallet acknowledged it. Errors are left for slates that couldn't be
+	/// delivered at all. Transports without acknowledgements only ever report
+	/// slates accepted.
+	fn deliver_tx(&self, slate: &Slate, method: &str) -> Result<SlateDelivery, Error> {
+		let slate = self.send_tx(slate)?;
+		Ok(SlateDelivery::accepted(slate, method))
+	}
+
+	/// Contact the other wallet without sending it a slate, checking it's reachable
+	/// and able to receive slates from this wallet. Transports that can't reach the
+	/// other wallet without a slate fail.
+	fn handshake(&self, method: &str) -> Result<RecipientHandshake, Error> {
+		Err(ErrorKind::WalletComms(format!(
+			"Recipients of method {} can't be checked without sending a slate",
+			method
+		)))?
+	}
+}
+
+/// A slate delivered to another wallet, with its acknowledgement
+#[derive(Clone)]
+pub struct SlateDelivery {
+	/// Acknowledgement of the other wallet
+	pub receipt: DeliveryReceipt,
+	/// The slate the other wallet returned, if it accepted it
+	pub slate: Option<Slate>,
+}
+
+impl SlateDelivery {
+	/// The other wallet accepted the slate, returning it
+	pub fn accepted(slate: Slate, method: &str) -> SlateDelivery {
+		SlateDelivery {
+			receipt: DeliveryReceipt::new(DeliveryStatus::Accepted, None, method),
+			slate: Some(slate),
+		}
+	}
+
+	/// The other wallet rejected the slate
+	pub fn rejected(reason: String, method: &str) -> SlateDelivery {
+		SlateDelivery {
+			receipt: DeliveryReceipt::new(DeliveryStatus::Rejected, Some(reason), method),
+			slate: None,
+		}
+	}
+
+	/// The slate waits for the other wallet to process it
+	pub fn queued(method: &str) -> SlateDelivery {
+		SlateDelivery {
+			receipt: DeliveryReceipt::new(DeliveryStatus::Queued, None, method),
+			slate: None,
+		}
+	}
+
+	/// The slate returned by the other wallet, or an error if it didn't accept it
+	pub fn into_slate(self) -> Result<Slate, Error> {
+		match self.slate {
+			Some(s) => Ok(s),
+			None => {
+				let status = self.receipt.status.to_string().to_lowercase();
+				let report = match self.receipt.reason {
+					Some(r) => format!("Slate {} by the recipient: {}", status, r),
+					None => format!("Slate {} by the recipient", status),
+				};
+				Err(ErrorKind::ClientCallback(report).into())
+			}
+		}
+	}
 }
 
 pub trait SlateReceiver {
@@ -56,8 +136,16 @@ pub trait SlateGetter {
 	fn get_tx(&self) -> Result<Slate, Error>;
 }
 
-/// select a SlateSender based on method and dest fields from, e.g., SendArgs
-pub fn create_sender(method: &str, dest: &str) -> Result<Box<dyn SlateSender>, Error> {
+/// select a SlateSender based on method and dest fields from, e.g., SendArgs.
+/// Tor destinations are reached through the SOCKS proxy at `tor_socks_proxy`.
+/// Requests to the destination fail unless answered within `timeout`.
+pub fn create_sender(
+	method: &str,
+	dest: &str,
+	chain_type: global::ChainTypes,
+	tor_socks_proxy: Option<String>,
+	timeout: Option<Duration>,
+) -> Result<Box<dyn SlateSender>, Error> {
 	use url::Url;
 
 	let invalid = || {
@@ -69,9 +157,19 @@ pub fn create_sender(method: &str, dest: &str) -> Result<Box<dyn SlateSender>, E
 	Ok(match method {
 		"http" => {
 			let url: Url = dest.parse().map_err(|_| invalid())?;
-			Box::new(HttpSlateSender::new(url).map_err(|_| invalid())?)
+			Box::new(
+				HttpSlateSender::new(url, chain_type, tor_socks_proxy, timeout)
+					.map_err(|_| invalid())?,
+			)
+		}
+		"tor" => {
+			let url = onion_url(dest).ok_or_else(invalid)?;
+			Box::new(
+				HttpSlateSender::new(url, chain_type, tor_socks_proxy, timeout)
+					.map_err(|_| invalid())?,
+			)
 		}
-		"keybase" => Box::new(KeybaseChannel::new(dest.to_owned())?),
+		"keybase" => Box::new(KeybaseChannel::new(dest.to_owned(), timeout)?),
 		"self" => {
 			return Err(ErrorKind::WalletComms(
 				"No sender implementation for \"self\".".to_string(),
@@ -0,0 +1,326 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Audit log of the owner API requests of a wallet. Every request is logged, and
+//! if so configured mirrored to a syslog server as plain syslog or CEF records,
+//! rate limited and with the sensitive parameters redacted.
+
+use chrono::{SecondsFormat, Utc};
+use serde_json::Value;
+use std::net::UdpSocket;
+use std::time::{Duration, Instant};
+
+use crate::config::{AuditLogConfig, AuditLogFormat};
+use crate::util::Mutex;
+use crate::{Error, ErrorKind};
+
+/// Parameters whose values are always redacted from the records
+const REDACTED_PARAMS: &[&str] = &["password", "old", "new", "mnemonic", "token", "ecdh_pubkey"];
+
+/// Syslog facility of the records if not configured, log audit
+const DEFAULT_FACILITY: u8 = 13;
+
+/// Records sent per minute if not configured
+const DEFAULT_MAX_RECORDS_PER_MINUTE: u32 = 600;
+
+/// Outcome of an audited request
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AuditOutcome {
+	/// The request succeeded
+	Success,
+	/// The request failed
+	Failure,
+}
+
+impl AuditOutcome {
+	fn as_str(&self) -> &'static str {
+		match self {
+			AuditOutcome::Success => "success",
+			AuditOutcome::Failure => "failure",
+		}
+	}
+}
+
+/// Audit log of the owner API listener of a wallet
+pub struct AuditLog {
+	syslog: Option<Mutex<AuditSyslog>>,
+}
+
+/// Syslog server the audit log is mirrored to
+struct AuditSyslog {
+	config: AuditLogConfig,
+	socket: UdpSocket,
+	window_start: Instant,
+	window_records: u32,
+	dropped: u64,
+}
+
+impl AuditSyslog {
+	/// Whether another record can be sent in the current minute. Records
+	/// dropped in the previous one are reported once it's over.
+	fn admit(&mut self) -> bool {
+		let max = self
+			.config
+			.max_records_per_minute
+			.unwrap_or(DEFAULT_MAX_RECORDS_PER_MINUTE);
+		if self.window_start.elapsed() >= Duration::from_secs(60) {
+			self.window_start = Instant::now();
+			self.window_records = 0;
+			if self.dropped > 0 {
+				let dropped = self.dropped;
+				self.dropped = 0;
+				warn!(
+					"{} owner API audit records over the rate limit were dropped",
+					dropped
+				);
+				let record = self.format("audit_rate_limited", "", AuditOutcome::Failure, None);
+				let record = format!("{} dropped={}", record, dropped);
+				self.send(&record);
+			}
+		}
+		if max > 0 && self.window_records >= max {
+			self.dropped += 1;
+			return false;
+		}
+		self.window_records += 1;
+		true
+	}
+
+	fn send(&self, record: &str) {
+		if let Err(e) = self.socket.send(record.as_bytes()) {
+			debug!("Unable to send owner API audit record: {}", e);
+		}
+	}
+
+	/// Syslog record of a request, per RFC 5424
+	fn format(
+		&self,
+		method: &str,
+		api: &str,
+		outcome: AuditOutcome,
+		params: Option<&Value>,
+	) -> String {
+		let severity = match outcome {
+			AuditOutcome::Success => 6,
+			AuditOutcome::Failure => 4,
+		};
+		let facility = self.config.facility.unwrap_or(DEFAULT_FACILITY) as u32;
+		let header = format!(
+			"<{}>1 {} - mwc-wallet - owner_api -",
+			facility * 8 + severity,
+			Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)
+		);
+		let params = params.map(|p| p.to_string());
+		let msg = match self.config.format {
+			AuditLogFormat::Syslog => {
+				let mut msg = format!("method={} api={} outcome={}", method, api, outcome.as_str());
+				if let Some(p) = params {
+					msg.push_str(&format!(" params={}", p));
+				}
+				msg
+			}
+			AuditLogFormat::Cef => {
+				let mut msg = format!(
+					"CEF:0|mwc|mwc-wallet|{}|{}|Owner API {}|{}|rt={} outcome={} app={}",
+					env!("CARGO_PKG_VERSION"),
+					cef_header(method),
+					cef_header(method),
+					severity_to_cef(outcome),
+					Utc::now().timestamp_millis(),
+					outcome.as_str(),
+					cef_value(api),
+				);
+				if let Some(p) = params {
+					msg.push_str(&format!(" cs1Label=params cs1={}", cef_value(&p)));
+				}
+				msg
+			}
+		};
+		format!("{} {}", header, msg)
+	}
+}
+
+fn severity_to_cef(outcome: AuditOutcome) -> u8 {
+	match outcome {
+		AuditOutcome::Success => 3,
+		AuditOutcome::Failure => 6,
+	}
+}
+
+/// Escape a CEF header field
+fn cef_header(s: &str) -> String {
+	s.replace('\\', "\\\\").replace('|', "\\|")
+}
+
+/// Escape a CEF extension value
+fn cef_value(s: &str) -> String {
+	s.replace('\\', "\\\\")
+		.replace('=', "\\=")
+		.replace('\n', "\\n")
+		.replace('\r', "\\r")
+}
+
+/// Replace the values of the redacted parameters, at any depth
+fn redact(val: &mut Value, redact_params: &[String]) {
+	match val {
+		Value::Object(map) => {
+			for (k, v) in map.iter_mut() {
+				let redacted =
+					REDACTED_PARAMS.contains(&k.as_str()) || redact_params.iter().any(|p| p == k);
+				if redacted {
+					*v = Value::String("***".to_owned());
+				} else {
+					redact(v, redact_params);
+				}
+			}
+		}
+		Value::Array(vals) => {
+			for v in vals.iter_mut() {
+				redact(v, redact_params);
+			}
+		}
+		_ => {}
+	}
+}
+
+impl AuditLog {
+	/// Audit log mirrored to the configured syslog server, if any
+	pub fn new(config: Option<AuditLogConfig>) -> Result<AuditLog, Error> {
+		let config = match config {
+			Some(c) => c,
+			None => return Ok(AuditLog { syslog: None }),
+		};
+		let socket = UdpSocket::bind("0.0.0.0:0")
+			.and_then(|s| s.connect(config.syslog_addr.as_str()).map(|_| s))
+			.map_err(|e| {
+				ErrorKind::GenericError(format!(
+					"Unable to reach syslog server {}: {}",
+					config.syslog_addr, e
+				))
+			})?;
+		warn!(
+			"Mirroring the owner API audit log to {}",
+			config.syslog_addr
+		);
+		let syslog = AuditSyslog {
+			config,
+			socket,
+			window_start: Instant::now(),
+			window_records: 0,
+			dropped: 0,
+		};
+		Ok(AuditLog {
+			syslog: Some(Mutex::new(syslog)),
+		})
+	}
+
+	/// Record an owner API request, `api` being the version of the API it came
+	/// through. Its parameters are only mirrored if configured so, redacted.
+	pub fn record(&self, method: &str, api: &str, outcome: AuditOutcome, params: &Value) {
+		info!(
+			"Owner API audit: method={} api={} outcome={}",
+			method,
+			api,
+			outcome.as_str()
+		);
+		let mut syslog = match self.syslog.as_ref() {
+			Some(s) => s.lock(),
+			None => return,
+		};
+		if !syslog.admit() {
+			return;
+		}
+		let params = match syslog.config.include_params {
+			true => {
+				let mut params = params.clone();
+				redact(&mut params, &syslog.config.redact_params);
+				Some(params)
+			}
+			false => None,
+		};
+		let record = syslog.format(method, api, outcome, params.as_ref());
+		syslog.send(&record);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn audit_records() {
+		let mut params = serde_json::json!({
+			"token": "d202964900000000d302964900000000",
+			"args": { "password": "secret", "dest": "http://1.2.3.4:3415", "amount": 1 },
+			"list": [{ "mnemonic": "a b c" }],
+		});
+		redact(&mut params, &["dest".to_owned()]);
+		assert_eq!(params["token"], "***");
+		assert_eq!(params["args"]["password"], "***");
+		assert_eq!(params["args"]["dest"], "***");
+		assert_eq!(params["args"]["amount"], 1);
+		assert_eq!(params["list"][0]["mnemonic"], "***");
+
+		assert_eq!(cef_header("a|b\\c"), "a\\|b\\\\c");
+		assert_eq!(cef_value("a=b\nc"), "a\\=b\\nc");
+
+		let mut syslog = AuditSyslog {
+			config: AuditLogConfig {
+				syslog_addr: "127.0.0.1:514".to_owned(),
+				format: AuditLogFormat::Cef,
+				facility: None,
+				max_records_per_minute: Some(2),
+				include_params: false,
+				redact_params: vec![],
+			},
+			socket: UdpSocket::bind("127.0.0.1:0").unwrap(),
+			window_start: Instant::now(),
+			window_records: 0,
+			dropped: 0,
+		};
+		let record = syslog.format("init_send_tx", "v3", AuditOutcome::Success, None);
+		assert!(record.starts_with("<110>1 "));
+		assert!(record.contains("CEF:0|mwc|mwc-wallet|"));
+		assert!(record.contains("|init_send_tx|Owner API init_send_tx|3|"));
+		assert!(record.ends_with("outcome=success app=v3"));
+
+		// records over the limit are dropped and counted
+		assert!(syslog.admit());
+		assert!(syslog.admit());
+		assert!(!syslog.admit());
+		assert_eq!(syslog.dropped, 1);
+
+		// Each audit log has its own syslog server
+		let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+		server
+			.set_read_timeout(Some(Duration::from_secs(5)))
+			.unwrap();
+		let config = AuditLogConfig {
+			syslog_addr: server.local_addr().unwrap().to_string(),
+			format: AuditLogFormat::Syslog,
+			facility: None,
+			max_records_per_minute: None,
+			include_params: false,
+			redact_params: vec![],
+		};
+		let mirrored = AuditLog::new(Some(config)).unwrap();
+		let local = AuditLog::new(None).unwrap();
+		local.record("cancel_tx", "v2", AuditOutcome::Success, &Value::Null);
+		mirrored.record("retrieve_txs", "v3", AuditOutcome::Failure, &Value::Null);
+		let mut buf = [0u8; 1024];
+		let n = server.recv(&mut buf).unwrap();
+		let record = String::from_utf8_lossy(&buf[..n]);
+		assert!(record.ends_with("method=retrieve_txs api=v3 outcome=failure"));
+	}
+}
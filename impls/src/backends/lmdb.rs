@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
 use std::{fs, path};
 
 // for writing stored transaction files
@@ -20,24 +21,32 @@ use std::fs::File;
 use std::io::{Read, Write};
 use std::marker::PhantomData;
 use std::path::Path;
+use std::sync::Arc;
 
+use chrono::{DateTime, Utc};
 use failure::ResultExt;
 use uuid::Uuid;
 
+use super::read_only::ReadOnlyStore;
 use crate::blake2::blake2b::{Blake2b, Blake2bResult};
+use crate::config::WalletConfig;
 
 use crate::keychain::{ChildNumber, ExtKeychain, Identifier, Keychain, SwitchCommitmentType};
 use crate::store::{self, option_to_not_found, to_key, to_key_u64};
 
 use crate::core::core::Transaction;
 use crate::core::ser;
-use crate::libwallet::{check_repair, restore};
+use crate::libwallet::{check_repair, import_output, restore, restore_commit, restore_scan};
 use crate::libwallet::{
-	AcctPathMapping, Context, Error, ErrorKind, NodeClient, OutputData, TxLogEntry, WalletBackend,
-	WalletOutputBatch,
+	AcctPathMapping, ApprovalToken, ArchivedSlate, BalanceHold, ChangeEntity, ChangeOp,
+	ChangeRecord, Context, Error, ErrorKind, FailedReceive, ForeignApiKey, FreezeState, NodeClient,
+	OnetimeAddress, OutputData, PendingDispatch, PriceSource, RestoreScan, SendMetrics,
+	SourceFilter, TotpSettings, TxLogEntry, VaultSettings, WalletBackend, WalletEvent,
+	WalletOutputBatch, WatchOnlyKey,
 };
 use crate::util::secp::constants::SECRET_KEY_SIZE;
 use crate::util::secp::key::SecretKey;
+use crate::util::secp::pedersen;
 use crate::util::{self, secp};
 
 use rand::rngs::mock::StepRng;
@@ -53,6 +62,25 @@ const PRIVATE_TX_CONTEXT_PREFIX: u8 = 'p' as u8;
 const TX_LOG_ENTRY_PREFIX: u8 = 't' as u8;
 const TX_LOG_ID_PREFIX: u8 = 'i' as u8;
 const ACCOUNT_PATH_MAPPING_PREFIX: u8 = 'a' as u8;
+const BALANCE_HOLD_PREFIX: u8 = 'h' as u8;
+const SOURCE_FILTER_PREFIX: u8 = 'f' as u8;
+const FAILED_RECEIVE_PREFIX: u8 = 'r' as u8;
+const VAULT_SETTINGS_PREFIX: u8 = 'v' as u8;
+const FREEZE_STATE_PREFIX: u8 = 'z' as u8;
+const TOTP_SETTINGS_PREFIX: u8 = 'w' as u8;
+const WALLET_EVENT_PREFIX: u8 = 'e' as u8;
+const EVENT_SEQ_PREFIX: u8 = 'n' as u8;
+const EVENT_OFFSET_PREFIX: u8 = 'g' as u8;
+const SLATE_ARCHIVE_PREFIX: u8 = 's' as u8;
+const CHANGE_SEQ_PREFIX: u8 = 'q' as u8;
+const CHANGE_JOURNAL_PREFIX: u8 = 'j' as u8;
+const PENDING_DISPATCH_PREFIX: u8 = 'k' as u8;
+const ONETIME_ADDRESS_PREFIX: u8 = 'l' as u8;
+const FOREIGN_API_KEY_PREFIX: u8 = 'm' as u8;
+const APPROVAL_TOKEN_PREFIX: u8 = 'x' as u8;
+
+/// Number of latest changes kept in the change journal
+const CHANGE_JOURNAL_MAX_RECORDS: u64 = 10_000;
 
 /// test to see if database files exist in the current directory. If so,
 /// use a DB backend for all operations
@@ -61,6 +89,40 @@ pub fn wallet_db_exists(data_file_dir: &str) -> bool {
 	db_path.exists()
 }
 
+/// Balance holds are keyed by account and reference
+fn balance_hold_key(hold: &BalanceHold) -> Vec<u8> {
+	let mut id = hold.parent_key_id.to_bytes().to_vec();
+	id.extend_from_slice(hold.reference.as_bytes());
+	to_key(BALANCE_HOLD_PREFIX, &mut id)
+}
+
+/// Failed receives are keyed by time, then slate id, to iterate in order
+fn failed_receive_key(entry: &FailedReceive) -> Vec<u8> {
+	let mut id = (entry.created.timestamp_nanos() as u64)
+		.to_be_bytes()
+		.to_vec();
+	id.extend_from_slice(entry.slate_id.as_bytes());
+	to_key(FAILED_RECEIVE_PREFIX, &mut id)
+}
+
+/// Archived slates are keyed by slate id and then time, so the versions of a
+/// slate are next to each other, oldest first
+fn archived_slate_key(entry: &ArchivedSlate) -> Vec<u8> {
+	let mut id = entry.slate_id.as_bytes().to_vec();
+	id.extend_from_slice(&(entry.created.timestamp_nanos() as u64).to_be_bytes());
+	to_key(SLATE_ARCHIVE_PREFIX, &mut id)
+}
+
+/// Wallet events are keyed by sequence number, big-endian to iterate in order
+fn wallet_event_key(seq: u64) -> Vec<u8> {
+	to_key(WALLET_EVENT_PREFIX, &mut seq.to_be_bytes().to_vec())
+}
+
+/// Change journal entries are keyed by counter, to iterate in order
+fn change_record_key(counter: u64) -> Vec<u8> {
+	to_key(CHANGE_JOURNAL_PREFIX, &mut counter.to_be_bytes().to_vec())
+}
+
 /// Helper to derive XOR keys for storing private transaction keys in the DB
 /// (blind_xor_key, nonce_xor_key)
 fn private_ctx_xor_keys<K>(
@@ -94,12 +156,60 @@ where
 	Ok((ret_blind, ret_nonce))
 }
 
+/// Helper to derive the XOR key of the secret of the second factor in the DB,
+/// h(root_key|"totp")
+fn totp_xor_key<K>(keychain: &K) -> Result<[u8; SECRET_KEY_SIZE], Error>
+where
+	K: Keychain,
+{
+	let root_key = keychain.derive_key(0, &K::root_key_id(), &SwitchCommitmentType::Regular)?;
+	let mut hasher = Blake2b::new(SECRET_KEY_SIZE);
+	hasher.update(&root_key.0[..]);
+	hasher.update(&"totp".as_bytes()[..]);
+	let xor_key = hasher.finalize();
+	let mut ret = [0; SECRET_KEY_SIZE];
+	ret.copy_from_slice(&xor_key.as_bytes()[0..SECRET_KEY_SIZE]);
+	Ok(ret)
+}
+
+/// Wallet database, opened read-write through the node's store, or read-only
+enum WalletStore {
+	ReadWrite(store::Store),
+	ReadOnly(ReadOnlyStore),
+}
+
+impl WalletStore {
+	fn get_ser<T: ser::Readable>(&self, key: &[u8]) -> Result<Option<T>, store::Error> {
+		match self {
+			WalletStore::ReadWrite(s) => s.get_ser(key),
+			WalletStore::ReadOnly(s) => s.get_ser(key),
+		}
+	}
+
+	fn iter<T: ser::Readable + 'static>(
+		&self,
+		prefix: &[u8],
+	) -> Result<Box<dyn Iterator<Item = (Vec<u8>, T)>>, store::Error> {
+		match self {
+			WalletStore::ReadWrite(s) => Ok(Box::new(s.iter(prefix)?)),
+			WalletStore::ReadOnly(s) => Ok(Box::new(s.iter(prefix)?.into_iter())),
+		}
+	}
+
+	fn batch(&self) -> Result<store::Batch<'_>, store::Error> {
+		match self {
+			WalletStore::ReadWrite(s) => s.batch(),
+			WalletStore::ReadOnly(s) => Err(s.write_error()),
+		}
+	}
+}
+
 pub struct LMDBBackend<'ck, C, K>
 where
 	C: NodeClient + 'ck,
 	K: Keychain + 'ck,
 {
-	db: store::Store,
+	db: WalletStore,
 	data_file_dir: String,
 	/// Keychain
 	pub keychain: Option<K>,
@@ -109,6 +219,20 @@ where
 	parent_key_id: Identifier,
 	/// wallet to node client
 	w2n_client: C,
+	/// Whether changes to the wallet are rejected
+	read_only: bool,
+	/// Change keys reserved ahead, by parent path
+	change_keys: HashMap<Identifier, VecDeque<Identifier>>,
+	/// Root public key of the wallet followed, if watch-only
+	watch_only: Option<WatchOnlyKey>,
+	/// Configuration the wallet runs with
+	config: WalletConfig,
+	/// Price provider the wallet values its coins with
+	price_source: Option<Arc<PriceSource>>,
+	/// Chain height last seen from the node, and when it was first seen
+	node_height_seen: Option<(u64, DateTime<Utc>)>,
+	/// Time spent in each step of the sends built since the wallet was opened
+	send_metrics: SendMetrics,
 	///phantom
 	_phantom: &'ck PhantomData<C>,
 }
@@ -147,12 +271,50 @@ where
 		}
 
 		let res = LMDBBackend {
-			db: store,
+			db: WalletStore::ReadWrite(store),
+			data_file_dir: data_file_dir.to_owned(),
+			keychain: None,
+			master_checksum: Box::new(None),
+			parent_key_id: LMDBBackend::<C, K>::default_path(),
+			w2n_client: n_client,
+			read_only: false,
+			change_keys: HashMap::new(),
+			watch_only: None,
+			config: WalletConfig::default(),
+			price_source: None,
+			node_height_seen: None,
+			send_metrics: SendMetrics::default(),
+			_phantom: &PhantomData,
+		};
+		Ok(res)
+	}
+
+	/// Open an existing wallet database without changing it. The LMDB environment
+	/// is opened read-only, so any attempt to write to it fails. LMDB handles
+	/// readers from several processes, so this can be used while another process
+	/// has the wallet open.
+	pub fn new_read_only(data_file_dir: &str, n_client: C) -> Result<Self, Error> {
+		let db_path = path::Path::new(data_file_dir).join(DB_DIR);
+		if !db_path.exists() {
+			let msg = format!("No wallet database in {}", data_file_dir);
+			return Err(ErrorKind::Lifecycle(msg).into());
+		}
+		let store = ReadOnlyStore::open(db_path.to_str().unwrap(), DB_DIR)?;
+
+		let res = LMDBBackend {
+			db: WalletStore::ReadOnly(store),
 			data_file_dir: data_file_dir.to_owned(),
 			keychain: None,
 			master_checksum: Box::new(None),
 			parent_key_id: LMDBBackend::<C, K>::default_path(),
 			w2n_client: n_client,
+			read_only: true,
+			change_keys: HashMap::new(),
+			watch_only: None,
+			config: WalletConfig::default(),
+			price_source: None,
+			node_height_seen: None,
+			send_metrics: SendMetrics::default(),
 			_phantom: &PhantomData,
 		};
 		Ok(res)
@@ -165,6 +327,12 @@ where
 		ExtKeychain::derive_key_id(2, 0, 0, 0, 0)
 	}
 
+	/// Make the wallet watch-only, following the outputs of the wallet of a root
+	/// public key. Its keychain must hold none of the keys of that wallet.
+	pub fn set_watch_only(&mut self, key: WatchOnlyKey) {
+		self.watch_only = Some(key);
+	}
+
 	/// Just test to see if database files exist in the current directory. If
 	/// so, use a DB backend for all operations
 	pub fn exists(data_file_dir: &str) -> bool {
@@ -244,6 +412,43 @@ where
 		}
 	}
 
+	/// Root public key of the wallet followed, if watch-only
+	fn watch_only_key(&self) -> Option<WatchOnlyKey> {
+		self.watch_only.clone()
+	}
+
+	fn config(&self) -> &WalletConfig {
+		&self.config
+	}
+
+	fn set_config(&mut self, config: WalletConfig) {
+		self.config = config;
+	}
+
+	fn price_source(&self) -> Option<&PriceSource> {
+		self.price_source.as_ref().map(|s| s.as_ref())
+	}
+
+	fn set_price_source(&mut self, source: Option<Arc<PriceSource>>) {
+		self.price_source = source;
+	}
+
+	fn node_height_seen(&self) -> Option<(u64, DateTime<Utc>)> {
+		self.node_height_seen
+	}
+
+	fn set_node_height_seen(&mut self, height: u64, since: DateTime<Utc>) {
+		self.node_height_seen = Some((height, since));
+	}
+
+	fn send_metrics(&self) -> &SendMetrics {
+		&self.send_metrics
+	}
+
+	fn send_metrics_mut(&mut self) -> &mut SendMetrics {
+		&mut self.send_metrics
+	}
+
 	/// Return the node client being used
 	fn w2n_client(&mut self) -> &mut C {
 		&mut self.w2n_client
@@ -353,7 +558,128 @@ where
 		self.db.get_ser(&acct_key).map_err(|e| e.into())
 	}
 
+	fn balance_hold_iter<'a>(&'a self) -> Box<dyn Iterator<Item = BalanceHold> + 'a> {
+		Box::new(self.db.iter(&[BALANCE_HOLD_PREFIX]).unwrap().map(|o| o.1))
+	}
+
+	fn source_filter(&self) -> Result<SourceFilter, Error> {
+		let filter_key = to_key(SOURCE_FILTER_PREFIX, &mut "filter".as_bytes().to_vec());
+		let filter: Option<SourceFilter> = self.db.get_ser(&filter_key)?;
+		Ok(filter.unwrap_or_default())
+	}
+
+	fn failed_receive_iter<'a>(&'a self) -> Box<dyn Iterator<Item = FailedReceive> + 'a> {
+		Box::new(self.db.iter(&[FAILED_RECEIVE_PREFIX]).unwrap().map(|o| o.1))
+	}
+
+	fn archived_slate_iter<'a>(&'a self) -> Box<dyn Iterator<Item = ArchivedSlate> + 'a> {
+		Box::new(self.db.iter(&[SLATE_ARCHIVE_PREFIX]).unwrap().map(|o| o.1))
+	}
+
+	fn pending_dispatch_iter<'a>(&'a self) -> Box<dyn Iterator<Item = PendingDispatch> + 'a> {
+		Box::new(
+			self.db
+				.iter(&[PENDING_DISPATCH_PREFIX])
+				.unwrap()
+				.map(|o| o.1),
+		)
+	}
+
+	fn get_pending_dispatch(&self, slate_id: &Uuid) -> Result<Option<PendingDispatch>, Error> {
+		let dispatch_key = to_key(PENDING_DISPATCH_PREFIX, &mut slate_id.as_bytes().to_vec());
+		self.db.get_ser(&dispatch_key).map_err(|e| e.into())
+	}
+
+	fn onetime_address_iter<'a>(&'a self) -> Box<dyn Iterator<Item = OnetimeAddress> + 'a> {
+		Box::new(
+			self.db
+				.iter(&[ONETIME_ADDRESS_PREFIX])
+				.unwrap()
+				.map(|o| o.1),
+		)
+	}
+
+	fn get_onetime_address(&self, id: &Uuid) -> Result<Option<OnetimeAddress>, Error> {
+		let address_key = to_key(ONETIME_ADDRESS_PREFIX, &mut id.as_bytes().to_vec());
+		self.db.get_ser(&address_key).map_err(|e| e.into())
+	}
+
+	fn foreign_api_key_iter<'a>(&'a self) -> Box<dyn Iterator<Item = ForeignApiKey> + 'a> {
+		Box::new(
+			self.db
+				.iter(&[FOREIGN_API_KEY_PREFIX])
+				.unwrap()
+				.map(|o| o.1),
+		)
+	}
+
+	fn get_foreign_api_key(&self, key_hash: &str) -> Result<Option<ForeignApiKey>, Error> {
+		let api_key = to_key(FOREIGN_API_KEY_PREFIX, &mut key_hash.as_bytes().to_vec());
+		self.db.get_ser(&api_key).map_err(|e| e.into())
+	}
+
+	fn approval_token_iter<'a>(&'a self) -> Box<dyn Iterator<Item = ApprovalToken> + 'a> {
+		Box::new(self.db.iter(&[APPROVAL_TOKEN_PREFIX]).unwrap().map(|o| o.1))
+	}
+
+	fn get_approval_token(&self, token_hash: &str) -> Result<Option<ApprovalToken>, Error> {
+		let token_key = to_key(APPROVAL_TOKEN_PREFIX, &mut token_hash.as_bytes().to_vec());
+		self.db.get_ser(&token_key).map_err(|e| e.into())
+	}
+
+	fn change_journal_iter<'a>(&'a self) -> Box<dyn Iterator<Item = ChangeRecord> + 'a> {
+		Box::new(self.db.iter(&[CHANGE_JOURNAL_PREFIX]).unwrap().map(|o| o.1))
+	}
+
+	fn vault_settings(&self, parent_key_id: &Identifier) -> Result<Option<VaultSettings>, Error> {
+		let vault_key = to_key(
+			VAULT_SETTINGS_PREFIX,
+			&mut parent_key_id.to_bytes().to_vec(),
+		);
+		self.db.get_ser(&vault_key).map_err(|e| e.into())
+	}
+
+	fn freeze_state(&self) -> Result<Option<FreezeState>, Error> {
+		let freeze_key = to_key(FREEZE_STATE_PREFIX, &mut "freeze".as_bytes().to_vec());
+		self.db.get_ser(&freeze_key).map_err(|e| e.into())
+	}
+
+	fn totp_settings(
+		&mut self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<Option<TotpSettings>, Error> {
+		let totp_key = to_key(TOTP_SETTINGS_PREFIX, &mut "totp".as_bytes().to_vec());
+		let mut settings: TotpSettings = match self.db.get_ser(&totp_key)? {
+			Some(s) => s,
+			None => return Ok(None),
+		};
+		let xor_key = totp_xor_key(&self.keychain(keychain_mask)?)?;
+		for (b, k) in settings.secret.iter_mut().zip(xor_key.iter()) {
+			*b = *b ^ k;
+		}
+		Ok(Some(settings))
+	}
+
+	fn wallet_event_iter<'a>(&'a self) -> Box<dyn Iterator<Item = WalletEvent> + 'a> {
+		Box::new(self.db.iter(&[WALLET_EVENT_PREFIX]).unwrap().map(|o| o.1))
+	}
+
+	fn event_offset(&self, consumer: &str) -> Result<u64, Error> {
+		let offset_key = to_key(EVENT_OFFSET_PREFIX, &mut consumer.as_bytes().to_vec());
+		let offset: Option<u64> = self.db.get_ser(&offset_key)?;
+		Ok(offset.unwrap_or(0))
+	}
+
+	fn change_seq(&self) -> Result<u64, Error> {
+		let seq_key = to_key(CHANGE_SEQ_PREFIX, &mut "seq".as_bytes().to_vec());
+		let seq: Option<u64> = self.db.get_ser(&seq_key)?;
+		Ok(seq.unwrap_or(0))
+	}
+
 	fn store_tx(&self, uuid: &str, tx: &Transaction) -> Result<(), Error> {
+		if self.read_only {
+			return Err(ErrorKind::ReadOnly("storing a transaction".to_owned()).into());
+		}
 		let filename = format!("{}.grintx", uuid);
 		let path = path::Path::new(&self.data_file_dir)
 			.join(TX_SAVE_DIR)
@@ -389,6 +715,9 @@ where
 		&'a mut self,
 		keychain_mask: Option<&SecretKey>,
 	) -> Result<Box<dyn WalletOutputBatch<K> + 'a>, Error> {
+		if self.read_only {
+			return Err(ErrorKind::ReadOnly("changing the wallet".to_owned()).into());
+		}
 		Ok(Box::new(Batch {
 			_store: self,
 			db: RefCell::new(Some(self.db.batch()?)),
@@ -416,6 +745,46 @@ where
 		Ok(Identifier::from_path(&return_path))
 	}
 
+	fn next_change_child<'a>(
+		&mut self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<Identifier, Error> {
+		let pool_size = self.config.change_key_pool_size.unwrap_or(0) as u32;
+		if pool_size == 0 {
+			return self.next_child(keychain_mask);
+		}
+		let parent_key_id = self.parent_key_id.clone();
+		let next_idx = self.next_child_index(&parent_key_id)?;
+		let mut pool = self.change_keys.remove(&parent_key_id).unwrap_or_default();
+		// Keys no longer below the child index, which a restore or check may have
+		// moved back, aren't reserved anymore
+		pool.retain(|id| id.to_path().last_path_index() < next_idx);
+		if pool.is_empty() {
+			let mut path = parent_key_id.to_path();
+			path.depth = path.depth + 1;
+			for i in next_idx..next_idx + pool_size {
+				path.path[path.depth as usize - 1] = ChildNumber::from(i);
+				pool.push_back(Identifier::from_path(&path));
+			}
+			let mut batch = self.batch(keychain_mask)?;
+			batch.save_child_index(&parent_key_id, next_idx + pool_size)?;
+			batch.commit()?;
+		}
+		let child = pool.pop_front();
+		self.change_keys.insert(parent_key_id, pool);
+		child.ok_or_else(|| ErrorKind::GenericError("Empty change key pool".to_owned()).into())
+	}
+
+	fn next_child_index(&mut self, parent_key_id: &Identifier) -> Result<u32, Error> {
+		let batch = self.db.batch()?;
+		let deriv_key = to_key(DERIV_PREFIX, &mut parent_key_id.to_bytes().to_vec());
+		let deriv_idx = match batch.get_ser(&deriv_key)? {
+			Some(idx) => idx,
+			None => 0,
+		};
+		Ok(deriv_idx)
+	}
+
 	fn last_confirmed_height<'a>(&mut self) -> Result<u64, Error> {
 		let batch = self.db.batch()?;
 		let height_key = to_key(
@@ -429,8 +798,12 @@ where
 		Ok(last_confirmed_height)
 	}
 
-	fn restore(&mut self, keychain_mask: Option<&SecretKey>) -> Result<(), Error> {
-		restore(self, keychain_mask).context(ErrorKind::Restore)?;
+	fn restore(
+		&mut self,
+		keychain_mask: Option<&SecretKey>,
+		birthday: Option<u64>,
+	) -> Result<(), Error> {
+		restore(self, keychain_mask, birthday).context(ErrorKind::Restore)?;
 		Ok(())
 	}
 
@@ -442,6 +815,34 @@ where
 		check_repair(self, keychain_mask, delete_unconfirmed).context(ErrorKind::Restore)?;
 		Ok(())
 	}
+
+	fn import_output(
+		&mut self,
+		keychain_mask: Option<&SecretKey>,
+		commit: &pedersen::Commitment,
+		value: u64,
+		key_id: &Identifier,
+		height: Option<u64>,
+	) -> Result<OutputData, Error> {
+		import_output(self, keychain_mask, commit, value, key_id, height)
+	}
+
+	fn restore_scan(
+		&mut self,
+		keychain_mask: Option<&SecretKey>,
+		scan: &mut RestoreScan,
+		max_outputs: u64,
+	) -> Result<(), Error> {
+		restore_scan(self, keychain_mask, scan, max_outputs)
+	}
+
+	fn restore_commit(
+		&mut self,
+		keychain_mask: Option<&SecretKey>,
+		scan: &RestoreScan,
+	) -> Result<(), Error> {
+		restore_commit(self, keychain_mask, scan)
+	}
 }
 
 /// An atomic batch in which all changes can be committed all at once or
@@ -458,6 +859,35 @@ where
 }
 
 #[allow(missing_docs)]
+impl<'a, C, K> Batch<'a, C, K>
+where
+	C: NodeClient,
+	K: Keychain,
+{
+	/// Increment the change counter and record the change in the journal,
+	/// dropping the oldest record once the journal is full. Returns the new
+	/// value of the counter.
+	fn journal(&self, op: ChangeOp, entity: ChangeEntity, id: String) -> Result<u64, Error> {
+		let seq_key = to_key(CHANGE_SEQ_PREFIX, &mut "seq".as_bytes().to_vec());
+		let db = self.db.borrow();
+		let db = db.as_ref().unwrap();
+		let counter = db.get_ser::<u64>(&seq_key)?.unwrap_or(0) + 1;
+		db.put_ser(&seq_key, &counter)?;
+		let record = ChangeRecord {
+			counter,
+			op,
+			entity,
+			id,
+			time: Utc::now(),
+		};
+		db.put_ser(&change_record_key(counter), &record)?;
+		if counter > CHANGE_JOURNAL_MAX_RECORDS {
+			let _ = db.delete(&change_record_key(counter - CHANGE_JOURNAL_MAX_RECORDS));
+		}
+		Ok(counter)
+	}
+}
+
 impl<'a, C, K> WalletOutputBatch<K> for Batch<'a, C, K>
 where
 	C: NodeClient,
@@ -467,7 +897,9 @@ where
 		self.keychain.as_mut().unwrap()
 	}
 
-	fn save(&mut self, out: OutputData) -> Result<(), Error> {
+	fn save(&mut self, mut out: OutputData) -> Result<(), Error> {
+		out.change_seq =
+			self.journal(ChangeOp::Save, ChangeEntity::Output, out.key_id.to_string())?;
 		// Save the output data to the db.
 		{
 			let key = match out.mmr_index {
@@ -505,6 +937,7 @@ where
 	}
 
 	fn delete(&mut self, id: &Identifier, mmr_index: &Option<u64>) -> Result<(), Error> {
+		self.journal(ChangeOp::Delete, ChangeEntity::Output, id.to_string())?;
 		// Delete the output data.
 		{
 			let key = match mmr_index {
@@ -548,6 +981,8 @@ where
 		parent_key_id: &Identifier,
 		height: u64,
 	) -> Result<(), Error> {
+		let id = parent_key_id.to_string();
+		self.journal(ChangeOp::Save, ChangeEntity::ConfirmedHeight, id)?;
 		let height_key = to_key(
 			CONFIRMED_HEIGHT_PREFIX,
 			&mut parent_key_id.to_bytes().to_vec(),
@@ -561,6 +996,11 @@ where
 	}
 
 	fn save_child_index(&mut self, parent_id: &Identifier, child_n: u32) -> Result<(), Error> {
+		self.journal(
+			ChangeOp::Save,
+			ChangeEntity::ChildIndex,
+			parent_id.to_string(),
+		)?;
 		let deriv_key = to_key(DERIV_PREFIX, &mut parent_id.to_bytes().to_vec());
 		self.db
 			.borrow()
@@ -572,9 +1012,11 @@ where
 
 	fn save_tx_log_entry(
 		&mut self,
-		tx_in: TxLogEntry,
+		mut tx_in: TxLogEntry,
 		parent_id: &Identifier,
 	) -> Result<(), Error> {
+		let id = format!("{}/{}", parent_id, tx_in.id);
+		tx_in.change_seq = self.journal(ChangeOp::Save, ChangeEntity::TxLogEntry, id)?;
 		let tx_log_key = to_key_u64(
 			TX_LOG_ENTRY_PREFIX,
 			&mut parent_id.to_bytes().to_vec(),
@@ -589,6 +1031,7 @@ where
 	}
 
 	fn save_acct_path(&mut self, mapping: AcctPathMapping) -> Result<(), Error> {
+		self.journal(ChangeOp::Save, ChangeEntity::Account, mapping.label.clone())?;
 		let acct_key = to_key(
 			ACCOUNT_PATH_MAPPING_PREFIX,
 			&mut mapping.label.as_bytes().to_vec(),
@@ -613,6 +1056,338 @@ where
 		)
 	}
 
+	fn save_balance_hold(&mut self, hold: &BalanceHold) -> Result<(), Error> {
+		let id = format!("{}/{}", hold.parent_key_id, hold.reference);
+		self.journal(ChangeOp::Save, ChangeEntity::BalanceHold, id)?;
+		let hold_key = balance_hold_key(hold);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&hold_key, hold)?;
+		Ok(())
+	}
+
+	fn delete_balance_hold(&mut self, hold: &BalanceHold) -> Result<(), Error> {
+		let id = format!("{}/{}", hold.parent_key_id, hold.reference);
+		self.journal(ChangeOp::Delete, ChangeEntity::BalanceHold, id)?;
+		let hold_key = balance_hold_key(hold);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.delete(&hold_key)
+			.map_err(|e| e.into())
+	}
+
+	fn save_source_filter(&mut self, filter: &SourceFilter) -> Result<(), Error> {
+		self.journal(
+			ChangeOp::Save,
+			ChangeEntity::SourceFilter,
+			"filter".to_owned(),
+		)?;
+		let filter_key = to_key(SOURCE_FILTER_PREFIX, &mut "filter".as_bytes().to_vec());
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&filter_key, filter)?;
+		Ok(())
+	}
+
+	fn save_failed_receive(&mut self, entry: &FailedReceive) -> Result<(), Error> {
+		let id = entry.slate_id.to_string();
+		self.journal(ChangeOp::Save, ChangeEntity::FailedReceive, id)?;
+		let entry_key = failed_receive_key(entry);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&entry_key, entry)?;
+		Ok(())
+	}
+
+	fn delete_failed_receive(&mut self, entry: &FailedReceive) -> Result<(), Error> {
+		let id = entry.slate_id.to_string();
+		self.journal(ChangeOp::Delete, ChangeEntity::FailedReceive, id)?;
+		let entry_key = failed_receive_key(entry);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.delete(&entry_key)
+			.map_err(|e| e.into())
+	}
+
+	fn save_archived_slate(&mut self, entry: &ArchivedSlate) -> Result<(), Error> {
+		let id = entry.slate_id.to_string();
+		self.journal(ChangeOp::Save, ChangeEntity::ArchivedSlate, id)?;
+		let entry_key = archived_slate_key(entry);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&entry_key, entry)?;
+		Ok(())
+	}
+
+	fn delete_archived_slate(&mut self, entry: &ArchivedSlate) -> Result<(), Error> {
+		let id = entry.slate_id.to_string();
+		self.journal(ChangeOp::Delete, ChangeEntity::ArchivedSlate, id)?;
+		let entry_key = archived_slate_key(entry);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.delete(&entry_key)
+			.map_err(|e| e.into())
+	}
+
+	fn save_pending_dispatch(&mut self, entry: &PendingDispatch) -> Result<(), Error> {
+		let id = entry.slate_id.to_string();
+		self.journal(ChangeOp::Save, ChangeEntity::PendingDispatch, id)?;
+		let dispatch_key = to_key(
+			PENDING_DISPATCH_PREFIX,
+			&mut entry.slate_id.as_bytes().to_vec(),
+		);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&dispatch_key, entry)?;
+		Ok(())
+	}
+
+	fn delete_pending_dispatch(&mut self, slate_id: &Uuid) -> Result<(), Error> {
+		self.journal(
+			ChangeOp::Delete,
+			ChangeEntity::PendingDispatch,
+			slate_id.to_string(),
+		)?;
+		let dispatch_key = to_key(PENDING_DISPATCH_PREFIX, &mut slate_id.as_bytes().to_vec());
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.delete(&dispatch_key)
+			.map_err(|e| e.into())
+	}
+
+	fn save_onetime_address(&mut self, address: &OnetimeAddress) -> Result<(), Error> {
+		let id = address.id.to_string();
+		self.journal(ChangeOp::Save, ChangeEntity::OnetimeAddress, id)?;
+		let address_key = to_key(ONETIME_ADDRESS_PREFIX, &mut address.id.as_bytes().to_vec());
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&address_key, address)?;
+		Ok(())
+	}
+
+	fn save_foreign_api_key(&mut self, key: &ForeignApiKey) -> Result<(), Error> {
+		self.journal(
+			ChangeOp::Save,
+			ChangeEntity::ForeignApiKey,
+			key.id.to_string(),
+		)?;
+		let api_key = to_key(
+			FOREIGN_API_KEY_PREFIX,
+			&mut key.key_hash.as_bytes().to_vec(),
+		);
+		self.db.borrow().as_ref().unwrap().put_ser(&api_key, key)?;
+		Ok(())
+	}
+
+	fn delete_foreign_api_key(&mut self, key: &ForeignApiKey) -> Result<(), Error> {
+		self.journal(
+			ChangeOp::Delete,
+			ChangeEntity::ForeignApiKey,
+			key.id.to_string(),
+		)?;
+		let api_key = to_key(
+			FOREIGN_API_KEY_PREFIX,
+			&mut key.key_hash.as_bytes().to_vec(),
+		);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.delete(&api_key)
+			.map_err(|e| e.into())
+	}
+
+	fn save_approval_token(&mut self, token: &ApprovalToken) -> Result<(), Error> {
+		self.journal(
+			ChangeOp::Save,
+			ChangeEntity::ApprovalToken,
+			token.id.to_string(),
+		)?;
+		let token_key = to_key(
+			APPROVAL_TOKEN_PREFIX,
+			&mut token.token_hash.as_bytes().to_vec(),
+		);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&token_key, token)?;
+		Ok(())
+	}
+
+	fn delete_approval_token(&mut self, token: &ApprovalToken) -> Result<(), Error> {
+		self.journal(
+			ChangeOp::Delete,
+			ChangeEntity::ApprovalToken,
+			token.id.to_string(),
+		)?;
+		let token_key = to_key(
+			APPROVAL_TOKEN_PREFIX,
+			&mut token.token_hash.as_bytes().to_vec(),
+		);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.delete(&token_key)
+			.map_err(|e| e.into())
+	}
+
+	fn save_vault_settings(&mut self, settings: &VaultSettings) -> Result<(), Error> {
+		let id = settings.parent_key_id.to_string();
+		self.journal(ChangeOp::Save, ChangeEntity::VaultSettings, id)?;
+		let vault_key = to_key(
+			VAULT_SETTINGS_PREFIX,
+			&mut settings.parent_key_id.to_bytes().to_vec(),
+		);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&vault_key, settings)?;
+		Ok(())
+	}
+
+	fn save_freeze_state(&mut self, state: &FreezeState) -> Result<(), Error> {
+		self.journal(
+			ChangeOp::Save,
+			ChangeEntity::FreezeState,
+			"freeze".to_owned(),
+		)?;
+		let freeze_key = to_key(FREEZE_STATE_PREFIX, &mut "freeze".as_bytes().to_vec());
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&freeze_key, state)?;
+		Ok(())
+	}
+
+	fn delete_freeze_state(&mut self) -> Result<(), Error> {
+		self.journal(
+			ChangeOp::Delete,
+			ChangeEntity::FreezeState,
+			"freeze".to_owned(),
+		)?;
+		let freeze_key = to_key(FREEZE_STATE_PREFIX, &mut "freeze".as_bytes().to_vec());
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.delete(&freeze_key)
+			.map_err(|e| e.into())
+	}
+
+	fn save_totp_settings(&mut self, settings: &TotpSettings) -> Result<(), Error> {
+		self.journal(
+			ChangeOp::Save,
+			ChangeEntity::TotpSettings,
+			"totp".to_owned(),
+		)?;
+		let totp_key = to_key(TOTP_SETTINGS_PREFIX, &mut "totp".as_bytes().to_vec());
+		let xor_key = totp_xor_key(self.keychain())?;
+		let mut s_settings = settings.clone();
+		for (b, k) in s_settings.secret.iter_mut().zip(xor_key.iter()) {
+			*b = *b ^ k;
+		}
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&totp_key, &s_settings)?;
+		Ok(())
+	}
+
+	fn delete_totp_settings(&mut self) -> Result<(), Error> {
+		self.journal(
+			ChangeOp::Delete,
+			ChangeEntity::TotpSettings,
+			"totp".to_owned(),
+		)?;
+		let totp_key = to_key(TOTP_SETTINGS_PREFIX, &mut "totp".as_bytes().to_vec());
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.delete(&totp_key)
+			.map_err(|e| e.into())
+	}
+
+	fn next_event_seq(&mut self) -> Result<u64, Error> {
+		let seq_key = to_key(EVENT_SEQ_PREFIX, &mut "seq".as_bytes().to_vec());
+		let last_seq = match self.db.borrow().as_ref().unwrap().get_ser(&seq_key)? {
+			Some(s) => s,
+			None => 0,
+		};
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&seq_key, &(last_seq + 1))?;
+		Ok(last_seq + 1)
+	}
+
+	fn save_wallet_event(&mut self, event: &WalletEvent) -> Result<(), Error> {
+		self.journal(
+			ChangeOp::Save,
+			ChangeEntity::WalletEvent,
+			event.seq.to_string(),
+		)?;
+		let event_key = wallet_event_key(event.seq);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&event_key, event)?;
+		Ok(())
+	}
+
+	fn delete_wallet_event(&mut self, seq: u64) -> Result<(), Error> {
+		self.journal(ChangeOp::Delete, ChangeEntity::WalletEvent, seq.to_string())?;
+		let event_key = wallet_event_key(seq);
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.delete(&event_key)
+			.map_err(|e| e.into())
+	}
+
+	fn save_event_offset(&mut self, consumer: &str, seq: u64) -> Result<(), Error> {
+		self.journal(
+			ChangeOp::Save,
+			ChangeEntity::EventOffset,
+			consumer.to_owned(),
+		)?;
+		let offset_key = to_key(EVENT_OFFSET_PREFIX, &mut consumer.as_bytes().to_vec());
+		self.db
+			.borrow()
+			.as_ref()
+			.unwrap()
+			.put_ser(&offset_key, &seq)?;
+		Ok(())
+	}
+
 	fn lock_output(&mut self, out: &mut OutputData) -> Result<(), Error> {
 		out.lock();
 		self.save(out.clone())
@@ -624,6 +1399,8 @@ where
 		participant_id: usize,
 		ctx: &Context,
 	) -> Result<(), Error> {
+		let id = format!("{}/{}", util::to_hex(slate_id.to_vec()), participant_id);
+		self.journal(ChangeOp::Save, ChangeEntity::PrivateContext, id)?;
 		let ctx_key = to_key_u64(
 			PRIVATE_TX_CONTEXT_PREFIX,
 			&mut slate_id.to_vec(),
@@ -650,6 +1427,8 @@ where
 		slate_id: &[u8],
 		participant_id: usize,
 	) -> Result<(), Error> {
+		let id = format!("{}/{}", util::to_hex(slate_id.to_vec()), participant_id);
+		self.journal(ChangeOp::Delete, ChangeEntity::PrivateContext, id)?;
 		let ctx_key = to_key_u64(
 			PRIVATE_TX_CONTEXT_PREFIX,
 			&mut slate_id.to_vec(),
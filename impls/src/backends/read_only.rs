@@ -0,0 +1,101 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Read-only access to the wallet database. The LMDB environment itself is opened
+//! read-only, so nothing can write to it, whichever way it's asked to.
+
+use lmdb_zero as lmdb;
+use lmdb_zero::traits::LmdbResultExt;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::core::ser;
+use crate::store;
+
+/// Version the node's store serializes the data with
+const DB_PROTOCOL_VERSION: ser::ProtocolVersion = ser::ProtocolVersion(1);
+
+/// A database created by the node's store, opened read-only
+pub struct ReadOnlyStore {
+	env: Arc<lmdb::Environment>,
+	db: lmdb::Database<'static>,
+}
+
+impl ReadOnlyStore {
+	/// Open the database `db_name` the node's store created under `root_path`,
+	/// which must exist
+	pub fn open(root_path: &str, db_name: &str) -> Result<ReadOnlyStore, store::Error> {
+		let env_path = Path::new(root_path).join("lmdb");
+		let mut env_builder = lmdb::EnvBuilder::new()?;
+		env_builder.set_maxdbs(8)?;
+		let env = unsafe {
+			env_builder.open(
+				env_path.to_str().unwrap(),
+				lmdb::open::RDONLY | lmdb::open::NOTLS,
+				0o600,
+			)?
+		};
+		let env = Arc::new(env);
+		let db = lmdb::Database::open(
+			env.clone(),
+			Some(db_name),
+			&lmdb::DatabaseOptions::defaults(),
+		)?;
+		Ok(ReadOnlyStore { env, db })
+	}
+
+	/// Gets a value from the db, deserialized
+	pub fn get_ser<T: ser::Readable>(&self, key: &[u8]) -> Result<Option<T>, store::Error> {
+		let txn = lmdb::ReadTransaction::new(self.env.clone())?;
+		let access = txn.access();
+		let res: Option<&[u8]> = access.get(&self.db, key).to_opt()?;
+		match res {
+			Some(mut v) => ser::deserialize(&mut v, DB_PROTOCOL_VERSION)
+				.map(Some)
+				.map_err(|e| store::Error::SerErr(format!("{}", e))),
+			None => Ok(None),
+		}
+	}
+
+	/// All the values whose keys start with the prefix, deserialized. They're
+	/// read at once, as the read transaction can't outlive the call.
+	pub fn iter<T: ser::Readable>(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, T)>, store::Error> {
+		let txn = lmdb::ReadTransaction::new(self.env.clone())?;
+		let access = txn.access();
+		let mut cursor = txn.cursor(&self.db)?;
+		let mut res = vec![];
+		let mut kv = cursor
+			.seek_range_k::<[u8], [u8]>(&access, prefix)
+			.to_opt()?;
+		while let Some((k, mut v)) = kv {
+			if !k.starts_with(prefix) {
+				break;
+			}
+			let value = ser::deserialize(&mut v, DB_PROTOCOL_VERSION)
+				.map_err(|e| store::Error::SerErr(format!("{}", e)))?;
+			res.push((k.to_vec(), value));
+			kv = cursor.next::<[u8], [u8]>(&access).to_opt()?;
+		}
+		Ok(res)
+	}
+
+	/// The error LMDB gives for any attempt to write to the database
+	pub fn write_error(&self) -> store::Error {
+		match lmdb::WriteTransaction::new(self.env.clone()) {
+			Err(e) => e.into(),
+			// Not the case of an environment opened read-only
+			Ok(_) => store::Error::NotFoundErr("read-only environment".to_owned()),
+		}
+	}
+}
@@ -0,0 +1,803 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encrypted wallet backups, pushed to remote storage such as an S3-compatible
+//! object store. A backup holds the files of the wallet directory, encrypted
+//! before leaving the wallet with a key derived from its seed. Backups go
+//! through a temporary file as they're made and fetched, so the wallet files
+//! are never held in memory whole.
+
+mod s3;
+
+pub use self::s3::S3Target;
+
+use chrono::{DateTime, Utc};
+use failure::ResultExt;
+use rand::{thread_rng, Rng};
+use ring::{aead, digest};
+use std::cmp;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::mem;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+use crate::blake2::blake2b::Blake2b;
+use crate::keychain::{Keychain, SwitchCommitmentType};
+use crate::util;
+use crate::{Error, ErrorKind, WALLET_LOCK_FILE};
+
+/// Magic bytes starting an encrypted backup
+const BACKUP_MAGIC: &[u8] = b"MWCB";
+/// Version of the backup format
+const BACKUP_VERSION: u8 = 1;
+/// Length of the nonce prefix following the version. The rest of the nonce of
+/// a segment is its counter and whether it's the last one.
+const NONCE_PREFIX_LEN: usize = 7;
+/// Size of the segments the archive is encrypted in
+const SEGMENT_LEN: usize = 64 * 1024;
+/// Longest path of a file in an archive
+const MAX_PATH_LEN: u64 = 4096;
+/// Extension of the backup names
+const BACKUP_EXT: &'static str = ".mwcbak";
+/// File of the wallet directory recording when the wallet was last backed up
+pub const LAST_BACKUP_FILE: &'static str = "last_backup";
+/// Files of the wallet directory left out of backups. LMDB's lock file and the
+/// wallet's own lock only make sense to the running processes, and a restored
+/// wallet wasn't backed up yet.
+const EXCLUDED_FILES: &[&str] = &["lock.mdb", WALLET_LOCK_FILE, LAST_BACKUP_FILE];
+
+/// Storage wallet backups are pushed to
+pub trait BackupTarget {
+	/// Store an object of `size` bytes read from `data`, replacing any existing
+	/// object of that name
+	fn put(&self, name: &str, data: &mut dyn Read, size: u64) -> Result<(), Error>;
+
+	/// Fetch an object, writing it to `out`
+	fn get(&self, name: &str, out: &mut dyn Write) -> Result<(), Error>;
+
+	/// Names of the stored objects starting with `prefix`
+	fn list(&self, prefix: &str) -> Result<Vec<String>, Error>;
+
+	/// Delete an object
+	fn delete(&self, name: &str) -> Result<(), Error>;
+}
+
+/// Backup pushed to a target
+#[derive(Clone, Debug)]
+pub struct BackupInfo {
+	/// Name of the backup
+	pub name: String,
+	/// Number of wallet files in the backup
+	pub files: usize,
+	/// Size of the encrypted backup
+	pub size: u64,
+	/// SHA-256 of the encrypted backup, matched by the stored copy
+	pub sha256: String,
+	/// Older backups deleted to keep within the retention
+	pub pruned: Vec<String>,
+}
+
+/// Key backups are encrypted with. It's derived from the wallet seed, so a
+/// wallet recovered from its recovery phrase can read its backups.
+fn backup_key<K>(keychain: &K) -> Result<[u8; 32], Error>
+where
+	K: Keychain,
+{
+	let root_key = keychain.derive_key(0, &K::root_key_id(), &SwitchCommitmentType::Regular)?;
+	let mut hasher = Blake2b::new(32);
+	hasher.update(&root_key.0[..]);
+	hasher.update(&"backup".as_bytes()[..]);
+	let mut key = [0; 32];
+	key.copy_from_slice(&hasher.finalize().as_bytes()[0..32]);
+	Ok(key)
+}
+
+/// Start of the names of the wallet's backups under `prefix`. It identifies the
+/// wallet without revealing anything of the key, so wallets sharing the storage,
+/// even under the same prefix, only ever list and prune their own backups.
+fn backup_name_prefix(prefix: &str, key: &[u8; 32]) -> String {
+	let mut hasher = Blake2b::new(32);
+	hasher.update(&key[..]);
+	hasher.update(&"backup name".as_bytes()[..]);
+	let id = util::to_hex(hasher.finalize().as_bytes()[0..8].to_vec());
+	format!("{}wallet-{}-", prefix, id)
+}
+
+/// Temporary file holding an encrypted backup, deleted when dropped
+struct TempFile(PathBuf);
+
+impl TempFile {
+	fn new() -> TempFile {
+		let id: [u8; 8] = thread_rng().gen();
+		let name = format!("mwc-backup-{}.tmp", util::to_hex(id.to_vec()));
+		TempFile(env::temp_dir().join(name))
+	}
+}
+
+impl Drop for TempFile {
+	fn drop(&mut self) {
+		let _ = fs::remove_file(&self.0);
+	}
+}
+
+/// Writer keeping the size and SHA-256 of what goes through it
+struct HashWriter<W: Write> {
+	inner: W,
+	digest: digest::Context,
+	len: u64,
+}
+
+impl<W: Write> HashWriter<W> {
+	fn new(inner: W) -> HashWriter<W> {
+		HashWriter {
+			inner,
+			digest: digest::Context::new(&digest::SHA256),
+			len: 0,
+		}
+	}
+
+	fn sha256(&self) -> String {
+		util::to_hex(self.digest.clone().finish().as_ref().to_vec())
+	}
+}
+
+impl<W: Write> Write for HashWriter<W> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let n = self.inner.write(buf)?;
+		self.digest.update(&buf[..n]);
+		self.len += n as u64;
+		Ok(n)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.inner.flush()
+	}
+}
+
+/// Nonce of a segment: the backup's nonce prefix, the segment counter and whether
+/// it's the last segment, so segments can't be reordered, dropped or truncated
+fn segment_nonce(header: &[u8], counter: u32, last: bool) -> [u8; 12] {
+	let mut nonce = [0; 12];
+	nonce[..NONCE_PREFIX_LEN].copy_from_slice(&header[BACKUP_MAGIC.len() + 1..]);
+	nonce[NONCE_PREFIX_LEN..NONCE_PREFIX_LEN + 4].copy_from_slice(&counter.to_be_bytes());
+	nonce[NONCE_PREFIX_LEN + 4] = last as u8;
+	nonce
+}
+
+/// Writer encrypting an archive in segments as it's written
+struct EncryptWriter<W: Write> {
+	out: W,
+	key: aead::SealingKey,
+	header: Vec<u8>,
+	counter: u32,
+	buf: Vec<u8>,
+}
+
+impl<W: Write> EncryptWriter<W> {
+	fn new(mut out: W, key: &[u8; 32]) -> Result<EncryptWriter<W>, Error> {
+		let prefix: [u8; NONCE_PREFIX_LEN] = thread_rng().gen();
+		let mut header = BACKUP_MAGIC.to_vec();
+		header.push(BACKUP_VERSION);
+		header.extend_from_slice(&prefix);
+		out.write_all(&header).context(ErrorKind::IO)?;
+		let key =
+			aead::SealingKey::new(&aead::CHACHA20_POLY1305, key).context(ErrorKind::Encryption)?;
+		Ok(EncryptWriter {
+			out,
+			key,
+			header,
+			counter: 0,
+			buf: vec![],
+		})
+	}
+
+	fn seal_segment(&mut self, len: usize, last: bool) -> io::Result<()> {
+		let tag_len = aead::CHACHA20_POLY1305.tag_len();
+		let mut segment: Vec<u8> = self.buf.drain(..len).collect();
+		segment.resize(len + tag_len, 0);
+		let nonce = segment_nonce(&self.header, self.counter, last);
+		aead::seal_in_place(&self.key, &nonce, &self.header, &mut segment, tag_len)
+			.map_err(|_| io::Error::new(io::ErrorKind::Other, "Backup encryption failed"))?;
+		self.counter = self
+			.counter
+			.checked_add(1)
+			.ok_or_else(|| io::Error::new(io::ErrorKind::Other, "Backup too large"))?;
+		self.out.write_all(&segment)
+	}
+
+	/// Seal the last segment, returning the underlying writer
+	fn finish(mut self) -> Result<W, Error> {
+		let len = self.buf.len();
+		self.seal_segment(len, true)
+			.context(ErrorKind::Encryption)?;
+		Ok(self.out)
+	}
+}
+
+impl<W: Write> Write for EncryptWriter<W> {
+	fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+		self.buf.extend_from_slice(data);
+		// The last segment is only sealed once the archive is complete
+		while self.buf.len() > SEGMENT_LEN {
+			self.seal_segment(SEGMENT_LEN, false)?;
+		}
+		Ok(data.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.out.flush()
+	}
+}
+
+/// Reader of an archive encrypted by `EncryptWriter`, failing on any segment
+/// altered, missing or out of place
+struct DecryptReader<R: Read> {
+	input: R,
+	key: aead::OpeningKey,
+	header: Vec<u8>,
+	counter: u32,
+	segment: Vec<u8>,
+	pos: usize,
+	/// Bytes read past the current segment, telling it isn't the last one
+	carry: Vec<u8>,
+	done: bool,
+}
+
+impl<R: Read> DecryptReader<R> {
+	fn new(mut input: R, key: &[u8; 32]) -> Result<DecryptReader<R>, Error> {
+		let mut header = vec![0; BACKUP_MAGIC.len() + 1 + NONCE_PREFIX_LEN];
+		if input.read_exact(&mut header).is_err() || &header[..BACKUP_MAGIC.len()] != BACKUP_MAGIC {
+			return Err(ErrorKind::GenericError("Not a wallet backup".to_owned()))?;
+		}
+		if header[BACKUP_MAGIC.len()] != BACKUP_VERSION {
+			return Err(ErrorKind::GenericError(format!(
+				"Unsupported backup version {}",
+				header[BACKUP_MAGIC.len()]
+			)))?;
+		}
+		let key =
+			aead::OpeningKey::new(&aead::CHACHA20_POLY1305, key).context(ErrorKind::Encryption)?;
+		Ok(DecryptReader {
+			input,
+			key,
+			header,
+			counter: 0,
+			segment: vec![],
+			pos: 0,
+			carry: vec![],
+			done: false,
+		})
+	}
+
+	fn next_segment(&mut self) -> io::Result<()> {
+		let sealed_len = SEGMENT_LEN + aead::CHACHA20_POLY1305.tag_len();
+		let mut segment = mem::replace(&mut self.carry, vec![]);
+		let missing = sealed_len + 1 - segment.len();
+		(&mut self.input)
+			.take(missing as u64)
+			.read_to_end(&mut segment)?;
+		let last = segment.len() <= sealed_len;
+		if !last {
+			self.carry = segment.split_off(sealed_len);
+		}
+		let nonce = segment_nonce(&self.header, self.counter, last);
+		let len = aead::open_in_place(&self.key, &nonce, &self.header, 0, &mut segment)
+			.map_err(|_| {
+				io::Error::new(
+					io::ErrorKind::InvalidData,
+					"backup altered, or not encrypted for this wallet",
+				)
+			})?
+			.len();
+		segment.truncate(len);
+		self.segment = segment;
+		self.pos = 0;
+		self.counter = self.counter.wrapping_add(1);
+		self.done = last;
+		Ok(())
+	}
+}
+
+impl<R: Read> Read for DecryptReader<R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		while self.pos == self.segment.len() {
+			if self.done {
+				return Ok(0);
+			}
+			self.next_segment()?;
+		}
+		let n = cmp::min(buf.len(), self.segment.len() - self.pos);
+		buf[..n].copy_from_slice(&self.segment[self.pos..self.pos + n]);
+		self.pos += n;
+		Ok(n)
+	}
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), Error> {
+	for entry in fs::read_dir(dir).context(ErrorKind::IO)? {
+		let path = entry.context(ErrorKind::IO)?.path();
+		if path.is_dir() {
+			collect_files(&path, files)?;
+		} else if !path
+			.file_name()
+			.map_or(false, |n| EXCLUDED_FILES.iter().any(|e| n == *e))
+		{
+			files.push(path);
+		}
+	}
+	Ok(())
+}
+
+fn corrupted() -> Error {
+	ErrorKind::GenericError("Corrupted backup archive".to_owned()).into()
+}
+
+fn unreadable(e: io::Error) -> Error {
+	ErrorKind::GenericError(format!("Unreadable backup: {}", e)).into()
+}
+
+fn write_len(out: &mut dyn Write, len: u64) -> Result<(), Error> {
+	out.write_all(&len.to_be_bytes()).context(ErrorKind::IO)?;
+	Ok(())
+}
+
+/// Read a length of an archive, none at its end
+fn read_len(input: &mut dyn Read) -> Result<Option<u64>, Error> {
+	let mut len = [0; 8];
+	let mut read = 0;
+	while read < len.len() {
+		match input.read(&mut len[read..]) {
+			Ok(0) if read == 0 => return Ok(None),
+			Ok(0) => return Err(corrupted()),
+			Ok(n) => read += n,
+			Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+			Err(e) => return Err(unreadable(e)),
+		}
+	}
+	Ok(Some(u64::from_be_bytes(len)))
+}
+
+/// Archive the files under `dir` into `out`, each as its length-prefixed path
+/// relative to `dir` followed by its length-prefixed content, copied from the
+/// file as it's written. Returns the number of files.
+fn archive_dir(dir: &Path, out: &mut dyn Write) -> Result<usize, Error> {
+	let mut files = vec![];
+	collect_files(dir, &mut files)?;
+	files.sort();
+	for f in files.iter() {
+		let path = f
+			.strip_prefix(dir)
+			.unwrap()
+			.components()
+			.map(|c| c.as_os_str().to_string_lossy().into_owned())
+			.collect::<Vec<_>>()
+			.join("/");
+		let file = File::open(f).context(ErrorKind::IO)?;
+		let size = file.metadata().context(ErrorKind::IO)?.len();
+		write_len(out, path.len() as u64)?;
+		out.write_all(path.as_bytes()).context(ErrorKind::IO)?;
+		write_len(out, size)?;
+		if io::copy(&mut file.take(size), &mut *out).context(ErrorKind::IO)? != size {
+			return Err(ErrorKind::GenericError(format!(
+				"{} changed while being backed up",
+				path
+			)))?;
+		}
+	}
+	Ok(files.len())
+}
+
+/// Go through the files of an archive made by `archive_dir`, handing each one's
+/// relative path and content to `f`, which reads as much of the content as it
+/// needs. Returns the number of files.
+fn unarchive<F>(input: &mut dyn Read, mut f: F) -> Result<usize, Error>
+where
+	F: FnMut(&str, &mut dyn Read) -> Result<(), Error>,
+{
+	let mut count = 0;
+	while let Some(path_len) = read_len(input)? {
+		if path_len == 0 || path_len > MAX_PATH_LEN {
+			return Err(corrupted());
+		}
+		let mut path = vec![0; path_len as usize];
+		input.read_exact(&mut path).map_err(unreadable)?;
+		let path = String::from_utf8(path)
+			.map_err(|_| ErrorKind::GenericError("Invalid path in backup archive".to_owned()))?;
+		// Paths come from remote storage, so they mustn't lead out of the directory
+		let escapes = Path::new(&path).components().any(|c| match c {
+			Component::Normal(_) => false,
+			_ => true,
+		});
+		if escapes {
+			return Err(ErrorKind::GenericError(format!(
+				"Invalid path in backup archive: {}",
+				path
+			)))?;
+		}
+		let size = read_len(input)?.ok_or_else(corrupted)?;
+		let mut content = (&mut *input).take(size);
+		f(&path, &mut content)?;
+		// What wasn't read is skipped, still checked as it's decrypted
+		io::copy(&mut content, &mut io::sink()).map_err(unreadable)?;
+		if content.limit() > 0 {
+			return Err(corrupted());
+		}
+		count += 1;
+	}
+	Ok(count)
+}
+
+/// Fetch a backup into a temporary file
+fn fetch_backup(target: &dyn BackupTarget, name: &str) -> Result<TempFile, Error> {
+	let tmp = TempFile::new();
+	let mut out = BufWriter::new(File::create(&tmp.0).context(ErrorKind::IO)?);
+	target.get(name, &mut out)?;
+	out.flush().context(ErrorKind::IO)?;
+	Ok(tmp)
+}
+
+/// Archive of a fetched backup, decrypted as it's read
+fn open_archive(
+	backup: &TempFile,
+	key: &[u8; 32],
+) -> Result<DecryptReader<BufReader<File>>, Error> {
+	let input = BufReader::new(File::open(&backup.0).context(ErrorKind::IO)?);
+	DecryptReader::new(input, key)
+}
+
+/// Names of the backups stored under `name_prefix`, oldest first
+fn list_named_backups(target: &dyn BackupTarget, name_prefix: &str) -> Result<Vec<String>, Error> {
+	let mut names: Vec<String> = target
+		.list(name_prefix)?
+		.into_iter()
+		.filter(|n| n.starts_with(name_prefix) && n.ends_with(BACKUP_EXT))
+		.collect();
+	// Names end with the time of the backup, so they sort in time order
+	names.sort();
+	Ok(names)
+}
+
+/// Names of the backups of the wallet stored under `prefix`, oldest first.
+/// Backups of other wallets under the same prefix aren't listed.
+pub fn list_backups<K>(
+	target: &dyn BackupTarget,
+	prefix: &str,
+	keychain: &K,
+) -> Result<Vec<String>, Error>
+where
+	K: Keychain,
+{
+	let key = backup_key(keychain)?;
+	list_named_backups(target, &backup_name_prefix(prefix, &key))
+}
+
+/// Encrypted archive of the wallet directory, held in a temporary file until it's
+/// pushed. It's made while the wallet is locked, so that its files are consistent,
+/// and pushed once the wallet is released.
+pub struct BackupArchive {
+	tmp: TempFile,
+	wallet_dir: PathBuf,
+	name_prefix: String,
+	name: String,
+	time: DateTime<Utc>,
+	files: usize,
+	size: u64,
+	sha256: String,
+}
+
+/// Archive the wallet directory and encrypt it into a temporary file, to be
+/// pushed under a name given by `prefix`
+pub fn archive_backup<K>(
+	prefix: &str,
+	wallet_dir: &Path,
+	keychain: &K,
+) -> Result<BackupArchive, Error>
+where
+	K: Keychain,
+{
+	let key = backup_key(keychain)?;
+	let name_prefix = backup_name_prefix(prefix, &key);
+	let time = Utc::now();
+	let name = format!(
+		"{}{}{}",
+		name_prefix,
+		time.format("%Y%m%dT%H%M%S%.3fZ"),
+		BACKUP_EXT
+	);
+
+	// The archive is encrypted into a temporary file as it's made
+	let tmp = TempFile::new();
+	let mut out = HashWriter::new(BufWriter::new(File::create(&tmp.0).context(ErrorKind::IO)?));
+	let mut encrypted = EncryptWriter::new(&mut out, &key)?;
+	let files = archive_dir(wallet_dir, &mut encrypted)?;
+	encrypted.finish()?;
+	out.flush().context(ErrorKind::IO)?;
+	let (size, sha256) = (out.len, out.sha256());
+	drop(out);
+
+	Ok(BackupArchive {
+		tmp,
+		wallet_dir: wallet_dir.to_path_buf(),
+		name_prefix,
+		name,
+		time,
+		files,
+		size,
+		sha256,
+	})
+}
+
+impl BackupArchive {
+	/// Push the archive to the target. The stored copy is read back and checked
+	/// against the pushed backup, then the oldest backups of the wallet beyond
+	/// `retention` are deleted, none if it's 0.
+	pub fn push(self, target: &dyn BackupTarget, retention: usize) -> Result<BackupInfo, Error> {
+		let mut data = File::open(&self.tmp.0).context(ErrorKind::IO)?;
+		target.put(&self.name, &mut data, self.size)?;
+		let mut stored = HashWriter::new(io::sink());
+		target.get(&self.name, &mut stored)?;
+		if stored.sha256() != self.sha256 {
+			return Err(ErrorKind::GenericError(format!(
+				"Stored backup {} doesn't match the pushed one",
+				self.name
+			)))?;
+		}
+		let record = format!("{}\n{}\n", self.time.to_rfc3339(), self.name);
+		fs::write(self.wallet_dir.join(LAST_BACKUP_FILE), record).context(ErrorKind::IO)?;
+
+		let mut pruned = vec![];
+		if retention > 0 {
+			let names = list_named_backups(target, &self.name_prefix)?;
+			for n in names.iter().take(names.len().saturating_sub(retention)) {
+				target.delete(n)?;
+				pruned.push(n.clone());
+			}
+		}
+		Ok(BackupInfo {
+			name: self.name.clone(),
+			files: self.files,
+			size: self.size,
+			sha256: self.sha256.clone(),
+			pruned,
+		})
+	}
+}
+
+/// When the wallet of the directory was last backed up, if ever
+pub fn last_backup_time(wallet_dir: &Path) -> Option<DateTime<Utc>> {
+	let record = fs::read_to_string(wallet_dir.join(LAST_BACKUP_FILE)).ok()?;
+	let time = DateTime::parse_from_rfc3339(record.lines().next()?.trim()).ok()?;
+	Some(time.with_timezone(&Utc))
+}
+
+/// Time until the next backup of the wallet of the directory, backed up every
+/// `interval`. Zero if it's due.
+pub fn next_backup_in(wallet_dir: &Path, interval: Duration) -> Duration {
+	let elapsed = match last_backup_time(wallet_dir) {
+		Some(t) => Utc::now()
+			.signed_duration_since(t)
+			.to_std()
+			.unwrap_or_default(),
+		None => return Duration::from_secs(0),
+	};
+	interval.checked_sub(elapsed).unwrap_or_default()
+}
+
+/// Fetch a backup and check it decrypts to a well-formed archive, returning
+/// the paths of the wallet files it holds
+pub fn verify_backup<K>(
+	target: &dyn BackupTarget,
+	name: &str,
+	keychain: &K,
+) -> Result<Vec<String>, Error>
+where
+	K: Keychain,
+{
+	let key = backup_key(keychain)?;
+	let backup = fetch_backup(target, name)?;
+	let mut paths = vec![];
+	unarchive(&mut open_archive(&backup, &key)?, |path, _| {
+		paths.push(path.to_owned());
+		Ok(())
+	})?;
+	Ok(paths)
+}
+
+/// Restore the wallet files of a backup into `dest_dir`, which mustn't hold a
+/// wallet already. Returns the number of files written.
+pub fn restore_backup<K>(
+	target: &dyn BackupTarget,
+	name: &str,
+	keychain: &K,
+	dest_dir: &Path,
+) -> Result<usize, Error>
+where
+	K: Keychain,
+{
+	let key = backup_key(keychain)?;
+	let backup = fetch_backup(target, name)?;
+	// Nothing is written unless the whole backup reads, and no file is overwritten
+	unarchive(&mut open_archive(&backup, &key)?, |path, _| {
+		if dest_dir.join(path).exists() {
+			return Err(ErrorKind::GenericError(format!(
+				"{} already exists in {}",
+				path,
+				dest_dir.display()
+			)))?;
+		}
+		Ok(())
+	})?;
+	unarchive(&mut open_archive(&backup, &key)?, |path, content| {
+		let dest = dest_dir.join(path);
+		if let Some(parent) = dest.parent() {
+			fs::create_dir_all(parent).context(ErrorKind::IO)?;
+		}
+		let mut file = File::create(&dest).context(ErrorKind::IO)?;
+		io::copy(content, &mut file).context(ErrorKind::IO)?;
+		file.sync_all().context(ErrorKind::IO)?;
+		Ok(())
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::keychain::ExtKeychain;
+	use crate::util::Mutex;
+	use std::collections::BTreeMap;
+
+	#[derive(Default)]
+	struct MemoryTarget(Mutex<BTreeMap<String, Vec<u8>>>);
+
+	impl BackupTarget for MemoryTarget {
+		fn put(&self, name: &str, data: &mut dyn Read, size: u64) -> Result<(), Error> {
+			let mut stored = vec![];
+			data.read_to_end(&mut stored).context(ErrorKind::IO)?;
+			assert_eq!(stored.len() as u64, size);
+			self.0.lock().insert(name.to_owned(), stored);
+			Ok(())
+		}
+
+		fn get(&self, name: &str, out: &mut dyn Write) -> Result<(), Error> {
+			let objects = self.0.lock();
+			let data = objects
+				.get(name)
+				.ok_or(ErrorKind::GenericError("Not found".to_owned()))?;
+			out.write_all(data).context(ErrorKind::IO)?;
+			Ok(())
+		}
+
+		fn list(&self, prefix: &str) -> Result<Vec<String>, Error> {
+			let objects = self.0.lock();
+			Ok(objects
+				.keys()
+				.filter(|k| k.starts_with(prefix))
+				.cloned()
+				.collect())
+		}
+
+		fn delete(&self, name: &str) -> Result<(), Error> {
+			self.0.lock().remove(name);
+			Ok(())
+		}
+	}
+
+	fn put(target: &MemoryTarget, name: &str, data: &[u8]) {
+		target.0.lock().insert(name.to_owned(), data.to_vec());
+	}
+
+	#[test]
+	fn backup_push_verify_restore() {
+		let dir = env::temp_dir().join(format!("mwc_backup_{}", thread_rng().gen::<u32>()));
+		let wallet_dir = dir.join("wallet_data");
+		fs::create_dir_all(wallet_dir.join("db").join("lmdb")).unwrap();
+		fs::write(wallet_dir.join("wallet.seed"), b"seed").unwrap();
+		// spanning several encrypted segments
+		let db: Vec<u8> = (0..3 * SEGMENT_LEN + 5).map(|i| i as u8).collect();
+		fs::write(wallet_dir.join("db").join("lmdb").join("data.mdb"), &db).unwrap();
+		fs::write(wallet_dir.join("db").join("lmdb").join("lock.mdb"), &[3]).unwrap();
+
+		let keychain = ExtKeychain::from_random_seed(false).unwrap();
+		let other = ExtKeychain::from_random_seed(false).unwrap();
+		let name_prefix = backup_name_prefix("main/", &backup_key(&keychain).unwrap());
+		let other_prefix = backup_name_prefix("main/", &backup_key(&other).unwrap());
+		let target = MemoryTarget::default();
+		for time in ["20190101T000000.000Z", "20190102T000000.000Z"].iter() {
+			put(
+				&target,
+				&format!("{}{}{}", name_prefix, time, BACKUP_EXT),
+				b"old",
+			);
+			put(
+				&target,
+				&format!("{}{}{}", other_prefix, time, BACKUP_EXT),
+				b"old",
+			);
+		}
+
+		// The backup holds the wallet files as they were archived, whatever
+		// happens to them before it's pushed
+		let archive = archive_backup("main/", &wallet_dir, &keychain).unwrap();
+		fs::write(wallet_dir.join("wallet.seed"), b"changed").unwrap();
+		let info = archive.push(&target, 2).unwrap();
+		assert_eq!(info.files, 2);
+		assert_eq!(
+			info.pruned,
+			vec![format!("{}20190101T000000.000Z{}", name_prefix, BACKUP_EXT)]
+		);
+		assert_eq!(list_backups(&target, "main/", &keychain).unwrap().len(), 2);
+		// the backups of other wallets under the same prefix are left alone
+		assert_eq!(list_backups(&target, "main/", &other).unwrap().len(), 2);
+		assert!(last_backup_time(&wallet_dir).is_some());
+
+		let files = verify_backup(&target, &info.name, &keychain).unwrap();
+		assert_eq!(files, vec!["db/lmdb/data.mdb", "wallet.seed"]);
+
+		// Other wallets can't read the backup
+		assert!(verify_backup(&target, &info.name, &other).is_err());
+
+		let restore_dir = dir.join("restored");
+		assert_eq!(
+			restore_backup(&target, &info.name, &keychain, &restore_dir).unwrap(),
+			2
+		);
+		assert_eq!(fs::read(restore_dir.join("wallet.seed")).unwrap(), b"seed");
+		let restored = fs::read(restore_dir.join("db").join("lmdb").join("data.mdb")).unwrap();
+		assert!(restored == db);
+		// Existing wallet files are never overwritten
+		assert!(restore_backup(&target, &info.name, &keychain, &restore_dir).is_err());
+
+		// Tampered and truncated backups are refused
+		let data = target.0.lock().get(&info.name).unwrap().clone();
+		let mut tampered = data.clone();
+		tampered[data.len() - 1] ^= 1;
+		put(&target, &info.name, &tampered);
+		assert!(verify_backup(&target, &info.name, &keychain).is_err());
+		let sealed_len = SEGMENT_LEN + aead::CHACHA20_POLY1305.tag_len();
+		let header_len = BACKUP_MAGIC.len() + 1 + NONCE_PREFIX_LEN;
+		put(&target, &info.name, &data[..header_len + 2 * sealed_len]);
+		assert!(verify_backup(&target, &info.name, &keychain).is_err());
+		put(&target, &info.name, &data);
+		assert!(verify_backup(&target, &info.name, &keychain).is_ok());
+
+		fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn backup_schedule() {
+		let dir = env::temp_dir().join(format!("mwc_backup_{}", thread_rng().gen::<u32>()));
+		fs::create_dir_all(&dir).unwrap();
+		let day = Duration::from_secs(24 * 3600);
+		// never backed up, so it's due
+		assert_eq!(next_backup_in(&dir, day), Duration::from_secs(0));
+
+		let record = |t: DateTime<Utc>| {
+			fs::write(
+				dir.join(LAST_BACKUP_FILE),
+				format!("{}\nname\n", t.to_rfc3339()),
+			)
+			.unwrap()
+		};
+		record(Utc::now() - chrono::Duration::hours(1));
+		let next = next_backup_in(&dir, day);
+		assert!(next <= Duration::from_secs(23 * 3600));
+		assert!(next > Duration::from_secs(22 * 3600));
+		record(Utc::now() - chrono::Duration::hours(25));
+		assert_eq!(next_backup_in(&dir, day), Duration::from_secs(0));
+
+		fs::remove_dir_all(dir).unwrap();
+	}
+}
@@ -0,0 +1,280 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Backup target storing backups in a bucket of an S3-compatible object store,
+//! with requests signed with AWS Signature Version 4
+
+use chrono::Utc;
+use hyper::header::{HeaderValue, AUTHORIZATION, CONTENT_LENGTH, HOST};
+use hyper::{Body, Request, Uri};
+use ring::{digest, hmac};
+use std::io::{Read, Write};
+
+use super::BackupTarget;
+use crate::api;
+use crate::client_utils::Client;
+use crate::util;
+use crate::{Error, ErrorKind};
+
+/// Headers covered by the request signatures
+const SIGNED_HEADERS: &'static str = "host;x-amz-content-sha256;x-amz-date";
+/// Payload hash of signed requests whose body isn't hashed, so it can be
+/// streamed. The stored backups are checked once pushed.
+const UNSIGNED_PAYLOAD: &'static str = "UNSIGNED-PAYLOAD";
+
+/// Bucket of an S3-compatible object store, addressed path-style so that
+/// services other than AWS work too
+pub struct S3Target {
+	client: Client,
+	endpoint: String,
+	host: String,
+	bucket: String,
+	region: String,
+	access_key: String,
+	secret_key: String,
+}
+
+impl S3Target {
+	/// Target the bucket of the service at `endpoint`, its base URL
+	pub fn new(
+		endpoint: &str,
+		bucket: &str,
+		region: &str,
+		access_key: &str,
+		secret_key: &str,
+	) -> Result<S3Target, Error> {
+		let endpoint = endpoint.trim_end_matches('/').to_owned();
+		let host = endpoint
+			.parse::<Uri>()
+			.ok()
+			.and_then(|u| u.authority_part().map(|a| a.as_str().to_owned()))
+			.ok_or_else(|| {
+				ErrorKind::ArgumentError(format!("Invalid storage endpoint {}", endpoint))
+			})?;
+		let client = Client::new(false)
+			.map_err(|e| ErrorKind::GenericError(format!("Unable to create client: {}", e)))?;
+		Ok(S3Target {
+			client,
+			endpoint,
+			host,
+			bucket: bucket.to_owned(),
+			region: region.to_owned(),
+			access_key: access_key.to_owned(),
+			secret_key: secret_key.to_owned(),
+		})
+	}
+
+	/// Signed request for the bucket, or one of its objects, with a body of the
+	/// given SHA-256, or `UNSIGNED-PAYLOAD` for bodies streamed as they're sent
+	fn signed_request(
+		&self,
+		method: &str,
+		key: Option<&str>,
+		query: &[(&str, &str)],
+		payload_hash: &str,
+	) -> Result<Request<()>, Error> {
+		let mut path = format!("/{}", uri_encode(&self.bucket, true));
+		if let Some(k) = key {
+			path.push('/');
+			path.push_str(&uri_encode(k, false));
+		}
+		let mut query: Vec<String> = query
+			.iter()
+			.map(|(k, v)| format!("{}={}", uri_encode(k, true), uri_encode(v, true)))
+			.collect();
+		query.sort();
+		let query = query.join("&");
+
+		let now = Utc::now();
+		let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+		let date = now.format("%Y%m%d").to_string();
+		let canonical_request = format!(
+			"{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+			method, path, query, self.host, payload_hash, amz_date, SIGNED_HEADERS, payload_hash
+		);
+		let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+		let string_to_sign = format!(
+			"AWS4-HMAC-SHA256\n{}\n{}\n{}",
+			amz_date,
+			scope,
+			sha256_hex(canonical_request.as_bytes())
+		);
+		let secret = format!("AWS4{}", self.secret_key);
+		let mut signing_key = hmac_sha256(secret.as_bytes(), date.as_bytes());
+		for part in [self.region.as_str(), "s3", "aws4_request"].iter() {
+			signing_key = hmac_sha256(&signing_key, part.as_bytes());
+		}
+		let signature = util::to_hex(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+		let authorization = format!(
+			"AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+			self.access_key, scope, SIGNED_HEADERS, signature
+		);
+
+		let mut url = format!("{}{}", self.endpoint, path);
+		if !query.is_empty() {
+			url.push('?');
+			url.push_str(&query);
+		}
+		let req = Request::builder()
+			.method(method)
+			.uri(url.as_str())
+			.header(HOST, self.host.as_str())
+			.header("x-amz-content-sha256", payload_hash)
+			.header("x-amz-date", amz_date.as_str())
+			.header(AUTHORIZATION, authorization)
+			.body(())
+			.map_err(|e| ErrorKind::GenericError(format!("Bad request {}: {}", url, e)))?;
+		Ok(req)
+	}
+
+	/// Send a signed request without a body, returning the response body
+	fn request(
+		&self,
+		method: &str,
+		key: Option<&str>,
+		query: &[(&str, &str)],
+	) -> Result<Vec<u8>, Error> {
+		let req = self.signed_request(method, key, query, &sha256_hex(&[]))?;
+		let uri = req.uri().to_string();
+		self.client
+			.send_request_bytes(req.map(|_| Body::empty()))
+			.map_err(|e| request_error(method, &uri, e))
+	}
+}
+
+fn request_error(method: &str, uri: &str, e: api::Error) -> Error {
+	ErrorKind::GenericError(format!("Storage request {} {} failed: {}", method, uri, e)).into()
+}
+
+impl BackupTarget for S3Target {
+	fn put(&self, name: &str, data: &mut dyn Read, size: u64) -> Result<(), Error> {
+		let mut req = self.signed_request("PUT", Some(name), &[], UNSIGNED_PAYLOAD)?;
+		req.headers_mut()
+			.insert(CONTENT_LENGTH, HeaderValue::from(size));
+		let uri = req.uri().to_string();
+		self.client
+			.send_request_from(req, data, size)
+			.map_err(|e| request_error("PUT", &uri, e))?;
+		Ok(())
+	}
+
+	fn get(&self, name: &str, out: &mut dyn Write) -> Result<(), Error> {
+		let req = self.signed_request("GET", Some(name), &[], &sha256_hex(&[]))?;
+		let uri = req.uri().to_string();
+		self.client
+			.send_request_to(req.map(|_| Body::empty()), out)
+			.map_err(|e| request_error("GET", &uri, e))
+	}
+
+	fn list(&self, prefix: &str) -> Result<Vec<String>, Error> {
+		let mut names = vec![];
+		let mut token: Option<String> = None;
+		loop {
+			let mut query = vec![("list-type", "2"), ("prefix", prefix)];
+			if let Some(t) = token.as_ref() {
+				query.push(("continuation-token", t.as_str()));
+			}
+			let body = self.request("GET", None, &query)?;
+			let body = String::from_utf8_lossy(&body);
+			names.extend(xml_values(&body, "Key"));
+			token = match xml_values(&body, "IsTruncated").first() {
+				Some(t) if t == "true" => xml_values(&body, "NextContinuationToken").pop(),
+				_ => None,
+			};
+			if token.is_none() {
+				return Ok(names);
+			}
+		}
+	}
+
+	fn delete(&self, name: &str) -> Result<(), Error> {
+		self.request("DELETE", Some(name), &[])?;
+		Ok(())
+	}
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+	util::to_hex(digest::digest(&digest::SHA256, data).as_ref().to_vec())
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+	let key = hmac::SigningKey::new(&digest::SHA256, key);
+	hmac::sign(&key, data).as_ref().to_vec()
+}
+
+/// URI encoding of the signature's canonical request: every byte but the
+/// unreserved characters is percent-encoded, slashes only if `encode_slash`
+fn uri_encode(s: &str, encode_slash: bool) -> String {
+	let mut out = String::new();
+	for b in s.bytes() {
+		match b {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+				out.push(b as char)
+			}
+			b'/' if !encode_slash => out.push('/'),
+			_ => out.push_str(&format!("%{:02X}", b)),
+		}
+	}
+	out
+}
+
+/// Text of the elements of an XML response with the given tag, which is all the
+/// parsing the listings need
+fn xml_values(xml: &str, tag: &str) -> Vec<String> {
+	let open = format!("<{}>", tag);
+	let close = format!("</{}>", tag);
+	let mut values = vec![];
+	let mut rest = xml;
+	while let Some(start) = rest.find(&open) {
+		rest = &rest[start + open.len()..];
+		let end = match rest.find(&close) {
+			Some(e) => e,
+			None => break,
+		};
+		values.push(
+			rest[..end]
+				.replace("&lt;", "<")
+				.replace("&gt;", ">")
+				.replace("&quot;", "\"")
+				.replace("&apos;", "'")
+				.replace("&amp;", "&"),
+		);
+		rest = &rest[end + close.len()..];
+	}
+	values
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn s3_uri_encode() {
+		assert_eq!(
+			uri_encode("main/wallet-1.mwcbak", false),
+			"main/wallet-1.mwcbak"
+		);
+		assert_eq!(uri_encode("main/a b+c", true), "main%2Fa%20b%2Bc");
+	}
+
+	#[test]
+	fn s3_list_parse() {
+		let xml = "<ListBucketResult><IsTruncated>true</IsTruncated>\
+		           <Contents><Key>a&amp;b</Key></Contents><Contents><Key>c</Key></Contents>\
+		           <NextContinuationToken>t1</NextContinuationToken></ListBucketResult>";
+		assert_eq!(xml_values(xml, "Key"), vec!["a&b", "c"]);
+		assert_eq!(xml_values(xml, "IsTruncated"), vec!["true"]);
+		assert_eq!(xml_values(xml, "NextContinuationToken"), vec!["t1"]);
+	}
+}
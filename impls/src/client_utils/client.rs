@@ -0,0 +1,438 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! High level JSON/HTTP client API, keeping a single pool of connections
+//! open to the node instead of opening a new one on every call
+
+use crate::api::{Error, ErrorKind};
+use crate::core::global;
+use crate::util::{to_base64, Mutex};
+use failure::{Fail, ResultExt};
+use futures::future::{self, err, ok, Either};
+use futures::sync::oneshot;
+use futures::{Future, Stream};
+use hyper::client::HttpConnector;
+use hyper::header::{ACCEPT, AUTHORIZATION, CONTENT_TYPE, USER_AGENT};
+use hyper::{Body, Request, StatusCode, Uri};
+use hyper_rustls::HttpsConnector;
+use serde::{Deserialize, Serialize};
+use std::cmp;
+use std::io::{Read, Write};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::{Runtime, TaskExecutor};
+use tokio::timer::Timeout;
+
+/// Future returned by the async request methods
+pub type ClientResponseFuture<T> = Box<dyn Future<Item = T, Error = Error> + Send>;
+
+/// Number of threads used by the connector for DNS resolution
+const DNS_THREADS: usize = 2;
+/// How long an idle pooled connection is kept around
+const KEEP_ALIVE_TIMEOUT_SECS: u64 = 90;
+/// Maximum number of idle connections kept per host
+const MAX_IDLE_PER_HOST: usize = 8;
+/// Size of the chunks request bodies are streamed in
+const BODY_CHUNK_SIZE: usize = 64 * 1024;
+
+/// HTTP client holding a pool of keep-alive connections, along with the
+/// runtime driving them. Cloning is cheap and all clones share the pool.
+#[derive(Clone)]
+pub struct Client {
+	client: hyper::Client<HttpsConnector<HttpConnector>, Body>,
+	executor: TaskExecutor,
+	_runtime: Arc<Mutex<Runtime>>,
+}
+
+impl Client {
+	/// Create a new client. If `http2_only` is set, connections are made
+	/// with HTTP/2 only, multiplexing all requests over a single connection.
+	pub fn new(http2_only: bool) -> Result<Client, Error> {
+		let runtime =
+			Runtime::new().context(ErrorKind::Internal("can't create Tokio runtime".to_owned()))?;
+		let executor = runtime.executor();
+		let https = HttpsConnector::new(DNS_THREADS);
+		let client = hyper::Client::builder()
+			.keep_alive(true)
+			.keep_alive_timeout(Some(Duration::from_secs(KEEP_ALIVE_TIMEOUT_SECS)))
+			.max_idle_per_host(MAX_IDLE_PER_HOST)
+			.http2_only(http2_only)
+			.executor(executor.clone())
+			.build::<_, Body>(https);
+		Ok(Client {
+			client,
+			executor,
+			_runtime: Arc::new(Mutex::new(runtime)),
+		})
+	}
+
+	/// Whether both clients share the same pool of connections
+	pub fn shares_pool(&self, other: &Client) -> bool {
+		Arc::ptr_eq(&self._runtime, &other._runtime)
+	}
+
+	/// Helper function to easily issue a HTTP GET request against a given URL that
+	/// returns a JSON object. Handles request building, JSON deserialization and
+	/// response code checking.
+	pub fn get<'a, T>(
+		&self,
+		url: &'a str,
+		api_secret: Option<String>,
+		chain_type: global::ChainTypes,
+	) -> Result<T, Error>
+	where
+		for<'de> T: Deserialize<'de> + Send + 'static,
+	{
+		self.run(self.get_async(url, api_secret, chain_type))
+	}
+
+	/// Helper function to easily issue an async HTTP GET request against a given
+	/// URL that returns a future. The future is driven by the client's own runtime
+	/// once handed to `run`, or by any other runtime the caller provides.
+	pub fn get_async<'a, T>(
+		&self,
+		url: &'a str,
+		api_secret: Option<String>,
+		chain_type: global::ChainTypes,
+	) -> ClientResponseFuture<T>
+	where
+		for<'de> T: Deserialize<'de> + Send + 'static,
+	{
+		match build_request(url, "GET", api_secret, chain_type, None) {
+			Ok(req) => Box::new(self.handle_request_async(req)),
+			Err(e) => Box::new(err(e)),
+		}
+	}
+
+	/// Issue an async HTTP GET request against a given URL, returning the raw
+	/// response body, for callers parsing it themselves
+	pub fn get_bytes_async<'a>(
+		&self,
+		url: &'a str,
+		api_secret: Option<String>,
+		chain_type: global::ChainTypes,
+	) -> ClientResponseFuture<Vec<u8>> {
+		match build_request(url, "GET", api_secret, chain_type, None) {
+			Ok(req) => self.send_request_bytes_async(req),
+			Err(e) => Box::new(err(e)),
+		}
+	}
+
+	/// Helper function to easily issue a HTTP POST request with the provided JSON
+	/// object as body on a given URL that returns a JSON object. Handles request
+	/// building, JSON serialization and deserialization, and response code
+	/// checking.
+	pub fn post<'a, IN, OUT>(
+		&self,
+		url: &'a str,
+		api_secret: Option<String>,
+		input: &IN,
+		chain_type: global::ChainTypes,
+	) -> Result<OUT, Error>
+	where
+		IN: Serialize,
+		for<'de> OUT: Deserialize<'de> + Send + 'static,
+	{
+		let req = create_post_request(url, api_secret, input, chain_type)?;
+		self.run(self.handle_request_async(req))
+	}
+
+	/// Helper function to easily issue a HTTP POST request with the provided JSON
+	/// object as body on a given URL that returns nothing. Handles request
+	/// building, JSON serialization, and response code checking.
+	pub fn post_no_ret<'a, IN>(
+		&self,
+		url: &'a str,
+		api_secret: Option<String>,
+		input: &IN,
+		chain_type: global::ChainTypes,
+	) -> Result<(), Error>
+	where
+		IN: Serialize,
+	{
+		let req = create_post_request(url, api_secret, input, chain_type)?;
+		self.send_request(req)?;
+		Ok(())
+	}
+
+	/// Send a prepared request over the pooled connections, returning the
+	/// response body
+	pub fn send_request(&self, req: Request<Body>) -> Result<String, Error> {
+		self.run(self.send_request_async(req))
+	}
+
+	/// Async version of `send_request`
+	pub fn send_request_async(&self, req: Request<Body>) -> ClientResponseFuture<String> {
+		Box::new(
+			self.send_request_bytes_async(req)
+				.map(|data| String::from_utf8_lossy(&data).to_string()),
+		)
+	}
+
+	/// Send a prepared request over the pooled connections, returning the raw
+	/// response body, for binary payloads
+	pub fn send_request_bytes(&self, req: Request<Body>) -> Result<Vec<u8>, Error> {
+		self.run(self.send_request_bytes_async(req))
+	}
+
+	/// Async version of `send_request_bytes`
+	pub fn send_request_bytes_async(&self, req: Request<Body>) -> ClientResponseFuture<Vec<u8>> {
+		Box::new(
+			self.client
+				.request(req)
+				.map_err(|e| ErrorKind::RequestError(format!("Cannot make request: {}", e)).into())
+				.and_then(|resp| {
+					if resp.status() == StatusCode::NOT_FOUND {
+						Either::A(err(ErrorKind::NotFound.into()))
+					} else if !resp.status().is_success() {
+						Either::A(err(ErrorKind::RequestError(format!(
+							"Wrong response code: {} with data {:?}",
+							resp.status(),
+							resp.body()
+						))
+						.into()))
+					} else {
+						Either::B(
+							resp.into_body()
+								.map_err(|e| {
+									ErrorKind::RequestError(format!(
+										"Cannot read response body: {}",
+										e
+									))
+									.into()
+								})
+								.concat2()
+								.and_then(|ch| ok(ch.to_vec())),
+						)
+					}
+				}),
+		)
+	}
+
+	/// Send a request whose `size` bytes of body are read from `data` as they're
+	/// sent, rather than held in memory, returning the raw response body
+	pub fn send_request_from(
+		&self,
+		req: Request<()>,
+		data: &mut dyn Read,
+		size: u64,
+	) -> Result<Vec<u8>, Error> {
+		let (mut sender, body) = Body::channel();
+		let req = req.map(|_| body);
+		// The request is driven by the runtime while its body is fed from here
+		let res = self.spawn(self.send_request_bytes_async(req));
+		let mut buf = vec![0; BODY_CHUNK_SIZE];
+		let mut sent = 0;
+		let fed: Result<(), Error> = loop {
+			if sent == size {
+				break Ok(());
+			}
+			let len = cmp::min(buf.len() as u64, size - sent) as usize;
+			let n = match data.read(&mut buf[..len]) {
+				Ok(0) => {
+					break Err(ErrorKind::Internal(format!(
+						"Request body ended after {} of {} bytes",
+						sent, size
+					))
+					.into())
+				}
+				Ok(n) => n,
+				Err(e) => {
+					break Err(e
+						.context(ErrorKind::Internal("Cannot read request body".to_owned()))
+						.into())
+				}
+			};
+			let ready = future::poll_fn(|| sender.poll_ready()).wait();
+			if ready.is_err() || sender.send_data(buf[..n].to_vec().into()).is_err() {
+				break Err(ErrorKind::RequestError("Connection closed".to_owned()).into());
+			}
+			sent += n as u64;
+		};
+		drop(sender);
+		// An error of the response tells more than the body not being sent
+		let res = res
+			.wait()
+			.map_err(|e| e.context(ErrorKind::Internal("Request task was dropped".to_owned())))?;
+		fed?;
+		res
+	}
+
+	/// Send a prepared request, writing the response body to `out` as it comes
+	/// rather than holding it in memory
+	pub fn send_request_to(&self, req: Request<Body>, out: &mut dyn Write) -> Result<(), Error> {
+		let resp = self.run(self.client.request(req).map_err(|e| {
+			Error::from(ErrorKind::RequestError(format!(
+				"Cannot make request: {}",
+				e
+			)))
+		}))?;
+		if resp.status() == StatusCode::NOT_FOUND {
+			return Err(ErrorKind::NotFound.into());
+		}
+		if !resp.status().is_success() {
+			return Err(
+				ErrorKind::RequestError(format!("Wrong response code: {}", resp.status())).into(),
+			);
+		}
+		// The connection is driven by the runtime, the body is read from here
+		for chunk in resp.into_body().wait() {
+			let chunk = chunk.map_err(|e| {
+				ErrorKind::RequestError(format!("Cannot read response body: {}", e))
+			})?;
+			out.write_all(&chunk)
+				.context(ErrorKind::Internal("Cannot write response body".to_owned()))?;
+		}
+		Ok(())
+	}
+
+	/// Send a prepared request over the pooled connections, returning the status
+	/// and raw body of the response, whatever the status. A request not answered
+	/// in full within `timeout` fails.
+	pub fn send_request_status(
+		&self,
+		req: Request<Body>,
+		timeout: Option<Duration>,
+	) -> Result<(u16, Vec<u8>), Error> {
+		let res = self
+			.client
+			.request(req)
+			.map_err(|e| {
+				Error::from(ErrorKind::RequestError(format!(
+					"Cannot make request: {}",
+					e
+				)))
+			})
+			.and_then(|resp| {
+				let status = resp.status().as_u16();
+				resp.into_body()
+					.map_err(|e| {
+						ErrorKind::RequestError(format!("Cannot read response body: {}", e)).into()
+					})
+					.concat2()
+					.map(move |ch| (status, ch.to_vec()))
+			});
+		match timeout {
+			Some(t) => self.run(Timeout::new(res, t).map_err(move |e| {
+				if e.is_elapsed() {
+					ErrorKind::RequestError(format!("No reply within {} seconds", t.as_secs()))
+						.into()
+				} else {
+					e.into_inner().unwrap_or_else(|| {
+						ErrorKind::Internal("Request timer failed".to_owned()).into()
+					})
+				}
+			})),
+			None => self.run(res),
+		}
+	}
+
+	/// Drive a future to completion on the client's runtime, so the connections
+	/// it opens stay in the pool after the call returns
+	pub fn run<T, F>(&self, task: F) -> Result<T, Error>
+	where
+		F: Future<Item = T, Error = Error> + Send + 'static,
+		T: Send + 'static,
+	{
+		self.spawn(task)
+			.wait()
+			.map_err(|e| e.context(ErrorKind::Internal("Request task was dropped".to_owned())))?
+	}
+
+	/// Start driving a future on the client's runtime, its result to be waited for
+	fn spawn<T, F>(&self, task: F) -> oneshot::Receiver<Result<T, Error>>
+	where
+		F: Future<Item = T, Error = Error> + Send + 'static,
+		T: Send + 'static,
+	{
+		let (tx, rx) = oneshot::channel();
+		self.executor.spawn(task.then(move |res| {
+			let _ = tx.send(res);
+			Ok(())
+		}));
+		rx
+	}
+
+	fn handle_request_async<T>(&self, req: Request<Body>) -> ClientResponseFuture<T>
+	where
+		for<'de> T: Deserialize<'de> + Send + 'static,
+	{
+		Box::new(self.send_request_async(req).and_then(|data| {
+			serde_json::from_str(&data).map_err(|e| {
+				e.context(ErrorKind::ResponseError("Cannot parse response".to_owned()))
+					.into()
+			})
+		}))
+	}
+}
+
+/// User name expected by the node for basic auth, which differs per chain
+fn basic_auth_user(chain_type: global::ChainTypes) -> &'static str {
+	match chain_type {
+		global::ChainTypes::Mainnet => "mwcmain",
+		global::ChainTypes::Floonet => "mwcfloo",
+		_ => "mwc",
+	}
+}
+
+fn build_request<'a>(
+	url: &'a str,
+	method: &str,
+	api_secret: Option<String>,
+	chain_type: global::ChainTypes,
+	body: Option<String>,
+) -> Result<Request<Body>, Error> {
+	let uri = url.parse::<Uri>().map_err::<Error, _>(|e| {
+		e.context(ErrorKind::Argument(format!("Invalid url {}", url)))
+			.into()
+	})?;
+	let mut builder = Request::builder();
+	if let Some(api_secret) = api_secret {
+		let basic_auth = format!(
+			"Basic {}",
+			to_base64(&format!("{}:{}", basic_auth_user(chain_type), api_secret))
+		);
+		builder.header(AUTHORIZATION, basic_auth);
+	}
+
+	builder
+		.method(method)
+		.uri(uri)
+		.header(USER_AGENT, "mwc-client")
+		.header(ACCEPT, "application/json")
+		.header(CONTENT_TYPE, "application/json")
+		.body(match body {
+			None => Body::empty(),
+			Some(json) => json.into(),
+		})
+		.map_err(|e| {
+			ErrorKind::RequestError(format!("Bad request {} {}: {}", method, url, e)).into()
+		})
+}
+
+/// Build a JSON POST request for the given url and input
+pub fn create_post_request<'a, IN>(
+	url: &'a str,
+	api_secret: Option<String>,
+	input: &IN,
+	chain_type: global::ChainTypes,
+) -> Result<Request<Body>, Error>
+where
+	IN: Serialize,
+{
+	let json = serde_json::to_string(input).context(ErrorKind::Internal(
+		"Could not serialize data to JSON".to_owned(),
+	))?;
+	build_request(url, "POST", api_secret, chain_type, Some(json))
+}
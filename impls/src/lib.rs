@@ -22,6 +22,8 @@ use blake2_rfc as blake2;
 extern crate serde_derive;
 #[macro_use]
 extern crate log;
+#[macro_use]
+extern crate lazy_static;
 use grin_wallet_libwallet as libwallet;
 use grin_wallet_util::grin_api as api;
 use grin_wallet_util::grin_chain as chain;
@@ -33,20 +35,46 @@ use grin_wallet_util::grin_util as util;
 use grin_wallet_config as config;
 
 mod adapters;
+mod audit;
 mod backends;
+mod backup;
+mod client_utils;
 mod error;
 mod lifecycle;
+mod logger;
 mod node_clients;
+mod price;
 pub mod test_framework;
+mod tor;
 
 pub use crate::adapters::{
-	create_sender, HttpSlateSender, KeybaseAllChannels, KeybaseChannel, PathToSlate, SlateGetter,
-	SlatePutter, SlateReceiver, SlateSender,
+	create_sender, is_onion, HttpSlateSender, HttpTransport, KeybaseAllChannels, KeybaseChannel,
+	PathToSlate, ReceiveChallenge, ReceiveChallenges, SlateDelivery, SlateExchangeChunk,
+	SlateExchangeStatus, SlateExchanges, SlateGetter, SlatePutter, SlateReceiver, SlateSender,
+	DNS_CACHE_TTL_SECS, FOREIGN_API_KEY_HEADER, FOREIGN_API_KEY_PATH,
+	MAX_RECEIVE_CHALLENGE_DIFFICULTY, RECEIVE_CHALLENGE_HEADER, RECEIVE_CHALLENGE_PATH,
+	RECEIVE_NONCE_HEADER, SLATE_EXCHANGE_PATH,
 };
+pub use crate::audit::{AuditLog, AuditOutcome};
 pub use crate::backends::{wallet_db_exists, LMDBBackend};
+pub use crate::backup::{
+	archive_backup, last_backup_time, list_backups, next_backup_in, restore_backup, verify_backup,
+	BackupArchive, BackupInfo, BackupTarget, S3Target, LAST_BACKUP_FILE,
+};
+pub use crate::client_utils::Client;
 pub use crate::error::{Error, ErrorKind};
-pub use crate::lifecycle::DefaultLCProvider;
-pub use crate::node_clients::HTTPNodeClient;
+pub use crate::lifecycle::{
+	available_space, dir_size, DefaultLCProvider, SeedKdfParams, WalletDirLock, ARGON2ID,
+	DEFAULT_SEED_KDF_ARGON2_PASSES, DEFAULT_SEED_KDF_ITERATIONS, DEFAULT_SEED_KDF_MEMORY_KIB,
+	PBKDF2_SHA512, WALLET_LOCK_FILE,
+};
+pub use crate::logger::{init_logger, set_log_level};
+pub use crate::node_clients::{
+	HTTPNodeClient, NodeCallStats, RequestThrottle, DEFAULT_OUTPUTS_QUERY_PARALLELISM,
+	DEFAULT_OUTPUTS_QUERY_RETRIES,
+};
+pub use crate::price::HttpPriceProvider;
+pub use crate::tor::{onion_url, TorProcess, DEFAULT_TOR_SOCKS_ADDR, TOR_DIR};
 
 use crate::keychain::{ExtKeychain, Keychain};
 
@@ -19,8 +19,13 @@ use crate::config::{
 };
 use crate::core::global;
 use crate::keychain::Keychain;
-use crate::libwallet::{Error, ErrorKind, NodeClient, WalletBackend, WalletLCProvider};
-use crate::lifecycle::seed::WalletSeed;
+use crate::libwallet::api_impl::owner;
+use crate::libwallet::{
+	self, Error, ErrorKind, NodeClient, PendingOperations, PriceSource, WalletBackend,
+	WalletLCProvider,
+};
+use crate::lifecycle::dir_lock::WalletDirLock;
+use crate::lifecycle::seed::{self, SeedKdfParams, WalletSeed};
 use crate::util::secp::key::SecretKey;
 use crate::util::ZeroingString;
 use crate::LMDBBackend;
@@ -28,6 +33,7 @@ use failure::ResultExt;
 use grin_wallet_util::grin_util::LoggingConfig;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 pub struct DefaultLCProvider<'a, C, K>
 where
@@ -37,6 +43,15 @@ where
 	data_dir: String,
 	node_client: C,
 	backend: Option<Box<dyn WalletBackend<'a, C, K> + 'a>>,
+	read_only: bool,
+	/// Lock of the data directory while the wallet is open for writing
+	dir_lock: Option<WalletDirLock>,
+	/// Configuration the wallets opened run with
+	config: WalletConfig,
+	/// Price provider the wallets opened value their coins with
+	price_source: Option<Arc<PriceSource>>,
+	/// Operations in progress on the wallet
+	pending_operations: Arc<PendingOperations>,
 }
 
 impl<'a, C, K> DefaultLCProvider<'a, C, K>
@@ -50,8 +65,21 @@ where
 			node_client,
 			data_dir: "default".to_owned(),
 			backend: None,
+			read_only: false,
+			dir_lock: None,
+			config: WalletConfig::default(),
+			price_source: None,
+			pending_operations: Arc::new(PendingOperations::default()),
 		}
 	}
+
+	/// Key derivation parameters the wallet is configured to encrypt its seed with
+	fn seed_kdf(&self) -> Result<SeedKdfParams, Error> {
+		SeedKdfParams::from_config(&self.config).map_err(|e| {
+			let msg = format!("Invalid seed key derivation settings: {}", e);
+			ErrorKind::Lifecycle(msg).into()
+		})
+	}
 }
 
 impl<'a, C, K> WalletLCProvider<'a, C, K> for DefaultLCProvider<'a, C, K>
@@ -68,6 +96,32 @@ where
 		Ok(self.data_dir.to_owned())
 	}
 
+	fn set_read_only(&mut self, read_only: bool) {
+		self.read_only = read_only;
+	}
+
+	fn set_wallet_config(&mut self, config: WalletConfig) {
+		if let Some(b) = self.backend.as_mut() {
+			b.set_config(config.clone());
+		}
+		self.config = config;
+	}
+
+	fn wallet_config(&self) -> &WalletConfig {
+		&self.config
+	}
+
+	fn set_price_source(&mut self, source: Option<Arc<PriceSource>>) {
+		if let Some(b) = self.backend.as_mut() {
+			b.set_price_source(source.clone());
+		}
+		self.price_source = source;
+	}
+
+	fn pending_operations(&self) -> Arc<PendingOperations> {
+		self.pending_operations.clone()
+	}
+
 	fn create_config(
 		&self,
 		chain_type: &global::ChainTypes,
@@ -83,15 +137,20 @@ where
 				None => None,
 			},
 		};
-		let wallet = match wallet_config {
+		let mut wallet = match wallet_config {
 			Some(w) => w,
 			None => match default_config.members {
 				Some(m) => m.wallet,
 				None => WalletConfig::default(),
 			},
 		};
+		wallet.apply_custom_chain();
 		default_config = GlobalWalletConfig {
-			members: Some(GlobalWalletConfigMembers { wallet, logging }),
+			members: Some(GlobalWalletConfigMembers {
+				wallet,
+				logging,
+				audit_log: None,
+			}),
 			..default_config
 		};
 		let mut config_file_name = PathBuf::from(self.data_dir.clone());
@@ -169,7 +228,9 @@ where
 				return Err(ErrorKind::WalletSeedExists(msg))?;
 			}
 		}
-		let _ = WalletSeed::init_file(&data_dir_name, mnemonic_length, mnemonic, password);
+		let kdf = self.seed_kdf()?;
+		let _ = WalletSeed::init_file(&data_dir_name, mnemonic_length, mnemonic, password, &kdf);
+		let _lock = WalletDirLock::acquire(&data_dir_name)?;
 		info!("Wallet seed file created");
 		let _wallet: LMDBBackend<'a, C, K> =
 			match LMDBBackend::new(&data_dir_name, self.node_client.clone()) {
@@ -184,32 +245,131 @@ where
 		Ok(())
 	}
 
-	fn open_wallet(
+	fn create_watch_only_wallet(
 		&mut self,
 		_name: Option<&str>,
+		root_public_key: &str,
 		password: ZeroingString,
-		create_mask: bool,
-		use_test_rng: bool,
-	) -> Result<Option<SecretKey>, Error> {
+	) -> Result<(), Error> {
 		let mut data_dir_name = PathBuf::from(self.data_dir.clone());
 		data_dir_name.push(GRIN_WALLET_DIR);
 		let data_dir_name = data_dir_name.to_str().unwrap();
-		let mut wallet: LMDBBackend<'a, C, K> =
+		if let Ok(true) = WalletSeed::seed_file_exists(&data_dir_name) {
+			let msg = format!("Wallet seed already exists at: {}", data_dir_name);
+			return Err(ErrorKind::WalletSeedExists(msg))?;
+		}
+		if seed::watch_only_file_exists(&data_dir_name) {
+			let msg = format!("Watch-only wallet already exists at: {}", data_dir_name);
+			return Err(ErrorKind::WalletSeedExists(msg))?;
+		}
+		let key = libwallet::WatchOnlyKey::from_hex(root_public_key)?;
+		let kdf = self.seed_kdf()?;
+		seed::write_watch_only_file(&data_dir_name, &key, password, &kdf).context(
+			ErrorKind::Lifecycle("Error creating watch-only wallet file".into()),
+		)?;
+		let _lock = WalletDirLock::acquire(&data_dir_name)?;
+		let _wallet: LMDBBackend<'a, C, K> =
 			match LMDBBackend::new(&data_dir_name, self.node_client.clone()) {
 				Err(e) => {
-					let msg = format!("Error opening wallet: {}, Data Dir: {}", e, &data_dir_name);
+					let msg = format!("Error creating wallet: {}, Data Dir: {}", e, &data_dir_name);
+					error!("{}", msg);
 					return Err(ErrorKind::Lifecycle(msg).into());
 				}
 				Ok(d) => d,
 			};
-		let wallet_seed = WalletSeed::from_file(&data_dir_name, password)
-			.context(ErrorKind::Lifecycle("Error opening wallet".into()))?;
-		let keychain = wallet_seed
-			.derive_keychain(global::is_floonet())
-			.context(ErrorKind::Lifecycle("Error deriving keychain".into()))?;
+		info!(
+			"Watch-only wallet of seed fingerprint {} created at {}",
+			key.fingerprint()?,
+			data_dir_name
+		);
+		Ok(())
+	}
+
+	fn open_wallet(
+		&mut self,
+		_name: Option<&str>,
+		password: ZeroingString,
+		create_mask: bool,
+		use_test_rng: bool,
+	) -> Result<Option<SecretKey>, Error> {
+		let mut data_dir_name = PathBuf::from(self.data_dir.clone());
+		data_dir_name.push(GRIN_WALLET_DIR);
+		let data_dir_name = data_dir_name.to_str().unwrap();
+		// Readers don't get in the way of the process writing to the wallet
+		let dir_lock = match self.read_only || self.dir_lock.is_some() {
+			true => None,
+			false => Some(WalletDirLock::acquire(&data_dir_name)?),
+		};
+		let backend = match self.read_only {
+			true => LMDBBackend::new_read_only(&data_dir_name, self.node_client.clone()),
+			false => LMDBBackend::new(&data_dir_name, self.node_client.clone()),
+		};
+		let mut wallet: LMDBBackend<'a, C, K> = match backend {
+			Err(e) => {
+				let msg = format!("Error opening wallet: {}, Data Dir: {}", e, &data_dir_name);
+				return Err(ErrorKind::Lifecycle(msg).into());
+			}
+			Ok(d) => d,
+		};
+		wallet.set_config(self.config.clone());
+		wallet.set_price_source(self.price_source.clone());
+		let is_floonet = self.node_client.chain_type() == global::ChainTypes::Floonet;
+		let watch_only = !WalletSeed::seed_file_exists(&data_dir_name).unwrap_or(false)
+			&& seed::watch_only_file_exists(&data_dir_name);
+		let keychain = match watch_only {
+			true => {
+				let key = seed::read_watch_only_file(&data_dir_name, password)
+					.context(ErrorKind::Lifecycle("Error opening wallet".into()))?;
+				info!(
+					"Opened watch-only wallet{} of seed fingerprint {}, Data Dir: {}",
+					if self.read_only { " read-only" } else { "" },
+					key.fingerprint()?,
+					data_dir_name
+				);
+				wallet.set_watch_only(key);
+				// A throwaway keychain for the mask and the curve, holding no key of the
+				// wallet followed
+				K::from_random_seed(is_floonet)
+					.context(ErrorKind::Lifecycle("Error creating keychain".into()))?
+			}
+			false => {
+				let wallet_seed = WalletSeed::from_file(&data_dir_name, password)
+					.context(ErrorKind::Lifecycle("Error opening wallet".into()))?;
+				let keychain = wallet_seed
+					.derive_keychain(is_floonet)
+					.context(ErrorKind::Lifecycle("Error deriving keychain".into()))?;
+				info!(
+					"Opened wallet{} with seed fingerprint {}, Data Dir: {}",
+					if self.read_only { " read-only" } else { "" },
+					libwallet::wallet_fingerprint(&keychain)?,
+					data_dir_name
+				);
+				keychain
+			}
+		};
 
 		let mask = wallet.set_keychain(Box::new(keychain), create_mask, use_test_rng)?;
+		// The outputs of a watch-only wallet can't be checked against its keys
+		if self.config.verify_outputs_on_open.unwrap_or(false) && !watch_only {
+			let issues = owner::verify_outputs(&mut wallet, mask.as_ref())?;
+			for i in issues.iter() {
+				warn!(
+					"Corrupt wallet output {}: {}",
+					i.output.key_id.to_bip_32_string(),
+					i.issue
+				);
+			}
+			if !issues.is_empty() {
+				warn!(
+					"{} wallet outputs are corrupt, spending them will fail",
+					issues.len()
+				);
+			}
+		}
 		self.backend = Some(Box::new(wallet));
+		if dir_lock.is_some() {
+			self.dir_lock = dir_lock;
+		}
 		Ok(mask)
 	}
 
@@ -219,6 +379,7 @@ where
 			None => {}
 		};
 		self.backend = None;
+		self.dir_lock = None;
 		Ok(())
 	}
 
@@ -229,7 +390,7 @@ where
 		let res = WalletSeed::seed_file_exists(&data_dir_name).context(ErrorKind::CallbackImpl(
 			"Error checking for wallet existence",
 		))?;
-		Ok(res)
+		Ok(res || seed::watch_only_file_exists(&data_dir_name))
 	}
 
 	fn get_mnemonic(
@@ -264,14 +425,32 @@ where
 		let mut data_dir_name = PathBuf::from(self.data_dir.clone());
 		data_dir_name.push(GRIN_WALLET_DIR);
 		let data_dir_name = data_dir_name.to_str().unwrap();
-		WalletSeed::recover_from_phrase(data_dir_name, mnemonic, password).context(
+		let kdf = self.seed_kdf()?;
+		WalletSeed::recover_from_phrase(data_dir_name, mnemonic, password, &kdf).context(
 			ErrorKind::Lifecycle("Error recovering from mnemonic".into()),
 		)?;
 		Ok(())
 	}
 
-	fn change_password(&self, _old: String, _new: String) -> Result<(), Error> {
-		unimplemented!()
+	fn change_password(
+		&self,
+		_name: Option<&str>,
+		old: ZeroingString,
+		new: ZeroingString,
+	) -> Result<(), Error> {
+		let mut data_dir_name = PathBuf::from(self.data_dir.clone());
+		data_dir_name.push(GRIN_WALLET_DIR);
+		let data_dir_name = data_dir_name.to_str().unwrap();
+		// Another process may be using the seed file
+		let _lock = match self.dir_lock.is_some() {
+			true => None,
+			false => Some(WalletDirLock::acquire(&data_dir_name)?),
+		};
+		let kdf = self.seed_kdf()?;
+		WalletSeed::change_password(&data_dir_name, old, new, &kdf).context(
+			ErrorKind::Lifecycle("Error changing wallet password".into()),
+		)?;
+		Ok(())
 	}
 
 	fn delete_wallet(&self, _name: Option<String>, _password: String) -> Result<(), Error> {
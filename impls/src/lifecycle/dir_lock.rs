@@ -0,0 +1,118 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lock of the wallet data directory, so that a single wallet instance writes to
+//! it. The lock is an exclusive lock of the operating system on the lock file,
+//! held as long as the file is open: it's released when the process exits, however
+//! it exits, and there's no stale lock to take over. The file holds the id of the
+//! process last owning it, to tell who's using the wallet.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::process;
+
+use chrono::Utc;
+use fs2::FileExt;
+
+use crate::libwallet::{Error, ErrorKind};
+
+/// Name of the lock file in the wallet data directory
+pub const WALLET_LOCK_FILE: &str = "wallet.lock";
+
+/// Lock of a wallet data directory, released when dropped
+pub struct WalletDirLock {
+	file: File,
+}
+
+impl WalletDirLock {
+	/// Lock the wallet data directory, failing with `WalletInUse` if another wallet
+	/// instance holds it, in this process or another
+	pub fn acquire(data_dir: &str) -> Result<WalletDirLock, Error> {
+		let path = Path::new(data_dir).join(WALLET_LOCK_FILE);
+		let mut file = OpenOptions::new()
+			.read(true)
+			.write(true)
+			.create(true)
+			.open(&path)?;
+		if let Err(e) = file.try_lock_exclusive() {
+			if e.raw_os_error() != fs2::lock_contended_error().raw_os_error() {
+				return Err(e.into());
+			}
+			let owner = match lock_owner(&path) {
+				Some(pid) => format!("process {}", pid),
+				None => "another process".to_owned(),
+			};
+			return Err(ErrorKind::WalletInUse(format!(
+				"{} is locked by {}",
+				data_dir, owner
+			)))?;
+		}
+		file.set_len(0)?;
+		let content = format!("{}\n{}\n", process::id(), Utc::now().to_rfc3339());
+		file.write_all(content.as_bytes())?;
+		file.sync_all()?;
+		Ok(WalletDirLock { file })
+	}
+}
+
+impl Drop for WalletDirLock {
+	fn drop(&mut self) {
+		// The file is left in place, removing it would let another process lock
+		// a new file while a third still has the old one open
+		if let Err(e) = self.file.unlock() {
+			error!("Unable to release wallet lock: {}", e);
+		}
+	}
+}
+
+/// Id of the process last owning the lock file, if it can be read
+fn lock_owner(path: &Path) -> Option<u32> {
+	let content = fs::read_to_string(path).ok()?;
+	content.lines().next()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn wallet_dir_lock() {
+		let dir = "target/test_output/wallet_dir_lock";
+		let _ = fs::remove_dir_all(dir);
+		fs::create_dir_all(dir).unwrap();
+		let path = Path::new(dir).join(WALLET_LOCK_FILE);
+
+		// A lock file left behind by a process no longer running doesn't lock
+		fs::write(&path, "4294967295\n").unwrap();
+		{
+			let _lock = WalletDirLock::acquire(dir).unwrap();
+			assert_eq!(lock_owner(&path), Some(process::id()));
+
+			// A single instance holds the lock, in this process too
+			match WalletDirLock::acquire(dir) {
+				Err(e) => match e.kind() {
+					ErrorKind::WalletInUse(m) => assert!(m.contains(&process::id().to_string())),
+					k => panic!("unexpected error {}", k),
+				},
+				Ok(_) => panic!("lock held twice"),
+			}
+			assert_eq!(lock_owner(&path), Some(process::id()));
+		}
+
+		// Released when dropped
+		assert!(WalletDirLock::acquire(dir).is_ok());
+		let _ = fs::remove_dir_all(dir);
+	}
+}
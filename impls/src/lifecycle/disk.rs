@@ -0,0 +1,59 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Disk usage of the wallet data directory
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Total size of the files in a directory and its subdirectories, in bytes
+pub fn dir_size(dir: &Path) -> io::Result<u64> {
+	let mut size = 0;
+	for entry in fs::read_dir(dir)? {
+		let entry = entry?;
+		let meta = entry.metadata()?;
+		size += match meta.is_dir() {
+			true => dir_size(&entry.path())?,
+			false => meta.len(),
+		};
+	}
+	Ok(size)
+}
+
+/// Space left on the disk holding a path, in bytes, if it can be told
+#[cfg(unix)]
+pub fn available_space(path: &Path) -> Option<u64> {
+	let output = Command::new("df")
+		.arg("-Pk")
+		.arg(path)
+		.stderr(Stdio::null())
+		.output()
+		.ok()?;
+	if !output.status.success() {
+		return None;
+	}
+	// Filesystem, 1024-blocks, Used, Available, Capacity, Mounted on
+	let stdout = String::from_utf8_lossy(&output.stdout);
+	let line = stdout.lines().nth(1)?;
+	let kb: u64 = line.split_whitespace().nth(3)?.parse().ok()?;
+	Some(kb * 1024)
+}
+
+/// Space left on the disk holding a path, in bytes, if it can be told
+#[cfg(windows)]
+pub fn available_space(_path: &Path) -> Option<u64> {
+	None
+}
@@ -13,6 +13,14 @@
 // limitations under the License.
 
 mod default;
+mod dir_lock;
+mod disk;
 mod seed;
 
 pub use self::default::DefaultLCProvider;
+pub use self::dir_lock::{WalletDirLock, WALLET_LOCK_FILE};
+pub use self::disk::{available_space, dir_size};
+pub use self::seed::{
+	SeedKdfParams, ARGON2ID, DEFAULT_SEED_KDF_ARGON2_PASSES, DEFAULT_SEED_KDF_ITERATIONS,
+	DEFAULT_SEED_KDF_MEMORY_KIB, PBKDF2_SHA512,
+};
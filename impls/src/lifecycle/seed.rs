@@ -24,13 +24,50 @@ use serde_json;
 use ring::aead;
 use ring::{digest, pbkdf2};
 
-use crate::keychain::{mnemonic, Keychain};
+use crate::config::WalletConfig;
+use crate::keychain::{mnemonic, ExtKeychain, Keychain};
+use crate::libwallet::{self, WatchOnlyKey};
 use crate::util;
 use crate::{Error, ErrorKind};
 use failure::ResultExt;
 
 pub const SEED_FILE: &'static str = "wallet.seed";
 
+/// File of a watch-only wallet, holding the encrypted root public key of the wallet
+/// it follows in place of a seed
+pub const WATCH_ONLY_FILE: &str = "wallet.watch";
+
+/// Key derivation function of the seed files written before it was recorded in them
+pub const LEGACY_SEED_KDF_ITERATIONS: u32 = 100;
+
+/// Number of PBKDF2 iterations seeds are encrypted with, unless configured otherwise
+pub const DEFAULT_SEED_KDF_ITERATIONS: u32 = 100_000;
+
+/// Number of Argon2id passes seeds are encrypted with, unless configured otherwise
+pub const DEFAULT_SEED_KDF_ARGON2_PASSES: u32 = 3;
+
+/// Memory Argon2id uses, in KiB, unless configured otherwise
+pub const DEFAULT_SEED_KDF_MEMORY_KIB: u32 = 65_536;
+
+/// Most PBKDF2 iterations accepted, so a seed file can't make opening the wallet
+/// run for hours
+const MAX_PBKDF2_ITERATIONS: u32 = 10_000_000;
+
+/// Most Argon2id passes accepted
+const MAX_ARGON2_PASSES: u32 = 100;
+
+/// Most memory Argon2id is allowed to use, in KiB
+const MAX_ARGON2_MEMORY_KIB: u32 = 4_194_304;
+
+/// Least memory Argon2id can use with a single lane, in KiB
+const MIN_ARGON2_MEMORY_KIB: u32 = 8;
+
+/// PBKDF2 with SHA-512
+pub const PBKDF2_SHA512: &str = "pbkdf2_sha512";
+
+/// Argon2id, version 1.3, with a single lane
+pub const ARGON2ID: &str = "argon2id";
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct WalletSeed(Vec<u8>);
 
@@ -121,6 +158,7 @@ impl WalletSeed {
 		data_file_dir: &str,
 		word_list: util::ZeroingString,
 		password: util::ZeroingString,
+		kdf: &SeedKdfParams,
 	) -> Result<(), Error> {
 		let seed_file_path = &format!("{}{}{}", data_file_dir, MAIN_SEPARATOR, SEED_FILE,);
 		debug!("data file dir: {}", data_file_dir);
@@ -136,11 +174,8 @@ impl WalletSeed {
 			))?;
 		}
 		let seed = WalletSeed::from_mnemonic(word_list)?;
-		let enc_seed = EncryptedWalletSeed::from_seed(&seed, password)?;
-		let enc_seed_json = serde_json::to_string_pretty(&enc_seed).context(ErrorKind::Format)?;
-		let mut file = File::create(seed_file_path).context(ErrorKind::IO)?;
-		file.write_all(&enc_seed_json.as_bytes())
-			.context(ErrorKind::IO)?;
+		let enc_seed = EncryptedWalletSeed::from_seed(&seed, password, kdf)?;
+		enc_seed.write_file(seed_file_path)?;
 		warn!("Seed created from word list");
 		Ok(())
 	}
@@ -150,6 +185,7 @@ impl WalletSeed {
 		seed_length: usize,
 		recovery_phrase: Option<util::ZeroingString>,
 		password: util::ZeroingString,
+		kdf: &SeedKdfParams,
 	) -> Result<WalletSeed, Error> {
 		// create directory if it doesn't exist
 		fs::create_dir_all(data_file_dir).context(ErrorKind::IO)?;
@@ -168,11 +204,8 @@ impl WalletSeed {
 			None => WalletSeed::init_new(seed_length),
 		};
 
-		let enc_seed = EncryptedWalletSeed::from_seed(&seed, password)?;
-		let enc_seed_json = serde_json::to_string_pretty(&enc_seed).context(ErrorKind::Format)?;
-		let mut file = File::create(seed_file_path).context(ErrorKind::IO)?;
-		file.write_all(&enc_seed_json.as_bytes())
-			.context(ErrorKind::IO)?;
+		let enc_seed = EncryptedWalletSeed::from_seed(&seed, password, kdf)?;
+		enc_seed.write_file(seed_file_path)?;
 		Ok(seed)
 	}
 
@@ -189,11 +222,7 @@ impl WalletSeed {
 		debug!("Using wallet seed file at: {}", seed_file_path);
 
 		if Path::new(seed_file_path).exists() {
-			let mut file = File::open(seed_file_path).context(ErrorKind::IO)?;
-			let mut buffer = String::new();
-			file.read_to_string(&mut buffer).context(ErrorKind::IO)?;
-			let enc_seed: EncryptedWalletSeed =
-				serde_json::from_str(&buffer).context(ErrorKind::Format)?;
+			let enc_seed = EncryptedWalletSeed::read_file(seed_file_path)?;
 			let wallet_seed = enc_seed.decrypt(&password)?;
 			Ok(wallet_seed)
 		} else {
@@ -205,6 +234,183 @@ impl WalletSeed {
 			Err(ErrorKind::WalletSeedDoesntExist)?
 		}
 	}
+
+	/// Encrypt the seed file with a new password, and the key derivation parameters
+	/// `kdf`. The seed file is only replaced once the new one is written.
+	pub fn change_password(
+		data_file_dir: &str,
+		old: util::ZeroingString,
+		new: util::ZeroingString,
+		kdf: &SeedKdfParams,
+	) -> Result<(), Error> {
+		let seed_file_path = &format!("{}{}{}", data_file_dir, MAIN_SEPARATOR, SEED_FILE,);
+		if !Path::new(seed_file_path).exists() {
+			return Err(ErrorKind::WalletSeedDoesntExist)?;
+		}
+		let enc_seed = EncryptedWalletSeed::read_file(seed_file_path)?;
+		let seed = enc_seed.decrypt(&old)?;
+		let enc_seed = EncryptedWalletSeed::from_seed(&seed, new, kdf)?;
+		let tmp_file_path = format!("{}.tmp", seed_file_path);
+		enc_seed.write_file(&tmp_file_path)?;
+		fs::rename(&tmp_file_path, seed_file_path).context(ErrorKind::IO)?;
+		warn!(
+			"Wallet seed encrypted with the new password, {} iterations of {}",
+			enc_seed.kdf.iterations, enc_seed.kdf.algorithm
+		);
+		Ok(())
+	}
+}
+
+/// Whether the wallet in the directory is a watch-only one
+pub fn watch_only_file_exists(data_file_dir: &str) -> bool {
+	Path::new(data_file_dir).join(WATCH_ONLY_FILE).exists()
+}
+
+/// Write the file of a watch-only wallet, its root public key encrypted like a seed
+pub fn write_watch_only_file(
+	data_file_dir: &str,
+	key: &WatchOnlyKey,
+	password: util::ZeroingString,
+	kdf: &SeedKdfParams,
+) -> Result<(), Error> {
+	fs::create_dir_all(data_file_dir).context(ErrorKind::IO)?;
+	let path = format!("{}{}{}", data_file_dir, MAIN_SEPARATOR, WATCH_ONLY_FILE);
+	let bytes = util::from_hex(key.root_public_key.clone())
+		.map_err(|_| ErrorKind::GenericError("Invalid root public key".to_owned()))?;
+	let enc_key = EncryptedWalletSeed::encrypt(&bytes, key.fingerprint()?, password, kdf)?;
+	enc_key.write_file(&path)?;
+	warn!("Watch-only wallet file created at: {}", path);
+	Ok(())
+}
+
+/// Root public key a watch-only wallet follows, from its file
+pub fn read_watch_only_file(
+	data_file_dir: &str,
+	password: util::ZeroingString,
+) -> Result<WatchOnlyKey, Error> {
+	let path = format!("{}{}{}", data_file_dir, MAIN_SEPARATOR, WATCH_ONLY_FILE);
+	if !Path::new(&path).exists() {
+		return Err(ErrorKind::WalletSeedDoesntExist)?;
+	}
+	let enc_key = EncryptedWalletSeed::read_file(&path)?;
+	let key = enc_key.decrypt(&password)?;
+	Ok(WatchOnlyKey::from_hex(&util::to_hex(key.0.clone()))?)
+}
+
+/// Key derivation function the key encrypting a seed is derived from the password
+/// with, recorded in the seed file so that each seed is decrypted with the parameters
+/// it was encrypted with, whatever the wallet is configured with since
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SeedKdfParams {
+	/// Name of the function, `pbkdf2_sha512` or `argon2id`
+	pub algorithm: String,
+	/// Number of iterations of PBKDF2, or of passes of Argon2id
+	pub iterations: u32,
+	/// Memory Argon2id uses, in KiB
+	#[serde(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub memory_kib: Option<u32>,
+}
+
+impl SeedKdfParams {
+	/// Parameters the wallet is configured to encrypt its seed with, when it's
+	/// created or recovered, or its password changed
+	pub fn from_config(config: &WalletConfig) -> Result<SeedKdfParams, Error> {
+		let algorithm = config
+			.seed_kdf_algorithm
+			.clone()
+			.unwrap_or(PBKDF2_SHA512.to_owned());
+		let params = match algorithm.as_str() {
+			ARGON2ID => SeedKdfParams {
+				algorithm,
+				iterations: config
+					.seed_kdf_iterations
+					.unwrap_or(DEFAULT_SEED_KDF_ARGON2_PASSES),
+				memory_kib: Some(
+					config
+						.seed_kdf_memory_kib
+						.unwrap_or(DEFAULT_SEED_KDF_MEMORY_KIB),
+				),
+			},
+			_ => SeedKdfParams {
+				algorithm,
+				iterations: config
+					.seed_kdf_iterations
+					.unwrap_or(DEFAULT_SEED_KDF_ITERATIONS),
+				memory_kib: None,
+			},
+		};
+		params.validate()?;
+		Ok(params)
+	}
+
+	/// Make sure the function is supported and its parameters within bounds, so a
+	/// tampered seed file or a typo in the configuration can't make the wallet
+	/// hang or exhaust the memory when opened
+	fn validate(&self) -> Result<(), Error> {
+		let valid = match self.algorithm.as_str() {
+			PBKDF2_SHA512 => {
+				self.iterations > 0
+					&& self.iterations <= MAX_PBKDF2_ITERATIONS
+					&& self.memory_kib.is_none()
+			}
+			ARGON2ID => {
+				self.iterations > 0
+					&& self.iterations <= MAX_ARGON2_PASSES
+					&& self
+						.memory_kib
+						.map(|m| m >= MIN_ARGON2_MEMORY_KIB && m <= MAX_ARGON2_MEMORY_KIB)
+						.unwrap_or(false)
+			}
+			_ => false,
+		};
+		if !valid {
+			let msg = format!(
+				"Unsupported seed key derivation: {} with {} iterations and {:?} KiB",
+				self.algorithm, self.iterations, self.memory_kib
+			);
+			return Err(ErrorKind::GenericError(msg))?;
+		}
+		Ok(())
+	}
+
+	/// Derive the encryption key from the password
+	fn derive_key(&self, salt: &[u8], password: &[u8]) -> Result<[u8; 32], Error> {
+		self.validate()?;
+		let mut key = [0; 32];
+		match self.algorithm.as_str() {
+			ARGON2ID => {
+				let config = argon2::Config {
+					variant: argon2::Variant::Argon2id,
+					version: argon2::Version::Version13,
+					mem_cost: self.memory_kib.unwrap_or(DEFAULT_SEED_KDF_MEMORY_KIB),
+					time_cost: self.iterations,
+					lanes: 1,
+					thread_mode: argon2::ThreadMode::Sequential,
+					secret: &[],
+					ad: &[],
+					hash_length: key.len() as u32,
+				};
+				let hash = argon2::hash_raw(password, salt, &config).map_err(|e| {
+					ErrorKind::GenericError(format!("Seed key derivation failed: {}", e))
+				})?;
+				key.copy_from_slice(&hash);
+			}
+			_ => pbkdf2::derive(&digest::SHA512, self.iterations, salt, password, &mut key),
+		}
+		Ok(key)
+	}
+}
+
+impl Default for SeedKdfParams {
+	/// Parameters of the seed files that don't record them
+	fn default() -> SeedKdfParams {
+		SeedKdfParams {
+			algorithm: PBKDF2_SHA512.to_owned(),
+			iterations: LEGACY_SEED_KDF_ITERATIONS,
+			memory_kib: None,
+		}
+	}
 }
 
 /// Encrypted wallet seed, for storing on disk and decrypting
@@ -218,6 +424,14 @@ pub struct EncryptedWalletSeed {
 	pub salt: String,
 	/// Nonce
 	pub nonce: String,
+	/// Fingerprint of the seed (hash of the root public key), not sensitive,
+	/// so the seed a file or backup belongs to can be checked without the password
+	#[serde(default)]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub fingerprint: Option<String>,
+	/// Key derivation function the encryption key was derived with
+	#[serde(default)]
+	pub kdf: SeedKdfParams,
 }
 
 impl EncryptedWalletSeed {
@@ -225,14 +439,24 @@ impl EncryptedWalletSeed {
 	pub fn from_seed(
 		seed: &WalletSeed,
 		password: util::ZeroingString,
+		kdf: &SeedKdfParams,
+	) -> Result<EncryptedWalletSeed, Error> {
+		let keychain: ExtKeychain = seed.derive_keychain(false)?;
+		let fingerprint = libwallet::wallet_fingerprint(&keychain)?;
+		EncryptedWalletSeed::encrypt(&seed.0, fingerprint, password, kdf)
+	}
+
+	/// Encrypt some content with the password, the fingerprint of its seed kept in clear
+	fn encrypt(
+		content: &[u8],
+		fingerprint: String,
+		password: util::ZeroingString,
+		kdf: &SeedKdfParams,
 	) -> Result<EncryptedWalletSeed, Error> {
 		let salt: [u8; 8] = thread_rng().gen();
 		let nonce: [u8; 12] = thread_rng().gen();
-		let password = password.as_bytes();
-		let mut key = [0; 32];
-		pbkdf2::derive(&digest::SHA512, 100, &salt, password, &mut key);
-		let content = seed.0.to_vec();
-		let mut enc_bytes = content.clone();
+		let key = kdf.derive_key(&salt, password.as_bytes())?;
+		let mut enc_bytes = content.to_vec();
 		let suffix_len = aead::CHACHA20_POLY1305.tag_len();
 		for _ in 0..suffix_len {
 			enc_bytes.push(0);
@@ -245,9 +469,28 @@ impl EncryptedWalletSeed {
 			encrypted_seed: util::to_hex(enc_bytes.to_vec()),
 			salt: util::to_hex(salt.to_vec()),
 			nonce: util::to_hex(nonce.to_vec()),
+			fingerprint: Some(fingerprint),
+			kdf: kdf.clone(),
 		})
 	}
 
+	/// Read an encrypted seed from its file
+	pub fn read_file(path: &str) -> Result<EncryptedWalletSeed, Error> {
+		let mut file = File::open(path).context(ErrorKind::IO)?;
+		let mut buffer = String::new();
+		file.read_to_string(&mut buffer).context(ErrorKind::IO)?;
+		Ok(serde_json::from_str(&buffer).context(ErrorKind::Format)?)
+	}
+
+	/// Write the encrypted seed to a file
+	pub fn write_file(&self, path: &str) -> Result<(), Error> {
+		let json = serde_json::to_string_pretty(self).context(ErrorKind::Format)?;
+		let mut file = File::create(path).context(ErrorKind::IO)?;
+		file.write_all(&json.as_bytes()).context(ErrorKind::IO)?;
+		file.sync_all().context(ErrorKind::IO)?;
+		Ok(())
+	}
+
 	/// Decrypt seed
 	pub fn decrypt(&self, password: &str) -> Result<WalletSeed, Error> {
 		let mut encrypted_seed = match util::from_hex(self.encrypted_seed.clone()) {
@@ -262,9 +505,7 @@ impl EncryptedWalletSeed {
 			Ok(s) => s,
 			Err(_) => return Err(ErrorKind::Encryption)?,
 		};
-		let password = password.as_bytes();
-		let mut key = [0; 32];
-		pbkdf2::derive(&digest::SHA512, 100, &salt, password, &mut key);
+		let key = self.kdf.derive_key(&salt, password.as_bytes())?;
 
 		let opening_key =
 			aead::OpeningKey::new(&aead::CHACHA20_POLY1305, &key).context(ErrorKind::Encryption)?;
@@ -283,12 +524,28 @@ mod tests {
 	fn wallet_seed_encrypt() {
 		let password = ZeroingString::from("passwoid");
 		let wallet_seed = WalletSeed::init_new(32);
+		let kdf = SeedKdfParams::from_config(&WalletConfig::default()).unwrap();
 		let mut enc_wallet_seed =
-			EncryptedWalletSeed::from_seed(&wallet_seed, password.clone()).unwrap();
+			EncryptedWalletSeed::from_seed(&wallet_seed, password.clone(), &kdf).unwrap();
 		println!("EWS: {:?}", enc_wallet_seed);
 		let decrypted_wallet_seed = enc_wallet_seed.decrypt(&password).unwrap();
 		assert_eq!(wallet_seed, decrypted_wallet_seed);
 
+		// Fingerprint doesn't depend on the network
+		let keychain: ExtKeychain = wallet_seed.derive_keychain(true).unwrap();
+		let fingerprint = libwallet::wallet_fingerprint(&keychain).unwrap();
+		assert_eq!(enc_wallet_seed.fingerprint, Some(fingerprint));
+		assert_eq!(enc_wallet_seed.kdf, kdf);
+		assert_eq!(kdf.iterations, DEFAULT_SEED_KDF_ITERATIONS);
+
+		// Seed files from before the parameters were recorded
+		let legacy_kdf = SeedKdfParams::default();
+		let mut legacy_json = serde_json::to_value(&enc_wallet_seed).unwrap();
+		legacy_json.as_object_mut().unwrap().remove("kdf");
+		let legacy: EncryptedWalletSeed = serde_json::from_value(legacy_json).unwrap();
+		assert_eq!(legacy.kdf, legacy_kdf);
+		assert!(legacy.decrypt(&password).is_err());
+
 		// Wrong password
 		let decrypted_wallet_seed = enc_wallet_seed.decrypt("");
 		assert!(decrypted_wallet_seed.is_err());
@@ -298,4 +555,72 @@ mod tests {
 		let decrypted_wallet_seed = enc_wallet_seed.decrypt(&password);
 		assert!(decrypted_wallet_seed.is_err());
 	}
+
+	#[test]
+	fn wallet_seed_change_password() {
+		let dir = "target/test_output/wallet_seed_change_password";
+		let _ = fs::remove_dir_all(dir);
+		let old = ZeroingString::from("passwoid");
+		let new = ZeroingString::from("new_passwoid");
+		let pbkdf2 = SeedKdfParams::from_config(&WalletConfig {
+			seed_kdf_iterations: Some(1_000),
+			..WalletConfig::default()
+		})
+		.unwrap();
+		let argon2 = SeedKdfParams::from_config(&WalletConfig {
+			seed_kdf_algorithm: Some(ARGON2ID.to_owned()),
+			seed_kdf_iterations: Some(1),
+			seed_kdf_memory_kib: Some(64),
+			..WalletConfig::default()
+		})
+		.unwrap();
+		let seed = WalletSeed::init_file(dir, 32, None, old.clone(), &pbkdf2).unwrap();
+
+		assert!(WalletSeed::change_password(dir, new.clone(), new.clone(), &argon2).is_err());
+		WalletSeed::change_password(dir, old.clone(), new.clone(), &argon2).unwrap();
+		assert!(WalletSeed::from_file(dir, old).is_err());
+		assert_eq!(WalletSeed::from_file(dir, new).unwrap(), seed);
+
+		// The seed file records the parameters it's now encrypted with
+		let seed_file_path = format!("{}{}{}", dir, MAIN_SEPARATOR, SEED_FILE);
+		let enc_seed = EncryptedWalletSeed::read_file(&seed_file_path).unwrap();
+		assert_eq!(enc_seed.kdf, argon2);
+		assert_eq!(enc_seed.kdf.memory_kib, Some(64));
+		let _ = fs::remove_dir_all(dir);
+	}
+
+	#[test]
+	fn wallet_seed_kdf_limits() {
+		let config = |algorithm: &str, iterations: u32, memory_kib: Option<u32>| WalletConfig {
+			seed_kdf_algorithm: Some(algorithm.to_owned()),
+			seed_kdf_iterations: Some(iterations),
+			seed_kdf_memory_kib: memory_kib,
+			..WalletConfig::default()
+		};
+		assert!(SeedKdfParams::from_config(&config(PBKDF2_SHA512, 0, None)).is_err());
+		assert!(
+			SeedKdfParams::from_config(&config(PBKDF2_SHA512, u32::max_value(), None)).is_err()
+		);
+		assert!(SeedKdfParams::from_config(&config(ARGON2ID, 101, None)).is_err());
+		assert!(SeedKdfParams::from_config(&config(ARGON2ID, 1, Some(4))).is_err());
+		assert!(SeedKdfParams::from_config(&config("scrypt", 1, None)).is_err());
+		let argon2 = SeedKdfParams::from_config(&config(ARGON2ID, 3, None)).unwrap();
+		assert_eq!(argon2.memory_kib, Some(DEFAULT_SEED_KDF_MEMORY_KIB));
+
+		// A seed file asking for more work than allowed isn't decrypted
+		let password = ZeroingString::from("passwoid");
+		let small = SeedKdfParams::from_config(&config(ARGON2ID, 1, Some(64))).unwrap();
+		let mut enc_seed =
+			EncryptedWalletSeed::from_seed(&WalletSeed::init_new(32), password.clone(), &small)
+				.unwrap();
+		assert!(enc_seed.decrypt(&password).is_ok());
+		enc_seed.kdf.memory_kib = Some(u32::max_value());
+		assert!(enc_seed.decrypt(&password).is_err());
+		enc_seed.kdf = SeedKdfParams {
+			algorithm: PBKDF2_SHA512.to_owned(),
+			iterations: u32::max_value(),
+			memory_kib: None,
+		};
+		assert!(enc_seed.decrypt(&password).is_err());
+	}
 }
@@ -0,0 +1,280 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Logging of the wallet, whose levels can be changed per module while the
+//! wallet runs
+
+use failure::ResultExt;
+use log::{LevelFilter, Record};
+use log4rs::append::console::ConsoleAppender;
+use log4rs::append::file::FileAppender;
+use log4rs::append::rolling_file::policy::compound::roll::fixed_window::FixedWindowRoller;
+use log4rs::append::rolling_file::policy::compound::trigger::size::SizeTrigger;
+use log4rs::append::rolling_file::policy::compound::CompoundPolicy;
+use log4rs::append::rolling_file::RollingFileAppender;
+use log4rs::append::Append;
+use log4rs::config::{Appender, Config, Root};
+use log4rs::encode::pattern::PatternEncoder;
+use log4rs::filter::{Filter, Response};
+use log4rs::Handle;
+use std::collections::BTreeMap;
+
+use crate::util::{LogLevel, LoggingConfig, Mutex};
+use crate::{Error, ErrorKind};
+
+/// Pattern of the log lines
+const LOG_PATTERN: &'static str = "{d(%Y%m%d %H:%M:%S%.3f)} {h({l})} {M} - {m}{n}";
+
+/// Number of rotated log files kept if not configured
+const DEFAULT_MAX_LOG_FILES: u32 = 32;
+
+lazy_static! {
+	static ref LOGGER: Mutex<LoggerState> = Mutex::new(LoggerState {
+		running: None,
+		levels: BTreeMap::new(),
+	});
+}
+
+/// Logger, once initialized, with the levels set at runtime for some modules
+struct LoggerState {
+	running: Option<(Handle, LoggingConfig)>,
+	levels: BTreeMap<String, LevelFilter>,
+}
+
+/// Filter of the records sent to an output: those of the modules whose level
+/// was set at runtime are let through up to that level, others only if they're
+/// from the wallet's own modules and within the output's configured level
+#[derive(Debug)]
+struct LevelsFilter {
+	level: LevelFilter,
+	levels: BTreeMap<String, LevelFilter>,
+}
+
+impl Filter for LevelsFilter {
+	fn filter(&self, record: &Record) -> Response {
+		let target = record.target();
+		// The most specific module set wins
+		let set_level = self
+			.levels
+			.iter()
+			.filter(|(m, _)| target == m.as_str() || target.starts_with(&format!("{}::", m)))
+			.max_by_key(|(m, _)| m.len())
+			.map(|(_, l)| *l);
+		let allowed = match set_level {
+			Some(l) => record.level() <= l,
+			None => target.starts_with("grin") && record.level() <= self.level,
+		};
+		if allowed {
+			Response::Neutral
+		} else {
+			Response::Reject
+		}
+	}
+}
+
+fn level_filter(level: &LogLevel) -> LevelFilter {
+	match level {
+		LogLevel::Error => LevelFilter::Error,
+		LogLevel::Warning => LevelFilter::Warn,
+		LogLevel::Info => LevelFilter::Info,
+		LogLevel::Debug => LevelFilter::Debug,
+		LogLevel::Trace => LevelFilter::Trace,
+	}
+}
+
+fn file_appender(config: &LoggingConfig, append: bool) -> Result<Box<dyn Append>, Error> {
+	let encoder = Box::new(PatternEncoder::new(LOG_PATTERN));
+	let path = &config.log_file_path;
+	let appender: Box<dyn Append> = match config.log_max_size {
+		Some(size) => {
+			let roller = FixedWindowRoller::builder()
+				.build(
+					&format!("{}.{{}}.gz", path),
+					config.log_max_files.unwrap_or(DEFAULT_MAX_LOG_FILES),
+				)
+				.map_err(|e| ErrorKind::GenericError(format!("Log rotation: {}", e)))?;
+			let policy = CompoundPolicy::new(Box::new(SizeTrigger::new(size)), Box::new(roller));
+			Box::new(
+				RollingFileAppender::builder()
+					.append(append)
+					.encoder(encoder)
+					.build(path, Box::new(policy))
+					.context(ErrorKind::IO)?,
+			)
+		}
+		None => Box::new(
+			FileAppender::builder()
+				.append(append)
+				.encoder(encoder)
+				.build(path)
+				.context(ErrorKind::IO)?,
+		),
+	};
+	Ok(appender)
+}
+
+/// Build the logger configuration. A log file that's not appended to is only
+/// replaced when the logger is first initialized, not when it's reconfigured.
+fn build_config(
+	config: &LoggingConfig,
+	levels: &BTreeMap<String, LevelFilter>,
+	initial: bool,
+) -> Result<Config, Error> {
+	let mut appenders = vec![];
+	let mut outputs = vec![];
+	let mut root_level = levels.values().cloned().max().unwrap_or(LevelFilter::Off);
+	if config.log_to_stdout {
+		let level = level_filter(&config.stdout_log_level);
+		root_level = root_level.max(level);
+		let console = ConsoleAppender::builder()
+			.encoder(Box::new(PatternEncoder::new(LOG_PATTERN)))
+			.build();
+		let filter = LevelsFilter {
+			level,
+			levels: levels.clone(),
+		};
+		appenders.push(
+			Appender::builder()
+				.filter(Box::new(filter))
+				.build("stdout", Box::new(console)),
+		);
+		outputs.push("stdout");
+	}
+	if config.log_to_file {
+		let level = level_filter(&config.file_log_level);
+		root_level = root_level.max(level);
+		let append = config.log_file_append || !initial;
+		let filter = LevelsFilter {
+			level,
+			levels: levels.clone(),
+		};
+		appenders.push(
+			Appender::builder()
+				.filter(Box::new(filter))
+				.build("file", file_appender(config, append)?),
+		);
+		outputs.push("file");
+	}
+
+	let root = Root::builder().appenders(outputs).build(root_level);
+	Config::builder()
+		.appenders(appenders)
+		.build(root)
+		.map_err(|e| ErrorKind::GenericError(format!("Logging configuration: {}", e)).into())
+}
+
+/// Initialize the logging of the wallet, whose levels can then be changed with
+/// [set_log_level](fn.set_log_level.html)
+pub fn init_logger(config: Option<LoggingConfig>) -> Result<(), Error> {
+	let config = match config {
+		Some(c) => c,
+		None => return Ok(()),
+	};
+	let mut state = LOGGER.lock();
+	let handle = log4rs::init_config(build_config(&config, &state.levels, true)?)
+		.map_err(|e| ErrorKind::GenericError(format!("Logger already set: {}", e)))?;
+	state.running = Some((handle, config));
+	Ok(())
+}
+
+/// Log the records of the module `target`, e.g. `grin_wallet_impls::node_clients`,
+/// and of its submodules at `level` rather than the configured levels, or at the
+/// configured levels again if None. Other crates' modules can be logged too.
+/// Levels set before the logger is initialized apply once it is.
+pub fn set_log_level(target: &str, level: Option<LogLevel>) -> Result<(), Error> {
+	if target.is_empty() {
+		return Err(ErrorKind::ArgumentError("Empty log target".to_owned()).into());
+	}
+	let mut state = LOGGER.lock();
+	let mut levels = state.levels.clone();
+	match level {
+		Some(ref l) => levels.insert(target.to_owned(), level_filter(l)),
+		None => levels.remove(target),
+	};
+	if let Some((handle, config)) = state.running.as_ref() {
+		handle.set_config(build_config(config, &levels, false)?);
+	}
+	state.levels = levels;
+	warn!("Log level of {} set to {:?}", target, level);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use log::Level;
+
+	fn allowed(filter: &LevelsFilter, target: &str, level: Level) -> bool {
+		let record = Record::builder().target(target).level(level).build();
+		match filter.filter(&record) {
+			Response::Reject => false,
+			_ => true,
+		}
+	}
+
+	#[test]
+	fn levels_set_override_the_configured_level() {
+		let mut levels = BTreeMap::new();
+		levels.insert("grin_wallet_impls".to_owned(), LevelFilter::Warn);
+		levels.insert(
+			"grin_wallet_impls::node_clients".to_owned(),
+			LevelFilter::Trace,
+		);
+		levels.insert("hyper".to_owned(), LevelFilter::Debug);
+		let filter = LevelsFilter {
+			level: LevelFilter::Info,
+			levels,
+		};
+
+		// the most specific module set wins, for its submodules too
+		assert!(allowed(
+			&filter,
+			"grin_wallet_impls::node_clients",
+			Level::Trace
+		));
+		assert!(allowed(
+			&filter,
+			"grin_wallet_impls::node_clients::http",
+			Level::Trace
+		));
+		assert!(!allowed(
+			&filter,
+			"grin_wallet_impls::backends",
+			Level::Info
+		));
+		assert!(allowed(&filter, "grin_wallet_impls::backends", Level::Warn));
+		// a module is only matched whole
+		assert!(!allowed(&filter, "grin_wallet_impls_extra", Level::Warn));
+		// other crates are logged once set
+		assert!(allowed(&filter, "hyper::client", Level::Debug));
+		assert!(!allowed(&filter, "hyper::client", Level::Trace));
+		assert!(!allowed(&filter, "tokio", Level::Error));
+		// the wallet's other modules at the configured level
+		assert!(allowed(&filter, "grin_wallet_libwallet", Level::Info));
+		assert!(!allowed(&filter, "grin_wallet_libwallet", Level::Debug));
+	}
+
+	#[test]
+	fn levels_are_set_and_reset() {
+		assert!(set_log_level("", Some(LogLevel::Debug)).is_err());
+
+		let target = "grin_wallet_impls::logger::tests";
+		set_log_level(target, Some(LogLevel::Debug)).unwrap();
+		assert_eq!(LOGGER.lock().levels.get(target), Some(&LevelFilter::Debug));
+		set_log_level(target, Some(LogLevel::Warning)).unwrap();
+		assert_eq!(LOGGER.lock().levels.get(target), Some(&LevelFilter::Warn));
+		set_log_level(target, None).unwrap();
+		assert!(LOGGER.lock().levels.get(target).is_none());
+	}
+}
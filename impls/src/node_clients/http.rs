@@ -15,32 +15,266 @@
 //! Client functions, implementations of the NodeClient trait
 //! specific to the FileWallet
 
-use futures::{stream, Stream};
+use futures::future::{self, Loop};
+use futures::{stream, Future, Stream};
 
+use crate::client_utils::{create_post_request, Client};
 use crate::core::global;
-use crate::libwallet::{NodeClient, NodeVersionInfo, TxWrapper};
+use crate::libwallet::{
+	NodeBlockOutputs, NodeClient, NodeClientStats, NodeHeaderInfo, NodeOutputProof, NodeOutputs,
+	NodePoolEntry, NodeStatus, NodeTxFees, NodeVersionInfo, TxWrapper,
+};
+use chrono::{DateTime, Utc};
+use failure::Fail;
+use hyper::body::Payload;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use std::collections::HashMap;
-use tokio::runtime::Runtime;
+use std::time::{Duration, Instant};
+use tokio::timer::Delay;
 
 use crate::api;
 use crate::libwallet;
+use crate::node_clients::{NodeCallStats, RequestThrottle};
 use crate::util;
 use crate::util::secp::pedersen;
 
+/// Number of commitments queried per request in get_outputs_from_node
+const OUTPUTS_CHUNK_SIZE: usize = 200;
+/// Number of output query chunks between progress reports
+const OUTPUTS_PROGRESS_CHUNKS: usize = 50;
+/// Default number of output queries in flight at once
+pub const DEFAULT_OUTPUTS_QUERY_PARALLELISM: usize = 4;
+/// Default number of retries for a failed output query
+pub const DEFAULT_OUTPUTS_QUERY_RETRIES: u32 = 2;
+/// Delay before the first retry, doubled on every further attempt
+const RETRY_BACKOFF_MS: u64 = 500;
+/// Longest delay between two retries
+const MAX_RETRY_BACKOFF_MS: u64 = 30_000;
+
 #[derive(Clone)]
 pub struct HTTPNodeClient {
 	node_url: String,
 	node_api_secret: Option<String>,
 	node_version_info: Option<NodeVersionInfo>,
+	client: Client,
+	outputs_query_parallelism: usize,
+	outputs_query_retries: u32,
+	throttle: Option<RequestThrottle>,
+	stats: NodeCallStats,
+	chain_type: global::ChainTypes,
 }
 
 impl HTTPNodeClient {
-	/// Create a new client that will communicate with the given mwc node
-	pub fn new(node_url: &str, node_api_secret: Option<String>) -> HTTPNodeClient {
-		HTTPNodeClient {
+	/// Create a new client that will communicate with the given mwc node, on the
+	/// given chain. The chain type picks the basic auth user name on the node API.
+	pub fn new(
+		node_url: &str,
+		node_api_secret: Option<String>,
+		chain_type: global::ChainTypes,
+	) -> Result<HTTPNodeClient, libwallet::Error> {
+		HTTPNodeClient::new_with_http2(node_url, node_api_secret, chain_type, false)
+	}
+
+	/// Create a new client, optionally talking HTTP/2 only to the node. All clones
+	/// of the returned client share the same pool of keep-alive connections. Fails
+	/// if the runtime driving the connections can't be started.
+	pub fn new_with_http2(
+		node_url: &str,
+		node_api_secret: Option<String>,
+		chain_type: global::ChainTypes,
+		http2_only: bool,
+	) -> Result<HTTPNodeClient, libwallet::Error> {
+		let client = Client::new(http2_only).map_err(|e| {
+			libwallet::ErrorKind::ClientCallback(format!(
+				"Unable to create node HTTP client: {}",
+				e
+			))
+		})?;
+		Ok(HTTPNodeClient {
 			node_url: node_url.to_owned(),
 			node_api_secret: node_api_secret,
 			node_version_info: None,
+			client,
+			outputs_query_parallelism: DEFAULT_OUTPUTS_QUERY_PARALLELISM,
+			outputs_query_retries: DEFAULT_OUTPUTS_QUERY_RETRIES,
+			throttle: None,
+			stats: NodeCallStats::new(),
+			chain_type,
+		})
+	}
+
+	/// Set how many output query chunks may be in flight at once, and how many
+	/// times a failed chunk is retried before being reported as failed
+	pub fn set_outputs_query_limits(&mut self, parallelism: usize, retries: u32) {
+		self.outputs_query_parallelism = parallelism.max(1);
+		self.outputs_query_retries = retries;
+	}
+
+	/// Limit the requests sent to the node to `requests_per_sec` on average, allowing
+	/// bursts of `burst` requests (by default, as many as a second allows). No limit
+	/// if `requests_per_sec` is `None`.
+	pub fn set_request_rate_limit(&mut self, requests_per_sec: Option<f64>, burst: Option<u32>) {
+		self.throttle = requests_per_sec.map(|rate| {
+			let burst = burst.unwrap_or(rate.ceil() as u32);
+			RequestThrottle::new(rate, burst)
+		});
+	}
+
+	/// Wait until the rate limit, if any, allows another request
+	fn wait_for_rate_limit(&self) {
+		if let Some(t) = self.throttle.as_ref() {
+			t.wait();
+		}
+	}
+
+	/// Convert a header as returned by the node API
+	fn header_info(header: api::BlockHeaderPrintable) -> Result<NodeHeaderInfo, libwallet::Error> {
+		let timestamp = DateTime::parse_from_rfc3339(&header.timestamp).map_err(|e| {
+			libwallet::ErrorKind::ClientCallback(format!("Parsing header timestamp: {}", e))
+		})?;
+		Ok(NodeHeaderInfo {
+			height: header.height,
+			hash: header.hash,
+			timestamp: timestamp.with_timezone(&Utc),
+			output_mmr_size: header.output_mmr_size,
+		})
+	}
+
+	/// GET a node API endpoint, recording the call in the client statistics
+	fn get<T>(&self, endpoint: &'static str, url: &str) -> Result<T, api::Error>
+	where
+		for<'de> T: Deserialize<'de> + Send + 'static,
+	{
+		self.client.run(recorded_get_async(
+			&self.client,
+			self.stats.clone(),
+			endpoint,
+			url,
+			self.node_api_secret(),
+			self.chain_type(),
+		))
+	}
+
+	/// POST a JSON body to a node API endpoint, recording the call in the client
+	/// statistics, and return the raw response body
+	fn post_bytes<IN>(&self, endpoint: &str, url: &str, input: &IN) -> Result<Vec<u8>, api::Error>
+	where
+		IN: Serialize,
+	{
+		let req = create_post_request(url, self.node_api_secret(), input, self.chain_type())?;
+		let sent = req.body().content_length().unwrap_or(0);
+		let started = Instant::now();
+		let res = self.client.send_request_bytes(req);
+		let received = res.as_ref().map(|d| d.len() as u64).unwrap_or(0);
+		self.stats
+			.record(endpoint, url, started, sent, received, res.is_ok());
+		res
+	}
+
+	/// POST a JSON body to a node API endpoint and parse the JSON response,
+	/// recording the call in the client statistics
+	fn post<IN, OUT>(&self, endpoint: &str, url: &str, input: &IN) -> Result<OUT, api::Error>
+	where
+		IN: Serialize,
+		for<'de> OUT: Deserialize<'de>,
+	{
+		parse_response(&self.post_bytes(endpoint, url, input)?)
+	}
+
+	/// Query a single chunk of outputs by id, retrying with a backoff on failure
+	fn get_outputs_chunk(
+		&self,
+		url: String,
+	) -> Box<dyn Future<Item = Vec<api::Output>, Error = api::Error> + Send> {
+		let client = self.client.clone();
+		let stats = self.stats.clone();
+		let api_secret = self.node_api_secret();
+		let chain_type = self.chain_type();
+		let retries = self.outputs_query_retries;
+		let throttle = self.throttle.clone();
+		Box::new(future::loop_fn(0u32, move |attempt| {
+			let wait: Box<dyn Future<Item = (), Error = api::Error> + Send> = match throttle {
+				Some(ref t) => t.wait_async(),
+				None => Box::new(future::ok(())),
+			};
+			let (client, stats, url) = (client.clone(), stats.clone(), url.clone());
+			let (api_secret, chain_type) = (api_secret.clone(), chain_type.clone());
+			wait.and_then(move |_| {
+				recorded_get_async::<Vec<api::Output>>(
+					&client,
+					stats,
+					"/v1/chain/outputs/byids",
+					url.as_str(),
+					api_secret,
+					chain_type,
+				)
+			})
+			.then(
+				move |res| -> Box<
+					dyn Future<Item = Loop<Vec<api::Output>, u32>, Error = api::Error> + Send,
+				> {
+					match res {
+						Ok(outputs) => Box::new(future::ok(Loop::Break(outputs))),
+						Err(e) => {
+							if attempt >= retries {
+								return Box::new(future::err(e));
+							}
+							warn!(
+								"Outputs by id query failed (attempt {} of {}), retrying: {}",
+								attempt + 1,
+								retries + 1,
+								e
+							);
+							let wait = retry_backoff(attempt);
+							Box::new(
+								Delay::new(Instant::now() + wait)
+									.then(move |_| Ok(Loop::Continue(attempt + 1))),
+							)
+						}
+					}
+				},
+			)
+		}))
+	}
+
+	/// Transactions in the node's mempool, as returned by its foreign API
+	fn get_pool_txs(&self) -> Result<Vec<Value>, libwallet::Error> {
+		let url = format!("{}/v2/foreign", self.node_url());
+		let req = json!({
+			"jsonrpc": "2.0",
+			"method": "get_unconfirmed_transactions",
+			"id": 1,
+			"params": []
+		});
+		self.wait_for_rate_limit();
+		let res = self.post::<Value, Value>("/v2/foreign", url.as_str(), &req);
+		let res = match res {
+			Err(e) => {
+				let report = format!("Getting mempool entries from node: {}", e);
+				return Err(libwallet::ErrorKind::ClientCallback(report).into());
+			}
+			Ok(r) => r,
+		};
+		match res["result"]["Ok"].as_array() {
+			Some(e) => Ok(e.clone()),
+			None => {
+				let report = format!("Unexpected mempool response from node: {}", res);
+				Err(libwallet::ErrorKind::ClientCallback(report).into())
+			}
+		}
+	}
+
+	/// Fee of a kernel, whether the node reports it as a field of the kernel or
+	/// of its features
+	fn kernel_fee(kernel: &Value) -> u64 {
+		match kernel["fee"].as_u64() {
+			Some(f) => f,
+			None => kernel["features"]
+				.as_object()
+				.and_then(|f| f.values().next())
+				.and_then(|f| f["fee"].as_u64())
+				.unwrap_or(0),
 		}
 	}
 
@@ -66,31 +300,27 @@ impl NodeClient for HTTPNodeClient {
 		self.node_api_secret = node_api_secret;
 	}
 
+	/// Also picks the basic auth user name on the node API
+	fn chain_type(&self) -> global::ChainTypes {
+		self.chain_type.clone()
+	}
+
+	fn set_chain_type(&mut self, chain_type: global::ChainTypes) {
+		self.chain_type = chain_type;
+	}
+
 	fn get_version_info(&mut self) -> Option<NodeVersionInfo> {
 		if let Some(v) = self.node_version_info.as_ref() {
 			return Some(v.clone());
 		}
 		let url = format!("{}/v1/version", self.node_url());
 
-		let chain_type = if global::is_main() {
-			global::ChainTypes::Mainnet
-		} else if global::is_floo() {
-			global::ChainTypes::Floonet
-		} else {
-			global::ChainTypes::UserTesting
-		};
-
-		let mut retval = match api::client::get::<NodeVersionInfo>(
-			url.as_str(),
-			self.node_api_secret(),
-			chain_type,
-		) {
+		self.wait_for_rate_limit();
+		let mut retval = match self.get::<NodeVersionInfo>("/v1/version", url.as_str()) {
 			Ok(n) => n,
 			Err(e) => {
 				// If node isn't available, allow offline functions
-				// unfortunately have to parse string due to error structure
-				let err_string = format!("{}", e);
-				if err_string.contains("404") {
+				if is_not_found(&e) {
 					return Some(NodeVersionInfo {
 						node_version: "1.0.0".into(),
 						block_header_version: 1,
@@ -107,6 +337,28 @@ impl NodeClient for HTTPNodeClient {
 		Some(retval)
 	}
 
+	/// Return the node's peer and sync state
+	fn get_status(&self) -> Result<NodeStatus, libwallet::Error> {
+		let url = format!("{}/v1/status", self.node_url());
+
+		self.wait_for_rate_limit();
+		let res = self.get::<api::Status>("/v1/status", url.as_str());
+		match res {
+			Err(e) => {
+				let report = format!("Getting status from node: {}", e);
+				error!("Get node status error: {}", e);
+				Err(libwallet::ErrorKind::ClientCallback(report).into())
+			}
+			Ok(s) => Ok(NodeStatus {
+				protocol_version: s.protocol_version,
+				user_agent: s.user_agent,
+				connections: s.connections,
+				height: s.tip.height,
+				sync_status: s.sync_status,
+			}),
+		}
+	}
+
 	/// Posts a transaction to a mwc node
 	fn post_tx(&self, tx: &TxWrapper, fluff: bool) -> Result<(), libwallet::Error> {
 		let url;
@@ -117,15 +369,8 @@ impl NodeClient for HTTPNodeClient {
 			url = format!("{}/v1/pool/push_tx", dest);
 		}
 
-		let chain_type = if global::is_main() {
-			global::ChainTypes::Mainnet
-		} else if global::is_floo() {
-			global::ChainTypes::Floonet
-		} else {
-			global::ChainTypes::UserTesting
-		};
-
-		let res = api::client::post_no_ret(url.as_str(), self.node_api_secret(), tx, chain_type);
+		self.wait_for_rate_limit();
+		let res = self.post_bytes("/v1/pool/push_tx", url.as_str(), tx);
 		if let Err(e) = res {
 			let report = format!("Posting transaction to node: {}", e);
 			error!("Post TX Error: {}", e);
@@ -139,15 +384,8 @@ impl NodeClient for HTTPNodeClient {
 		let addr = self.node_url();
 		let url = format!("{}/v1/chain", addr);
 
-		let chain_type = if global::is_main() {
-			global::ChainTypes::Mainnet
-		} else if global::is_floo() {
-			global::ChainTypes::Floonet
-		} else {
-			global::ChainTypes::UserTesting
-		};
-
-		let res = api::client::get::<api::Tip>(url.as_str(), self.node_api_secret(), chain_type);
+		self.wait_for_rate_limit();
+		let res = self.get::<api::Tip>("/v1/chain", url.as_str());
 		match res {
 			Err(e) => {
 				let report = format!("Getting chain height from node: {}", e);
@@ -162,63 +400,279 @@ impl NodeClient for HTTPNodeClient {
 	fn get_outputs_from_node(
 		&self,
 		wallet_outputs: Vec<pedersen::Commitment>,
-	) -> Result<HashMap<pedersen::Commitment, (String, u64, u64)>, libwallet::Error> {
-		let addr = self.node_url();
-		// build the necessary query params -
-		// ?id=xxx&id=yyy&id=zzz
-		let query_params: Vec<String> = wallet_outputs
-			.iter()
-			.map(|commit| format!("id={}", util::to_hex(commit.as_ref().to_vec())))
+	) -> Result<NodeOutputs, libwallet::Error> {
+		let addr = self.node_url().to_owned();
+		let chunks: Vec<Vec<pedersen::Commitment>> = wallet_outputs
+			.chunks(OUTPUTS_CHUNK_SIZE)
+			.map(|c| c.to_vec())
 			.collect();
+		let total_chunks = chunks.len();
 
-		// build a map of api outputs by commit so we can look them up efficiently
-		let mut api_outputs: HashMap<pedersen::Commitment, (String, u64, u64)> = HashMap::new();
-		let mut tasks = Vec::new();
+		// the query of a chunk is only built once it's its turn to run, with at
+		// most outputs_query_parallelism chunks in flight at once
+		let node_client = self.clone();
+		let queries = stream::iter_ok(chunks).map(move |commits| {
+			// build the necessary query params for each chunk -
+			// ?id=xxx&id=yyy&id=zzz
+			let query_params: Vec<String> = commits
+				.iter()
+				.map(|commit| format!("id={}", util::to_hex(commit.as_ref().to_vec())))
+				.collect();
+			let url = format!("{}/v1/chain/outputs/byids?{}", addr, query_params.join("&"));
+			node_client
+				.get_outputs_chunk(url)
+				.then(move |res| Ok::<_, api::Error>((commits, res)))
+		});
 
-		for query_chunk in query_params.chunks(200) {
-			let url = format!("{}/v1/chain/outputs/byids?{}", addr, query_chunk.join("&"),);
+		// outputs are added to the map of outputs by commit as each chunk comes
+		// back, collecting the outcome of each rather than stopping at the first
+		// failure
+		let task = queries
+			.buffer_unordered(self.outputs_query_parallelism)
+			.fold(
+				(NodeOutputs::default(), 0),
+				move |(mut node_outputs, done), (commits, res)| {
+					match res {
+						Ok(outputs) => {
+							for out in outputs {
+								node_outputs.outputs.insert(
+									out.commit.commit(),
+									(util::to_hex(out.commit.to_vec()), out.height, out.mmr_index),
+								);
+							}
+						}
+						Err(e) => {
+							error!(
+								"Outputs by id failed for a chunk of {} outputs: {}",
+								commits.len(),
+								e
+							);
+							node_outputs.errors.push(format!("{}", e));
+							node_outputs.failed.extend(commits);
+						}
+					}
+					let done = done + 1;
+					if done % OUTPUTS_PROGRESS_CHUNKS == 0 {
+						debug!("Outputs by id: {} of {} chunks done", done, total_chunks);
+					}
+					Ok::<_, api::Error>((node_outputs, done))
+				},
+			);
 
-			let chain_type = if global::is_main() {
-				global::ChainTypes::Mainnet
-			} else if global::is_floo() {
-				global::ChainTypes::Floonet
-			} else {
-				global::ChainTypes::UserTesting
-			};
+		let node_outputs = match self.client.run(task) {
+			Ok((o, _)) => o,
+			Err(e) => {
+				let report = format!("Getting outputs by id: {}", e);
+				error!("Outputs by id failed: {}", e);
+				return Err(libwallet::ErrorKind::ClientCallback(report).into());
+			}
+		};
+		Ok(node_outputs)
+	}
 
-			tasks.push(api::client::get_async::<Vec<api::Output>>(
-				url.as_str(),
-				self.node_api_secret(),
-				chain_type,
-			));
+	/// Get the header of the block at the given height
+	fn get_header_by_height(&self, height: u64) -> Result<NodeHeaderInfo, libwallet::Error> {
+		let url = format!("{}/v1/headers/{}", self.node_url(), height);
+		self.wait_for_rate_limit();
+		let res = self.get::<api::BlockHeaderPrintable>("/v1/headers", url.as_str());
+		match res {
+			Err(e) => {
+				let report = format!("Getting header at height {} from node: {}", height, e);
+				error!("Get header error: {}", e);
+				Err(libwallet::ErrorKind::ClientCallback(report).into())
+			}
+			Ok(h) => HTTPNodeClient::header_info(h),
 		}
+	}
 
-		let task = stream::futures_unordered(tasks).collect();
+	/// Find the block including the given kernel
+	fn get_block_by_kernel(
+		&self,
+		excess: &pedersen::Commitment,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Option<NodeHeaderInfo>, libwallet::Error> {
+		let mut query_params = vec![];
+		if let Some(h) = min_height {
+			query_params.push(format!("min_height={}", h));
+		}
+		if let Some(h) = max_height {
+			query_params.push(format!("max_height={}", h));
+		}
+		let url = format!(
+			"{}/v1/chain/kernels/{}?{}",
+			self.node_url(),
+			util::to_hex(excess.as_ref().to_vec()),
+			query_params.join("&")
+		);
+		self.wait_for_rate_limit();
+		let res = self.get::<api::LocatedTxKernel>("/v1/chain/kernels", url.as_str());
+		match res {
+			Err(e) => {
+				// kernel not found on chain
+				if is_not_found(&e) {
+					return Ok(None);
+				}
+				let report = format!("Getting kernel from node: {}", e);
+				error!("Get kernel error: {}", e);
+				Err(libwallet::ErrorKind::ClientCallback(report).into())
+			}
+			Ok(k) => Ok(Some(self.get_header_by_height(k.height)?)),
+		}
+	}
 
-		let mut rt = Runtime::new().unwrap();
-		let results = match rt.block_on(task) {
-			Ok(outputs) => outputs,
+	/// Get the outputs created in a block, with their proofs. The header is then
+	/// looked up by hash, so that its output root is from the same block
+	fn get_block_outputs(&self, height: u64) -> Result<Option<NodeBlockOutputs>, libwallet::Error> {
+		let url = format!(
+			"{}/v1/chain/outputs/byheight?start_height={}&end_height={}&include_rp",
+			self.node_url(),
+			height,
+			height
+		);
+		self.wait_for_rate_limit();
+		let res = self.get::<Vec<api::BlockOutputs>>("/v1/chain/outputs/byheight", url.as_str());
+		let block = match res {
 			Err(e) => {
-				let report = format!("Getting outputs by id: {}", e);
-				error!("Outputs by id failed: {}", e);
+				let report = format!("Getting outputs at height {} from node: {}", height, e);
+				error!("Get block outputs error: {}", e);
 				return Err(libwallet::ErrorKind::ClientCallback(report).into());
 			}
+			Ok(blocks) => match blocks.into_iter().find(|b| b.header.height == height) {
+				Some(b) => b,
+				None => return Ok(None),
+			},
 		};
 
-		for res in results {
-			for out in res {
-				api_outputs.insert(
-					out.commit.commit(),
-					(util::to_hex(out.commit.to_vec()), out.height, out.mmr_index),
+		let url = format!("{}/v1/headers/{}", self.node_url(), block.header.hash);
+		self.wait_for_rate_limit();
+		let header = self
+			.get::<api::BlockHeaderPrintable>("/v1/headers", url.as_str())
+			.map_err(|e| {
+				let report = format!("Getting header {} from node: {}", block.header.hash, e);
+				libwallet::ErrorKind::ClientCallback(report)
+			})?;
+		Ok(Some(NodeBlockOutputs {
+			height,
+			output_root: header.output_root,
+			outputs: block
+				.outputs
+				.into_iter()
+				.map(|o| NodeOutputProof {
+					commit: o.commit,
+					is_coinbase: o.output_type == api::OutputType::Coinbase,
+					mmr_index: o.mmr_index,
+					proof: o.proof,
+					merkle_proof: o.merkle_proof,
+				})
+				.collect(),
+		}))
+	}
+
+	/// Get the transactions in the node's mempool through the node's v2 foreign
+	/// API, indexed by the excess of their kernels
+	fn get_pool_entries(
+		&self,
+	) -> Result<HashMap<pedersen::Commitment, NodePoolEntry>, libwallet::Error> {
+		let entries = self.get_pool_txs()?;
+		let mut pool = HashMap::new();
+		for entry in entries.iter() {
+			let kernels = match entry["tx"]["body"]["kernels"].as_array() {
+				Some(k) => k,
+				None => continue,
+			};
+			let src = match entry["src"].as_str() {
+				Some(s) => s.to_owned(),
+				None => entry["src"].to_string(),
+			};
+			let tx_at = entry["tx_at"]
+				.as_str()
+				.and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+				.map(|t| t.with_timezone(&Utc));
+			for k in kernels.iter() {
+				let excess = match k["excess"].as_str().map(|e| util::from_hex(e.to_owned())) {
+					Some(Ok(e)) => pedersen::Commitment::from_vec(e),
+					_ => continue,
+				};
+				pool.insert(
+					excess,
+					NodePoolEntry {
+						src: src.clone(),
+						tx_at,
+					},
 				);
 			}
 		}
-		Ok(api_outputs)
+		Ok(pool)
+	}
+
+	/// Get the fees of the transactions in a block from the block as the node
+	/// API prints it, leaving the coinbase outputs and kernels out
+	fn get_block_fees(&self, height: u64) -> Result<Option<NodeTxFees>, libwallet::Error> {
+		let url = format!("{}/v1/blocks/{}", self.node_url(), height);
+		self.wait_for_rate_limit();
+		let res = self.get::<Value>("/v1/blocks", url.as_str());
+		let block = match res {
+			Err(e) => {
+				// no block at that height
+				if is_not_found(&e) {
+					return Ok(None);
+				}
+				let report = format!("Getting block at height {} from node: {}", height, e);
+				error!("Get block fees error: {}", e);
+				return Err(libwallet::ErrorKind::ClientCallback(report).into());
+			}
+			Ok(b) => b,
+		};
+		let empty = vec![];
+		let kernels = block["kernels"].as_array().unwrap_or(&empty);
+		let kernels = kernels
+			.iter()
+			.filter(|k| k["features"].as_str() != Some("Coinbase"))
+			.collect::<Vec<&Value>>();
+		let num_outputs = block["outputs"]
+			.as_array()
+			.unwrap_or(&empty)
+			.iter()
+			.filter(|o| o["output_type"].as_str() != Some("Coinbase"))
+			.count();
+		Ok(Some(NodeTxFees {
+			num_inputs: block["inputs"].as_array().map(|i| i.len()).unwrap_or(0),
+			num_outputs,
+			num_kernels: kernels.len(),
+			fee: kernels.iter().map(|k| HTTPNodeClient::kernel_fee(k)).sum(),
+		}))
+	}
+
+	fn get_pool_fees(&self) -> Result<Vec<NodeTxFees>, libwallet::Error> {
+		let entries = self.get_pool_txs()?;
+		let len = |v: &Value| v.as_array().map(|a| a.len()).unwrap_or(0);
+		Ok(entries
+			.iter()
+			.map(|entry| {
+				let body = &entry["tx"]["body"];
+				let fee = match body["kernels"].as_array() {
+					Some(kernels) => kernels.iter().map(|k| HTTPNodeClient::kernel_fee(k)).sum(),
+					None => 0,
+				};
+				NodeTxFees {
+					num_inputs: len(&body["inputs"]),
+					num_outputs: len(&body["outputs"]),
+					num_kernels: len(&body["kernels"]),
+					fee,
+				}
+			})
+			.collect())
+	}
+
+	fn get_stats(&self, reset: bool) -> NodeClientStats {
+		self.stats.get(reset)
 	}
 
 	fn get_outputs_by_pmmr_index(
 		&self,
-		start_height: u64,
+		start_index: u64,
+		end_index: Option<u64>,
 		max_outputs: u64,
 	) -> Result<
 		(
@@ -229,28 +683,27 @@ impl NodeClient for HTTPNodeClient {
 		libwallet::Error,
 	> {
 		let addr = self.node_url();
-		let query_param = format!("start_index={}&max={}", start_height, max_outputs);
+		let mut query_param = format!("start_index={}&max={}", start_index, max_outputs);
+		if let Some(e) = end_index {
+			query_param = format!("{}&end_index={}", query_param, e);
+		}
 
 		let url = format!("{}/v1/txhashset/outputs?{}", addr, query_param,);
 
 		let mut api_outputs: Vec<(pedersen::Commitment, pedersen::RangeProof, bool, u64, u64)> =
 			Vec::new();
 
-		let chain_type = if global::is_main() {
-			global::ChainTypes::Mainnet
-		} else if global::is_floo() {
-			global::ChainTypes::Floonet
-		} else {
-			global::ChainTypes::UserTesting
-		};
-
-		match api::client::get::<api::OutputListing>(
-			url.as_str(),
-			self.node_api_secret(),
-			chain_type,
-		) {
+		self.wait_for_rate_limit();
+		match self.get::<api::OutputListing>("/v1/txhashset/outputs", url.as_str()) {
 			Ok(o) => {
+				// nodes not supporting end_index may return more than was asked for
+				let mut last_retrieved_index = o.last_retrieved_index;
 				for out in o.outputs {
+					if let Some(e) = end_index {
+						if out.mmr_index > e {
+							continue;
+						}
+					}
 					let is_coinbase = match out.output_type {
 						api::OutputType::Coinbase => true,
 						api::OutputType::Transaction => false,
@@ -264,7 +717,10 @@ impl NodeClient for HTTPNodeClient {
 					));
 				}
 
-				Ok((o.highest_index, o.last_retrieved_index, api_outputs))
+				if let Some(e) = end_index {
+					last_retrieved_index = last_retrieved_index.min(e);
+				}
+				Ok((o.highest_index, last_retrieved_index, api_outputs))
 			}
 			Err(e) => {
 				// if we got anything other than 200 back from server, bye
@@ -279,6 +735,63 @@ impl NodeClient for HTTPNodeClient {
 	}
 }
 
+/// GET a node API endpoint asynchronously, recording the call in the client
+/// statistics once it completes
+fn recorded_get_async<T>(
+	client: &Client,
+	stats: NodeCallStats,
+	endpoint: &'static str,
+	url: &str,
+	api_secret: Option<String>,
+	chain_type: global::ChainTypes,
+) -> Box<dyn Future<Item = T, Error = api::Error> + Send>
+where
+	for<'de> T: Deserialize<'de> + Send + 'static,
+{
+	let started = Instant::now();
+	let url_s = url.to_owned();
+	Box::new(
+		client
+			.get_bytes_async(url, api_secret, chain_type)
+			.then(move |res| {
+				let received = res.as_ref().map(|d| d.len() as u64).unwrap_or(0);
+				stats.record(endpoint, &url_s, started, 0, received, res.is_ok());
+				res
+			})
+			.and_then(|data| parse_response(&data)),
+	)
+}
+
+/// Whether the node answered a query with a 404, i.e. what was queried doesn't
+/// exist, rather than failing to answer it
+fn is_not_found(e: &api::Error) -> bool {
+	match e.kind() {
+		api::ErrorKind::NotFound => true,
+		_ => false,
+	}
+}
+
+/// Parse a JSON response of the node
+fn parse_response<T>(data: &[u8]) -> Result<T, api::Error>
+where
+	for<'de> T: Deserialize<'de>,
+{
+	serde_json::from_slice(data).map_err(|e| {
+		e.context(api::ErrorKind::ResponseError(
+			"Cannot parse response".to_owned(),
+		))
+		.into()
+	})
+}
+
+/// Delay before retrying a query that failed `attempt` times before, doubled on every
+/// attempt up to `MAX_RETRY_BACKOFF_MS`, whatever the number of retries configured
+fn retry_backoff(attempt: u32) -> Duration {
+	let factor = 1u64.checked_shl(attempt).unwrap_or(u64::max_value());
+	let ms = RETRY_BACKOFF_MS.saturating_mul(factor);
+	Duration::from_millis(ms.min(MAX_RETRY_BACKOFF_MS))
+}
+
 /*
 /// Call the wallet API to create a coinbase output for the given block_fees.
 /// Will retry based on default "retry forever with backoff" behavior.
@@ -305,3 +818,256 @@ fn single_create_coinbase(url: &str, block_fees: &BlockFees) -> Result<CbData, E
 	))?;
 	Ok(res)
 }*/
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::io::{BufRead, BufReader, Read, Write};
+	use std::net::TcpListener;
+	use std::sync::{mpsc, Arc, Mutex};
+	use std::thread;
+
+	/// A node answering every request with the given status and no body
+	fn node_answering(status: &'static str) -> HTTPNodeClient {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}", listener.local_addr().unwrap());
+		thread::spawn(move || {
+			for stream in listener.incoming() {
+				let mut stream = match stream {
+					Ok(s) => s,
+					Err(_) => break,
+				};
+				let mut buf = [0u8; 4096];
+				let _ = stream.read(&mut buf);
+				let _ = write!(
+					stream,
+					"HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+					status
+				);
+			}
+		});
+		HTTPNodeClient::new(&url, None, global::ChainTypes::Floonet).unwrap()
+	}
+
+	/// A node answering every request with the given JSON body, passing on the
+	/// request line of each request
+	fn node_answering_json(body: String) -> (HTTPNodeClient, mpsc::Receiver<String>) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}", listener.local_addr().unwrap());
+		let (tx, rx) = mpsc::channel();
+		thread::spawn(move || {
+			for stream in listener.incoming() {
+				let mut stream = match stream {
+					Ok(s) => s,
+					Err(_) => break,
+				};
+				let mut buf = [0u8; 4096];
+				let len = stream.read(&mut buf).unwrap_or(0);
+				let request = String::from_utf8_lossy(&buf[..len]).to_string();
+				let _ = tx.send(request.lines().next().unwrap_or("").to_owned());
+				let _ = write!(
+					stream,
+					"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+					 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+					body.len(),
+					body
+				);
+			}
+		});
+		let node = HTTPNodeClient::new(&url, None, global::ChainTypes::Floonet).unwrap();
+		(node, rx)
+	}
+
+	/// A node answering outputs by id queries with the outputs asked for, at the mmr
+	/// index of their position in the query, but for the queries of the failing
+	/// commitment. Keeps the number of queries it's answering, and the most it had
+	/// to answer at once.
+	fn node_answering_outputs(failing: String) -> (HTTPNodeClient, Arc<Mutex<(usize, usize)>>) {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}", listener.local_addr().unwrap());
+		let in_flight = Arc::new(Mutex::new((0, 0)));
+		let counts = in_flight.clone();
+		thread::spawn(move || {
+			for stream in listener.incoming() {
+				let mut stream = match stream {
+					Ok(s) => s,
+					Err(_) => break,
+				};
+				let (failing, counts) = (failing.clone(), counts.clone());
+				thread::spawn(move || {
+					{
+						let mut c = counts.lock().unwrap();
+						c.0 += 1;
+						c.1 = c.1.max(c.0);
+					}
+					let mut reader = BufReader::new(stream.try_clone().unwrap());
+					let mut request = String::new();
+					let _ = reader.read_line(&mut request);
+					let mut header = String::new();
+					while reader.read_line(&mut header).unwrap_or(0) > 2 {
+						header.clear();
+					}
+					let ids: Vec<&str> = request
+						.split(|c| c == '?' || c == '&' || c == ' ')
+						.filter(|p| p.starts_with("id="))
+						.map(|p| &p[3..])
+						.collect();
+					// give the other queries time to come in
+					thread::sleep(Duration::from_millis(50));
+					if ids.contains(&failing.as_str()) {
+						let _ = write!(
+							stream,
+							"HTTP/1.1 500 Internal Server Error\r\n\
+							 Content-Length: 0\r\nConnection: close\r\n\r\n"
+						);
+					} else {
+						let outputs: Vec<Value> = ids
+							.iter()
+							.enumerate()
+							.map(|(i, id)| json!({"commit": id, "height": 1, "mmr_index": i + 1}))
+							.collect();
+						let body = Value::Array(outputs).to_string();
+						let _ = write!(
+							stream,
+							"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\
+							 Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+							body.len(),
+							body
+						);
+					}
+					counts.lock().unwrap().0 -= 1;
+				});
+			}
+		});
+		let node = HTTPNodeClient::new(&url, None, global::ChainTypes::Floonet).unwrap();
+		(node, in_flight)
+	}
+
+	#[test]
+	fn outputs_are_folded_as_their_queries_come_back() {
+		let commits: Vec<pedersen::Commitment> = (0..2 * OUTPUTS_CHUNK_SIZE + 10)
+			.map(|i| {
+				let mut c = vec![9u8; 33];
+				c[1] = (i >> 8) as u8;
+				c[2] = i as u8;
+				pedersen::Commitment::from_vec(c)
+			})
+			.collect();
+		// the query of the second chunk fails, without retries
+		let failing = util::to_hex(commits[OUTPUTS_CHUNK_SIZE].as_ref().to_vec());
+		let (mut node, in_flight) = node_answering_outputs(failing);
+		node.set_outputs_query_limits(2, 0);
+
+		let res = node.get_outputs_from_node(commits.clone()).unwrap();
+		assert_eq!(res.outputs.len(), commits.len() - OUTPUTS_CHUNK_SIZE);
+		assert_eq!(res.failed.len(), OUTPUTS_CHUNK_SIZE);
+		assert_eq!(res.errors.len(), 1);
+		for (i, commit) in commits.iter().enumerate() {
+			if i / OUTPUTS_CHUNK_SIZE == 1 {
+				assert!(res.failed.contains(commit));
+				continue;
+			}
+			let (hex, height, mmr_index) = &res.outputs[commit];
+			assert_eq!(*hex, util::to_hex(commit.as_ref().to_vec()));
+			assert_eq!(*height, 1);
+			assert_eq!(*mmr_index, (i % OUTPUTS_CHUNK_SIZE) as u64 + 1);
+		}
+
+		// the three queries ran, never more than two at once
+		let (left, most) = *in_flight.lock().unwrap();
+		assert_eq!(left, 0);
+		assert_eq!(most, 2);
+	}
+
+	#[test]
+	fn outputs_are_queried_up_to_the_end_index() {
+		let output = |mmr_index: u64| {
+			json!({
+				"output_type": "Transaction",
+				"commit": format!("09{}", "11".repeat(32)),
+				"spent": false,
+				"proof": "00".repeat(8),
+				"proof_hash": "00".repeat(32),
+				"block_height": 10,
+				"merkle_proof": null,
+				"mmr_index": mmr_index,
+			})
+		};
+		// a node not supporting the end index returns outputs past it
+		let listing = json!({
+			"highest_index": 20,
+			"last_retrieved_index": 12,
+			"outputs": [output(5), output(9), output(12)],
+		});
+		let (node, requests) = node_answering_json(listing.to_string());
+
+		let (highest, last, outputs) = node.get_outputs_by_pmmr_index(5, Some(9), 100).unwrap();
+		let request = requests.recv().unwrap();
+		assert!(request.contains("start_index=5&max=100&end_index=9"));
+		assert_eq!(highest, 20);
+		assert_eq!(last, 9);
+		let indexes: Vec<u64> = outputs.iter().map(|o| o.4).collect();
+		assert_eq!(indexes, vec![5, 9]);
+
+		// without one, everything returned is kept
+		let (highest, last, outputs) = node.get_outputs_by_pmmr_index(5, None, 100).unwrap();
+		let request = requests.recv().unwrap();
+		assert!(!request.contains("end_index"));
+		assert_eq!(highest, 20);
+		assert_eq!(last, 12);
+		assert_eq!(outputs.len(), 3);
+		assert_eq!(outputs[2].3, 10);
+		assert!(!outputs[2].2);
+	}
+
+	#[test]
+	fn not_found_is_told_from_failures() {
+		let excess = pedersen::Commitment::from_vec(vec![0; 33]);
+
+		// what the node doesn't have isn't an error
+		let node = node_answering("404 Not Found");
+		assert!(node
+			.get_block_by_kernel(&excess, Some(1), Some(10))
+			.unwrap()
+			.is_none());
+		assert!(node.get_block_fees(5).unwrap().is_none());
+
+		// but a node failing to answer is
+		let node = node_answering("500 Internal Server Error");
+		assert!(node
+			.get_block_by_kernel(&excess, Some(1), Some(10))
+			.is_err());
+		assert!(node.get_block_fees(5).is_err());
+	}
+
+	#[test]
+	fn clones_share_connections() {
+		let node = HTTPNodeClient::new("http://127.0.0.1:3413", None, global::ChainTypes::Floonet)
+			.unwrap();
+		assert!(node.clone().client.shares_pool(&node.client));
+		assert_eq!(node.chain_type(), global::ChainTypes::Floonet);
+		let other = HTTPNodeClient::new("http://127.0.0.1:3413", None, global::ChainTypes::Floonet)
+			.unwrap();
+		assert!(!other.client.shares_pool(&node.client));
+	}
+
+	#[test]
+	fn retry_backoff_is_capped() {
+		assert_eq!(retry_backoff(0), Duration::from_millis(RETRY_BACKOFF_MS));
+		assert_eq!(
+			retry_backoff(1),
+			Duration::from_millis(2 * RETRY_BACKOFF_MS)
+		);
+		assert_eq!(
+			retry_backoff(3),
+			Duration::from_millis(8 * RETRY_BACKOFF_MS)
+		);
+		// large retry counts neither overflow nor wait for ages
+		for attempt in &[6, 40, 63, 64, 100, u32::max_value()] {
+			assert_eq!(
+				retry_backoff(*attempt),
+				Duration::from_millis(MAX_RETRY_BACKOFF_MS)
+			);
+		}
+	}
+}
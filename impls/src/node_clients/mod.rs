@@ -13,5 +13,11 @@
 // limitations under the License.
 
 mod http;
+mod stats;
+mod throttle;
 
-pub use self::http::HTTPNodeClient;
+pub use self::http::{
+	HTTPNodeClient, DEFAULT_OUTPUTS_QUERY_PARALLELISM, DEFAULT_OUTPUTS_QUERY_RETRIES,
+};
+pub use self::stats::NodeCallStats;
+pub use self::throttle::RequestThrottle;
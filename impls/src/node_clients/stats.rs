@@ -0,0 +1,98 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Statistics of the calls made to the node, per endpoint, to tell whether a
+//! slow wallet is waiting on its node. Calls slower than a threshold are logged.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::libwallet::{NodeClientStats, NodeEndpointStats};
+use crate::util::Mutex;
+
+/// Calls taking longer than this are logged and counted as slow
+const SLOW_CALL_MS: u64 = 2000;
+
+/// Statistics shared by all the clones of a node client
+#[derive(Clone)]
+pub struct NodeCallStats {
+	stats: Arc<Mutex<NodeClientStats>>,
+}
+
+impl NodeCallStats {
+	/// Start empty statistics
+	pub fn new() -> NodeCallStats {
+		NodeCallStats {
+			stats: Arc::new(Mutex::new(NodeClientStats::new())),
+		}
+	}
+
+	/// Record a call to an endpoint started at `started`, along with the sizes
+	/// of the request and response bodies
+	pub fn record(
+		&self,
+		endpoint: &str,
+		url: &str,
+		started: Instant,
+		bytes_sent: u64,
+		bytes_received: u64,
+		ok: bool,
+	) {
+		let elapsed = started.elapsed();
+		let slow = elapsed >= Duration::from_millis(SLOW_CALL_MS);
+		if slow {
+			// Queries by id can be long, the path is enough
+			warn!(
+				"Slow node call: {} took {} ms{}",
+				url.split('?').next().unwrap_or(url),
+				elapsed.as_millis(),
+				if ok { "" } else { " and failed" }
+			);
+		}
+		let elapsed_us = elapsed.as_micros() as u64;
+		let mut stats = self.stats.lock();
+		let i = match stats.endpoints.iter().position(|e| e.endpoint == endpoint) {
+			Some(i) => i,
+			None => {
+				stats.endpoints.push(NodeEndpointStats {
+					endpoint: endpoint.to_owned(),
+					..Default::default()
+				});
+				stats.endpoints.len() - 1
+			}
+		};
+		let e = &mut stats.endpoints[i];
+		e.calls += 1;
+		if !ok {
+			e.errors += 1;
+		}
+		if slow {
+			e.slow_calls += 1;
+		}
+		e.total_us += elapsed_us;
+		e.max_us = e.max_us.max(elapsed_us);
+		e.bytes_sent += bytes_sent;
+		e.bytes_received += bytes_received;
+	}
+
+	/// Statistics so far, starting over afterwards if `reset` is set
+	pub fn get(&self, reset: bool) -> NodeClientStats {
+		let mut stats = self.stats.lock();
+		let res = stats.clone();
+		if reset {
+			*stats = NodeClientStats::new();
+		}
+		res
+	}
+}
@@ -0,0 +1,112 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Token bucket limiting the rate of the requests sent to the node
+
+use futures::future::{self, Future};
+use rand::{thread_rng, Rng};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tokio::timer::Delay;
+
+use crate::api;
+use crate::util::Mutex;
+
+/// Largest share of a wait added to it at random, so that wallets throttled
+/// by the same node don't send their requests in lockstep
+const MAX_JITTER: f64 = 0.2;
+
+struct Bucket {
+	tokens: f64,
+	updated: Instant,
+}
+
+/// Token bucket shared by all the clones of a node client. The bucket holds up
+/// to `burst` tokens and is refilled at `rate` tokens a second; each request
+/// takes a token, waiting for it if the bucket is empty.
+#[derive(Clone)]
+pub struct RequestThrottle {
+	rate: f64,
+	burst: f64,
+	bucket: Arc<Mutex<Bucket>>,
+}
+
+impl RequestThrottle {
+	/// Allow `rate` requests a second on average, and bursts of `burst` requests
+	pub fn new(rate: f64, burst: u32) -> RequestThrottle {
+		let burst = (burst as f64).max(1.0);
+		RequestThrottle {
+			rate: rate.max(0.001),
+			burst,
+			bucket: Arc::new(Mutex::new(Bucket {
+				tokens: burst,
+				updated: Instant::now(),
+			})),
+		}
+	}
+
+	/// Take a token, returning how long to wait before it's available. Waiting
+	/// requests hold their token already, so they're served in order.
+	fn reserve(&self) -> Duration {
+		let mut bucket = self.bucket.lock();
+		let now = Instant::now();
+		let elapsed = now.duration_since(bucket.updated);
+		let elapsed = elapsed.as_secs() as f64 + elapsed.subsec_micros() as f64 / 1e6;
+		bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst) - 1.0;
+		bucket.updated = now;
+		if bucket.tokens >= 0.0 {
+			return Duration::from_secs(0);
+		}
+		let wait = -bucket.tokens / self.rate;
+		let jitter = thread_rng().gen_range(0.0, MAX_JITTER);
+		Duration::from_micros((wait * (1.0 + jitter) * 1e6) as u64)
+	}
+
+	/// Block until a request may be sent
+	pub fn wait(&self) {
+		let wait = self.reserve();
+		if wait > Duration::from_secs(0) {
+			trace!("Throttling node request for {:?}", wait);
+			thread::sleep(wait);
+		}
+	}
+
+	/// Future resolving once a request may be sent
+	pub fn wait_async(&self) -> Box<dyn Future<Item = (), Error = api::Error> + Send> {
+		let wait = self.reserve();
+		if wait == Duration::from_secs(0) {
+			return Box::new(future::ok(()));
+		}
+		Box::new(Delay::new(Instant::now() + wait).then(|_| Ok(())))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn throttle_burst_then_rate() {
+		let throttle = RequestThrottle::new(10.0, 3);
+		for _ in 0..3 {
+			assert_eq!(throttle.reserve(), Duration::from_secs(0));
+		}
+		// The next tokens come a tenth of a second apart, give or take the jitter
+		let wait = throttle.reserve();
+		assert!(wait >= Duration::from_millis(90) && wait <= Duration::from_millis(125));
+		let wait = throttle.reserve();
+		assert!(wait >= Duration::from_millis(190) && wait <= Duration::from_millis(245));
+	}
+}
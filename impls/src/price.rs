@@ -0,0 +1,70 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Price of the coin fetched over http, for the cost basis of the outputs received
+
+use crate::client_utils::Client;
+use crate::core::global;
+use crate::libwallet::{Error, ErrorKind, PriceProvider};
+use serde_json::Value;
+
+/// Placeholder of the price provider url replaced by the fiat currency
+const CURRENCY_PLACEHOLDER: &str = "{currency}";
+
+/// Price provider answering a GET of its url with a json object whose `price` field,
+/// a number or a string, is the price of a whole coin. `{currency}` in the url is
+/// replaced by the ticker of the fiat currency, e.g.
+/// `https://prices.example.com/mwc?vs={currency}`.
+pub struct HttpPriceProvider {
+	url: String,
+	client: Client,
+}
+
+impl HttpPriceProvider {
+	/// New provider fetching prices from a url
+	pub fn new(url: &str) -> Result<HttpPriceProvider, Error> {
+		let client = Client::new(false)
+			.map_err(|e| ErrorKind::GenericError(format!("Unable to create HTTP client: {}", e)))?;
+		Ok(HttpPriceProvider {
+			url: url.to_owned(),
+			client,
+		})
+	}
+}
+
+impl PriceProvider for HttpPriceProvider {
+	fn name(&self) -> String {
+		self.url.clone()
+	}
+
+	fn price(&self, currency: &str) -> Result<u64, Error> {
+		let url = self.url.replace(CURRENCY_PLACEHOLDER, currency);
+		let res: Value = self
+			.client
+			.get(&url, None, global::ChainTypes::Mainnet)
+			.map_err(|e| ErrorKind::ClientCallback(format!("Fetching price: {}", e)))?;
+		let price = match &res["price"] {
+			Value::Number(n) => n.as_f64(),
+			Value::String(s) => s.trim().parse::<f64>().ok(),
+			_ => None,
+		};
+		match price {
+			Some(p) if p.is_finite() && p >= 0.0 => Ok((p * 100.0).round() as u64),
+			_ => Err(ErrorKind::ClientCallback(format!(
+				"No price in the response of {}",
+				url
+			)))?,
+		}
+	}
+}
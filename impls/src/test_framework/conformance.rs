@@ -0,0 +1,355 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conformance suite for slate transports. Runs full send, receive and
+//! finalize cycles through a transport under test, between two wallets on an
+//! in-memory chain, so authors of `SlateSender` and `SlateReceiver`
+//! implementations can check that their adapters work with the wallet.
+//! Available with the `test-util` feature.
+
+use super::{award_blocks_to_wallet, wallet_info, LocalWalletClient, WalletProxy};
+use crate::core::consensus;
+use crate::keychain::ExtKeychain;
+use crate::libwallet::api_impl::{foreign, owner};
+use crate::libwallet::{self, ErrorKind, InitTxArgs, Slate, WalletInst};
+use crate::util::secp::key::SecretKey;
+use crate::util::{Mutex, ZeroingString};
+use crate::{DefaultLCProvider, DefaultWalletImpl, SlateSender};
+use std::fs;
+use std::sync::Arc;
+use std::thread;
+
+/// Hands a slate delivered by a transport to the recipient wallet, returning
+/// the slate the transport has to deliver back to the sender
+pub type SlateHandler = Arc<dyn Fn(&Slate) -> Result<Slate, libwallet::Error> + Send + Sync>;
+
+/// Transport checked by the conformance suite
+pub trait TransportUnderTest {
+	/// Start receiving slates, handing each of them to `handler` as the
+	/// listener of a `SlateReceiver` would, and return the destination the
+	/// slates have to be sent to
+	fn start_receiver(&mut self, handler: SlateHandler) -> Result<String, libwallet::Error>;
+
+	/// Sender delivering slates to the given destination
+	fn sender(&self, dest: &str) -> Result<Box<dyn SlateSender>, libwallet::Error>;
+
+	/// Stop receiving slates, once the suite is done
+	fn stop_receiver(&mut self) {}
+}
+
+/// Outcome of a case of the conformance suite
+#[derive(Clone, Debug)]
+pub struct ConformanceCase {
+	/// Name of the case
+	pub name: &'static str,
+	/// Why the case failed, none if it passed
+	pub error: Option<String>,
+}
+
+/// Outcome of the conformance suite for a transport
+#[derive(Clone, Debug)]
+pub struct ConformanceReport {
+	/// Cases run, in order
+	pub cases: Vec<ConformanceCase>,
+}
+
+impl ConformanceReport {
+	/// Whether all the cases passed
+	pub fn passed(&self) -> bool {
+		self.cases.iter().all(|c| c.error.is_none())
+	}
+
+	/// Panic with the failed cases, if any
+	pub fn assert_passed(&self) {
+		let failed: Vec<String> = self
+			.cases
+			.iter()
+			.filter_map(|c| c.error.as_ref().map(|e| format!("{}: {}", c.name, e)))
+			.collect();
+		if !failed.is_empty() {
+			panic!("Transport failed conformance cases:\n{}", failed.join("\n"));
+		}
+	}
+}
+
+type TestWallet = Arc<
+	Mutex<
+		Box<
+			dyn WalletInst<
+				'static,
+				DefaultLCProvider<'static, LocalWalletClient, ExtKeychain>,
+				LocalWalletClient,
+				ExtKeychain,
+			>,
+		>,
+	>,
+>;
+
+/// Number of blocks mined by the sender before the cases are run
+const FUNDING_BLOCKS: usize = 10;
+/// Number of sends of the sequential sends case
+const SEQUENTIAL_SENDS: u64 = 3;
+
+/// Run the conformance suite against a transport. Wallets and chain are
+/// created in `test_dir`, which is emptied first. Errors are returned for a
+/// suite that couldn't be set up, failed cases are part of the report.
+pub fn run_conformance_suite<T>(
+	transport: &mut T,
+	test_dir: &str,
+) -> Result<ConformanceReport, libwallet::Error>
+where
+	T: TransportUnderTest,
+{
+	let _ = fs::remove_dir_all(test_dir);
+	let mut proxy: WalletProxy<
+		DefaultLCProvider<LocalWalletClient, ExtKeychain>,
+		LocalWalletClient,
+		ExtKeychain,
+	> = WalletProxy::new(test_dir);
+	let chain = proxy.chain.clone();
+
+	let (sender, sender_mask) = create_wallet(&mut proxy, test_dir, "sender")?;
+	let (recipient, recipient_mask) = create_wallet(&mut proxy, test_dir, "recipient")?;
+	thread::spawn(move || {
+		if let Err(e) = proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+	award_blocks_to_wallet(
+		&chain,
+		sender.clone(),
+		sender_mask.as_ref(),
+		FUNDING_BLOCKS,
+		false,
+	)?;
+
+	let handler: SlateHandler = {
+		let (recipient, mask) = (recipient.clone(), recipient_mask.clone());
+		Arc::new(move |slate: &Slate| {
+			let mut w_lock = recipient.lock();
+			let w = w_lock.lc_provider()?.wallet_inst()?;
+			foreign::receive_tx(&mut **w, mask.as_ref(), slate, None, None, false)
+		})
+	};
+	let dest = transport.start_receiver(handler)?;
+	let slate_sender = transport.sender(&dest)?;
+
+	let suite = Suite {
+		sender,
+		sender_mask,
+		recipient,
+		recipient_mask,
+		slate_sender,
+		amount: consensus::MWC_FIRST_GROUP_REWARD,
+	};
+	let case = |name: &'static str, res: Result<(), String>| ConformanceCase {
+		name,
+		error: res.err(),
+	};
+	let sent = suite.send_receive_finalize(1);
+	let cases = vec![
+		case(
+			"send_receive_finalize",
+			sent.as_ref().map(|_| ()).map_err(|e| e.clone()),
+		),
+		case(
+			"duplicate_slate_rejected",
+			suite.duplicate_slate_rejected(sent.ok()),
+		),
+		case("sequential_sends", suite.sequential_sends()),
+	];
+
+	transport.stop_receiver();
+	Ok(ConformanceReport { cases })
+}
+
+/// Create a wallet on the in-memory chain of the proxy
+fn create_wallet(
+	proxy: &mut WalletProxy<
+		DefaultLCProvider<'static, LocalWalletClient, ExtKeychain>,
+		LocalWalletClient,
+		ExtKeychain,
+	>,
+	test_dir: &str,
+	name: &str,
+) -> Result<(TestWallet, Option<SecretKey>), libwallet::Error> {
+	let client = LocalWalletClient::new(name, proxy.tx.clone());
+	let mut wallet = Box::new(
+		DefaultWalletImpl::<LocalWalletClient>::new(client.clone())
+			.map_err(|e| ErrorKind::GenericError(e.to_string()))?,
+	)
+		as Box<
+			dyn WalletInst<
+				DefaultLCProvider<'static, LocalWalletClient, ExtKeychain>,
+				LocalWalletClient,
+				ExtKeychain,
+			>,
+		>;
+	let mask = {
+		let lc = wallet.lc_provider()?;
+		lc.set_top_level_directory(&format!("{}/{}", test_dir, name))?;
+		lc.create_wallet(None, None, 32, ZeroingString::from(""), false)?;
+		lc.open_wallet(None, ZeroingString::from(""), false, false)?
+	};
+	let wallet = Arc::new(Mutex::new(wallet));
+	proxy.add_wallet(
+		name,
+		client.get_send_instance(),
+		wallet.clone(),
+		mask.clone(),
+	);
+	Ok((wallet, mask))
+}
+
+/// Wallet pair and transport the cases run with
+struct Suite {
+	sender: TestWallet,
+	sender_mask: Option<SecretKey>,
+	recipient: TestWallet,
+	recipient_mask: Option<SecretKey>,
+	slate_sender: Box<dyn SlateSender>,
+	amount: u64,
+}
+
+impl Suite {
+	/// Send through the transport and complete the transaction, checking the
+	/// slate coming back and the balance of the recipient. Returns the slate
+	/// the sender started with.
+	fn send_receive_finalize(&self, expected_sends: u64) -> Result<Slate, String> {
+		let sender_mask = self.sender_mask.as_ref();
+		let mut w_lock = self.sender.lock();
+		let w = w_lock.lc_provider().and_then(|lc| lc.wallet_inst());
+		let w = w.map_err(|e| e.to_string())?;
+		let args = InitTxArgs {
+			src_acct_name: None,
+			amount: self.amount,
+			minimum_confirmations: 2,
+			max_outputs: 500,
+			num_change_outputs: 1,
+			selection_strategy_is_use_all: true,
+			..Default::default()
+		};
+		let slate_i = owner::init_send_tx(&mut **w, sender_mask, args, false)
+			.map_err(|e| format!("Unable to start the send: {}", e))?;
+		let slate = self
+			.slate_sender
+			.send_tx(&slate_i)
+			.map_err(|e| format!("Transport failed to send the slate: {}", e))?;
+		if slate.id != slate_i.id {
+			return Err(format!("Got slate {} back for {}", slate.id, slate_i.id));
+		}
+		if slate.amount != slate_i.amount || slate.fee != slate_i.fee {
+			return Err("Amount or fee of the slate changed in transit".to_owned());
+		}
+		if slate.participant_data.len() != 2 {
+			return Err("Slate came back without the recipient's participant data".to_owned());
+		}
+		owner::tx_lock_outputs(&mut **w, sender_mask, &slate, 0).map_err(|e| e.to_string())?;
+		let slate = owner::finalize_tx(&mut **w, sender_mask, &slate)
+			.map_err(|e| format!("Unable to finalize the slate sent back: {}", e))?;
+		let client = w.w2n_client().clone();
+		drop(w_lock);
+		// Mines a block
+		owner::post_tx(&client, &slate.tx, false).map_err(|e| e.to_string())?;
+
+		let info = wallet_info(self.recipient.clone(), self.recipient_mask.as_ref())
+			.map_err(|e| e.to_string())?;
+		if info.total != self.amount * expected_sends {
+			return Err(format!(
+				"Recipient holds {} instead of {}",
+				info.total,
+				self.amount * expected_sends
+			));
+		}
+		Ok(slate_i)
+	}
+
+	/// A slate the recipient refuses has to come back as an error of the
+	/// transport, not as a slate
+	fn duplicate_slate_rejected(&self, sent: Option<Slate>) -> Result<(), String> {
+		let slate = sent.ok_or_else(|| "No slate was sent to send again".to_owned())?;
+		match self.slate_sender.send_tx(&slate) {
+			Ok(_) => Err("Transport didn't report the recipient refusing the slate".to_owned()),
+			Err(_) => Ok(()),
+		}
+	}
+
+	/// The transport keeps working for sends following each other
+	fn sequential_sends(&self) -> Result<(), String> {
+		let done = wallet_info(self.recipient.clone(), self.recipient_mask.as_ref())
+			.map_err(|e| e.to_string())?
+			.total / self.amount;
+		for i in 1..=SEQUENTIAL_SENDS {
+			self.send_receive_finalize(done + i)
+				.map_err(|e| format!("Send {}: {}", i, e))?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{PathToSlate, SlateGetter, SlatePutter};
+	use std::path::PathBuf;
+
+	/// Transport exchanging the slates as files
+	struct FileTransport {
+		dir: PathBuf,
+		handler: Option<SlateHandler>,
+	}
+
+	struct FileSender {
+		dir: PathBuf,
+		handler: SlateHandler,
+	}
+
+	impl SlateSender for FileSender {
+		fn send_tx(&self, slate: &Slate) -> Result<Slate, libwallet::Error> {
+			let request = PathToSlate(self.dir.join(format!("{}.tx", slate.id)));
+			request.put_tx(slate)?;
+			let response = PathToSlate(self.dir.join(format!("{}.tx.response", slate.id)));
+			response.put_tx(&(self.handler)(&request.get_tx()?)?)?;
+			response.get_tx()
+		}
+	}
+
+	impl TransportUnderTest for FileTransport {
+		fn start_receiver(&mut self, handler: SlateHandler) -> Result<String, libwallet::Error> {
+			fs::create_dir_all(&self.dir)?;
+			self.handler = Some(handler);
+			Ok(self.dir.to_string_lossy().into_owned())
+		}
+
+		fn sender(&self, dest: &str) -> Result<Box<dyn SlateSender>, libwallet::Error> {
+			Ok(Box::new(FileSender {
+				dir: dest.into(),
+				handler: self.handler.clone().unwrap(),
+			}))
+		}
+	}
+
+	#[test]
+	fn file_transport_conformance() {
+		let test_dir = "test_output/conformance";
+		let mut transport = FileTransport {
+			dir: PathBuf::from(test_dir).join("slates"),
+			handler: None,
+		};
+		let report = run_conformance_suite(&mut transport, test_dir).unwrap();
+		report.assert_passed();
+		assert_eq!(report.cases.len(), 3);
+		let _ = fs::remove_dir_all(test_dir);
+	}
+}
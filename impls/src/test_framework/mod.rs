@@ -16,13 +16,17 @@ use crate::api;
 use crate::chain;
 use crate::chain::Chain;
 use crate::core;
-use crate::core::core::{Output, OutputFeatures, OutputIdentifier, Transaction, TxKernel};
+use crate::core::core::hash::Hashed;
+use crate::core::core::{
+	BlockHeader, KernelFeatures, Output, OutputFeatures, OutputIdentifier, Transaction, TxKernel,
+};
 use crate::core::{consensus, global, pow};
 use crate::keychain;
 use crate::libwallet;
 use crate::libwallet::api_impl::{foreign, owner};
 use crate::libwallet::{
-	BlockFees, InitTxArgs, NodeClient, WalletInfo, WalletInst, WalletLCProvider,
+	BlockFees, InitTxArgs, NodeBlockOutputs, NodeClient, NodeHeaderInfo, NodeOutputProof,
+	NodeTxFees, WalletInfo, WalletInst, WalletLCProvider,
 };
 use crate::util::secp::key::SecretKey;
 use crate::util::secp::pedersen;
@@ -31,9 +35,15 @@ use chrono::Duration;
 use std::sync::Arc;
 use std::thread;
 
+#[cfg(any(test, feature = "test-util"))]
+mod conformance;
 mod testclient;
 
-pub use self::{testclient::LocalWalletClient, testclient::WalletProxy};
+#[cfg(any(test, feature = "test-util"))]
+pub use self::conformance::{
+	run_conformance_suite, ConformanceCase, ConformanceReport, SlateHandler, TransportUnderTest,
+};
+pub use self::testclient::{LocalWalletClient, LyingNodeClient, NodeLies, WalletProxy};
 
 /// Get an output from the chain locally and present it back as an API output
 fn get_output_local(chain: &chain::Chain, commit: &pedersen::Commitment) -> Option<api::Output> {
@@ -52,16 +62,103 @@ fn get_output_local(chain: &chain::Chain, commit: &pedersen::Commitment) -> Opti
 	None
 }
 
+/// Present a block header back as header info, as the node API would
+fn header_info_local(header: &BlockHeader) -> NodeHeaderInfo {
+	NodeHeaderInfo {
+		height: header.height,
+		hash: header.hash().to_hex(),
+		timestamp: header.timestamp,
+		output_mmr_size: header.output_mmr_size,
+	}
+}
+
+/// Get the header of the block at a given height locally
+fn get_header_by_height_local(chain: &chain::Chain, height: u64) -> Option<NodeHeaderInfo> {
+	chain
+		.get_header_by_height(height)
+		.ok()
+		.map(|h| header_info_local(&h))
+}
+
+/// Find the block including a kernel locally, searching down from max_height
+fn get_block_by_kernel_local(
+	chain: &chain::Chain,
+	excess: &pedersen::Commitment,
+	min_height: Option<u64>,
+	max_height: Option<u64>,
+) -> Option<NodeHeaderInfo> {
+	let head_height = chain.head().unwrap().height;
+	let max_height = max_height.unwrap_or(head_height).min(head_height);
+	let min_height = min_height.unwrap_or(0);
+	for height in (min_height..=max_height).rev() {
+		let header = chain.get_header_by_height(height).ok()?;
+		let block = chain.get_block(&header.hash()).ok()?;
+		if block.kernels().iter().any(|k| k.excess == *excess) {
+			return Some(header_info_local(&header));
+		}
+	}
+	None
+}
+
+/// Get the outputs created in the block at a given height locally, with their proofs
+fn get_block_outputs_local(chain: Arc<chain::Chain>, height: u64) -> Option<NodeBlockOutputs> {
+	let header = chain.get_header_by_height(height).ok()?;
+	let block = chain.get_block(&header.hash()).ok()?;
+	let outputs = block
+		.outputs()
+		.iter()
+		.map(|o| {
+			let o = api::OutputPrintable::from_output(o, chain.clone(), Some(&header), true, true)
+				.unwrap();
+			NodeOutputProof {
+				commit: o.commit,
+				is_coinbase: o.output_type == api::OutputType::Coinbase,
+				mmr_index: o.mmr_index,
+				proof: o.proof,
+				merkle_proof: o.merkle_proof,
+			}
+		})
+		.collect();
+	Some(NodeBlockOutputs {
+		height,
+		output_root: header.output_root.to_hex(),
+		outputs,
+	})
+}
+
+/// Get the fees of the transactions in the block at a given height locally,
+/// coinbase excluded
+fn get_block_fees_local(chain: &chain::Chain, height: u64) -> Option<NodeTxFees> {
+	let header = chain.get_header_by_height(height).ok()?;
+	let block = chain.get_block(&header.hash()).ok()?;
+	let fees = block
+		.kernels()
+		.iter()
+		.filter_map(|k| match k.features {
+			KernelFeatures::Plain { fee } => Some(fee),
+			KernelFeatures::HeightLocked { fee, .. } => Some(fee),
+			KernelFeatures::Coinbase => None,
+		})
+		.collect::<Vec<u64>>();
+	Some(NodeTxFees {
+		num_inputs: block.inputs().len(),
+		num_outputs: block.outputs().iter().filter(|o| !o.is_coinbase()).count(),
+		num_kernels: fees.len(),
+		fee: fees.iter().sum(),
+	})
+}
+
 /// get output listing traversing pmmr from local
 fn get_outputs_by_pmmr_index_local(
 	chain: Arc<chain::Chain>,
 	start_index: u64,
+	end_index: Option<u64>,
 	max: u64,
 ) -> api::OutputListing {
 	let outputs = chain
 		.unspent_outputs_by_insertion_index(start_index, max)
 		.unwrap();
-	api::OutputListing {
+	let mut listing = api::OutputListing {
 		last_retrieved_index: outputs.0,
 		highest_index: outputs.1,
 		outputs: outputs
@@ -71,7 +168,12 @@ fn get_outputs_by_pmmr_index_local(
 				api::OutputPrintable::from_output(x, chain.clone(), None, true, false).unwrap()
 			})
 			.collect(),
+	};
+	if let Some(e) = end_index {
+		listing.outputs.retain(|o| o.mmr_index <= e);
+		listing.last_retrieved_index = listing.last_retrieved_index.min(e);
 	}
+	listing
 }
 
 /// Adds a block with a given reward to the chain and mines it
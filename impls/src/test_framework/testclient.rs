@@ -28,13 +28,15 @@ use crate::libwallet;
 use crate::libwallet::api_impl::foreign;
 use crate::libwallet::slate_versions::v2::SlateV2;
 use crate::libwallet::{
-	NodeClient, NodeVersionInfo, Slate, TxWrapper, WalletInst, WalletLCProvider,
+	NodeBlockOutputs, NodeClient, NodeClientStats, NodeHeaderInfo, NodeOutputs, NodePoolEntry,
+	NodeStatus, NodeTxFees, NodeVersionInfo, Slate, TxWrapper, WalletInst, WalletLCProvider,
 };
 use crate::util;
 use crate::util::secp::key::SecretKey;
 use crate::util::secp::pedersen;
 use crate::util::secp::pedersen::Commitment;
 use crate::util::{Mutex, RwLock};
+use chrono::{DateTime, Utc};
 use failure::ResultExt;
 use serde_json;
 use std::collections::HashMap;
@@ -85,6 +87,11 @@ where
 	pub rx: Receiver<WalletProxyMessage>,
 	/// queue control
 	pub running: Arc<AtomicBool>,
+	/// Whether posted transactions are held in the mempool rather than mined
+	/// right away
+	pub hold_posted: Arc<AtomicBool>,
+	/// Transactions held in the mempool, with the time they were posted
+	pub pool: Arc<Mutex<Vec<(Transaction, DateTime<Utc>)>>>,
 }
 
 impl<'a, L, C, K> WalletProxy<'a, L, C, K>
@@ -116,6 +123,8 @@ where
 			rx: rx,
 			wallets: HashMap::new(),
 			running: Arc::new(AtomicBool::new(false)),
+			hold_posted: Arc::new(AtomicBool::new(false)),
+			pool: Arc::new(Mutex::new(vec![])),
 		};
 		retval
 	}
@@ -149,6 +158,11 @@ where
 				"get_chain_height" => self.get_chain_height(m)?,
 				"get_outputs_from_node" => self.get_outputs_from_node(m)?,
 				"get_outputs_by_pmmr_index" => self.get_outputs_by_pmmr_index(m)?,
+				"get_header_by_height" => self.get_header_by_height(m)?,
+				"get_block_by_kernel" => self.get_block_by_kernel(m)?,
+				"get_block_outputs" => self.get_block_outputs(m)?,
+				"get_block_fees" => self.get_block_fees(m)?,
+				"get_pool_entries" => self.get_pool_entries(m)?,
 				"send_tx_slate" => self.send_tx_slate(m)?,
 				"post_tx" => self.post_tx(m)?,
 				_ => panic!("Unknown Wallet Proxy Message"),
@@ -172,7 +186,8 @@ where
 		}
 	}
 
-	/// post transaction to the chain (and mine it, taking the reward)
+	/// post transaction to the chain (and mine it, taking the reward), or
+	/// to the mempool if posted transactions are held
 	fn post_tx(&mut self, m: WalletProxyMessage) -> Result<WalletProxyMessage, libwallet::Error> {
 		let dest_wallet = self.wallets.get_mut(&m.sender_id).unwrap().1.clone();
 		let dest_wallet_mask = self.wallets.get_mut(&m.sender_id).unwrap().2.clone();
@@ -189,12 +204,16 @@ where
 				"Error parsing TxWrapper: tx".to_owned(),
 			))?;
 
-		super::award_block_to_wallet(
-			&self.chain,
-			vec![&tx],
-			dest_wallet,
-			(&dest_wallet_mask).as_ref(),
-		)?;
+		if self.hold_posted.load(Ordering::Relaxed) {
+			self.pool.lock().push((tx, Utc::now()));
+		} else {
+			super::award_block_to_wallet(
+				&self.chain,
+				vec![&tx],
+				dest_wallet,
+				(&dest_wallet_mask).as_ref(),
+			)?;
+		}
 
 		Ok(WalletProxyMessage {
 			sender_id: "node".to_owned(),
@@ -283,6 +302,100 @@ where
 		})
 	}
 
+	/// get block header by height
+	fn get_header_by_height(
+		&mut self,
+		m: WalletProxyMessage,
+	) -> Result<WalletProxyMessage, libwallet::Error> {
+		let height = m.body.parse::<u64>().unwrap();
+		let header = super::get_header_by_height_local(&self.chain, height);
+		Ok(WalletProxyMessage {
+			sender_id: "node".to_owned(),
+			dest: m.sender_id,
+			method: m.method,
+			body: serde_json::to_string(&header).unwrap(),
+		})
+	}
+
+	/// get block including a kernel
+	fn get_block_by_kernel(
+		&mut self,
+		m: WalletProxyMessage,
+	) -> Result<WalletProxyMessage, libwallet::Error> {
+		let split = m.body.split(",").collect::<Vec<&str>>();
+		let excess = Commitment::from_vec(util::from_hex(split[0].to_owned()).unwrap());
+		let min_height = split[1].parse::<u64>().ok();
+		let max_height = split[2].parse::<u64>().ok();
+		let header = super::get_block_by_kernel_local(&self.chain, &excess, min_height, max_height);
+		Ok(WalletProxyMessage {
+			sender_id: "node".to_owned(),
+			dest: m.sender_id,
+			method: m.method,
+			body: serde_json::to_string(&header).unwrap(),
+		})
+	}
+
+	/// get the fees of the transactions of a block
+	fn get_block_fees(
+		&mut self,
+		m: WalletProxyMessage,
+	) -> Result<WalletProxyMessage, libwallet::Error> {
+		let height = m.body.parse::<u64>().unwrap();
+		let fees = super::get_block_fees_local(&self.chain, height);
+		Ok(WalletProxyMessage {
+			sender_id: "node".to_owned(),
+			dest: m.sender_id,
+			method: m.method,
+			body: serde_json::to_string(&fees).unwrap(),
+		})
+	}
+
+	/// get the transactions in the mempool, leaving out those mined since they
+	/// were posted
+	fn get_pool_entries(
+		&mut self,
+		m: WalletProxyMessage,
+	) -> Result<WalletProxyMessage, libwallet::Error> {
+		let mut pool = self.pool.lock();
+		let chain = self.chain.clone();
+		pool.retain(|(tx, _)| {
+			tx.kernels()
+				.iter()
+				.all(|k| super::get_block_by_kernel_local(&chain, &k.excess, None, None).is_none())
+		});
+		let mut entries = vec![];
+		for (tx, tx_at) in pool.iter() {
+			for k in tx.kernels() {
+				let entry = NodePoolEntry {
+					src: "Broadcast".to_owned(),
+					tx_at: Some(*tx_at),
+				};
+				entries.push((util::to_hex(k.excess.0.to_vec()), entry));
+			}
+		}
+		Ok(WalletProxyMessage {
+			sender_id: "node".to_owned(),
+			dest: m.sender_id,
+			method: m.method,
+			body: serde_json::to_string(&entries).unwrap(),
+		})
+	}
+
+	/// get the outputs of a block with their proofs
+	fn get_block_outputs(
+		&mut self,
+		m: WalletProxyMessage,
+	) -> Result<WalletProxyMessage, libwallet::Error> {
+		let height = m.body.parse::<u64>().unwrap();
+		let outputs = super::get_block_outputs_local(self.chain.clone(), height);
+		Ok(WalletProxyMessage {
+			sender_id: "node".to_owned(),
+			dest: m.sender_id,
+			method: m.method,
+			body: serde_json::to_string(&outputs).unwrap(),
+		})
+	}
+
 	/// get api outputs
 	fn get_outputs_by_pmmr_index(
 		&mut self,
@@ -290,8 +403,10 @@ where
 	) -> Result<WalletProxyMessage, libwallet::Error> {
 		let split = m.body.split(",").collect::<Vec<&str>>();
 		let start_index = split[0].parse::<u64>().unwrap();
-		let max = split[1].parse::<u64>().unwrap();
-		let ol = super::get_outputs_by_pmmr_index_local(self.chain.clone(), start_index, max);
+		let end_index = split[1].parse::<u64>().ok();
+		let max = split[2].parse::<u64>().unwrap();
+		let ol =
+			super::get_outputs_by_pmmr_index_local(self.chain.clone(), start_index, end_index, max);
 		Ok(WalletProxyMessage {
 			sender_id: "node".to_owned(),
 			dest: m.sender_id,
@@ -311,6 +426,8 @@ pub struct LocalWalletClient {
 	pub rx: Arc<Mutex<Receiver<WalletProxyMessage>>>,
 	/// my tx queue
 	pub tx: Arc<Mutex<Sender<WalletProxyMessage>>>,
+	/// chain the simulated node runs
+	pub chain_type: ChainTypes,
 }
 
 impl LocalWalletClient {
@@ -322,6 +439,7 @@ impl LocalWalletClient {
 			proxy_tx: Arc::new(Mutex::new(proxy_rx)),
 			rx: Arc::new(Mutex::new(rx)),
 			tx: Arc::new(Mutex::new(tx)),
+			chain_type: ChainTypes::AutomatedTesting,
 		}
 	}
 
@@ -367,9 +485,25 @@ impl NodeClient for LocalWalletClient {
 	}
 	fn set_node_url(&mut self, _node_url: &str) {}
 	fn set_node_api_secret(&mut self, _node_api_secret: Option<String>) {}
+	fn chain_type(&self) -> ChainTypes {
+		self.chain_type.clone()
+	}
+	fn set_chain_type(&mut self, chain_type: ChainTypes) {
+		self.chain_type = chain_type;
+	}
 	fn get_version_info(&mut self) -> Option<NodeVersionInfo> {
 		None
 	}
+	/// The test chain is always in sync, with no peers
+	fn get_status(&self) -> Result<NodeStatus, libwallet::Error> {
+		Ok(NodeStatus {
+			protocol_version: 2,
+			user_agent: "test".to_owned(),
+			connections: 0,
+			height: self.get_chain_height()?,
+			sync_status: "no_sync".to_owned(),
+		})
+	}
 	/// Posts a transaction to a grin node
 	/// In this case it will create a new block with award rewarded to
 	fn post_tx(&self, tx: &TxWrapper, _fluff: bool) -> Result<(), libwallet::Error> {
@@ -419,7 +553,7 @@ impl NodeClient for LocalWalletClient {
 	fn get_outputs_from_node(
 		&self,
 		wallet_outputs: Vec<pedersen::Commitment>,
-	) -> Result<HashMap<pedersen::Commitment, (String, u64, u64)>, libwallet::Error> {
+	) -> Result<NodeOutputs, libwallet::Error> {
 		let query_params: Vec<String> = wallet_outputs
 			.iter()
 			.map(|commit| format!("{}", util::to_hex(commit.as_ref().to_vec())))
@@ -447,12 +581,143 @@ impl NodeClient for LocalWalletClient {
 				(util::to_hex(out.commit.to_vec()), out.height, out.mmr_index),
 			);
 		}
-		Ok(api_outputs)
+		Ok(NodeOutputs::new(api_outputs))
+	}
+
+	fn get_header_by_height(&self, height: u64) -> Result<NodeHeaderInfo, libwallet::Error> {
+		let m = WalletProxyMessage {
+			sender_id: self.id.clone(),
+			dest: self.node_url().to_owned(),
+			method: "get_header_by_height".to_owned(),
+			body: format!("{}", height),
+		};
+		{
+			let p = self.proxy_tx.lock();
+			p.send(m).context(libwallet::ErrorKind::ClientCallback(
+				"Get header by height send".to_owned(),
+			))?;
+		}
+		let r = self.rx.lock();
+		let m = r.recv().unwrap();
+		let header: Option<NodeHeaderInfo> = serde_json::from_str(&m.body).unwrap();
+		match header {
+			Some(h) => Ok(h),
+			None => Err(libwallet::ErrorKind::ClientCallback(format!(
+				"No header at height {}",
+				height
+			)))?,
+		}
+	}
+
+	fn get_block_by_kernel(
+		&self,
+		excess: &pedersen::Commitment,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Option<NodeHeaderInfo>, libwallet::Error> {
+		// excess, min height, max height (empty if none)
+		let opt_str = |h: Option<u64>| match h {
+			Some(h) => format!("{}", h),
+			None => "".to_owned(),
+		};
+		let query_str = format!(
+			"{},{},{}",
+			util::to_hex(excess.as_ref().to_vec()),
+			opt_str(min_height),
+			opt_str(max_height)
+		);
+		let m = WalletProxyMessage {
+			sender_id: self.id.clone(),
+			dest: self.node_url().to_owned(),
+			method: "get_block_by_kernel".to_owned(),
+			body: query_str,
+		};
+		{
+			let p = self.proxy_tx.lock();
+			p.send(m).context(libwallet::ErrorKind::ClientCallback(
+				"Get block by kernel send".to_owned(),
+			))?;
+		}
+		let r = self.rx.lock();
+		let m = r.recv().unwrap();
+		Ok(serde_json::from_str(&m.body).unwrap())
+	}
+
+	fn get_block_outputs(&self, height: u64) -> Result<Option<NodeBlockOutputs>, libwallet::Error> {
+		let m = WalletProxyMessage {
+			sender_id: self.id.clone(),
+			dest: self.node_url().to_owned(),
+			method: "get_block_outputs".to_owned(),
+			body: format!("{}", height),
+		};
+		{
+			let p = self.proxy_tx.lock();
+			p.send(m).context(libwallet::ErrorKind::ClientCallback(
+				"Get block outputs send".to_owned(),
+			))?;
+		}
+		let r = self.rx.lock();
+		let m = r.recv().unwrap();
+		Ok(serde_json::from_str(&m.body).unwrap())
+	}
+
+	/// Posted transactions sit in the mempool only while the proxy holds them
+	fn get_pool_entries(
+		&self,
+	) -> Result<HashMap<pedersen::Commitment, NodePoolEntry>, libwallet::Error> {
+		let m = WalletProxyMessage {
+			sender_id: self.id.clone(),
+			dest: self.node_url().to_owned(),
+			method: "get_pool_entries".to_owned(),
+			body: "".to_owned(),
+		};
+		{
+			let p = self.proxy_tx.lock();
+			p.send(m).context(libwallet::ErrorKind::ClientCallback(
+				"Get pool entries send".to_owned(),
+			))?;
+		}
+		let r = self.rx.lock();
+		let m = r.recv().unwrap();
+		let entries: Vec<(String, NodePoolEntry)> = serde_json::from_str(&m.body).unwrap();
+		Ok(entries
+			.into_iter()
+			.map(|(excess, e)| (Commitment::from_vec(util::from_hex(excess).unwrap()), e))
+			.collect())
+	}
+
+	fn get_block_fees(&self, height: u64) -> Result<Option<NodeTxFees>, libwallet::Error> {
+		let m = WalletProxyMessage {
+			sender_id: self.id.clone(),
+			dest: self.node_url().to_owned(),
+			method: "get_block_fees".to_owned(),
+			body: format!("{}", height),
+		};
+		{
+			let p = self.proxy_tx.lock();
+			p.send(m).context(libwallet::ErrorKind::ClientCallback(
+				"Get block fees send".to_owned(),
+			))?;
+		}
+		let r = self.rx.lock();
+		let m = r.recv().unwrap();
+		Ok(serde_json::from_str(&m.body).unwrap())
+	}
+
+	/// The mempool is always empty
+	fn get_pool_fees(&self) -> Result<Vec<NodeTxFees>, libwallet::Error> {
+		Ok(vec![])
+	}
+
+	/// Calls to the simulated node aren't recorded
+	fn get_stats(&self, _reset: bool) -> NodeClientStats {
+		NodeClientStats::new()
 	}
 
 	fn get_outputs_by_pmmr_index(
 		&self,
-		start_height: u64,
+		start_index: u64,
+		end_index: Option<u64>,
 		max_outputs: u64,
 	) -> Result<
 		(
@@ -462,8 +727,12 @@ impl NodeClient for LocalWalletClient {
 		),
 		libwallet::Error,
 	> {
-		// start index, max
-		let query_str = format!("{},{}", start_height, max_outputs);
+		// start index, end index (empty if none), max
+		let end_str = match end_index {
+			Some(e) => format!("{}", e),
+			None => "".to_owned(),
+		};
+		let query_str = format!("{},{},{}", start_index, end_str, max_outputs);
 		let m = WalletProxyMessage {
 			sender_id: self.id.clone(),
 			dest: self.node_url().to_owned(),
@@ -507,3 +776,199 @@ where
 	K: Keychain + 'a,
 {
 }
+
+/// Lies a node tells, for tests of how the wallet checks what the node says
+#[derive(Clone, Debug, Default)]
+pub struct NodeLies {
+	/// Outputs reported unspent at the given (height, mmr index), whether they're on
+	/// chain or not
+	pub outputs: HashMap<Commitment, (u64, u64)>,
+	/// Whether the rangeproofs of the outputs of blocks are corrupted
+	pub bad_proofs: bool,
+	/// Outputs whose query fails, as if the node never answered it
+	pub unanswered: Vec<Commitment>,
+	/// Outputs reported as not on chain, as if their block had been orphaned
+	pub orphaned: Vec<Commitment>,
+	/// Nodes at the given urls, lagging the given number of blocks behind
+	pub lagging: HashMap<String, u64>,
+	/// Nodes at the given urls following a fork, whose headers hash differently
+	pub forked: Vec<String>,
+	/// Nodes at the given urls that don't answer
+	pub down: Vec<String>,
+	/// Sync status reported, as if the node were syncing
+	pub sync_status: Option<String>,
+}
+
+/// Node client answering as the simulated node does, but for the lies it's told to
+/// tell, which can be changed as a test goes
+#[derive(Clone)]
+pub struct LyingNodeClient {
+	/// Client of the honest simulated node
+	pub inner: LocalWalletClient,
+	/// Lies told
+	pub lies: Arc<RwLock<NodeLies>>,
+	/// Url of the node asked, which picks the lies told about the chain
+	url: String,
+}
+
+impl LyingNodeClient {
+	/// new, telling no lies until told to
+	pub fn new(inner: LocalWalletClient) -> Self {
+		let url = inner.node_url().to_owned();
+		LyingNodeClient {
+			inner,
+			lies: Arc::new(RwLock::new(NodeLies::default())),
+			url,
+		}
+	}
+
+	/// Fails if the node asked is down
+	fn answer(&self) -> Result<NodeLies, libwallet::Error> {
+		let lies = self.lies.read().clone();
+		if lies.down.contains(&self.url) {
+			return Err(libwallet::ErrorKind::ClientCallback(format!(
+				"Node {} is down",
+				self.url
+			)))?;
+		}
+		Ok(lies)
+	}
+}
+
+impl NodeClient for LyingNodeClient {
+	fn node_url(&self) -> &str {
+		&self.url
+	}
+	fn node_api_secret(&self) -> Option<String> {
+		self.inner.node_api_secret()
+	}
+	fn set_node_url(&mut self, node_url: &str) {
+		self.url = node_url.to_owned();
+	}
+	fn set_node_api_secret(&mut self, node_api_secret: Option<String>) {
+		self.inner.set_node_api_secret(node_api_secret)
+	}
+	fn chain_type(&self) -> ChainTypes {
+		self.inner.chain_type()
+	}
+	fn set_chain_type(&mut self, chain_type: ChainTypes) {
+		self.inner.set_chain_type(chain_type)
+	}
+	fn get_version_info(&mut self) -> Option<NodeVersionInfo> {
+		self.inner.get_version_info()
+	}
+	/// The node is syncing if told to
+	fn get_status(&self) -> Result<NodeStatus, libwallet::Error> {
+		let lies = self.answer()?;
+		let mut status = self.inner.get_status()?;
+		if let Some(s) = lies.sync_status {
+			status.sync_status = s;
+		}
+		Ok(status)
+	}
+	fn post_tx(&self, tx: &TxWrapper, fluff: bool) -> Result<(), libwallet::Error> {
+		self.inner.post_tx(tx, fluff)
+	}
+	/// Lagging nodes are behind the simulated node
+	fn get_chain_height(&self) -> Result<u64, libwallet::Error> {
+		let lies = self.answer()?;
+		let height = self.inner.get_chain_height()?;
+		let lag = lies.lagging.get(&self.url).cloned().unwrap_or(0);
+		Ok(height.saturating_sub(lag))
+	}
+
+	/// The outputs lied about are reported wherever they are
+	fn get_outputs_from_node(
+		&self,
+		wallet_outputs: Vec<Commitment>,
+	) -> Result<NodeOutputs, libwallet::Error> {
+		let lies = self.lies.read().clone();
+		let mut res = self.inner.get_outputs_from_node(wallet_outputs.clone())?;
+		for commit in wallet_outputs {
+			if lies.unanswered.contains(&commit) {
+				res.outputs.remove(&commit);
+				res.failed.push(commit);
+				res.errors.push("Outputs by id query timed out".to_owned());
+			} else if lies.orphaned.contains(&commit) {
+				res.outputs.remove(&commit);
+			} else if let Some((height, mmr_index)) = lies.outputs.get(&commit) {
+				let commit_hex = util::to_hex(commit.as_ref().to_vec());
+				res.outputs
+					.insert(commit, (commit_hex, *height, *mmr_index));
+			}
+		}
+		Ok(res)
+	}
+
+	/// Forked nodes hash their headers differently
+	fn get_header_by_height(&self, height: u64) -> Result<NodeHeaderInfo, libwallet::Error> {
+		let lies = self.answer()?;
+		let mut header = self.inner.get_header_by_height(height)?;
+		if lies.forked.contains(&self.url) {
+			header.hash = match header.hash.starts_with("00") {
+				true => format!("ff{}", &header.hash[2..]),
+				false => format!("00{}", &header.hash[2..]),
+			};
+		}
+		Ok(header)
+	}
+
+	fn get_block_by_kernel(
+		&self,
+		excess: &Commitment,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Option<NodeHeaderInfo>, libwallet::Error> {
+		self.inner
+			.get_block_by_kernel(excess, min_height, max_height)
+	}
+
+	/// Rangeproofs are corrupted if the node is told to
+	fn get_block_outputs(&self, height: u64) -> Result<Option<NodeBlockOutputs>, libwallet::Error> {
+		let mut block = self.inner.get_block_outputs(height)?;
+		if self.lies.read().bad_proofs {
+			for o in block.iter_mut().flat_map(|b| b.outputs.iter_mut()) {
+				o.proof = o.proof.as_ref().map(|p| match p.starts_with("00") {
+					true => format!("ff{}", &p[2..]),
+					false => format!("00{}", &p[2..]),
+				});
+			}
+		}
+		Ok(block)
+	}
+
+	fn get_pool_entries(
+		&self,
+	) -> Result<HashMap<pedersen::Commitment, NodePoolEntry>, libwallet::Error> {
+		self.inner.get_pool_entries()
+	}
+
+	fn get_block_fees(&self, height: u64) -> Result<Option<NodeTxFees>, libwallet::Error> {
+		self.inner.get_block_fees(height)
+	}
+
+	fn get_pool_fees(&self) -> Result<Vec<NodeTxFees>, libwallet::Error> {
+		self.inner.get_pool_fees()
+	}
+
+	fn get_stats(&self, reset: bool) -> NodeClientStats {
+		self.inner.get_stats(reset)
+	}
+
+	fn get_outputs_by_pmmr_index(
+		&self,
+		start_index: u64,
+		end_index: Option<u64>,
+		max_outputs: u64,
+	) -> Result<
+		(
+			u64,
+			u64,
+			Vec<(Commitment, pedersen::RangeProof, bool, u64, u64)>,
+		),
+		libwallet::Error,
+	> {
+		self.inner
+			.get_outputs_by_pmmr_index(start_index, end_index, max_outputs)
+	}
+}
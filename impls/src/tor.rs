@@ -0,0 +1,208 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tor onion service the foreign API listener is reached through. A tor process
+//! is run with a torrc of the wallet's own, publishing the listener as an onion
+//! service whose keys are kept in the wallet's data directory, so that its
+//! address doesn't change between runs.
+
+use crate::libwallet::{Error, ErrorKind};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// Directory of the wallet's data directory holding the state of its tor process
+pub const TOR_DIR: &str = "tor";
+/// Address of the SOCKS proxy of the tor process run by the wallet, when no other
+/// proxy is set
+pub const DEFAULT_TOR_SOCKS_ADDR: &str = "127.0.0.1:59050";
+/// Seconds allowed for tor to connect to the network
+const BOOTSTRAP_TIMEOUT_SECS: u64 = 180;
+/// Length of a v3 onion address, without its .onion suffix
+const ONION_V3_ADDRESS_LEN: usize = 56;
+
+/// A tor process publishing a listener as an onion service, stopped when dropped
+pub struct TorProcess {
+	child: Child,
+	onion_address: String,
+}
+
+impl TorProcess {
+	/// Run `tor_binary`, publishing the listener at `listen_addr` as an onion service
+	/// on port 80 and serving a SOCKS proxy at `socks_addr` if given. Returns once tor
+	/// is connected to the network and the onion service can be reached.
+	pub fn start(
+		tor_binary: &str,
+		data_dir: &Path,
+		listen_addr: &str,
+		socks_addr: Option<&str>,
+	) -> Result<TorProcess, Error> {
+		let tor_dir = data_dir.join(TOR_DIR);
+		let service_dir = tor_dir.join("onion_service");
+		create_private_dir(&tor_dir)?;
+		create_private_dir(&service_dir)?;
+
+		let torrc = tor_dir.join("torrc");
+		let conf = format!(
+			"SocksPort {}\nDataDirectory {}\nHiddenServiceDir {}\nHiddenServicePort 80 {}\n",
+			socks_addr.unwrap_or("0"),
+			tor_dir.join("data").display(),
+			service_dir.display(),
+			listen_addr,
+		);
+		fs::write(&torrc, conf)
+			.map_err(|e| ErrorKind::Tor(format!("Unable to write {}: {}", torrc.display(), e)))?;
+
+		let mut child = Command::new(tor_binary)
+			.arg("-f")
+			.arg(&torrc)
+			.stdout(Stdio::piped())
+			.stderr(Stdio::null())
+			.spawn()
+			.map_err(|e| ErrorKind::Tor(format!("Unable to run {}: {}", tor_binary, e)))?;
+
+		// tor logs to stdout, which is drained for as long as it runs
+		let stdout = child.stdout.take().unwrap();
+		let (tx, rx) = mpsc::channel();
+		thread::spawn(move || {
+			for line in BufReader::new(stdout).lines() {
+				match line {
+					Ok(l) => {
+						debug!("tor: {}", l);
+						let _ = tx.send(l);
+					}
+					Err(_) => break,
+				}
+			}
+		});
+
+		// Stopped on drop if it fails to start
+		let mut process = TorProcess {
+			child,
+			onion_address: String::new(),
+		};
+		let deadline = Instant::now() + Duration::from_secs(BOOTSTRAP_TIMEOUT_SECS);
+		loop {
+			let now = Instant::now();
+			if now >= deadline {
+				return Err(ErrorKind::Tor(format!(
+					"Not connected to the network after {}s",
+					BOOTSTRAP_TIMEOUT_SECS
+				)))?;
+			}
+			match rx.recv_timeout(deadline - now) {
+				Ok(line) => {
+					if line.contains("[err]") {
+						return Err(ErrorKind::Tor(line))?;
+					}
+					if line.contains("Bootstrapped 100%") {
+						break;
+					}
+				}
+				Err(mpsc::RecvTimeoutError::Timeout) => continue,
+				Err(mpsc::RecvTimeoutError::Disconnected) => {
+					return Err(ErrorKind::Tor(
+						"tor exited before connecting to the network".to_owned(),
+					))?;
+				}
+			}
+		}
+
+		let hostname = service_dir.join("hostname");
+		process.onion_address = fs::read_to_string(&hostname)
+			.map_err(|e| ErrorKind::Tor(format!("Unable to read {}: {}", hostname.display(), e)))?
+			.trim()
+			.to_owned();
+		Ok(process)
+	}
+
+	/// Onion address the listener is published at
+	pub fn onion_address(&self) -> &str {
+		&self.onion_address
+	}
+}
+
+impl Drop for TorProcess {
+	fn drop(&mut self) {
+		let _ = self.child.kill();
+		let _ = self.child.wait();
+	}
+}
+
+/// Create a directory only the user can access, as tor requires of the
+/// directories of onion services
+fn create_private_dir(dir: &Path) -> Result<(), Error> {
+	let failed =
+		|e: std::io::Error| ErrorKind::Tor(format!("Unable to create {}: {}", dir.display(), e));
+	fs::create_dir_all(dir).map_err(failed)?;
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+		fs::set_permissions(dir, fs::Permissions::from_mode(0o700)).map_err(failed)?;
+	}
+	Ok(())
+}
+
+/// Url of the wallet listening at an onion address, given as an http url or as a
+/// bare address, with or without its .onion suffix
+pub fn onion_url(dest: &str) -> Option<Url> {
+	let dest = dest.trim();
+	let mut url: Url = if dest.contains("://") {
+		dest.parse().ok()?
+	} else {
+		format!("http://{}", dest).parse().ok()?
+	};
+	if url.scheme() != "http" {
+		return None;
+	}
+	let host = url.host_str()?.to_lowercase();
+	if !host.ends_with(".onion") {
+		if host.len() != ONION_V3_ADDRESS_LEN || !host.chars().all(|c| c.is_ascii_alphanumeric()) {
+			return None;
+		}
+		url.set_host(Some(&format!("{}.onion", host))).ok()?;
+	}
+	Some(url)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn onion_destinations() {
+		let address = "2gzyxa5ihm7nsggfxnu52rck2vv4rvmdlkiu3zzui5du4xyclen53wid";
+		let url = format!("http://{}.onion/", address);
+		assert_eq!(onion_url(address).unwrap().as_str(), url);
+		assert_eq!(
+			onion_url(&format!("{}.onion", address)).unwrap().as_str(),
+			url
+		);
+		assert_eq!(onion_url(&url).unwrap().as_str(), url);
+		assert_eq!(
+			onion_url(&format!("http://{}.onion:3415", address))
+				.unwrap()
+				.port(),
+			Some(3415)
+		);
+		assert!(onion_url(&format!("https://{}.onion", address)).is_none());
+		assert!(onion_url("http://127.0.0.1:3415").is_none());
+		assert!(onion_url("example.com").is_none());
+	}
+}
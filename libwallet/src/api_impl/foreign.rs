@@ -17,15 +17,19 @@ use strum::IntoEnumIterator;
 
 use crate::grin_keychain::Keychain;
 use crate::grin_util::secp::key::SecretKey;
-use crate::internal::{tx, updater};
+use crate::internal::{selection, tx, updater};
+use crate::payment_proof;
 use crate::slate_versions::SlateVersion;
 use crate::{
-	BlockFees, CbData, Error, ErrorKind, NodeClient, Slate, TxLogEntryType, VersionInfo,
-	WalletBackend,
+	BlockFees, CbData, Error, ErrorKind, FailedReceive, ForeignApiKey, NodeClient, Slate,
+	SlateDirection, SourceFilter, TxLogEntryType, VersionInfo, WalletBackend,
 };
+use chrono::Utc;
 
 const FOREIGN_API_VERSION: u16 = 2;
 const USER_MESSAGE_MAX_LEN: usize = 256;
+/// Number of failed receives kept in the log, the oldest are dropped first
+const MAX_FAILED_RECEIVES: usize = 1000;
 
 /// Return the version info
 pub fn check_version() -> VersionInfo {
@@ -47,9 +51,117 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
+	check_not_frozen(&mut *w)?;
 	updater::build_coinbase(&mut *w, keychain_mask, block_fees, test_mode)
 }
 
+/// Build a coinbase transaction keyed by the block height
+pub fn build_coinbase_at_height<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	block_fees: &BlockFees,
+	test_mode: bool,
+) -> Result<CbData, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	check_not_frozen(&mut *w)?;
+	updater::build_coinbase_at_height(&mut *w, keychain_mask, block_fees, test_mode)
+}
+
+/// Fail if the wallet is frozen
+pub fn check_not_frozen<'a, T: ?Sized, C, K>(w: &mut T) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	match w.freeze_state()? {
+		Some(f) => Err(ErrorKind::WalletFrozen(f.since.to_rfc3339()))?,
+		None => Ok(()),
+	}
+}
+
+/// Check that slates from the origin are received, counting the rejected ones
+pub fn check_slate_source<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	origin: &str,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut filter = w.source_filter()?;
+	if filter.allows(origin) {
+		return Ok(());
+	}
+	let origin = SourceFilter::normalize(origin);
+	*filter.hits.entry(origin.clone()).or_insert(0) += 1;
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_source_filter(&filter)?;
+	batch.commit()?;
+	Err(ErrorKind::SourceRejected(origin))?
+}
+
+/// Log an incoming slate that couldn't be received, along with the reason
+pub fn record_failed_receive<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate: &Slate,
+	origin: Option<&str>,
+	reason: &Error,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let entry = FailedReceive {
+		slate_id: slate.id,
+		origin: origin.map(|o| SourceFilter::normalize(o)),
+		amount: slate.amount,
+		reason: format!("{}", reason),
+		created: Utc::now(),
+	};
+	let expired: Vec<FailedReceive> = {
+		let count = w.failed_receive_iter().count();
+		w.failed_receive_iter()
+			.take((count + 1).saturating_sub(MAX_FAILED_RECEIVES))
+			.collect()
+	};
+	let mut batch = w.batch(keychain_mask)?;
+	for e in expired.iter() {
+		batch.delete_failed_receive(e)?;
+	}
+	batch.save_failed_receive(&entry)?;
+	batch.commit()?;
+	Ok(())
+}
+
+/// Account the slates sent to the foreign listener with the given key are
+/// received into
+pub fn foreign_api_key_account<'a, T: ?Sized, C, K>(w: &mut T, key: &str) -> Result<String, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let api_key = w
+		.get_foreign_api_key(&ForeignApiKey::hash(key))?
+		.ok_or_else(|| ErrorKind::ForeignApiKey("Unknown key".to_owned()))?;
+	match w.acct_path_iter().find(|a| a.path == api_key.parent_key_id) {
+		Some(a) => Ok(a.label),
+		None => Err(ErrorKind::ForeignApiKey(format!(
+			"Account of key {} doesn't exist",
+			api_key.id
+		)))?,
+	}
+}
+
 /// verify slate messages
 pub fn verify_slate_messages(slate: &Slate) -> Result<(), Error> {
 	slate.verify_messages()
@@ -69,16 +181,41 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
+	check_not_frozen(&mut *w)?;
+	updater::archive_slate(
+		&mut *w,
+		keychain_mask,
+		slate,
+		SlateDirection::Inbound,
+		"receive_tx",
+	);
 	let mut ret_slate = slate.clone();
-	let parent_key_id = match dest_acct_name {
-		Some(d) => {
+	// A payment to a one-time address goes to the account of the address, once
+	let mut onetime_address = None;
+	if let Some(id) = ret_slate.onetime_address_id() {
+		let address = w
+			.get_onetime_address(&id)?
+			.ok_or_else(|| ErrorKind::OnetimeAddress(format!("Unknown one-time address {}", id)))?;
+		if address.tx_slate_id.is_some() {
+			let msg = format!("One-time address {} was already paid to", id);
+			return Err(ErrorKind::OnetimeAddress(msg).into());
+		}
+		if address.is_expired() {
+			let msg = format!("One-time address {} expired", id);
+			return Err(ErrorKind::OnetimeAddress(msg).into());
+		}
+		onetime_address = Some(address);
+	}
+	let parent_key_id = match (&onetime_address, dest_acct_name) {
+		(Some(a), _) => a.parent_key_id.clone(),
+		(None, Some(d)) => {
 			let pm = w.get_acct_path(d.to_owned())?;
 			match pm {
 				Some(p) => p.path,
 				None => w.parent_key_id(),
 			}
 		}
-		None => w.parent_key_id(),
+		(None, None) => w.parent_key_id(),
 	};
 	// Don't do this multiple times
 	let tx = updater::retrieve_txs(
@@ -102,7 +239,13 @@ where
 		None => None,
 	};
 
-	tx::add_output_to_slate(
+	ret_slate.check_limits()?;
+	ret_slate.verify_attachments()?;
+
+	let contribute_input = ret_slate.payjoin_requested()
+		&& w.config().payjoin_contribute_input.unwrap_or(false)
+		&& selection::payjoin_contribution_allowed(&mut *w);
+	let context = tx::add_output_to_slate(
 		&mut *w,
 		keychain_mask,
 		&mut ret_slate,
@@ -110,9 +253,31 @@ where
 		1,
 		message,
 		false,
+		contribute_input,
 		use_test_rng,
 	)?;
+	{
+		let keychain = w.keychain(keychain_mask)?;
+		let (sec_key, id) = (&context.sec_key, context.participant_id);
+		payment_proof::sign_payment_proof(&keychain, &mut ret_slate, sec_key, id)?;
+	}
 	tx::update_message(&mut *w, keychain_mask, &mut ret_slate)?;
+	tx::update_attachments(&mut *w, keychain_mask, &ret_slate, &context)?;
+	if let Some(mut address) = onetime_address {
+		address.tx_slate_id = Some(ret_slate.id);
+		address.amount = Some(ret_slate.amount);
+		let mut batch = w.batch(keychain_mask)?;
+		batch.save_onetime_address(&address)?;
+		batch.commit()?;
+	}
+	let step = "receive_tx";
+	updater::archive_slate(
+		&mut *w,
+		keychain_mask,
+		&ret_slate,
+		SlateDirection::Outbound,
+		step,
+	);
 	Ok(ret_slate)
 }
 
@@ -127,15 +292,22 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
+	check_not_frozen(&mut *w)?;
+	let step = "finalize_invoice_tx";
+	updater::archive_slate(&mut *w, keychain_mask, slate, SlateDirection::Inbound, step);
 	let mut sl = slate.clone();
+	sl.check_limits()?;
+	sl.verify_attachments()?;
 	let context = w.get_private_context(keychain_mask, sl.id.as_bytes(), 1)?;
 	tx::complete_tx(&mut *w, keychain_mask, &mut sl, 1, &context)?;
 	tx::update_stored_tx(&mut *w, &mut sl, true)?;
 	tx::update_message(&mut *w, keychain_mask, &mut sl)?;
+	tx::update_attachments(&mut *w, keychain_mask, &sl, &context)?;
 	{
 		let mut batch = w.batch(keychain_mask)?;
 		batch.delete_private_context(sl.id.as_bytes(), 1)?;
 		batch.commit()?;
 	}
+	updater::archive_slate(&mut *w, keychain_mask, &sl, SlateDirection::Outbound, step);
 	Ok(sl)
 }
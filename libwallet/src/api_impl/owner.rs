@@ -14,25 +14,73 @@
 
 //! Generic implementation of owner API functions
 
+use chrono::{DateTime, Duration, Utc};
+use rand::{thread_rng, Rng};
+use std::cmp;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use std::thread;
+use std::time::Instant;
 use uuid::Uuid;
 
+use crate::grin_core::consensus;
 use crate::grin_core::core::hash::Hashed;
-use crate::grin_core::core::Transaction;
+use crate::grin_core::core::verifier_cache::LruVerifierCache;
+use crate::grin_core::core::{
+	amount_from_hr_string, amount_to_hr_string, transaction, Transaction, Weighting,
+};
+use crate::grin_core::libtx::tx_fee;
 use crate::grin_core::ser;
 use crate::grin_util;
 use crate::grin_util::secp::key::SecretKey;
+use crate::grin_util::secp::pedersen;
+use crate::grin_util::RwLock;
 
-use crate::grin_keychain::{Identifier, Keychain};
+use crate::api_impl::foreign;
+use crate::config::WalletConfig;
+use crate::cost_basis::{self, FiatPrice};
+use crate::grin_keychain::{Identifier, Keychain, SwitchCommitmentType};
 use crate::internal::{keys, selection, tx, updater};
-use crate::slate::Slate;
-use crate::types::{AcctPathMapping, NodeClient, TxLogEntry, TxWrapper, WalletBackend, WalletInfo};
-use crate::{Error, ErrorKind};
+use crate::payment_proof::{self, PaymentProof};
+use crate::slate::{ParticipantAttachmentData, Slate, SlateDescription, REFUND_ADDRESS_ATTACHMENT};
+use crate::slate_versions::v2::TransactionV2;
+use crate::totp::TotpSettings;
+use crate::types::{
+	AcctPathMapping, ApprovalToken, ArchivedSlate, BalanceHold, ChangeRecord, CoinbaseReport,
+	Context, DeliveryReceipt, DerivationScheme, FailedReceive, ForeignApiKey, FreezeState,
+	NodeClient, NodeClientStats, NodeStatus, NodeTxFees, OnetimeAddress, OutputStatus,
+	PendingDispatch, RefreshReport, SlateDirection, SourceFilter, TxApproval, TxLogEntry,
+	TxWrapper, VaultSettings, WalletBackend, WalletEvent, WalletInfo,
+};
+use crate::view_only::{
+	self, ViewOnlyAttestation, ViewOnlyBundle, ViewOnlyOutput, ViewOnlyOutputAudit, WatchOnlyKey,
+	VIEW_ONLY_BUNDLE_VERSION,
+};
 use crate::{
-	InitTxArgs, IssueInvoiceTxArgs, NodeHeightResult, OutputCommitMapping, TxLogEntryType,
+	ChangeOutput, ChildIndexScan, CostBasisLot, FeeRateEstimate, IndexRebuildReport, InitTxArgs,
+	InitTxSendArgs, IssueInvoiceTxArgs, LedgerDirection, LedgerEntry, LedgerMismatch,
+	NodeHeightResult, OperationState, OperationStatus, OutputCommitMapping, OutputData,
+	OutputDerivationAudit, OutputIntegrityIssue, PendingOperation, PendingOperations,
+	ReconcileArgs, ReconciliationReport, RefundAddress, RefusedTxState, ReserveOutputsArgs,
+	RestoreScan, SendMetrics, SlateAttachmentArgs, SweepArgs, SweepBatch, SweepPlan, TotpSetup,
+	TxCancelResult, TxDetails, TxExportEntry, TxFilter, TxLogEntryType, WalletStats,
 };
+use crate::{Error, ErrorKind};
 
 const USER_MESSAGE_MAX_LEN: usize = 256;
 
+/// Recent blocks a fee rate estimate looks at, if not given
+const FEE_ESTIMATE_BLOCKS: u64 = 10;
+
+/// Operations ended whose status is kept
+const MAX_ENDED_OPERATIONS: usize = 100;
+
+/// Longest an output reservation can be held for
+const MAX_RESERVATION_SECS: u64 = 366 * 24 * 3600;
+
+/// Longest the sends of a vault account can be locked for, a week of blocks
+const MAX_VAULT_LOCK_BLOCKS: u64 = 7 * 1440;
+
 /// List of accounts
 pub fn accounts<'a, T: ?Sized, C, K>(w: &mut T) -> Result<Vec<AcctPathMapping>, Error>
 where
@@ -43,18 +91,57 @@ where
 	keys::accounts(&mut *w)
 }
 
-/// new account path
+/// new account path, its keys derived with the given scheme
 pub fn create_account_path<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
 	label: &str,
+	scheme: DerivationScheme,
 ) -> Result<Identifier, Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	keys::new_acct_path(&mut *w, keychain_mask, label)
+	keys::new_acct_path(&mut *w, keychain_mask, label, scheme)
+}
+
+/// next child index of an account
+pub fn get_next_child_index<'a, T: ?Sized, C, K>(w: &mut T, label: &str) -> Result<u32, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	keys::next_child_index(&mut *w, label)
+}
+
+/// set next child index of an account
+pub fn set_next_child_index<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	label: &str,
+	index: u32,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	keys::set_next_child_index(&mut *w, keychain_mask, label, index)
+}
+
+/// scan child indices used by an account
+pub fn scan_child_indices<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	label: &str,
+) -> Result<ChildIndexScan, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	keys::scan_child_indices(&mut *w, label)
 }
 
 /// set active account
@@ -125,13 +212,13 @@ where
 	))
 }
 
-/// Retrieve summary info
-pub fn retrieve_summary_info<'a, T: ?Sized, C, K>(
+/// Retrieve outputs saved since a change cursor, along with the cursor to give next
+pub fn retrieve_outputs_since<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
 	refresh_from_node: bool,
-	minimum_confirmations: u64,
-) -> Result<(bool, WalletInfo), Error>
+	since: u64,
+) -> Result<(bool, u64, Vec<OutputCommitMapping>), Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
@@ -144,354 +231,3016 @@ where
 		validated = update_outputs(w, keychain_mask, false)?;
 	}
 
-	let wallet_info = updater::retrieve_info(&mut *w, &parent_key_id, minimum_confirmations)?;
-	Ok((validated, wallet_info))
+	// Spent outputs are included, for pollers to learn they were spent
+	let outputs =
+		updater::retrieve_outputs(&mut *w, keychain_mask, true, None, Some(&parent_key_id))?
+			.into_iter()
+			.filter(|o| o.output.change_seq > since)
+			.collect();
+	Ok((validated, w.change_seq()?, outputs))
 }
 
-/// Initiate tx as sender
-pub fn init_send_tx<'a, T: ?Sized, C, K>(
+/// Retrieve txs saved since a change cursor, along with the cursor to give next
+pub fn retrieve_txs_since<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
-	args: InitTxArgs,
-	use_test_rng: bool,
-) -> Result<Slate, Error>
+	refresh_from_node: bool,
+	since: u64,
+) -> Result<(bool, u64, Vec<TxLogEntry>), Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let parent_key_id = match args.src_acct_name {
-		Some(d) => {
-			let pm = w.get_acct_path(d)?;
-			match pm {
-				Some(p) => p.path,
-				None => w.parent_key_id(),
-			}
-		}
-		None => w.parent_key_id(),
-	};
-
-	let message = match args.message {
-		Some(mut m) => {
-			m.truncate(USER_MESSAGE_MAX_LEN);
-			Some(m)
-		}
-		None => None,
-	};
-
-	let mut slate = tx::new_tx_slate(&mut *w, args.amount, 2, use_test_rng)?;
+	let parent_key_id = w.parent_key_id();
 
-	// if we just want to estimate, don't save a context, just send the results
-	// back
-	if let Some(true) = args.estimate_only {
-		let (total, fee) = tx::estimate_send_tx(
-			&mut *w,
-			keychain_mask,
-			args.amount,
-			args.minimum_confirmations,
-			args.max_outputs as usize,
-			args.num_change_outputs as usize,
-			args.selection_strategy_is_use_all,
-			&parent_key_id,
-		)?;
-		slate.amount = total;
-		slate.fee = fee;
-		return Ok(slate);
+	let mut validated = false;
+	if refresh_from_node {
+		validated = update_outputs(w, keychain_mask, false)?;
 	}
 
-	let context = tx::add_inputs_to_slate(
-		&mut *w,
-		keychain_mask,
-		&mut slate,
-		args.minimum_confirmations,
-		args.max_outputs as usize,
-		args.num_change_outputs as usize,
-		args.selection_strategy_is_use_all,
-		&parent_key_id,
-		0,
-		message,
-		true,
-		use_test_rng,
-	)?;
-
-	// Save the aggsig context in our DB for when we
-	// recieve the transaction back
-	{
-		let mut batch = w.batch(keychain_mask)?;
-		batch.save_private_context(slate.id.as_bytes(), 0, &context)?;
-		batch.commit()?;
-	}
-	if let Some(v) = args.target_slate_version {
-		slate.version_info.orig_version = v;
-	}
-	Ok(slate)
+	let txs = updater::retrieve_txs(&mut *w, None, None, Some(&parent_key_id), false)?
+		.into_iter()
+		.filter(|t| t.change_seq > since)
+		.collect();
+	Ok((validated, w.change_seq()?, txs))
 }
 
-/// Initiate a transaction as the recipient (invoicing)
-pub fn issue_invoice_tx<'a, T: ?Sized, C, K>(
+/// Retrieve summary info
+pub fn retrieve_summary_info<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
-	args: IssueInvoiceTxArgs,
-	use_test_rng: bool,
-) -> Result<Slate, Error>
+	refresh_from_node: bool,
+	minimum_confirmations: u64,
+) -> Result<(bool, WalletInfo), Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let parent_key_id = match args.dest_acct_name {
-		Some(d) => {
-			let pm = w.get_acct_path(d)?;
-			match pm {
-				Some(p) => p.path,
-				None => w.parent_key_id(),
-			}
-		}
-		None => w.parent_key_id(),
-	};
-
-	let message = match args.message {
-		Some(mut m) => {
-			m.truncate(USER_MESSAGE_MAX_LEN);
-			Some(m)
-		}
-		None => None,
-	};
-
-	let mut slate = tx::new_tx_slate(&mut *w, args.amount, 2, use_test_rng)?;
-	let context = tx::add_output_to_slate(
-		&mut *w,
-		keychain_mask,
-		&mut slate,
-		&parent_key_id,
-		1,
-		message,
-		true,
-		use_test_rng,
-	)?;
-
-	// Save the aggsig context in our DB for when we
-	// recieve the transaction back
-	{
-		let mut batch = w.batch(keychain_mask)?;
-		println!("Saving private context: {:?}", slate.id.as_bytes());
-		batch.save_private_context(slate.id.as_bytes(), 1, &context)?;
-		batch.commit()?;
-	}
+	let parent_key_id = w.parent_key_id();
 
-	if let Some(v) = args.target_slate_version {
-		slate.version_info.orig_version = v;
+	let mut validated = false;
+	if refresh_from_node {
+		validated = update_outputs(w, keychain_mask, false)?;
 	}
 
-	Ok(slate)
+	let wallet_info = updater::retrieve_info(&mut *w, &parent_key_id, minimum_confirmations)?;
+	Ok((validated, wallet_info))
 }
 
-/// Receive an invoice tx, essentially adding inputs to whatever
-/// output was specified
-pub fn process_invoice_tx<'a, T: ?Sized, C, K>(
+/// Retrieve coinbase report
+pub fn retrieve_coinbase_report<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
-	slate: &Slate,
-	args: InitTxArgs,
-	use_test_rng: bool,
-) -> Result<Slate, Error>
+	refresh_from_node: bool,
+) -> Result<(bool, CoinbaseReport), Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let mut ret_slate = slate.clone();
-	let parent_key_id = match args.src_acct_name {
-		Some(d) => {
-			let pm = w.get_acct_path(d.to_owned())?;
-			match pm {
-				Some(p) => p.path,
-				None => w.parent_key_id(),
-			}
-		}
-		None => w.parent_key_id(),
-	};
-	// Don't do this multiple times
-	let tx = updater::retrieve_txs(
-		&mut *w,
-		None,
-		Some(ret_slate.id),
-		Some(&parent_key_id),
-		use_test_rng,
-	)?;
-	for t in &tx {
-		if t.tx_type == TxLogEntryType::TxSent {
-			return Err(ErrorKind::TransactionAlreadyReceived(ret_slate.id.to_string()).into());
-		}
-	}
-
-	let message = match args.message {
-		Some(mut m) => {
-			m.truncate(USER_MESSAGE_MAX_LEN);
-			Some(m)
-		}
-		None => None,
-	};
-
-	// update slate current height
-	ret_slate.height = w.w2n_client().get_chain_height()?;
-
-	let context = tx::add_inputs_to_slate(
-		&mut *w,
-		keychain_mask,
-		&mut ret_slate,
-		args.minimum_confirmations,
-		args.max_outputs as usize,
-		args.num_change_outputs as usize,
-		args.selection_strategy_is_use_all,
-		&parent_key_id,
-		0,
-		message,
-		false,
-		use_test_rng,
-	)?;
-
-	// Save the aggsig context in our DB for when we
-	// recieve the transaction back
-	{
-		let mut batch = w.batch(keychain_mask)?;
-		batch.save_private_context(slate.id.as_bytes(), 0, &context)?;
-		batch.commit()?;
-	}
+	let parent_key_id = w.parent_key_id();
 
-	if let Some(v) = args.target_slate_version {
-		ret_slate.version_info.orig_version = v;
+	let mut validated = false;
+	if refresh_from_node {
+		validated = update_outputs(w, keychain_mask, false)?;
 	}
 
-	Ok(ret_slate)
+	let report = updater::retrieve_coinbase_report(&mut *w, &parent_key_id)?;
+	Ok((validated, report))
 }
 
-/// Lock sender outputs
-pub fn tx_lock_outputs<'a, T: ?Sized, C, K>(
+/// Retrieve the wallet fingerprint
+pub fn wallet_fingerprint<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
-	slate: &Slate,
-	participant_id: usize,
-) -> Result<(), Error>
+) -> Result<String, Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let context = w.get_private_context(keychain_mask, slate.id.as_bytes(), participant_id)?;
-	selection::lock_tx_context(&mut *w, keychain_mask, slate, &context)
+	if let Some(key) = w.watch_only_key() {
+		return key.fingerprint();
+	}
+	let keychain = w.keychain(keychain_mask)?;
+	keys::wallet_fingerprint(&keychain)
 }
 
-/// Finalize slate
-pub fn finalize_tx<'a, T: ?Sized, C, K>(
+/// Root public key of the wallet, which a watch-only wallet can be created from
+pub fn watch_only_key<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
-	slate: &Slate,
-) -> Result<Slate, Error>
+) -> Result<WatchOnlyKey, Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let mut sl = slate.clone();
-	let context = w.get_private_context(keychain_mask, sl.id.as_bytes(), 0)?;
-	tx::complete_tx(&mut *w, keychain_mask, &mut sl, 0, &context)?;
-	tx::update_stored_tx(&mut *w, &mut sl, false)?;
-	tx::update_message(&mut *w, keychain_mask, &mut sl)?;
+	match w.watch_only_key() {
+		Some(key) => Ok(key),
+		None => view_only::watch_only_key(&w.keychain(keychain_mask)?),
+	}
+}
+
+/// Hold outputs for an upcoming scheduled payment
+pub fn reserve_outputs<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	args: ReserveOutputsArgs,
+) -> Result<Vec<OutputData>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if args.label.is_empty() {
+		return Err(ErrorKind::GenericError(
+			"Output reservations need a label".to_owned(),
+		))?;
+	}
+	if args.expires_in_secs > MAX_RESERVATION_SECS {
+		return Err(ErrorKind::GenericError(format!(
+			"Outputs can't be held for more than {} seconds",
+			MAX_RESERVATION_SECS
+		)))?;
+	}
+	let parent_key_id = w.parent_key_id();
+	let current_height = w.w2n_client().get_chain_height()?;
+	updater::refresh_outputs(&mut *w, keychain_mask, &parent_key_id, false)?.check_complete()?;
+	let expires = Utc::now() + Duration::seconds(args.expires_in_secs as i64);
+	selection::reserve_outputs(
+		&mut *w,
+		keychain_mask,
+		&args.label,
+		args.amount,
+		current_height,
+		args.minimum_confirmations,
+		expires,
+		&parent_key_id,
+	)
+}
+
+/// Release the outputs held for a reservation
+pub fn release_reservation<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	label: &str,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = w.parent_key_id();
+	selection::release_reservation(&mut *w, keychain_mask, label, &parent_key_id)?;
+	Ok(())
+}
+
+/// Hold outputs of the active account given by their commitments, for a reference
+pub fn lock_outputs<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	commitments: &[String],
+	ttl_secs: u64,
+	reference: &str,
+) -> Result<Vec<OutputData>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if reference.is_empty() {
+		return Err(ErrorKind::GenericError(
+			"Output locks need a reference".to_owned(),
+		))?;
+	}
+	if commitments.is_empty() {
+		return Err(ErrorKind::GenericError("No output to lock".to_owned()))?;
+	}
+	if ttl_secs > MAX_RESERVATION_SECS {
+		return Err(ErrorKind::GenericError(format!(
+			"Outputs can't be held for more than {} seconds",
+			MAX_RESERVATION_SECS
+		)))?;
+	}
+	let parent_key_id = w.parent_key_id();
+	updater::refresh_outputs(&mut *w, keychain_mask, &parent_key_id, false)?.check_complete()?;
+	let expires = Utc::now() + Duration::seconds(ttl_secs as i64);
+	selection::lock_outputs(
+		&mut *w,
+		keychain_mask,
+		commitments,
+		reference,
+		expires,
+		&parent_key_id,
+	)
+}
+
+/// Release the outputs of the active account held for a reference, returning them
+pub fn unlock_by_ref<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	reference: &str,
+) -> Result<Vec<OutputData>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = w.parent_key_id();
+	selection::release_reservation(&mut *w, keychain_mask, reference, &parent_key_id)
+}
+
+/// Plan the transactions sweeping the whole spendable balance of an account. The
+/// outputs are spent smallest first, as many per transaction as the constraints and
+/// the weight of a block allow, so each transaction is the one
+/// [`init_send_tx`](fn.init_send_tx.html) builds when sending its amount with all
+/// outputs used, and creates no change.
+pub fn plan_sweep<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	args: SweepArgs,
+) -> Result<SweepPlan, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = match args.src_acct_name.as_ref() {
+		Some(d) => match w.get_acct_path(d.to_owned())? {
+			Some(p) => p.path,
+			None => w.parent_key_id(),
+		},
+		None => w.parent_key_id(),
+	};
+	// However many outputs there are to sweep, each transaction has to fit in a block
+	// along with the coinbase
+	let fixed = (consensus::BLOCK_OUTPUT_WEIGHT + consensus::BLOCK_KERNEL_WEIGHT) as u64;
+	let per_input = consensus::BLOCK_INPUT_WEIGHT as u64;
+	let block_room = (w.w2n_client().max_block_weight() as u64).saturating_sub(fixed);
+	let max_weight = match args.max_tx_weight {
+		Some(w) => cmp::min(w, block_room),
+		None => block_room,
+	};
+	let per_tx = cmp::min(
+		args.max_outputs_per_tx as u64,
+		max_weight.saturating_sub(fixed) / per_input,
+	);
+	if per_tx == 0 {
+		return Err(ErrorKind::Sweep(
+			"The constraints don't allow a transaction spending a single output".to_owned(),
+		))?;
+	}
+
+	let current_height = w.w2n_client().get_chain_height()?;
+	updater::refresh_outputs(&mut *w, keychain_mask, &parent_key_id, false)?.check_complete()?;
+	let keychain = w.keychain(keychain_mask)?;
+	let now = Utc::now();
+	let mut eligible = w
+		.iter()
+		.filter(|out| {
+			out.root_key_id == parent_key_id
+				&& out.eligible_to_spend(current_height, args.minimum_confirmations)
+				&& !out.is_reserved(None, now)
+		})
+		.collect::<Vec<OutputData>>();
+	eligible.sort_by_key(|out| out.value);
+
+	let mut plan = SweepPlan {
+		src_acct_name: args.src_acct_name.clone(),
+		minimum_confirmations: args.minimum_confirmations,
+		batches: vec![],
+		amount: 0,
+		fee: 0,
+		dust: 0,
+	};
+	for outputs in eligible.chunks(per_tx as usize) {
+		let total: u64 = outputs.iter().map(|o| o.value).sum();
+		let fee = tx_fee(outputs.len(), 1, 1, None);
+		if total <= fee {
+			plan.dust += total;
+			continue;
+		}
+		let mut inputs = vec![];
+		for o in outputs {
+			let commit = match o.commit.clone() {
+				Some(c) => c,
+				None => grin_util::to_hex(
+					keychain
+						.commit(o.value, &o.key_id, &SwitchCommitmentType::Regular)?
+						.0
+						.to_vec(),
+				),
+			};
+			inputs.push(commit);
+		}
+		plan.amount += total - fee;
+		plan.fee += fee;
+		plan.batches.push(SweepBatch {
+			inputs,
+			amount: total - fee,
+			fee,
+		});
+	}
+	if let Some(budget) = args.fee_budget {
+		if plan.fee > budget {
+			return Err(ErrorKind::Sweep(format!(
+				"The {} transactions need {} in fees, more than the budget of {}",
+				plan.batches.len(),
+				amount_to_hr_string(plan.fee, false),
+				amount_to_hr_string(budget, false)
+			)))?;
+		}
+	}
+	Ok(plan)
+}
+
+/// Hold part of the spendable balance of the active account
+pub fn create_hold<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	amount: u64,
+	ttl_secs: u64,
+	reference: &str,
+) -> Result<BalanceHold, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if reference.is_empty() {
+		return Err(ErrorKind::GenericError(
+			"Balance holds need a reference".to_owned(),
+		))?;
+	}
+	if ttl_secs > MAX_RESERVATION_SECS {
+		return Err(ErrorKind::GenericError(format!(
+			"Balance can't be held for more than {} seconds",
+			MAX_RESERVATION_SECS
+		)))?;
+	}
+	let parent_key_id = w.parent_key_id();
+	updater::refresh_outputs(&mut *w, keychain_mask, &parent_key_id, false)?.check_complete()?;
+	let now = Utc::now();
+	let holds: Vec<BalanceHold> = w
+		.balance_hold_iter()
+		.filter(|h| h.parent_key_id == parent_key_id)
+		.collect();
+	if holds
+		.iter()
+		.any(|h| h.reference == reference && h.is_active(now))
 	{
+		return Err(ErrorKind::GenericError(format!(
+			"Balance hold {} already exists",
+			reference
+		)))?;
+	}
+
+	// Holds are checked against the balance spendable with a single confirmation
+	let info = updater::retrieve_info(&mut *w, &parent_key_id, 1)?;
+	if amount > info.amount_available {
+		return Err(ErrorKind::NotEnoughFunds {
+			available: info.amount_available,
+			available_disp: amount_to_hr_string(info.amount_available, false),
+			needed: amount,
+			needed_disp: amount_to_hr_string(amount, false),
+		})?;
+	}
+
+	let hold = BalanceHold {
+		reference: reference.to_owned(),
+		parent_key_id,
+		amount,
+		created: now,
+		expires: now + Duration::seconds(ttl_secs as i64),
+	};
+	let mut batch = w.batch(keychain_mask)?;
+	for h in holds.iter().filter(|h| !h.is_active(now)) {
+		batch.delete_balance_hold(h)?;
+	}
+	batch.save_balance_hold(&hold)?;
+	batch.commit()?;
+	Ok(hold)
+}
+
+/// Release a balance hold of the active account
+pub fn release_hold<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	reference: &str,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = w.parent_key_id();
+	let hold = w
+		.balance_hold_iter()
+		.find(|h| h.parent_key_id == parent_key_id && h.reference == reference);
+	if let Some(h) = hold {
 		let mut batch = w.batch(keychain_mask)?;
-		batch.delete_private_context(sl.id.as_bytes(), 0)?;
+		batch.delete_balance_hold(&h)?;
 		batch.commit()?;
 	}
-	Ok(sl)
+	Ok(())
+}
+
+/// Filter on the origins of incoming slates
+pub fn retrieve_source_filter<'a, T: ?Sized, C, K>(w: &mut T) -> Result<SourceFilter, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	w.source_filter()
+}
+
+/// Incoming slates that couldn't be received, oldest first
+pub fn retrieve_failed_receives<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	tx_slate_id: Option<Uuid>,
+) -> Result<Vec<FailedReceive>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let entries = w
+		.failed_receive_iter()
+		.filter(|e| tx_slate_id.map_or(true, |id| e.slate_id == id))
+		.collect();
+	Ok(entries)
+}
+
+/// Sends prepared and not dispatched yet
+pub fn retrieve_pending_dispatches<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	tx_slate_id: Option<Uuid>,
+) -> Result<Vec<PendingDispatch>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let entries = w
+		.pending_dispatch_iter()
+		.filter(|e| tx_slate_id.map_or(true, |id| e.slate_id == id))
+		.collect();
+	Ok(entries)
+}
+
+/// Save a send prepared and not dispatched yet, or the progress of its dispatch
+pub fn save_pending_dispatch<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	entry: &PendingDispatch,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_pending_dispatch(entry)?;
+	batch.commit()?;
+	Ok(())
+}
+
+/// Drop a prepared send once it's dispatched
+pub fn delete_pending_dispatch<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate_id: &Uuid,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if w.get_pending_dispatch(slate_id)?.is_none() {
+		return Ok(());
+	}
+	let mut batch = w.batch(keychain_mask)?;
+	batch.delete_pending_dispatch(slate_id)?;
+	batch.commit()?;
+	Ok(())
+}
+
+/// Record the acknowledgement of a slate by the wallet it was sent to on the
+/// transaction log entries of the slate, if the wallet has any yet
+pub fn save_delivery_receipt<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	tx_slate_id: &Uuid,
+	receipt: &DeliveryReceipt,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let txs: Vec<TxLogEntry> = w
+		.tx_log_iter()
+		.filter(|t| t.tx_slate_id == Some(*tx_slate_id))
+		.collect();
+	if txs.is_empty() {
+		return Ok(());
+	}
+	let mut batch = w.batch(keychain_mask)?;
+	for mut t in txs {
+		t.delivery_receipt = Some(receipt.clone());
+		let parent_key_id = t.parent_key_id.clone();
+		batch.save_tx_log_entry(t, &parent_key_id)?;
+	}
+	batch.commit()?;
+	Ok(())
+}
+
+/// Hand out a one-time address for a payment into the active account, through
+/// the foreign listener reachable at `listener_url`
+pub fn get_onetime_address<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	listener_url: &str,
+	label: Option<String>,
+	expires_in_hours: Option<u64>,
+) -> Result<OnetimeAddress, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let listener_url = listener_url.trim();
+	if !listener_url.starts_with("http://") && !listener_url.starts_with("https://") {
+		return Err(ErrorKind::OnetimeAddress(format!(
+			"Invalid listener url '{}', expected an http(s) url",
+			listener_url
+		)))?;
+	}
+	if listener_url.contains('#') {
+		return Err(ErrorKind::OnetimeAddress(format!(
+			"Listener url '{}' already has a fragment",
+			listener_url
+		)))?;
+	}
+	let id = Uuid::new_v4();
+	let created = Utc::now();
+	let address = OnetimeAddress {
+		id,
+		address: OnetimeAddress::format(listener_url, &id),
+		parent_key_id: w.parent_key_id(),
+		label,
+		created,
+		expires: expires_in_hours.map(|h| created + Duration::hours(h as i64)),
+		tx_slate_id: None,
+		amount: None,
+		confirmed: false,
+	};
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_onetime_address(&address)?;
+	batch.commit()?;
+	Ok(address)
+}
+
+/// One-time addresses handed out by the wallet, oldest first
+pub fn retrieve_onetime_addresses<'a, T: ?Sized, C, K>(
+	w: &mut T,
+) -> Result<Vec<OnetimeAddress>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut addresses: Vec<OnetimeAddress> = w.onetime_address_iter().collect();
+	addresses.sort_by_key(|a| a.created);
+	Ok(addresses)
+}
+
+/// Create a key for the foreign listener, slates sent with it being received
+/// into the given account. The key itself is only returned here, the wallet
+/// only keeps its hash.
+pub fn create_foreign_api_key<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	account_label: &str,
+	label: Option<String>,
+) -> Result<(String, ForeignApiKey), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = match w.get_acct_path(account_label.to_owned())? {
+		Some(a) => a.path,
+		None => return Err(ErrorKind::UnknownAccountLabel(account_label.to_owned()))?,
+	};
+	let key_bytes: [u8; 16] = thread_rng().gen();
+	let key = grin_util::to_hex(key_bytes.to_vec());
+	let api_key = ForeignApiKey {
+		id: Uuid::new_v4(),
+		key_hash: ForeignApiKey::hash(&key),
+		parent_key_id,
+		label,
+		created: Utc::now(),
+	};
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_foreign_api_key(&api_key)?;
+	batch.commit()?;
+	Ok((key, api_key))
+}
+
+/// Keys of the foreign listener, oldest first
+pub fn retrieve_foreign_api_keys<'a, T: ?Sized, C, K>(
+	w: &mut T,
+) -> Result<Vec<ForeignApiKey>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut keys: Vec<ForeignApiKey> = w.foreign_api_key_iter().collect();
+	keys.sort_by_key(|k| k.created);
+	Ok(keys)
+}
+
+/// Revoke a key of the foreign listener, slates sent with it being refused
+pub fn revoke_foreign_api_key<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	id: &Uuid,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let api_key = w
+		.foreign_api_key_iter()
+		.find(|k| k.id == *id)
+		.ok_or_else(|| ErrorKind::ForeignApiKey(format!("Unknown key {}", id)))?;
+	let mut batch = w.batch(keychain_mask)?;
+	batch.delete_foreign_api_key(&api_key)?;
+	batch.commit()?;
+	Ok(())
+}
+
+/// Create a token for an operator creating or approving txs requiring approval.
+/// The token itself is only returned here, the wallet only keeps its hash.
+pub fn create_approval_token<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	label: Option<String>,
+) -> Result<(String, ApprovalToken), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let token_bytes: [u8; 32] = thread_rng().gen();
+	let token = grin_util::to_hex(token_bytes.to_vec());
+	let record = ApprovalToken {
+		id: Uuid::new_v4(),
+		token_hash: ApprovalToken::hash(&token),
+		label,
+		created: Utc::now(),
+	};
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_approval_token(&record)?;
+	batch.commit()?;
+	Ok((token, record))
+}
+
+/// Approval tokens of the wallet, oldest first
+pub fn retrieve_approval_tokens<'a, T: ?Sized, C, K>(w: &mut T) -> Result<Vec<ApprovalToken>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut tokens: Vec<ApprovalToken> = w.approval_token_iter().collect();
+	tokens.sort_by_key(|t| t.created);
+	Ok(tokens)
+}
+
+/// Revoke an approval token, which can't create or approve txs anymore
+pub fn revoke_approval_token<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	id: &Uuid,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let record = w
+		.approval_token_iter()
+		.find(|t| t.id == *id)
+		.ok_or_else(|| {
+			ErrorKind::TransactionApprovalError(format!("Unknown approval token {}", id))
+		})?;
+	let mut batch = w.batch(keychain_mask)?;
+	batch.delete_approval_token(&record)?;
+	batch.commit()?;
+	Ok(())
+}
+
+/// Export the unspent outputs of the wallet, of all its accounts, along with the
+/// rewind hash of the wallet, in a bundle without any spend key
+pub fn export_view_only_bundle<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	refresh_from_node: bool,
+) -> Result<(bool, ViewOnlyBundle), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut validated = false;
+	if refresh_from_node {
+		validated = update_outputs(w, keychain_mask, true)?;
+	}
+	let keychain = w.keychain(keychain_mask)?;
+	let mut outputs = vec![];
+	for o in w.iter() {
+		if o.status != OutputStatus::Unspent && o.status != OutputStatus::Locked {
+			continue;
+		}
+		let commit = match o.commit.as_ref() {
+			Some(c) => pedersen::Commitment::from_vec(
+				grin_util::from_hex(c.clone())
+					.map_err(|_| ErrorKind::Format(format!("Invalid commitment {}", c)))?,
+			),
+			None => keychain.commit(o.value, &o.key_id, &SwitchCommitmentType::Regular)?,
+		};
+		outputs.push(ViewOnlyOutput {
+			commit,
+			value: o.value,
+			height: o.height,
+			is_coinbase: o.is_coinbase,
+		});
+	}
+	outputs.sort_by_key(|o| o.height);
+	let key = watch_only_key(w, keychain_mask)?;
+	let bundle = ViewOnlyBundle {
+		version: VIEW_ONLY_BUNDLE_VERSION,
+		fingerprint: key.fingerprint()?,
+		rewind_hash: key.rewind_hash()?,
+		height: w.last_confirmed_height()?,
+		created: Utc::now(),
+		total: outputs.iter().map(|o| o.value).sum(),
+		outputs,
+	};
+	Ok((validated, bundle))
+}
+
+/// Audit a view-only bundle, possibly of another wallet, against the node: every
+/// output has to be unspent, with a range proof rewinding with the rewind hash of
+/// the bundle to the reported value. The result is signed by this wallet.
+pub fn audit_view_only_bundle<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	bundle: &ViewOnlyBundle,
+) -> Result<ViewOnlyAttestation, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if bundle.version > VIEW_ONLY_BUNDLE_VERSION {
+		let msg = format!("Unsupported view-only bundle version {}", bundle.version);
+		return Err(ErrorKind::Format(msg))?;
+	}
+	keys::check_not_watch_only(w, "signing an attestation")?;
+	let keychain = w.keychain(keychain_mask)?;
+	let builder = bundle.proof_builder()?;
+	let client = w.w2n_client().clone();
+	let height = client.get_chain_height()?;
+	let commits: Vec<pedersen::Commitment> = bundle.outputs.iter().map(|o| o.commit).collect();
+	let node_outputs = client.get_outputs_from_node(commits)?;
+	let mut audits = vec![];
+	for o in bundle.outputs.iter() {
+		let mut audit = ViewOnlyOutputAudit {
+			commit: o.commit,
+			reported_value: o.value,
+			verified_value: None,
+			issue: None,
+		};
+		let mmr_index = match node_outputs.outputs.get(&o.commit) {
+			Some((_, _, i)) => *i,
+			None => {
+				audit.issue = Some(match node_outputs.failed.contains(&o.commit) {
+					true => "Unable to query the node".to_owned(),
+					false => "Not unspent on the node".to_owned(),
+				});
+				audits.push(audit);
+				continue;
+			}
+		};
+		let (_, _, found) = client.get_outputs_by_pmmr_index(mmr_index, Some(mmr_index), 1)?;
+		audit.verified_value = match found.into_iter().find(|f| f.0 == o.commit) {
+			Some((commit, proof, _, _, _)) => {
+				builder.rewind_value(keychain.secp(), commit, proof)?
+			}
+			None => None,
+		};
+		audit.issue = match audit.verified_value {
+			None => Some("Range proof not made by the wallet of the bundle".to_owned()),
+			Some(v) if v != o.value => Some(format!("Value is {}", amount_to_hr_string(v, false))),
+			Some(_) => None,
+		};
+		audits.push(audit);
+	}
+	ViewOnlyAttestation::new(&keychain, bundle, height, audits)
+}
+
+/// Versions of the slate kept in the slate archive, oldest first
+pub fn get_slate_history<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	slate_id: &Uuid,
+) -> Result<Vec<ArchivedSlate>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut history: Vec<ArchivedSlate> = w
+		.archived_slate_iter()
+		.filter(|s| s.slate_id == *slate_id)
+		.collect();
+	history.sort_by_key(|s| s.created);
+	Ok(history)
+}
+
+/// Up to `max_records` records of the change journal after the counter `since`,
+/// oldest first
+pub fn retrieve_change_journal<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	since: u64,
+	max_records: usize,
+) -> Result<Vec<ChangeRecord>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	Ok(w.change_journal_iter()
+		.filter(|r| r.counter > since)
+		.take(max_records)
+		.collect())
+}
+
+/// Acknowledge the events of the wallet event queue up to `cursor` for the
+/// consumer, if given, then return up to `max_events` events the consumer
+/// hasn't acknowledged, oldest first
+pub fn poll_events<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	consumer: &str,
+	cursor: Option<u64>,
+	max_events: usize,
+) -> Result<Vec<WalletEvent>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if consumer.is_empty() {
+		return Err(ErrorKind::GenericError(
+			"Event consumer name is empty".to_owned(),
+		))?;
+	}
+	let offset = match cursor {
+		Some(c) => {
+			let mut batch = w.batch(keychain_mask)?;
+			batch.save_event_offset(consumer, c)?;
+			batch.commit()?;
+			c
+		}
+		None => w.event_offset(consumer)?,
+	};
+	let events = w
+		.wallet_event_iter()
+		.skip_while(|e| e.seq <= offset)
+		.take(max_events)
+		.collect();
+	Ok(events)
+}
+
+/// Add an origin to, or remove it from, the blocklist or the allowlist of the
+/// incoming slates filter
+pub fn update_source_filter<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	origin: &str,
+	allowlist: bool,
+	listed: bool,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let origin = SourceFilter::normalize(origin);
+	if origin.is_empty() {
+		return Err(ErrorKind::GenericError(
+			"Slate origins can't be empty".to_owned(),
+		))?;
+	}
+	let mut filter = w.source_filter()?;
+	let list = if allowlist {
+		&mut filter.allowlist
+	} else {
+		&mut filter.blocklist
+	};
+	list.retain(|o| *o != origin);
+	if listed {
+		list.push(origin);
+	}
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_source_filter(&filter)?;
+	batch.commit()?;
+	Ok(())
+}
+
+/// Path of an existing account
+fn account_path<'a, T: ?Sized, C, K>(w: &mut T, account: &str) -> Result<Identifier, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	match w.get_acct_path(account.to_owned())? {
+		Some(p) => Ok(p.path),
+		None => Err(ErrorKind::UnknownAccountLabel(account.to_owned()))?,
+	}
+}
+
+/// Time lock settings of an account, if it's a vault
+pub fn retrieve_vault_settings<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	account: &str,
+) -> Result<Option<VaultSettings>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = account_path(&mut *w, account)?;
+	w.vault_settings(&parent_key_id)
+}
+
+/// Make an account a vault whose sends are locked for `lock_blocks`, or change its
+/// lock. A shorter lock, 0 turning the vault off, only takes effect once the current
+/// lock has passed.
+pub fn set_vault_lock<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	account: &str,
+	lock_blocks: u64,
+) -> Result<VaultSettings, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if lock_blocks > MAX_VAULT_LOCK_BLOCKS {
+		return Err(ErrorKind::GenericError(format!(
+			"Vault sends can't be locked for more than {} blocks",
+			MAX_VAULT_LOCK_BLOCKS
+		)))?;
+	}
+	let parent_key_id = account_path(&mut *w, account)?;
+	let height = w.w2n_client().get_chain_height()?;
+	let current = match w.vault_settings(&parent_key_id)? {
+		Some(v) => v.lock_blocks_at(height),
+		None => 0,
+	};
+	let settings = if lock_blocks >= current {
+		VaultSettings {
+			parent_key_id,
+			lock_blocks,
+			pending_lock_blocks: None,
+			pending_height: 0,
+		}
+	} else {
+		VaultSettings {
+			parent_key_id,
+			lock_blocks: current,
+			pending_lock_blocks: Some(lock_blocks),
+			pending_height: height + current,
+		}
+	};
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_vault_settings(&settings)?;
+	batch.commit()?;
+	Ok(settings)
+}
+
+/// Lock height of the kernels of the sends from an account, 0 if it isn't a vault
+fn vault_lock_height<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	parent_key_id: &Identifier,
+	height: u64,
+) -> Result<u64, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let lock_blocks = match w.vault_settings(parent_key_id)? {
+		Some(v) => v.lock_blocks_at(height),
+		None => 0,
+	};
+	match lock_blocks {
+		0 => Ok(0),
+		l => Ok(height + l),
+	}
+}
+
+/// Whether the wallet is frozen, and since when
+pub fn retrieve_freeze_state<'a, T: ?Sized, C, K>(w: &mut T) -> Result<Option<FreezeState>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	w.freeze_state()
+}
+
+/// Freeze the wallet, keeping the time of the first freeze if already frozen
+pub fn freeze_wallet<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+) -> Result<FreezeState, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if let Some(f) = w.freeze_state()? {
+		return Ok(f);
+	}
+	let state = FreezeState { since: Utc::now() };
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_freeze_state(&state)?;
+	batch.commit()?;
+	warn!("Wallet frozen");
+	Ok(state)
+}
+
+/// Unfreeze the wallet. The caller is responsible for checking the wallet password.
+pub fn unfreeze_wallet<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if w.freeze_state()?.is_none() {
+		return Ok(());
+	}
+	let mut batch = w.batch(keychain_mask)?;
+	batch.delete_freeze_state()?;
+	batch.commit()?;
+	warn!("Wallet unfrozen");
+	Ok(())
+}
+
+/// Whether the owner API requires a second factor for spends and changes of the
+/// security settings of the wallet
+pub fn totp_enabled<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+) -> Result<bool, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	Ok(w.totp_settings(keychain_mask)?
+		.map(|s| s.confirmed)
+		.unwrap_or(false))
+}
+
+/// Set up a new second factor, replacing one not confirmed yet. Codes are only
+/// required once confirmed with [`confirm_totp`](fn.confirm_totp.html).
+pub fn setup_totp<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+) -> Result<TotpSetup, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if totp_enabled(w, keychain_mask)? {
+		return Err(ErrorKind::Totp(
+			"A second factor is already enabled, disable it first".to_owned(),
+		))?;
+	}
+	let (settings, recovery_codes) = TotpSettings::new();
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_totp_settings(&settings)?;
+	batch.commit()?;
+	Ok(TotpSetup {
+		secret: settings.secret_base32(),
+		uri: settings.uri("mwc-wallet"),
+		recovery_codes,
+	})
+}
+
+/// Enable the second factor set up, once a code of it is entered
+pub fn confirm_totp<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	code: &str,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut settings = match w.totp_settings(keychain_mask)? {
+		Some(s) if !s.confirmed => s,
+		Some(_) => return Err(ErrorKind::Totp("Already enabled".to_owned()))?,
+		None => return Err(ErrorKind::Totp("No second factor set up".to_owned()))?,
+	};
+	settings.verify(code, Utc::now().timestamp() as u64, false)?;
+	settings.confirmed = true;
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_totp_settings(&settings)?;
+	batch.commit()?;
+	warn!("Second factor enabled on owner API spends");
+	Ok(())
+}
+
+/// Check a code of the second factor, or a recovery code, if one is enabled
+pub fn verify_totp<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	code: Option<&str>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut settings = match w.totp_settings(keychain_mask)? {
+		Some(s) if s.confirmed => s,
+		_ => return Ok(()),
+	};
+	let code = match code {
+		Some(c) => c,
+		None => return Err(ErrorKind::Totp("A code is required".to_owned()))?,
+	};
+	settings.verify(code, Utc::now().timestamp() as u64, true)?;
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save_totp_settings(&settings)?;
+	batch.commit()?;
+	Ok(())
+}
+
+/// Remove the second factor, which takes one of its codes once enabled
+pub fn disable_totp<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	code: Option<&str>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if w.totp_settings(keychain_mask)?.is_none() {
+		return Ok(());
+	}
+	verify_totp(w, keychain_mask, code)?;
+	let mut batch = w.batch(keychain_mask)?;
+	batch.delete_totp_settings()?;
+	batch.commit()?;
+	warn!("Second factor disabled");
+	Ok(())
+}
+
+/// Hex of the proof address of the wallet
+pub fn payment_proof_address<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+) -> Result<String, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let keychain = w.keychain(keychain_mask)?;
+	let address = payment_proof::payment_proof_address(&keychain)?;
+	Ok(payment_proof::proof_address_to_hex(
+		keychain.secp(),
+		&address,
+	))
+}
+
+/// Payment proof of a transaction sent asking the recipient for one
+pub fn retrieve_payment_proof<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	tx_id: Option<u32>,
+	tx_slate_id: Option<Uuid>,
+) -> Result<PaymentProof, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if tx_id.is_none() && tx_slate_id.is_none() {
+		return Err(ErrorKind::PaymentProof(
+			"A transaction id or a slate id is required".to_owned(),
+		))?;
+	}
+	let txs = updater::retrieve_txs(&mut *w, tx_id, tx_slate_id, None, false)?;
+	match txs.into_iter().filter_map(|t| t.payment_proof).next() {
+		Some(p) => Ok(p),
+		None => Err(ErrorKind::PaymentProof(
+			"No payment proof for the transaction".to_owned(),
+		))?,
+	}
+}
+
+/// Verify a payment proof: both its signatures, and its kernel being on chain.
+/// Returns whether the sender and whether the recipient is this wallet.
+pub fn verify_payment_proof<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	proof: &PaymentProof,
+) -> Result<(bool, bool), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	proof.verify()?;
+	if w.w2n_client()
+		.get_block_by_kernel(&proof.excess, None, None)?
+		.is_none()
+	{
+		return Err(ErrorKind::PaymentProof(
+			"The kernel of the payment isn't on chain".to_owned(),
+		))?;
+	}
+	let keychain = w.keychain(keychain_mask)?;
+	let address = payment_proof::payment_proof_address(&keychain)?;
+	Ok((
+		proof.sender_address == address,
+		proof.recipient_address == address,
+	))
+}
+
+/// Make sure a send leaves the amounts held for other payments unspent. Returns
+/// the hold the send is made for, if any.
+fn check_balance_holds<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	parent_key_id: &Identifier,
+	hold: Option<&str>,
+	minimum_confirmations: u64,
+	amount_with_fee: u64,
+) -> Result<Option<BalanceHold>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let holds = updater::active_balance_holds(&mut *w, parent_key_id);
+	let own = match hold {
+		Some(r) => match holds.iter().find(|h| h.reference == r) {
+			Some(h) => Some(h.clone()),
+			None => {
+				return Err(ErrorKind::GenericError(format!(
+					"No active balance hold {}",
+					r
+				)))?;
+			}
+		},
+		None => None,
+	};
+	let held: u64 = holds
+		.iter()
+		.filter(|h| Some(h.reference.as_str()) != hold)
+		.map(|h| h.amount)
+		.sum();
+	if held > 0 {
+		let info = updater::retrieve_info(&mut *w, parent_key_id, minimum_confirmations)?;
+		let available = info.amount_currently_spendable.saturating_sub(held);
+		if amount_with_fee > available {
+			return Err(ErrorKind::NotEnoughFunds {
+				available,
+				available_disp: amount_to_hr_string(available, false),
+				needed: amount_with_fee,
+				needed_disp: amount_to_hr_string(amount_with_fee, false),
+			})?;
+		}
+	}
+	Ok(own)
+}
+
+/// Add the requested attachments to our participant data in the slate
+fn add_attachments<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate: &mut Slate,
+	context: &Context,
+	attachments: Option<Vec<SlateAttachmentArgs>>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let attachments = match attachments {
+		Some(a) => a,
+		None => return Ok(()),
+	};
+	let keychain = w.keychain(keychain_mask)?;
+	for a in attachments {
+		if a.kind == REFUND_ADDRESS_ATTACHMENT {
+			RefundAddress::parse(&a.content)?;
+		}
+		slate.add_attachment(
+			keychain.secp(),
+			&context.sec_key,
+			context.participant_id,
+			&a.kind,
+			&a.content,
+			a.encrypt,
+		)?;
+	}
+	Ok(())
+}
+
+/// Change outputs of a send initiated by this wallet, from the transaction context
+/// saved for it until it's finalized
+pub fn retrieve_change_outputs<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	tx_slate_id: &Uuid,
+) -> Result<Vec<ChangeOutput>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let context = w.get_private_context(keychain_mask, tx_slate_id.as_bytes(), 0)?;
+	let keychain = w.keychain(keychain_mask)?;
+	let mut change_outputs = vec![];
+	for (key_id, _, value) in context.get_outputs() {
+		let commit = keychain.commit(value, &key_id, &SwitchCommitmentType::Regular)?;
+		change_outputs.push(ChangeOutput {
+			key_id,
+			commit,
+			value,
+		});
+	}
+	Ok(change_outputs)
+}
+
+/// Approval a new send, or invoice paid, has to wait for: when asked for, or
+/// always when the wallet requires sends to be approved
+fn new_tx_approval<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	requires_approval: Option<bool>,
+	approval_token: Option<&String>,
+) -> Result<Option<TxApproval>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let required = w.config().require_tx_approval.unwrap_or(false);
+	if requires_approval != Some(true) && !required {
+		return Ok(None);
+	}
+	match approval_token {
+		Some(t) => Ok(Some(TxApproval::new(tx::approval_token_id(&mut *w, t)?))),
+		None => Err(ErrorKind::TransactionApprovalError(
+			"Transactions requiring approval must be created with an approval token".to_owned(),
+		))?,
+	}
+}
+
+/// Initiate tx as sender
+pub fn init_send_tx<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	args: InitTxArgs,
+	use_test_rng: bool,
+) -> Result<Slate, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	foreign::check_not_frozen(&mut *w)?;
+	check_node_not_stale(&mut *w)?;
+	let parent_key_id = match args.src_acct_name {
+		Some(d) => {
+			let pm = w.get_acct_path(d)?;
+			match pm {
+				Some(p) => p.path,
+				None => w.parent_key_id(),
+			}
+		}
+		None => w.parent_key_id(),
+	};
+
+	let message = match args.message {
+		Some(mut m) => {
+			m.truncate(USER_MESSAGE_MAX_LEN);
+			Some(m)
+		}
+		None => None,
+	};
+
+	let mut slate = tx::new_tx_slate(&mut *w, args.amount, 2, use_test_rng)?;
+	if let Some(id) = args.slate_id {
+		let txs = updater::retrieve_txs(&mut *w, None, Some(id), None, false)?;
+		if !txs.is_empty() {
+			return Err(ErrorKind::GenericError(format!(
+				"Slate id {} is already used by a transaction",
+				id
+			)))?;
+		}
+		slate.id = id;
+	}
+	// sends from a vault can't be mined before its lock has passed
+	slate.lock_height = vault_lock_height(&mut *w, &parent_key_id, slate.height)?;
+
+	let fee_from_amount = args.fee_from_amount.unwrap_or(false);
+
+	// if we just want to estimate, don't save a context, just send the results
+	// back
+	if let Some(true) = args.estimate_only {
+		let (total, fee) = tx::estimate_send_tx(
+			&mut *w,
+			keychain_mask,
+			args.amount,
+			args.minimum_confirmations,
+			args.max_outputs as usize,
+			args.num_change_outputs as usize,
+			args.selection_strategy_is_use_all,
+			fee_from_amount,
+			&parent_key_id,
+			args.reservation.as_ref().map(|r| r.as_str()),
+		)?;
+		check_balance_holds(
+			&mut *w,
+			&parent_key_id,
+			args.hold.as_ref().map(|h| h.as_str()),
+			args.minimum_confirmations,
+			match fee_from_amount {
+				true => args.amount,
+				false => args.amount + fee,
+			},
+		)?;
+		slate.amount = total;
+		slate.fee = fee;
+		return Ok(slate);
+	}
+
+	let approval = new_tx_approval(
+		&mut *w,
+		args.requires_approval,
+		args.approval_token.as_ref(),
+	)?;
+
+	let mut context = tx::add_inputs_to_slate(
+		&mut *w,
+		keychain_mask,
+		&mut slate,
+		args.minimum_confirmations,
+		args.max_outputs as usize,
+		args.num_change_outputs as usize,
+		args.selection_strategy_is_use_all,
+		fee_from_amount,
+		&parent_key_id,
+		args.reservation.as_ref().map(|r| r.as_str()),
+		0,
+		message,
+		true,
+		use_test_rng,
+	)?;
+	let hold = check_balance_holds(
+		&mut *w,
+		&parent_key_id,
+		args.hold.as_ref().map(|h| h.as_str()),
+		args.minimum_confirmations,
+		slate.amount + slate.fee,
+	)?;
+	context.approval = approval;
+	let mut attachments = args.attachments;
+	if let Some(true) = args.payjoin {
+		attachments
+			.get_or_insert(vec![])
+			.push(SlateAttachmentArgs::payjoin());
+	}
+	// Paying to a one-time address, tell the recipient which
+	let onetime_id = args
+		.send_args
+		.as_ref()
+		.and_then(|s| OnetimeAddress::parse_id(&s.dest));
+	if let Some(id) = onetime_id {
+		attachments
+			.get_or_insert(vec![])
+			.push(SlateAttachmentArgs::onetime_address(&id));
+	}
+	if let Some(true) = args.payment_proof {
+		let keychain = w.keychain(keychain_mask)?;
+		let address = payment_proof::payment_proof_address(&keychain)?;
+		let address = payment_proof::proof_address_to_hex(keychain.secp(), &address);
+		attachments
+			.get_or_insert(vec![])
+			.push(SlateAttachmentArgs::payment_proof_request(&address));
+	}
+	add_attachments(&mut *w, keychain_mask, &mut slate, &context, attachments)?;
+
+	// Save the aggsig context in our DB for when we
+	// recieve the transaction back, the hold the send was made for is used up
+	{
+		let mut batch = w.batch(keychain_mask)?;
+		batch.save_private_context(slate.id.as_bytes(), 0, &context)?;
+		if let Some(h) = hold {
+			batch.delete_balance_hold(&h)?;
+		}
+		batch.commit()?;
+	}
+	if let Some(v) = args.target_slate_version {
+		slate.set_serialization_version(v)?;
+	}
+	updater::archive_slate(
+		&mut *w,
+		keychain_mask,
+		&slate,
+		SlateDirection::Outbound,
+		"init_send_tx",
+	);
+	Ok(slate)
+}
+
+/// Initiate a transaction as the recipient (invoicing)
+pub fn issue_invoice_tx<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	args: IssueInvoiceTxArgs,
+	use_test_rng: bool,
+) -> Result<Slate, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = match args.dest_acct_name {
+		Some(d) => {
+			let pm = w.get_acct_path(d)?;
+			match pm {
+				Some(p) => p.path,
+				None => w.parent_key_id(),
+			}
+		}
+		None => w.parent_key_id(),
+	};
+
+	let message = match args.message {
+		Some(mut m) => {
+			m.truncate(USER_MESSAGE_MAX_LEN);
+			Some(m)
+		}
+		None => None,
+	};
+
+	let mut slate = tx::new_tx_slate(&mut *w, args.amount, 2, use_test_rng)?;
+	let context = tx::add_output_to_slate(
+		&mut *w,
+		keychain_mask,
+		&mut slate,
+		&parent_key_id,
+		1,
+		message,
+		true,
+		false,
+		use_test_rng,
+	)?;
+	add_attachments(
+		&mut *w,
+		keychain_mask,
+		&mut slate,
+		&context,
+		args.attachments,
+	)?;
+
+	// Save the aggsig context in our DB for when we
+	// recieve the transaction back
+	{
+		let mut batch = w.batch(keychain_mask)?;
+		println!("Saving private context: {:?}", slate.id.as_bytes());
+		batch.save_private_context(slate.id.as_bytes(), 1, &context)?;
+		batch.commit()?;
+	}
+
+	if let Some(v) = args.target_slate_version {
+		slate.set_serialization_version(v)?;
+	}
+	let step = "issue_invoice_tx";
+	updater::archive_slate(
+		&mut *w,
+		keychain_mask,
+		&slate,
+		SlateDirection::Outbound,
+		step,
+	);
+
+	Ok(slate)
+}
+
+/// Receive an invoice tx, essentially adding inputs to whatever
+/// output was specified
+pub fn process_invoice_tx<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate: &Slate,
+	args: InitTxArgs,
+	use_test_rng: bool,
+) -> Result<Slate, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	foreign::check_not_frozen(&mut *w)?;
+	check_node_not_stale(&mut *w)?;
+	let step = "process_invoice_tx";
+	updater::archive_slate(&mut *w, keychain_mask, slate, SlateDirection::Inbound, step);
+	let mut ret_slate = slate.clone();
+	// An invoice paid requiring approval is signed once approved, given back the
+	// slate returned when it was processed
+	if let Ok(context) = w.get_private_context(keychain_mask, slate.id.as_bytes(), 0) {
+		if let Some(a) = &context.approval {
+			if !a.is_approved() {
+				return Err(ErrorKind::TransactionApprovalRequired(slate.id.to_string()))?;
+			}
+			let _ = ret_slate.fill_round_2(
+				&w.keychain(keychain_mask)?,
+				&context.sec_key,
+				&context.sec_nonce,
+				0,
+			)?;
+			if let Some(v) = args.target_slate_version {
+				ret_slate.set_serialization_version(v)?;
+			}
+			updater::archive_slate(
+				&mut *w,
+				keychain_mask,
+				&ret_slate,
+				SlateDirection::Outbound,
+				step,
+			);
+			return Ok(ret_slate);
+		}
+	}
+	let parent_key_id = match args.src_acct_name {
+		Some(d) => {
+			let pm = w.get_acct_path(d.to_owned())?;
+			match pm {
+				Some(p) => p.path,
+				None => w.parent_key_id(),
+			}
+		}
+		None => w.parent_key_id(),
+	};
+	// Don't do this multiple times
+	let tx = updater::retrieve_txs(
+		&mut *w,
+		None,
+		Some(ret_slate.id),
+		Some(&parent_key_id),
+		use_test_rng,
+	)?;
+	for t in &tx {
+		if t.tx_type == TxLogEntryType::TxSent {
+			return Err(ErrorKind::TransactionAlreadyReceived(ret_slate.id.to_string()).into());
+		}
+	}
+
+	let message = match args.message {
+		Some(mut m) => {
+			m.truncate(USER_MESSAGE_MAX_LEN);
+			Some(m)
+		}
+		None => None,
+	};
+
+	ret_slate.verify_attachments()?;
+	let approval = new_tx_approval(
+		&mut *w,
+		args.requires_approval,
+		args.approval_token.as_ref(),
+	)?;
+
+	// update slate current height
+	ret_slate.height = w.w2n_client().get_chain_height()?;
+	let lock_height = vault_lock_height(&mut *w, &parent_key_id, ret_slate.height)?;
+	ret_slate.lock_height = cmp::max(ret_slate.lock_height, lock_height);
+
+	// The invoiced amount is owed in full, the fee can't be deducted from it. The
+	// partial signature is held back until the payment is approved
+	let mut context = tx::add_inputs_to_slate(
+		&mut *w,
+		keychain_mask,
+		&mut ret_slate,
+		args.minimum_confirmations,
+		args.max_outputs as usize,
+		args.num_change_outputs as usize,
+		args.selection_strategy_is_use_all,
+		false,
+		&parent_key_id,
+		args.reservation.as_ref().map(|r| r.as_str()),
+		0,
+		message,
+		approval.is_some(),
+		use_test_rng,
+	)?;
+	context.approval = approval;
+	add_attachments(
+		&mut *w,
+		keychain_mask,
+		&mut ret_slate,
+		&context,
+		args.attachments,
+	)?;
+
+	// Save the aggsig context in our DB for when we
+	// recieve the transaction back
+	{
+		let mut batch = w.batch(keychain_mask)?;
+		batch.save_private_context(slate.id.as_bytes(), 0, &context)?;
+		batch.commit()?;
+	}
+
+	if let Some(v) = args.target_slate_version {
+		ret_slate.set_serialization_version(v)?;
+	}
+	updater::archive_slate(
+		&mut *w,
+		keychain_mask,
+		&ret_slate,
+		SlateDirection::Outbound,
+		step,
+	);
+
+	Ok(ret_slate)
+}
+
+/// Lock sender outputs
+pub fn tx_lock_outputs<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate: &Slate,
+	participant_id: usize,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let context = w.get_private_context(keychain_mask, slate.id.as_bytes(), participant_id)?;
+	selection::lock_tx_context(&mut *w, keychain_mask, slate, &context)
+}
+
+/// Finalize slate
+pub fn finalize_tx<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate: &Slate,
+) -> Result<Slate, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	foreign::check_not_frozen(&mut *w)?;
+	check_node_not_stale(&mut *w)?;
+	updater::archive_slate(
+		&mut *w,
+		keychain_mask,
+		slate,
+		SlateDirection::Inbound,
+		"finalize_tx",
+	);
+	let mut sl = slate.clone();
+	sl.verify_attachments()?;
+	let context = w.get_private_context(keychain_mask, sl.id.as_bytes(), 0)?;
+	let approved = match &context.approval {
+		Some(a) => a.is_approved(),
+		None => !w.config().require_tx_approval.unwrap_or(false),
+	};
+	if !approved {
+		return Err(ErrorKind::TransactionApprovalRequired(sl.id.to_string()))?;
+	}
+	tx::complete_tx(&mut *w, keychain_mask, &mut sl, 0, &context)?;
+	let proof = {
+		let keychain = w.keychain(keychain_mask)?;
+		PaymentProof::from_slate(&keychain, &sl)?
+	};
+	tx::update_stored_tx(&mut *w, &mut sl, false)?;
+	tx::update_message(&mut *w, keychain_mask, &mut sl)?;
+	tx::update_attachments(&mut *w, keychain_mask, &sl, &context)?;
+	let txs = match proof {
+		Some(_) => updater::retrieve_txs(&mut *w, None, Some(sl.id), None, false)?,
+		None => vec![],
+	};
+	{
+		let mut batch = w.batch(keychain_mask)?;
+		for mut t in txs.into_iter() {
+			t.payment_proof = proof.clone();
+			let parent_key_id = t.parent_key_id.clone();
+			batch.save_tx_log_entry(t, &parent_key_id)?;
+		}
+		batch.delete_private_context(sl.id.as_bytes(), 0)?;
+		batch.commit()?;
+	}
+	updater::archive_slate(
+		&mut *w,
+		keychain_mask,
+		&sl,
+		SlateDirection::Outbound,
+		"finalize_tx",
+	);
+	Ok(sl)
+}
+
+/// Finalize invoices issued by this wallet and paid, aggregating their transactions
+/// into one
+pub fn finalize_invoice_txs_aggregated<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slates: &[Slate],
+) -> Result<Transaction, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if slates.is_empty() {
+		return Err(ErrorKind::ArgumentError(
+			"No slates to aggregate".to_owned(),
+		))?;
+	}
+	// Check them all before finalizing any
+	let mut ids = HashSet::new();
+	for s in slates.iter() {
+		if !ids.insert(s.id) {
+			return Err(ErrorKind::ArgumentError(format!(
+				"Slate {} given more than once",
+				s.id
+			)))?;
+		}
+		match s.participant_with_id(1) {
+			Some(p) if !p.is_complete() => {}
+			_ => {
+				return Err(ErrorKind::ArgumentError(format!(
+					"Slate {} isn't a paid invoice awaiting finalization",
+					s.id
+				)))?;
+			}
+		}
+		s.verify_messages()?;
+	}
+	let mut txs = vec![];
+	for s in slates.iter() {
+		let sl = foreign::finalize_invoice_tx(&mut *w, keychain_mask, s)?;
+		txs.push(sl.tx);
+	}
+	let tx = transaction::aggregate(txs)?;
+	let verifier_cache = Arc::new(RwLock::new(LruVerifierCache::new()));
+	tx.validate(Weighting::AsTransaction, verifier_cache)?;
+	debug!(
+		"api: finalize_invoice_txs_aggregated: {} invoices aggregated in tx {}",
+		slates.len(),
+		tx.hash()
+	);
+	Ok(tx)
+}
+
+/// cancel tx
+pub fn cancel_tx<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	tx_id: Option<u32>,
+	tx_slate_id: Option<Uuid>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = w.parent_key_id();
+	if !update_outputs(w, keychain_mask, false)? {
+		return Err(ErrorKind::TransactionCancellationError(
+			"Can't contact running Grin node. Not Cancelling.",
+		))?;
+	}
+	tx::cancel_tx(&mut *w, keychain_mask, &parent_key_id, tx_id, tx_slate_id)
+}
+
+/// cancel the transactions selected by a filter
+pub fn cancel_txs<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	filter: &TxFilter,
+) -> Result<Vec<TxCancelResult>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = match filter.account.as_ref() {
+		Some(a) => Some(account_path(&mut *w, a)?),
+		None => None,
+	};
+	if !update_outputs(w, keychain_mask, false)? {
+		return Err(ErrorKind::TransactionCancellationError(
+			"Can't contact running Grin node. Not Cancelling.",
+		))?;
+	}
+	let now = Utc::now();
+	let txs: Vec<TxLogEntry> =
+		updater::retrieve_txs(&mut *w, None, None, parent_key_id.as_ref(), false)?
+			.into_iter()
+			.filter(|t| filter.matches(t, now))
+			.collect();
+	let mut results = vec![];
+	for t in txs {
+		let res = tx::cancel_tx(&mut *w, keychain_mask, &t.parent_key_id, Some(t.id), None);
+		if let Err(e) = res.as_ref() {
+			warn!("api: cancel_txs: unable to cancel tx {}: {}", t.id, e);
+		}
+		results.push(TxCancelResult {
+			tx_id: t.id,
+			tx_slate_id: t.tx_slate_id,
+			error: res.err().map(|e| e.to_string()),
+		});
+	}
+	Ok(results)
+}
+
+/// mark a received tx its sender never posted as abandoned
+pub fn abandon_tx<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	tx_id: Option<u32>,
+	tx_slate_id: Option<Uuid>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = w.parent_key_id();
+	tx::abandon_tx(&mut *w, keychain_mask, &parent_key_id, tx_id, tx_slate_id)
+}
+
+/// approve tx
+pub fn approve_tx<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	approval_token: &str,
+	tx_id: Option<u32>,
+	tx_slate_id: Option<Uuid>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = w.parent_key_id();
+	tx::approve_tx(
+		&mut *w,
+		keychain_mask,
+		&parent_key_id,
+		approval_token,
+		tx_id,
+		tx_slate_id,
+	)
+}
+
+/// check a tx about to be posted doesn't need approval first
+pub fn check_tx_approved<'a, T: ?Sized, C, K>(w: &mut T, tx: &Transaction) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	tx::check_tx_approved(&mut *w, tx)
+}
+
+/// hold back a tx about to be posted if its kernel can't be mined yet, such as a
+/// send from a vault, returning whether it was held. It's posted on refresh once
+/// its lock height is reached
+pub fn defer_locked_tx<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	tx: &Transaction,
+) -> Result<bool, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let height = w.w2n_client().get_chain_height()?;
+	tx::defer_locked_tx(&mut *w, keychain_mask, tx, height)
+}
+
+/// whether the send of a slate is waiting for its lock height to be posted
+pub fn is_tx_deferred<'a, T: ?Sized, C, K>(w: &mut T, tx_slate_id: &Uuid) -> Result<bool, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let txs = updater::retrieve_txs(&mut *w, None, Some(*tx_slate_id), None, false)?;
+	Ok(txs
+		.iter()
+		.any(|t| t.tx_type == TxLogEntryType::TxSent && t.post_at_height.is_some()))
+}
+
+/// audit the derivation of the outputs created by a tx
+pub fn audit_tx_outputs<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	tx_id: Option<u32>,
+	tx_slate_id: Option<Uuid>,
+) -> Result<Vec<OutputDerivationAudit>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = w.parent_key_id();
+	let txs = updater::retrieve_txs(&mut *w, tx_id, tx_slate_id, Some(&parent_key_id), false)?;
+	if txs.len() != 1 {
+		let tx_id_string = match (tx_id, tx_slate_id) {
+			(Some(id), _) => id.to_string(),
+			(None, Some(slate_id)) => slate_id.to_string(),
+			(None, None) => String::new(),
+		};
+		return Err(ErrorKind::TransactionDoesntExist(tx_id_string))?;
+	}
+	updater::audit_tx_outputs(&mut *w, keychain_mask, &txs[0])
+}
+
+/// check the stored outputs are consistent with the keys they were derived from
+pub fn verify_outputs<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+) -> Result<Vec<OutputIntegrityIssue>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	updater::verify_outputs(&mut *w, keychain_mask)
+}
+
+/// usage statistics of the wallet database, over all accounts. What's read
+/// from the data directory rather than the database is left to the caller.
+pub fn wallet_stats<'a, T: ?Sized, C, K>(w: &mut T) -> Result<WalletStats, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut outputs = BTreeMap::new();
+	for o in w.iter() {
+		*outputs.entry(format!("{:?}", o.status)).or_insert(0) += 1;
+	}
+	let mut tx_entries = BTreeMap::new();
+	let mut oldest_unconfirmed_tx: Option<TxLogEntry> = None;
+	for t in w.tx_log_iter() {
+		*tx_entries.entry(format!("{:?}", t.tx_type)).or_insert(0) += 1;
+		let pending = match t.tx_type {
+			TxLogEntryType::TxSent | TxLogEntryType::TxReceived => !t.confirmed && !t.abandoned,
+			_ => false,
+		};
+		let older = match oldest_unconfirmed_tx.as_ref() {
+			Some(o) => t.creation_ts < o.creation_ts,
+			None => true,
+		};
+		if pending && older {
+			oldest_unconfirmed_tx = Some(t);
+		}
+	}
+	Ok(WalletStats {
+		outputs,
+		tx_entries,
+		accounts: w.acct_path_iter().count() as u64,
+		oldest_unconfirmed_tx,
+		data_dir_size: None,
+		disk_free_space: None,
+		last_backup: None,
+	})
+}
+
+/// build the args sending a refund for a received tx back to the
+/// refund address its payer attached
+pub fn refund_tx_args<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	tx_slate_id: Uuid,
+	amount: Option<u64>,
+) -> Result<InitTxArgs, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = w.parent_key_id();
+	let txs = updater::retrieve_txs(
+		&mut *w,
+		None,
+		Some(tx_slate_id),
+		Some(&parent_key_id),
+		false,
+	)?;
+	let tx = match txs
+		.into_iter()
+		.find(|t| t.tx_type == TxLogEntryType::TxReceived)
+	{
+		Some(t) => t,
+		None => return Err(ErrorKind::TransactionDoesntExist(tx_slate_id.to_string()))?,
+	};
+	if !tx.confirmed {
+		return Err(ErrorKind::RefundError(format!(
+			"Transaction {} isn't confirmed yet",
+			tx_slate_id
+		)))?;
+	}
+	if let Some(r) = tx.refund_slate_id {
+		return Err(ErrorKind::RefundError(format!(
+			"Transaction {} was already refunded by {}",
+			tx_slate_id, r
+		)))?;
+	}
+	let address = match tx.refund_address() {
+		Some(a) => RefundAddress::parse(a)?,
+		None => {
+			return Err(ErrorKind::RefundError(format!(
+				"Transaction {} has no readable refund address",
+				tx_slate_id
+			)))?;
+		}
+	};
+	let amount = amount.unwrap_or(tx.amount_credited);
+	if amount == 0 || amount > tx.amount_credited {
+		return Err(ErrorKind::RefundError(format!(
+			"Refund amount must be between 1 and {}",
+			tx.amount_credited
+		)))?;
+	}
+	Ok(InitTxArgs {
+		amount,
+		message: Some(format!("Refund for {}", tx_slate_id)),
+		send_args: Some(InitTxSendArgs {
+			method: address.method,
+			dest: address.dest,
+			finalize: true,
+			post_tx: true,
+			fluff: false,
+			cancel_on_failure: None,
+			timeout_secs: None,
+		}),
+		..Default::default()
+	})
+}
+
+/// record the refund sent back for a received tx
+pub fn set_tx_refunded<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	tx_slate_id: Uuid,
+	refund_slate_id: Uuid,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = w.parent_key_id();
+	let txs = updater::retrieve_txs(
+		&mut *w,
+		None,
+		Some(tx_slate_id),
+		Some(&parent_key_id),
+		false,
+	)?;
+	let mut batch = w.batch(keychain_mask)?;
+	for mut t in txs
+		.into_iter()
+		.filter(|t| t.tx_type == TxLogEntryType::TxReceived)
+	{
+		t.refund_slate_id = Some(refund_slate_id);
+		batch.save_tx_log_entry(t, &parent_key_id)?;
+	}
+	batch.commit()?;
+	Ok(())
+}
+
+/// retrieve the attachments recorded for a tx
+pub fn retrieve_tx_attachments<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	tx_id: Option<u32>,
+	tx_slate_id: Option<Uuid>,
+) -> Result<Vec<ParticipantAttachmentData>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = w.parent_key_id();
+	let mut txs = updater::retrieve_txs(&mut *w, tx_id, tx_slate_id, Some(&parent_key_id), false)?;
+	if txs.len() != 1 {
+		let tx_id_string = match (tx_id, tx_slate_id) {
+			(Some(id), _) => id.to_string(),
+			(None, Some(slate_id)) => slate_id.to_string(),
+			(None, None) => String::new(),
+		};
+		return Err(ErrorKind::TransactionDoesntExist(tx_id_string))?;
+	}
+	Ok(txs.remove(0).attachments)
+}
+
+/// get stored tx
+pub fn get_stored_tx<'a, T: ?Sized, C, K>(
+	w: &T,
+	entry: &TxLogEntry,
+) -> Result<Option<Transaction>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	w.get_stored_tx(entry)
+}
+
+/// Targeted refresh of the inputs of a transaction the node refused. The refusal
+/// may hide a transaction that went through all the same (a post that timed out,
+/// a node posted to twice), so if a kernel of the transaction is on chain or in
+/// the node's pool, nothing is touched. Otherwise the node is asked which of the
+/// inputs are still unspent, and a wallet output is marked spent only if it was
+/// confirmed in a block the node has and the node answered that it's gone from
+/// the UTXO set, so it isn't selected again.
+pub fn refresh_spent_inputs<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	tx: &Transaction,
+) -> Result<RefusedTxState, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let inputs_hex: HashSet<String> = tx
+		.inputs()
+		.iter()
+		.map(|i| grin_util::to_hex(i.commitment().0.to_vec()))
+		.collect();
+	let outputs: Vec<OutputData> = w
+		.iter()
+		.filter(|o| match o.commit.as_ref() {
+			Some(c) => inputs_hex.contains(c),
+			None => false,
+		})
+		.filter(|o| o.status == OutputStatus::Unspent || o.status == OutputStatus::Locked)
+		.collect();
+
+	// The transaction can't be on chain before the inputs it spends
+	let min_height = outputs.iter().map(|o| o.height).max();
+	let pool = w.w2n_client().get_pool_entries()?;
+	for k in tx.kernels() {
+		if pool.contains_key(&k.excess)
+			|| w.w2n_client()
+				.get_block_by_kernel(&k.excess, min_height, None)?
+				.is_some()
+		{
+			info!(
+				"Kernel {} of the refused tx is on chain or in the pool",
+				grin_util::to_hex(k.excess.0.to_vec())
+			);
+			return Ok(RefusedTxState::Landed);
+		}
+	}
+
+	let tip = w.w2n_client().get_chain_height()?;
+	let inputs: Vec<pedersen::Commitment> = tx.inputs().iter().map(|i| i.commitment()).collect();
+	let res = w.w2n_client().get_outputs_from_node(inputs.clone())?;
+	let gone: HashSet<String> = inputs
+		.iter()
+		.filter(|c| !res.outputs.contains_key(c) && !res.failed.contains(c))
+		.map(|c| grin_util::to_hex(c.0.to_vec()))
+		.collect();
+	// An output confirmed above the node's tip is missing from a node behind the
+	// wallet, not spent
+	let outputs: Vec<OutputData> = outputs
+		.into_iter()
+		.filter(|o| o.height > 0 && o.height <= tip)
+		.filter(|o| gone.contains(o.commit.as_ref().unwrap()))
+		.collect();
+	let marked: HashSet<String> = outputs.iter().filter_map(|o| o.commit.clone()).collect();
+	let mut batch = w.batch(keychain_mask)?;
+	for mut o in outputs {
+		warn!(
+			"Output {} was already spent, marking it spent",
+			o.key_id.to_bip_32_string()
+		);
+		o.mark_spent();
+		batch.save(o)?;
+	}
+	batch.commit()?;
+	let spent = inputs
+		.into_iter()
+		.filter(|c| marked.contains(&grin_util::to_hex(c.0.to_vec())))
+		.collect();
+	Ok(RefusedTxState::InputsSpent(spent))
+}
+
+/// Posts a transaction to the chain
+/// take a client impl instead of wallet so as not to have to lock the wallet,
+/// callers check the node isn't stale before with the wallet locked
+pub fn post_tx<'a, C>(client: &C, tx: &Transaction, fluff: bool) -> Result<(), Error>
+where
+	C: NodeClient + 'a,
+{
+	let tx_hex = grin_util::to_hex(ser::ser_vec(tx, ser::ProtocolVersion::local()).unwrap());
+	let res = client.post_tx(&TxWrapper { tx_hex: tx_hex }, fluff);
+	if let Err(e) = res {
+		error!("api: post_tx: failed with error: {}", e);
+		Err(e)
+	} else {
+		debug!(
+			"api: post_tx: successfully posted tx: {}, fluff? {}",
+			tx.hash(),
+			fluff
+		);
+		Ok(())
+	}
+}
+
+/// Parse a transaction that didn't originate from this wallet, serialized as hex
+/// or as JSON (either the transaction or a slate carrying it), and validate it
+pub fn parse_raw_tx(raw: &str) -> Result<Transaction, Error> {
+	let raw = raw.trim();
+	let tx = if raw.starts_with('{') {
+		let value: serde_json::Value = serde_json::from_str(raw)
+			.map_err(|e| ErrorKind::Format(format!("Invalid transaction JSON: {}", e)))?;
+		if value.get("tx").is_some() {
+			Slate::deserialize_upgrade(raw)?.tx
+		} else {
+			let tx: TransactionV2 = serde_json::from_value(value)
+				.map_err(|e| ErrorKind::Format(format!("Invalid transaction JSON: {}", e)))?;
+			Transaction::from(tx)
+		}
+	} else {
+		let tx_bin = grin_util::from_hex(raw.to_owned())
+			.map_err(|_| ErrorKind::Format("Invalid transaction hex".to_owned()))?;
+		ser::deserialize(&mut &tx_bin[..], ser::ProtocolVersion::local())
+			.map_err(|e| ErrorKind::Format(format!("Invalid transaction: {}", e)))?
+	};
+	if tx.kernels().is_empty() {
+		return Err(ErrorKind::Format("Transaction has no kernel".to_owned()))?;
+	}
+	let verifier_cache = Arc::new(RwLock::new(LruVerifierCache::new()));
+	tx.validate(Weighting::AsTransaction, verifier_cache)?;
+	Ok(tx)
+}
+
+/// Parse a ledger to reconcile, either a JSON array of ledger entries or CSV lines
+/// of `tx_slate_id,direction,amount[,reference]`, where the direction is `deposit`
+/// or `withdrawal` and the amount is in MWC, as in `1.5`. A header line starting
+/// with `tx_slate_id` is skipped.
+pub fn parse_ledger(raw: &str) -> Result<Vec<LedgerEntry>, Error> {
+	let raw = raw.trim();
+	if raw.starts_with('[') {
+		let entries = serde_json::from_str(raw)
+			.map_err(|e| ErrorKind::Format(format!("Invalid ledger JSON: {}", e)))?;
+		return Ok(entries);
+	}
+	let mut entries = vec![];
+	for (i, line) in raw.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() || (i == 0 && line.starts_with("tx_slate_id")) {
+			continue;
+		}
+		let bad_line = |msg: &str| ErrorKind::Format(format!("Ledger line {}: {}", i + 1, msg));
+		let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+		if fields.len() < 3 || fields.len() > 4 {
+			return Err(bad_line(
+				"expected tx_slate_id,direction,amount[,reference]",
+			))?;
+		}
+		let tx_slate_id = Uuid::parse_str(fields[0]).map_err(|_| bad_line("invalid slate id"))?;
+		let direction = match fields[1].to_lowercase().as_str() {
+			"deposit" => LedgerDirection::Deposit,
+			"withdrawal" => LedgerDirection::Withdrawal,
+			_ => return Err(bad_line("direction must be deposit or withdrawal"))?,
+		};
+		let amount = amount_from_hr_string(fields[2]).map_err(|_| bad_line("invalid amount"))?;
+		let reference = fields
+			.get(3)
+			.filter(|r| !r.is_empty())
+			.map(|r| r.to_string());
+		entries.push(LedgerEntry {
+			tx_slate_id,
+			direction,
+			amount,
+			reference,
+		});
+	}
+	Ok(entries)
+}
+
+/// verify slate messages
+pub fn verify_slate_messages(slate: &Slate) -> Result<(), Error> {
+	slate.verify_messages()
+}
+
+/// describe slate, labelling its amounts with the currency of the wallet
+pub fn describe_slate<'a, T: ?Sized, C, K>(w: &mut T, slate: &Slate) -> SlateDescription
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	slate.inspect(&w.config().currency())
+}
+
+/// time spent in the steps of the sends built since the wallet was opened
+pub fn retrieve_send_metrics<'a, T: ?Sized, C, K>(w: &T) -> SendMetrics
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	w.send_metrics().clone()
+}
+
+/// Registration of a pending operation, ending the operation when dropped
+pub struct PendingOperationGuard {
+	operations: Arc<PendingOperations>,
+	id: u64,
+	error: Option<String>,
+}
+
+impl PendingOperationGuard {
+	/// Id of the operation
+	pub fn id(&self) -> u64 {
+		self.id
+	}
+
+	/// Record the error the operation fails with, reported once it ends
+	pub fn set_error(&mut self, error: String) {
+		self.error = Some(error);
+	}
+}
+
+impl Drop for PendingOperationGuard {
+	fn drop(&mut self) {
+		let mut pending = self.operations.state.lock();
+		let i = match pending.operations.iter().position(|o| o.id == self.id) {
+			Some(i) => i,
+			None => return,
+		};
+		let operation = pending.operations.remove(i);
+		if pending.ended.len() >= MAX_ENDED_OPERATIONS {
+			pending.ended.pop_front();
+		}
+		pending.ended.push_back(OperationStatus {
+			operation,
+			state: match self.error {
+				Some(_) => OperationState::Failed,
+				None => OperationState::Completed,
+			},
+			error: self.error.take(),
+			finished: Some(Utc::now()),
+		});
+	}
+}
+
+/// Register an operation using the wallet across several locks of it, such as a
+/// send waiting for its recipient, among the `operations` of the wallet until the
+/// returned guard is dropped. Closing the wallet waits for it. Refused while the
+/// wallet is being closed.
+pub fn begin_operation(
+	operations: &Arc<PendingOperations>,
+	kind: &str,
+	tx_slate_id: Option<Uuid>,
+) -> Result<PendingOperationGuard, Error> {
+	let mut pending = operations.state.lock();
+	if pending.closing > 0 {
+		let msg = format!("Wallet is being closed, {} refused", kind);
+		return Err(ErrorKind::Lifecycle(msg))?;
+	}
+	pending.next_id += 1;
+	let id = pending.next_id;
+	pending.operations.push(PendingOperation {
+		id,
+		kind: kind.to_owned(),
+		tx_slate_id,
+		started: Utc::now(),
+	});
+	Ok(PendingOperationGuard {
+		operations: operations.clone(),
+		id,
+		error: None,
+	})
+}
+
+/// Operations of the wallet in progress, oldest first
+pub fn retrieve_pending_operations(operations: &PendingOperations) -> Vec<PendingOperation> {
+	operations.state.lock().operations.clone()
+}
+
+/// Status of an operation of the wallet, running or among the last ones ended
+pub fn retrieve_operation_status(
+	operations: &PendingOperations,
+	id: u64,
+) -> Option<OperationStatus> {
+	let pending = operations.state.lock();
+	if let Some(o) = pending.operations.iter().find(|o| o.id == id) {
+		return Some(OperationStatus {
+			operation: o.clone(),
+			state: OperationState::Running,
+			error: None,
+			finished: None,
+		});
+	}
+	pending.ended.iter().find(|o| o.operation.id == id).cloned()
+}
+
+/// Close the wallet in two phases. New operations are refused first, and those in
+/// progress given up to `wait_secs` to end. Once none is left, the wallet is closed
+/// with `close`. Otherwise nothing is closed, new operations are accepted again and
+/// the operations still in progress are returned in an `OperationsPending` error.
+pub fn close_after_operations<F>(
+	operations: &PendingOperations,
+	wait_secs: Option<u64>,
+	close: F,
+) -> Result<(), Error>
+where
+	F: FnOnce() -> Result<(), Error>,
+{
+	operations.state.lock().closing += 1;
+	let end_closing = || operations.state.lock().closing -= 1;
+	let start = Instant::now();
+	loop {
+		let pending = retrieve_pending_operations(operations);
+		if pending.is_empty() {
+			break;
+		}
+		if start.elapsed().as_secs() >= wait_secs.unwrap_or(0) {
+			end_closing();
+			return Err(ErrorKind::OperationsPending(pending))?;
+		}
+		thread::sleep(std::time::Duration::from_millis(100));
+	}
+	let res = close();
+	end_closing();
+	res
+}
+
+/// Attempt to restore contents of wallet, from the birthday height on if given
+pub fn restore<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	birthday: Option<u64>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	w.restore(keychain_mask, birthday)
+}
+
+/// check repair
+pub fn check_repair<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	delete_unconfirmed: bool,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	update_outputs(w, keychain_mask, true)?;
+	w.check_repair(keychain_mask, delete_unconfirmed)
+}
+
+/// Import an output that's on chain but not in the wallet
+pub fn import_output<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	commit: &pedersen::Commitment,
+	value: u64,
+	key_id: &Identifier,
+	height: Option<u64>,
+) -> Result<OutputData, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	w.import_output(keychain_mask, commit, value, key_id, height)
 }
 
-/// cancel tx
-pub fn cancel_tx<'a, T: ?Sized, C, K>(
+/// Start a step-wise restore, from the birthday height if given
+pub fn restore_scan_start<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	birthday: Option<u64>,
+) -> Result<RestoreScan, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	crate::internal::restore::restore_scan_start(w, birthday)
+}
+
+/// Scan the next chunk of outputs of a step-wise restore
+pub fn restore_scan<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
-	tx_id: Option<u32>,
-	tx_slate_id: Option<Uuid>,
+	mut scan: RestoreScan,
+	max_outputs: u64,
+) -> Result<RestoreScan, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	w.restore_scan(keychain_mask, &mut scan, max_outputs)?;
+	Ok(scan)
+}
+
+/// Save the outputs found by a complete step-wise restore
+pub fn restore_commit<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	scan: &RestoreScan,
 ) -> Result<(), Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let parent_key_id = w.parent_key_id();
-	if !update_outputs(w, keychain_mask, false)? {
-		return Err(ErrorKind::TransactionCancellationError(
-			"Can't contact running Grin node. Not Cancelling.",
-		))?;
+	w.restore_commit(keychain_mask, scan)
+}
+
+/// Rebuild the data derived from the outputs and the tx log: cached output
+/// commitments, links from outputs to tx log entries and links from sent
+/// transactions to their stored transaction file
+pub fn rebuild_indexes<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+) -> Result<IndexRebuildReport, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut report = IndexRebuildReport::default();
+	let tx_ids: HashSet<(Identifier, u32)> = w
+		.tx_log_iter()
+		.map(|t| (t.parent_key_id.clone(), t.id))
+		.collect();
+
+	// The commitments of a watch-only wallet come from the chain, it has no keys
+	// to check them against
+	let watch_only = w.watch_only_key().is_some();
+	let mut outputs = vec![];
+	for mut o in w.iter().collect::<Vec<_>>() {
+		let mut changed = false;
+		let commit = match watch_only {
+			true => o.commit.clone(),
+			false => w.calc_commit_for_cache(keychain_mask, o.value, &o.key_id)?,
+		};
+		if o.commit != commit {
+			o.commit = commit;
+			report.commits_fixed += 1;
+			changed = true;
+		}
+		if let Some(id) = o.tx_log_entry {
+			if !tx_ids.contains(&(o.root_key_id.clone(), id)) {
+				o.tx_log_entry = None;
+				report.tx_links_pruned += 1;
+				changed = true;
+			}
+		}
+		if changed {
+			outputs.push(o);
+		}
 	}
-	tx::cancel_tx(&mut *w, keychain_mask, &parent_key_id, tx_id, tx_slate_id)
+
+	let mut txs = vec![];
+	for mut t in w.tx_log_iter().collect::<Vec<_>>() {
+		if t.stored_tx.is_some() {
+			if w.get_stored_tx(&t).is_err() {
+				t.stored_tx = None;
+				report.stored_txs_pruned += 1;
+				txs.push(t);
+			}
+			continue;
+		}
+		// Only sent transactions are stored, under their slate id
+		let slate_id = match t.tx_slate_id {
+			Some(id) if t.tx_type == TxLogEntryType::TxSent => id,
+			_ => continue,
+		};
+		t.stored_tx = Some(format!("{}.grintx", slate_id));
+		if let Ok(Some(_)) = w.get_stored_tx(&t) {
+			report.stored_txs_relinked += 1;
+			txs.push(t);
+		}
+	}
+
+	if !outputs.is_empty() || !txs.is_empty() {
+		let mut batch = w.batch(keychain_mask)?;
+		for o in outputs {
+			batch.save(o)?;
+		}
+		for t in txs {
+			let parent_key_id = t.parent_key_id.clone();
+			batch.save_tx_log_entry(t, &parent_key_id)?;
+		}
+		batch.commit()?;
+	}
+	Ok(report)
 }
 
-/// get stored tx
-pub fn get_stored_tx<'a, T: ?Sized, C, K>(
-	w: &T,
-	entry: &TxLogEntry,
-) -> Result<Option<Transaction>, Error>
+/// Retrieve a tx along with its outputs, kernel and confirmations
+pub fn get_tx_details<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	refresh_from_node: bool,
+	tx_slate_id: Uuid,
+) -> Result<(bool, TxDetails), Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	w.get_stored_tx(entry)
+	let parent_key_id = w.parent_key_id();
+
+	let mut validated = false;
+	if refresh_from_node {
+		validated = update_outputs(w, keychain_mask, false)?;
+	}
+
+	// A send to self has a sent and a received entry, the sent one comes first
+	let txs = updater::retrieve_txs(
+		&mut *w,
+		None,
+		Some(tx_slate_id),
+		Some(&parent_key_id),
+		false,
+	)?;
+	let tx = match txs.into_iter().next() {
+		Some(t) => t,
+		None => return Err(ErrorKind::TransactionDoesntExist(tx_slate_id.to_string()))?,
+	};
+	let outputs = updater::retrieve_outputs(
+		&mut *w,
+		keychain_mask,
+		true,
+		Some(tx.id),
+		Some(&parent_key_id),
+	)?;
+
+	let mut details = TxDetails {
+		tx,
+		inputs: vec![],
+		outputs: vec![],
+		change: vec![],
+		kernel_excess: None,
+		has_stored_tx: false,
+		confirmations: 0,
+		currency: w.config().currency(),
+	};
+	for o in outputs {
+		match (&details.tx.tx_type, &o.output.status) {
+			// Cancelling a send deletes its change and releases its inputs
+			(TxLogEntryType::TxSentCancelled, _) => details.inputs.push(o),
+			(_, OutputStatus::Locked) | (_, OutputStatus::Spent) => details.inputs.push(o),
+			(TxLogEntryType::TxSent, _) => details.change.push(o),
+			_ => details.outputs.push(o),
+		}
+	}
+
+	if let Ok(Some(stored)) = w.get_stored_tx(&details.tx) {
+		details.has_stored_tx = true;
+		// The kernel excess is only set once the transaction is finalized
+		details.kernel_excess = stored
+			.kernels()
+			.iter()
+			.map(|k| k.excess)
+			.find(|e| e.0.iter().any(|b| *b != 0))
+			.map(|e| grin_util::to_hex(e.0.to_vec()));
+	}
+
+	if details.tx.confirmed {
+		if let Some(h) = details.tx.confirmed_height {
+			details.confirmations = w.last_confirmed_height()?.saturating_sub(h) + 1;
+		}
+	}
+	Ok((validated, details))
 }
 
-/// Posts a transaction to the chain
-/// take a client impl instead of wallet so as not to have to lock the wallet
-pub fn post_tx<'a, C>(client: &C, tx: &Transaction, fluff: bool) -> Result<(), Error>
+/// Transactions of the active account with their fiat values, and the gains the
+/// sent ones realized
+pub fn export_txs<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	refresh_from_node: bool,
+) -> Result<(bool, Vec<TxExportEntry>), Error>
 where
+	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
+	K: Keychain + 'a,
 {
-	let tx_hex = grin_util::to_hex(ser::ser_vec(tx, ser::ProtocolVersion::local()).unwrap());
-	let res = client.post_tx(&TxWrapper { tx_hex: tx_hex }, fluff);
-	if let Err(e) = res {
-		error!("api: post_tx: failed with error: {}", e);
-		Err(e)
-	} else {
-		debug!(
-			"api: post_tx: successfully posted tx: {}, fluff? {}",
-			tx.hash(),
-			fluff
-		);
-		Ok(())
+	let parent_key_id = w.parent_key_id();
+	let mut validated = false;
+	if refresh_from_node {
+		validated = update_outputs(w, keychain_mask, false)?;
 	}
+	let txs = updater::retrieve_txs(&mut *w, None, None, Some(&parent_key_id), false)?;
+	let outputs: Vec<OutputData> = w
+		.iter()
+		.filter(|o| o.root_key_id == parent_key_id)
+		.collect();
+	let entries = txs.into_iter().map(|t| export_tx(t, &outputs)).collect();
+	Ok((validated, entries))
 }
 
-/// verify slate messages
-pub fn verify_slate_messages(slate: &Slate) -> Result<(), Error> {
-	slate.verify_messages()
+/// Fiat values of a transaction. A sent transaction realizes a gain on the amount
+/// and fee it sends, valued at its price, over the cost basis of that share of its
+/// inputs. The rest of the cost basis of the inputs went to the change.
+fn export_tx(tx: TxLogEntry, outputs: &[OutputData]) -> TxExportEntry {
+	let mut entry = TxExportEntry {
+		tx,
+		lots: vec![],
+		fiat_value: None,
+		cost_basis: None,
+		realized_gain: None,
+		currency: None,
+	};
+	let tx = &entry.tx;
+	match tx.tx_type {
+		TxLogEntryType::TxReceived | TxLogEntryType::ConfirmedCoinbase => {
+			// A coinbase is valued at the cost basis of its output
+			let price = tx.price.as_ref().or_else(|| {
+				outputs
+					.iter()
+					.find(|o| o.tx_log_entry == Some(tx.id))
+					.and_then(|o| o.cost_basis.as_ref())
+			});
+			let received = tx.amount_credited.saturating_sub(tx.amount_debited);
+			entry.fiat_value = price.map(|p| p.value_of(received));
+			entry.currency = price.map(|p| p.currency.clone());
+		}
+		TxLogEntryType::TxSent if tx.confirmed => {
+			let sent = tx.amount_debited.saturating_sub(tx.amount_credited);
+			let lots: Vec<CostBasisLot> = outputs
+				.iter()
+				.filter(|o| o.tx_log_entry == Some(tx.id) && o.status == OutputStatus::Spent)
+				.map(|o| CostBasisLot {
+					commit: o.commit.clone(),
+					value: o.value,
+					cost_basis: o.cost_basis.clone(),
+				})
+				.collect();
+			let inputs: Vec<(u64, Option<&FiatPrice>)> = lots
+				.iter()
+				.map(|l| (l.value, l.cost_basis.as_ref()))
+				.collect();
+			if let Some(price) = tx.price.as_ref() {
+				let fiat_value = price.value_of(sent);
+				if let Some(basis) = cost_basis::blend(&inputs) {
+					if basis.currency == price.currency {
+						let cost = basis.value_of(sent);
+						entry.cost_basis = Some(cost);
+						entry.realized_gain = Some(fiat_value as i64 - cost as i64);
+					}
+				}
+				entry.fiat_value = Some(fiat_value);
+				entry.currency = Some(price.currency.clone());
+			}
+			entry.lots = lots;
+		}
+		_ => {}
+	}
+	entry
 }
 
-/// Attempt to restore contents of wallet
-pub fn restore<'a, T: ?Sized, C, K>(
+/// Set by hand the price of the coin a tx of the active account was sent or received
+/// at. The outputs it received and still holds are given it as their cost basis.
+pub fn set_tx_price<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
+	tx_slate_id: Uuid,
+	currency: &str,
+	unit_price: u64,
 ) -> Result<(), Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	w.restore(keychain_mask)
+	let parent_key_id = w.parent_key_id();
+	let txs = updater::retrieve_txs(
+		&mut *w,
+		None,
+		Some(tx_slate_id),
+		Some(&parent_key_id),
+		false,
+	)?;
+	if txs.is_empty() {
+		return Err(ErrorKind::TransactionDoesntExist(tx_slate_id.to_string()))?;
+	}
+	let outputs: Vec<OutputData> = w
+		.iter()
+		.filter(|o| {
+			o.root_key_id == parent_key_id
+				&& [OutputStatus::Unconfirmed, OutputStatus::Unspent].contains(&o.status)
+		})
+		.collect();
+	let mut batch = w.batch(keychain_mask)?;
+	for mut t in txs {
+		let price = FiatPrice {
+			currency: currency.to_owned(),
+			unit_price,
+			source: cost_basis::MANUAL_PRICE_SOURCE.to_owned(),
+			time: t.confirmation_ts.unwrap_or(t.creation_ts),
+		};
+		if t.tx_type == TxLogEntryType::TxReceived {
+			for o in outputs.iter().filter(|o| o.tx_log_entry == Some(t.id)) {
+				let mut o = o.clone();
+				o.cost_basis = Some(price.clone());
+				batch.save(o)?;
+			}
+		}
+		t.price = Some(price);
+		batch.save_tx_log_entry(t, &parent_key_id)?;
+	}
+	batch.commit()?;
+	Ok(())
 }
 
-/// check repair
-pub fn check_repair<'a, T: ?Sized, C, K>(
+/// Set by hand the cost basis of an output of the active account, given by its
+/// commitment, spent or not
+pub fn set_output_cost_basis<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
-	delete_unconfirmed: bool,
-) -> Result<(), Error>
+	commitment: &str,
+	currency: &str,
+	unit_price: u64,
+	acquired: DateTime<Utc>,
+) -> Result<OutputData, Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	update_outputs(w, keychain_mask, true)?;
-	w.check_repair(keychain_mask, delete_unconfirmed)
+	let parent_key_id = w.parent_key_id();
+	let mut output = w
+		.iter()
+		.find(|o| {
+			o.root_key_id == parent_key_id
+				&& o.commit.as_ref().map(|c| c.as_str()) == Some(commitment)
+		})
+		.ok_or_else(|| {
+			ErrorKind::GenericError(format!("No output {} in the account", commitment))
+		})?;
+	output.cost_basis = Some(FiatPrice {
+		currency: currency.to_owned(),
+		unit_price,
+		source: cost_basis::MANUAL_PRICE_SOURCE.to_owned(),
+		time: acquired,
+	});
+	let mut batch = w.batch(keychain_mask)?;
+	batch.save(output.clone())?;
+	batch.commit()?;
+	Ok(output)
 }
 
 /// node height
@@ -524,7 +3273,362 @@ where
 	}
 }
 
-/// Attempt to update outputs in wallet, return whether it was successful
+/// Reconcile a ledger against the transactions and balance of the active account.
+/// Deposits must be confirmed to match, withdrawals only not cancelled.
+pub fn reconcile<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	refresh_from_node: bool,
+	args: ReconcileArgs,
+) -> Result<(bool, ReconciliationReport), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = w.parent_key_id();
+
+	let mut validated = false;
+	if refresh_from_node {
+		validated = update_outputs(w, keychain_mask, false)?;
+	}
+
+	let txs = updater::retrieve_txs(&mut *w, None, None, Some(&parent_key_id), false)?;
+	let wallet_info = updater::retrieve_info(&mut *w, &parent_key_id, 1)?;
+	let mut report = ReconciliationReport {
+		expected_balance: args.expected_balance,
+		actual_balance: wallet_info.total,
+		..Default::default()
+	};
+
+	let mut matched_txs = HashSet::new();
+	for entry in args.entries {
+		let tx_type = match entry.direction {
+			LedgerDirection::Deposit => TxLogEntryType::TxReceived,
+			LedgerDirection::Withdrawal => TxLogEntryType::TxSent,
+		};
+		let tx = txs
+			.iter()
+			.find(|t| t.tx_slate_id == Some(entry.tx_slate_id) && t.tx_type == tx_type);
+		let tx = match tx {
+			Some(t) if t.confirmed || entry.direction == LedgerDirection::Withdrawal => t,
+			_ => {
+				if let Some(t) = tx {
+					// an unconfirmed deposit is expected, just not there yet
+					matched_txs.insert(t.id);
+				}
+				match entry.direction {
+					LedgerDirection::Deposit => report.missing_deposits.push(entry),
+					LedgerDirection::Withdrawal => report.missing_withdrawals.push(entry),
+				}
+				continue;
+			}
+		};
+		matched_txs.insert(tx.id);
+		let actual_amount = match entry.direction {
+			LedgerDirection::Deposit => tx.amount_credited,
+			LedgerDirection::Withdrawal => tx
+				.amount_debited
+				.saturating_sub(tx.amount_credited)
+				.saturating_sub(tx.fee.unwrap_or(0)),
+		};
+		if actual_amount == entry.amount {
+			report.matched += 1;
+		} else {
+			report.amount_mismatches.push(LedgerMismatch {
+				entry,
+				actual_amount,
+			});
+		}
+	}
+
+	for tx in txs.into_iter() {
+		if matched_txs.contains(&tx.id) || args.since.map(|s| tx.creation_ts < s).unwrap_or(false) {
+			continue;
+		}
+		match tx.tx_type {
+			TxLogEntryType::TxReceived if tx.confirmed => report.unexpected_deposits.push(tx),
+			TxLogEntryType::TxSent => report.unexpected_sends.push(tx),
+			_ => {}
+		}
+	}
+	Ok((validated, report))
+}
+
+/// Peers, sync state and protocol version of the wallet's node
+pub fn node_status<'a, T: ?Sized, C, K>(w: &mut T) -> Result<NodeStatus, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	w.w2n_client().get_status()
+}
+
+/// Statistics of the calls made to the node, reset afterwards if asked
+pub fn node_client_stats<'a, T: ?Sized, C, K>(w: &mut T, reset: bool) -> NodeClientStats
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	w.w2n_client().get_stats(reset)
+}
+
+/// Fee paid per unit of weight, as counted by `tx_fee`
+fn fee_rate(fees: &NodeTxFees) -> u64 {
+	fees.fee / tx_fee(fees.num_inputs, fees.num_outputs, fees.num_kernels, Some(1))
+}
+
+/// Weight of transactions as counted in a block
+fn block_weight(fees: &NodeTxFees) -> u64 {
+	(fees.num_inputs * consensus::BLOCK_INPUT_WEIGHT
+		+ fees.num_outputs * consensus::BLOCK_OUTPUT_WEIGHT
+		+ fees.num_kernels * consensus::BLOCK_KERNEL_WEIGHT) as u64
+}
+
+/// Estimate the fee rate for a transaction to confirm quickly, from the fees
+/// paid in the last `num_blocks` blocks and the transactions waiting in the
+/// node's mempool. Never less than the minimum fee rate.
+pub fn recommended_fee_rate<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	num_blocks: Option<u64>,
+) -> Result<FeeRateEstimate, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let num_blocks = num_blocks.unwrap_or(FEE_ESTIMATE_BLOCKS);
+	let client = w.w2n_client();
+	// A transaction of weight 1 pays the base fee
+	let minimum_fee_rate = tx_fee(0, 0, 1, None);
+
+	let height = client.get_chain_height()?;
+	let mut rates = vec![];
+	for h in (height + 1).saturating_sub(num_blocks)..=height {
+		if let Some(fees) = client.get_block_fees(h)? {
+			if fees.num_kernels > 0 {
+				rates.push(fee_rate(&fees));
+			}
+		}
+	}
+	rates.sort();
+	let recent_blocks_fee_rate = rates.get(rates.len() / 2).cloned();
+	let mut recommended_fee_rate = cmp::max(minimum_fee_rate, recent_blocks_fee_rate.unwrap_or(0));
+
+	// Miners take the best paying transactions first. If the next block can't
+	// take the whole mempool, outbid the first transaction left out.
+	let mut pool = client.get_pool_fees()?;
+	pool.sort_by_key(|f| cmp::Reverse(fee_rate(f)));
+	let coinbase = NodeTxFees {
+		num_outputs: 1,
+		num_kernels: 1,
+		..Default::default()
+	};
+	let mut room = (client.max_block_weight() as u64).saturating_sub(block_weight(&coinbase));
+	let mut pool_congested = false;
+	for fees in pool.iter() {
+		let weight = block_weight(fees);
+		if weight > room {
+			pool_congested = true;
+			recommended_fee_rate =
+				cmp::max(recommended_fee_rate, fee_rate(fees) + minimum_fee_rate);
+			break;
+		}
+		room -= weight;
+	}
+
+	Ok(FeeRateEstimate {
+		minimum_fee_rate,
+		recent_blocks_fee_rate,
+		num_blocks,
+		pool_txs: pool.len(),
+		pool_congested,
+		recommended_fee_rate,
+	})
+}
+
+/// Refuse to go on if the wallet is set to check the node and the node's chain
+/// hasn't advanced in the time allowed: its tip is older than that, or its
+/// height stayed the same for that long since the wallet first saw it
+pub fn check_node_not_stale<'a, T: ?Sized, C, K>(w: &mut T) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let limit = match w.config().stale_node_after_mins {
+		Some(m) if m > 0 => Duration::minutes(m as i64),
+		_ => return Ok(()),
+	};
+	let height = w.w2n_client().get_chain_height()?;
+	let tip = w.w2n_client().get_header_by_height(height)?;
+	let now = Utc::now();
+	if now - tip.timestamp > limit {
+		return Err(ErrorKind::StaleNode(format!(
+			"the node's tip at height {} is from {}, over {} minutes ago",
+			height,
+			tip.timestamp,
+			limit.num_minutes()
+		)))?;
+	}
+	match w.node_height_seen() {
+		Some((h, since)) if h == height => {
+			if now - since > limit {
+				return Err(ErrorKind::StaleNode(format!(
+					"the node's height has been {} since {}, over {} minutes ago",
+					height,
+					since,
+					limit.num_minutes()
+				)))?;
+			}
+		}
+		_ => w.set_node_height_seen(height, now),
+	}
+	Ok(())
+}
+
+/// When a quorum is configured, ask `check_node_api_http_addr` and the quorum nodes for
+/// their chain, and switch the client to a node the majority agrees with. Nodes within
+/// `node_quorum_max_deviation` blocks of the upper median height are asked for their
+/// header at the lowest of their heights, and only the largest group reporting the same
+/// hash is trusted. The configured node is preferred, then the one in use. Fails if no
+/// majority of the nodes agree. If no node can be reached the client is left as is, so
+/// offline commands keep working.
+pub fn select_node_by_quorum<C>(node_client: &mut C, config: &WalletConfig) -> Result<(), Error>
+where
+	C: NodeClient,
+{
+	let max_deviation = match config.node_quorum_max_deviation {
+		Some(d) => d,
+		None => return Ok(()),
+	};
+	let mut addrs = vec![config.check_node_api_http_addr.clone()];
+	for a in config.quorum_node_api_http_addrs.iter().flatten() {
+		if !addrs.contains(a) {
+			addrs.push(a.clone());
+		}
+	}
+	if addrs.len() < 2 {
+		return Ok(());
+	}
+
+	let mut heights = vec![];
+	for addr in addrs.iter() {
+		let mut client = node_client.clone();
+		client.set_node_url(addr);
+		match client.get_chain_height() {
+			Ok(h) => heights.push((client, h)),
+			Err(e) => warn!("Node {} left out of the quorum: {}", addr, e),
+		}
+	}
+	if heights.is_empty() {
+		warn!(
+			"None of the {} configured nodes could be reached",
+			addrs.len()
+		);
+		return Ok(());
+	}
+
+	// Upper median, so that nodes lagging behind can't drag the quorum down
+	let mut sorted: Vec<u64> = heights.iter().map(|(_, h)| *h).collect();
+	sorted.sort();
+	let median = sorted[sorted.len() / 2];
+	let mut candidates = vec![];
+	for (client, h) in heights {
+		let deviation = if h > median { h - median } else { median - h };
+		if deviation > max_deviation {
+			warn!(
+				"Not trusting node {} at height {}, the quorum is at height {}",
+				client.node_url(),
+				h,
+				median
+			);
+		} else {
+			candidates.push((client, h));
+		}
+	}
+
+	// Nodes at about the same height may still be on different chains
+	let height = candidates.iter().map(|(_, h)| *h).min().unwrap_or(median);
+	let mut chains: Vec<(String, Vec<String>)> = vec![];
+	for (client, _) in candidates {
+		let addr = client.node_url().to_owned();
+		let hash = match client.get_header_by_height(height) {
+			Ok(header) => header.hash,
+			Err(e) => {
+				warn!("Node {} left out of the quorum: {}", addr, e);
+				continue;
+			}
+		};
+		match chains.iter_mut().find(|(h, _)| *h == hash) {
+			Some((_, nodes)) => nodes.push(addr),
+			None => chains.push((hash, vec![addr])),
+		}
+	}
+	chains.sort_by_key(|(_, nodes)| cmp::Reverse(nodes.len()));
+	let (hash, trusted) = chains.into_iter().next().unwrap_or_default();
+	if trusted.len() * 2 <= addrs.len() {
+		return Err(ErrorKind::NodeQuorum(format!(
+			"only {} of {} nodes agree on the chain at height {}, not trusting any of them",
+			trusted.len(),
+			addrs.len(),
+			height
+		)))?;
+	}
+	let current = node_client.node_url().to_owned();
+	let selected = if trusted.contains(&addrs[0]) {
+		addrs[0].clone()
+	} else if trusted.contains(&current) {
+		current.clone()
+	} else {
+		trusted[0].clone()
+	};
+	if selected != current {
+		info!(
+			"Using node {} instead of {}, its block {} is {}",
+			selected, current, height, hash
+		);
+		node_client.set_node_url(&selected);
+	}
+	Ok(())
+}
+
+/// Update the outputs and transactions of the wallet from the node, reporting the
+/// outputs that couldn't be queried. Outputs aren't updated from a node that's still
+/// syncing, as it would report recent outputs as missing. With a node quorum, the node
+/// is picked again first
+pub fn update_wallet_state<'a, T: ?Sized, C, K>(
+	w: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	update_all: bool,
+) -> Result<RefreshReport, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	// The nodes may have parted ways since the last refresh
+	if w.config().node_quorum_max_deviation.is_some() {
+		let config = w.config().clone();
+		select_node_by_quorum(w.w2n_client(), &config)?;
+	}
+	// Nodes that don't report their status are assumed to be synced
+	if let Ok(status) = w.w2n_client().get_status() {
+		if !status.is_synced() {
+			warn!(
+				"Node is syncing ({}), not updating outputs from it",
+				status.sync_status
+			);
+			return Ok(RefreshReport::default());
+		}
+	}
+	let parent_key_id = w.parent_key_id();
+	updater::refresh_outputs(&mut *w, keychain_mask, &parent_key_id, update_all)
+}
+
+/// Attempt to update outputs in wallet, return whether all of them were updated
 fn update_outputs<'a, T: ?Sized, C, K>(
 	w: &mut T,
 	keychain_mask: Option<&SecretKey>,
@@ -535,9 +3639,8 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let parent_key_id = w.parent_key_id();
-	match updater::refresh_outputs(&mut *w, keychain_mask, &parent_key_id, update_all) {
-		Ok(_) => Ok(true),
+	match update_wallet_state(&mut *w, keychain_mask, update_all) {
+		Ok(r) => Ok(r.is_complete()),
 		Err(e) => {
 			if let ErrorKind::InvalidKeychainMask = e.kind() {
 				return Err(e);
@@ -14,11 +14,21 @@
 
 //! Types specific to the wallet api, mostly argument serialization
 
+use crate::cost_basis::FiatPrice;
+use crate::error::{Error, ErrorKind};
 use crate::grin_core::libtx::secp_ser;
 use crate::grin_keychain::Identifier;
 use crate::grin_util::secp::pedersen;
+use crate::grin_util::Mutex;
+use crate::slate::{
+	Slate, ONETIME_ADDRESS_ATTACHMENT, PAYJOIN_ATTACHMENT, PAYMENT_PROOF_REQUEST_ATTACHMENT,
+	REFUND_ADDRESS_ATTACHMENT,
+};
 use crate::slate_versions::SlateVersion;
-use crate::types::OutputData;
+use crate::types::{OutputData, TxLogEntry, TxLogEntryType};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, VecDeque};
+use uuid::Uuid;
 
 /// Send TX API Args
 // TODO: This is here to ensure the legacy V1 API remains intact
@@ -94,6 +104,134 @@ pub struct InitTxArgs {
 	/// Sender arguments. If present, the underlying function will also attempt to send the
 	/// transaction to a destination and optionally finalize the result
 	pub send_args: Option<InitTxSendArgs>,
+	/// If `true`, the transaction can only be finalized or posted once it has been approved via
+	/// [`approve_tx`](../grin_wallet_api/owner/struct.Owner.html#method.approve_tx) with a
+	/// different approval token than `approval_token`, which is then required. Every
+	/// transaction needs approval, whatever this is set to, when the wallet's
+	/// `require_tx_approval` setting is. An invoice paid needing approval is signed by
+	/// processing it again once approved.
+	pub requires_approval: Option<bool>,
+	/// Approval token of the operator creating a transaction requiring approval, as created by
+	/// [`create_approval_token`](../grin_wallet_api/owner/struct.Owner.html#method.create_approval_token).
+	#[serde(default)]
+	pub approval_token: Option<String>,
+	/// Optional structured attachments (such as an order id or a refund address) to add
+	/// alongside the sender's ParticipantData. Encrypted attachments can only be added once
+	/// the other participant has joined the slate, i.e. when paying an invoice.
+	pub attachments: Option<Vec<SlateAttachmentArgs>>,
+	/// Label of the output reservation made for this payment via
+	/// [`reserve_outputs`](../grin_wallet_api/owner/struct.Owner.html#method.reserve_outputs).
+	/// Outputs held for it can be spent along with unreserved ones, while outputs held for
+	/// other reservations are never selected.
+	pub reservation: Option<String>,
+	/// Reference of the balance hold made for this payment via
+	/// [`create_hold`](../grin_wallet_api/owner/struct.Owner.html#method.create_hold).
+	/// The send can spend the held amount, and the hold is released once the transaction
+	/// is created. Amounts held by other holds are never spent.
+	pub hold: Option<String>,
+	/// If `true`, ask the recipient to contribute one of its own inputs to the transaction,
+	/// so that not all of its inputs are the sender's. Recipients that don't contribute
+	/// inputs just receive the amount as usual.
+	pub payjoin: Option<bool>,
+	/// If `true`, the fee is deducted from `amount` rather than added on top of it: the
+	/// transaction spends `amount` in total and the recipient receives `amount` less the fee,
+	/// whatever the number of inputs selected. The amount of the returned slate is then what
+	/// the recipient receives.
+	pub fee_from_amount: Option<bool>,
+	/// If `true`, ask the recipient for a payment proof, signed with its proof address.
+	/// Finalizing the transaction fails if the recipient doesn't sign one. The proof is
+	/// then retrieved with
+	/// [`retrieve_payment_proof`](../grin_wallet_api/owner/struct.Owner.html#method.retrieve_payment_proof).
+	pub payment_proof: Option<bool>,
+	/// Optional id to give the slate, rather than a random one. Must not be the id
+	/// of a tx already in the wallet
+	#[serde(default)]
+	pub slate_id: Option<Uuid>,
+}
+
+/// A structured attachment to add to a slate
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SlateAttachmentArgs {
+	/// Kind of attachment, e.g. "order_id" or "refund_address". Alphanumeric, '_' or '-',
+	/// at most 32 characters
+	pub kind: String,
+	/// Attachment content, at most 512 bytes
+	pub content: String,
+	/// Whether to encrypt the content so only the other participant can read it
+	#[serde(default)]
+	pub encrypt: bool,
+}
+
+impl SlateAttachmentArgs {
+	/// A refund address attachment, encrypted to the other participant if `encrypt` is set
+	pub fn refund_address(address: &str, encrypt: bool) -> SlateAttachmentArgs {
+		SlateAttachmentArgs {
+			kind: REFUND_ADDRESS_ATTACHMENT.to_owned(),
+			content: address.to_owned(),
+			encrypt,
+		}
+	}
+
+	/// A payjoin request, asking the recipient to contribute one of its inputs
+	pub fn payjoin() -> SlateAttachmentArgs {
+		SlateAttachmentArgs {
+			kind: PAYJOIN_ATTACHMENT.to_owned(),
+			content: "1".to_owned(),
+			encrypt: false,
+		}
+	}
+
+	/// A payment proof request, giving the proof address of the sender
+	pub fn payment_proof_request(address: &str) -> SlateAttachmentArgs {
+		SlateAttachmentArgs {
+			kind: PAYMENT_PROOF_REQUEST_ATTACHMENT.to_owned(),
+			content: address.to_owned(),
+			encrypt: false,
+		}
+	}
+
+	/// Id of the one-time address of the recipient being paid to
+	pub fn onetime_address(id: &Uuid) -> SlateAttachmentArgs {
+		SlateAttachmentArgs {
+			kind: ONETIME_ADDRESS_ATTACHMENT.to_owned(),
+			content: id.to_simple().to_string(),
+			encrypt: false,
+		}
+	}
+}
+
+/// Where funds should be refunded to, as given in a slate's refund address attachment.
+/// By convention this is either the http(s) url of a listening wallet, or
+/// `keybase:<username>`
+#[derive(Clone, Debug, PartialEq)]
+pub struct RefundAddress {
+	/// Send method, 'http' or 'keybase'
+	pub method: String,
+	/// Destination for the send method
+	pub dest: String,
+}
+
+impl RefundAddress {
+	/// Parse a refund address attachment
+	pub fn parse(address: &str) -> Result<RefundAddress, Error> {
+		let address = address.trim();
+		if address.starts_with("http://") || address.starts_with("https://") {
+			return Ok(RefundAddress {
+				method: "http".to_owned(),
+				dest: address.to_owned(),
+			});
+		}
+		if address.starts_with("keybase:") && address.len() > "keybase:".len() {
+			return Ok(RefundAddress {
+				method: "keybase".to_owned(),
+				dest: address["keybase:".len()..].to_owned(),
+			});
+		}
+		Err(ErrorKind::RefundError(format!(
+			"Invalid refund address '{}', expected an http(s) url or keybase:<username>",
+			address
+		)))?
+	}
 }
 
 /// Send TX API Args, for convenience functionality that inits the transaction and sends
@@ -110,6 +248,15 @@ pub struct InitTxSendArgs {
 	pub post_tx: bool,
 	/// Whether to use dandelion when posting. If false, skip the dandelion relay
 	pub fluff: bool,
+	/// Whether to cancel the transaction, unlocking its outputs, if finalizing or
+	/// posting it fails. Defaults to the `cancel_tx_on_send_failure` setting of
+	/// the wallet
+	#[serde(default)]
+	pub cancel_on_failure: Option<bool>,
+	/// Seconds the recipient is given to answer each request of the send, the default
+	/// timeouts of the method if `None`. A send timing out is kept to be dispatched again.
+	#[serde(default)]
+	pub timeout_secs: Option<u64>,
 }
 
 impl Default for InitTxArgs {
@@ -125,10 +272,125 @@ impl Default for InitTxArgs {
 			target_slate_version: None,
 			estimate_only: Some(false),
 			send_args: None,
+			requires_approval: None,
+			approval_token: None,
+			attachments: None,
+			reservation: None,
+			hold: None,
+			payjoin: None,
+			fee_from_amount: None,
+			payment_proof: None,
+			slate_id: None,
 		}
 	}
 }
 
+/// Arguments to hold outputs for an upcoming scheduled payment
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ReserveOutputsArgs {
+	/// Label of the reservation, to give as the `reservation` of the payment's
+	/// [`InitTxArgs`](struct.InitTxArgs.html)
+	pub label: String,
+	/// The amount the payment will send, in nanogrins. Enough outputs are held to cover it
+	/// and the fee.
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount: u64,
+	/// The minimum number of confirmations of the held outputs
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub minimum_confirmations: u64,
+	/// How long the outputs are held, in seconds. Once the hold expires, the outputs can
+	/// be selected by any send again.
+	pub expires_in_secs: u64,
+}
+
+/// Constraints of a sweep of the whole balance to another (cold) wallet
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SweepArgs {
+	/// The human readable account name to sweep, the active account if `None`
+	pub src_acct_name: Option<String>,
+	/// The minimum number of confirmations of the swept outputs
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub minimum_confirmations: u64,
+	/// Most outputs spent by a single transaction
+	pub max_outputs_per_tx: u32,
+	/// Largest weight of a single transaction, counted as in a block, if limited
+	/// below the weight of a block. Transactions always leave room in a block for its
+	/// coinbase, so a sweep of many outputs is split to get mined.
+	pub max_tx_weight: Option<u64>,
+	/// Largest total of the fees of all the transactions, if limited
+	#[serde(with = "secp_ser::opt_string_or_u64")]
+	pub fee_budget: Option<u64>,
+}
+
+/// A transaction of a sweep
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SweepBatch {
+	/// Commitments of the outputs the transaction spends
+	pub inputs: Vec<String>,
+	/// Amount sent, the value of the inputs less the fee
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount: u64,
+	/// Fee of the transaction
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub fee: u64,
+}
+
+/// Fee rate recommended for a transaction to confirm quickly, per unit of
+/// transaction weight as counted by `tx_fee` (4 per output, 1 per kernel, -1 per
+/// input, at least 1)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FeeRateEstimate {
+	/// Lowest fee rate nodes accept, which the wallet uses by default
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub minimum_fee_rate: u64,
+	/// Median fee rate of the recent blocks including transactions, if any
+	#[serde(with = "secp_ser::opt_string_or_u64")]
+	pub recent_blocks_fee_rate: Option<u64>,
+	/// Number of recent blocks looked at
+	pub num_blocks: u64,
+	/// Number of transactions in the node's mempool
+	pub pool_txs: usize,
+	/// Whether the mempool holds more transactions than fit in the next block
+	pub pool_congested: bool,
+	/// Recommended fee rate
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub recommended_fee_rate: u64,
+}
+
+/// Transactions moving the whole balance of an account, executed in order
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SweepPlan {
+	/// Account the outputs are swept from
+	pub src_acct_name: Option<String>,
+	/// The minimum number of confirmations of the swept outputs
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub minimum_confirmations: u64,
+	/// The transactions
+	pub batches: Vec<SweepBatch>,
+	/// Total amount sent
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount: u64,
+	/// Total of the fees
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub fee: u64,
+	/// Value of the outputs left behind because they're worth less than the fee to spend them
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub dust: u64,
+}
+
+/// Progress of a sweep, reported as each transaction is posted and confirmed
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SweepProgress {
+	/// Index of the transaction in the plan
+	pub batch: usize,
+	/// Number of transactions in the plan
+	pub batches: usize,
+	/// Slate id of the transaction
+	pub tx_slate_id: Uuid,
+	/// Whether the transaction is confirmed
+	pub confirmed: bool,
+}
+
 /// V2 Issue Invoice Tx Args
 #[derive(Clone, Serialize, Deserialize)]
 pub struct IssueInvoiceTxArgs {
@@ -145,6 +407,8 @@ pub struct IssueInvoiceTxArgs {
 	/// down to the minimum slate version compatible with the current. If `None` the slate
 	/// is generated with the latest version.
 	pub target_slate_version: Option<u16>,
+	/// Optional unencrypted structured attachments, such as an order id
+	pub attachments: Option<Vec<SlateAttachmentArgs>>,
 }
 
 impl Default for IssueInvoiceTxArgs {
@@ -154,6 +418,7 @@ impl Default for IssueInvoiceTxArgs {
 			amount: 0,
 			message: None,
 			target_slate_version: None,
+			attachments: None,
 		}
 	}
 }
@@ -191,6 +456,356 @@ pub struct OutputCommitMapping {
 	pub commit: pedersen::Commitment,
 }
 
+/// A change output created by a send, as it will be spent to the wallet
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChangeOutput {
+	/// Key id of the output
+	pub key_id: Identifier,
+	/// The commit
+	#[serde(
+		serialize_with = "secp_ser::as_hex",
+		deserialize_with = "secp_ser::commitment_from_hex"
+	)]
+	pub commit: pedersen::Commitment,
+	/// Value of the output
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub value: u64,
+}
+
+/// Result of initiating a send, along with the change outputs the transaction creates
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct InitTxResult {
+	/// The slate, as returned by [`init_send_tx`](../grin_wallet_api/owner/struct.Owner.html#method.init_send_tx)
+	pub slate: Slate,
+	/// Change outputs created by the transaction, none if it spends its inputs exactly
+	pub change_outputs: Vec<ChangeOutput>,
+}
+
+/// Result of auditing how an output created by a transaction was derived
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OutputDerivationAudit {
+	/// Output Data
+	pub output: OutputData,
+	/// Whether the output is change from a send, rather than a received output
+	pub is_change: bool,
+	/// Derivation index (last path component) the output key was derived with
+	pub derivation_index: u32,
+	/// Whether the output can be rebuilt from the wallet seed and its derivation
+	/// path alone, i.e. would be found again by a restore
+	pub recoverable: bool,
+	/// Whether the range proof of the output on the chain was rewound with the
+	/// wallet seed, as a restore does, rather than only its commitment rebuilt
+	pub rangeproof_checked: bool,
+	/// Whether another output in the wallet was derived from the same key
+	pub index_reused: bool,
+	/// Problems found with the output, empty if none
+	pub issues: Vec<String>,
+}
+
+/// Derivation index usage of an account, as found among the outputs in the wallet
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChildIndexScan {
+	/// Account label
+	pub label: String,
+	/// Parent derivation path of the account
+	pub parent_key_id: Identifier,
+	/// Index the next new output of the account will be derived with
+	pub next_child_index: u32,
+	/// Highest index used by an output in the wallet, if any
+	pub highest_used_index: Option<u32>,
+	/// Inclusive ranges of indices below `next_child_index` not used by any output in
+	/// the wallet
+	pub unused_ranges: Vec<(u32, u32)>,
+	/// Indices used by outputs at or beyond `next_child_index`, which new outputs would
+	/// reuse unless the counter is moved past them
+	pub used_beyond_next: Vec<u32>,
+}
+
+/// An output of the wallet found by a step-wise restore, not yet saved to the wallet
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RestoredOutput {
+	/// The commit
+	#[serde(
+		serialize_with = "secp_ser::as_hex",
+		deserialize_with = "secp_ser::commitment_from_hex"
+	)]
+	pub commit: pedersen::Commitment,
+	/// Key id of the output
+	pub key_id: Identifier,
+	/// Value of the output
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub value: u64,
+	/// Height of the block the output was created in
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub height: u64,
+	/// Insertion index of the output in the output MMR
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub mmr_index: u64,
+	/// Whether the output is a coinbase output
+	pub is_coinbase: bool,
+}
+
+/// Progress of a step-wise restore. Nothing is written to the wallet until the scan
+/// is committed, so it's handed back to each step and can be dropped at any point to
+/// cancel the restore, or kept to resume it later.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RestoreScan {
+	/// Height of the first block whose outputs are rewound
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub min_height: u64,
+	/// Insertion index of the first output scanned
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub start_index: u64,
+	/// Insertion index of the last output scanned, the last one on chain when the
+	/// scan was started
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub end_index: u64,
+	/// Insertion index of the next output to scan, past `end_index` once the scan
+	/// is complete
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub next_index: u64,
+	/// Outputs of the wallet found so far
+	pub outputs: Vec<RestoredOutput>,
+}
+
+impl RestoreScan {
+	/// Whether all the outputs of the range were scanned
+	pub fn is_complete(&self) -> bool {
+		self.next_index > self.end_index
+	}
+
+	/// Number of output indices in the range, an estimate of the length of the scan
+	/// as spent outputs are skipped
+	pub fn total(&self) -> u64 {
+		(self.end_index + 1).saturating_sub(self.start_index)
+	}
+
+	/// Number of output indices left to scan
+	pub fn remaining(&self) -> u64 {
+		(self.end_index + 1).saturating_sub(self.next_index)
+	}
+
+	/// Total value of the outputs found so far, the balance the restore recovers
+	pub fn amount_found(&self) -> u64 {
+		self.outputs.iter().map(|o| o.value).sum()
+	}
+}
+
+/// A transaction with its outputs, kernel and confirmations
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TxDetails {
+	/// Transaction log entry
+	pub tx: TxLogEntry,
+	/// Wallet outputs the transaction spends
+	pub inputs: Vec<OutputCommitMapping>,
+	/// Outputs the transaction creates for the wallet, other than change
+	pub outputs: Vec<OutputCommitMapping>,
+	/// Change outputs of a sent transaction
+	pub change: Vec<OutputCommitMapping>,
+	/// Excess of the transaction kernel, once the transaction is finalized
+	pub kernel_excess: Option<String>,
+	/// Whether the transaction is stored in the wallet
+	pub has_stored_tx: bool,
+	/// Number of blocks confirming the transaction, 0 while unconfirmed
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub confirmations: u64,
+	/// Ticker of the currency the amounts are in
+	#[serde(default)]
+	pub currency: String,
+}
+
+/// Output spent by a transaction, a lot of the cost-basis report of the transaction
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CostBasisLot {
+	/// Commitment of the output
+	pub commit: Option<String>,
+	/// Value of the output
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub value: u64,
+	/// Price of the coin when the output was acquired, if known
+	pub cost_basis: Option<FiatPrice>,
+}
+
+/// Transaction of the log as exported for accounting. Fiat values are in hundredths
+/// of the currency of the price of the transaction.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TxExportEntry {
+	/// Transaction log entry
+	pub tx: TxLogEntry,
+	/// Outputs a confirmed sent transaction spent
+	pub lots: Vec<CostBasisLot>,
+	/// Fiat value of the amount received, or of the amount and fee sent
+	pub fiat_value: Option<u64>,
+	/// Cost basis of the amount and fee sent, for a confirmed sent transaction
+	pub cost_basis: Option<u64>,
+	/// Gain realized by a confirmed sent transaction, its fiat value less its cost basis
+	pub realized_gain: Option<i64>,
+	/// Fiat currency the values are in, `None` if the transaction wasn't valued
+	pub currency: Option<String>,
+}
+
+/// Changes made while rebuilding the indexes derived from the outputs and tx log
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct IndexRebuildReport {
+	/// Outputs whose cached commitment was missing or didn't match their key and value
+	pub commits_fixed: u32,
+	/// Outputs referring to a transaction log entry that doesn't exist, reference removed
+	pub tx_links_pruned: u32,
+	/// Sent transactions linked back to the transaction file stored under their slate id
+	pub stored_txs_relinked: u32,
+	/// Transactions referring to a stored transaction file that doesn't exist, reference
+	/// removed
+	pub stored_txs_pruned: u32,
+}
+
+/// Whether a ledger entry is a deposit to the wallet or a withdrawal from it
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum LedgerDirection {
+	/// Funds received by the wallet
+	Deposit,
+	/// Funds sent by the wallet
+	Withdrawal,
+}
+
+/// A transaction of an externally kept ledger, such as an exchange's books
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LedgerEntry {
+	/// Slate id of the transaction
+	pub tx_slate_id: Uuid,
+	/// Whether the funds were received or sent
+	pub direction: LedgerDirection,
+	/// Amount received, or sent to the other party not counting the fee
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount: u64,
+	/// Reference of the entry in the external system
+	pub reference: Option<String>,
+}
+
+/// Ledger to reconcile against the wallet
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct ReconcileArgs {
+	/// Expected transactions
+	pub entries: Vec<LedgerEntry>,
+	/// Expected total balance of the account, if it should be checked
+	#[serde(with = "secp_ser::opt_string_or_u64")]
+	pub expected_balance: Option<u64>,
+	/// Wallet transactions created before this time aren't reported as unexpected,
+	/// as they were covered by an earlier ledger
+	pub since: Option<DateTime<Utc>>,
+}
+
+/// A ledger entry whose amount differs from the wallet's
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LedgerMismatch {
+	/// The ledger entry
+	pub entry: LedgerEntry,
+	/// Amount of the wallet transaction
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub actual_amount: u64,
+}
+
+/// Differences between a ledger and the wallet
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ReconciliationReport {
+	/// Number of ledger entries matching a wallet transaction
+	pub matched: usize,
+	/// Expected deposits the wallet didn't receive, or that aren't confirmed yet
+	pub missing_deposits: Vec<LedgerEntry>,
+	/// Expected withdrawals the wallet didn't send, or that were cancelled
+	pub missing_withdrawals: Vec<LedgerEntry>,
+	/// Confirmed received transactions not in the ledger
+	pub unexpected_deposits: Vec<TxLogEntry>,
+	/// Sent transactions not in the ledger
+	pub unexpected_sends: Vec<TxLogEntry>,
+	/// Ledger entries whose amount differs from the wallet transaction's
+	pub amount_mismatches: Vec<LedgerMismatch>,
+	/// Expected total balance, if checked
+	#[serde(with = "secp_ser::opt_string_or_u64")]
+	pub expected_balance: Option<u64>,
+	/// Total balance of the account
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub actual_balance: u64,
+}
+
+impl ReconciliationReport {
+	/// Whether the wallet matches the ledger
+	pub fn is_reconciled(&self) -> bool {
+		self.missing_deposits.is_empty()
+			&& self.missing_withdrawals.is_empty()
+			&& self.unexpected_deposits.is_empty()
+			&& self.unexpected_sends.is_empty()
+			&& self.amount_mismatches.is_empty()
+			&& self
+				.expected_balance
+				.map(|b| b == self.actual_balance)
+				.unwrap_or(true)
+	}
+}
+
+/// Selects the transactions of a bulk cancel. Only unconfirmed sent or received
+/// transactions are ever selected, all of them with the default filter.
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct TxFilter {
+	/// Only transactions of this type, `TxSent` or `TxReceived`
+	pub tx_type: Option<TxLogEntryType>,
+	/// Only transactions created at least this many seconds ago
+	pub min_age_secs: Option<u64>,
+	/// Only transactions of this account, those of all accounts if `None`
+	pub account: Option<String>,
+}
+
+impl TxFilter {
+	/// Whether the filter selects the transaction, at the given time
+	pub fn matches(&self, tx: &TxLogEntry, now: DateTime<Utc>) -> bool {
+		if tx.confirmed
+			|| (tx.tx_type != TxLogEntryType::TxSent && tx.tx_type != TxLogEntryType::TxReceived)
+		{
+			return false;
+		}
+		if let Some(t) = self.tx_type.as_ref() {
+			if *t != tx.tx_type {
+				return false;
+			}
+		}
+		match self.min_age_secs {
+			Some(secs) => now.signed_duration_since(tx.creation_ts).num_seconds() >= secs as i64,
+			None => true,
+		}
+	}
+}
+
+/// Outcome of the cancellation of a transaction by a bulk cancel
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TxCancelResult {
+	/// Id of the transaction in the log
+	pub tx_id: u32,
+	/// Id of the slate of the transaction
+	pub tx_slate_id: Option<Uuid>,
+	/// Why the transaction couldn't be cancelled, none if it was
+	pub error: Option<String>,
+}
+
+/// What became of a transaction the node refused
+#[derive(Debug, Clone, PartialEq)]
+pub enum RefusedTxState {
+	/// A kernel of the transaction is on chain or in the node's pool, the
+	/// transaction went through after all
+	Landed,
+	/// Inputs of the transaction the node shows spent by another one, the
+	/// outputs of the wallet of which were marked spent. Empty if there's no
+	/// such evidence
+	InputsSpent(Vec<pedersen::Commitment>),
+}
+
+/// Stored output found inconsistent by an integrity check of the wallet
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OutputIntegrityIssue {
+	/// Output Data
+	pub output: OutputData,
+	/// What's wrong with the output
+	pub issue: String,
+}
+
 /// Node height result
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct NodeHeightResult {
@@ -201,6 +816,162 @@ pub struct NodeHeightResult {
 	pub updated_from_node: bool,
 }
 
+/// Ping result
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PingResult {
+	/// Time on the wallet server
+	pub server_time: DateTime<Utc>,
+	/// Version of the owner API answering
+	pub owner_api_version: u16,
+	/// Whether a wallet is open
+	pub wallet_open: bool,
+}
+
+/// Time spent in each step of building the sends of a wallet since it was opened,
+/// to tell which dominates their latency. Times are totals over all sends, in
+/// microseconds.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct SendMetrics {
+	/// Number of sends built, invoices paid included
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub sends: u64,
+	/// Refreshing the wallet's outputs from the node
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub refresh_us: u64,
+	/// Selecting the inputs and reserving the change keys
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub selection_us: u64,
+	/// Building the inputs and change outputs, change rangeproofs included
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub build_us: u64,
+	/// Whole sends, the steps above included
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub total_us: u64,
+}
+
+/// Operation of this process using the wallet across several locks of it, such
+/// as a send waiting for its recipient, which closing the wallet would break
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PendingOperation {
+	/// Id of the operation in this process
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub id: u64,
+	/// What the operation does, e.g. `send` or `dispatch_tx`
+	pub kind: String,
+	/// Slate of the transaction of the operation, if known
+	pub tx_slate_id: Option<Uuid>,
+	/// When the operation started
+	pub started: DateTime<Utc>,
+}
+
+/// State of an operation of this process
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum OperationState {
+	/// Still in progress
+	Running,
+	/// Ended successfully
+	Completed,
+	/// Ended with an error
+	Failed,
+}
+
+/// Status of an operation of this process, running or recently ended
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct OperationStatus {
+	/// The operation
+	pub operation: PendingOperation,
+	/// Whether it's running, or how it ended
+	pub state: OperationState,
+	/// Error the operation failed with
+	pub error: Option<String>,
+	/// When the operation ended
+	pub finished: Option<DateTime<Utc>>,
+}
+
+/// Operations using a wallet instance across several locks of it, which closing
+/// the wallet waits for. Shared by all the users of the wallet instance, whatever
+/// wallet lock they hold.
+#[derive(Default)]
+pub struct PendingOperations {
+	pub(crate) state: Mutex<PendingOperationsState>,
+}
+
+/// Operations in progress, the last ones ended, and number of closes of the
+/// wallet refusing new ones
+#[derive(Default)]
+pub(crate) struct PendingOperationsState {
+	pub next_id: u64,
+	pub closing: usize,
+	pub operations: Vec<PendingOperation>,
+	pub ended: VecDeque<OperationStatus>,
+}
+
+/// Handle of a send dispatched in the background
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SendHandle {
+	/// Id of the operation dispatching the send, to query its status with
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub operation_id: u64,
+	/// Slate of the transaction sent
+	pub tx_slate_id: Uuid,
+}
+
+/// What a recipient reported when contacted without sending it a slate, to check it
+/// can receive from this wallet before locking any funds
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecipientHandshake {
+	/// Method the recipient was contacted with
+	pub method: String,
+	/// Destination of the recipient
+	pub dest: String,
+	/// Foreign API version of the recipient
+	pub foreign_api_version: u16,
+	/// Slate versions the recipient supports
+	pub supported_slate_versions: Vec<String>,
+	/// Version slates would be sent to the recipient as
+	pub slate_version: u16,
+	/// Difficulty of the receive challenge the recipient requires, if any
+	pub receive_challenge_difficulty: Option<u8>,
+	/// Whether the recipient receives slates in resumable chunks
+	pub slate_exchange: bool,
+	/// Time the recipient took to reply to the version check, in milliseconds
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub latency_ms: u64,
+}
+
+/// Usage statistics of a wallet, for capacity planning and health checks
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WalletStats {
+	/// Number of outputs of each status, over all accounts
+	pub outputs: BTreeMap<String, u64>,
+	/// Number of transaction log entries of each type, over all accounts
+	pub tx_entries: BTreeMap<String, u64>,
+	/// Number of accounts
+	pub accounts: u64,
+	/// Oldest transaction neither confirmed, cancelled nor abandoned, if any
+	pub oldest_unconfirmed_tx: Option<TxLogEntry>,
+	/// Size of the files of the wallet data directory, in bytes, if it could be read
+	#[serde(with = "secp_ser::opt_string_or_u64")]
+	pub data_dir_size: Option<u64>,
+	/// Space left on the disk of the wallet data directory, in bytes, if known
+	#[serde(with = "secp_ser::opt_string_or_u64")]
+	pub disk_free_space: Option<u64>,
+	/// When the wallet was last backed up with the `backup` command, if ever
+	pub last_backup: Option<DateTime<Utc>>,
+}
+
+/// A second factor just set up, to be entered into an authenticator app. The
+/// recovery codes are only ever shown here.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TotpSetup {
+	/// Secret, in base32
+	pub secret: String,
+	/// `otpauth` uri of the secret, usually shown as a QR code
+	pub uri: String,
+	/// Codes usable once each in place of a code of the authenticator
+	pub recovery_codes: Vec<String>,
+}
+
 /// Version request result
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct VersionInfo {
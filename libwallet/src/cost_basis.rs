@@ -0,0 +1,179 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Fiat values of the coins held, for the jurisdictions taxing the gains made on
+//! them lot by lot. Received outputs are tagged with the price of the coin when
+//! received, their cost basis, and sent transactions with the price they were
+//! sent at. The price comes from the price provider the wallet is given, or is
+//! entered by hand. Change outputs carry over the cost basis of the inputs they
+//! were split from.
+
+use crate::error::Error;
+use crate::grin_core::consensus::GRIN_BASE;
+use crate::grin_util::RwLock;
+use chrono::prelude::*;
+use chrono::Duration;
+use std::sync::Arc;
+
+/// Seconds a price fetched from the provider is reused for
+const PRICE_CACHE_SECS: i64 = 300;
+
+/// Source of the prices entered by hand
+pub const MANUAL_PRICE_SOURCE: &str = "manual";
+
+/// Price of a whole coin in a fiat currency at some time
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FiatPrice {
+	/// Fiat currency of the price, e.g. `USD`
+	pub currency: String,
+	/// Price of a whole coin, in hundredths of the fiat currency
+	pub unit_price: u64,
+	/// Where the price came from, the price provider, `manual` or `blended` for the
+	/// cost basis carried over from several outputs
+	pub source: String,
+	/// When the price applied. For a blended cost basis, the earliest acquisition.
+	pub time: DateTime<Utc>,
+}
+
+impl FiatPrice {
+	/// Fiat value of an amount of nanocoins, in hundredths of the fiat currency
+	pub fn value_of(&self, amount: u64) -> u64 {
+		(u128::from(amount) * u128::from(self.unit_price) / u128::from(GRIN_BASE)) as u64
+	}
+}
+
+/// Source of the fiat price of the coin
+pub trait PriceProvider: Send + Sync {
+	/// Name of the provider, recorded as the source of its prices
+	fn name(&self) -> String;
+
+	/// Current price of a whole coin, in hundredths of the fiat currency
+	fn price(&self, currency: &str) -> Result<u64, Error>;
+}
+
+/// Price provider of a wallet, with the fiat currency it values its coins in and the
+/// last price the provider gave
+pub struct PriceSource {
+	currency: String,
+	provider: Arc<dyn PriceProvider>,
+	last_price: RwLock<Option<FiatPrice>>,
+}
+
+impl PriceSource {
+	/// Value coins in `currency` at the prices of `provider`
+	pub fn new(currency: &str, provider: Arc<dyn PriceProvider>) -> PriceSource {
+		PriceSource {
+			currency: currency.to_owned(),
+			provider,
+			last_price: RwLock::new(None),
+		}
+	}
+
+	/// Fiat currency coins are valued in
+	pub fn currency(&self) -> &str {
+		&self.currency
+	}
+
+	/// Current price of the coin from the provider, reusing one fetched in the last
+	/// few minutes. `None` if the provider failed, which doesn't keep transactions
+	/// from going through.
+	pub fn current_price(&self) -> Option<FiatPrice> {
+		let now = Utc::now();
+		if let Some(p) = self.last_price.read().as_ref() {
+			if now - p.time < Duration::seconds(PRICE_CACHE_SECS) {
+				return Some(p.clone());
+			}
+		}
+		match self.provider.price(&self.currency) {
+			Ok(unit_price) => {
+				let price = FiatPrice {
+					currency: self.currency.clone(),
+					unit_price,
+					source: self.provider.name(),
+					time: now,
+				};
+				*self.last_price.write() = Some(price.clone());
+				Some(price)
+			}
+			Err(e) => {
+				warn!(
+					"Unable to get the price of the coin in {}: {}",
+					self.currency, e
+				);
+				None
+			}
+		}
+	}
+}
+
+/// Cost basis of an output made of several lots, the average of their unit prices
+/// weighted by their amounts. `None` unless all the lots have a cost basis in the
+/// same currency.
+pub fn blend(lots: &[(u64, Option<&FiatPrice>)]) -> Option<FiatPrice> {
+	if lots.len() == 1 {
+		return lots[0].1.cloned();
+	}
+	let first = lots.first()?.1?;
+	let mut total = 0u128;
+	let mut cost = 0u128;
+	let mut time = first.time;
+	for (amount, basis) in lots {
+		let basis = (*basis)?;
+		if basis.currency != first.currency {
+			return None;
+		}
+		total += u128::from(*amount);
+		cost += u128::from(*amount) * u128::from(basis.unit_price);
+		time = time.min(basis.time);
+	}
+	if total == 0 {
+		return None;
+	}
+	Some(FiatPrice {
+		currency: first.currency.clone(),
+		unit_price: (cost / total) as u64,
+		source: "blended".to_owned(),
+		time,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn price(unit_price: u64, days_ago: i64) -> FiatPrice {
+		FiatPrice {
+			currency: "USD".to_owned(),
+			unit_price,
+			source: MANUAL_PRICE_SOURCE.to_owned(),
+			time: Utc::now() - Duration::days(days_ago),
+		}
+	}
+
+	#[test]
+	fn blended_cost_basis() {
+		let (old, new) = (price(100, 30), price(400, 1));
+		assert_eq!(old.value_of(3 * GRIN_BASE / 2), 150);
+
+		let blended = blend(&[(GRIN_BASE, Some(&old)), (3 * GRIN_BASE, Some(&new))]).unwrap();
+		assert_eq!(blended.unit_price, 325);
+		assert_eq!(blended.time, old.time);
+		assert_eq!(blended.value_of(4 * GRIN_BASE), 1300);
+
+		assert!(blend(&[(GRIN_BASE, Some(&old)), (GRIN_BASE, None)]).is_none());
+		let mut eur = new.clone();
+		eur.currency = "EUR".to_owned();
+		assert!(blend(&[(GRIN_BASE, Some(&old)), (GRIN_BASE, Some(&eur))]).is_none());
+	}
+}
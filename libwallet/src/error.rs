@@ -14,11 +14,13 @@
 
 //! Error types for libwallet
 
+use crate::api_impl::types::PendingOperation;
 use crate::grin_core::core::{committed, transaction};
 use crate::grin_core::libtx;
 use crate::grin_keychain;
 use crate::grin_store;
 use crate::grin_util::secp;
+use crate::l10n;
 use failure::{Backtrace, Context, Fail};
 use std::env;
 use std::fmt::{self, Display};
@@ -69,6 +71,13 @@ pub enum ErrorKind {
 	#[fail(display = "Client Callback Error: {}", _0)]
 	ClientCallback(String),
 
+	/// Some outputs couldn't be queried from the node during a refresh
+	#[fail(
+		display = "Partial output refresh, {} of {} outputs couldn't be queried: {}",
+		_0, _1, _2
+	)]
+	PartialRefresh(usize, usize, String),
+
 	/// Secp Error
 	#[fail(display = "Secp error")]
 	Secp(secp::Error),
@@ -137,6 +146,10 @@ pub enum ErrorKind {
 	#[fail(display = "Wallet seed decryption error")]
 	WalletSeedDecryption,
 
+	/// Wallet data directory locked by another process
+	#[fail(display = "Wallet already in use: {}", _0)]
+	WalletInUse(String),
+
 	/// Transaction doesn't exist
 	#[fail(display = "Transaction {} doesn't exist", _0)]
 	TransactionDoesntExist(String),
@@ -145,6 +158,14 @@ pub enum ErrorKind {
 	#[fail(display = "Transaction {} cannot be cancelled", _0)]
 	TransactionNotCancellable(String),
 
+	/// Transaction can't be marked abandoned
+	#[fail(display = "Transaction {} cannot be abandoned", _0)]
+	TransactionNotAbandonable(String),
+
+	/// No send prepared for the slate
+	#[fail(display = "Transaction {} is not waiting to be dispatched", _0)]
+	NotPendingDispatch(String),
+
 	/// Cancellation error
 	#[fail(display = "Cancellation Error: {}", _0)]
 	TransactionCancellationError(&'static str),
@@ -165,6 +186,10 @@ pub enum ErrorKind {
 	#[fail(display = "Transaction building not completed: {}", _0)]
 	TransactionBuildingNotCompleted(u32),
 
+	/// Attempt to set a child index that would cause key reuse
+	#[fail(display = "Invalid child index: {}", _0)]
+	InvalidChildIndex(String),
+
 	/// Invalid BIP-32 Depth
 	#[fail(display = "Invalid BIP32 Depth (must be 1 or greater)")]
 	InvalidBIP32Depth,
@@ -213,6 +238,131 @@ pub enum ErrorKind {
 	#[fail(display = "Supplied Keychain Mask Token is incorrect")]
 	InvalidKeychainMask,
 
+	/// Transaction can't be finalized or posted until approved by a second token
+	#[fail(
+		display = "Transaction {} requires approval from a second Owner API token",
+		_0
+	)]
+	TransactionApprovalRequired(String),
+
+	/// Approval error
+	#[fail(display = "Approval Error: {}", _0)]
+	TransactionApprovalError(String),
+
+	/// Slate attachment error
+	#[fail(display = "Slate attachment error: {}", _0)]
+	SlateAttachment(String),
+
+	/// Refund error
+	#[fail(display = "Refund Error: {}", _0)]
+	RefundError(String),
+
+	/// One-time address error
+	#[fail(display = "One-time address error: {}", _0)]
+	OnetimeAddress(String),
+
+	/// Inputs of a transaction were already spent
+	#[fail(display = "{} inputs of the transaction were already spent", _0)]
+	InputsAlreadySpent(usize),
+
+	/// Wallet not closed, operations still in progress
+	#[fail(display = "{} operations still in progress", _0.len())]
+	OperationsPending(Vec<PendingOperation>),
+
+	/// Slate or request from another party beyond the limits the wallet accepts
+	#[fail(display = "{} is {}, more than the limit of {}", limit, actual, max)]
+	LimitExceeded {
+		/// What is limited
+		limit: String,
+		/// Value of the slate or request
+		actual: u64,
+		/// Limit
+		max: u64,
+	},
+
+	/// Foreign API key error
+	#[fail(display = "Foreign API key error: {}", _0)]
+	ForeignApiKey(String),
+
+	/// Miner API error
+	#[fail(display = "Miner API error: {}", _0)]
+	MinerApi(String),
+
+	/// Receive challenge error
+	#[fail(display = "Receive challenge error: {}", _0)]
+	ReceiveChallenge(String),
+
+	/// Foreign API method not enabled on the listener
+	#[fail(display = "Method {} is not enabled on this listener", _0)]
+	MethodDisabled(String),
+
+	/// Slate exchange error
+	#[fail(display = "Slate exchange error: {}", _0)]
+	SlateExchange(String),
+
+	/// Slate from a rejected origin
+	#[fail(display = "Slates from {} are not accepted", _0)]
+	SourceRejected(String),
+
+	/// Wallet frozen
+	#[fail(
+		display = "Wallet frozen since {}, unfreeze it with the wallet password",
+		_0
+	)]
+	WalletFrozen(String),
+
+	/// Watch-only wallet, without the seed
+	#[fail(display = "Watch-only wallet, {} needs the wallet seed", _0)]
+	WatchOnly(String),
+
+	/// Wallet opened read-only
+	#[fail(display = "Wallet opened read-only, {} isn't allowed", _0)]
+	ReadOnly(String),
+
+	/// Sweep error
+	#[fail(display = "Sweep error: {}", _0)]
+	Sweep(String),
+
+	/// Output import error
+	#[fail(display = "Output import error: {}", _0)]
+	ImportOutput(String),
+
+	/// Step-wise restore error
+	#[fail(display = "Restore scan error: {}", _0)]
+	RestoreScan(String),
+
+	/// Destination of a send couldn't be resolved
+	#[fail(display = "Unable to resolve destination: {}", _0)]
+	DestinationResolution(String),
+
+	/// Destination of a send couldn't be connected to
+	#[fail(display = "Unable to connect to destination: {}", _0)]
+	DestinationConnection(String),
+
+	/// Destination of a send replied with an HTTP error
+	#[fail(display = "Destination replied with HTTP status {}: {}", _0, _1)]
+	DestinationHttp(u16, String),
+
+	/// Tor error
+	#[fail(display = "Tor error: {}", _0)]
+	Tor(String),
+
+	/// Payment proof error
+	#[fail(display = "Payment proof error: {}", _0)]
+	PaymentProof(String),
+
+	/// Second factor (TOTP) error
+	#[fail(display = "Second factor error: {}", _0)]
+	Totp(String),
+
+	/// The node's chain stopped advancing
+	#[fail(display = "Stale node: {}", _0)]
+	StaleNode(String),
+
+	/// The configured nodes don't agree on the chain
+	#[fail(display = "No node quorum: {}", _0)]
+	NodeQuorum(String),
+
 	/// Other
 	#[fail(display = "Generic error: {}", _0)]
 	GenericError(String),
@@ -244,6 +394,46 @@ impl Display for Error {
 	}
 }
 
+impl ErrorKind {
+	/// Message of the error in `locale`, the `locale` a wallet is configured with.
+	/// Catalog messages are keyed by the name of the kind, with its fields as
+	/// variables named after them, or `arg0`, `arg1`... if unnamed.
+	pub fn localized(&self, locale: Option<&str>) -> String {
+		let display = format!("{}", self);
+		if l10n::catalog_locale(locale).is_none() {
+			return display;
+		}
+		let (id, fields) = match serde_json::to_value(self) {
+			Ok(serde_json::Value::String(id)) => (id, serde_json::Value::Null),
+			Ok(serde_json::Value::Object(o)) => match o.into_iter().next() {
+				Some(f) => f,
+				None => return display,
+			},
+			_ => return display,
+		};
+		let fields: Vec<(String, serde_json::Value)> = match fields {
+			serde_json::Value::Object(o) => o.into_iter().collect(),
+			serde_json::Value::Array(a) => a
+				.into_iter()
+				.enumerate()
+				.map(|(i, v)| (format!("arg{}", i), v))
+				.collect(),
+			serde_json::Value::Null => vec![],
+			v => vec![("arg0".to_owned(), v)],
+		};
+		let args: Vec<(String, String)> = fields
+			.into_iter()
+			.filter_map(|(name, v)| match v {
+				serde_json::Value::String(s) => Some((name, s)),
+				serde_json::Value::Number(n) => Some((name, n.to_string())),
+				serde_json::Value::Bool(b) => Some((name, b.to_string())),
+				_ => None,
+			})
+			.collect();
+		l10n::localize(locale, &id, &args).unwrap_or(display)
+	}
+}
+
 impl Error {
 	/// get kind
 	pub fn kind(&self) -> ErrorKind {
@@ -13,10 +13,14 @@
 // limitations under the License.
 
 //! Wallet key management functions
+use crate::blake2::blake2b::blake2b;
 use crate::error::{Error, ErrorKind};
-use crate::grin_keychain::{ChildNumber, ExtKeychain, Identifier, Keychain};
-use crate::grin_util::secp::key::SecretKey;
-use crate::types::{AcctPathMapping, NodeClient, WalletBackend};
+use crate::grin_keychain::{ChildNumber, Identifier, Keychain, SwitchCommitmentType};
+use crate::grin_util;
+use crate::grin_util::secp::key::{PublicKey, SecretKey};
+use crate::types::{AcctPathMapping, DerivationScheme, NodeClient, WalletBackend};
+use crate::ChildIndexScan;
+use std::collections::BTreeSet;
 
 /// Get next available key in the wallet for a given parent
 pub fn next_available_key<'a, T: ?Sized, C, K>(
@@ -28,10 +32,55 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
+	check_not_watch_only(wallet, "deriving a key")?;
 	let child = wallet.next_child(keychain_mask)?;
 	Ok(child)
 }
 
+/// Fail if the wallet is watch-only, its keychain holding none of the keys of the
+/// wallet it follows. Anything sent to a key it derived would be lost.
+pub fn check_not_watch_only<'a, T: ?Sized, C, K>(wallet: &T, action: &str) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	match wallet.watch_only_key() {
+		Some(_) => Err(ErrorKind::WatchOnly(action.to_owned()))?,
+		None => Ok(()),
+	}
+}
+
+/// First derivation index of coinbase keys derived from the block height
+/// rather than the account's child index counter. These are hardened indices
+/// the counter is never allowed to reach.
+pub const COINBASE_KEY_INDEX_BASE: u32 = 1 << 31;
+
+/// Whether a derivation index belongs to a coinbase key derived from the
+/// block height
+pub fn is_coinbase_key_index(n_child: u32) -> bool {
+	n_child >= COINBASE_KEY_INDEX_BASE
+}
+
+/// Key for the coinbase output at the given height, under the given parent
+/// path. The same height always gives the same key.
+pub fn coinbase_key_for_height(
+	parent_key_id: &Identifier,
+	height: u64,
+) -> Result<Identifier, Error> {
+	if height >= COINBASE_KEY_INDEX_BASE as u64 {
+		return Err(ErrorKind::GenericError(format!(
+			"Height {} is too high to derive a coinbase key for",
+			height
+		))
+		.into());
+	}
+	let mut path = parent_key_id.to_path();
+	path.depth = path.depth + 1;
+	path.path[path.depth as usize - 1] = ChildNumber::from(COINBASE_KEY_INDEX_BASE + height as u32);
+	Ok(Identifier::from_path(&path))
+}
+
 /// Retrieve an existing key from a wallet
 pub fn retrieve_existing_key<'a, T: ?Sized, C, K>(
 	wallet: &T,
@@ -59,11 +108,13 @@ where
 	Ok(wallet.acct_path_iter().collect())
 }
 
-/// Adds an new parent account path with a given label
+/// Adds an new parent account path with a given label, its keys derived with
+/// the given scheme
 pub fn new_acct_path<'a, T: ?Sized, C, K>(
 	wallet: &mut T,
 	keychain_mask: Option<&SecretKey>,
 	label: &str,
+	scheme: DerivationScheme,
 ) -> Result<Identifier, Error>
 where
 	T: WalletBackend<'a, C, K>,
@@ -75,22 +126,19 @@ where
 		return Err(ErrorKind::AccountLabelAlreadyExists(label.clone()).into());
 	}
 
-	// We're always using paths at m/k/0 for parent keys for output derivations
-	// so find the highest of those, then increment (to conform with external/internal
-	// derivation chains in BIP32 spec)
-
-	let highest_entry = wallet.acct_path_iter().max_by(|a, b| {
-		<u32>::from(a.path.to_path().path[0]).cmp(&<u32>::from(b.path.to_path().path[0]))
-	});
-
-	let return_id = {
-		if let Some(e) = highest_entry {
-			let mut p = e.path.to_path();
-			p.path[0] = ChildNumber::from(<u32>::from(p.path[0]) + 1);
-			p.to_identifier()
-		} else {
-			ExtKeychain::derive_key_id(2, 0, 0, 0, 0)
-		}
+	// We're always using paths at m/k/0 (or m/k'/0 for hardened accounts) for parent
+	// keys for output derivations so find the highest k of those, whatever their
+	// scheme, then increment (to conform with external/internal derivation chains
+	// in BIP32 spec)
+
+	let highest_index = wallet
+		.acct_path_iter()
+		.map(|a| DerivationScheme::account_index(&a.path))
+		.max();
+
+	let return_id = match highest_index {
+		Some(i) => scheme.account_path(i + 1),
+		None => scheme.account_path(0),
 	};
 
 	let save_path = AcctPathMapping {
@@ -127,3 +175,123 @@ where
 	batch.commit()?;
 	Ok(())
 }
+
+/// Derivation path of the account with the given label
+fn acct_parent_key_id<'a, T: ?Sized, C, K>(wallet: &mut T, label: &str) -> Result<Identifier, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	match wallet.get_acct_path(label.to_owned())? {
+		Some(p) => Ok(p.path),
+		None => Err(ErrorKind::UnknownAccountLabel(label.to_owned()).into()),
+	}
+}
+
+/// Child index the next output of the given account will be derived with
+pub fn next_child_index<'a, T: ?Sized, C, K>(wallet: &mut T, label: &str) -> Result<u32, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = acct_parent_key_id(wallet, label)?;
+	wallet.next_child_index(&parent_key_id)
+}
+
+/// Sets the child index the next output of the given account will be derived
+/// with. Refuses indices at or below one already used by an output in the
+/// wallet, as new outputs would then reuse keys.
+pub fn set_next_child_index<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	label: &str,
+	index: u32,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if is_coinbase_key_index(index) {
+		return Err(ErrorKind::InvalidChildIndex(format!(
+			"{} is in the range reserved for coinbase keys",
+			index
+		))
+		.into());
+	}
+	let parent_key_id = acct_parent_key_id(wallet, label)?;
+	let highest_used = wallet
+		.iter()
+		.filter(|o| o.root_key_id == parent_key_id && !is_coinbase_key_index(o.n_child))
+		.map(|o| o.n_child)
+		.max();
+	if let Some(h) = highest_used {
+		if index <= h {
+			return Err(ErrorKind::InvalidChildIndex(format!(
+				"{} is not above index {} already used by an output of account '{}'",
+				index, h, label
+			))
+			.into());
+		}
+	}
+	let mut batch = wallet.batch(keychain_mask)?;
+	batch.save_child_index(&parent_key_id, index)?;
+	batch.commit()?;
+	Ok(())
+}
+
+/// Scans the outputs in the wallet for the derivation indices the given account
+/// has used, reporting gaps below the child index counter and any use at or
+/// beyond it
+pub fn scan_child_indices<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	label: &str,
+) -> Result<ChildIndexScan, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = acct_parent_key_id(wallet, label)?;
+	let next_child_index = wallet.next_child_index(&parent_key_id)?;
+	let used: BTreeSet<u32> = wallet
+		.iter()
+		.filter(|o| o.root_key_id == parent_key_id && !is_coinbase_key_index(o.n_child))
+		.map(|o| o.n_child)
+		.collect();
+
+	let mut unused_ranges = vec![];
+	let mut gap_start = 0;
+	for &i in used.iter().take_while(|&&i| i < next_child_index) {
+		if i > gap_start {
+			unused_ranges.push((gap_start, i - 1));
+		}
+		gap_start = i + 1;
+	}
+	if gap_start < next_child_index {
+		unused_ranges.push((gap_start, next_child_index - 1));
+	}
+
+	Ok(ChildIndexScan {
+		label: label.to_owned(),
+		parent_key_id,
+		next_child_index,
+		highest_used_index: used.iter().next_back().cloned(),
+		unused_ranges,
+		used_beyond_next: used.range(next_child_index..).cloned().collect(),
+	})
+}
+
+/// Stable, non-sensitive fingerprint of the wallet seed: the first 8 bytes
+/// of the blake2b hash of the root public key, hex encoded
+pub fn wallet_fingerprint<K>(keychain: &K) -> Result<String, Error>
+where
+	K: Keychain,
+{
+	let root_key = keychain.derive_key(0, &K::root_key_id(), &SwitchCommitmentType::None)?;
+	let root_pub = PublicKey::from_secret_key(keychain.secp(), &root_key)?;
+	let hash = blake2b(8, &[], &root_pub.serialize_vec(keychain.secp(), true)[..]);
+	Ok(grin_util::to_hex(hash.as_bytes().to_vec()))
+}
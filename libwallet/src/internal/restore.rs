@@ -15,14 +15,14 @@
 
 use crate::grin_core::consensus::{valid_header_version, WEEK_HEIGHT};
 use crate::grin_core::core::HeaderVersion;
-use crate::grin_core::global;
 use crate::grin_core::libtx::proof;
 use crate::grin_keychain::{ExtKeychain, Identifier, Keychain, SwitchCommitmentType};
+use crate::grin_util as util;
 use crate::grin_util::secp::key::SecretKey;
 use crate::grin_util::secp::pedersen;
 use crate::internal::{keys, updater};
 use crate::types::*;
-use crate::{Error, OutputCommitMapping};
+use crate::{Error, ErrorKind, OutputCommitMapping, RestoreScan, RestoredOutput};
 use std::collections::HashMap;
 use std::time::Instant;
 
@@ -77,15 +77,24 @@ where
 	);
 
 	let keychain = wallet.keychain(keychain_mask)?;
+	let coinbase_maturity = wallet.w2n_client().coinbase_maturity();
 	let legacy_builder = proof::LegacyProofBuilder::new(&keychain);
 	let builder = proof::ProofBuilder::new(&keychain);
 	let legacy_version = HeaderVersion(1);
+	// A watch-only wallet only has the rewind hash of the wallet it follows,
+	// which doesn't rewind the legacy proofs
+	let view_builder = match wallet.watch_only_key() {
+		Some(key) => Some(key.proof_builder()?),
+		None => None,
+	};
 
 	for output in outputs.iter() {
 		let (commit, proof, is_coinbase, height, mmr_index) = output;
 		// attempt to unwind message from the RP and get a value
 		// will fail if it's not ours
-		let info = {
+		let info = if let Some(view_builder) = &view_builder {
+			proof::rewind(keychain.secp(), view_builder, *commit, None, *proof)?
+		} else {
 			// Before HF+2wk, try legacy rewind first
 			let info_legacy =
 				if valid_header_version(height.saturating_sub(2 * WEEK_HEIGHT), legacy_version) {
@@ -110,7 +119,7 @@ where
 		};
 
 		let lock_height = if *is_coinbase {
-			*height + global::coinbase_maturity()
+			*height + coinbase_maturity
 		} else {
 			*height
 		};
@@ -138,9 +147,29 @@ where
 	Ok(wallet_outputs)
 }
 
+/// Index of the last output of the UTXO set, the end of a scan started now
+fn last_output_index<'a, T: ?Sized, C, K>(wallet: &mut T, start_index: u64) -> Result<u64, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	Ok(wallet
+		.w2n_client()
+		.get_outputs_by_pmmr_index(start_index, None, 1)?
+		.0)
+}
+
+/// Collect the outputs belonging to this wallet from the UTXO set, between
+/// start_index and end_index. The outputs of blocks mined during the scan are
+/// left to the wallet's next update. Only the rangeproofs of outputs created at
+/// min_height or above are rewound.
 fn collect_chain_outputs<'a, T, C, K>(
 	wallet: &mut T,
 	keychain_mask: Option<&SecretKey>,
+	start_index: u64,
+	end_index: u64,
+	min_height: u64,
 ) -> Result<Vec<OutputResult>, Error>
 where
 	T: WalletBackend<'a, C, K>,
@@ -148,12 +177,12 @@ where
 	K: Keychain + 'a,
 {
 	let batch_size = 1000;
-	let mut start_index = 1;
 	let mut result_vec: Vec<OutputResult> = vec![];
-	loop {
-		let (highest_index, last_retrieved_index, outputs) = wallet
+	let mut start_index = start_index;
+	while start_index <= end_index {
+		let (highest_index, last_retrieved_index, mut outputs) = wallet
 			.w2n_client()
-			.get_outputs_by_pmmr_index(start_index, batch_size)?;
+			.get_outputs_by_pmmr_index(start_index, Some(end_index), batch_size)?;
 		warn!(
 			"Checking {} outputs, up to index {}. (Highest index: {})",
 			outputs.len(),
@@ -161,13 +190,14 @@ where
 			last_retrieved_index,
 		);
 
+		outputs.retain(|o| o.3 >= min_height);
 		result_vec.append(&mut identify_utxo_outputs(
 			wallet,
 			keychain_mask,
 			outputs.clone(),
 		)?);
 
-		if highest_index == last_retrieved_index {
+		if highest_index == last_retrieved_index || last_retrieved_index < start_index {
 			break;
 		}
 		start_index = last_retrieved_index + 1;
@@ -188,7 +218,11 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let commit = wallet.calc_commit_for_cache(keychain_mask, output.value, &output.key_id)?;
+	// Without the keys, a watch-only wallet keeps the commitment found on chain
+	let commit = match wallet.watch_only_key() {
+		Some(_) => Some(util::to_hex(output.commit.0.to_vec())),
+		None => wallet.calc_commit_for_cache(keychain_mask, output.value, &output.key_id)?,
+	};
 	let mut batch = wallet.batch(keychain_mask)?;
 
 	let parent_key_id = output.key_id.parent_path();
@@ -214,6 +248,7 @@ where
 		};
 		let mut t = TxLogEntry::new(parent_key_id.clone(), entry_type, log_id);
 		t.confirmed = true;
+		t.confirmed_height = Some(output.height);
 		t.amount_credited = output.value;
 		t.num_outputs = 1;
 		t.update_confirmation_ts();
@@ -248,10 +283,14 @@ where
 		lock_height: output.lock_height,
 		is_coinbase: output.is_coinbase,
 		tx_log_entry: Some(log_id),
+		reservation: None,
+		cost_basis: None,
+		change_seq: 0,
 	});
 
+	// coinbase keys derived from the height don't advance the child index
 	let max_child_index = found_parents.get(&parent_key_id).unwrap().clone();
-	if output.n_child >= max_child_index {
+	if output.n_child >= max_child_index && !keys::is_coinbase_key_index(output.n_child) {
 		found_parents.insert(parent_key_id.clone(), output.n_child);
 	}
 
@@ -316,7 +355,8 @@ where
 {
 	// First, get a definitive list of outputs we own from the chain
 	warn!("Starting wallet check.");
-	let chain_outs = collect_chain_outputs(wallet, keychain_mask)?;
+	let end_index = last_output_index(wallet, 1)?;
+	let chain_outs = collect_chain_outputs(wallet, keychain_mask, 1, end_index, 0)?;
 	warn!(
 		"Identified {} wallet_outputs as belonging to this wallet",
 		chain_outs.len(),
@@ -412,12 +452,11 @@ where
 	}
 
 	// restore labels, account paths and child derivation indices
-	let label_base = "account";
 	let mut acct_index = 1;
 	for (path, max_child_index) in found_parents.iter() {
 		// default path already exists
 		if *path != ExtKeychain::derive_key_id(2, 0, 0, 0, 0) {
-			let label = format!("{}_{}", label_base, acct_index);
+			let label = restored_acct_label(path, acct_index);
 			keys::set_acct_path(wallet, keychain_mask, &label, path)?;
 			acct_index += 1;
 		}
@@ -429,35 +468,64 @@ where
 	Ok(())
 }
 
-/// Restore a wallet
-pub fn restore<'a, T, C, K>(wallet: &mut T, keychain_mask: Option<&SecretKey>) -> Result<(), Error>
+/// Label of an account found by a restore. Outputs of accounts of both derivation
+/// schemes are found, as their rangeproofs carry the full key path.
+fn restored_acct_label(path: &Identifier, acct_index: usize) -> String {
+	match DerivationScheme::of(path) {
+		DerivationScheme::Standard => format!("account_{}", acct_index),
+		DerivationScheme::Hardened => format!("hardened_account_{}", acct_index),
+	}
+}
+
+/// Insertion index of the first output and height of the first block to rewind the
+/// outputs of, to restore a wallet from its birthday if given
+fn restore_start<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	birthday: Option<u64>,
+) -> Result<(u64, u64), Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	// Don't proceed if wallet_data has anything in it
-	let is_empty = wallet.iter().next().is_none();
-	if !is_empty {
-		error!("Not restoring. Please back up and remove existing db directory first.");
-		return Ok(());
+	match birthday {
+		Some(b) if b > 1 => {
+			// Outputs are queried by their position in the output MMR, and those
+			// created since the birthday come after the last position of the MMR
+			// as of the block before
+			let header = wallet.w2n_client().get_header_by_height(b - 1)?;
+			let start_index = header.output_mmr_size + 1;
+			warn!(
+				"Starting restore from birthday {}, at output index {}.",
+				b, start_index
+			);
+			Ok((start_index, b))
+		}
+		_ => {
+			warn!("Starting restore.");
+			Ok((1, 0))
+		}
 	}
+}
 
-	let now = Instant::now();
-	warn!("Starting restore.");
-
-	let result_vec = collect_chain_outputs(wallet, keychain_mask)?;
-
-	warn!(
-		"Identified {} wallet_outputs as belonging to this wallet",
-		result_vec.len(),
-	);
-
+/// Save the outputs found by a restore, with a transaction log entry for each coinbase
+/// output and one for all others of each account, and restore the accounts they belong
+/// to along with their child derivation indices
+fn save_restored_outputs<'a, T, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	outputs: Vec<OutputResult>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
 	let mut found_parents: HashMap<Identifier, u32> = HashMap::new();
 	let mut restore_stats = HashMap::new();
 
 	// Now save what we have
-	for output in result_vec {
+	for output in outputs {
 		restore_missing_output(
 			wallet,
 			keychain_mask,
@@ -468,12 +536,11 @@ where
 	}
 
 	// restore labels, account paths and child derivation indices
-	let label_base = "account";
 	let mut acct_index = 1;
 	for (path, max_child_index) in found_parents.iter() {
 		// default path already exists
 		if *path != ExtKeychain::derive_key_id(2, 0, 0, 0, 0) {
-			let label = format!("{}_{}", label_base, acct_index);
+			let label = restored_acct_label(path, acct_index);
 			keys::set_acct_path(wallet, keychain_mask, &label, path)?;
 			acct_index += 1;
 		}
@@ -493,6 +560,45 @@ where
 		debug!("Next child for account {} is {}", path, max_child_index + 1);
 		batch.commit()?;
 	}
+	Ok(())
+}
+
+/// Restore a wallet. Given the wallet's birthday, the height of the block before
+/// which it can't own any output, only the part of the UTXO set added from that
+/// block on is downloaded and only the rangeproofs of the outputs created since
+/// are rewound, which makes restoring a young wallet much faster. Spent outputs
+/// are skipped by the node as it walks its UTXO set, so no bitmap of the unspent
+/// outputs is needed on the wallet's side.
+pub fn restore<'a, T, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	birthday: Option<u64>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	// Don't proceed if wallet_data has anything in it
+	let is_empty = wallet.iter().next().is_none();
+	if !is_empty {
+		error!("Not restoring. Please back up and remove existing db directory first.");
+		return Ok(());
+	}
+
+	let now = Instant::now();
+	let (start_index, min_height) = restore_start(wallet, birthday)?;
+
+	let end_index = last_output_index(wallet, start_index)?;
+	let result_vec =
+		collect_chain_outputs(wallet, keychain_mask, start_index, end_index, min_height)?;
+
+	warn!(
+		"Identified {} wallet_outputs as belonging to this wallet",
+		result_vec.len(),
+	);
+
+	save_restored_outputs(wallet, keychain_mask, result_vec)?;
 
 	let mut sec = now.elapsed().as_secs();
 	let min = sec / 60;
@@ -501,3 +607,245 @@ where
 
 	Ok(())
 }
+
+/// Start a step-wise restore, from the wallet's birthday if given. The scan covers
+/// the outputs on chain up to the last one at the time, the outputs of blocks mined
+/// later being found by the wallet's next update.
+pub fn restore_scan_start<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	birthday: Option<u64>,
+) -> Result<RestoreScan, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let (start_index, min_height) = restore_start(wallet, birthday)?;
+	let end_index = last_output_index(wallet, start_index)?;
+	Ok(RestoreScan {
+		min_height,
+		start_index,
+		end_index,
+		next_index: start_index,
+		outputs: vec![],
+	})
+}
+
+/// Scan the next chunk of at most `max_outputs` outputs of a step-wise restore,
+/// adding the ones belonging to the wallet to those found. Nothing is written to
+/// the wallet.
+pub fn restore_scan<'a, T, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	scan: &mut RestoreScan,
+	max_outputs: u64,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if scan.is_complete() {
+		return Ok(());
+	}
+	let (highest_index, last_retrieved_index, mut outputs) = wallet
+		.w2n_client()
+		.get_outputs_by_pmmr_index(scan.next_index, Some(scan.end_index), max_outputs.max(1))?;
+	warn!(
+		"Checking {} outputs, from index {} up to {}.",
+		outputs.len(),
+		scan.next_index,
+		last_retrieved_index,
+	);
+
+	outputs.retain(|o| o.3 >= scan.min_height);
+	for o in identify_utxo_outputs(wallet, keychain_mask, outputs)? {
+		scan.outputs.push(RestoredOutput {
+			commit: o.commit,
+			key_id: o.key_id,
+			value: o.value,
+			height: o.height,
+			mmr_index: o.mmr_index,
+			is_coinbase: o.is_coinbase,
+		});
+	}
+
+	// nothing left past the last output retrieved
+	scan.next_index =
+		if last_retrieved_index < scan.next_index || highest_index == last_retrieved_index {
+			scan.end_index + 1
+		} else {
+			last_retrieved_index + 1
+		};
+	Ok(())
+}
+
+/// Save the outputs found by a complete step-wise restore to the wallet, which must
+/// still be empty. Each output is checked to be the commitment of its value under
+/// its key, as the scan comes back from the caller.
+pub fn restore_commit<'a, T, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	scan: &RestoreScan,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	if !scan.is_complete() {
+		return Err(ErrorKind::RestoreScan(format!(
+			"{} outputs left to scan",
+			scan.remaining()
+		)))?;
+	}
+	if wallet.iter().next().is_some() {
+		return Err(ErrorKind::RestoreScan(
+			"wallet isn't empty, back up and remove the existing db directory first".into(),
+		))?;
+	}
+
+	keys::check_not_watch_only(wallet, "checking the outputs scanned")?;
+	let keychain = wallet.keychain(keychain_mask)?;
+	let mut outputs = vec![];
+	for o in scan.outputs.iter() {
+		if keychain.commit(o.value, &o.key_id, &SwitchCommitmentType::Regular)? != o.commit {
+			return Err(ErrorKind::RestoreScan(format!(
+				"{:?} isn't the commitment of {} under {}",
+				o.commit, o.value, o.key_id
+			)))?;
+		}
+		let lock_height = if o.is_coinbase {
+			o.height + wallet.w2n_client().coinbase_maturity()
+		} else {
+			o.height
+		};
+		outputs.push(OutputResult {
+			commit: o.commit,
+			key_id: o.key_id.clone(),
+			n_child: o.key_id.to_path().last_path_index(),
+			mmr_index: o.mmr_index,
+			value: o.value,
+			height: o.height,
+			lock_height,
+			is_coinbase: o.is_coinbase,
+		});
+	}
+
+	warn!("Saving {} outputs found by the restore scan", outputs.len());
+	save_restored_outputs(wallet, keychain_mask, outputs)
+}
+
+/// Import an output of the given value under the given key that the wallet
+/// doesn't know of, e.g. after losing part of its database or when the output was
+/// created by another tool. It's only accepted if it's derived from an account of
+/// the wallet, its commitment is the one of the value under the key, it's in the
+/// UTXO set and, if `height` is given, it was created at that height. The account's
+/// child index is moved past the key so that it's not derived again.
+pub fn import_output<'a, T, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	commit: &pedersen::Commitment,
+	value: u64,
+	key_id: &Identifier,
+	height: Option<u64>,
+) -> Result<OutputData, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let parent_key_id = key_id.parent_path();
+	if !wallet.acct_path_iter().any(|a| a.path == parent_key_id) {
+		return Err(ErrorKind::ImportOutput(format!(
+			"{} isn't derived from an account of the wallet",
+			key_id
+		)))?;
+	}
+	keys::check_not_watch_only(wallet, "importing an output")?;
+	let keychain = wallet.keychain(keychain_mask)?;
+	if keychain.commit(value, key_id, &SwitchCommitmentType::Regular)? != *commit {
+		return Err(ErrorKind::ImportOutput(format!(
+			"{:?} isn't the commitment of {} under {}",
+			commit, value, key_id
+		)))?;
+	}
+	let commit_hex = util::to_hex(commit.0.to_vec());
+	if wallet
+		.iter()
+		.any(|o| o.commit.as_ref() == Some(&commit_hex))
+	{
+		return Err(ErrorKind::ImportOutput(format!(
+			"{:?} is already in the wallet",
+			commit
+		)))?;
+	}
+
+	let node_outputs = wallet
+		.w2n_client()
+		.get_outputs_from_node(vec![commit.clone()])?;
+	if !node_outputs.is_complete() {
+		return Err(ErrorKind::ImportOutput(format!(
+			"Unable to query the node for {:?}: {}",
+			commit,
+			node_outputs.errors.join(", ")
+		)))?;
+	}
+	let (_, output_height, mmr_index) = match node_outputs.outputs.get(commit) {
+		Some(o) => o.clone(),
+		None => {
+			return Err(ErrorKind::ImportOutput(format!(
+				"{:?} isn't in the UTXO set",
+				commit
+			)))?;
+		}
+	};
+	if let Some(h) = height {
+		if h != output_height {
+			return Err(ErrorKind::ImportOutput(format!(
+				"{:?} was created at height {}, not {}",
+				commit, output_height, h
+			)))?;
+		}
+	}
+
+	let n_child = key_id.to_path().last_path_index();
+	let is_coinbase = keys::is_coinbase_key_index(n_child);
+	let lock_height = if is_coinbase {
+		output_height + wallet.w2n_client().coinbase_maturity()
+	} else {
+		output_height
+	};
+	warn!(
+		"Importing output for {} with ID {} ({:?}) at height {}",
+		value, key_id, commit, output_height
+	);
+	let next_child = wallet.next_child_index(&parent_key_id)?;
+	restore_missing_output(
+		wallet,
+		keychain_mask,
+		OutputResult {
+			commit: commit.clone(),
+			key_id: key_id.clone(),
+			n_child,
+			mmr_index,
+			value,
+			height: output_height,
+			lock_height,
+			is_coinbase,
+		},
+		&mut HashMap::new(),
+		&mut None,
+	)?;
+
+	if !is_coinbase && n_child >= next_child {
+		let mut batch = wallet.batch(keychain_mask)?;
+		batch.save_child_index(&parent_key_id, n_child + 1)?;
+		batch.commit()?;
+	}
+	let output = wallet
+		.iter()
+		.find(|o| o.key_id == *key_id && o.mmr_index == Some(mmr_index))
+		.ok_or(ErrorKind::ImportOutput(format!("{:?} not saved", commit)))?;
+	Ok(output)
+}
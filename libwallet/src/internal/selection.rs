@@ -14,19 +14,27 @@
 
 //! Selection of inputs for building transactions
 
+use crate::cost_basis;
 use crate::error::{Error, ErrorKind};
-use crate::grin_core::core::amount_to_hr_string;
+use crate::grin_core::core::{amount_to_hr_string, Output, OutputFeatures, Transaction};
 use crate::grin_core::libtx::{
 	build,
-	proof::{ProofBuild, ProofBuilder},
+	proof::{self, ProofBuild, ProofBuilder},
 	tx_fee,
 };
-use crate::grin_keychain::{Identifier, Keychain};
+use crate::grin_keychain::{BlindSum, BlindingFactor, Identifier, Keychain, SwitchCommitmentType};
 use crate::grin_util::secp::key::SecretKey;
-use crate::internal::keys;
+use crate::internal::{keys, updater};
 use crate::slate::Slate;
 use crate::types::*;
+use chrono::{DateTime, Duration, Utc};
+use rayon::prelude::*;
 use std::collections::HashMap;
+use std::mem;
+use std::time::Instant;
+
+/// Confirmations of the input a recipient contributes to a payjoin
+const PAYJOIN_MINIMUM_CONFIRMATIONS: u64 = 10;
 
 /// Initialize a transaction on the sender side, returns a corresponding
 /// libwallet transaction slate with the appropriate inputs selected,
@@ -42,7 +50,9 @@ pub fn build_send_tx<'a, T: ?Sized, C, K>(
 	max_outputs: usize,
 	change_outputs: usize,
 	selection_strategy_is_use_all: bool,
+	fee_from_amount: bool,
 	parent_key_id: Identifier,
+	reservation: Option<&str>,
 	use_test_nonce: bool,
 ) -> Result<Context, Error>
 where
@@ -50,7 +60,8 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let (elems, inputs, change_amounts_derivations, fee) = select_send_tx(
+	let start = Instant::now();
+	let (elems, inputs, change_amounts_derivations, amount, fee) = select_send_tx(
 		wallet,
 		keychain_mask,
 		slate.amount,
@@ -60,10 +71,23 @@ where
 		max_outputs,
 		change_outputs,
 		selection_strategy_is_use_all,
+		fee_from_amount,
 		&parent_key_id,
+		reservation,
 	)?;
+	let selected = Instant::now();
 	let blinding = slate.add_transaction_elements(keychain, &ProofBuilder::new(keychain), elems)?;
-
+	let change: Vec<(u64, Identifier)> = change_amounts_derivations
+		.iter()
+		.map(|(amount, id, _)| (*amount, id.clone()))
+		.collect();
+	let blinding = add_outputs(slate, keychain, blinding, &change)?;
+	let metrics = wallet.send_metrics_mut();
+	metrics.selection_us += selected.duration_since(start).as_micros() as u64;
+	metrics.build_us += selected.elapsed().as_micros() as u64;
+
+	// The recipient's amount, less the fee if it's deducted from it
+	slate.amount = amount;
 	slate.fee = fee;
 
 	// Create our own private context
@@ -76,6 +100,7 @@ where
 	);
 
 	context.fee = fee;
+	context.fee_from_amount = fee_from_amount;
 
 	// Store our private identifiers for each input
 	for input in inputs {
@@ -96,6 +121,48 @@ where
 	Ok(context)
 }
 
+/// Builds outputs of the given amounts and keys and adds them to the slate's
+/// transaction, returning the blinding factor accounting for them on top of
+/// `blinding`. Their rangeproofs, the slow part of building a transaction with
+/// many outputs, are generated on a thread pool.
+fn add_outputs<K>(
+	slate: &mut Slate,
+	keychain: &K,
+	blinding: BlindingFactor,
+	outputs: &[(u64, Identifier)],
+) -> Result<BlindingFactor, Error>
+where
+	K: Keychain,
+{
+	if outputs.is_empty() {
+		return Ok(blinding);
+	}
+	let builder = ProofBuilder::new(keychain);
+	let switch = SwitchCommitmentType::Regular;
+	let built = outputs
+		.par_iter()
+		.map(|(amount, key_id)| -> Result<Output, Error> {
+			let commit = keychain.commit(*amount, key_id, &switch)?;
+			let proof = proof::create(keychain, &builder, *amount, key_id, &switch, commit, None)?;
+			Ok(Output {
+				features: OutputFeatures::Plain,
+				commit,
+				proof,
+			})
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+
+	let mut sum = BlindSum::new().add_blinding_factor(blinding);
+	let mut tx = mem::replace(&mut slate.tx, Transaction::empty());
+	for ((amount, key_id), output) in outputs.iter().zip(built) {
+		debug!("Building output: {}, {:?}", amount, output.commit);
+		sum = sum.add_key_id(key_id.to_value_path(*amount));
+		tx = tx.with_output(output);
+	}
+	slate.tx = tx;
+	Ok(keychain.blind_sum(&sum)?)
+}
+
 /// Locks all corresponding outputs in the context, creates
 /// change outputs and tx log entry
 pub fn lock_tx_context<'a, T: ?Sized, C, K>(
@@ -121,6 +188,18 @@ where
 		);
 	}
 
+	let attachments = {
+		let keychain = wallet.keychain(keychain_mask)?;
+		slate.attachments(
+			keychain.secp(),
+			Some(&context.sec_key),
+			context.participant_id,
+		)
+	};
+
+	// Fetched before locking the wallet, as the price of the coin the tx is sent at
+	let price = wallet.price_source().and_then(|s| s.current_price());
+
 	let tx_entry = {
 		let lock_inputs = context.get_inputs().clone();
 		let messages = Some(slate.participant_messages());
@@ -134,17 +213,28 @@ where
 		let filename = format!("{}.grintx", slate_id);
 		t.stored_tx = Some(filename);
 		t.fee = Some(slate.fee);
+		t.approval = context.approval.clone();
+		t.fee_from_amount = context.fee_from_amount;
+		t.price = price;
 		let mut amount_debited = 0;
+		let mut input_lots = vec![];
 		t.num_inputs = lock_inputs.len();
 		for id in lock_inputs {
 			let mut coin = batch.get(&id.0, &id.1).unwrap();
 			coin.tx_log_entry = Some(log_id);
 			amount_debited = amount_debited + coin.value;
+			input_lots.push((coin.value, coin.cost_basis.clone()));
 			batch.lock_output(&mut coin)?;
 		}
 
 		t.amount_debited = amount_debited;
 		t.messages = messages;
+		t.attachments = attachments;
+
+		// Change carries over the cost basis of the inputs
+		let lots: Vec<(u64, Option<&cost_basis::FiatPrice>)> =
+			input_lots.iter().map(|(v, b)| (*v, b.as_ref())).collect();
+		let change_cost_basis = cost_basis::blend(&lots);
 
 		// write the output representing our change
 		for (id, _, _) in &context.get_outputs() {
@@ -163,6 +253,9 @@ where
 				lock_height: 0,
 				is_coinbase: false,
 				tx_log_entry: Some(log_id),
+				reservation: None,
+				cost_basis: change_cost_basis.clone(),
+				change_seq: 0,
 			})?;
 		}
 		batch.save_tx_log_entry(t.clone(), &parent_key_id)?;
@@ -181,6 +274,7 @@ pub fn build_recipient_output<'a, T: ?Sized, C, K>(
 	keychain_mask: Option<&SecretKey>,
 	slate: &mut Slate,
 	parent_key_id: Identifier,
+	contribute_input: bool,
 	use_test_rng: bool,
 ) -> Result<(Identifier, Context), Error>
 where
@@ -188,19 +282,35 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
+	// An input we contribute is spent into our output, which then holds its
+	// value on top of the amount received
+	let input = match contribute_input {
+		true => payjoin_input(wallet, slate.height, &parent_key_id),
+		false => None,
+	};
+	let input_value = input.as_ref().map(|i| i.value).unwrap_or(0);
+
 	// Create a potential output for this transaction
 	let key_id = keys::next_available_key(wallet, keychain_mask).unwrap();
 	let keychain = wallet.keychain(keychain_mask)?;
 	let key_id_inner = key_id.clone();
-	let amount = slate.amount;
+	let amount = slate.amount + input_value;
 	let height = slate.height;
 
 	let slate_id = slate.id.clone();
-	let blinding = slate.add_transaction_elements(
-		&keychain,
-		&ProofBuilder::new(&keychain),
-		vec![build::output(amount, key_id.clone())],
-	)?;
+	let mut elems = vec![build::output(amount, key_id.clone())];
+	if let Some(i) = input.as_ref() {
+		debug!(
+			"Contributing input {} of {} to the payjoin",
+			i.key_id, i.value
+		);
+		match i.is_coinbase {
+			true => elems.push(build::coinbase_input(i.value, i.key_id.clone())),
+			false => elems.push(build::input(i.value, i.key_id.clone())),
+		}
+	}
+	let blinding =
+		slate.add_transaction_elements(&keychain, &ProofBuilder::new(&keychain), elems)?;
 
 	// Add blinding sum to our context
 	let mut context = Context::new(
@@ -216,6 +326,15 @@ where
 	context.add_output(&key_id, &None, amount);
 	let messages = Some(slate.participant_messages());
 	let commit = wallet.calc_commit_for_cache(keychain_mask, amount, &key_id_inner)?;
+	// The amount received is acquired at the current price, a payjoin input keeps its own
+	let price = wallet.price_source().and_then(|s| s.current_price());
+	let output_cost_basis = match input.as_ref() {
+		Some(i) => cost_basis::blend(&[
+			(slate.amount, price.as_ref()),
+			(i.value, i.cost_basis.as_ref()),
+		]),
+		None => price.clone(),
+	};
 	let mut batch = wallet.batch(keychain_mask)?;
 	let log_id = batch.next_tx_log_id(&parent_key_id)?;
 	let mut t = TxLogEntry::new(parent_key_id.clone(), TxLogEntryType::TxReceived, log_id);
@@ -223,6 +342,14 @@ where
 	t.amount_credited = amount;
 	t.num_outputs = 1;
 	t.messages = messages;
+	t.price = price;
+	if let Some(mut i) = input {
+		context.add_input(&i.key_id, &i.mmr_index, i.value);
+		t.amount_debited = i.value;
+		t.num_inputs = 1;
+		i.tx_log_entry = Some(log_id);
+		batch.lock_output(&mut i)?;
+	}
 	batch.save(OutputData {
 		root_key_id: parent_key_id.clone(),
 		key_id: key_id_inner.clone(),
@@ -235,16 +362,75 @@ where
 		lock_height: 0,
 		is_coinbase: false,
 		tx_log_entry: Some(log_id),
+		reservation: None,
+		cost_basis: output_cost_basis,
+		change_seq: 0,
 	})?;
+	updater::record_event(&mut *batch, WalletEventType::Received, &t)?;
 	batch.save_tx_log_entry(t, &parent_key_id)?;
 	batch.commit()?;
 
 	Ok((key_id, context))
 }
 
+/// The input a recipient contributes when the sender asks for a payjoin: its
+/// smallest output spendable by sends, if any
+fn payjoin_input<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	current_height: u64,
+	parent_key_id: &Identifier,
+) -> Option<OutputData>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let now = Utc::now();
+	wallet
+		.iter()
+		.filter(|out| {
+			out.root_key_id == *parent_key_id
+				&& out.eligible_to_spend(current_height, PAYJOIN_MINIMUM_CONFIRMATIONS)
+				&& !out.is_reserved(None, now)
+		})
+		.min_by_key(|out| out.value)
+}
+
+/// Whether the wallet can contribute an input to one more payjoin, having
+/// contributed fewer in the last hour than it's configured to at most. The
+/// payjoins cancelled count, so that asking for payjoins that are never posted
+/// can't be used to learn the wallet's outputs one after the other.
+pub fn payjoin_contribution_allowed<'a, T: ?Sized, C, K>(wallet: &mut T) -> bool
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let max = wallet.config().payjoin_max_contributions_per_hour() as usize;
+	let since = Utc::now() - Duration::hours(1);
+	let contributed = wallet
+		.tx_log_iter()
+		.filter(|t| {
+			(t.tx_type == TxLogEntryType::TxReceived
+				|| t.tx_type == TxLogEntryType::TxReceivedCancelled)
+				&& t.num_inputs > 0
+				&& t.creation_ts > since
+		})
+		.count();
+	if contributed >= max {
+		warn!(
+			"{} inputs contributed to payjoins in the last hour, receiving without one",
+			contributed
+		);
+		return false;
+	}
+	true
+}
+
 /// Builds a transaction to send to someone from the HD seed associated with the
 /// wallet and the amount to send. Handles reading through the wallet data file,
-/// selecting outputs to spend and building the change.
+/// selecting outputs to spend and building the change. If `fee_from_amount`, the
+/// fee is deducted from the amount, which is returned as the recipient receives it.
 pub fn select_send_tx<'a, T: ?Sized, C, K, B>(
 	wallet: &mut T,
 	keychain_mask: Option<&SecretKey>,
@@ -255,12 +441,15 @@ pub fn select_send_tx<'a, T: ?Sized, C, K, B>(
 	max_outputs: usize,
 	change_outputs: usize,
 	selection_strategy_is_use_all: bool,
+	fee_from_amount: bool,
 	parent_key_id: &Identifier,
+	reservation: Option<&str>,
 ) -> Result<
 	(
 		Vec<Box<build::Append<K, B>>>,
 		Vec<OutputData>,
 		Vec<(u64, Identifier, Option<u64>)>, // change amounts and derivations
+		u64,                                 // amount
 		u64,                                 // fee
 	),
 	Error,
@@ -279,7 +468,9 @@ where
 		max_outputs,
 		change_outputs,
 		selection_strategy_is_use_all,
+		fee_from_amount,
 		&parent_key_id,
+		reservation,
 	)?;
 
 	// build transaction skeleton with inputs and change
@@ -291,10 +482,12 @@ where
 		parts.push(build::with_lock_height(lock_height));
 	}
 
-	Ok((parts, coins, change_amounts_derivations, fee))
+	Ok((parts, coins, change_amounts_derivations, amount, fee))
 }
 
-/// Select outputs and calculating fee.
+/// Select outputs and calculating fee. If `fee_from_amount`, outputs are only
+/// selected for the amount, the fee being deducted from it rather than added on
+/// top, and the amount returned is what's left for the recipient.
 pub fn select_coins_and_fee<'a, T: ?Sized, C, K>(
 	wallet: &mut T,
 	amount: u64,
@@ -303,7 +496,9 @@ pub fn select_coins_and_fee<'a, T: ?Sized, C, K>(
 	max_outputs: usize,
 	change_outputs: usize,
 	selection_strategy_is_use_all: bool,
+	fee_from_amount: bool,
 	parent_key_id: &Identifier,
+	reservation: Option<&str>,
 ) -> Result<
 	(
 		Vec<OutputData>,
@@ -327,6 +522,7 @@ where
 		max_outputs,
 		selection_strategy_is_use_all,
 		parent_key_id,
+		reservation,
 	);
 
 	// sender is responsible for setting the fee on the partial tx
@@ -337,9 +533,13 @@ where
 	// TODO - Does this not potentially reveal the senders private key?
 	//
 	// First attempt to spend without change
+	let with_fee = |fee: u64| match fee_from_amount {
+		true => amount,
+		false => amount + fee,
+	};
 	let mut fee = tx_fee(coins.len(), 1, 1, None);
 	let mut total: u64 = coins.iter().map(|c| c.value).sum();
-	let mut amount_with_fee = amount + fee;
+	let mut amount_with_fee = with_fee(fee);
 
 	if total == 0 {
 		return Err(ErrorKind::NotEnoughFunds {
@@ -365,7 +565,7 @@ where
 	// We need to add a change address or amount with fee is more than total
 	if total != amount_with_fee {
 		fee = tx_fee(coins.len(), num_outputs, 1, None);
-		amount_with_fee = amount + fee;
+		amount_with_fee = with_fee(fee);
 
 		// Here check if we have enough outputs for the amount including fee otherwise
 		// look for other outputs and check again
@@ -389,17 +589,176 @@ where
 				max_outputs,
 				selection_strategy_is_use_all,
 				parent_key_id,
+				reservation,
 			)
 			.1;
 			fee = tx_fee(coins.len(), num_outputs, 1, None);
 			total = coins.iter().map(|c| c.value).sum();
-			amount_with_fee = amount + fee;
+			amount_with_fee = with_fee(fee);
+		}
+	}
+	if !fee_from_amount {
+		return Ok((coins, total, amount, fee));
+	}
+	if fee >= amount {
+		return Err(ErrorKind::Fee(format!(
+			"fee of {} can't be deducted from the amount of {}",
+			amount_to_hr_string(fee, false),
+			amount_to_hr_string(amount, false)
+		)))?;
+	}
+	Ok((coins, total, amount - fee, fee))
+}
+
+/// Holds enough spendable outputs to send `amount` for the reservation `label` until
+/// `expires`. Outputs it already held are kept if still needed, released otherwise.
+pub fn reserve_outputs<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	label: &str,
+	amount: u64,
+	current_height: u64,
+	minimum_confirmations: u64,
+	expires: DateTime<Utc>,
+	parent_key_id: &Identifier,
+) -> Result<Vec<OutputData>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	// Same soft limit on the number of inputs as the default for sends
+	let (coins, _total, _amount, _fee) = select_coins_and_fee(
+		wallet,
+		amount,
+		current_height,
+		minimum_confirmations,
+		500,
+		1,
+		false,
+		false,
+		parent_key_id,
+		Some(label),
+	)?;
+
+	let released: Vec<OutputData> = wallet
+		.iter()
+		.filter(|out| {
+			out.root_key_id == *parent_key_id
+				&& out.reservation.as_ref().map(|r| r.label.as_str()) == Some(label)
+				&& !coins
+					.iter()
+					.any(|c| c.key_id == out.key_id && c.mmr_index == out.mmr_index)
+		})
+		.collect();
+
+	let reservation = OutputReservation {
+		label: label.to_owned(),
+		expires,
+	};
+	let mut batch = wallet.batch(keychain_mask)?;
+	for mut out in released {
+		out.reservation = None;
+		batch.save(out)?;
+	}
+	let mut reserved = vec![];
+	for mut out in coins {
+		out.reservation = Some(reservation.clone());
+		batch.save(out.clone())?;
+		reserved.push(out);
+	}
+	batch.commit()?;
+	Ok(reserved)
+}
+
+/// Holds the outputs with the given commitments for the reservation `label` until
+/// `expires`. Either all of them are held or none: each must be unspent or unconfirmed,
+/// and not held for another reservation.
+pub fn lock_outputs<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	commits: &[String],
+	label: &str,
+	expires: DateTime<Utc>,
+	parent_key_id: &Identifier,
+) -> Result<Vec<OutputData>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let now = Utc::now();
+	let mut outputs: Vec<OutputData> = vec![];
+	for commit in commits {
+		if outputs.iter().any(|o| o.commit.as_ref() == Some(commit)) {
+			continue;
+		}
+		let out = wallet
+			.iter()
+			.find(|out| out.root_key_id == *parent_key_id && out.commit.as_ref() == Some(commit))
+			.ok_or_else(|| {
+				ErrorKind::GenericError(format!("No output {} in the account", commit))
+			})?;
+		if ![OutputStatus::Unspent, OutputStatus::Unconfirmed].contains(&out.status) {
+			return Err(ErrorKind::GenericError(format!(
+				"Output {} can't be held, its status is {}",
+				commit, out.status
+			)))?;
+		}
+		if out.is_reserved(Some(label), now) {
+			return Err(ErrorKind::GenericError(format!(
+				"Output {} is held for another reservation",
+				commit
+			)))?;
 		}
+		outputs.push(out);
+	}
+
+	let reservation = OutputReservation {
+		label: label.to_owned(),
+		expires,
+	};
+	let mut batch = wallet.batch(keychain_mask)?;
+	for out in outputs.iter_mut() {
+		out.reservation = Some(reservation.clone());
+		batch.save(out.clone())?;
 	}
-	Ok((coins, total, amount, fee))
+	batch.commit()?;
+	Ok(outputs)
 }
 
-/// Selects inputs and change for a transaction
+/// Releases the outputs held for the reservation `label`, returning them
+pub fn release_reservation<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	label: &str,
+	parent_key_id: &Identifier,
+) -> Result<Vec<OutputData>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let held: Vec<OutputData> = wallet
+		.iter()
+		.filter(|out| {
+			out.root_key_id == *parent_key_id
+				&& out.reservation.as_ref().map(|r| r.label.as_str()) == Some(label)
+		})
+		.collect();
+	let mut batch = wallet.batch(keychain_mask)?;
+	let mut released = vec![];
+	for mut out in held {
+		out.reservation = None;
+		batch.save(out.clone())?;
+		released.push(out);
+	}
+	batch.commit()?;
+	Ok(released)
+}
+
+/// Selects inputs and change for a transaction. The change outputs are only
+/// derived, they're built along with the rest of the transaction.
 pub fn inputs_and_change<'a, T: ?Sized, C, K, B>(
 	coins: &Vec<OutputData>,
 	wallet: &mut T,
@@ -462,10 +821,9 @@ where
 				part_change
 			};
 
-			let change_key = wallet.next_child(keychain_mask).unwrap();
+			let change_key = wallet.next_change_child(keychain_mask)?;
 
-			change_amounts_derivations.push((change_amount, change_key.clone(), None));
-			parts.push(build::output(change_amount, change_key));
+			change_amounts_derivations.push((change_amount, change_key, None));
 		}
 	}
 
@@ -487,6 +845,7 @@ pub fn select_coins<'a, T: ?Sized, C, K>(
 	max_outputs: usize,
 	select_all: bool,
 	parent_key_id: &Identifier,
+	reservation: Option<&str>,
 ) -> (usize, Vec<OutputData>)
 //    max_outputs_available, Outputs
 where
@@ -494,12 +853,15 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	// first find all eligible outputs based on number of confirmations
+	// first find all eligible outputs based on number of confirmations,
+	// leaving the ones held for other reservations
+	let now = Utc::now();
 	let mut eligible = wallet
 		.iter()
 		.filter(|out| {
 			out.root_key_id == *parent_key_id
 				&& out.eligible_to_spend(current_height, minimum_confirmations)
+				&& !out.is_reserved(reservation, now)
 		})
 		.collect::<Vec<OutputData>>();
 
@@ -572,3 +934,48 @@ fn select_from(amount: u64, select_all: bool, outputs: Vec<OutputData>) -> Optio
 		None
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::add_outputs;
+	use crate::grin_keychain::{
+		BlindSum, BlindingFactor, ExtKeychain, ExtKeychainPath, Keychain, SwitchCommitmentType,
+	};
+	use crate::slate::Slate;
+
+	#[test]
+	// the outputs built in parallel are those that would be built one by one
+	fn outputs_are_added_with_their_blinding() {
+		let keychain = ExtKeychain::from_random_seed(false).unwrap();
+		let outputs: Vec<_> = (1..6)
+			.map(|i| {
+				let key_id = ExtKeychainPath::new(1, i, 0, 0, 0).to_identifier();
+				(i as u64 * 1_000, key_id)
+			})
+			.collect();
+		let mut slate = Slate::blank(2);
+
+		let blinding = add_outputs(&mut slate, &keychain, BlindingFactor::zero(), &[]).unwrap();
+		assert!(blinding == BlindingFactor::zero());
+		assert!(slate.tx.outputs().is_empty());
+
+		let blinding =
+			add_outputs(&mut slate, &keychain, BlindingFactor::zero(), &outputs).unwrap();
+		assert_eq!(slate.tx.outputs().len(), outputs.len());
+		let mut sum = BlindSum::new();
+		for (amount, key_id) in outputs.iter() {
+			let commit = keychain
+				.commit(*amount, key_id, &SwitchCommitmentType::Regular)
+				.unwrap();
+			let output = slate
+				.tx
+				.outputs()
+				.iter()
+				.find(|o| o.commitment() == commit)
+				.unwrap();
+			assert!(output.verify_proof().is_ok());
+			sum = sum.add_key_id(key_id.to_value_path(*amount));
+		}
+		assert!(blinding == keychain.blind_sum(&sum).unwrap());
+	}
+}
@@ -14,16 +14,20 @@
 
 //! Transaction building functions
 
+use std::collections::HashSet;
+use std::time::Instant;
+
+use chrono::prelude::*;
 use uuid::Uuid;
 
 use crate::grin_core::consensus::valid_header_version;
-use crate::grin_core::core::HeaderVersion;
+use crate::grin_core::core::{HeaderVersion, Transaction};
 use crate::grin_keychain::{Identifier, Keychain};
 use crate::grin_util::secp::key::SecretKey;
 use crate::grin_util::Mutex;
-use crate::internal::{selection, updater};
+use crate::internal::{keys, selection, updater};
 use crate::slate::Slate;
-use crate::types::{Context, NodeClient, TxLogEntryType, WalletBackend};
+use crate::types::{ApprovalToken, Context, NodeClient, TxLogEntryType, WalletBackend};
 use crate::{Error, ErrorKind};
 
 // static for incrementing test UUIDs
@@ -77,7 +81,9 @@ pub fn estimate_send_tx<'a, T: ?Sized, C, K>(
 	max_outputs: usize,
 	num_change_outputs: usize,
 	selection_strategy_is_use_all: bool,
+	fee_from_amount: bool,
 	parent_key_id: &Identifier,
+	reservation: Option<&str>,
 ) -> Result<
 	(
 		u64, // total
@@ -93,7 +99,7 @@ where
 	// Get lock height
 	let current_height = wallet.w2n_client().get_chain_height()?;
 	// ensure outputs we're selecting are up to date
-	updater::refresh_outputs(wallet, keychain_mask, parent_key_id, false)?;
+	updater::refresh_outputs(wallet, keychain_mask, parent_key_id, false)?.check_complete()?;
 
 	// Sender selects outputs into a new slate and save our corresponding keys in
 	// a transaction context. The secret key in our transaction context will be
@@ -110,7 +116,9 @@ where
 		max_outputs,
 		num_change_outputs,
 		selection_strategy_is_use_all,
+		fee_from_amount,
 		parent_key_id,
+		reservation,
 	)?;
 	Ok((total, fee))
 }
@@ -124,7 +132,9 @@ pub fn add_inputs_to_slate<'a, T: ?Sized, C, K>(
 	max_outputs: usize,
 	num_change_outputs: usize,
 	selection_strategy_is_use_all: bool,
+	fee_from_amount: bool,
 	parent_key_id: &Identifier,
+	reservation: Option<&str>,
 	participant_id: usize,
 	message: Option<String>,
 	is_initator: bool,
@@ -135,8 +145,12 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
+	keys::check_not_watch_only(wallet, "sending")?;
+
+	let start = Instant::now();
 	// sender should always refresh outputs
-	updater::refresh_outputs(wallet, keychain_mask, parent_key_id, false)?;
+	updater::refresh_outputs(wallet, keychain_mask, parent_key_id, false)?.check_complete()?;
+	let refresh = start.elapsed();
 
 	// Sender selects outputs into a new slate and save our corresponding keys in
 	// a transaction context. The secret key in our transaction context will be
@@ -154,7 +168,9 @@ where
 		max_outputs,
 		num_change_outputs,
 		selection_strategy_is_use_all,
+		fee_from_amount,
 		parent_key_id.clone(),
+		reservation,
 		use_test_rng,
 	)?;
 
@@ -180,10 +196,15 @@ where
 		)?;
 	}
 
+	let metrics = wallet.send_metrics_mut();
+	metrics.sends += 1;
+	metrics.refresh_us += refresh.as_micros() as u64;
+	metrics.total_us += start.elapsed().as_micros() as u64;
 	Ok(context)
 }
 
-/// Add receiver output to the slate
+/// Add receiver output to the slate, along with one of our inputs if
+/// `contribute_input` is set and one is spendable
 pub fn add_output_to_slate<'a, T: ?Sized, C, K>(
 	wallet: &mut T,
 	keychain_mask: Option<&SecretKey>,
@@ -192,6 +213,7 @@ pub fn add_output_to_slate<'a, T: ?Sized, C, K>(
 	participant_id: usize,
 	message: Option<String>,
 	is_initiator: bool,
+	contribute_input: bool,
 	use_test_rng: bool,
 ) -> Result<Context, Error>
 where
@@ -199,12 +221,15 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
+	keys::check_not_watch_only(wallet, "receiving")?;
+
 	// create an output using the amount in the slate
 	let (_, mut context) = selection::build_recipient_output(
 		wallet,
 		keychain_mask,
 		slate,
 		parent_key_id.clone(),
+		contribute_input,
 		use_test_rng,
 	)?;
 
@@ -299,6 +324,224 @@ where
 	Ok(())
 }
 
+/// Mark a received tx its sender never posted as abandoned. Its outputs are
+/// kept, in case the sender posts it after all.
+pub fn abandon_tx<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	parent_key_id: &Identifier,
+	tx_id: Option<u32>,
+	tx_slate_id: Option<Uuid>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mut tx_id_string = String::new();
+	if let Some(tx_id) = tx_id {
+		tx_id_string = tx_id.to_string();
+	} else if let Some(tx_slate_id) = tx_slate_id {
+		tx_id_string = tx_slate_id.to_string();
+	}
+	let tx_vec = updater::retrieve_txs(wallet, tx_id, tx_slate_id, Some(&parent_key_id), false)?;
+	if tx_vec.len() != 1 {
+		return Err(ErrorKind::TransactionDoesntExist(tx_id_string))?;
+	}
+	let mut tx = tx_vec[0].clone();
+	if tx.tx_type != TxLogEntryType::TxReceived || tx.confirmed || tx.in_mempool {
+		return Err(ErrorKind::TransactionNotAbandonable(tx_id_string))?;
+	}
+	if tx.abandoned {
+		return Ok(());
+	}
+	tx.abandoned = true;
+	let mut batch = wallet.batch(keychain_mask)?;
+	batch.save_tx_log_entry(tx, parent_key_id)?;
+	batch.commit()?;
+	Ok(())
+}
+
+/// Id of the approval token given by an operator, which must be one of the
+/// wallet's
+pub fn approval_token_id<'a, T: ?Sized, C, K>(wallet: &mut T, token: &str) -> Result<String, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	match wallet.get_approval_token(&ApprovalToken::hash(token))? {
+		Some(t) => Ok(t.id.to_string()),
+		None => Err(ErrorKind::TransactionApprovalError(
+			"Unknown or revoked approval token".to_owned(),
+		))?,
+	}
+}
+
+/// Approve a tx that was created requiring dual-control approval. The
+/// approving token must differ from the one that created the tx.
+pub fn approve_tx<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	parent_key_id: &Identifier,
+	approval_token: &str,
+	tx_id: Option<u32>,
+	tx_slate_id: Option<Uuid>,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let approver = approval_token_id(wallet, approval_token)?;
+	let mut tx_id_string = String::new();
+	if let Some(tx_id) = tx_id {
+		tx_id_string = tx_id.to_string();
+	} else if let Some(tx_slate_id) = tx_slate_id {
+		tx_id_string = tx_slate_id.to_string();
+	}
+	// the log entry only exists once outputs are locked, before that the
+	// approval lives in the private context alone
+	let tx_vec = updater::retrieve_txs(wallet, tx_id, tx_slate_id, Some(&parent_key_id), false)?;
+	let mut tx_entry = match tx_vec.len() {
+		0 => None,
+		1 => Some(tx_vec[0].clone()),
+		_ => return Err(ErrorKind::TransactionDoesntExist(tx_id_string))?,
+	};
+	let slate_id = match tx_entry
+		.as_ref()
+		.and_then(|t| t.tx_slate_id)
+		.or(tx_slate_id)
+	{
+		Some(s) => s,
+		None => return Err(ErrorKind::TransactionDoesntExist(tx_id_string))?,
+	};
+	let mut context = wallet
+		.get_private_context(keychain_mask, slate_id.as_bytes(), 0)
+		.ok();
+
+	let approval = context
+		.as_ref()
+		.and_then(|c| c.approval.clone())
+		.or(tx_entry.as_ref().and_then(|t| t.approval.clone()));
+	let mut approval = match approval {
+		Some(a) => a,
+		None => {
+			return Err(ErrorKind::TransactionApprovalError(format!(
+				"Transaction {} doesn't require approval",
+				tx_id_string
+			)))?;
+		}
+	};
+	if approval.is_approved() {
+		return Ok(());
+	}
+	if approval.created_by == approver {
+		return Err(ErrorKind::TransactionApprovalError(
+			"Transaction must be approved by a different token than the one that created it"
+				.to_owned(),
+		))?;
+	}
+	approval.approved_by = Some(approver);
+	approval.approved_at = Some(Utc::now());
+
+	let mut batch = wallet.batch(keychain_mask)?;
+	if let Some(c) = context.as_mut() {
+		c.approval = Some(approval.clone());
+		batch.save_private_context(slate_id.as_bytes(), 0, c)?;
+	}
+	if let Some(mut t) = tx_entry.take() {
+		t.approval = Some(approval);
+		batch.save_tx_log_entry(t, parent_key_id)?;
+	}
+	batch.commit()?;
+	Ok(())
+}
+
+/// Refuse to post a tx spending inputs locked by a tx that is still awaiting
+/// approval, or by a send that wasn't approved when the wallet requires sends
+/// to be
+pub fn check_tx_approved<'a, T: ?Sized, C, K>(wallet: &mut T, tx: &Transaction) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let required = wallet.config().require_tx_approval.unwrap_or(false);
+	let inputs: HashSet<_> = tx.inputs().iter().map(|i| i.commitment()).collect();
+	let pending: Vec<_> = wallet
+		.tx_log_iter()
+		.filter(|t| match &t.approval {
+			Some(a) => !a.is_approved(),
+			None => required && t.tx_type == TxLogEntryType::TxSent && !t.confirmed,
+		})
+		.collect();
+	for t in pending {
+		if let Some(stored) = wallet.get_stored_tx(&t)? {
+			if stored
+				.inputs()
+				.iter()
+				.any(|i| inputs.contains(&i.commitment()))
+			{
+				let id = match t.tx_slate_id {
+					Some(s) => s.to_string(),
+					None => t.id.to_string(),
+				};
+				return Err(ErrorKind::TransactionApprovalRequired(id))?;
+			}
+		}
+	}
+	Ok(())
+}
+
+/// Hold back the posting of a send whose kernel can't be mined at the next block,
+/// as the node would refuse it. Its log entry records the lock height, and the tx
+/// is posted on refresh once the chain reaches it. Returns whether the tx was held
+pub fn defer_locked_tx<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	tx: &Transaction,
+	height: u64,
+) -> Result<bool, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let lock_height = tx.lock_height();
+	if lock_height <= height + 1 {
+		return Ok(false);
+	}
+	let excess = match tx.kernels().first() {
+		Some(k) => k.excess,
+		None => return Ok(false),
+	};
+	let sent: Vec<_> = wallet
+		.tx_log_iter()
+		.filter(|t| t.tx_type == TxLogEntryType::TxSent && !t.confirmed)
+		.collect();
+	for mut t in sent {
+		let stored = match wallet.get_stored_tx(&t)? {
+			Some(s) => s,
+			None => continue,
+		};
+		if stored.kernels().first().map(|k| k.excess) != Some(excess) {
+			continue;
+		}
+		info!(
+			"Tx {} can't be mined before height {}, posting it then",
+			t.id, lock_height
+		);
+		t.post_at_height = Some(lock_height);
+		let parent_key_id = t.parent_key_id.clone();
+		let mut batch = wallet.batch(keychain_mask)?;
+		batch.save_tx_log_entry(t, &parent_key_id)?;
+		batch.commit()?;
+		return Ok(true);
+	}
+	Ok(false)
+}
+
 /// Update the stored transaction (this update needs to happen when the TX is finalised)
 pub fn update_stored_tx<'a, T: ?Sized, C, K>(
 	wallet: &mut T,
@@ -357,6 +600,42 @@ where
 	Ok(())
 }
 
+/// Update the stored transaction's attachments, decrypting them with our
+/// context's secret key where we have one
+pub fn update_attachments<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate: &Slate,
+	context: &Context,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let tx_vec = updater::retrieve_txs(wallet, None, Some(slate.id), None, false)?;
+	if tx_vec.is_empty() {
+		return Err(ErrorKind::TransactionDoesntExist(slate.id.to_string()))?;
+	}
+	let attachments = {
+		let keychain = wallet.keychain(keychain_mask)?;
+		slate.attachments(
+			keychain.secp(),
+			Some(&context.sec_key),
+			context.participant_id,
+		)
+	};
+	let mut batch = wallet.batch(keychain_mask)?;
+	for mut tx in tx_vec.into_iter() {
+		tx.attachments = attachments.clone();
+		tx.participant_id = Some(context.participant_id as u64);
+		let parent_key = tx.parent_key_id.clone();
+		batch.save_tx_log_entry(tx, &parent_key)?;
+	}
+	batch.commit()?;
+	Ok(())
+}
+
 #[cfg(test)]
 mod test {
 	use crate::grin_core::libtx::{build, ProofBuilder};
@@ -15,24 +15,36 @@
 //! Utilities to check the status of all the outputs we have stored in
 //! the wallet storage and update them.
 
-use std::collections::HashMap;
+use chrono::{Duration, Utc};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 use crate::error::Error;
 use crate::grin_core::consensus::reward;
-use crate::grin_core::core::{Output, TxKernel};
-use crate::grin_core::global;
-use crate::grin_core::libtx::proof::ProofBuilder;
+use crate::grin_core::core::hash::Hash;
+use crate::grin_core::core::{Output, OutputFeatures, OutputIdentifier, TxKernel};
+use crate::grin_core::libtx::proof::{self, ProofBuild, ProofBuilder};
 use crate::grin_core::libtx::reward;
+use crate::grin_core::ser;
 use crate::grin_keychain::{Identifier, Keychain, SwitchCommitmentType};
 use crate::grin_util as util;
+use crate::grin_util::secp::constants::MAX_PROOF_SIZE;
 use crate::grin_util::secp::key::SecretKey;
-use crate::grin_util::secp::pedersen;
+use crate::grin_util::secp::{pedersen, Secp256k1};
 use crate::internal::keys;
+use crate::slate::Slate;
 use crate::types::{
-	NodeClient, OutputData, OutputStatus, TxLogEntry, TxLogEntryType, WalletBackend, WalletInfo,
+	ArchivedSlate, BalanceHold, CoinbaseInfo, CoinbaseReport, CoinbaseStatus, NodeBlockOutputs,
+	NodeClient, NodeOutputs, OnetimeAddress, OutputData, OutputStatus, RefreshReport,
+	SlateDirection, TxLogEntry, TxLogEntryType, TxWrapper, WalletBackend, WalletEvent,
+	WalletEventType, WalletInfo, WalletOutputBatch,
 };
-use crate::{BlockFees, CbData, OutputCommitMapping};
+use crate::{BlockFees, CbData, OutputCommitMapping, OutputDerivationAudit, OutputIntegrityIssue};
+
+/// Number of events kept in the wallet event queue, the oldest are dropped
+/// beyond that
+const MAX_WALLET_EVENTS: u64 = 10_000;
 
 /// Retrieve all of the outputs (doesn't attempt to update from node)
 pub fn retrieve_outputs<'a, T: ?Sized, C, K>(
@@ -72,21 +84,240 @@ where
 	outputs.sort_by_key(|out| out.n_child);
 	let keychain = wallet.keychain(keychain_mask)?;
 
-	let res = outputs
-		.into_iter()
-		.map(|output| {
-			let commit = match output.commit.clone() {
-				Some(c) => pedersen::Commitment::from_vec(util::from_hex(c).unwrap()),
-				None => keychain
-					.commit(output.value, &output.key_id, &SwitchCommitmentType::Regular)
-					.unwrap(), // TODO: proper support for different switch commitment schemes
-			};
-			OutputCommitMapping { output, commit }
-		})
+	let mut res = vec![];
+	for output in outputs {
+		let commit = output_commit(&keychain, &output)?;
+		res.push(OutputCommitMapping { output, commit });
+	}
+	Ok(res)
+}
+
+/// Commitment of an output, the stored one if any. Otherwise it's derived from
+/// the key and value with a regular switch commitment, the only scheme the
+/// wallet builds outputs with.
+fn output_commit<K>(keychain: &K, output: &OutputData) -> Result<pedersen::Commitment, Error>
+where
+	K: Keychain,
+{
+	match output.commit.as_ref() {
+		Some(c) => Ok(pedersen::Commitment::from_vec(
+			util::from_hex(c.clone()).unwrap(),
+		)),
+		None => {
+			Ok(keychain.commit(output.value, &output.key_id, &SwitchCommitmentType::Regular)?)
+		}
+	}
+}
+
+/// Switch commitment scheme the commitment of an output was built with. The
+/// scheme isn't stored with the output, so it's the one its commitment is
+/// derived with from its key and value, none if no scheme derives it.
+pub fn output_switch_type<K>(
+	keychain: &K,
+	output: &OutputData,
+) -> Result<Option<SwitchCommitmentType>, Error>
+where
+	K: Keychain,
+{
+	let commit = output_commit(keychain, output)?;
+	for switch in &[SwitchCommitmentType::Regular, SwitchCommitmentType::None] {
+		if keychain.commit(output.value, &output.key_id, switch)? == commit {
+			return Ok(Some(switch.clone()));
+		}
+	}
+	Ok(None)
+}
+
+/// Check the outputs a tx created were derived from the wallet seed, so a
+/// restore would find them again, and that no other output shares their key.
+/// The range proof of an output on the chain is rewound, as a restore does.
+pub fn audit_tx_outputs<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	tx: &TxLogEntry,
+) -> Result<Vec<OutputDerivationAudit>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let keychain = wallet.keychain(keychain_mask)?;
+	let client = wallet.w2n_client().clone();
+	let builder = ProofBuilder::new(&keychain);
+	let legacy_builder = proof::LegacyProofBuilder::new(&keychain);
+	let all_outputs: Vec<OutputData> = wallet.iter().collect();
+	let is_change =
+		tx.tx_type == TxLogEntryType::TxSent || tx.tx_type == TxLogEntryType::TxSentCancelled;
+
+	let mut outputs: Vec<&OutputData> = all_outputs
+		.iter()
+		.filter(|o| o.tx_log_entry == Some(tx.id) && o.root_key_id == tx.parent_key_id)
 		.collect();
+	outputs.sort_by_key(|o| o.n_child);
+	let mut commits = vec![];
+	for output in outputs.iter() {
+		commits.push(output_commit(&keychain, output)?);
+	}
+	let node_outputs = client.get_outputs_from_node(commits.clone())?;
+
+	let mut res = vec![];
+	for (output, commit) in outputs.into_iter().zip(commits) {
+		let mut issues = vec![];
+		let derivation_index = output.key_id.to_path().last_path_index();
+		if output.key_id.parent_path() != output.root_key_id {
+			issues.push(format!(
+				"Key {} isn't derived from account path {}",
+				output.key_id.to_bip_32_string(),
+				output.root_key_id.to_bip_32_string()
+			));
+		}
+		if output.n_child != derivation_index {
+			issues.push(format!(
+				"Recorded index {} doesn't match key derivation index {}",
+				output.n_child, derivation_index
+			));
+		}
+		if output_switch_type(&keychain, output)?.is_none() {
+			issues.push(
+				"Commitment doesn't match the one derived from the seed (out-of-band key)"
+					.to_owned(),
+			);
+		}
+
+		// An output on the chain is found by a restore if its range proof
+		// rewinds to its key and value with the wallet seed
+		let mut rangeproof_checked = false;
+		if let Some((_, _, mmr_index)) = node_outputs.outputs.get(&commit) {
+			let mmr_index = *mmr_index;
+			let (_, _, found) = client.get_outputs_by_pmmr_index(mmr_index, Some(mmr_index), 1)?;
+			if let Some((commit, proof, _, _, _)) = found.into_iter().find(|f| f.0 == commit) {
+				let secp = keychain.secp();
+				let info = match proof::rewind(secp, &builder, commit, None, proof)? {
+					Some(i) => Some(i),
+					None => proof::rewind(secp, &legacy_builder, commit, None, proof)?,
+				};
+				match info {
+					Some((value, key_id, _)) => {
+						if value != output.value || key_id != output.key_id {
+							issues.push(format!(
+								"Range proof rewinds to key {} and value {} instead",
+								key_id.to_bip_32_string(),
+								value
+							));
+						}
+					}
+					None => issues.push(
+						"Range proof doesn't rewind with the wallet seed, a restore wouldn't find it"
+							.to_owned(),
+					),
+				}
+				rangeproof_checked = true;
+			}
+		}
+		let recoverable = issues.is_empty();
+
+		let index_reused = all_outputs.iter().any(|o| {
+			o.key_id == output.key_id
+				&& (o.commit != output.commit || o.tx_log_entry != output.tx_log_entry)
+		});
+		if index_reused {
+			issues.push(format!(
+				"Derivation index {} is also used by another output",
+				derivation_index
+			));
+		}
+
+		res.push(OutputDerivationAudit {
+			output: output.clone(),
+			is_change,
+			derivation_index,
+			recoverable,
+			rangeproof_checked,
+			index_reused,
+			issues,
+		});
+	}
 	Ok(res)
 }
 
+/// Check every stored output against the wallet seed: its commitment has to
+/// be the one derived from its key and value and, for outputs that can still
+/// be spent, a valid rangeproof has to be built for it. Outputs are checked on
+/// a thread pool, as building rangeproofs is slow.
+pub fn verify_outputs<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+) -> Result<Vec<OutputIntegrityIssue>, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let keychain = wallet.keychain(keychain_mask)?;
+	let outputs: Vec<OutputData> = wallet.iter().collect();
+	let builder = ProofBuilder::new(&keychain);
+	let issues = outputs
+		.par_iter()
+		.filter_map(|output| {
+			verify_output(&keychain, &builder, output)
+				.err()
+				.map(|issue| OutputIntegrityIssue {
+					output: output.clone(),
+					issue,
+				})
+		})
+		.collect();
+	Ok(issues)
+}
+
+/// Check a stored output is consistent with the key it was derived from
+fn verify_output<K, B>(keychain: &K, builder: &B, output: &OutputData) -> Result<(), String>
+where
+	K: Keychain,
+	B: ProofBuild,
+{
+	if output.key_id.parent_path() != output.root_key_id {
+		return Err(format!(
+			"key {} isn't derived from account path {}",
+			output.key_id.to_bip_32_string(),
+			output.root_key_id.to_bip_32_string()
+		));
+	}
+	if output.n_child != output.key_id.to_path().last_path_index() {
+		return Err(format!(
+			"recorded index {} doesn't match its key",
+			output.n_child
+		));
+	}
+	let switch = match output_switch_type(keychain, output)
+		.map_err(|e| format!("unable to derive commitment: {}", e))?
+	{
+		Some(s) => s,
+		None => return Err("commitment doesn't match its key and value".to_owned()),
+	};
+	let commit = keychain
+		.commit(output.value, &output.key_id, &switch)
+		.map_err(|e| format!("unable to derive commitment: {}", e))?;
+	match output.status {
+		OutputStatus::Unconfirmed | OutputStatus::Unspent => {
+			let rangeproof = proof::create(
+				keychain,
+				builder,
+				output.value,
+				&output.key_id,
+				&switch,
+				commit,
+				None,
+			)
+			.map_err(|e| format!("unable to build rangeproof: {}", e))?;
+			proof::verify(keychain.secp(), commit, rangeproof, None)
+				.map_err(|e| format!("invalid rangeproof: {}", e))?;
+		}
+		_ => {}
+	}
+	Ok(())
+}
+
 /// Retrieve all of the transaction entries, or a particular entry
 /// if `parent_key_id` is set, only return entries from that key
 pub fn retrieve_txs<'a, T: ?Sized, C, K>(
@@ -132,21 +363,21 @@ where
 }
 
 /// Refreshes the outputs in a wallet with the latest information
-/// from a node
+/// from a node. Outputs that couldn't be queried are left as they were
+/// and counted in the returned report
 pub fn refresh_outputs<'a, T: ?Sized, C, K>(
 	wallet: &mut T,
 	keychain_mask: Option<&SecretKey>,
 	parent_key_id: &Identifier,
 	update_all: bool,
-) -> Result<(), Error>
+) -> Result<RefreshReport, Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
 	let height = wallet.w2n_client().get_chain_height()?;
-	refresh_output_state(wallet, keychain_mask, height, parent_key_id, update_all)?;
-	Ok(())
+	refresh_output_state(wallet, keychain_mask, height, parent_key_id, update_all)
 }
 
 /// build a local map of wallet outputs keyed by commit
@@ -165,6 +396,8 @@ where
 	let mut wallet_outputs: HashMap<pedersen::Commitment, (Identifier, Option<u64>)> =
 		HashMap::new();
 	let keychain = wallet.keychain(keychain_mask)?;
+	let last_confirmed_height = wallet.last_confirmed_height()?;
+	let finality_depth = wallet.config().finality_depth();
 	let unspents: Vec<OutputData> = wallet
 		.iter()
 		.filter(|x| x.root_key_id == *parent_key_id && x.status != OutputStatus::Spent)
@@ -172,31 +405,42 @@ where
 
 	let tx_entries = retrieve_txs(wallet, None, None, Some(&parent_key_id), true)?;
 
-	// Only select outputs that are actually involved in an outstanding transaction
+	// Only select outputs that are actually involved in an outstanding transaction,
+	// or immature coinbases, which disappear if their block is orphaned, and the
+	// orphaned ones, which are back if their block is, until past the reorg horizon.
+	// Received outputs not yet final are checked too, as a reorg can revert them
 	let unspents: Vec<OutputData> = match update_all {
 		false => unspents
 			.into_iter()
-			.filter(|x| match x.tx_log_entry.as_ref() {
-				Some(t) => {
-					if let Some(_) = tx_entries.iter().find(|&te| te.id == *t) {
-						true
-					} else {
-						false
+			.filter(|x| {
+				if x.is_coinbase
+					&& (x.lock_height > last_confirmed_height || x.status == OutputStatus::Reverted)
+				{
+					return true;
+				}
+				if !x.is_coinbase
+					&& x.status == OutputStatus::Unspent
+					&& !x.is_final(last_confirmed_height, finality_depth)
+				{
+					return true;
+				}
+				match x.tx_log_entry.as_ref() {
+					Some(t) => {
+						if let Some(_) = tx_entries.iter().find(|&te| te.id == *t) {
+							true
+						} else {
+							false
+						}
 					}
+					None => true,
 				}
-				None => true,
 			})
 			.collect(),
 		true => unspents,
 	};
 
 	for out in unspents {
-		let commit = match out.commit.clone() {
-			Some(c) => pedersen::Commitment::from_vec(util::from_hex(c).unwrap()),
-			None => keychain
-				.commit(out.value, &out.key_id, &SwitchCommitmentType::Regular)
-				.unwrap(), // TODO: proper support for different switch commitment schemes
-		};
+		let commit = output_commit(&keychain, &out)?;
 		wallet_outputs.insert(commit, (out.key_id.clone(), out.mmr_index));
 	}
 	Ok(wallet_outputs)
@@ -215,7 +459,15 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
+	// a send prepared and not dispatched yet is dropped along with its tx
+	let pending_dispatch = match tx.tx_slate_id {
+		Some(id) => wallet.get_pending_dispatch(&id)?.map(|_| id),
+		None => None,
+	};
 	let mut batch = wallet.batch(keychain_mask)?;
+	if let Some(id) = pending_dispatch {
+		batch.delete_pending_dispatch(&id)?;
+	}
 
 	for mut o in outputs {
 		// unlock locked outputs
@@ -234,11 +486,91 @@ where
 	if tx.tx_type == TxLogEntryType::TxReceived {
 		tx.tx_type = TxLogEntryType::TxReceivedCancelled;
 	}
+	record_event(&mut *batch, WalletEventType::Cancelled, &tx)?;
 	batch.save_tx_log_entry(tx, parent_key_id)?;
 	batch.commit()?;
 	Ok(())
 }
 
+/// Queue an event about a transaction, as part of the batch changing it so
+/// that the event is recorded if and only if the change is
+pub fn record_event<K>(
+	batch: &mut dyn WalletOutputBatch<K>,
+	event_type: WalletEventType,
+	tx: &TxLogEntry,
+) -> Result<(), Error>
+where
+	K: Keychain,
+{
+	let seq = batch.next_event_seq()?;
+	batch.save_wallet_event(&WalletEvent {
+		seq,
+		event_type,
+		tx: tx.clone(),
+		created: Utc::now(),
+	})?;
+	if seq > MAX_WALLET_EVENTS {
+		batch.delete_wallet_event(seq - MAX_WALLET_EVENTS)?;
+	}
+	Ok(())
+}
+
+/// Keep a copy of the slate in the slate archive, if slates are archived, and
+/// drop the archived slates older than the retention period. The archive is
+/// only there to look into failed exchanges, so failing to archive a slate
+/// doesn't fail the step it went through.
+pub fn archive_slate<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	slate: &Slate,
+	direction: SlateDirection,
+	step: &str,
+) where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let retention_days = match wallet.config().slate_archive_retention_days {
+		Some(d) => d,
+		None => return,
+	};
+	let entry = ArchivedSlate {
+		slate_id: slate.id,
+		direction,
+		step: step.to_owned(),
+		slate: slate.clone(),
+		created: Utc::now(),
+	};
+	if let Err(e) = save_archived_slate(wallet, keychain_mask, &entry, retention_days) {
+		warn!("Unable to archive slate {}: {}", slate.id, e);
+	}
+}
+
+fn save_archived_slate<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	entry: &ArchivedSlate,
+	retention_days: u64,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let expiry = entry.created - Duration::days(retention_days as i64);
+	let expired: Vec<ArchivedSlate> = wallet
+		.archived_slate_iter()
+		.filter(|s| s.created < expiry)
+		.collect();
+	let mut batch = wallet.batch(keychain_mask)?;
+	for s in expired.iter() {
+		batch.delete_archived_slate(s)?;
+	}
+	batch.save_archived_slate(entry)?;
+	batch.commit()?;
+	Ok(())
+}
+
 /// Apply refreshed API output data to the wallet
 pub fn apply_api_outputs<'a, T: ?Sized, C, K>(
 	wallet: &mut T,
@@ -268,11 +600,32 @@ where
 			warn!("Please wait for sync on node to complete or fork to resolve and try again.");
 			return Ok(());
 		}
+		let finality_depth = wallet.config().finality_depth();
 		let mut batch = wallet.batch(keychain_mask)?;
 		for (commit, (id, mmr_index)) in wallet_outputs.iter() {
 			if let Ok(mut output) = batch.get(id, mmr_index) {
+				let unchanged = output.clone();
 				match api_outputs.get(&commit) {
 					Some(o) => {
+						// an orphaned coinbase is back with its block
+						if output.is_coinbase && output.status == OutputStatus::Reverted {
+							let tx = batch.tx_log_iter().find(|t| {
+								Some(t.id) == output.tx_log_entry
+									&& t.parent_key_id == *parent_key_id
+							});
+							if let Some(mut t) = tx {
+								info!(
+									"Orphaned coinbase output at height {} is back on chain",
+									o.1
+								);
+								t.tx_type = TxLogEntryType::ConfirmedCoinbase;
+								t.confirmed = true;
+								t.confirmed_height = Some(o.1);
+								t.confirmed_block_hash = None;
+								t.update_confirmation_ts();
+								batch.save_tx_log_entry(t, &parent_key_id)?;
+							}
+						}
 						// if this is a coinbase tx being confirmed, it's recordable in tx log
 						if output.is_coinbase && output.status == OutputStatus::Unconfirmed {
 							let log_id = batch.next_tx_log_id(parent_key_id)?;
@@ -282,33 +635,108 @@ where
 								log_id,
 							);
 							t.confirmed = true;
+							t.confirmed_height = Some(o.1);
 							t.amount_credited = output.value;
 							t.amount_debited = 0;
 							t.num_outputs = 1;
 							t.update_confirmation_ts();
 							output.tx_log_entry = Some(log_id);
+							record_event(&mut *batch, WalletEventType::Confirmed, &t)?;
 							batch.save_tx_log_entry(t, &parent_key_id)?;
 						}
 						// also mark the transaction in which this output is involved as confirmed
 						// note that one involved input/output confirmation SHOULD be enough
 						// to reliably confirm the tx
-						if !output.is_coinbase && output.status == OutputStatus::Unconfirmed {
+						// a reverted tx confirmed again is back to being a received one
+						if !output.is_coinbase
+							&& (output.status == OutputStatus::Unconfirmed
+								|| output.status == OutputStatus::Reverted)
+						{
 							let tx = batch.tx_log_iter().find(|t| {
 								Some(t.id) == output.tx_log_entry
 									&& t.parent_key_id == *parent_key_id
 							});
 							if let Some(mut t) = tx {
+								if t.tx_type == TxLogEntryType::TxReverted {
+									t.tx_type = TxLogEntryType::TxReceived;
+								}
+								let newly_confirmed = !t.confirmed;
 								t.update_confirmation_ts();
 								t.confirmed = true;
+								t.confirmed_height = Some(o.1);
+								t.in_mempool = false;
+								t.abandoned = false;
+								if newly_confirmed {
+									record_event(&mut *batch, WalletEventType::Confirmed, &t)?;
+								}
 								batch.save_tx_log_entry(t, &parent_key_id)?;
 							}
 						}
 						output.height = o.1;
 						output.mark_unspent();
 					}
-					None => output.mark_spent(),
+					None => {
+						// an immature coinbase can't have been spent, so its block was orphaned
+						if output.is_coinbase
+							&& output.status == OutputStatus::Unspent
+							&& height < output.lock_height
+						{
+							warn!(
+								"Coinbase output at height {} was orphaned, marking it unusable",
+								output.height
+							);
+							let tx = batch.tx_log_iter().find(|t| {
+								Some(t.id) == output.tx_log_entry
+									&& t.parent_key_id == *parent_key_id
+							});
+							if let Some(mut t) = tx {
+								t.tx_type = TxLogEntryType::OrphanedCoinbase;
+								t.confirmed = false;
+								t.confirmed_height = None;
+								t.confirmed_block_hash = None;
+								batch.save_tx_log_entry(t, &parent_key_id)?;
+							}
+							// kept reverted rather than spent, in case its block is
+							// reorged back in
+							output.mark_reverted();
+						}
+						// an orphaned coinbase whose block didn't come back before
+						// the reorg horizon is never going to
+						if output.is_coinbase
+							&& output.status == OutputStatus::Reverted
+							&& output.is_final(height, finality_depth)
+						{
+							output.status = OutputStatus::Spent;
+						}
+						// an output this wallet received and didn't spend itself was
+						// reorged out along with the tx it came in
+						if !output.is_coinbase && output.status == OutputStatus::Unspent {
+							let tx = batch.tx_log_iter().find(|t| {
+								Some(t.id) == output.tx_log_entry
+									&& t.parent_key_id == *parent_key_id
+									&& t.tx_type == TxLogEntryType::TxReceived
+									&& t.confirmed
+							});
+							if let Some(mut t) = tx {
+								warn!(
+									"Output of tx {} at height {} was reverted by a reorg",
+									t.id, output.height
+								);
+								t.tx_type = TxLogEntryType::TxReverted;
+								t.confirmed = false;
+								t.confirmed_height = None;
+								t.confirmed_block_hash = None;
+								batch.save_tx_log_entry(t, &parent_key_id)?;
+								output.mark_reverted();
+							}
+						}
+						output.mark_spent();
+					}
 				};
-				batch.save(output)?;
+				// Outputs are only saved when changed, to keep the change counter meaningful
+				if output != unchanged {
+					batch.save(output)?;
+				}
 			}
 		}
 		{
@@ -319,6 +747,142 @@ where
 	Ok(())
 }
 
+/// Check the outputs the node reports against the blocks the same node serves.
+/// Outputs the wallet would newly take as confirmed, or that moved to another block,
+/// must be in the block the node places them in, with a valid rangeproof and, if the
+/// node provides one, a valid Merkle proof of inclusion against the output root of
+/// the block's header. Outputs failing the checks are reported as failed queries, so
+/// the wallet leaves them as they are.
+///
+/// This catches a node reporting outputs its own chain doesn't back, not a node
+/// forging its answers consistently: the headers come from that node too, and
+/// outputs it keeps reporting unspent aren't checked again. Only a header source
+/// independent of the node would.
+fn verify_node_outputs<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	wallet_outputs: &HashMap<pedersen::Commitment, (Identifier, Option<u64>)>,
+	node_outputs: &mut NodeOutputs,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let keychain = wallet.keychain(keychain_mask)?;
+
+	// outputs to verify by the height the node reports them at, with whether
+	// they're coinbase outputs
+	let mut to_verify: HashMap<u64, Vec<(pedersen::Commitment, bool)>> = HashMap::new();
+	for (commit, (id, mmr_index)) in wallet_outputs.iter() {
+		let height = match node_outputs.outputs.get(commit) {
+			Some(o) => o.1,
+			None => continue,
+		};
+		let output = match wallet.get(id, mmr_index) {
+			Ok(o) => o,
+			Err(_) => continue,
+		};
+		if output.status != OutputStatus::Unconfirmed && output.height == height {
+			continue;
+		}
+		to_verify
+			.entry(height)
+			.or_insert_with(Vec::new)
+			.push((commit.clone(), output.is_coinbase));
+	}
+
+	let mut rejected = vec![];
+	for (height, commits) in to_verify {
+		let block = match wallet.w2n_client().get_block_outputs(height) {
+			Ok(b) => b,
+			Err(e) => {
+				for (commit, _) in commits {
+					rejected.push((commit, format!("{}", e)));
+				}
+				continue;
+			}
+		};
+		for (commit, is_coinbase) in commits {
+			let mmr_index = node_outputs.outputs[&commit].2;
+			let res = verify_node_output(
+				keychain.secp(),
+				&commit,
+				is_coinbase,
+				mmr_index,
+				block.as_ref(),
+			);
+			if let Err(e) = res {
+				rejected.push((commit, e));
+			}
+		}
+	}
+
+	for (commit, e) in rejected {
+		let commit_hex = util::to_hex(commit.as_ref().to_vec());
+		warn!(
+			"Output {} reported by the node failed verification: {}",
+			commit_hex, e
+		);
+		node_outputs.outputs.remove(&commit);
+		node_outputs.failed.push(commit);
+		node_outputs
+			.errors
+			.push(format!("Output {} not verified: {}", commit_hex, e));
+	}
+	Ok(())
+}
+
+/// Check an output is in the block the node reports it in, with valid proofs
+fn verify_node_output(
+	secp: &Secp256k1,
+	commit: &pedersen::Commitment,
+	is_coinbase: bool,
+	mmr_index: u64,
+	block: Option<&NodeBlockOutputs>,
+) -> Result<(), String> {
+	let block = block.ok_or_else(|| "no block at the reported height".to_owned())?;
+	let output = block
+		.outputs
+		.iter()
+		.find(|o| o.commit == *commit)
+		.ok_or_else(|| format!("not in block {}", block.height))?;
+	if output.is_coinbase != is_coinbase || output.mmr_index != mmr_index {
+		return Err(format!(
+			"doesn't match the output in block {}",
+			block.height
+		));
+	}
+
+	let bytes = match output.proof.as_ref().map(|p| util::from_hex(p.clone())) {
+		Some(Ok(b)) => b,
+		Some(Err(_)) => return Err("invalid rangeproof".to_owned()),
+		None => return Err("no rangeproof".to_owned()),
+	};
+	if bytes.len() > MAX_PROOF_SIZE {
+		return Err("invalid rangeproof".to_owned());
+	}
+	let mut rangeproof = pedersen::RangeProof::zero();
+	rangeproof.proof[..bytes.len()].copy_from_slice(&bytes);
+	rangeproof.plen = bytes.len();
+	proof::verify(secp, commit.clone(), rangeproof, None)
+		.map_err(|e| format!("invalid rangeproof: {}", e))?;
+
+	if let Some(merkle_proof) = output.merkle_proof.as_ref() {
+		let root = Hash::from_hex(&block.output_root)
+			.map_err(|e| format!("invalid output root: {}", e))?;
+		let features = if is_coinbase {
+			OutputFeatures::Coinbase
+		} else {
+			OutputFeatures::Plain
+		};
+		merkle_proof
+			.verify(root, &OutputIdentifier::new(features, commit), mmr_index)
+			.map_err(|e| format!("invalid Merkle proof: {:?}", e))?;
+	}
+	Ok(())
+}
+
 /// Builds a single api query to retrieve the latest output data from the node.
 /// So we can refresh the local wallet outputs.
 fn refresh_output_state<'a, T: ?Sized, C, K>(
@@ -327,7 +891,7 @@ fn refresh_output_state<'a, T: ?Sized, C, K>(
 	height: u64,
 	parent_key_id: &Identifier,
 	update_all: bool,
-) -> Result<(), Error>
+) -> Result<RefreshReport, Error>
 where
 	T: WalletBackend<'a, C, K>,
 	C: NodeClient + 'a,
@@ -337,22 +901,510 @@ where
 
 	// build a local map of wallet outputs keyed by commit
 	// and a list of outputs we want to query the node for
-	let wallet_outputs = map_wallet_outputs(wallet, keychain_mask, parent_key_id, update_all)?;
+	let mut wallet_outputs = map_wallet_outputs(wallet, keychain_mask, parent_key_id, update_all)?;
 
 	let wallet_output_keys = wallet_outputs.keys().map(|commit| commit.clone()).collect();
 
-	let api_outputs = wallet
+	let mut node_outputs = wallet
 		.w2n_client()
 		.get_outputs_from_node(wallet_output_keys)?;
+	verify_node_outputs(wallet, keychain_mask, &wallet_outputs, &mut node_outputs)?;
+
+	// outputs we couldn't query are left as they are, rather than being
+	// taken as missing from the chain (and so marked spent)
+	let total = wallet_outputs.len();
+	for commit in node_outputs.failed.iter() {
+		wallet_outputs.remove(commit);
+	}
+
 	apply_api_outputs(
 		wallet,
 		keychain_mask,
 		&wallet_outputs,
-		&api_outputs,
+		&node_outputs.outputs,
 		height,
 		parent_key_id,
 	)?;
 	clean_old_unconfirmed(wallet, keychain_mask, height)?;
+	update_confirming_blocks(wallet, keychain_mask, parent_key_id)?;
+	update_tx_conflicts(wallet, keychain_mask, parent_key_id)?;
+	post_deferred_txs(wallet, keychain_mask, height, parent_key_id)?;
+	update_mempool_status(wallet, keychain_mask, parent_key_id)?;
+	update_abandoned_receives(wallet, keychain_mask, parent_key_id)?;
+	release_payjoin_inputs(wallet, keychain_mask, parent_key_id)?;
+	update_onetime_addresses(wallet, keychain_mask, parent_key_id)?;
+
+	if !node_outputs.is_complete() {
+		warn!(
+			"Refreshed {} of {} outputs, the rest couldn't be queried from the node",
+			total - node_outputs.failed.len(),
+			total
+		);
+	}
+	Ok(RefreshReport {
+		node_synced: true,
+		outputs_total: total,
+		outputs_failed: node_outputs.failed.len(),
+		errors: node_outputs.errors,
+	})
+}
+
+/// Fill in the confirming block of confirmed txs that don't have it yet, from
+/// the height recorded when they were confirmed, or by looking up the kernel
+/// of the stored transaction. Kernel lookups are bounded by the heights the tx
+/// can be at, and a kernel not found is next looked up from where this lookup
+/// left off, less the finality depth. Failures to reach the node are logged
+/// and the lookup is retried on the next refresh
+fn update_confirming_blocks<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	parent_key_id: &Identifier,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let txs: Vec<TxLogEntry> = wallet
+		.tx_log_iter()
+		.filter(|t| {
+			t.parent_key_id == *parent_key_id && t.confirmed && t.confirmed_block_hash.is_none()
+		})
+		.collect();
+	if txs.is_empty() {
+		return Ok(());
+	}
+	let tip = wallet.last_confirmed_height()?;
+	let finality_depth = wallet.config().finality_depth();
+	// a tx can't be in a block below the heights its own outputs were created at
+	let mut output_heights: HashMap<u32, u64> = HashMap::new();
+	for o in wallet.iter().filter(|o| o.root_key_id == *parent_key_id) {
+		if let Some(id) = o.tx_log_entry {
+			let h = output_heights.entry(id).or_insert(o.height);
+			*h = (*h).min(o.height);
+		}
+	}
+	let mut updated = vec![];
+	for mut t in txs {
+		if let Some(h) = t.confirmed_height {
+			match wallet.w2n_client().get_header_by_height(h) {
+				Ok(header) => {
+					t.set_confirming_block(&header);
+					updated.push(t);
+				}
+				Err(e) => {
+					warn!("Unable to get confirming block for tx {}: {}", t.id, e);
+					break;
+				}
+			}
+			continue;
+		}
+		let excess = match wallet.get_stored_tx(&t)? {
+			Some(tx) => match tx.kernels().first() {
+				Some(k) => k.excess,
+				None => continue,
+			},
+			None => continue,
+		};
+		let min_height = match (output_heights.get(&t.id), t.kernel_lookup_min_height) {
+			(Some(o), Some(l)) => Some((*o).max(l)),
+			(o, l) => o.cloned().or(l),
+		};
+		if min_height.map(|h| h > tip).unwrap_or(false) {
+			continue;
+		}
+		match wallet
+			.w2n_client()
+			.get_block_by_kernel(&excess, min_height, Some(tip))
+		{
+			Ok(Some(h)) => {
+				t.set_confirming_block(&h);
+				updated.push(t);
+			}
+			Ok(None) => {
+				let next = tip
+					.saturating_sub(finality_depth)
+					.max(min_height.unwrap_or(0));
+				if t.kernel_lookup_min_height != Some(next) {
+					t.kernel_lookup_min_height = Some(next);
+					updated.push(t);
+				}
+			}
+			Err(e) => {
+				warn!("Unable to get confirming block for tx {}: {}", t.id, e);
+				break;
+			}
+		}
+	}
+	if updated.is_empty() {
+		return Ok(());
+	}
+	let mut batch = wallet.batch(keychain_mask)?;
+	for t in updated {
+		batch.save_tx_log_entry(t, parent_key_id)?;
+	}
+	batch.commit()?;
+	Ok(())
+}
+
+/// Find the sends spending any of the same inputs as an outstanding send, and
+/// record the conflicts on the outstanding one. Once one of a set of
+/// conflicting sends is confirmed the others never can be, so they're
+/// cancelled along with their outputs. Only this wallet's own sends spend its
+/// outputs, received txs are left for their sender to sort out.
+fn update_tx_conflicts<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	parent_key_id: &Identifier,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let txs: Vec<TxLogEntry> = wallet
+		.tx_log_iter()
+		.filter(|t| {
+			t.parent_key_id == *parent_key_id
+				&& t.tx_type == TxLogEntryType::TxSent
+				&& t.stored_tx.is_some()
+		})
+		.collect();
+	// conflicts only matter while some send is still outstanding
+	if txs.iter().all(|t| t.confirmed) {
+		return Ok(());
+	}
+
+	let mut tx_inputs: Vec<(TxLogEntry, HashSet<pedersen::Commitment>)> = vec![];
+	for t in txs {
+		if let Some(tx) = wallet.get_stored_tx(&t)? {
+			let inputs: HashSet<pedersen::Commitment> =
+				tx.inputs().iter().map(|i| i.commitment()).collect();
+			if !inputs.is_empty() {
+				tx_inputs.push((t, inputs));
+			}
+		}
+	}
+
+	let mut changed = vec![];
+	let mut losers = vec![];
+	for (i, (t, inputs)) in tx_inputs.iter().enumerate() {
+		// confirmed sends can only win against the outstanding ones
+		if t.confirmed {
+			continue;
+		}
+		let conflicts: Vec<&TxLogEntry> = tx_inputs
+			.iter()
+			.enumerate()
+			.filter(|(j, (_, other))| *j != i && !inputs.is_disjoint(other))
+			.map(|(_, (o, _))| o)
+			.collect();
+		let mut conflicts_with: Vec<u32> = conflicts.iter().map(|o| o.id).collect();
+		conflicts_with.sort();
+		let lost = conflicts.iter().any(|o| o.confirmed);
+		if lost {
+			warn!(
+				"Tx {} conflicts with confirmed tx(s) {:?} and will be cancelled",
+				t.id, conflicts_with
+			);
+			let mut t = t.clone();
+			t.conflicts_with = conflicts_with;
+			losers.push(t);
+		} else if conflicts_with != t.conflicts_with {
+			let mut t = t.clone();
+			t.conflicts_with = conflicts_with;
+			changed.push(t);
+		}
+	}
+
+	if !changed.is_empty() {
+		let mut batch = wallet.batch(keychain_mask)?;
+		for t in changed {
+			batch.save_tx_log_entry(t, parent_key_id)?;
+		}
+		batch.commit()?;
+	}
+	for t in losers {
+		let outputs = retrieve_outputs(
+			wallet,
+			keychain_mask,
+			false,
+			Some(t.id),
+			Some(parent_key_id),
+		)?
+		.into_iter()
+		.map(|m| m.output)
+		.collect();
+		cancel_tx_and_outputs(wallet, keychain_mask, t, outputs, parent_key_id)?;
+	}
+	Ok(())
+}
+
+/// Post the sends held back until their kernel's lock height, such as the sends
+/// of a vault, now that the chain has reached it. A send the node refuses is kept
+/// to be posted again on the next refresh
+fn post_deferred_txs<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	height: u64,
+	parent_key_id: &Identifier,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let txs: Vec<TxLogEntry> = wallet
+		.tx_log_iter()
+		.filter(|t| {
+			t.parent_key_id == *parent_key_id
+				&& t.tx_type == TxLogEntryType::TxSent
+				&& !t.confirmed
+				&& t.post_at_height.map(|h| h <= height + 1).unwrap_or(false)
+		})
+		.collect();
+	let mut posted = vec![];
+	for mut t in txs {
+		let tx = match wallet.get_stored_tx(&t)? {
+			Some(tx) => tx,
+			None => continue,
+		};
+		let tx_hex = util::to_hex(ser::ser_vec(&tx, ser::ProtocolVersion::local()).unwrap());
+		match wallet.w2n_client().post_tx(&TxWrapper { tx_hex }, false) {
+			Ok(()) => {
+				info!("Posted tx {}, its lock height was reached", t.id);
+				t.post_at_height = None;
+				posted.push(t);
+			}
+			Err(e) => warn!("Unable to post tx {}, retrying on refresh: {}", t.id, e),
+		}
+	}
+	if posted.is_empty() {
+		return Ok(());
+	}
+	let mut batch = wallet.batch(keychain_mask)?;
+	for t in posted {
+		batch.save_tx_log_entry(t, parent_key_id)?;
+	}
+	batch.commit()?;
+	Ok(())
+}
+
+/// Check whether posted but unconfirmed txs are sitting in the node's mempool.
+/// If the node can't tell us, the previous status is kept
+fn update_mempool_status<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	parent_key_id: &Identifier,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let txs: Vec<TxLogEntry> = wallet
+		.tx_log_iter()
+		.filter(|t| {
+			t.parent_key_id == *parent_key_id
+				&& !t.confirmed
+				&& (t.in_mempool
+					|| t.tx_type == TxLogEntryType::TxSent
+					|| t.tx_type == TxLogEntryType::TxReceived)
+		})
+		.collect();
+	let mut checked = vec![];
+	for t in txs {
+		let excess = match wallet.get_stored_tx(&t)? {
+			Some(tx) => tx.kernels().first().map(|k| k.excess),
+			None => None,
+		};
+		if let Some(e) = excess {
+			checked.push((t, e));
+		}
+	}
+	if checked.is_empty() {
+		return Ok(());
+	}
+	// the pool is fetched once for all the txs
+	let pool = match wallet.w2n_client().get_pool_entries() {
+		Ok(p) => p,
+		Err(e) => {
+			debug!("Unable to check mempool status of txs: {}", e);
+			return Ok(());
+		}
+	};
+	let mut updated = vec![];
+	for (mut t, excess) in checked {
+		let in_pool = pool.contains_key(&excess);
+		if in_pool != t.in_mempool {
+			t.in_mempool = in_pool;
+			updated.push(t);
+		}
+	}
+	if updated.is_empty() {
+		return Ok(());
+	}
+	let mut batch = wallet.batch(keychain_mask)?;
+	for t in updated {
+		batch.save_tx_log_entry(t, parent_key_id)?;
+	}
+	batch.commit()?;
+	Ok(())
+}
+
+/// Mark received txs as abandoned when their sender never posted them: not
+/// confirmed nor in the mempool after the configured number of hours, and with
+/// a kernel the node doesn't know of. Nothing is done unless the hours are set
+fn update_abandoned_receives<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	parent_key_id: &Identifier,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let hours = match wallet.config().abandon_received_after_hours {
+		Some(h) => h,
+		None => return Ok(()),
+	};
+	let cutoff = Utc::now() - Duration::hours(hours as i64);
+	let txs: Vec<TxLogEntry> = wallet
+		.tx_log_iter()
+		.filter(|t| {
+			t.parent_key_id == *parent_key_id
+				&& t.tx_type == TxLogEntryType::TxReceived
+				&& !t.confirmed
+				&& !t.abandoned
+				&& !t.in_mempool
+				&& t.creation_ts < cutoff
+		})
+		.collect();
+	let mut updated = vec![];
+	for mut t in txs {
+		let excess = match wallet.get_stored_tx(&t)? {
+			Some(tx) => tx.kernels().first().map(|k| k.excess),
+			None => None,
+		};
+		if let Some(e) = excess {
+			match wallet.w2n_client().get_block_by_kernel(&e, None, None) {
+				Ok(None) => {}
+				Ok(Some(_)) => continue,
+				Err(e) => {
+					debug!("Unable to look up the kernel of tx {}: {}", t.id, e);
+					break;
+				}
+			}
+		}
+		t.abandoned = true;
+		updated.push(t);
+	}
+	if updated.is_empty() {
+		return Ok(());
+	}
+	let mut batch = wallet.batch(keychain_mask)?;
+	for t in updated {
+		warn!(
+			"Received tx {} was never posted by its sender, marking it abandoned",
+			t.id
+		);
+		batch.save_tx_log_entry(t, parent_key_id)?;
+	}
+	batch.commit()?;
+	Ok(())
+}
+
+/// Cancel the payjoins received that weren't posted or confirmed within the
+/// configured number of minutes, releasing the input the wallet contributed so
+/// that it can be spent again. A payjoin posted afterwards is still valid as
+/// long as the input isn't spent, and its output is then only found by a scan.
+fn release_payjoin_inputs<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	parent_key_id: &Identifier,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let mins = wallet.config().payjoin_release_after_mins();
+	let cutoff = Utc::now() - Duration::minutes(mins as i64);
+	let txs: Vec<TxLogEntry> = wallet
+		.tx_log_iter()
+		.filter(|t| {
+			t.parent_key_id == *parent_key_id
+				&& t.tx_type == TxLogEntryType::TxReceived
+				&& t.num_inputs > 0
+				&& !t.confirmed
+				&& !t.in_mempool
+				&& t.creation_ts < cutoff
+		})
+		.collect();
+	for t in txs {
+		let outputs: Vec<OutputData> = wallet
+			.iter()
+			.filter(|o| o.tx_log_entry == Some(t.id) && o.root_key_id == *parent_key_id)
+			.collect();
+		warn!(
+			"Payjoin received in tx {} wasn't posted after {} minutes, releasing its input",
+			t.id, mins
+		);
+		cancel_tx_and_outputs(wallet, keychain_mask, t, outputs, parent_key_id)?;
+	}
+	Ok(())
+}
+
+/// Detect the payments to the one-time addresses of the account: an address is
+/// used once the tx paying to it confirms, and can be paid to again if the tx
+/// was cancelled or abandoned
+fn update_onetime_addresses<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	parent_key_id: &Identifier,
+) -> Result<(), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let addresses: Vec<OnetimeAddress> = wallet
+		.onetime_address_iter()
+		.filter(|a| a.parent_key_id == *parent_key_id && a.tx_slate_id.is_some() && !a.confirmed)
+		.collect();
+	let mut updated = vec![];
+	for mut a in addresses {
+		let tx = wallet
+			.tx_log_iter()
+			.find(|t| t.parent_key_id == *parent_key_id && t.tx_slate_id == a.tx_slate_id);
+		let tx = match tx {
+			Some(t) => t,
+			None => continue,
+		};
+		if tx.confirmed {
+			info!("Payment to one-time address {} confirmed", a.id);
+			a.confirmed = true;
+		} else if tx.tx_type == TxLogEntryType::TxReceivedCancelled || tx.abandoned {
+			info!(
+				"Payment to one-time address {} failed, releasing the address",
+				a.id
+			);
+			a.tx_slate_id = None;
+			a.amount = None;
+		} else {
+			continue;
+		}
+		updated.push(a);
+	}
+	if updated.is_empty() {
+		return Ok(());
+	}
+	let mut batch = wallet.batch(keychain_mask)?;
+	for a in updated {
+		batch.save_onetime_address(&a)?;
+	}
+	batch.commit()?;
 	Ok(())
 }
 
@@ -400,6 +1452,12 @@ where
 	K: Keychain + 'a,
 {
 	let current_height = wallet.last_confirmed_height()?;
+	let finality_depth = wallet.config().finality_depth();
+	let abandoned: HashSet<u32> = wallet
+		.tx_log_iter()
+		.filter(|t| t.parent_key_id == *parent_key_id && t.abandoned)
+		.map(|t| t.id)
+		.collect();
 	let outputs = wallet
 		.iter()
 		.filter(|out| out.root_key_id == *parent_key_id);
@@ -409,10 +1467,16 @@ where
 	let mut awaiting_finalization_total = 0;
 	let mut unconfirmed_total = 0;
 	let mut locked_total = 0;
+	let mut finalized_total = 0;
+	let mut abandoned_total = 0;
+	let mut reverted_total = 0;
 
 	for out in outputs {
 		match out.status {
 			OutputStatus::Unspent => {
+				if out.is_final(current_height, finality_depth) {
+					finalized_total += out.value;
+				}
 				if out.is_coinbase && out.lock_height > current_height {
 					immature_total += out.value;
 				} else if out.num_confirmations(current_height) < minimum_confirmations {
@@ -425,7 +1489,9 @@ where
 			OutputStatus::Unconfirmed => {
 				// We ignore unconfirmed coinbase outputs completely.
 				if !out.is_coinbase {
-					if minimum_confirmations == 0 {
+					if out.tx_log_entry.map_or(false, |id| abandoned.contains(&id)) {
+						abandoned_total += out.value;
+					} else if minimum_confirmations == 0 {
 						unconfirmed_total += out.value;
 					} else {
 						awaiting_finalization_total += out.value;
@@ -435,10 +1501,18 @@ where
 			OutputStatus::Locked => {
 				locked_total += out.value;
 			}
+			OutputStatus::Reverted => {
+				reverted_total += out.value;
+			}
 			OutputStatus::Spent => {}
 		}
 	}
 
+	let held_total: u64 = active_balance_holds(wallet, parent_key_id)
+		.iter()
+		.map(|h| h.amount)
+		.sum();
+
 	Ok(WalletInfo {
 		last_confirmed_height: current_height,
 		minimum_confirmations,
@@ -446,8 +1520,93 @@ where
 		amount_awaiting_finalization: awaiting_finalization_total,
 		amount_awaiting_confirmation: unconfirmed_total,
 		amount_immature: immature_total,
+		amount_reverted: reverted_total,
 		amount_locked: locked_total,
 		amount_currently_spendable: unspent_total,
+		amount_held: held_total,
+		amount_available: unspent_total.saturating_sub(held_total),
+		amount_finalized: finalized_total,
+		amount_abandoned: abandoned_total,
+		finality_depth,
+		currency: wallet.config().currency(),
+	})
+}
+
+/// Balance holds of the given account that haven't expired
+pub fn active_balance_holds<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	parent_key_id: &Identifier,
+) -> Vec<BalanceHold>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let now = Utc::now();
+	wallet
+		.balance_hold_iter()
+		.filter(|h| h.parent_key_id == *parent_key_id && h.is_active(now))
+		.collect()
+}
+
+/// Report on the coinbase outputs of the given account
+/// caller should refresh first if desired
+pub fn retrieve_coinbase_report<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	parent_key_id: &Identifier,
+) -> Result<CoinbaseReport, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let current_height = wallet.last_confirmed_height()?;
+	let orphaned: HashSet<u32> = wallet
+		.tx_log_iter()
+		.filter(|t| {
+			t.parent_key_id == *parent_key_id && t.tx_type == TxLogEntryType::OrphanedCoinbase
+		})
+		.map(|t| t.id)
+		.collect();
+	let mut coinbases: Vec<CoinbaseInfo> = wallet
+		.iter()
+		.filter(|out| out.root_key_id == *parent_key_id && out.is_coinbase)
+		.map(|out| {
+			let status = match out.status {
+				OutputStatus::Unconfirmed => CoinbaseStatus::Pending,
+				OutputStatus::Reverted => CoinbaseStatus::Orphaned,
+				OutputStatus::Spent => match out.tx_log_entry {
+					Some(id) if orphaned.contains(&id) => CoinbaseStatus::Orphaned,
+					_ => CoinbaseStatus::Spent,
+				},
+				_ if out.lock_height > current_height => CoinbaseStatus::Immature,
+				_ => CoinbaseStatus::Mature,
+			};
+			CoinbaseInfo {
+				height: out.height,
+				key_id: out.key_id,
+				commit: out.commit,
+				value: out.value,
+				status,
+				tx_log_entry: out.tx_log_entry,
+			}
+		})
+		.collect();
+	coinbases.sort_by_key(|c| c.height);
+
+	let amount = |status: CoinbaseStatus| -> u64 {
+		coinbases
+			.iter()
+			.filter(|c| c.status == status)
+			.map(|c| c.value)
+			.sum()
+	};
+	Ok(CoinbaseReport {
+		last_confirmed_height: current_height,
+		amount_immature: amount(CoinbaseStatus::Immature),
+		amount_mature: amount(CoinbaseStatus::Mature),
+		amount_orphaned: amount(CoinbaseStatus::Orphaned),
+		coinbases,
 	})
 }
 
@@ -472,6 +1631,33 @@ where
 	})
 }
 
+/// Build a coinbase output keyed by the block height and insert into wallet.
+/// Repeated requests for the same height use the same key, and replace the
+/// stored output.
+pub fn build_coinbase_at_height<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	block_fees: &BlockFees,
+	test_mode: bool,
+) -> Result<CbData, Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	keys::check_not_watch_only(wallet, "building a coinbase")?;
+	let parent_key_id = wallet.parent_key_id();
+	let key_id = keys::coinbase_key_for_height(&parent_key_id, block_fees.height)?;
+	let (out, kern, block_fees) =
+		receive_coinbase_with_key(wallet, keychain_mask, block_fees, key_id, test_mode)?;
+
+	Ok(CbData {
+		output: out,
+		kernel: kern,
+		key_id: block_fees.key_id,
+	})
+}
+
 //TODO: Split up the output creation and the wallet insertion
 /// Build a coinbase output and the corresponding kernel
 pub fn receive_coinbase<'a, T: ?Sized, C, K>(
@@ -485,23 +1671,38 @@ where
 	C: NodeClient + 'a,
 	K: Keychain + 'a,
 {
-	let height = block_fees.height;
-	let lock_height = height + global::coinbase_maturity();
-	let key_id = block_fees.key_id();
-	let parent_key_id = wallet.parent_key_id();
-
-	let key_id = match key_id {
+	let key_id = match block_fees.key_id() {
 		Some(key_id) => match keys::retrieve_existing_key(wallet, key_id, None) {
 			Ok(k) => k.0,
 			Err(_) => keys::next_available_key(wallet, keychain_mask)?,
 		},
 		None => keys::next_available_key(wallet, keychain_mask)?,
 	};
+	receive_coinbase_with_key(wallet, keychain_mask, block_fees, key_id, test_mode)
+}
+
+/// Build a coinbase output and the corresponding kernel with the given key
+fn receive_coinbase_with_key<'a, T: ?Sized, C, K>(
+	wallet: &mut T,
+	keychain_mask: Option<&SecretKey>,
+	block_fees: &BlockFees,
+	key_id: Identifier,
+	test_mode: bool,
+) -> Result<(Output, TxKernel, BlockFees), Error>
+where
+	T: WalletBackend<'a, C, K>,
+	C: NodeClient + 'a,
+	K: Keychain + 'a,
+{
+	let height = block_fees.height;
+	let lock_height = height + wallet.w2n_client().coinbase_maturity();
+	let parent_key_id = wallet.parent_key_id();
 
 	{
 		// Now acquire the wallet lock and write the new output.
 		let amount = reward(block_fees.fees, height);
 		let commit = wallet.calc_commit_for_cache(keychain_mask, amount, &key_id)?;
+		let price = wallet.price_source().and_then(|s| s.current_price());
 		let mut batch = wallet.batch(keychain_mask)?;
 		batch.save(OutputData {
 			root_key_id: parent_key_id,
@@ -515,6 +1716,9 @@ where
 			lock_height: lock_height,
 			is_coinbase: true,
 			tx_log_entry: None,
+			reservation: None,
+			cost_basis: price,
+			change_seq: 0,
 		})?;
 		batch.commit()?;
 	}
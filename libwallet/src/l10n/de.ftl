@@ -0,0 +1,76 @@
+# German messages of the wallet errors, see en.ftl
+
+NotEnoughFunds = Nicht genügend Guthaben. Benötigt: { $needed_disp }, verfügbar: { $available_disp }
+Fee = Gebührenfehler: { $arg0 }
+LibTX = LibTx-Fehler
+Keychain = Schlüsselbundfehler
+Transaction = Transaktionsfehler
+ClientCallback = Fehler beim Client-Callback: { $arg0 }
+PartialRefresh = Unvollständige Aktualisierung, { $arg0 } von { $arg1 } Outputs konnten nicht abgefragt werden: { $arg2 }
+Secp = Secp-Fehler
+CallbackImpl = Fehler in der Trait-Implementierung
+Backend = Fehler im Wallet-Speicher: { $arg0 }
+Restore = Fehler bei der Wiederherstellung
+Format = Fehler im JSON-Format: { $arg0 }
+Deser = Fehler bei der (De-)Serialisierung
+IO = Ein-/Ausgabefehler
+Node = Fehler der Node-API
+WalletComms = Fehler bei der Kommunikation mit der Wallet: { $arg0 }
+Hyper = Hyper-Fehler
+Uri = Fehler beim Parsen der URI
+Signature = Signaturfehler: { $arg0 }
+APIEncryption = { $arg0 }
+DuplicateTransactionId = Doppelte Transaktions-ID
+WalletSeedExists = Wallet-Seed existiert bereits: { $arg0 }
+WalletSeedDoesntExist = Wallet-Seed existiert nicht
+WalletSeedDecryption = Wallet-Seed konnte nicht entschlüsselt werden
+WalletInUse = Wallet wird bereits verwendet: { $arg0 }
+TransactionDoesntExist = Transaktion { $arg0 } existiert nicht
+TransactionNotCancellable = Transaktion { $arg0 } kann nicht storniert werden
+TransactionNotAbandonable = Transaktion { $arg0 } kann nicht als aufgegeben markiert werden
+NotPendingDispatch = Transaktion { $arg0 } wartet nicht auf den Versand
+TransactionCancellationError = Fehler beim Stornieren: { $arg0 }
+TransactionDumpError = Fehler beim Ausgeben der Transaktion: { $arg0 }
+TransactionAlreadyConfirmed = Transaktion ist bereits bestätigt
+TransactionAlreadyReceived = Transaktion { $arg0 } wurde bereits empfangen
+TransactionBuildingNotCompleted = Erstellung der Transaktion nicht abgeschlossen: { $arg0 }
+InvalidChildIndex = Ungültiger Kindindex: { $arg0 }
+InvalidBIP32Depth = Ungültige BIP32-Tiefe (muss mindestens 1 sein)
+AccountLabelAlreadyExists = Konto '{ $arg0 }' existiert bereits
+UnknownAccountLabel = Unbekanntes Konto '{ $arg0 }'
+Committed = Fehler beim Summieren der Commitments
+SlateVersionParse = Slate-Version kann nicht gelesen werden
+SlateSer = Slate kann nicht serialisiert werden
+SlateDeser = Slate kann nicht deserialisiert werden
+SlateVersion = Unbekannte Slate-Version: { $arg0 }
+Compatibility = Kompatibilitätsfehler: { $arg0 }
+KeychainDoesntExist = Schlüsselbund existiert nicht (wurde die Wallet geöffnet?)
+Lifecycle = Fehler im Lebenszyklus: { $arg0 }
+InvalidKeychainMask = Das angegebene Schlüsselbund-Maskentoken ist falsch
+TransactionApprovalRequired = Transaktion { $arg0 } muss von einem zweiten Owner-API-Token genehmigt werden
+TransactionApprovalError = Fehler bei der Genehmigung: { $arg0 }
+SlateAttachment = Fehler im Slate-Anhang: { $arg0 }
+RefundError = Fehler bei der Rückerstattung: { $arg0 }
+OnetimeAddress = Fehler der Einmaladresse: { $arg0 }
+InputsAlreadySpent = { $arg0 } Eingänge der Transaktion wurden bereits ausgegeben
+OperationsPending = Es laufen noch Vorgänge
+LimitExceeded = { $limit } ist { $actual }, mehr als die Grenze von { $max }
+ForeignApiKey = Fehler des Foreign-API-Schlüssels: { $arg0 }
+MinerApi = Fehler der Miner-API: { $arg0 }
+ReceiveChallenge = Fehler bei der Empfangs-Challenge: { $arg0 }
+MethodDisabled = Methode { $arg0 } ist auf diesem Listener nicht aktiviert
+SlateExchange = Fehler beim Slate-Austausch: { $arg0 }
+SourceRejected = Slates von { $arg0 } werden nicht angenommen
+WalletFrozen = Wallet seit { $arg0 } eingefroren, zum Auftauen das Wallet-Passwort verwenden
+ReadOnly = Wallet schreibgeschützt geöffnet, { $arg0 } ist nicht erlaubt
+Sweep = Fehler beim Zusammenführen: { $arg0 }
+ImportOutput = Fehler beim Importieren des Outputs: { $arg0 }
+RestoreScan = Fehler beim schrittweisen Wiederherstellen: { $arg0 }
+DestinationResolution = Ziel konnte nicht aufgelöst werden: { $arg0 }
+DestinationConnection = Keine Verbindung zum Ziel: { $arg0 }
+DestinationHttp = Ziel antwortete mit HTTP-Status { $arg0 }: { $arg1 }
+Tor = Tor-Fehler: { $arg0 }
+PaymentProof = Fehler beim Zahlungsnachweis: { $arg0 }
+Totp = Fehler beim zweiten Faktor: { $arg0 }
+StaleNode = Veralteter Knoten: { $arg0 }
+GenericError = Allgemeiner Fehler: { $arg0 }
@@ -0,0 +1,77 @@
+# Messages of the wallet errors, keyed by the name of the error kind. Fields of
+# the error are variables named after them, or $arg0, $arg1... if unnamed
+
+NotEnoughFunds = Not enough funds. Required: { $needed_disp }, Available: { $available_disp }
+Fee = Fee Error: { $arg0 }
+LibTX = LibTx Error
+Keychain = Keychain error
+Transaction = Transaction error
+ClientCallback = Client Callback Error: { $arg0 }
+PartialRefresh = Partial output refresh, { $arg0 } of { $arg1 } outputs couldn't be queried: { $arg2 }
+Secp = Secp error
+CallbackImpl = Trait Implementation error
+Backend = Wallet store error: { $arg0 }
+Restore = Restore Error
+Format = JSON format error: { $arg0 }
+Deser = Ser/Deserialization error
+IO = I/O error
+Node = Node API error
+WalletComms = Wallet Communication Error: { $arg0 }
+Hyper = Hyper error
+Uri = Uri parsing error
+Signature = Signature error: { $arg0 }
+APIEncryption = { $arg0 }
+DuplicateTransactionId = Duplicate transaction ID error
+WalletSeedExists = Wallet seed exists error: { $arg0 }
+WalletSeedDoesntExist = Wallet seed doesn't exist error
+WalletSeedDecryption = Wallet seed decryption error
+WalletInUse = Wallet already in use: { $arg0 }
+TransactionDoesntExist = Transaction { $arg0 } doesn't exist
+TransactionNotCancellable = Transaction { $arg0 } cannot be cancelled
+TransactionNotAbandonable = Transaction { $arg0 } cannot be abandoned
+NotPendingDispatch = Transaction { $arg0 } is not waiting to be dispatched
+TransactionCancellationError = Cancellation Error: { $arg0 }
+TransactionDumpError = Tx dump Error: { $arg0 }
+TransactionAlreadyConfirmed = Transaction already confirmed error
+TransactionAlreadyReceived = Transaction { $arg0 } has already been received
+TransactionBuildingNotCompleted = Transaction building not completed: { $arg0 }
+InvalidChildIndex = Invalid child index: { $arg0 }
+InvalidBIP32Depth = Invalid BIP32 Depth (must be 1 or greater)
+AccountLabelAlreadyExists = Account Label '{ $arg0 }' already exists
+UnknownAccountLabel = Unknown Account Label '{ $arg0 }'
+Committed = Committed Error
+SlateVersionParse = Can't parse slate version
+SlateSer = Can't Serialize slate
+SlateDeser = Can't Deserialize slate
+SlateVersion = Unknown Slate Version: { $arg0 }
+Compatibility = Compatibility Error: { $arg0 }
+KeychainDoesntExist = Keychain doesn't exist (has wallet been opened?)
+Lifecycle = Lifecycle Error: { $arg0 }
+InvalidKeychainMask = Supplied Keychain Mask Token is incorrect
+TransactionApprovalRequired = Transaction { $arg0 } requires approval from a second Owner API token
+TransactionApprovalError = Approval Error: { $arg0 }
+SlateAttachment = Slate attachment error: { $arg0 }
+RefundError = Refund Error: { $arg0 }
+OnetimeAddress = One-time address error: { $arg0 }
+InputsAlreadySpent = { $arg0 } inputs of the transaction were already spent
+OperationsPending = Operations still in progress
+LimitExceeded = { $limit } is { $actual }, more than the limit of { $max }
+ForeignApiKey = Foreign API key error: { $arg0 }
+MinerApi = Miner API error: { $arg0 }
+ReceiveChallenge = Receive challenge error: { $arg0 }
+MethodDisabled = Method { $arg0 } is not enabled on this listener
+SlateExchange = Slate exchange error: { $arg0 }
+SourceRejected = Slates from { $arg0 } are not accepted
+WalletFrozen = Wallet frozen since { $arg0 }, unfreeze it with the wallet password
+ReadOnly = Wallet opened read-only, { $arg0 } isn't allowed
+Sweep = Sweep error: { $arg0 }
+ImportOutput = Output import error: { $arg0 }
+RestoreScan = Restore scan error: { $arg0 }
+DestinationResolution = Unable to resolve destination: { $arg0 }
+DestinationConnection = Unable to connect to destination: { $arg0 }
+DestinationHttp = Destination replied with HTTP status { $arg0 }: { $arg1 }
+Tor = Tor error: { $arg0 }
+PaymentProof = Payment proof error: { $arg0 }
+Totp = Second factor error: { $arg0 }
+StaleNode = Stale node: { $arg0 }
+GenericError = Generic error: { $arg0 }
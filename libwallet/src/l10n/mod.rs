@@ -0,0 +1,155 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Localization of the user-facing messages, from catalogs embedded in the
+//! wallet. Catalogs are written in the subset of the Fluent syntax made of
+//! comments and single line messages with `{ $variable }` placeables.
+
+use std::collections::HashMap;
+
+/// Locale the catalogs fall back to, whose messages are the ones displayed
+/// by the errors themselves
+pub const DEFAULT_LOCALE: &'static str = "en";
+
+/// Embedded catalogs, by locale
+const CATALOGS: &[(&str, &str)] = &[
+	("en", include_str!("en.ftl")),
+	("de", include_str!("de.ftl")),
+	("ru", include_str!("ru.ftl")),
+];
+
+lazy_static! {
+	static ref MESSAGES: HashMap<&'static str, HashMap<String, String>> = CATALOGS
+		.iter()
+		.map(|(locale, ftl)| (*locale, parse_catalog(ftl)))
+		.collect();
+}
+
+/// Catalog of the messages in `locale`, if one other than the default. Locales
+/// are matched on their language, so `de_DE.UTF-8` picks the `de` catalog;
+/// unknown locales leave the messages in English.
+pub fn catalog_locale(locale: Option<&str>) -> Option<&'static str> {
+	let lang = locale?
+		.split(|c| c == '_' || c == '-' || c == '.')
+		.next()?
+		.to_lowercase();
+	MESSAGES
+		.keys()
+		.find(|l| **l == lang && **l != DEFAULT_LOCALE)
+		.map(|l| *l)
+}
+
+/// Message `id` in `locale`, falling back to English, with its variables
+/// replaced by `args`. None if the message is unknown or one of its variables
+/// is missing.
+pub fn localize(locale: Option<&str>, id: &str, args: &[(String, String)]) -> Option<String> {
+	let pattern = catalog_locale(locale)
+		.and_then(|l| MESSAGES.get(l))
+		.and_then(|m| m.get(id))
+		.or_else(|| MESSAGES.get(DEFAULT_LOCALE).and_then(|m| m.get(id)))?;
+	format_message(pattern, args)
+}
+
+/// Messages of a catalog, by id
+fn parse_catalog(ftl: &str) -> HashMap<String, String> {
+	let mut messages = HashMap::new();
+	for line in ftl.lines() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		if let Some(i) = line.find('=') {
+			let id = line[..i].trim();
+			if !id.is_empty() {
+				messages.insert(id.to_owned(), line[i + 1..].trim().to_owned());
+			}
+		}
+	}
+	messages
+}
+
+fn format_message(pattern: &str, args: &[(String, String)]) -> Option<String> {
+	let mut out = String::new();
+	let mut rest = pattern;
+	while let Some(start) = rest.find('{') {
+		out.push_str(&rest[..start]);
+		rest = &rest[start + 1..];
+		let end = rest.find('}')?;
+		let placeable = rest[..end].trim();
+		if placeable.starts_with('$') {
+			let (_, value) = args.iter().find(|(name, _)| name == &placeable[1..])?;
+			out.push_str(value);
+		} else if placeable.len() >= 2 && placeable.starts_with('"') && placeable.ends_with('"') {
+			out.push_str(&placeable[1..placeable.len() - 1]);
+		} else {
+			return None;
+		}
+		rest = &rest[end + 1..];
+	}
+	out.push_str(rest);
+	Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn l10n_catalogs_cover_english() {
+		let en = &MESSAGES[DEFAULT_LOCALE];
+		for (locale, messages) in MESSAGES.iter() {
+			for id in messages.keys() {
+				assert!(
+					en.contains_key(id),
+					"{} message {} not in English",
+					locale,
+					id
+				);
+			}
+		}
+	}
+
+	#[test]
+	fn l10n_format() {
+		let args = vec![("arg0".to_owned(), "abc".to_owned())];
+		assert_eq!(
+			format_message("Transaction { $arg0 } {\"{\"}x}", &args),
+			Some("Transaction abc {x}".to_owned())
+		);
+		assert_eq!(format_message("Missing { $arg1 }", &args), None);
+	}
+
+	#[test]
+	fn l10n_locales() {
+		assert_eq!(catalog_locale(Some("de_DE.UTF-8")), Some("de"));
+		assert_eq!(catalog_locale(Some("RU")), Some("ru"));
+		assert_eq!(catalog_locale(Some("en_US")), None);
+		assert_eq!(catalog_locale(Some("xx")), None);
+		assert_eq!(catalog_locale(None), None);
+
+		// Each call is localized in the locale it's given
+		let args = vec![("arg0".to_owned(), "2019".to_owned())];
+		let de = localize(Some("de"), "WalletFrozen", &args).unwrap();
+		let ru = localize(Some("ru"), "WalletFrozen", &args).unwrap();
+		let en = localize(None, "WalletFrozen", &args).unwrap();
+		assert_eq!(
+			en,
+			"Wallet frozen since 2019, unfreeze it with the wallet password"
+		);
+		assert!(de.starts_with("Wallet seit 2019"));
+		assert_ne!(de, ru);
+		assert_eq!(localize(Some("xx"), "WalletFrozen", &args), Some(en));
+		assert_eq!(localize(Some("de"), "NoSuchMessage", &args), None);
+	}
+}
@@ -0,0 +1,76 @@
+# Russian messages of the wallet errors, see en.ftl
+
+NotEnoughFunds = Недостаточно средств. Требуется: { $needed_disp }, доступно: { $available_disp }
+Fee = Ошибка комиссии: { $arg0 }
+LibTX = Ошибка LibTx
+Keychain = Ошибка связки ключей
+Transaction = Ошибка транзакции
+ClientCallback = Ошибка обратного вызова клиента: { $arg0 }
+PartialRefresh = Неполное обновление, не удалось запросить { $arg0 } из { $arg1 } выходов: { $arg2 }
+Secp = Ошибка secp
+CallbackImpl = Ошибка реализации трейта
+Backend = Ошибка хранилища кошелька: { $arg0 }
+Restore = Ошибка восстановления
+Format = Ошибка формата JSON: { $arg0 }
+Deser = Ошибка сериализации
+IO = Ошибка ввода-вывода
+Node = Ошибка API узла
+WalletComms = Ошибка связи с кошельком: { $arg0 }
+Hyper = Ошибка Hyper
+Uri = Ошибка разбора URI
+Signature = Ошибка подписи: { $arg0 }
+APIEncryption = { $arg0 }
+DuplicateTransactionId = Повторный идентификатор транзакции
+WalletSeedExists = Сид кошелька уже существует: { $arg0 }
+WalletSeedDoesntExist = Сид кошелька не существует
+WalletSeedDecryption = Не удалось расшифровать сид кошелька
+WalletInUse = Кошелёк уже используется: { $arg0 }
+TransactionDoesntExist = Транзакция { $arg0 } не существует
+TransactionNotCancellable = Транзакцию { $arg0 } нельзя отменить
+TransactionNotAbandonable = Транзакцию { $arg0 } нельзя пометить как брошенную
+NotPendingDispatch = Транзакция { $arg0 } не ожидает отправки
+TransactionCancellationError = Ошибка отмены: { $arg0 }
+TransactionDumpError = Ошибка выгрузки транзакции: { $arg0 }
+TransactionAlreadyConfirmed = Транзакция уже подтверждена
+TransactionAlreadyReceived = Транзакция { $arg0 } уже получена
+TransactionBuildingNotCompleted = Построение транзакции не завершено: { $arg0 }
+InvalidChildIndex = Неверный дочерний индекс: { $arg0 }
+InvalidBIP32Depth = Неверная глубина BIP32 (должна быть не меньше 1)
+AccountLabelAlreadyExists = Счёт '{ $arg0 }' уже существует
+UnknownAccountLabel = Неизвестный счёт '{ $arg0 }'
+Committed = Ошибка суммирования коммитментов
+SlateVersionParse = Не удалось разобрать версию слейта
+SlateSer = Не удалось сериализовать слейт
+SlateDeser = Не удалось десериализовать слейт
+SlateVersion = Неизвестная версия слейта: { $arg0 }
+Compatibility = Ошибка совместимости: { $arg0 }
+KeychainDoesntExist = Связка ключей не существует (кошелёк открыт?)
+Lifecycle = Ошибка жизненного цикла: { $arg0 }
+InvalidKeychainMask = Указан неверный токен маски связки ключей
+TransactionApprovalRequired = Транзакция { $arg0 } требует одобрения вторым токеном Owner API
+TransactionApprovalError = Ошибка одобрения: { $arg0 }
+SlateAttachment = Ошибка вложения слейта: { $arg0 }
+RefundError = Ошибка возврата: { $arg0 }
+OnetimeAddress = Ошибка одноразового адреса: { $arg0 }
+InputsAlreadySpent = { $arg0 } входов транзакции уже потрачены
+OperationsPending = Операции ещё выполняются
+LimitExceeded = { $limit }: { $actual }, больше предела { $max }
+ForeignApiKey = Ошибка ключа Foreign API: { $arg0 }
+MinerApi = Ошибка API майнера: { $arg0 }
+ReceiveChallenge = Ошибка проверки получения: { $arg0 }
+MethodDisabled = Метод { $arg0 } не включён на этом слушателе
+SlateExchange = Ошибка обмена слейтами: { $arg0 }
+SourceRejected = Слейты от { $arg0 } не принимаются
+WalletFrozen = Кошелёк заморожен с { $arg0 }, разморозьте его паролем кошелька
+ReadOnly = Кошелёк открыт только для чтения, { $arg0 } запрещено
+Sweep = Ошибка консолидации: { $arg0 }
+ImportOutput = Ошибка импорта выхода: { $arg0 }
+RestoreScan = Ошибка пошагового восстановления: { $arg0 }
+DestinationResolution = Не удалось разрешить адрес получателя: { $arg0 }
+DestinationConnection = Не удалось подключиться к получателю: { $arg0 }
+DestinationHttp = Получатель ответил с HTTP статусом { $arg0 }: { $arg1 }
+Tor = Ошибка Tor: { $arg0 }
+PaymentProof = Ошибка подтверждения платежа: { $arg0 }
+Totp = Ошибка второго фактора: { $arg0 }
+StaleNode = Устаревший узел: { $arg0 }
+GenericError = Общая ошибка: { $arg0 }
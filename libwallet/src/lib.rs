@@ -45,25 +45,60 @@ extern crate strum;
 extern crate strum_macros;
 
 pub mod api_impl;
+pub mod cost_basis;
 mod error;
 mod internal;
+mod l10n;
+pub mod payment_proof;
 mod slate;
 pub mod slate_versions;
+pub mod test_vectors;
+pub mod totp;
 mod types;
+pub mod view_only;
 
 pub use crate::error::{Error, ErrorKind};
-pub use crate::slate::{ParticipantData, ParticipantMessageData, Slate};
+pub use crate::slate::{
+	check_json_depth, ParticipantAttachmentData, ParticipantData, ParticipantDescription,
+	ParticipantMessageData, Slate, SlateAttachment, SlateDescription, MAX_ATTACHMENTS,
+	MAX_ATTACHMENT_KIND_LEN, MAX_ATTACHMENT_LEN, MAX_JSON_DEPTH, MAX_SLATE_MESSAGE_LEN,
+	MAX_SLATE_PARTICIPANTS, MAX_SLATE_SIZE, ONETIME_ADDRESS_ATTACHMENT, PAYJOIN_ATTACHMENT,
+	PAYMENT_PROOF_ATTACHMENT, PAYMENT_PROOF_REQUEST_ATTACHMENT, REFUND_ADDRESS_ATTACHMENT,
+};
 pub use crate::slate_versions::{
 	SlateVersion, VersionedCoinbase, VersionedSlate, CURRENT_SLATE_VERSION,
 	GRIN_BLOCK_HEADER_VERSION,
 };
 pub use api_impl::types::{
-	BlockFees, InitTxArgs, InitTxSendArgs, IssueInvoiceTxArgs, NodeHeightResult,
-	OutputCommitMapping, SendTXArgs, VersionInfo,
+	BlockFees, ChangeOutput, ChildIndexScan, CostBasisLot, FeeRateEstimate, IndexRebuildReport,
+	InitTxArgs, InitTxResult, InitTxSendArgs, IssueInvoiceTxArgs, LedgerDirection, LedgerEntry,
+	LedgerMismatch, NodeHeightResult, OperationState, OperationStatus, OutputCommitMapping,
+	OutputDerivationAudit, OutputIntegrityIssue, PendingOperation, PendingOperations, PingResult,
+	RecipientHandshake, ReconcileArgs, ReconciliationReport, RefundAddress, RefusedTxState,
+	ReserveOutputsArgs, RestoreScan, RestoredOutput, SendHandle, SendMetrics, SendTXArgs,
+	SlateAttachmentArgs, SweepArgs, SweepBatch, SweepPlan, SweepProgress, TotpSetup,
+	TxCancelResult, TxDetails, TxFilter, VersionInfo, WalletStats,
+};
+pub use cost_basis::{FiatPrice, PriceProvider, PriceSource};
+pub use internal::keys::wallet_fingerprint;
+pub use internal::restore::{
+	check_repair, import_output, restore, restore_commit, restore_scan, restore_scan_start,
 };
-pub use internal::restore::{check_repair, restore};
+pub use l10n::{catalog_locale, localize, DEFAULT_LOCALE};
+pub use payment_proof::PaymentProof;
+pub use totp::TotpSettings;
 pub use types::{
-	AcctPathMapping, BlockIdentifier, CbData, Context, NodeClient, NodeVersionInfo, OutputData,
-	OutputStatus, TxLogEntry, TxLogEntryType, TxWrapper, WalletBackend, WalletInfo, WalletInst,
-	WalletLCProvider, WalletOutputBatch,
+	amount_to_currency_string, AcctPathMapping, ApprovalToken, ArchivedSlate, BalanceHold,
+	BlockIdentifier, CbData, ChangeEntity, ChangeOp, ChangeRecord, CoinbaseInfo, CoinbaseReport,
+	CoinbaseStatus, Context, DeliveryReceipt, DeliveryStatus, DerivationScheme, FailedReceive,
+	ForeignApiKey, FreezeState, NodeBlockOutputs, NodeClient, NodeClientStats, NodeEndpointStats,
+	NodeHeaderInfo, NodeOutputProof, NodeOutputs, NodePoolEntry, NodeStatus, NodeTxFees,
+	NodeVersionInfo, OnetimeAddress, OutputData, OutputReservation, OutputStatus, PendingDispatch,
+	RefreshReport, SlateDirection, SourceFilter, TxApproval, TxLogEntry, TxLogEntryType, TxWrapper,
+	VaultSettings, WalletBackend, WalletEvent, WalletEventType, WalletInfo, WalletInst,
+	WalletLCProvider, WalletOutputBatch, DEFAULT_CURRENCY, DEFAULT_FINALITY_DEPTH,
+};
+pub use view_only::{
+	ViewOnlyAttestation, ViewOnlyBundle, ViewOnlyOutput, ViewOnlyOutputAudit, WatchOnlyKey,
+	VIEW_ONLY_BUNDLE_VERSION,
 };
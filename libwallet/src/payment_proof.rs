@@ -0,0 +1,230 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Payment proofs, showing a payment was made to the holder of a proof address.
+//! A sender asking for a proof gives its own proof address in the slate, and the
+//! recipient signs the amount, the kernel excess and the sender's address with the
+//! key of its proof address. Along with the sender's signature of the same, this
+//! makes the proof, which holds once its kernel is on chain.
+
+use crate::blake2::blake2b::blake2b;
+use crate::error::{Error, ErrorKind};
+use crate::grin_core::libtx::{aggsig, secp_ser};
+use crate::grin_keychain::{Keychain, SwitchCommitmentType};
+use crate::grin_util::secp::key::{PublicKey, SecretKey};
+use crate::grin_util::secp::pedersen::Commitment;
+use crate::grin_util::secp::{self, Secp256k1, Signature};
+use crate::grin_util::{self, static_secp_instance};
+use crate::slate::{Slate, PAYMENT_PROOF_ATTACHMENT, PAYMENT_PROOF_REQUEST_ATTACHMENT};
+use serde_json;
+
+/// Index of the key of the proof address of a wallet, at depth 1 of its tree,
+/// where no output is derived
+const PAYMENT_PROOF_KEY_INDEX: u32 = u32::max_value() - 1;
+
+/// Proof of a payment of an amount to the holder of a proof address, signed by
+/// both the recipient and the sender
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PaymentProof {
+	/// Amount paid
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount: u64,
+	/// Excess of the kernel of the payment
+	#[serde(
+		serialize_with = "secp_ser::as_hex",
+		deserialize_with = "secp_ser::commitment_from_hex"
+	)]
+	pub excess: Commitment,
+	/// Proof address of the recipient
+	#[serde(with = "secp_ser::pubkey_serde")]
+	pub recipient_address: PublicKey,
+	/// Signature of the recipient
+	#[serde(with = "secp_ser::sig_serde")]
+	pub recipient_sig: Signature,
+	/// Proof address of the sender
+	#[serde(with = "secp_ser::pubkey_serde")]
+	pub sender_address: PublicKey,
+	/// Signature of the sender
+	#[serde(with = "secp_ser::sig_serde")]
+	pub sender_sig: Signature,
+}
+
+/// Signature a recipient adds to a slate asking for a payment proof
+#[derive(Serialize, Deserialize)]
+struct RecipientSignature {
+	#[serde(with = "secp_ser::pubkey_serde")]
+	address: PublicKey,
+	#[serde(with = "secp_ser::sig_serde")]
+	signature: Signature,
+}
+
+impl PaymentProof {
+	/// Proof of the payment of a slate finalized by its sender, if the sender asked
+	/// for one. Fails if the recipient didn't sign the proof, or signed it wrong.
+	pub fn from_slate<K>(keychain: &K, slate: &Slate) -> Result<Option<PaymentProof>, Error>
+	where
+		K: Keychain,
+	{
+		let sender_address = match requested_proof_address(keychain.secp(), slate) {
+			Some(a) => a,
+			None => return Ok(None),
+		};
+		let recipient = slate
+			.participant_data
+			.iter()
+			.filter(|p| p.id == 1)
+			.flat_map(|p| p.attachments.iter())
+			.find(|a| a.kind == PAYMENT_PROOF_ATTACHMENT && !a.encrypted)
+			.ok_or_else(|| {
+				ErrorKind::PaymentProof("The recipient didn't sign a payment proof".to_owned())
+			})?;
+		let recipient: RecipientSignature = serde_json::from_str(&recipient.content)
+			.map_err(|e| ErrorKind::PaymentProof(format!("Invalid recipient signature: {}", e)))?;
+		let excess = match slate.tx.kernels().first() {
+			Some(k) => k.excess.clone(),
+			None => return Err(ErrorKind::PaymentProof("No kernel".to_owned()))?,
+		};
+		let (key, address) = payment_proof_key(keychain)?;
+		if address != sender_address {
+			let msg = "Sender address of another wallet".to_owned();
+			return Err(ErrorKind::PaymentProof(msg))?;
+		}
+		let msg = payment_proof_message(
+			keychain.secp(),
+			slate.amount,
+			&excess.to_pubkey(keychain.secp())?,
+			&sender_address,
+		)?;
+		let sender_sig = aggsig::sign_single(keychain.secp(), &msg, &key, None, Some(&address))?;
+		let proof = PaymentProof {
+			amount: slate.amount,
+			excess,
+			recipient_address: recipient.address,
+			recipient_sig: recipient.signature,
+			sender_address,
+			sender_sig,
+		};
+		proof.verify()?;
+		Ok(Some(proof))
+	}
+
+	/// Check the proof was signed by both its recipient and its sender. Whether its
+	/// kernel is on chain is left to the caller.
+	pub fn verify(&self) -> Result<(), Error> {
+		let secp = static_secp_instance();
+		let secp = secp.lock();
+		let msg = payment_proof_message(
+			&secp,
+			self.amount,
+			&self.excess.to_pubkey(&secp)?,
+			&self.sender_address,
+		)?;
+		let signed = |sig: &Signature, key: &PublicKey| {
+			aggsig::verify_single(&secp, sig, &msg, None, key, Some(key), false)
+		};
+		if !signed(&self.recipient_sig, &self.recipient_address) {
+			let msg = "Invalid recipient signature".to_owned();
+			return Err(ErrorKind::PaymentProof(msg))?;
+		}
+		if !signed(&self.sender_sig, &self.sender_address) {
+			return Err(ErrorKind::PaymentProof(
+				"Invalid sender signature".to_owned(),
+			))?;
+		}
+		Ok(())
+	}
+}
+
+/// Proof address of a wallet, which payment proofs name it by
+pub fn payment_proof_address<K>(keychain: &K) -> Result<PublicKey, Error>
+where
+	K: Keychain,
+{
+	Ok(payment_proof_key(keychain)?.1)
+}
+
+/// Hex of a proof address, as it's given in slates
+pub fn proof_address_to_hex(secp: &Secp256k1, address: &PublicKey) -> String {
+	grin_util::to_hex(address.serialize_vec(secp, true).to_vec())
+}
+
+/// Sign a payment proof for the sender of a slate received, if it asked for one.
+/// The recipient's data must be in the slate already, and the secret key must be
+/// the one the recipient's public blind excess was created from.
+pub fn sign_payment_proof<K>(
+	keychain: &K,
+	slate: &mut Slate,
+	sec_key: &SecretKey,
+	participant_id: usize,
+) -> Result<(), Error>
+where
+	K: Keychain,
+{
+	let sender_address = match requested_proof_address(keychain.secp(), slate) {
+		Some(a) => a,
+		None => return Ok(()),
+	};
+	let (key, address) = payment_proof_key(keychain)?;
+	let excess = slate.pub_blind_sum(keychain.secp())?;
+	let msg = payment_proof_message(keychain.secp(), slate.amount, &excess, &sender_address)?;
+	let signature = aggsig::sign_single(keychain.secp(), &msg, &key, None, Some(&address))?;
+	let content = serde_json::to_string(&RecipientSignature { address, signature })
+		.map_err(|e| ErrorKind::Format(e.to_string()))?;
+	slate.add_attachment(
+		keychain.secp(),
+		sec_key,
+		participant_id,
+		PAYMENT_PROOF_ATTACHMENT,
+		&content,
+		false,
+	)
+}
+
+/// Proof address the sender of a slate gave, if it asked for a payment proof
+fn requested_proof_address(secp: &Secp256k1, slate: &Slate) -> Option<PublicKey> {
+	slate
+		.participant_data
+		.iter()
+		.filter(|p| p.id == 0)
+		.flat_map(|p| p.attachments.iter())
+		.find(|a| a.kind == PAYMENT_PROOF_REQUEST_ATTACHMENT && !a.encrypted)
+		.and_then(|a| grin_util::from_hex(a.content.clone()).ok())
+		.and_then(|a| PublicKey::from_slice(secp, &a).ok())
+}
+
+/// Key of the proof address of a wallet
+fn payment_proof_key<K>(keychain: &K) -> Result<(SecretKey, PublicKey), Error>
+where
+	K: Keychain,
+{
+	let key_id = K::derive_key_id(1, PAYMENT_PROOF_KEY_INDEX, 0, 0, 0);
+	let key = keychain.derive_key(0, &key_id, &SwitchCommitmentType::None)?;
+	let public_key = PublicKey::from_secret_key(keychain.secp(), &key)?;
+	Ok((key, public_key))
+}
+
+/// Message signed in a payment proof, the hash of the amount, the kernel excess
+/// and the sender's address
+fn payment_proof_message(
+	secp: &Secp256k1,
+	amount: u64,
+	excess: &PublicKey,
+	sender_address: &PublicKey,
+) -> Result<secp::Message, Error> {
+	let mut data = amount.to_be_bytes().to_vec();
+	data.extend_from_slice(&excess.serialize_vec(secp, true)[..]);
+	data.extend_from_slice(&sender_address.serialize_vec(secp, true)[..]);
+	let hashed = blake2b(secp::constants::MESSAGE_SIZE, &[], &data);
+	Ok(secp::Message::from_slice(hashed.as_bytes())?)
+}
@@ -17,12 +17,14 @@
 
 use crate::blake2::blake2b::blake2b;
 use crate::error::{Error, ErrorKind};
+use crate::grin_core::consensus;
 use crate::grin_core::core::amount_to_hr_string;
 use crate::grin_core::core::committed::Committed;
 use crate::grin_core::core::transaction::{
 	Input, KernelFeatures, Output, Transaction, TransactionBody, TxKernel, Weighting,
 };
 use crate::grin_core::core::verifier_cache::LruVerifierCache;
+use crate::grin_core::global;
 use crate::grin_core::libtx::{aggsig, build, proof::ProofBuild, secp_ser, tx_fee};
 use crate::grin_core::map_vec;
 use crate::grin_keychain::{BlindSum, BlindingFactor, Keychain};
@@ -31,19 +33,21 @@ use crate::grin_util::secp::Signature;
 use crate::grin_util::{self, secp, RwLock};
 use failure::ResultExt;
 use rand::rngs::mock::StepRng;
-use rand::thread_rng;
+use rand::{thread_rng, Rng};
+use ring::{aead, digest, hkdf, hmac};
 use serde::ser::{Serialize, Serializer};
 use serde_json;
+use std::cmp;
 use std::fmt;
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::slate_versions::v2::{
-	CoinbaseV2, InputV2, OutputV2, ParticipantDataV2, SlateV2, TransactionBodyV2, TransactionV2,
-	TxKernelV2, VersionCompatInfoV2,
+	AttachmentV2, CoinbaseV2, InputV2, OutputV2, ParticipantDataV2, SlateV2, TransactionBodyV2,
+	TransactionV2, TxKernelV2, VersionCompatInfoV2,
 };
 use crate::slate_versions::{CURRENT_SLATE_VERSION, GRIN_BLOCK_HEADER_VERSION};
-use crate::types::CbData;
+use crate::types::{amount_to_currency_string, CbData};
 
 /// Public data for each participant in the slate
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -65,6 +69,9 @@ pub struct ParticipantData {
 	/// Signature, created with private key corresponding to 'public_blind_excess'
 	#[serde(with = "secp_ser::option_sig_serde")]
 	pub message_sig: Option<Signature>,
+	/// Structured memo attachments for other participants
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub attachments: Vec<SlateAttachment>,
 }
 
 impl ParticipantData {
@@ -142,6 +149,175 @@ impl fmt::Display for ParticipantMessageData {
 	}
 }
 
+/// Maximum number of attachments a single participant may add to a slate
+pub const MAX_ATTACHMENTS: usize = 8;
+/// Maximum length of an attachment kind
+pub const MAX_ATTACHMENT_KIND_LEN: usize = 32;
+/// Maximum length in bytes of an attachment's (unencrypted) content
+pub const MAX_ATTACHMENT_LEN: usize = 512;
+/// Kind of the attachment a payer adds to tell the other participant where
+/// funds should be refunded to, see
+/// [`RefundAddress`](../api_impl/types/struct.RefundAddress.html)
+pub const REFUND_ADDRESS_ATTACHMENT: &str = "refund_address";
+/// Kind of the unencrypted attachment a sender adds to ask the recipient to
+/// contribute one of its own inputs along with its output, so that the inputs
+/// of the transaction don't all belong to the sender
+pub const PAYJOIN_ATTACHMENT: &str = "payjoin";
+/// Kind of the unencrypted attachment a sender adds when paying to a one-time
+/// address of the recipient, holding the id of the address, see
+/// [`OnetimeAddress`](../types/struct.OnetimeAddress.html)
+pub const ONETIME_ADDRESS_ATTACHMENT: &str = "onetime_address";
+/// Kind of the unencrypted attachment a sender adds to ask the recipient for a
+/// payment proof, holding the sender's proof address, see
+/// [`PaymentProof`](../payment_proof/struct.PaymentProof.html)
+pub const PAYMENT_PROOF_REQUEST_ATTACHMENT: &str = "payment_proof_request";
+/// Kind of the unencrypted attachment a recipient asked for a payment proof adds,
+/// holding its proof address and its signature of the payment
+pub const PAYMENT_PROOF_ATTACHMENT: &str = "payment_proof";
+
+/// Largest serialized slate accepted from another wallet, in bytes
+pub const MAX_SLATE_SIZE: usize = 4 * 1024 * 1024;
+/// Most participants a slate from another wallet can have
+pub const MAX_SLATE_PARTICIPANTS: usize = 16;
+/// Longest participant message accepted in a slate, in bytes
+pub const MAX_SLATE_MESSAGE_LEN: usize = 1024;
+/// Deepest nesting of arrays and objects accepted in slates and API requests
+pub const MAX_JSON_DEPTH: usize = 32;
+
+/// Check JSON received from another party doesn't nest arrays and objects deeper
+/// than `max_depth`, before it's parsed
+pub fn check_json_depth(json: &[u8], max_depth: usize) -> Result<(), Error> {
+	let mut depth = 0;
+	let mut in_string = false;
+	let mut escaped = false;
+	for b in json {
+		if in_string {
+			match b {
+				_ if escaped => escaped = false,
+				b'\\' => escaped = true,
+				b'"' => in_string = false,
+				_ => {}
+			}
+			continue;
+		}
+		match b {
+			b'"' => in_string = true,
+			b'{' | b'[' => {
+				depth += 1;
+				if depth > max_depth {
+					return Err(ErrorKind::LimitExceeded {
+						limit: "JSON nesting depth".to_owned(),
+						actual: depth as u64,
+						max: max_depth as u64,
+					})?;
+				}
+			}
+			b'}' | b']' => depth = depth.saturating_sub(1),
+			_ => {}
+		}
+	}
+	Ok(())
+}
+
+/// Info the encryption key of attachments is derived with
+const ATTACHMENT_KEY_INFO: &'static [u8] = b"mwc_slate_attachment_key";
+
+/// Structured memo attached by a participant, such as an order id or a
+/// refund address. Encrypted content can only be read by the participant
+/// who added it and the other participant it was encrypted to. Attachments
+/// are signed with the participant's public blind excess, like its message.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SlateAttachment {
+	/// Kind of attachment, e.g. "order_id" or "refund_address"
+	pub kind: String,
+	/// Content, hex encoded ciphertext if encrypted
+	pub content: String,
+	/// Whether the content is encrypted
+	#[serde(default)]
+	pub encrypted: bool,
+	/// Hex encoded nonce, if encrypted
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub nonce: Option<String>,
+	/// Signature of the attachment by the participant who added it
+	#[serde(default, with = "secp_ser::option_sig_serde")]
+	pub sig: Option<Signature>,
+}
+
+impl SlateAttachment {
+	/// Check kind and content are well formed and within size limits
+	pub fn validate(&self) -> Result<(), Error> {
+		if self.kind.is_empty() || self.kind.len() > MAX_ATTACHMENT_KIND_LEN {
+			return Err(ErrorKind::SlateAttachment(format!(
+				"Attachment kind must be between 1 and {} characters",
+				MAX_ATTACHMENT_KIND_LEN
+			)))?;
+		}
+		if !self
+			.kind
+			.chars()
+			.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+		{
+			return Err(ErrorKind::SlateAttachment(format!(
+				"Invalid attachment kind '{}'",
+				self.kind
+			)))?;
+		}
+		if !self.encrypted {
+			if self.nonce.is_some() {
+				return Err(ErrorKind::SlateAttachment(format!(
+					"Unencrypted attachment '{}' has a nonce",
+					self.kind
+				)))?;
+			}
+			if self.content.len() > MAX_ATTACHMENT_LEN {
+				return Err(ErrorKind::SlateAttachment(format!(
+					"Attachment '{}' is longer than {} bytes",
+					self.kind, MAX_ATTACHMENT_LEN
+				)))?;
+			}
+			return Ok(());
+		}
+		let nonce_ok = match &self.nonce {
+			Some(n) => grin_util::from_hex(n.clone())
+				.map(|n| n.len() == 12)
+				.unwrap_or(false),
+			None => false,
+		};
+		if !nonce_ok {
+			return Err(ErrorKind::SlateAttachment(format!(
+				"Encrypted attachment '{}' has an invalid nonce",
+				self.kind
+			)))?;
+		}
+		let tag_len = aead::AES_256_GCM.tag_len();
+		let content_ok = match grin_util::from_hex(self.content.clone()) {
+			Ok(c) => c.len() >= tag_len && c.len() <= MAX_ATTACHMENT_LEN + tag_len,
+			Err(_) => false,
+		};
+		if !content_ok {
+			return Err(ErrorKind::SlateAttachment(format!(
+				"Encrypted attachment '{}' has invalid or oversized content",
+				self.kind
+			)))?;
+		}
+		Ok(())
+	}
+}
+
+/// Attachment data as seen by this wallet (for serialising and storage)
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ParticipantAttachmentData {
+	/// id of the participant who added the attachment
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub id: u64,
+	/// Kind of attachment
+	pub kind: String,
+	/// Content, None if encrypted and this wallet can't decrypt it
+	pub content: Option<String>,
+	/// Whether the attachment was encrypted in the slate
+	pub encrypted: bool,
+}
+
 /// A 'Slate' is passed around to all parties to build up all of the public
 /// transaction data needed to create a finalized transaction. Callers can pass
 /// the slate around by whatever means they choose, (but we can provide some
@@ -194,6 +370,103 @@ pub struct ParticipantMessages {
 	pub messages: Vec<ParticipantMessageData>,
 }
 
+/// Human readable breakdown of a slate, of what signing it commits to
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SlateDescription {
+	/// Unique transaction ID
+	pub id: Uuid,
+	/// Slate version
+	pub version: u16,
+	/// Amount sent, excluding the fee
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount: u64,
+	/// Amount sent, with its currency, e.g. `1.5 MWC`
+	pub amount_display: String,
+	/// Fee
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub fee: u64,
+	/// Fee, with its currency
+	pub fee_display: String,
+	/// Block height the transaction was built at
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub height: u64,
+	/// Height before which the transaction can't be mined, 0 if none
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub lock_height: u64,
+	/// Number of inputs in the transaction so far
+	pub num_inputs: usize,
+	/// Number of outputs in the transaction so far
+	pub num_outputs: usize,
+	/// Number of participants intended to take part in the transaction
+	pub num_participants: usize,
+	/// Whether all participants added their public data (round 1)
+	pub round1_complete: bool,
+	/// Whether all participants added their partial signature (round 2)
+	pub round2_complete: bool,
+	/// Whether the transaction was finalized, its kernel signed
+	pub finalized: bool,
+	/// Participants who took part so far
+	pub participants: Vec<ParticipantDescription>,
+}
+
+/// Human readable breakdown of a participant's data in a slate
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ParticipantDescription {
+	/// Id of participant in the transaction. (For now, 0=sender, 1=rec)
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub id: u64,
+	/// Role of the participant, "Sender" or "Recipient"
+	pub role: String,
+	/// Whether the participant added their partial signature
+	pub signed: bool,
+	/// Message for other participants
+	pub message: Option<String>,
+	/// Whether the message signature is valid, None if there's no message
+	pub message_sig_valid: Option<bool>,
+	/// Kinds of the attachments added by the participant
+	pub attachment_kinds: Vec<String>,
+}
+
+impl fmt::Display for SlateDescription {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		writeln!(f, "Slate {} (version {})", self.id, self.version)?;
+		writeln!(f, "---------------------")?;
+		writeln!(f, "Amount: {}", self.amount_display)?;
+		writeln!(f, "Fee: {}", self.fee_display)?;
+		writeln!(f, "Height: {}", self.height)?;
+		writeln!(f, "Lock Height: {}", self.lock_height)?;
+		writeln!(
+			f,
+			"Inputs: {}, Outputs: {}",
+			self.num_inputs, self.num_outputs
+		)?;
+		writeln!(
+			f,
+			"Participants: {} of {}",
+			self.participants.len(),
+			self.num_participants
+		)?;
+		writeln!(f, "Round 1 Complete: {}", self.round1_complete)?;
+		writeln!(f, "Round 2 Complete: {}", self.round2_complete)?;
+		writeln!(f, "Finalized: {}", self.finalized)?;
+		for p in self.participants.iter() {
+			writeln!(f, "")?;
+			writeln!(f, "Participant ID {} ({})", p.id, p.role)?;
+			writeln!(f, "Signed: {}", p.signed)?;
+			let message = match (&p.message, p.message_sig_valid) {
+				(None, _) => "None".to_owned(),
+				(Some(m), Some(true)) => m.clone(),
+				(Some(m), _) => format!("{} (INVALID SIGNATURE)", m),
+			};
+			writeln!(f, "Message: {}", message)?;
+			if !p.attachment_kinds.is_empty() {
+				writeln!(f, "Attachments: {}", p.attachment_kinds.join(", "))?;
+			}
+		}
+		Ok(())
+	}
+}
+
 impl Slate {
 	/// Attempt to find slate version
 	pub fn parse_slate_version(slate_json: &str) -> Result<u16, Error> {
@@ -202,8 +475,17 @@ impl Slate {
 		Ok(probe.version())
 	}
 
-	/// Recieve a slate, upgrade it to the latest version internally
+	/// Recieve a slate, upgrade it to the latest version internally. Slates beyond the
+	/// limits accepted from other wallets are refused.
 	pub fn deserialize_upgrade(slate_json: &str) -> Result<Slate, Error> {
+		if slate_json.len() > MAX_SLATE_SIZE {
+			return Err(ErrorKind::LimitExceeded {
+				limit: "Slate size".to_owned(),
+				actual: slate_json.len() as u64,
+				max: MAX_SLATE_SIZE as u64,
+			})?;
+		}
+		check_json_depth(slate_json.as_bytes(), MAX_JSON_DEPTH)?;
 		let version = Slate::parse_slate_version(slate_json)?;
 		let v2: SlateV2 = match version {
 			2 => serde_json::from_str(slate_json).context(ErrorKind::SlateDeser)?,
@@ -216,7 +498,66 @@ impl Slate {
 			}*/
 			_ => return Err(ErrorKind::SlateVersion(version).into()),
 		};
-		Ok(v2.into())
+		let slate: Slate = v2.into();
+		slate.check_limits()?;
+		Ok(slate)
+	}
+
+	/// Check the slate is within the limits accepted from other wallets: its number
+	/// of participants, the weight of its transaction, which can't be more than a
+	/// block's, and the length of the participant messages
+	pub fn check_limits(&self) -> Result<(), Error> {
+		let participants = cmp::max(self.num_participants, self.participant_data.len());
+		if participants > MAX_SLATE_PARTICIPANTS {
+			return Err(ErrorKind::LimitExceeded {
+				limit: "Number of slate participants".to_owned(),
+				actual: participants as u64,
+				max: MAX_SLATE_PARTICIPANTS as u64,
+			})?;
+		}
+		let body = &self.tx.body;
+		let weight = body.inputs.len() * consensus::BLOCK_INPUT_WEIGHT
+			+ body.outputs.len() * consensus::BLOCK_OUTPUT_WEIGHT
+			+ body.kernels.len() * consensus::BLOCK_KERNEL_WEIGHT;
+		if weight > global::max_block_weight() {
+			return Err(ErrorKind::LimitExceeded {
+				limit: "Slate transaction weight".to_owned(),
+				actual: weight as u64,
+				max: global::max_block_weight() as u64,
+			})?;
+		}
+		for p in self.participant_data.iter() {
+			let len = p.message.as_ref().map(|m| m.len()).unwrap_or(0);
+			if len > MAX_SLATE_MESSAGE_LEN {
+				return Err(ErrorKind::LimitExceeded {
+					limit: format!("Length of the message of participant {}", p.id),
+					actual: len as u64,
+					max: MAX_SLATE_MESSAGE_LEN as u64,
+				})?;
+			}
+		}
+		Ok(())
+	}
+
+	/// Set the version the slate is serialized as, for a counterparty running an
+	/// older (or newer) wallet. Fails if the slate carries data that would be lost
+	/// in that version.
+	pub fn set_serialization_version(&mut self, version: u16) -> Result<(), Error> {
+		match version {
+			// V2 carries every field of the current slate
+			2 => {}
+			// left as a reminder, a version without attachments would need
+			/*1 => {
+				if self.participant_data.iter().any(|p| !p.attachments.is_empty()) {
+					return Err(ErrorKind::Compatibility(
+						"V1 slates can't carry attachments".to_owned(),
+					))?;
+				}
+			}*/
+			v => return Err(ErrorKind::SlateVersion(v))?,
+		}
+		self.version_info.orig_version = version;
+		Ok(())
 	}
 
 	/// Create a new slate
@@ -344,6 +685,25 @@ impl Slate {
 		self.finalize_transaction(keychain, &final_sig)
 	}
 
+	/// Whether the sender asked the recipient to contribute an input
+	pub fn payjoin_requested(&self) -> bool {
+		self.participant_data
+			.iter()
+			.filter(|p| p.id == 0)
+			.flat_map(|p| p.attachments.iter())
+			.any(|a| a.kind == PAYJOIN_ATTACHMENT && !a.encrypted)
+	}
+
+	/// Id of the one-time address of the recipient the sender is paying to, if any
+	pub fn onetime_address_id(&self) -> Option<Uuid> {
+		self.participant_data
+			.iter()
+			.filter(|p| p.id == 0)
+			.flat_map(|p| p.attachments.iter())
+			.find(|a| a.kind == ONETIME_ADDRESS_ATTACHMENT && !a.encrypted)
+			.and_then(|a| Uuid::parse_str(&a.content).ok())
+	}
+
 	/// Return the participant with the given id
 	pub fn participant_with_id(&self, id: usize) -> Option<ParticipantData> {
 		for p in self.participant_data.iter() {
@@ -368,7 +728,7 @@ impl Slate {
 	}
 
 	/// Return the sum of public blinding factors
-	fn pub_blind_sum(&self, secp: &secp::Secp256k1) -> Result<PublicKey, Error> {
+	pub(crate) fn pub_blind_sum(&self, secp: &secp::Secp256k1) -> Result<PublicKey, Error> {
 		let pub_blinds = self
 			.participant_data
 			.iter()
@@ -439,10 +799,239 @@ impl Slate {
 			part_sig: part_sig,
 			message: message,
 			message_sig: message_sig,
+			attachments: vec![],
 		});
 		Ok(())
 	}
 
+	/// Shared attachment encryption key between our secret key and another
+	/// participant's public blind excess, derived from their ECDH secret with
+	/// HKDF-SHA256, salted with the slate id
+	fn attachment_key(
+		&self,
+		secp: &secp::Secp256k1,
+		sec_key: &SecretKey,
+		other: &PublicKey,
+	) -> Result<[u8; 32], Error> {
+		let mut shared_pubkey = other.clone();
+		shared_pubkey.mul_assign(secp, sec_key)?;
+		let x_coord = shared_pubkey.serialize_vec(secp, true);
+		let salt = hmac::SigningKey::new(&digest::SHA256, self.id.as_bytes());
+		let mut key = [0u8; 32];
+		hkdf::extract_and_expand(&salt, &x_coord[1..], ATTACHMENT_KEY_INFO, &mut key);
+		Ok(key)
+	}
+
+	/// Message an attachment's signature is over, binding it to the slate
+	fn attachment_msg(&self, attachment: &SlateAttachment) -> Result<secp::Message, Error> {
+		let mut data = self.id.as_bytes().to_vec();
+		data.extend_from_slice(attachment.kind.as_bytes());
+		data.push(attachment.encrypted as u8);
+		if let Some(n) = &attachment.nonce {
+			data.extend_from_slice(n.as_bytes());
+		}
+		data.push(0);
+		data.extend_from_slice(attachment.content.as_bytes());
+		let hashed = blake2b(secp::constants::MESSAGE_SIZE, &[], &data);
+		Ok(secp::Message::from_slice(hashed.as_bytes())?)
+	}
+
+	/// Public blind excess of the first participant other than the given one,
+	/// which attachments from `id` are encrypted to
+	fn attachment_counterparty(&self, id: u64) -> Option<PublicKey> {
+		self.participant_data
+			.iter()
+			.find(|p| p.id != id)
+			.map(|p| p.public_blind_excess)
+	}
+
+	/// Additional data authenticated along with encrypted attachments
+	fn attachment_ad(&self, kind: &str) -> Vec<u8> {
+		let mut ad = self.id.as_bytes().to_vec();
+		ad.extend_from_slice(kind.as_bytes());
+		ad
+	}
+
+	/// Add an attachment to our participant data. The secret key must be
+	/// the one our public blind excess was created from. Encrypted
+	/// attachments can only be added once the other participant has added
+	/// their data to the slate.
+	pub fn add_attachment(
+		&mut self,
+		secp: &secp::Secp256k1,
+		sec_key: &SecretKey,
+		participant_id: usize,
+		kind: &str,
+		content: &str,
+		encrypt: bool,
+	) -> Result<(), Error> {
+		let id = participant_id as u64;
+		let pub_key = PublicKey::from_secret_key(secp, sec_key)?;
+		match self.participant_with_id(participant_id) {
+			Some(p) if p.public_blind_excess == pub_key => {}
+			_ => {
+				return Err(ErrorKind::SlateAttachment(format!(
+					"No participant data for id {} with the given key",
+					id
+				)))?;
+			}
+		}
+		let mut attachment = SlateAttachment {
+			kind: kind.to_owned(),
+			content: content.to_owned(),
+			encrypted: false,
+			nonce: None,
+			sig: None,
+		};
+		attachment.validate()?;
+		if encrypt {
+			let other = self.attachment_counterparty(id).ok_or_else(|| {
+				ErrorKind::SlateAttachment(format!(
+					"Can't encrypt attachment '{}' before the other participant has joined",
+					kind
+				))
+			})?;
+			let key = self.attachment_key(secp, sec_key, &other)?;
+			let sealing_key = aead::SealingKey::new(&aead::AES_256_GCM, &key).map_err(|_| {
+				ErrorKind::SlateAttachment("Unable to create encryption key".to_owned())
+			})?;
+			let nonce: [u8; 12] = thread_rng().gen();
+			let suffix_len = aead::AES_256_GCM.tag_len();
+			let mut to_encrypt = content.as_bytes().to_vec();
+			for _ in 0..suffix_len {
+				to_encrypt.push(0);
+			}
+			let ad = self.attachment_ad(kind);
+			aead::seal_in_place(&sealing_key, &nonce, &ad, &mut to_encrypt, suffix_len)
+				.map_err(|_| ErrorKind::SlateAttachment("Encryption failed".to_owned()))?;
+			attachment.content = grin_util::to_hex(to_encrypt);
+			attachment.encrypted = true;
+			attachment.nonce = Some(grin_util::to_hex(nonce.to_vec()));
+		}
+		let msg = self.attachment_msg(&attachment)?;
+		attachment.sig = Some(aggsig::sign_single(
+			secp,
+			&msg,
+			sec_key,
+			None,
+			Some(&pub_key),
+		)?);
+		let p = self
+			.participant_data
+			.iter_mut()
+			.find(|p| p.id == id)
+			.ok_or_else(|| {
+				ErrorKind::SlateAttachment(format!("No participant data for id {}", id))
+			})?;
+		if p.attachments.len() >= MAX_ATTACHMENTS {
+			return Err(ErrorKind::SlateAttachment(format!(
+				"A participant can't add more than {} attachments",
+				MAX_ATTACHMENTS
+			)))?;
+		}
+		p.attachments.push(attachment);
+		Ok(())
+	}
+
+	/// Verifies all attachments in the slate are well formed, within limits and
+	/// signed by the participant who added them
+	pub fn verify_attachments(&self) -> Result<(), Error> {
+		let secp = secp::Secp256k1::with_caps(secp::ContextFlag::VerifyOnly);
+		for p in self.participant_data.iter() {
+			if p.attachments.len() > MAX_ATTACHMENTS {
+				return Err(ErrorKind::SlateAttachment(format!(
+					"Participant {} has more than {} attachments",
+					p.id, MAX_ATTACHMENTS
+				)))?;
+			}
+			for a in p.attachments.iter() {
+				a.validate()?;
+				let msg = self.attachment_msg(a)?;
+				let valid = match a.sig {
+					Some(sig) => aggsig::verify_single(
+						&secp,
+						&sig,
+						&msg,
+						None,
+						&p.public_blind_excess,
+						Some(&p.public_blind_excess),
+						false,
+					),
+					None => false,
+				};
+				if !valid {
+					return Err(ErrorKind::SlateAttachment(format!(
+						"Attachment '{}' of participant {} isn't signed by it",
+						a.kind, p.id
+					)))?;
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Return all attachments in the slate, decrypting those encrypted between
+	/// us (as `participant_id`) and another participant where a secret key
+	/// is given. Content we can't decrypt is returned as None.
+	pub fn attachments(
+		&self,
+		secp: &secp::Secp256k1,
+		sec_key: Option<&SecretKey>,
+		participant_id: usize,
+	) -> Vec<ParticipantAttachmentData> {
+		let id = participant_id as u64;
+		let mut ret = vec![];
+		for p in self.participant_data.iter() {
+			// Our own attachments were encrypted to the other participant
+			let other = match p.id == id {
+				true => self.attachment_counterparty(id),
+				false => Some(p.public_blind_excess),
+			};
+			for a in p.attachments.iter() {
+				let content = match a.encrypted {
+					false => Some(a.content.clone()),
+					true => match (sec_key, other) {
+						(Some(k), Some(o)) => self.decrypt_attachment(secp, k, &o, a).ok(),
+						_ => None,
+					},
+				};
+				ret.push(ParticipantAttachmentData {
+					id: p.id,
+					kind: a.kind.clone(),
+					content,
+					encrypted: a.encrypted,
+				});
+			}
+		}
+		ret
+	}
+
+	fn decrypt_attachment(
+		&self,
+		secp: &secp::Secp256k1,
+		sec_key: &SecretKey,
+		other: &PublicKey,
+		attachment: &SlateAttachment,
+	) -> Result<String, Error> {
+		let key = self.attachment_key(secp, sec_key, other)?;
+		let opening_key = aead::OpeningKey::new(&aead::AES_256_GCM, &key).map_err(|_| {
+			ErrorKind::SlateAttachment("Unable to create decryption key".to_owned())
+		})?;
+		let nonce = grin_util::from_hex(attachment.nonce.clone().unwrap_or_default())
+			.map_err(|_| ErrorKind::SlateAttachment("Invalid nonce".to_owned()))?;
+		let mut to_decrypt = grin_util::from_hex(attachment.content.clone())
+			.map_err(|_| ErrorKind::SlateAttachment("Invalid content".to_owned()))?;
+		let ad = self.attachment_ad(&attachment.kind);
+		aead::open_in_place(&opening_key, &nonce, &ad, 0, &mut to_decrypt)
+			.map_err(|_| ErrorKind::SlateAttachment("Decryption failed".to_owned()))?;
+		for _ in 0..aead::AES_256_GCM.tag_len() {
+			to_decrypt.pop();
+		}
+		let content = String::from_utf8(to_decrypt)
+			.map_err(|_| ErrorKind::SlateAttachment("Invalid UTF-8".to_owned()))?;
+		Ok(content)
+	}
+
 	/// helper to return all participant messages
 	pub fn participant_messages(&self) -> ParticipantMessages {
 		let mut ret = ParticipantMessages { messages: vec![] };
@@ -453,6 +1042,70 @@ impl Slate {
 		ret
 	}
 
+	/// Human readable breakdown of the slate: amounts, labelled with the ticker
+	/// of `currency`, heights, participants, which rounds are complete and the
+	/// embedded messages, to show users what they're about to sign
+	pub fn inspect(&self, currency: &str) -> SlateDescription {
+		let secp = secp::Secp256k1::with_caps(secp::ContextFlag::VerifyOnly);
+		let participants = self
+			.participant_data
+			.iter()
+			.map(|p| {
+				let message_sig_valid = p.message.as_ref().map(|msg| {
+					let hashed = blake2b(secp::constants::MESSAGE_SIZE, &[], msg.as_bytes());
+					match (secp::Message::from_slice(hashed.as_bytes()), p.message_sig) {
+						(Ok(m), Some(sig)) => aggsig::verify_single(
+							&secp,
+							&sig,
+							&m,
+							None,
+							&p.public_blind_excess,
+							Some(&p.public_blind_excess),
+							false,
+						),
+						_ => false,
+					}
+				});
+				ParticipantDescription {
+					id: p.id,
+					role: match p.id {
+						0 => "Sender".to_owned(),
+						_ => "Recipient".to_owned(),
+					},
+					signed: p.is_complete(),
+					message: p.message.clone(),
+					message_sig_valid,
+					attachment_kinds: p.attachments.iter().map(|a| a.kind.clone()).collect(),
+				}
+			})
+			.collect();
+		let round1_complete = self.participant_data.len() >= self.num_participants;
+		SlateDescription {
+			id: self.id,
+			version: self.version_info.orig_version,
+			amount: self.amount,
+			amount_display: amount_to_currency_string(self.amount, false, currency),
+			fee: self.fee,
+			fee_display: amount_to_currency_string(self.fee, false, currency),
+			height: self.height,
+			lock_height: self.lock_height,
+			num_inputs: self.tx.body.inputs.len(),
+			num_outputs: self.tx.body.outputs.len(),
+			num_participants: self.num_participants,
+			round1_complete,
+			round2_complete: round1_complete
+				&& self.participant_data.iter().all(|p| p.is_complete()),
+			finalized: !self.tx.body.kernels.is_empty()
+				&& self
+					.tx
+					.body
+					.kernels
+					.iter()
+					.all(|k| k.excess.0.iter().any(|b| *b != 0)),
+			participants,
+		}
+	}
+
 	/// Somebody involved needs to generate an offset with their private key
 	/// For now, we'll have the transaction initiator be responsible for it
 	/// Return offset private key for the participant to use later in the
@@ -801,6 +1454,7 @@ impl From<&ParticipantData> for ParticipantDataV2 {
 			part_sig,
 			message,
 			message_sig,
+			attachments,
 		} = data;
 		let id = *id;
 		let public_blind_excess = *public_blind_excess;
@@ -808,6 +1462,7 @@ impl From<&ParticipantData> for ParticipantDataV2 {
 		let part_sig = *part_sig;
 		let message: Option<String> = message.as_ref().map(|t| String::from(&**t));
 		let message_sig = *message_sig;
+		let attachments = map_vec!(attachments, |a| AttachmentV2::from(a));
 		ParticipantDataV2 {
 			id,
 			public_blind_excess,
@@ -815,6 +1470,26 @@ impl From<&ParticipantData> for ParticipantDataV2 {
 			part_sig,
 			message,
 			message_sig,
+			attachments,
+		}
+	}
+}
+
+impl From<&SlateAttachment> for AttachmentV2 {
+	fn from(a: &SlateAttachment) -> AttachmentV2 {
+		let SlateAttachment {
+			kind,
+			content,
+			encrypted,
+			nonce,
+			sig,
+		} = a;
+		AttachmentV2 {
+			kind: kind.clone(),
+			content: content.clone(),
+			encrypted: *encrypted,
+			nonce: nonce.clone(),
+			sig: *sig,
 		}
 	}
 }
@@ -954,6 +1629,7 @@ impl From<&ParticipantDataV2> for ParticipantData {
 			part_sig,
 			message,
 			message_sig,
+			attachments,
 		} = data;
 		let id = *id;
 		let public_blind_excess = *public_blind_excess;
@@ -961,6 +1637,7 @@ impl From<&ParticipantDataV2> for ParticipantData {
 		let part_sig = *part_sig;
 		let message: Option<String> = message.as_ref().map(|t| String::from(&**t));
 		let message_sig = *message_sig;
+		let attachments = map_vec!(attachments, |a| SlateAttachment::from(a));
 		ParticipantData {
 			id,
 			public_blind_excess,
@@ -968,6 +1645,26 @@ impl From<&ParticipantDataV2> for ParticipantData {
 			part_sig,
 			message,
 			message_sig,
+			attachments,
+		}
+	}
+}
+
+impl From<&AttachmentV2> for SlateAttachment {
+	fn from(a: &AttachmentV2) -> SlateAttachment {
+		let AttachmentV2 {
+			kind,
+			content,
+			encrypted,
+			nonce,
+			sig,
+		} = a;
+		SlateAttachment {
+			kind: kind.clone(),
+			content: content.clone(),
+			encrypted: *encrypted,
+			nonce: nonce.clone(),
+			sig: *sig,
 		}
 	}
 }
@@ -103,6 +103,27 @@ pub struct ParticipantDataV2 {
 	/// Signature, created with private key corresponding to 'public_blind_excess'
 	#[serde(with = "secp_ser::option_sig_serde")]
 	pub message_sig: Option<Signature>,
+	/// Structured memo attachments for other participants
+	#[serde(default, skip_serializing_if = "Vec::is_empty")]
+	pub attachments: Vec<AttachmentV2>,
+}
+
+/// A structured memo attachment
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AttachmentV2 {
+	/// Kind of attachment
+	pub kind: String,
+	/// Content, hex encoded ciphertext if encrypted
+	pub content: String,
+	/// Whether the content is encrypted
+	#[serde(default)]
+	pub encrypted: bool,
+	/// Hex encoded nonce, if encrypted
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub nonce: Option<String>,
+	/// Signature of the attachment by the participant who added it
+	#[serde(default, with = "secp_ser::option_sig_serde")]
+	pub sig: Option<Signature>,
 }
 
 /// A transaction
@@ -0,0 +1,205 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deterministic test vectors, for other wallet implementations to check
+//! they read and write slates the way this wallet does. For every supported
+//! slate version, a transaction is built between two wallets of fixed seeds,
+//! and the slate is serialized at each step of the exchange: as sent, as
+//! returned by the recipient and once finalized. The signed participant
+//! messages the slates carry act as proofs of their senders' keys.
+//!
+//! Payment proofs and armored slate encodings aren't supported by this wallet
+//! yet, so there are no vectors for them.
+
+use crate::error::{Error, ErrorKind};
+use crate::grin_core::libtx::{build, proof::ProofBuilder, tx_fee};
+use crate::grin_keychain::{ExtKeychain, Keychain};
+use crate::grin_util::secp::key::SecretKey;
+use crate::slate::Slate;
+use crate::slate_versions::SlateVersion;
+use serde_json;
+use strum::IntoEnumIterator;
+use uuid::Uuid;
+
+/// Id of the slates of the test vectors
+pub const TEST_VECTOR_SLATE_ID: &str = "0436430c-2b02-624c-2032-570501212b00";
+
+/// Amount sent in the transaction of the test vectors
+pub const TEST_VECTOR_AMOUNT: u64 = 2_000_000_000;
+
+/// Value of the input spent by the sender
+const INPUT_VALUE: u64 = 60_000_000_000;
+
+/// Step of the slate exchange a test vector was taken at
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum SlateStage {
+	/// Initiated by the sender, with its inputs, change and public keys
+	Sent,
+	/// Returned by the recipient, with its output and partial signature
+	Received,
+	/// Finalized by the sender, with the complete transaction
+	Finalized,
+}
+
+/// A canonical slate, as this wallet serializes it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SlateTestVector {
+	/// Version the slate is serialized as
+	pub version: u16,
+	/// Step of the exchange the slate was taken at
+	pub stage: SlateStage,
+	/// Serialized slate
+	pub slate: String,
+}
+
+/// Keychain of a test wallet, from a fixed seed
+fn test_keychain(seed: u8) -> Result<ExtKeychain, Error> {
+	Ok(ExtKeychain::from_seed(&[seed; 32], false)?)
+}
+
+/// Fixed secret key, used as signature nonce
+fn test_nonce(keychain: &ExtKeychain, byte: u8) -> Result<SecretKey, Error> {
+	Ok(SecretKey::from_slice(keychain.secp(), &[byte; 32])?)
+}
+
+/// Serialize a slate as the given version
+fn serialize_as(slate: &Slate, version: u16) -> Result<String, Error> {
+	let mut slate = slate.clone();
+	slate.set_serialization_version(version)?;
+	Ok(serde_json::to_string(&slate).map_err(|_| ErrorKind::SlateSer)?)
+}
+
+/// The slates of a transaction between two test wallets at each step of
+/// the exchange
+fn test_slates() -> Result<Vec<(SlateStage, Slate)>, Error> {
+	let sender = test_keychain(1)?;
+	let recipient = test_keychain(2)?;
+	let sender_nonce = test_nonce(&sender, 3)?;
+	let recipient_nonce = test_nonce(&recipient, 4)?;
+	let key_id = ExtKeychain::derive_key_id(3, 0, 0, 0, 0);
+	let change_key_id = ExtKeychain::derive_key_id(3, 0, 0, 1, 0);
+	let fee = tx_fee(1, 2, 1, None);
+
+	let mut slate = Slate::blank(2);
+	slate.id = Uuid::parse_str(TEST_VECTOR_SLATE_ID).map_err(|_| ErrorKind::SlateDeser)?;
+	slate.amount = TEST_VECTOR_AMOUNT;
+	slate.fee = fee;
+	slate.height = 100;
+	let mut slates = vec![];
+
+	// Sender adds its input and change, and its public keys
+	let elems = vec![
+		build::input(INPUT_VALUE, key_id.clone()),
+		build::output(INPUT_VALUE - TEST_VECTOR_AMOUNT - fee, change_key_id),
+		build::with_fee(fee),
+	];
+	let blind = slate.add_transaction_elements(&sender, &ProofBuilder::new(&sender), elems)?;
+	let mut sender_key = blind.secret_key(sender.secp())?;
+	let message = Some("Test vector payment".to_owned());
+	slate.fill_round_1(&sender, &mut sender_key, &sender_nonce, 0, message, true)?;
+	slates.push((SlateStage::Sent, slate.clone()));
+
+	// Recipient adds its output, public keys and partial signature
+	let elems = vec![build::output(TEST_VECTOR_AMOUNT, key_id)];
+	let builder = ProofBuilder::new(&recipient);
+	let blind = slate.add_transaction_elements(&recipient, &builder, elems)?;
+	let mut recipient_key = blind.secret_key(recipient.secp())?;
+	let message = Some("Test vector receipt".to_owned());
+	slate.fill_round_1(
+		&recipient,
+		&mut recipient_key,
+		&recipient_nonce,
+		1,
+		message,
+		true,
+	)?;
+	slate.fill_round_2(&recipient, &recipient_key, &recipient_nonce, 1)?;
+	slates.push((SlateStage::Received, slate.clone()));
+
+	// Sender signs and finalizes the transaction
+	slate.fill_round_2(&sender, &sender_key, &sender_nonce, 0)?;
+	slate.finalize(&sender)?;
+	slates.push((SlateStage::Finalized, slate));
+	Ok(slates)
+}
+
+/// Version number of a slate version
+fn version_number(version: &SlateVersion) -> u16 {
+	match version {
+		SlateVersion::V2 => 2,
+	}
+}
+
+/// Test vectors of the slates, for every supported slate version and step of
+/// the exchange. The same vectors are generated on every call.
+pub fn slate_test_vectors() -> Result<Vec<SlateTestVector>, Error> {
+	let slates = test_slates()?;
+	let mut vectors = vec![];
+	for version in SlateVersion::iter() {
+		let version = version_number(&version);
+		for (stage, slate) in slates.iter() {
+			vectors.push(SlateTestVector {
+				version,
+				stage: *stage,
+				slate: serialize_as(slate, version)?,
+			});
+		}
+	}
+	Ok(vectors)
+}
+
+/// Check a test vector produced by another implementation: the slate has to
+/// be read by this wallet, carry valid message signatures and, written back
+/// in the same version, be identical to the canonical vector of this wallet.
+pub fn verify_slate_test_vector(vector: &SlateTestVector) -> Result<(), Error> {
+	let version = Slate::parse_slate_version(&vector.slate)?;
+	if version != vector.version {
+		return Err(ErrorKind::SlateVersion(version))?;
+	}
+	let slate = Slate::deserialize_upgrade(&vector.slate)?;
+	slate.verify_messages()?;
+	if serialize_as(&slate, version)? != vector.slate {
+		let msg = format!("{:?} slate isn't written back as read", vector.stage);
+		return Err(ErrorKind::GenericError(msg))?;
+	}
+	let expected = slate_test_vectors()?
+		.into_iter()
+		.find(|v| v.version == vector.version && v.stage == vector.stage)
+		.ok_or(ErrorKind::SlateVersion(version))?;
+	if expected.slate != vector.slate {
+		let msg = format!("{:?} slate differs from the canonical one", vector.stage);
+		return Err(ErrorKind::GenericError(msg))?;
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn slate_test_vectors_verify() {
+		let vectors = slate_test_vectors().unwrap();
+		assert_eq!(vectors.len(), 3);
+		assert_eq!(vectors, slate_test_vectors().unwrap());
+		for v in vectors.iter() {
+			verify_slate_test_vector(v).unwrap();
+		}
+		let mut altered = vectors[0].clone();
+		altered.slate = altered
+			.slate
+			.replace("Test vector payment", "Altered payment");
+		assert!(verify_slate_test_vector(&altered).is_err());
+	}
+}
@@ -0,0 +1,209 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Time-based one-time passwords (RFC 6238), the second factor the owner API can
+//! require of spends and changes of the security settings of the wallet. Codes are the
+//! 6 digit HMAC-SHA1 codes of 30 second time steps computed by authenticator apps.
+//! Recovery codes, each usable once, stand in for a code when the authenticator
+//! is lost.
+
+use crate::blake2::blake2b::blake2b;
+use crate::error::{Error, ErrorKind};
+use crate::grin_core::ser;
+use crate::grin_util;
+use rand::{thread_rng, Rng};
+use ring::{digest, hmac};
+use serde_json;
+
+/// Seconds a code is valid for
+pub const TOTP_STEP_SECS: u64 = 30;
+/// Number of digits of a code
+pub const TOTP_DIGITS: u32 = 6;
+/// Number of recovery codes given out when setting up a secret
+pub const TOTP_RECOVERY_CODES: usize = 10;
+/// Steps before and after the current one whose codes are accepted, for the
+/// clock of the authenticator to drift
+const TOTP_SKEW_STEPS: u64 = 1;
+
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Secret of the second factor of a wallet, with its unused recovery codes
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TotpSettings {
+	/// Shared secret the codes are computed from
+	pub secret: Vec<u8>,
+	/// Whether a code was entered since the secret was set up. Codes are only
+	/// required once the secret is confirmed.
+	pub confirmed: bool,
+	/// Time step of the last code accepted, older codes can't be replayed
+	pub last_step: u64,
+	/// Hashes of the recovery codes not used yet
+	pub recovery_code_hashes: Vec<String>,
+}
+
+impl TotpSettings {
+	/// New random secret, unconfirmed, along with its recovery codes
+	pub fn new() -> (TotpSettings, Vec<String>) {
+		let secret: [u8; 20] = thread_rng().gen();
+		let recovery_codes: Vec<String> = (0..TOTP_RECOVERY_CODES)
+			.map(|_| {
+				let code = base32_encode(&thread_rng().gen::<[u8; 5]>()).to_lowercase();
+				format!("{}-{}", &code[..4], &code[4..])
+			})
+			.collect();
+		let settings = TotpSettings {
+			secret: secret.to_vec(),
+			confirmed: false,
+			last_step: 0,
+			recovery_code_hashes: recovery_codes
+				.iter()
+				.map(|c| recovery_code_hash(c))
+				.collect(),
+		};
+		(settings, recovery_codes)
+	}
+
+	/// Secret in base32, as authenticator apps take it
+	pub fn secret_base32(&self) -> String {
+		base32_encode(&self.secret)
+	}
+
+	/// `otpauth` uri of the secret, for authenticator apps to scan
+	pub fn uri(&self, label: &str) -> String {
+		format!(
+			"otpauth://totp/{}?secret={}&issuer=mwc-wallet&digits={}&period={}",
+			label,
+			self.secret_base32(),
+			TOTP_DIGITS,
+			TOTP_STEP_SECS
+		)
+	}
+
+	/// Check a code at the given time, in seconds since the epoch, accepting a
+	/// recovery code too if asked to. A code is accepted once, a recovery code is
+	/// removed once used. The caller is responsible for saving the settings.
+	pub fn verify(&mut self, code: &str, time: u64, allow_recovery: bool) -> Result<(), Error> {
+		let step = time / TOTP_STEP_SECS;
+		let first = step.saturating_sub(TOTP_SKEW_STEPS).max(self.last_step + 1);
+		if let Ok(c) = code.trim().parse::<u32>() {
+			if let Some(s) =
+				(first..=step + TOTP_SKEW_STEPS).find(|s| totp_code(&self.secret, *s) == c)
+			{
+				self.last_step = s;
+				return Ok(());
+			}
+		}
+		if allow_recovery {
+			let hash = recovery_code_hash(code);
+			if let Some(i) = self.recovery_code_hashes.iter().position(|h| *h == hash) {
+				self.recovery_code_hashes.remove(i);
+				warn!(
+					"Recovery code used, {} left",
+					self.recovery_code_hashes.len()
+				);
+				return Ok(());
+			}
+		}
+		Err(ErrorKind::Totp("Invalid or already used code".to_owned()))?
+	}
+}
+
+impl ser::Writeable for TotpSettings {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for TotpSettings {
+	fn read(reader: &mut dyn ser::Reader) -> Result<TotpSettings, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+/// Code of a secret at a time step
+pub fn totp_code(secret: &[u8], step: u64) -> u32 {
+	let key = hmac::SigningKey::new(&digest::SHA1, secret);
+	let mac = hmac::sign(&key, &step.to_be_bytes());
+	let mac = mac.as_ref();
+	let offset = (mac[mac.len() - 1] & 0x0f) as usize;
+	let bin = (u32::from(mac[offset] & 0x7f) << 24)
+		| (u32::from(mac[offset + 1]) << 16)
+		| (u32::from(mac[offset + 2]) << 8)
+		| u32::from(mac[offset + 3]);
+	bin % 10u32.pow(TOTP_DIGITS)
+}
+
+/// Hash of a recovery code, as it's stored. Case and dashes don't matter.
+fn recovery_code_hash(code: &str) -> String {
+	let code: String = code
+		.trim()
+		.chars()
+		.filter(|c| *c != '-')
+		.collect::<String>()
+		.to_lowercase();
+	grin_util::to_hex(blake2b(32, &[], code.as_bytes()).as_bytes().to_vec())
+}
+
+/// Base32 (RFC 4648) without padding
+fn base32_encode(data: &[u8]) -> String {
+	let mut out = String::new();
+	let mut buffer: u32 = 0;
+	let mut bits = 0;
+	for b in data {
+		buffer = (buffer << 8) | u32::from(*b);
+		bits += 8;
+		while bits >= 5 {
+			out.push(BASE32_ALPHABET[((buffer >> (bits - 5)) & 0x1f) as usize] as char);
+			bits -= 5;
+		}
+	}
+	if bits > 0 {
+		out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rfc6238_codes() {
+		// SHA1 test vectors of RFC 6238, truncated to 6 digits
+		let secret = b"12345678901234567890";
+		assert_eq!(totp_code(secret, 59 / TOTP_STEP_SECS), 287082);
+		assert_eq!(totp_code(secret, 1111111109 / TOTP_STEP_SECS), 81804);
+		assert_eq!(totp_code(secret, 1234567890 / TOTP_STEP_SECS), 5924);
+		assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+	}
+
+	#[test]
+	fn codes_and_recovery_codes_used_once() {
+		let (mut settings, recovery_codes) = TotpSettings::new();
+		assert_eq!(recovery_codes.len(), TOTP_RECOVERY_CODES);
+		let time = 1_600_000_000;
+		let code = format!("{:06}", totp_code(&settings.secret, time / TOTP_STEP_SECS));
+		assert!(settings.verify("000000x", time, true).is_err());
+		settings.verify(&code, time, false).unwrap();
+		assert!(settings.verify(&code, time, false).is_err());
+
+		assert!(settings.verify(&recovery_codes[0], time, false).is_err());
+		settings
+			.verify(&recovery_codes[0].to_uppercase(), time, true)
+			.unwrap();
+		assert!(settings.verify(&recovery_codes[0], time, true).is_err());
+		assert_eq!(settings.recovery_code_hashes.len(), TOTP_RECOVERY_CODES - 1);
+	}
+}
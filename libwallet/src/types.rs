@@ -15,25 +15,49 @@
 //! Types and traits that should be provided by a wallet
 //! implementation
 
+use crate::api_impl::types::{
+	InitTxArgs, InitTxSendArgs, PendingOperations, RestoreScan, SendMetrics,
+};
+use crate::blake2::blake2b::blake2b;
 use crate::config::WalletConfig;
+use crate::cost_basis::{FiatPrice, PriceSource};
 use crate::error::{Error, ErrorKind};
 use crate::grin_core::core::hash::Hash;
-use crate::grin_core::core::{Output, Transaction, TxKernel};
+use crate::grin_core::core::merkle_proof::MerkleProof;
+use crate::grin_core::core::{amount_to_hr_string, Output, Transaction, TxKernel};
 use crate::grin_core::libtx::{aggsig, secp_ser};
-use crate::grin_core::{global, ser};
-use crate::grin_keychain::{Identifier, Keychain};
+use crate::grin_core::{consensus, global, ser};
+use crate::grin_keychain::{ExtKeychain, Identifier, Keychain};
 use crate::grin_util::secp::key::{PublicKey, SecretKey};
 use crate::grin_util::secp::{self, pedersen, Secp256k1};
-use crate::grin_util::{LoggingConfig, ZeroingString};
-use crate::slate::ParticipantMessages;
+use crate::grin_util::{self, LoggingConfig, ZeroingString};
+use crate::payment_proof::PaymentProof;
+use crate::slate::{
+	ParticipantAttachmentData, ParticipantMessages, Slate, REFUND_ADDRESS_ATTACHMENT,
+};
+use crate::totp::TotpSettings;
+use crate::view_only::WatchOnlyKey;
 use chrono::prelude::*;
 use failure::ResultExt;
 use serde;
 use serde_json;
 use std::collections::HashMap;
 use std::fmt;
+use std::sync::Arc;
 use uuid::Uuid;
 
+pub use crate::config::types::DEFAULT_FINALITY_DEPTH;
+
+/// Ticker of the currency held by the wallets, unless configured otherwise
+pub const DEFAULT_CURRENCY: &'static str = "MWC";
+
+/// Amount in human readable form followed by the ticker of the currency, e.g.
+/// `1.5 MWC`. Wallets label the amounts they report with the `currency` they're
+/// configured with, forks of the wallet for other currencies only set theirs.
+pub fn amount_to_currency_string(amount: u64, truncate: bool, currency: &str) -> String {
+	format!("{} {}", amount_to_hr_string(amount, truncate), currency)
+}
+
 /// Combined trait to allow dynamic wallet dispatch
 pub trait WalletInst<'a, L, C, K>: Send + Sync
 where
@@ -78,6 +102,35 @@ where
 		test_mode: bool,
 	) -> Result<(), Error>;
 
+	/// Create a watch-only wallet from the root public key of another wallet, exported
+	/// with its seed. It finds and follows the outputs of that wallet, but can't spend
+	/// or receive. The key is encrypted with the password like a seed.
+	fn create_watch_only_wallet(
+		&mut self,
+		name: Option<&str>,
+		root_public_key: &str,
+		password: ZeroingString,
+	) -> Result<(), Error>;
+
+	/// Open wallets read-only from now on. Read-only wallets reject any change,
+	/// so they can be opened by another process while the wallet is in use
+	fn set_read_only(&mut self, read_only: bool);
+
+	/// Configuration the wallets opened from now on run with, also applied to the
+	/// wallet currently open
+	fn set_wallet_config(&mut self, config: WalletConfig);
+
+	/// Configuration the wallets are opened with
+	fn wallet_config(&self) -> &WalletConfig;
+
+	/// Price provider the wallets opened from now on value their coins with, also
+	/// given to the wallet currently open. `None` to not value coins as they're
+	/// received.
+	fn set_price_source(&mut self, source: Option<Arc<PriceSource>>);
+
+	/// Operations in progress on the wallet, which closing it waits for
+	fn pending_operations(&self) -> Arc<PendingOperations>;
+
 	///
 	fn open_wallet(
 		&mut self,
@@ -111,8 +164,14 @@ where
 		password: ZeroingString,
 	) -> Result<(), Error>;
 
-	/// changes password
-	fn change_password(&self, old: String, new: String) -> Result<(), Error>;
+	/// Encrypt the wallet seed with a new password, and the key derivation
+	/// parameters currently configured
+	fn change_password(
+		&self,
+		name: Option<&str>,
+		old: ZeroingString,
+		new: ZeroingString,
+	) -> Result<(), Error>;
 
 	/// deletes wallet
 	fn delete_wallet(&self, name: Option<String>, password: String) -> Result<(), Error>;
@@ -148,6 +207,34 @@ where
 	/// Can optionally take a mask value
 	fn keychain(&self, mask: Option<&SecretKey>) -> Result<K, Error>;
 
+	/// Root public key the wallet follows the outputs of, if it's a watch-only wallet
+	/// opened without the seed. Its keychain then holds no key of the wallet.
+	fn watch_only_key(&self) -> Option<WatchOnlyKey>;
+
+	/// Configuration the wallet runs with
+	fn config(&self) -> &WalletConfig;
+
+	/// Set the configuration the wallet runs with
+	fn set_config(&mut self, config: WalletConfig);
+
+	/// Price provider the wallet values its coins with, if any
+	fn price_source(&self) -> Option<&PriceSource>;
+
+	/// Set the price provider the wallet values its coins with
+	fn set_price_source(&mut self, source: Option<Arc<PriceSource>>);
+
+	/// Chain height last seen from the node, and when it was first seen
+	fn node_height_seen(&self) -> Option<(u64, DateTime<Utc>)>;
+
+	/// Record the chain height seen from the node, and when it was first seen
+	fn set_node_height_seen(&mut self, height: u64, since: DateTime<Utc>);
+
+	/// Time spent so far in each step of the sends built since the wallet was opened
+	fn send_metrics(&self) -> &SendMetrics;
+
+	/// Time spent in each step of the sends, for a send to add its own
+	fn send_metrics_mut(&mut self) -> &mut SendMetrics;
+
 	/// Return the client being used to communicate with the node
 	fn w2n_client(&mut self) -> &mut C;
 
@@ -195,6 +282,68 @@ where
 	/// Gets an account path for a given label
 	fn get_acct_path(&self, label: String) -> Result<Option<AcctPathMapping>, Error>;
 
+	/// Iterate over the balance holds of all accounts, expired or not
+	fn balance_hold_iter<'a>(&'a self) -> Box<dyn Iterator<Item = BalanceHold> + 'a>;
+
+	/// Filter on the origins of incoming slates
+	fn source_filter(&self) -> Result<SourceFilter, Error>;
+
+	/// Iterate over the log of incoming slates that couldn't be received, oldest first
+	fn failed_receive_iter<'a>(&'a self) -> Box<dyn Iterator<Item = FailedReceive> + 'a>;
+
+	/// Iterate over the slate archive, by slate id and then oldest first
+	fn archived_slate_iter<'a>(&'a self) -> Box<dyn Iterator<Item = ArchivedSlate> + 'a>;
+
+	/// Iterate over the sends prepared and not dispatched yet
+	fn pending_dispatch_iter<'a>(&'a self) -> Box<dyn Iterator<Item = PendingDispatch> + 'a>;
+
+	/// Send prepared for the given slate and not dispatched yet, if any
+	fn get_pending_dispatch(&self, slate_id: &Uuid) -> Result<Option<PendingDispatch>, Error>;
+
+	/// Iterate over the one-time addresses handed out by the wallet
+	fn onetime_address_iter<'a>(&'a self) -> Box<dyn Iterator<Item = OnetimeAddress> + 'a>;
+
+	/// One-time address with the given id, if any
+	fn get_onetime_address(&self, id: &Uuid) -> Result<Option<OnetimeAddress>, Error>;
+
+	/// Iterate over the foreign API keys of the wallet
+	fn foreign_api_key_iter<'a>(&'a self) -> Box<dyn Iterator<Item = ForeignApiKey> + 'a>;
+
+	/// Foreign API key of the given hash, if any
+	fn get_foreign_api_key(&self, key_hash: &str) -> Result<Option<ForeignApiKey>, Error>;
+
+	/// Iterate over the approval tokens of the wallet
+	fn approval_token_iter<'a>(&'a self) -> Box<dyn Iterator<Item = ApprovalToken> + 'a>;
+
+	/// Approval token of the given hash, if any
+	fn get_approval_token(&self, token_hash: &str) -> Result<Option<ApprovalToken>, Error>;
+
+	/// Time lock settings of an account, if it's a vault
+	fn vault_settings(&self, parent_key_id: &Identifier) -> Result<Option<VaultSettings>, Error>;
+
+	/// Whether the wallet is frozen, and since when
+	fn freeze_state(&self) -> Result<Option<FreezeState>, Error>;
+
+	/// Second factor of the owner API, if one is set up. Its secret is stored
+	/// encrypted with the keychain.
+	fn totp_settings(
+		&mut self,
+		keychain_mask: Option<&SecretKey>,
+	) -> Result<Option<TotpSettings>, Error>;
+
+	/// Iterate over the queued wallet events, oldest first
+	fn wallet_event_iter<'a>(&'a self) -> Box<dyn Iterator<Item = WalletEvent> + 'a>;
+
+	/// Sequence number of the last event a consumer acknowledged, 0 if none
+	fn event_offset(&self, consumer: &str) -> Result<u64, Error>;
+
+	/// Current value of the change counter, incremented on every change to the
+	/// wallet data, each of which is recorded in the change journal
+	fn change_seq(&self) -> Result<u64, Error>;
+
+	/// Iterate over the change journal, oldest first
+	fn change_journal_iter<'a>(&'a self) -> Box<dyn Iterator<Item = ChangeRecord> + 'a>;
+
 	/// Stores a transaction
 	fn store_tx(&self, uuid: &str, tx: &Transaction) -> Result<(), Error>;
 
@@ -210,11 +359,26 @@ where
 	/// Next child ID when we want to create a new output, based on current parent
 	fn next_child<'a>(&mut self, keychain_mask: Option<&SecretKey>) -> Result<Identifier, Error>;
 
+	/// Next child ID for a change output, based on current parent. Taken from
+	/// the keys reserved ahead if the wallet is configured with a change key pool
+	/// size, so that most sends don't write the child index.
+	fn next_change_child(&mut self, keychain_mask: Option<&SecretKey>)
+		-> Result<Identifier, Error>;
+
+	/// Child index the next output under the given parent will be derived with,
+	/// without advancing it
+	fn next_child_index(&mut self, parent_key_id: &Identifier) -> Result<u32, Error>;
+
 	/// last verified height of outputs directly descending from the given parent key
 	fn last_confirmed_height<'a>(&mut self) -> Result<u64, Error>;
 
-	/// Attempt to restore the contents of a wallet from seed
-	fn restore(&mut self, keychain_mask: Option<&SecretKey>) -> Result<(), Error>;
+	/// Attempt to restore the contents of a wallet from seed, only looking at
+	/// the outputs created from the birthday height on if given
+	fn restore(
+		&mut self,
+		keychain_mask: Option<&SecretKey>,
+		birthday: Option<u64>,
+	) -> Result<(), Error>;
 
 	/// Attempt to check and fix wallet state
 	fn check_repair(
@@ -222,6 +386,33 @@ where
 		keychain_mask: Option<&SecretKey>,
 		delete_unconfirmed: bool,
 	) -> Result<(), Error>;
+
+	/// Import an output of the wallet that's on chain but not in the wallet, after
+	/// checking it against the key and the chain
+	fn import_output(
+		&mut self,
+		keychain_mask: Option<&SecretKey>,
+		commit: &pedersen::Commitment,
+		value: u64,
+		key_id: &Identifier,
+		height: Option<u64>,
+	) -> Result<OutputData, Error>;
+
+	/// Scan the next chunk of at most `max_outputs` outputs of a step-wise restore,
+	/// without writing to the wallet
+	fn restore_scan(
+		&mut self,
+		keychain_mask: Option<&SecretKey>,
+		scan: &mut RestoreScan,
+		max_outputs: u64,
+	) -> Result<(), Error>;
+
+	/// Save the outputs found by a complete step-wise restore to the empty wallet
+	fn restore_commit(
+		&mut self,
+		keychain_mask: Option<&SecretKey>,
+		scan: &RestoreScan,
+	) -> Result<(), Error>;
 }
 
 /// Batch trait to update the output data backend atomically. Trying to use a
@@ -273,6 +464,76 @@ where
 	/// Iterate over account names stored in backend
 	fn acct_path_iter(&self) -> Box<dyn Iterator<Item = AcctPathMapping>>;
 
+	/// Save a balance hold, replacing any hold with the same reference in its account
+	fn save_balance_hold(&mut self, hold: &BalanceHold) -> Result<(), Error>;
+
+	/// Delete a balance hold
+	fn delete_balance_hold(&mut self, hold: &BalanceHold) -> Result<(), Error>;
+
+	/// Save the filter on the origins of incoming slates
+	fn save_source_filter(&mut self, filter: &SourceFilter) -> Result<(), Error>;
+
+	/// Add an entry to the log of failed receives
+	fn save_failed_receive(&mut self, entry: &FailedReceive) -> Result<(), Error>;
+
+	/// Delete an entry from the log of failed receives
+	fn delete_failed_receive(&mut self, entry: &FailedReceive) -> Result<(), Error>;
+
+	/// Add a slate to the slate archive
+	fn save_archived_slate(&mut self, entry: &ArchivedSlate) -> Result<(), Error>;
+
+	/// Delete a slate from the slate archive
+	fn delete_archived_slate(&mut self, entry: &ArchivedSlate) -> Result<(), Error>;
+
+	/// Save a send prepared and not dispatched yet
+	fn save_pending_dispatch(&mut self, entry: &PendingDispatch) -> Result<(), Error>;
+
+	/// Delete a prepared send, once dispatched or cancelled
+	fn delete_pending_dispatch(&mut self, slate_id: &Uuid) -> Result<(), Error>;
+
+	/// Save a one-time address
+	fn save_onetime_address(&mut self, address: &OnetimeAddress) -> Result<(), Error>;
+
+	/// Save a foreign API key
+	fn save_foreign_api_key(&mut self, key: &ForeignApiKey) -> Result<(), Error>;
+
+	/// Delete a foreign API key, once revoked
+	fn delete_foreign_api_key(&mut self, key: &ForeignApiKey) -> Result<(), Error>;
+
+	/// Save an approval token
+	fn save_approval_token(&mut self, token: &ApprovalToken) -> Result<(), Error>;
+
+	/// Delete an approval token, once revoked
+	fn delete_approval_token(&mut self, token: &ApprovalToken) -> Result<(), Error>;
+
+	/// Save the time lock settings of a vault account
+	fn save_vault_settings(&mut self, settings: &VaultSettings) -> Result<(), Error>;
+
+	/// Freeze the wallet
+	fn save_freeze_state(&mut self, state: &FreezeState) -> Result<(), Error>;
+
+	/// Unfreeze the wallet
+	fn delete_freeze_state(&mut self) -> Result<(), Error>;
+
+	/// Save the second factor of the owner API, encrypting its secret with the
+	/// keychain
+	fn save_totp_settings(&mut self, settings: &TotpSettings) -> Result<(), Error>;
+
+	/// Remove the second factor of the owner API
+	fn delete_totp_settings(&mut self) -> Result<(), Error>;
+
+	/// Get the sequence number of the next wallet event
+	fn next_event_seq(&mut self) -> Result<u64, Error>;
+
+	/// Add an event to the wallet event queue
+	fn save_wallet_event(&mut self, event: &WalletEvent) -> Result<(), Error>;
+
+	/// Drop an event from the wallet event queue
+	fn delete_wallet_event(&mut self, seq: u64) -> Result<(), Error>;
+
+	/// Save the sequence number of the last event a consumer acknowledged
+	fn save_event_offset(&mut self, consumer: &str, seq: u64) -> Result<(), Error>;
+
 	/// Save an output as locked in the backend
 	fn lock_output(&mut self, out: &mut OutputData) -> Result<(), Error>;
 
@@ -310,6 +571,35 @@ pub trait NodeClient: Send + Sync + Clone {
 	/// Change the API secret
 	fn set_node_api_secret(&mut self, node_api_secret: Option<String>);
 
+	/// Return the chain the node runs (mainnet, floonet...), which the wallet
+	/// uses instead of the process-wide chain type
+	fn chain_type(&self) -> global::ChainTypes;
+
+	/// Set the chain the node runs
+	fn set_chain_type(&mut self, chain_type: global::ChainTypes);
+
+	/// Number of blocks a coinbase output of the node's chain is locked for
+	fn coinbase_maturity(&self) -> u64 {
+		match self.chain_type() {
+			global::ChainTypes::AutomatedTesting => global::AUTOMATED_TESTING_COINBASE_MATURITY,
+			global::ChainTypes::UserTesting => global::USER_TESTING_COINBASE_MATURITY,
+			_ => consensus::COINBASE_MATURITY,
+		}
+	}
+
+	/// Maximum weight of a block of the node's chain
+	fn max_block_weight(&self) -> usize {
+		match self.chain_type() {
+			global::ChainTypes::AutomatedTesting | global::ChainTypes::UserTesting => {
+				global::TESTING_MAX_BLOCK_WEIGHT
+			}
+			_ => consensus::MAX_BLOCK_WEIGHT,
+		}
+	}
+
+	/// Returns the node's connections, sync state and protocol version
+	fn get_status(&self) -> Result<NodeStatus, Error>;
+
 	/// Posts a transaction to a grin node
 	fn post_tx(&self, tx: &TxWrapper, fluff: bool) -> Result<(), Error>;
 
@@ -322,19 +612,60 @@ pub trait NodeClient: Send + Sync + Clone {
 
 	/// retrieve a list of outputs from the specified grin node
 	/// need "by_height" and "by_id" variants
+	/// Commitments that couldn't be queried are reported in the result rather
+	/// than failing the whole call
 	fn get_outputs_from_node(
 		&self,
 		wallet_outputs: Vec<pedersen::Commitment>,
-	) -> Result<HashMap<pedersen::Commitment, (String, u64, u64)>, Error>;
+	) -> Result<NodeOutputs, Error>;
+
+	/// Get the header of the block at the given height
+	fn get_header_by_height(&self, height: u64) -> Result<NodeHeaderInfo, Error>;
+
+	/// Find the block that includes the kernel with the given excess, looking
+	/// between min_height and max_height if given. Returns None if the kernel
+	/// isn't on chain
+	fn get_block_by_kernel(
+		&self,
+		excess: &pedersen::Commitment,
+		min_height: Option<u64>,
+		max_height: Option<u64>,
+	) -> Result<Option<NodeHeaderInfo>, Error>;
+
+	/// Get the outputs created in the block at the given height, with their
+	/// rangeproofs and, where the node provides them, Merkle proofs of their
+	/// inclusion in the block's output MMR. Returns None if there's no block
+	/// at that height
+	fn get_block_outputs(&self, height: u64) -> Result<Option<NodeBlockOutputs>, Error>;
+
+	/// Get the transactions in the node's mempool, indexed by the excess of
+	/// each of their kernels, so the whole pool is fetched once however many
+	/// transactions are looked up in it
+	fn get_pool_entries(&self) -> Result<HashMap<pedersen::Commitment, NodePoolEntry>, Error>;
+
+	/// Get the inputs, outputs, kernels and fees of the transactions in the block
+	/// at the given height, coinbase excluded. Returns None if there's no block
+	/// at that height
+	fn get_block_fees(&self, height: u64) -> Result<Option<NodeTxFees>, Error>;
+
+	/// Get the inputs, outputs, kernels and fee of each transaction in the
+	/// node's mempool
+	fn get_pool_fees(&self) -> Result<Vec<NodeTxFees>, Error>;
+
+	/// Latency, errors and payload sizes of the calls made to the node so far,
+	/// per endpoint, starting over afterwards if `reset` is set
+	fn get_stats(&self, reset: bool) -> NodeClientStats;
 
 	/// Get a list of outputs from the node by traversing the UTXO
-	/// set in PMMR index order.
+	/// set in PMMR index order, from start_index up to end_index (inclusive)
+	/// if given.
 	/// Returns
 	/// (last available output index, last insertion index retrieved,
 	/// outputs(commit, proof, is_coinbase, height, mmr_index))
 	fn get_outputs_by_pmmr_index(
 		&self,
-		start_height: u64,
+		start_index: u64,
+		end_index: Option<u64>,
 		max_outputs: u64,
 	) -> Result<
 		(
@@ -357,6 +688,199 @@ pub struct NodeVersionInfo {
 	pub verified: Option<bool>,
 }
 
+/// Node network and sync state, as reported by the node
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NodeStatus {
+	/// Protocol version the node speaks to its peers
+	pub protocol_version: u32,
+	/// Node user agent
+	pub user_agent: String,
+	/// Number of connected peers
+	pub connections: u32,
+	/// Height of the node's chain tip
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub height: u64,
+	/// Sync status, `no_sync` once the node is in sync with its peers
+	pub sync_status: String,
+}
+
+impl NodeStatus {
+	/// Whether the node has caught up with its peers, so that the outputs it
+	/// reports reflect the chain
+	pub fn is_synced(&self) -> bool {
+		self.sync_status == "no_sync"
+	}
+}
+
+/// Block header details as reported by the node
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NodeHeaderInfo {
+	/// Block height
+	pub height: u64,
+	/// Block hash, hex encoded
+	pub hash: String,
+	/// Block timestamp
+	pub timestamp: DateTime<Utc>,
+	/// Size of the output MMR once the block is applied, 0 if the node
+	/// doesn't report it
+	#[serde(default)]
+	pub output_mmr_size: u64,
+}
+
+/// Outputs created in a block, as reported by the node
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NodeBlockOutputs {
+	/// Block height
+	pub height: u64,
+	/// Root of the output MMR committed to in the block header, hex encoded
+	pub output_root: String,
+	/// Outputs created in the block
+	pub outputs: Vec<NodeOutputProof>,
+}
+
+/// An output with the proofs backing it, as reported by the node
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NodeOutputProof {
+	/// Output commitment
+	#[serde(
+		serialize_with = "secp_ser::as_hex",
+		deserialize_with = "secp_ser::commitment_from_hex"
+	)]
+	pub commit: pedersen::Commitment,
+	/// Whether the output is a coinbase output
+	pub is_coinbase: bool,
+	/// Position of the output in the output MMR
+	pub mmr_index: u64,
+	/// Rangeproof, hex encoded
+	pub proof: Option<String>,
+	/// Proof of inclusion in the output MMR of the block
+	pub merkle_proof: Option<MerkleProof>,
+}
+
+/// Transaction entry in the node's mempool
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NodePoolEntry {
+	/// Where the node got the transaction from
+	pub src: String,
+	/// Time the transaction entered the pool, if reported
+	pub tx_at: Option<DateTime<Utc>>,
+}
+
+/// Inputs, outputs and kernels of a transaction and the fee it pays, or the
+/// total of the transactions of a block
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct NodeTxFees {
+	/// Number of inputs
+	pub num_inputs: usize,
+	/// Number of outputs
+	pub num_outputs: usize,
+	/// Number of kernels
+	pub num_kernels: usize,
+	/// Fee paid
+	pub fee: u64,
+}
+
+/// Statistics of the calls a node client made to an endpoint of the node API.
+/// Times are in microseconds, from the request being sent to the response
+/// being read.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct NodeEndpointStats {
+	/// Endpoint, the path of the node API called
+	pub endpoint: String,
+	/// Number of calls
+	pub calls: u64,
+	/// Number of calls that failed, the node being unreachable or answering
+	/// with an error status
+	pub errors: u64,
+	/// Number of calls slower than the threshold logged as slow
+	pub slow_calls: u64,
+	/// Total time of the calls
+	pub total_us: u64,
+	/// Time of the slowest call
+	pub max_us: u64,
+	/// Total size of the request bodies sent, in bytes
+	pub bytes_sent: u64,
+	/// Total size of the response bodies received, in bytes
+	pub bytes_received: u64,
+}
+
+/// Statistics of the calls a node client made to the node
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NodeClientStats {
+	/// When the statistics were started, or last reset
+	pub since: DateTime<Utc>,
+	/// Statistics of each endpoint called
+	pub endpoints: Vec<NodeEndpointStats>,
+}
+
+impl NodeClientStats {
+	/// Start empty statistics
+	pub fn new() -> NodeClientStats {
+		NodeClientStats {
+			since: Utc::now(),
+			endpoints: vec![],
+		}
+	}
+}
+
+/// Outputs returned by the node for a set of queried commitments
+#[derive(Debug, Clone, Default)]
+pub struct NodeOutputs {
+	/// Outputs found on the node, (commit hex, height, mmr index) by commitment
+	pub outputs: HashMap<pedersen::Commitment, (String, u64, u64)>,
+	/// Commitments for which the query failed, and whose state is unknown
+	pub failed: Vec<pedersen::Commitment>,
+	/// Description of each failed request
+	pub errors: Vec<String>,
+}
+
+impl NodeOutputs {
+	/// Create from a complete set of outputs
+	pub fn new(outputs: HashMap<pedersen::Commitment, (String, u64, u64)>) -> NodeOutputs {
+		NodeOutputs {
+			outputs,
+			..Default::default()
+		}
+	}
+
+	/// Whether every requested commitment was successfully queried
+	pub fn is_complete(&self) -> bool {
+		self.failed.is_empty()
+	}
+}
+
+/// Outcome of a refresh of the wallet outputs from the node
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct RefreshReport {
+	/// Whether the node was synced. Outputs aren't refreshed from a node that's syncing
+	pub node_synced: bool,
+	/// Number of outputs queried from the node
+	pub outputs_total: usize,
+	/// Number of outputs whose query failed. They're left as they were
+	pub outputs_failed: usize,
+	/// Description of each failed request
+	pub errors: Vec<String>,
+}
+
+impl RefreshReport {
+	/// Whether every output was refreshed
+	pub fn is_complete(&self) -> bool {
+		self.node_synced && self.outputs_failed == 0
+	}
+
+	/// Fail with `PartialRefresh` if some outputs couldn't be queried
+	pub fn check_complete(&self) -> Result<(), Error> {
+		if self.outputs_failed > 0 {
+			return Err(ErrorKind::PartialRefresh(
+				self.outputs_failed,
+				self.outputs_total,
+				self.errors.join("; "),
+			))?;
+		}
+		Ok(())
+	}
+}
+
 /// Information about an output that's being tracked by the wallet. Must be
 /// enough to reconstruct the commitment associated with the ouput when the
 /// root private key is known.
@@ -390,6 +914,26 @@ pub struct OutputData {
 	pub is_coinbase: bool,
 	/// Optional corresponding internal entry in tx entry log
 	pub tx_log_entry: Option<u32>,
+	/// Hold keeping this output for an upcoming scheduled payment, if any
+	#[serde(default)]
+	pub reservation: Option<OutputReservation>,
+	/// Price of the coin when this output was acquired, if known
+	#[serde(default)]
+	pub cost_basis: Option<FiatPrice>,
+	/// Value of the change counter of the wallet when this output was last saved,
+	/// 0 if before the counter was kept
+	#[serde(default)]
+	pub change_seq: u64,
+}
+
+/// Hold on an output, keeping it for an upcoming scheduled payment. Sends that
+/// aren't made for the reservation don't select the output until the hold expires.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd, Eq, Ord)]
+pub struct OutputReservation {
+	/// Label of the reservation, e.g. the scheduled payment it's kept for
+	pub label: String,
+	/// When the hold expires
+	pub expires: DateTime<Utc>,
 }
 
 impl ser::Writeable for OutputData {
@@ -429,6 +973,22 @@ impl OutputData {
 		}
 	}
 
+	/// Whether the output is buried at least `finality_depth` blocks deep, so
+	/// it's treated as final, i.e. no longer subject to reorg rollback checks
+	pub fn is_final(&self, current_height: u64, finality_depth: u64) -> bool {
+		self.status != OutputStatus::Unconfirmed
+			&& self.num_confirmations(current_height) >= finality_depth
+	}
+
+	/// Whether the output is held, at the given time, for another reservation
+	/// than `reservation`
+	pub fn is_reserved(&self, reservation: Option<&str>, now: DateTime<Utc>) -> bool {
+		match self.reservation {
+			Some(ref r) => r.expires > now && reservation != Some(r.label.as_str()),
+			None => false,
+		}
+	}
+
 	/// Check if output is eligible to spend based on state and height and
 	/// confirmations
 	pub fn eligible_to_spend(&self, current_height: u64, minimum_confirmations: u64) -> bool {
@@ -449,10 +1009,20 @@ impl OutputData {
 		}
 	}
 
-	/// Marks this output as unspent if it was previously unconfirmed
+	/// Marks this output as unspent if it was previously unconfirmed, or
+	/// reverted and now back on chain
 	pub fn mark_unspent(&mut self) {
 		match self.status {
 			OutputStatus::Unconfirmed => self.status = OutputStatus::Unspent,
+			OutputStatus::Reverted => self.status = OutputStatus::Unspent,
+			_ => (),
+		}
+	}
+
+	/// Marks this output as reverted, its block having been reorged out
+	pub fn mark_reverted(&mut self) {
+		match self.status {
+			OutputStatus::Unspent => self.status = OutputStatus::Reverted,
 			_ => (),
 		}
 	}
@@ -480,6 +1050,8 @@ pub enum OutputStatus {
 	Locked,
 	/// Spent
 	Spent,
+	/// Confirmed, then gone from the chain with the block it was in
+	Reverted,
 }
 
 impl fmt::Display for OutputStatus {
@@ -489,6 +1061,7 @@ impl fmt::Display for OutputStatus {
 			OutputStatus::Unspent => write!(f, "Unspent"),
 			OutputStatus::Locked => write!(f, "Locked"),
 			OutputStatus::Spent => write!(f, "Spent"),
+			OutputStatus::Reverted => write!(f, "Reverted"),
 		}
 	}
 }
@@ -513,6 +1086,12 @@ pub struct Context {
 	pub fee: u64,
 	/// keep track of the participant id
 	pub participant_id: usize,
+	/// dual-control approval state, if the tx needs one before finalizing
+	#[serde(default)]
+	pub approval: Option<TxApproval>,
+	/// whether the fee was deducted from the amount sent
+	#[serde(default)]
+	pub fee_from_amount: bool,
 }
 
 impl Context {
@@ -536,6 +1115,8 @@ impl Context {
 			output_ids: vec![],
 			fee: 0,
 			participant_id: participant_id,
+			approval: None,
+			fee_from_amount: false,
 		}
 	}
 }
@@ -668,12 +1249,88 @@ pub struct WalletInfo {
 	/// coinbases waiting for lock height
 	#[serde(with = "secp_ser::string_or_u64")]
 	pub amount_immature: u64,
+	/// amount received in transactions that were reorged out of the chain, not
+	/// part of the total unless they're confirmed again
+	#[serde(default, with = "secp_ser::string_or_u64")]
+	pub amount_reverted: u64,
 	/// amount currently spendable
 	#[serde(with = "secp_ser::string_or_u64")]
 	pub amount_currently_spendable: u64,
 	/// amount locked via previous transactions
 	#[serde(with = "secp_ser::string_or_u64")]
 	pub amount_locked: u64,
+	/// part of the spendable amount held for upcoming payments
+	#[serde(default, with = "secp_ser::string_or_u64")]
+	pub amount_held: u64,
+	/// spendable amount that isn't held
+	#[serde(default, with = "secp_ser::string_or_u64")]
+	pub amount_available: u64,
+	/// part of the total that is buried deeper than the finality depth
+	#[serde(default, with = "secp_ser::string_or_u64")]
+	pub amount_finalized: u64,
+	/// amount of the received transactions given up on, not counted as awaiting
+	/// finalization or confirmation
+	#[serde(default, with = "secp_ser::string_or_u64")]
+	pub amount_abandoned: u64,
+	/// Number of confirmations after which an output is treated as "final".
+	#[serde(default, with = "secp_ser::string_or_u64")]
+	pub finality_depth: u64,
+	/// Ticker of the currency the amounts are in
+	#[serde(default)]
+	pub currency: String,
+}
+
+/// Status of a coinbase output built by this wallet
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub enum CoinbaseStatus {
+	/// Built for a block candidate, not seen on chain
+	Pending,
+	/// On chain, waiting for the coinbase maturity period
+	Immature,
+	/// On chain and spendable
+	Mature,
+	/// Spent
+	Spent,
+	/// Its block was orphaned, the output will never be spendable
+	Orphaned,
+}
+
+/// A coinbase output built by this wallet, for a given block height
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct CoinbaseInfo {
+	/// Height of the block the coinbase was built for
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub height: u64,
+	/// Key id of the output
+	pub key_id: Identifier,
+	/// Output commitment, if known
+	pub commit: Option<String>,
+	/// Value of the output
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub value: u64,
+	/// Status
+	pub status: CoinbaseStatus,
+	/// Transaction log entry, once confirmed
+	pub tx_log_entry: Option<u32>,
+}
+
+/// Report on the coinbase outputs of the active account
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
+pub struct CoinbaseReport {
+	/// height from which the report was taken
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub last_confirmed_height: u64,
+	/// Coinbase outputs, by height
+	pub coinbases: Vec<CoinbaseInfo>,
+	/// amount in coinbases waiting for lock height
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount_immature: u64,
+	/// amount in mature coinbases that aren't spent yet
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount_mature: u64,
+	/// amount lost to orphaned blocks
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount_orphaned: u64,
 }
 
 /// Types of transactions that can be contained within a TXLog entry
@@ -689,16 +1346,22 @@ pub enum TxLogEntryType {
 	TxReceivedCancelled,
 	/// Sent transaction that was rolled back by user
 	TxSentCancelled,
+	/// A confirmed coinbase whose block was orphaned
+	OrphanedCoinbase,
+	/// A confirmed received transaction that was reorged out of the chain
+	TxReverted,
 }
 
 impl fmt::Display for TxLogEntryType {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		match *self {
-			TxLogEntryType::ConfirmedCoinbase => write!(f, "Confirmed \nCoinbase"),
+			TxLogEntryType::ConfirmedCoinbase => write!(f, "Confirmed\nCoinbase"),
 			TxLogEntryType::TxReceived => write!(f, "Received Tx"),
 			TxLogEntryType::TxSent => write!(f, "Sent Tx"),
 			TxLogEntryType::TxReceivedCancelled => write!(f, "Received Tx\n- Cancelled"),
 			TxLogEntryType::TxSentCancelled => write!(f, "Sent Tx\n- Cancelled"),
+			TxLogEntryType::OrphanedCoinbase => write!(f, "Orphaned\nCoinbase"),
+			TxLogEntryType::TxReverted => write!(f, "Received Tx\n- Reverted"),
 		}
 	}
 }
@@ -743,6 +1406,63 @@ pub struct TxLogEntry {
 	pub messages: Option<ParticipantMessages>,
 	/// Location of the store transaction, (reference or resending)
 	pub stored_tx: Option<String>,
+	/// Height of the block this tx was confirmed in
+	#[serde(default, with = "secp_ser::opt_string_or_u64")]
+	pub confirmed_height: Option<u64>,
+	/// Hash of the block this tx was confirmed in
+	#[serde(default)]
+	pub confirmed_block_hash: Option<String>,
+	/// Lowest height the kernel of this tx is still looked up from, to find its
+	/// confirming block. Blocks below were already searched
+	#[serde(default, with = "secp_ser::opt_string_or_u64")]
+	pub kernel_lookup_min_height: Option<u64>,
+	/// Whether this unconfirmed tx was in the node's mempool at the last refresh
+	#[serde(default)]
+	pub in_mempool: bool,
+	/// Ids of other sends in this log spending one or more of the same inputs,
+	/// as of the last refresh this send was unconfirmed at. At most one of them
+	/// can ever be confirmed
+	#[serde(default)]
+	pub conflicts_with: Vec<u32>,
+	/// Dual-control approval state, if this tx needed approval by a second
+	/// Owner API token
+	#[serde(default)]
+	pub approval: Option<TxApproval>,
+	/// Slate attachments, decrypted where this wallet was able to
+	#[serde(default)]
+	pub attachments: Vec<ParticipantAttachmentData>,
+	/// Id of the slate participant this wallet took part as, if known
+	#[serde(default, with = "secp_ser::opt_string_or_u64")]
+	pub participant_id: Option<u64>,
+	/// Slate id of the refund sent back for this tx, if any
+	#[serde(default)]
+	pub refund_slate_id: Option<Uuid>,
+	/// Whether this received tx was given up on, its sender never having posted
+	/// it. Its amount isn't counted as awaiting finalization anymore.
+	#[serde(default)]
+	pub abandoned: bool,
+	/// Acknowledgement of the slate by the wallet it was sent to, if sent by
+	/// this wallet over a transport
+	#[serde(default)]
+	pub delivery_receipt: Option<DeliveryReceipt>,
+	/// Whether the fee of this sent tx was deducted from the amount sent rather than
+	/// paid on top of it
+	#[serde(default)]
+	pub fee_from_amount: bool,
+	/// Value of the change counter of the wallet when this entry was last saved,
+	/// 0 if before the counter was kept
+	#[serde(default)]
+	pub change_seq: u64,
+	/// Proof of the payment, if this sent tx was sent asking the recipient for one
+	#[serde(default)]
+	pub payment_proof: Option<PaymentProof>,
+	/// Price of the coin when this tx was sent or received, if known
+	#[serde(default)]
+	pub price: Option<FiatPrice>,
+	/// Lock height of the kernel of this send, while its posting waits for the
+	/// chain to reach it. It's posted on refresh once it can be mined
+	#[serde(default, with = "secp_ser::opt_string_or_u64")]
+	pub post_at_height: Option<u64>,
 }
 
 impl ser::Writeable for TxLogEntry {
@@ -776,6 +1496,22 @@ impl TxLogEntry {
 			fee: None,
 			messages: None,
 			stored_tx: None,
+			confirmed_height: None,
+			confirmed_block_hash: None,
+			kernel_lookup_min_height: None,
+			in_mempool: false,
+			conflicts_with: vec![],
+			approval: None,
+			attachments: vec![],
+			participant_id: None,
+			refund_slate_id: None,
+			abandoned: false,
+			delivery_receipt: None,
+			fee_from_amount: false,
+			change_seq: 0,
+			payment_proof: None,
+			price: None,
+			post_at_height: None,
 		}
 	}
 
@@ -791,6 +1527,154 @@ impl TxLogEntry {
 	pub fn update_confirmation_ts(&mut self) {
 		self.confirmation_ts = Some(Utc::now());
 	}
+
+	/// Record the block this tx was confirmed in, taking the block time as
+	/// confirmation time
+	pub fn set_confirming_block(&mut self, header: &NodeHeaderInfo) {
+		self.confirmed_height = Some(header.height);
+		self.confirmed_block_hash = Some(header.hash.clone());
+		self.confirmation_ts = Some(header.timestamp);
+	}
+
+	/// Number of confirmations at the given chain height, if the confirming
+	/// block is known
+	pub fn num_confirmations(&self, current_height: u64) -> Option<u64> {
+		match (self.confirmed, self.confirmed_height) {
+			(true, Some(h)) if h <= current_height => Some(current_height - h + 1),
+			_ => None,
+		}
+	}
+
+	/// Whether the tx is buried at least `finality_depth` blocks deep, so it's
+	/// treated as final and left out of reorg rollback checks
+	pub fn is_final(&self, current_height: u64, finality_depth: u64) -> bool {
+		match self.num_confirmations(current_height) {
+			Some(n) => n >= finality_depth,
+			None => false,
+		}
+	}
+
+	/// Refund address attached by the other participant, the payer of a received
+	/// tx whether it was sent or invoiced, if this wallet could read it
+	pub fn refund_address(&self) -> Option<&str> {
+		self.attachments
+			.iter()
+			.filter(|a| Some(a.id) != self.participant_id)
+			.find(|a| a.kind == REFUND_ADDRESS_ATTACHMENT)
+			.and_then(|a| a.content.as_ref())
+			.map(|c| c.as_str())
+	}
+}
+
+/// What became of a slate sent to another wallet, as that wallet acknowledged it
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum DeliveryStatus {
+	/// Received by the other wallet, which returned the slate
+	Accepted,
+	/// Refused by the other wallet
+	Rejected,
+	/// Delivered, waiting for the other wallet to process it
+	Queued,
+}
+
+impl fmt::Display for DeliveryStatus {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			DeliveryStatus::Accepted => write!(f, "Accepted"),
+			DeliveryStatus::Rejected => write!(f, "Rejected"),
+			DeliveryStatus::Queued => write!(f, "Queued"),
+		}
+	}
+}
+
+/// Acknowledgement of a slate by the wallet it was sent to, kept on the
+/// transaction log entry of the sender
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DeliveryReceipt {
+	/// What the other wallet did with the slate
+	pub status: DeliveryStatus,
+	/// Why the other wallet rejected the slate, if it did
+	pub reason: Option<String>,
+	/// Transport the slate was sent over, e.g. 'http' or 'keybase'
+	pub method: String,
+	/// When the acknowledgement was received
+	pub time: DateTime<Utc>,
+}
+
+impl DeliveryReceipt {
+	/// Receipt of an acknowledgement received now
+	pub fn new(status: DeliveryStatus, reason: Option<String>, method: &str) -> DeliveryReceipt {
+		DeliveryReceipt {
+			status,
+			reason,
+			method: method.to_owned(),
+			time: Utc::now(),
+		}
+	}
+}
+
+/// Approval record for a tx that must be signed off by a second approval
+/// token before it can be finalized or posted. Tokens are recorded by id.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TxApproval {
+	/// Id of the approval token that created the tx
+	pub created_by: String,
+	/// Id of the approval token that approved the tx, if approved
+	pub approved_by: Option<String>,
+	/// Time of approval
+	pub approved_at: Option<DateTime<Utc>>,
+}
+
+impl TxApproval {
+	/// New pending approval for a tx created by the given token
+	pub fn new(created_by: String) -> Self {
+		TxApproval {
+			created_by,
+			approved_by: None,
+			approved_at: None,
+		}
+	}
+
+	/// Whether a second token has approved the tx
+	pub fn is_approved(&self) -> bool {
+		self.approved_by.is_some()
+	}
+}
+
+/// Token of an operator creating or approving txs requiring dual-control
+/// approval. Unlike the Owner API token of a session, it doesn't depend on the
+/// wallet password and stays valid until revoked. Only the hash of the token
+/// is stored.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ApprovalToken {
+	/// Id of the token, as recorded in the approvals it's used for
+	pub id: Uuid,
+	/// Hash of the token, see [`ApprovalToken::hash`](#method.hash)
+	pub token_hash: String,
+	/// Optional label, e.g. the operator the token was given to
+	pub label: Option<String>,
+	/// When the token was created
+	pub created: DateTime<Utc>,
+}
+
+impl ApprovalToken {
+	/// Hash of a token, as it's stored
+	pub fn hash(token: &str) -> String {
+		grin_util::to_hex(blake2b(32, &[], token.as_bytes()).as_bytes().to_vec())
+	}
+}
+
+impl ser::Writeable for ApprovalToken {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for ApprovalToken {
+	fn read(reader: &mut dyn ser::Reader) -> Result<ApprovalToken, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
 }
 
 /// Map of named accounts to BIP32 paths
@@ -802,6 +1686,66 @@ pub struct AcctPathMapping {
 	pub path: Identifier,
 }
 
+impl AcctPathMapping {
+	/// Scheme the keys of the account are derived with
+	pub fn derivation_scheme(&self) -> DerivationScheme {
+		DerivationScheme::of(&self.path)
+	}
+}
+
+/// Flag of the hardened BIP32 derivation indices
+const HARDENED_INDEX_FLAG: u32 = 1 << 31;
+
+/// Scheme the keys of an account are derived with, from the account's index
+/// in its parent BIP32 path
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub enum DerivationScheme {
+	/// Normal account index, `m/k/0`, as all accounts were derived before
+	Standard,
+	/// Hardened account index, `m/k'/0`. A leaked output key and the account's
+	/// public key don't give away the other keys of the wallet.
+	Hardened,
+}
+
+impl DerivationScheme {
+	/// Scheme of an account path
+	pub fn of(path: &Identifier) -> DerivationScheme {
+		match <u32>::from(path.to_path().path[0]) & HARDENED_INDEX_FLAG {
+			0 => DerivationScheme::Standard,
+			_ => DerivationScheme::Hardened,
+		}
+	}
+
+	/// Account path of the given index
+	pub fn account_path(&self, index: u32) -> Identifier {
+		let index = match *self {
+			DerivationScheme::Standard => index,
+			DerivationScheme::Hardened => index | HARDENED_INDEX_FLAG,
+		};
+		ExtKeychain::derive_key_id(2, index, 0, 0, 0)
+	}
+
+	/// Index of an account path, whatever its scheme
+	pub fn account_index(path: &Identifier) -> u32 {
+		<u32>::from(path.to_path().path[0]) & !HARDENED_INDEX_FLAG
+	}
+}
+
+impl Default for DerivationScheme {
+	fn default() -> DerivationScheme {
+		DerivationScheme::Standard
+	}
+}
+
+impl fmt::Display for DerivationScheme {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match *self {
+			DerivationScheme::Standard => write!(f, "Standard"),
+			DerivationScheme::Hardened => write!(f, "Hardened"),
+		}
+	}
+}
+
 impl ser::Writeable for AcctPathMapping {
 	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
 		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
@@ -815,6 +1759,437 @@ impl ser::Readable for AcctPathMapping {
 	}
 }
 
+/// Soft hold on part of an account's spendable balance, e.g. for a quoted checkout
+/// session. Holds aren't tied to outputs, sends just can't spend the held amount.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct BalanceHold {
+	/// Reference given by the caller, unique in the account
+	pub reference: String,
+	/// Account the amount is held in
+	pub parent_key_id: Identifier,
+	/// Amount held
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount: u64,
+	/// When the hold was created
+	pub created: DateTime<Utc>,
+	/// When the hold expires
+	pub expires: DateTime<Utc>,
+}
+
+impl BalanceHold {
+	/// Whether the hold still applies
+	pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+		self.expires > now
+	}
+}
+
+impl ser::Writeable for BalanceHold {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for BalanceHold {
+	fn read(reader: &mut dyn ser::Reader) -> Result<BalanceHold, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+/// Filter on the origins incoming slates are received from, such as
+/// `keybase:<username>`. Origins are compared case insensitively.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct SourceFilter {
+	/// Origins whose slates are rejected
+	pub blocklist: Vec<String>,
+	/// If not empty, only slates from these origins are received
+	pub allowlist: Vec<String>,
+	/// Number of slates rejected, by origin
+	pub hits: HashMap<String, u64>,
+}
+
+impl SourceFilter {
+	/// Normalized form of an origin
+	pub fn normalize(origin: &str) -> String {
+		origin.trim().to_lowercase()
+	}
+
+	/// Whether slates from the origin are received
+	pub fn allows(&self, origin: &str) -> bool {
+		let origin = SourceFilter::normalize(origin);
+		!self.blocklist.contains(&origin)
+			&& (self.allowlist.is_empty() || self.allowlist.contains(&origin))
+	}
+}
+
+impl ser::Writeable for SourceFilter {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for SourceFilter {
+	fn read(reader: &mut dyn ser::Reader) -> Result<SourceFilter, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+/// Incoming slate that couldn't be received, kept so that payments a sender
+/// claims to have made can be traced
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct FailedReceive {
+	/// Id of the slate
+	pub slate_id: Uuid,
+	/// Where the slate came from, e.g. `keybase:alice`, if known
+	pub origin: Option<String>,
+	/// Amount of the slate
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub amount: u64,
+	/// Why the slate was rejected
+	pub reason: String,
+	/// When the slate was rejected
+	pub created: DateTime<Utc>,
+}
+
+impl ser::Writeable for FailedReceive {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for FailedReceive {
+	fn read(reader: &mut dyn ser::Reader) -> Result<FailedReceive, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+/// Send prepared by [`init_send_tx`](../api_impl/owner/fn.init_send_tx.html) with
+/// send arguments, its outputs locked, kept until the slate is sent, finalized
+/// and posted so that a failed dispatch can be retried. A slate that failed to
+/// be delivered may still have reached the recipient, so its transaction is
+/// cancelled and the send retried with a new slate.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PendingDispatch {
+	/// Id of the slate
+	pub slate_id: Uuid,
+	/// The slate, as built by the wallet
+	pub slate: Slate,
+	/// How to send, finalize and post the slate
+	pub send_args: InitTxSendArgs,
+	/// The slate returned by the recipient once they got it, or once finalized
+	pub response: Option<Slate>,
+	/// Whether the returned slate was finalized
+	pub finalized: bool,
+	/// Dispatch attempts so far
+	pub attempts: u32,
+	/// Error of the last failed attempt
+	pub last_error: Option<String>,
+	/// When the send was prepared
+	pub created: DateTime<Utc>,
+	/// Arguments the send was prepared with, to build a new slate from
+	#[serde(default)]
+	pub args: Option<InitTxArgs>,
+	/// Whether the transaction of the slate was cancelled after a failed delivery,
+	/// its outputs unlocked
+	#[serde(default)]
+	pub cancelled: bool,
+}
+
+impl ser::Writeable for PendingDispatch {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for PendingDispatch {
+	fn read(reader: &mut dyn ser::Reader) -> Result<PendingDispatch, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+/// Address handed out for a single payment to the wallet. Mimblewimble has no
+/// outputs a sender can create alone for the recipient, so the payment is still
+/// interactive: the address is the url of the foreign listener of the wallet
+/// with the id of the address as fragment, which the sender adds to the slate
+/// in an attachment. The wallet receives into the account the address was made
+/// for, refuses a second payment to it, and marks it used once the payment
+/// confirms.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OnetimeAddress {
+	/// Id of the address
+	pub id: Uuid,
+	/// The address, `<foreign listener url>#<id>`
+	pub address: String,
+	/// Account the payment is received into
+	pub parent_key_id: Identifier,
+	/// Optional label, e.g. an invoice number
+	pub label: Option<String>,
+	/// When the address was handed out
+	pub created: DateTime<Utc>,
+	/// When the address stops accepting a payment, if ever
+	pub expires: Option<DateTime<Utc>>,
+	/// Id of the slate paying to the address, once received
+	pub tx_slate_id: Option<Uuid>,
+	/// Amount paid to the address, once received
+	pub amount: Option<u64>,
+	/// Whether the payment confirmed
+	pub confirmed: bool,
+}
+
+impl OnetimeAddress {
+	/// Address of a one-time address id, for the foreign listener at the url
+	pub fn format(listener_url: &str, id: &Uuid) -> String {
+		format!("{}#{}", listener_url.trim_end_matches('/'), id.to_simple())
+	}
+
+	/// Id of the one-time address the destination of a send is, if any
+	pub fn parse_id(dest: &str) -> Option<Uuid> {
+		let mut parts = dest.rsplitn(2, '#');
+		match (parts.next(), parts.next()) {
+			(Some(id), Some(_)) => Uuid::parse_str(id).ok(),
+			_ => None,
+		}
+	}
+
+	/// Whether the address can't be paid to anymore
+	pub fn is_expired(&self) -> bool {
+		self.expires.map(|e| e < Utc::now()).unwrap_or(false)
+	}
+}
+
+impl ser::Writeable for OnetimeAddress {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for OnetimeAddress {
+	fn read(reader: &mut dyn ser::Reader) -> Result<OnetimeAddress, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+/// Key given to senders of a business unit, so the slates they send to the
+/// foreign listener with it are received into the key's account rather than
+/// the active one. Only the hash of the key is stored.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ForeignApiKey {
+	/// Id of the key
+	pub id: Uuid,
+	/// Hash of the key, see [`ForeignApiKey::hash`](#method.hash)
+	pub key_hash: String,
+	/// Account slates sent with the key are received into
+	pub parent_key_id: Identifier,
+	/// Optional label, e.g. the business unit the key was given to
+	pub label: Option<String>,
+	/// When the key was created
+	pub created: DateTime<Utc>,
+}
+
+impl ForeignApiKey {
+	/// Hash of a key, as it's stored
+	pub fn hash(key: &str) -> String {
+		grin_util::to_hex(blake2b(32, &[], key.as_bytes()).as_bytes().to_vec())
+	}
+}
+
+impl ser::Writeable for ForeignApiKey {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for ForeignApiKey {
+	fn read(reader: &mut dyn ser::Reader) -> Result<ForeignApiKey, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+/// Whether an archived slate was received or sent by the wallet
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub enum SlateDirection {
+	/// Slate received by the wallet, as it was before the wallet processed it
+	Inbound,
+	/// Slate sent by the wallet, as the wallet returned it
+	Outbound,
+}
+
+/// Version of a slate the wallet received or sent at a step of a transaction,
+/// kept in the slate archive so exchanges that failed over several rounds can
+/// be looked into
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArchivedSlate {
+	/// Id of the slate
+	pub slate_id: Uuid,
+	/// Whether the slate was received or sent
+	pub direction: SlateDirection,
+	/// API call the slate went through, e.g. `receive_tx`
+	pub step: String,
+	/// The slate, as received or sent
+	pub slate: Slate,
+	/// When the slate was archived
+	pub created: DateTime<Utc>,
+}
+
+impl ser::Writeable for ArchivedSlate {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for ArchivedSlate {
+	fn read(reader: &mut dyn ser::Reader) -> Result<ArchivedSlate, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+/// Kind of a change to the wallet data
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub enum ChangeOp {
+	/// Data added or updated
+	Save,
+	/// Data removed
+	Delete,
+}
+
+/// Kind of wallet data a change applies to
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub enum ChangeEntity {
+	/// An output, by key id
+	Output,
+	/// A transaction log entry, by account and log id
+	TxLogEntry,
+	/// An account, by label
+	Account,
+	/// Next child index of an account
+	ChildIndex,
+	/// Last height an account was confirmed at
+	ConfirmedHeight,
+	/// Private context of a transaction, by slate id and participant
+	PrivateContext,
+	/// A balance hold, by account and reference
+	BalanceHold,
+	/// Filter on the origins of incoming slates
+	SourceFilter,
+	/// A record of a rejected incoming slate, by slate id
+	FailedReceive,
+	/// A version of a slate in the slate archive, by slate id
+	ArchivedSlate,
+	/// Vault settings of an account
+	VaultSettings,
+	/// Freeze state of the wallet
+	FreezeState,
+	/// Second factor of the owner API
+	TotpSettings,
+	/// A wallet event, by sequence number
+	WalletEvent,
+	/// Event offset of a consumer, by consumer
+	EventOffset,
+	/// A send prepared and not dispatched yet, by slate id
+	PendingDispatch,
+	/// A one-time address, by id
+	OnetimeAddress,
+	/// A foreign API key, by id
+	ForeignApiKey,
+	/// An approval token, by id
+	ApprovalToken,
+}
+
+/// Entry of the change journal, recording a change to the wallet data. The
+/// journal keeps the latest changes only, older entries are compacted away.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChangeRecord {
+	/// Value of the change counter of the wallet after the change
+	pub counter: u64,
+	/// Kind of change
+	pub op: ChangeOp,
+	/// Kind of data changed
+	pub entity: ChangeEntity,
+	/// Identifier of the data changed, within its kind
+	pub id: String,
+	/// When the change was made
+	pub time: DateTime<Utc>,
+}
+
+impl ser::Writeable for ChangeRecord {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for ChangeRecord {
+	fn read(reader: &mut dyn ser::Reader) -> Result<ChangeRecord, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+/// Time lock of a vault account. The kernels of the account's sends can't be mined
+/// before the lock has passed, which leaves the owner that long to cancel a send by
+/// spending its inputs elsewhere. Shortening the lock only takes effect once the
+/// current lock has passed, so a stolen wallet can't just turn it off.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct VaultSettings {
+	/// The vault account
+	pub parent_key_id: Identifier,
+	/// Number of blocks the kernels of the account's sends are locked for
+	pub lock_blocks: u64,
+	/// Shorter lock, 0 turning the vault off, waiting to take effect
+	pub pending_lock_blocks: Option<u64>,
+	/// Height the pending lock takes effect at
+	pub pending_height: u64,
+}
+
+impl VaultSettings {
+	/// Number of blocks the sends are locked for at the given height
+	pub fn lock_blocks_at(&self, height: u64) -> u64 {
+		match self.pending_lock_blocks {
+			Some(l) if height >= self.pending_height => l,
+			_ => self.lock_blocks,
+		}
+	}
+}
+
+impl ser::Writeable for VaultSettings {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for VaultSettings {
+	fn read(reader: &mut dyn ser::Reader) -> Result<VaultSettings, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
+/// Freeze of a wallet suspected to be compromised. While frozen, the wallet
+/// receives no slates and serves no owner API request changing it.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct FreezeState {
+	/// When the wallet was frozen
+	pub since: DateTime<Utc>,
+}
+
+impl ser::Writeable for FreezeState {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for FreezeState {
+	fn read(reader: &mut dyn ser::Reader) -> Result<FreezeState, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
+
 /// Dummy wrapper for the hex-encoded serialized transaction.
 #[derive(Serialize, Deserialize)]
 pub struct TxWrapper {
@@ -834,3 +2209,44 @@ pub struct CbData {
 	/// Key Id
 	pub key_id: Option<Identifier>,
 }
+
+/// Kind of change to a transaction recorded in the wallet event queue
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub enum WalletEventType {
+	/// A transaction receiving funds was created, by receiving a slate or
+	/// issuing an invoice
+	Received,
+	/// A transaction was confirmed on chain
+	Confirmed,
+	/// A transaction was cancelled
+	Cancelled,
+}
+
+/// Entry of the wallet event queue. Events are numbered in the order they're
+/// recorded and are kept until the queue is full, whether consumers have
+/// acknowledged them or not.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct WalletEvent {
+	/// Position of the event in the queue, starting at 1
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub seq: u64,
+	/// What happened
+	pub event_type: WalletEventType,
+	/// The transaction, as of the event
+	pub tx: TxLogEntry,
+	/// When the event was recorded
+	pub created: DateTime<Utc>,
+}
+
+impl ser::Writeable for WalletEvent {
+	fn write<W: ser::Writer>(&self, writer: &mut W) -> Result<(), ser::Error> {
+		writer.write_bytes(&serde_json::to_vec(self).map_err(|_| ser::Error::CorruptedData)?)
+	}
+}
+
+impl ser::Readable for WalletEvent {
+	fn read(reader: &mut dyn ser::Reader) -> Result<WalletEvent, ser::Error> {
+		let data = reader.read_bytes_len_prefix()?;
+		serde_json::from_slice(&data[..]).map_err(|_| ser::Error::CorruptedData)
+	}
+}
@@ -0,0 +1,379 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! View-only bundles of the wallet state, to check the balance a wallet reports
+//! against a node without its spend keys. A bundle holds the commitments of the
+//! unspent outputs of the wallet along with its rewind hash, which lets the range
+//! proofs of the outputs be rewound to recover their values, but not the outputs
+//! be spent. A wallet given a bundle audits it against its own node and signs an
+//! attestation of the result, which anyone can verify.
+//!
+//! The root public key of a wallet, from which its rewind hash is computed, lets a
+//! watch-only wallet without the seed find the outputs of the wallet on chain and
+//! follow its balance.
+
+use crate::blake2::blake2b::blake2b;
+use crate::error::{Error, ErrorKind};
+use crate::grin_core::libtx::proof::{self, ProofBuild};
+use crate::grin_core::libtx::{self, aggsig, secp_ser};
+use crate::grin_keychain::{Identifier, Keychain, SwitchCommitmentType};
+use crate::grin_util::secp::key::{PublicKey, SecretKey};
+use crate::grin_util::secp::pedersen::{Commitment, ProofMessage, RangeProof};
+use crate::grin_util::secp::{self, Secp256k1, Signature};
+use crate::grin_util::{self, static_secp_instance};
+use chrono::{DateTime, Utc};
+use serde_json;
+
+/// Version of the view-only bundles written by this wallet
+pub const VIEW_ONLY_BUNDLE_VERSION: u16 = 1;
+
+/// Index of the key signing the attestations of a wallet, at depth 1 of its
+/// tree, where no output is derived
+const ATTESTATION_KEY_INDEX: u32 = u32::max_value();
+
+/// Unspent output of a wallet in a view-only bundle
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ViewOnlyOutput {
+	/// Commitment of the output
+	#[serde(
+		serialize_with = "secp_ser::as_hex",
+		deserialize_with = "secp_ser::commitment_from_hex"
+	)]
+	pub commit: Commitment,
+	/// Value of the output, as reported by the wallet
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub value: u64,
+	/// Height of the block the output was confirmed in
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub height: u64,
+	/// Whether the output is a coinbase
+	pub is_coinbase: bool,
+}
+
+/// Unspent outputs of a wallet and the rewind hash to check their values, without
+/// the keys to spend them
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ViewOnlyBundle {
+	/// Version of the bundle
+	pub version: u16,
+	/// Fingerprint of the wallet seed
+	pub fingerprint: String,
+	/// Hex of the hash the range proofs of the wallet outputs are rewound with
+	pub rewind_hash: String,
+	/// Height of the chain the wallet was refreshed to when exporting
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub height: u64,
+	/// When the bundle was exported
+	pub created: DateTime<Utc>,
+	/// Total value of the outputs, as reported by the wallet
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub total: u64,
+	/// Unspent outputs of the wallet
+	pub outputs: Vec<ViewOnlyOutput>,
+}
+
+impl ViewOnlyBundle {
+	/// Hex of the hash of the bundle, which attestations refer to
+	pub fn hash(&self) -> Result<String, Error> {
+		let data = serde_json::to_vec(self).map_err(|e| ErrorKind::Format(e.to_string()))?;
+		Ok(grin_util::to_hex(
+			blake2b(32, &[], &data).as_bytes().to_vec(),
+		))
+	}
+
+	/// Range proof builder rewinding the proofs of the outputs of the bundle
+	pub fn proof_builder(&self) -> Result<ViewOnlyProofBuilder, Error> {
+		ViewOnlyProofBuilder::new(&self.rewind_hash)
+	}
+}
+
+/// Root public key of a wallet, all a watch-only wallet following the outputs of
+/// that wallet holds
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WatchOnlyKey {
+	/// Hex of the compressed root public key
+	pub root_public_key: String,
+}
+
+impl WatchOnlyKey {
+	/// Key from the hex of a compressed root public key, as exported by the wallet
+	pub fn from_hex(root_public_key: &str) -> Result<WatchOnlyKey, Error> {
+		let bytes = grin_util::from_hex(root_public_key.trim().to_owned())
+			.map_err(|_| ErrorKind::Format("Invalid root public key".to_owned()))?;
+		let secp = static_secp_instance();
+		let secp = secp.lock();
+		let key = PublicKey::from_slice(&secp, &bytes)
+			.map_err(|_| ErrorKind::Format("Invalid root public key".to_owned()))?;
+		Ok(WatchOnlyKey {
+			root_public_key: grin_util::to_hex(key.serialize_vec(&secp, true).to_vec()),
+		})
+	}
+
+	/// Fingerprint of the seed of the wallet of the key
+	pub fn fingerprint(&self) -> Result<String, Error> {
+		let hash = blake2b(8, &[], &self.bytes()?);
+		Ok(grin_util::to_hex(hash.as_bytes().to_vec()))
+	}
+
+	/// Hex of the hash the range proofs of the outputs of the wallet are rewound with
+	pub fn rewind_hash(&self) -> Result<String, Error> {
+		let hash = blake2b(32, &[], &self.bytes()?);
+		Ok(grin_util::to_hex(hash.as_bytes().to_vec()))
+	}
+
+	/// Range proof builder rewinding the proofs of the outputs of the wallet
+	pub fn proof_builder(&self) -> Result<ViewOnlyProofBuilder, Error> {
+		ViewOnlyProofBuilder::new(&self.rewind_hash()?)
+	}
+
+	fn bytes(&self) -> Result<Vec<u8>, Error> {
+		grin_util::from_hex(self.root_public_key.clone())
+			.map_err(|_| ErrorKind::Format("Invalid root public key".to_owned()).into())
+	}
+}
+
+/// Result of the audit of an output of a view-only bundle
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ViewOnlyOutputAudit {
+	/// Commitment of the output
+	#[serde(
+		serialize_with = "secp_ser::as_hex",
+		deserialize_with = "secp_ser::commitment_from_hex"
+	)]
+	pub commit: Commitment,
+	/// Value of the output reported in the bundle
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub reported_value: u64,
+	/// Value recovered from the range proof of the output on the node, if any
+	#[serde(with = "secp_ser::opt_string_or_u64")]
+	pub verified_value: Option<u64>,
+	/// Why the output doesn't count towards the verified total, if it doesn't
+	pub issue: Option<String>,
+}
+
+/// Signed result of the audit of a view-only bundle against a node
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ViewOnlyAttestation {
+	/// Hash of the audited bundle
+	pub bundle_hash: String,
+	/// Fingerprint of the wallet of the bundle
+	pub fingerprint: String,
+	/// Height of the chain of the node the bundle was audited against
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub height: u64,
+	/// When the bundle was audited
+	pub audited: DateTime<Utc>,
+	/// Total value reported in the bundle
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub reported_total: u64,
+	/// Total value of the outputs unspent on the node, as recovered from their
+	/// range proofs
+	#[serde(with = "secp_ser::string_or_u64")]
+	pub verified_total: u64,
+	/// Audit of each output of the bundle
+	pub outputs: Vec<ViewOnlyOutputAudit>,
+	/// Public key of the auditing wallet
+	#[serde(with = "secp_ser::pubkey_serde")]
+	pub public_key: PublicKey,
+	/// Signature of the attestation by the auditing wallet
+	#[serde(with = "secp_ser::sig_serde")]
+	pub signature: Signature,
+}
+
+impl ViewOnlyAttestation {
+	/// Attestation of the audit of a bundle against a node at the given height,
+	/// signed with the attestation key of the auditing wallet
+	pub fn new<K>(
+		keychain: &K,
+		bundle: &ViewOnlyBundle,
+		height: u64,
+		outputs: Vec<ViewOnlyOutputAudit>,
+	) -> Result<ViewOnlyAttestation, Error>
+	where
+		K: Keychain,
+	{
+		let key_id = K::derive_key_id(1, ATTESTATION_KEY_INDEX, 0, 0, 0);
+		let key = keychain.derive_key(0, &key_id, &SwitchCommitmentType::None)?;
+		let public_key = PublicKey::from_secret_key(keychain.secp(), &key)?;
+		let verified_total = outputs
+			.iter()
+			.filter(|o| o.issue.is_none())
+			.filter_map(|o| o.verified_value)
+			.sum();
+		let mut attestation = ViewOnlyAttestation {
+			bundle_hash: bundle.hash()?,
+			fingerprint: bundle.fingerprint.clone(),
+			height,
+			audited: Utc::now(),
+			reported_total: bundle.total,
+			verified_total,
+			outputs,
+			public_key,
+			signature: Signature::from_raw_data(&[0; 64])?,
+		};
+		let msg = attestation.message()?;
+		attestation.signature =
+			aggsig::sign_single(keychain.secp(), &msg, &key, None, Some(&public_key))?;
+		Ok(attestation)
+	}
+
+	/// Whether all the outputs of the bundle are unspent with the reported values
+	pub fn is_clean(&self) -> bool {
+		self.outputs.iter().all(|o| o.issue.is_none())
+	}
+
+	/// Message signed by the auditing wallet
+	fn message(&self) -> Result<secp::Message, Error> {
+		let data = serde_json::to_vec(&(
+			&self.bundle_hash,
+			&self.fingerprint,
+			self.height,
+			&self.audited,
+			self.reported_total,
+			self.verified_total,
+			&self.outputs,
+		))
+		.map_err(|e| ErrorKind::Format(e.to_string()))?;
+		let hashed = blake2b(secp::constants::MESSAGE_SIZE, &[], &data);
+		Ok(secp::Message::from_slice(hashed.as_bytes())?)
+	}
+
+	/// Check the attestation was signed by the holder of its public key, and
+	/// refers to the given bundle if any
+	pub fn verify(&self, bundle: Option<&ViewOnlyBundle>) -> Result<(), Error> {
+		if let Some(b) = bundle {
+			if b.hash()? != self.bundle_hash {
+				let msg = "Attestation of another bundle".to_owned();
+				return Err(ErrorKind::Signature(msg))?;
+			}
+		}
+		let secp = static_secp_instance();
+		let secp = secp.lock();
+		let valid = aggsig::verify_single(
+			&secp,
+			&self.signature,
+			&self.message()?,
+			None,
+			&self.public_key,
+			Some(&self.public_key),
+			false,
+		);
+		if !valid {
+			return Err(ErrorKind::Signature(
+				"Invalid attestation signature".to_owned(),
+			))?;
+		}
+		Ok(())
+	}
+}
+
+/// Root public key of a wallet, which a watch-only wallet is created from
+pub fn watch_only_key<K>(keychain: &K) -> Result<WatchOnlyKey, Error>
+where
+	K: Keychain,
+{
+	let root_key = keychain.derive_key(0, &K::root_key_id(), &SwitchCommitmentType::None)?;
+	let root_pub = PublicKey::from_secret_key(keychain.secp(), &root_key)?;
+	Ok(WatchOnlyKey {
+		root_public_key: grin_util::to_hex(root_pub.serialize_vec(keychain.secp(), true).to_vec()),
+	})
+}
+
+/// Hex of the hash the range proofs of the outputs of a wallet are rewound with,
+/// as made by the proof builder of the wallet. It lets the values of the outputs
+/// be recovered, but not their keys.
+pub fn rewind_hash<K>(keychain: &K) -> Result<String, Error>
+where
+	K: Keychain,
+{
+	watch_only_key(keychain)?.rewind_hash()
+}
+
+/// Range proof builder only able to rewind the proofs of a wallet, from its
+/// rewind hash
+pub struct ViewOnlyProofBuilder {
+	rewind_hash: Vec<u8>,
+}
+
+impl ViewOnlyProofBuilder {
+	/// Builder rewinding the proofs made with the rewind hash, given in hex
+	pub fn new(rewind_hash: &str) -> Result<ViewOnlyProofBuilder, Error> {
+		let rewind_hash = grin_util::from_hex(rewind_hash.to_owned())
+			.map_err(|_| ErrorKind::Format("Invalid rewind hash".to_owned()))?;
+		Ok(ViewOnlyProofBuilder { rewind_hash })
+	}
+
+	/// Value of the output of the commitment, recovered from its range proof,
+	/// if the proof was made by the wallet of the rewind hash
+	pub fn rewind_value(
+		&self,
+		secp: &Secp256k1,
+		commit: Commitment,
+		proof: RangeProof,
+	) -> Result<Option<u64>, Error> {
+		let res = proof::rewind(secp, self, commit, None, proof)?;
+		Ok(res.map(|(value, _, _)| value))
+	}
+}
+
+impl ProofBuild for ViewOnlyProofBuilder {
+	fn rewind_nonce(
+		&self,
+		secp: &Secp256k1,
+		commit: &Commitment,
+	) -> Result<SecretKey, libtx::Error> {
+		let res = blake2b(32, &commit.0, &self.rewind_hash);
+		SecretKey::from_slice(secp, res.as_bytes()).map_err(|e| libtx::ErrorKind::Secp(e).into())
+	}
+
+	fn private_nonce(&self, _: &Secp256k1, _: &Commitment) -> Result<SecretKey, libtx::Error> {
+		let msg = "View-only builder can't create range proofs".to_owned();
+		Err(libtx::ErrorKind::RangeProof(msg).into())
+	}
+
+	fn proof_message(
+		&self,
+		_: &Secp256k1,
+		_: &Identifier,
+		_: &SwitchCommitmentType,
+	) -> Result<ProofMessage, libtx::Error> {
+		let msg = "View-only builder can't create range proofs".to_owned();
+		Err(libtx::ErrorKind::RangeProof(msg).into())
+	}
+
+	fn check_output(
+		&self,
+		_: &Secp256k1,
+		_: &Commitment,
+		_: u64,
+		message: ProofMessage,
+	) -> Result<Option<(Identifier, SwitchCommitmentType)>, libtx::Error> {
+		// The commitment can't be checked without the keys, the message of a
+		// proof of the wallet is enough
+		let msg = message.as_bytes();
+		if msg.len() != 20 || msg[0] != 0 || msg[1] != 0 {
+			return Ok(None);
+		}
+		let switch = match msg[2] {
+			0 => SwitchCommitmentType::None,
+			1 => SwitchCommitmentType::Regular,
+			_ => return Ok(None),
+		};
+		let depth = u8::min(msg[3], 4);
+		Ok(Some((
+			Identifier::from_serialized_path(depth, &msg[4..]),
+			switch,
+		)))
+	}
+}
@@ -0,0 +1,55 @@
+// Copyright 2019 The Grin Developers
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Limits on the slates read from other wallets
+use grin_wallet_libwallet::{check_json_depth, ErrorKind, Slate, MAX_JSON_DEPTH};
+use serde_json::Value;
+
+fn is_limit_exceeded(res: Result<Slate, grin_wallet_libwallet::Error>) -> bool {
+	match res {
+		Err(e) => match e.kind() {
+			ErrorKind::LimitExceeded { .. } => true,
+			_ => false,
+		},
+		Ok(_) => false,
+	}
+}
+
+#[test]
+fn slate_limits() {
+	let v2 = include_str!("slates/v2.slate");
+	assert!(Slate::deserialize_upgrade(&v2).is_ok());
+
+	// Nesting deeper than the limit, brackets in strings don't count
+	let nested = format!(
+		"{}{}",
+		"[".repeat(MAX_JSON_DEPTH + 8),
+		"]".repeat(MAX_JSON_DEPTH + 8)
+	);
+	assert!(check_json_depth(nested.as_bytes(), MAX_JSON_DEPTH).is_err());
+	assert!(is_limit_exceeded(Slate::deserialize_upgrade(&nested)));
+	let quoted = format!("{{\"a\": \"{}\"}}", "[".repeat(MAX_JSON_DEPTH + 8));
+	assert!(check_json_depth(quoted.as_bytes(), MAX_JSON_DEPTH).is_ok());
+
+	// Message longer than the limit
+	let mut slate: Value = serde_json::from_str(v2).unwrap();
+	slate["participant_data"][0]["message"] = Value::String("a".repeat(2000));
+	let res = Slate::deserialize_upgrade(&slate.to_string());
+	assert!(is_limit_exceeded(res));
+
+	// Too many participants
+	let mut slate: Value = serde_json::from_str(v2).unwrap();
+	slate["num_participants"] = Value::from(17);
+	let res = Slate::deserialize_upgrade(&slate.to_string());
+	assert!(is_limit_exceeded(res));
+}
@@ -20,12 +20,13 @@ extern crate clap;
 #[macro_use]
 extern crate log;
 use crate::core::global;
-use crate::util::init_logger;
 use clap::App;
 use grin_wallet_config as config;
-use grin_wallet_impls::HTTPNodeClient;
+use grin_wallet_impls::{
+	init_logger, HTTPNodeClient, DEFAULT_OUTPUTS_QUERY_PARALLELISM, DEFAULT_OUTPUTS_QUERY_RETRIES,
+};
+use grin_wallet_libwallet::{catalog_locale, DEFAULT_LOCALE};
 use grin_wallet_util::grin_core as core;
-use grin_wallet_util::grin_util as util;
 use std::env;
 
 use grin_wallet::cmd;
@@ -104,7 +105,9 @@ fn real_main() -> i32 {
 
 	// Load logging config
 	let l = config.members.as_mut().unwrap().logging.clone().unwrap();
-	init_logger(Some(l));
+	init_logger(Some(l)).unwrap_or_else(|e| {
+		panic!("Error initializing logging: {}", e);
+	});
 	info!(
 		"Using wallet configuration file at {}",
 		config.config_file_path.as_ref().unwrap().to_str().unwrap()
@@ -125,7 +128,39 @@ fn real_main() -> i32 {
 	);
 
 	let wallet_config = config.clone().members.unwrap().wallet;
-	let node_client = HTTPNodeClient::new(&wallet_config.check_node_api_http_addr, None);
+	if let Some(l) = wallet_config.locale.as_ref() {
+		if catalog_locale(Some(l)).is_none() && !l.to_lowercase().starts_with(DEFAULT_LOCALE) {
+			warn!(
+				"No message catalog for locale {}, using {}",
+				l, DEFAULT_LOCALE
+			);
+		}
+	}
+	let node_client = HTTPNodeClient::new_with_http2(
+		&wallet_config.check_node_api_http_addr,
+		None,
+		wallet_config.chain_type(),
+		wallet_config.node_api_http2.unwrap_or(false),
+	);
+	let mut node_client = match node_client {
+		Ok(c) => c,
+		Err(e) => {
+			error!("{}", e);
+			return 1;
+		}
+	};
+	node_client.set_outputs_query_limits(
+		wallet_config
+			.node_outputs_query_parallelism
+			.unwrap_or(DEFAULT_OUTPUTS_QUERY_PARALLELISM),
+		wallet_config
+			.node_outputs_query_retries
+			.unwrap_or(DEFAULT_OUTPUTS_QUERY_RETRIES),
+	);
+	node_client.set_request_rate_limit(
+		wallet_config.node_requests_per_second,
+		wallet_config.node_request_burst,
+	);
 
 	cmd::wallet_command(&args, config, node_client)
 }
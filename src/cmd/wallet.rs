@@ -31,7 +31,8 @@ where
 	C: NodeClient + 'static,
 {
 	// just get defaults from the global config
-	let wallet_config = config.members.unwrap().wallet;
+	let members = config.members.unwrap();
+	let wallet_config = members.wallet;
 
 	// Check the node version info, and exit with report if we're not compatible
 	//let mut node_client = HTTPNodeClient::new(&wallet_config.check_node_api_http_addr, None);
@@ -57,7 +58,14 @@ where
 	}
 	// ... if node isn't available, allow offline functions
 
-	let res = wallet_args::wallet_command(wallet_args, wallet_config, node_client, false, |_| {});
+	let res = wallet_args::wallet_command(
+		wallet_args,
+		wallet_config,
+		members.audit_log,
+		node_client,
+		false,
+		|_| {},
+	);
 
 	// we need to give log output a chance to catch up before exiting
 	thread::sleep(Duration::from_millis(100));
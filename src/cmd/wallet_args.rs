@@ -19,19 +19,23 @@ use crate::util::{Mutex, ZeroingString};
 /// Argument parsing and error handling for wallet commands
 use clap::ArgMatches;
 use failure::Fail;
-use grin_wallet_config::WalletConfig;
-use grin_wallet_controller::command;
+use grin_wallet_config::{AuditLogConfig, WalletConfig};
+use grin_wallet_controller::controller::ListenerShutdown;
+use grin_wallet_controller::daemon::{self, DaemonArgs, DaemonHandle};
+use grin_wallet_controller::{command, display};
 use grin_wallet_controller::{Error, ErrorKind};
-use grin_wallet_impls::{DefaultLCProvider, DefaultWalletImpl};
+use grin_wallet_impls::{DefaultLCProvider, DefaultWalletImpl, HttpPriceProvider};
 use grin_wallet_impls::{PathToSlate, SlateGetter as _};
 use grin_wallet_libwallet::Slate;
-use grin_wallet_libwallet::{IssueInvoiceTxArgs, NodeClient, WalletInst, WalletLCProvider};
+use grin_wallet_libwallet::{
+	DerivationScheme, IssueInvoiceTxArgs, NodeClient, PriceSource, WalletInst, WalletLCProvider,
+};
 use grin_wallet_util::grin_core as core;
 use grin_wallet_util::grin_core::core::amount_to_hr_string;
-use grin_wallet_util::grin_core::global;
 use grin_wallet_util::grin_keychain as keychain;
 use linefeed::terminal::Signal;
 use linefeed::{Interface, ReadResult};
+use log::warn;
 use rpassword;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -174,7 +178,7 @@ fn prompt_pay_invoice(slate: &Slate, method: &str, dest: &str) -> Result<bool, P
 		"* {} of your wallet funds will be added to the transaction to pay this invoice.",
 		amount
 	);
-	if method == "http" {
+	if method == "http" || method == "tor" {
 		println!("* The resulting transaction will IMMEDIATELY be sent to the wallet listening at: '{}'.", dest);
 	} else {
 		println!("* The resulting transaction will be saved to the file '{}', which you can manually send back to the invoice creator.", dest);
@@ -229,9 +233,29 @@ where
 		as Box<dyn WalletInst<'static, L, C, K>>;
 	let lc = wallet.lc_provider().unwrap();
 	let _ = lc.set_top_level_directory(&config.data_file_dir);
+	if let (Some(currency), Some(url)) = (
+		config.accounting_currency.as_ref(),
+		config.price_provider_url.as_ref(),
+	) {
+		match HttpPriceProvider::new(url) {
+			Ok(p) => lc.set_price_source(Some(Arc::new(PriceSource::new(currency, Arc::new(p))))),
+			Err(e) => warn!("Coins won't be valued in {}: {}", currency, e),
+		}
+	}
+	lc.set_wallet_config(config);
 	Ok(Arc::new(Mutex::new(wallet)))
 }
 
+/// Shutdown handle of a listener, triggered by SIGINT or SIGTERM so that the
+/// listener stops gracefully instead of being killed mid-request
+fn shutdown_on_signal() -> Result<ListenerShutdown, Error> {
+	let shutdown = ListenerShutdown::new();
+	let s = shutdown.clone();
+	ctrlc::set_handler(move || s.request())
+		.map_err(|e| ErrorKind::GenericError(format!("Unable to set the signal handler: {}", e)))?;
+	Ok(shutdown)
+}
+
 // parses a required value, or throws error with message otherwise
 fn parse_required<'a>(args: &'a ArgMatches, name: &str) -> Result<&'a str, ParseError> {
 	let arg = args.value_of(name);
@@ -285,13 +309,7 @@ pub fn parse_global_args(
 		}
 	};
 
-	let chain_type = match config.chain_type.clone() {
-		None => {
-			let param_ref = global::CHAIN_TYPE.read();
-			param_ref.clone()
-		}
-		Some(c) => c,
-	};
+	let chain_type = config.chain_type();
 
 	Ok(command::GlobalArgs {
 		account: account.to_owned(),
@@ -300,6 +318,7 @@ pub fn parse_global_args(
 		node_api_secret: node_api_secret,
 		password: password,
 		tls_conf: tls_conf,
+		audit_log: None,
 	})
 }
 
@@ -323,9 +342,12 @@ where
 		true => Some(prompt_recovery_phrase(wallet)?),
 		false => None,
 	};
+	let watch_only = args.value_of("watch_only").map(|k| k.to_owned());
 
 	if recovery_phrase.is_some() {
 		println!("Please provide a new password for the recovered wallet");
+	} else if watch_only.is_some() {
+		println!("Please enter a password for your new watch-only wallet");
 	} else {
 		println!("Please enter a password for your new wallet");
 	}
@@ -340,6 +362,7 @@ where
 		password: password,
 		config: config.clone(),
 		recovery_phrase: recovery_phrase,
+		watch_only,
 		restore: false,
 	})
 }
@@ -409,15 +432,36 @@ pub fn parse_owner_api_args(
 	Ok(())
 }
 
+pub fn parse_daemon_args(args: &ArgMatches) -> Result<DaemonArgs, ParseError> {
+	let update_interval = parse_u64(parse_required(args, "update_interval")?, "update_interval")?;
+	let max_restarts = parse_u64(parse_required(args, "max_restarts")?, "max_restarts")?;
+	Ok(DaemonArgs {
+		owner_api: !args.is_present("no_owner_api"),
+		foreign_api: !args.is_present("no_foreign_api"),
+		update_interval_secs: match update_interval {
+			0 => None,
+			s => Some(s),
+		},
+		max_restarts: max_restarts as u32,
+	})
+}
+
 pub fn parse_account_args(account_args: &ArgMatches) -> Result<command::AccountArgs, ParseError> {
 	let create = match account_args.value_of("create") {
 		None => None,
 		Some(s) => Some(s.to_owned()),
 	};
-	Ok(command::AccountArgs { create: create })
+	let scheme = match account_args.is_present("hardened") {
+		true => DerivationScheme::Hardened,
+		false => DerivationScheme::Standard,
+	};
+	Ok(command::AccountArgs {
+		create: create,
+		scheme: scheme,
+	})
 }
 
-pub fn parse_send_args(args: &ArgMatches) -> Result<command::SendArgs, ParseError> {
+pub fn parse_send_args(args: &ArgMatches, min_conf: u64) -> Result<command::SendArgs, ParseError> {
 	// amount
 	let amount = parse_required(args, "amount")?;
 	let amount = core::core::amount_from_hr_string(amount);
@@ -438,9 +482,11 @@ pub fn parse_send_args(args: &ArgMatches) -> Result<command::SendArgs, ParseErro
 		false => None,
 	};
 
-	// minimum_confirmations
-	let min_c = parse_required(args, "minimum_confirmations")?;
-	let min_c = parse_u64(min_c, "minimum_confirmations")?;
+	// minimum_confirmations, the configured ones if not given
+	let min_c = match args.value_of("minimum_confirmations") {
+		Some(c) => parse_u64(c, "minimum_confirmations")?,
+		None => min_conf,
+	};
 
 	// selection_strategy
 	let selection_strategy = parse_required(args, "selection_strategy")?;
@@ -485,6 +531,12 @@ pub fn parse_send_args(args: &ArgMatches) -> Result<command::SendArgs, ParseErro
 	// fluff
 	let fluff = args.is_present("fluff");
 
+	// payjoin
+	let payjoin = args.is_present("payjoin");
+
+	// fee_from_amount
+	let fee_from_amount = args.is_present("fee_from_amount");
+
 	// max_outputs
 	let max_outputs = 500;
 
@@ -511,6 +563,9 @@ pub fn parse_send_args(args: &ArgMatches) -> Result<command::SendArgs, ParseErro
 		fluff: fluff,
 		max_outputs: max_outputs,
 		target_slate_version: target_slate_version,
+		payjoin,
+		fee_from_amount,
+		totp: args.value_of("totp").map(|c| c.to_owned()),
 	})
 }
 
@@ -538,15 +593,24 @@ pub fn parse_receive_args(receive_args: &ArgMatches) -> Result<command::ReceiveA
 
 pub fn parse_finalize_args(args: &ArgMatches) -> Result<command::FinalizeArgs, ParseError> {
 	let fluff = args.is_present("fluff");
-	let tx_file = parse_required(args, "input")?;
-
-	if !Path::new(&tx_file).is_file() {
-		let msg = format!("File {} not found.", tx_file);
-		return Err(ParseError::ArgumentError(msg));
+	parse_required(args, "input")?;
+	let tx_files: Vec<String> = args
+		.values_of("input")
+		.into_iter()
+		.flatten()
+		.map(|f| f.to_owned())
+		.collect();
+
+	for tx_file in tx_files.iter() {
+		if !Path::new(tx_file).is_file() {
+			let msg = format!("File {} not found.", tx_file);
+			return Err(ParseError::ArgumentError(msg));
+		}
 	}
 	Ok(command::FinalizeArgs {
-		input: tx_file.to_owned(),
+		inputs: tx_files,
 		fluff: fluff,
+		totp: args.value_of("totp").map(|c| c.to_owned()),
 	})
 }
 
@@ -589,12 +653,14 @@ pub fn parse_issue_invoice_args(
 			amount,
 			message,
 			target_slate_version,
+			attachments: None,
 		},
 	})
 }
 
 pub fn parse_process_invoice_args(
 	args: &ArgMatches,
+	min_conf: u64,
 	prompt: bool,
 ) -> Result<command::ProcessInvoiceArgs, ParseError> {
 	// TODO: display and prompt for confirmation of what we're doing
@@ -604,9 +670,11 @@ pub fn parse_process_invoice_args(
 		false => None,
 	};
 
-	// minimum_confirmations
-	let min_c = parse_required(args, "minimum_confirmations")?;
-	let min_c = parse_u64(min_c, "minimum_confirmations")?;
+	// minimum_confirmations, the configured ones if not given
+	let min_c = match args.value_of("minimum_confirmations") {
+		Some(c) => parse_u64(c, "minimum_confirmations")?,
+		None => min_conf,
+	};
 
 	// selection_strategy
 	let selection_strategy = parse_required(args, "selection_strategy")?;
@@ -671,18 +739,92 @@ pub fn parse_process_invoice_args(
 		dest: dest.to_owned(),
 		max_outputs: max_outputs,
 		input: tx_file.to_owned(),
+		totp: args.value_of("totp").map(|c| c.to_owned()),
 	})
 }
 
-pub fn parse_info_args(args: &ArgMatches) -> Result<command::InfoArgs, ParseError> {
-	// minimum_confirmations
-	let mc = parse_required(args, "minimum_confirmations")?;
-	let mc = parse_u64(mc, "minimum_confirmations")?;
+pub fn parse_info_args(args: &ArgMatches, min_conf: u64) -> Result<command::InfoArgs, ParseError> {
+	// minimum_confirmations, the configured ones if not given
+	let mc = match args.value_of("minimum_confirmations") {
+		Some(c) => parse_u64(c, "minimum_confirmations")?,
+		None => min_conf,
+	};
 	Ok(command::InfoArgs {
 		minimum_confirmations: mc,
 	})
 }
 
+pub fn parse_reconcile_args(args: &ArgMatches) -> Result<command::ReconcileArgs, ParseError> {
+	let input = parse_required(args, "input")?;
+	let expected_balance = match args.value_of("balance") {
+		None => None,
+		Some(b) => match core::core::amount_from_hr_string(b) {
+			Ok(a) => Some(a),
+			Err(e) => {
+				let msg = format!(
+					"Could not parse balance as a number with optional decimal point. e={}",
+					e
+				);
+				return Err(ParseError::ArgumentError(msg));
+			}
+		},
+	};
+	Ok(command::ReconcileArgs {
+		input: input.to_owned(),
+		expected_balance,
+	})
+}
+
+pub fn parse_check_recipient_args(
+	args: &ArgMatches,
+) -> Result<command::CheckRecipientArgs, ParseError> {
+	let method = parse_required(args, "method")?;
+	let dest = parse_required(args, "dest")?;
+	if method == "http" && !dest.starts_with("http://") && !dest.starts_with("https://") {
+		let msg = format!(
+			"HTTP Destination should start with http://: or https://: {}",
+			dest,
+		);
+		return Err(ParseError::ArgumentError(msg));
+	}
+	Ok(command::CheckRecipientArgs {
+		method: method.to_owned(),
+		dest: dest.to_owned(),
+	})
+}
+
+pub fn parse_view_bundle_args(args: &ArgMatches) -> Result<command::ViewBundleArgs, ParseError> {
+	let dest = parse_required(args, "dest")?;
+	Ok(command::ViewBundleArgs {
+		audit: args.value_of("audit").map(|a| a.to_owned()),
+		root_key: args.is_present("root_key"),
+		dest: dest.to_owned(),
+	})
+}
+
+pub fn parse_backup_args(args: &ArgMatches) -> Result<command::BackupArgs, ParseError> {
+	let restore = match args.value_of("restore") {
+		None => None,
+		Some(name) => {
+			let dir = parse_required(args, "dir")?;
+			Some((name.to_owned(), dir.to_owned()))
+		}
+	};
+	Ok(command::BackupArgs {
+		list: args.is_present("list"),
+		verify: args.value_of("verify").map(|v| v.to_owned()),
+		restore,
+	})
+}
+
+pub fn parse_restore_args(args: &ArgMatches) -> Result<command::RestoreArgs, ParseError> {
+	let birthday = match args.value_of("birthday") {
+		None => None,
+		Some(b) => Some(parse_u64(b, "birthday")?),
+	};
+	Ok(command::RestoreArgs { birthday })
+}
+
 pub fn parse_check_args(args: &ArgMatches) -> Result<command::CheckArgs, ParseError> {
 	let delete_unconfirmed = args.is_present("delete_unconfirmed");
 	Ok(command::CheckArgs {
@@ -731,6 +873,7 @@ pub fn parse_repost_args(args: &ArgMatches) -> Result<command::RepostArgs, Parse
 		id: tx_id.unwrap(),
 		dump_file: dump_file,
 		fluff: fluff,
+		totp: args.value_of("totp").map(|c| c.to_owned()),
 	})
 }
 
@@ -761,12 +904,14 @@ pub fn parse_cancel_args(args: &ArgMatches) -> Result<command::CancelArgs, Parse
 		tx_id: tx_id,
 		tx_slate_id: tx_slate_id,
 		tx_id_string: tx_id_string.to_owned(),
+		totp: args.value_of("totp").map(|c| c.to_owned()),
 	})
 }
 
 pub fn wallet_command<C, F>(
 	wallet_args: &ArgMatches,
 	mut wallet_config: WalletConfig,
+	audit_log: Option<AuditLogConfig>,
 	mut node_client: C,
 	test_mode: bool,
 	wallet_inst_cb: F,
@@ -788,8 +933,8 @@ where
 		>,
 	),
 {
-	if let Some(t) = wallet_config.chain_type.clone() {
-		core::global::set_mining_mode(t);
+	if wallet_config.chain_type.is_some() || wallet_config.custom_chain.is_some() {
+		core::global::set_mining_mode(wallet_config.chain_type());
 	}
 
 	if wallet_args.is_present("external") {
@@ -804,10 +949,14 @@ where
 		wallet_config.check_node_api_http_addr = sa.to_string().clone();
 	}
 
-	let global_wallet_args = arg_parse!(parse_global_args(&wallet_config, &wallet_args));
+	let mut global_wallet_args = arg_parse!(parse_global_args(&wallet_config, &wallet_args));
+	global_wallet_args.audit_log = audit_log;
 
 	node_client.set_node_url(&wallet_config.check_node_api_http_addr);
 	node_client.set_node_api_secret(global_wallet_args.node_api_secret.clone());
+	node_client.set_chain_type(wallet_config.chain_type());
+	command::select_node_by_quorum(&mut node_client, &wallet_config)?;
+	command::check_custom_chain(&node_client, &wallet_config)?;
 
 	// legacy hack to avoid the need for changes in existing grin-wallet.toml files
 	// remove `wallet_data` from end of path as
@@ -857,6 +1006,7 @@ where
 		true => {
 			let mut wallet_lock = wallet.lock();
 			let lc = wallet_lock.lc_provider().unwrap();
+			lc.set_read_only(wallet_args.is_present("read_only"));
 			let mask = lc.open_wallet(
 				None,
 				prompt_password(&global_wallet_args.password),
@@ -882,7 +1032,11 @@ where
 				&global_wallet_args,
 				&args
 			));
-			command::init(wallet, &global_wallet_args, a)
+			command::init(wallet, &global_wallet_args, a).map(|r| {
+				if let Some(phrase) = r.recovery_phrase {
+					display::recovery_phrase(&phrase);
+				}
+			})
 		}
 		("recover", Some(args)) => {
 			let a = arg_parse!(parse_recover_args(
@@ -890,7 +1044,11 @@ where
 				&global_wallet_args,
 				&args
 			));
-			command::recover(wallet, a)
+			command::recover(wallet, a).map(|r| {
+				if let Some(phrase) = r.recovery_phrase {
+					display::recovery_phrase(&phrase);
+				}
+			})
 		}
 		("listen", Some(args)) => {
 			let mut c = wallet_config.clone();
@@ -908,38 +1066,73 @@ where
 			let mut g = global_wallet_args.clone();
 			g.tls_conf = None;
 			arg_parse!(parse_owner_api_args(&mut c, &args));
-			command::owner_api(wallet, keychain_mask, &c, &g)
+			let shutdown = shutdown_on_signal()?;
+			command::owner_api(wallet, keychain_mask, &c, &g, shutdown)
 		}
 		("web", Some(_)) => {
-			command::owner_api(wallet, keychain_mask, &wallet_config, &global_wallet_args)
+			let shutdown = shutdown_on_signal()?;
+			command::owner_api(
+				wallet,
+				keychain_mask,
+				&wallet_config,
+				&global_wallet_args,
+				shutdown,
+			)
+		}
+		("daemon", Some(args)) => {
+			let a = arg_parse!(parse_daemon_args(&args));
+			let handle = DaemonHandle::new(shutdown_on_signal()?);
+			daemon::daemon(
+				wallet,
+				keychain_mask,
+				&wallet_config,
+				&global_wallet_args,
+				&a,
+				handle,
+			)
 		}
 		("account", Some(args)) => {
 			let a = arg_parse!(parse_account_args(&args));
 			command::account(wallet, km, a)
 		}
 		("send", Some(args)) => {
-			let a = arg_parse!(parse_send_args(&args));
-			command::send(
-				wallet,
-				km,
-				a,
-				wallet_config.dark_background_color_scheme.unwrap_or(true),
-			)
+			let min_conf = wallet_config.spend_minimum_confirmations(&global_wallet_args.account);
+			let mut a = arg_parse!(parse_send_args(&args, min_conf));
+			if a.target_slate_version.is_none() {
+				a.target_slate_version = wallet_config.pinned_slate_version(&a.dest);
+			}
+			let amount = a.amount;
+			command::send(wallet, km, a).map(|r| {
+				if !r.estimates.is_empty() {
+					let estimates = r
+						.estimates
+						.iter()
+						.map(|e| (e.strategy.as_str(), e.total, e.fee))
+						.collect();
+					display::estimate(
+						amount,
+						estimates,
+						&wallet_config.currency(),
+						wallet_config.dark_background_color_scheme.unwrap_or(true),
+					);
+				}
+			})
 		}
 		("receive", Some(args)) => {
 			let a = arg_parse!(parse_receive_args(&args));
-			command::receive(wallet, km, &global_wallet_args, a)
+			command::receive(wallet, km, &global_wallet_args, a).map(|_| ())
 		}
 		("finalize", Some(args)) => {
 			let a = arg_parse!(parse_finalize_args(&args));
-			command::finalize(wallet, km, a)
+			command::finalize(wallet, km, a).map(|_| ())
 		}
 		("invoice", Some(args)) => {
 			let a = arg_parse!(parse_issue_invoice_args(&args));
 			command::issue_invoice_tx(wallet, km, a)
 		}
 		("pay", Some(args)) => {
-			let a = arg_parse!(parse_process_invoice_args(&args, !test_mode));
+			let min_conf = wallet_config.spend_minimum_confirmations(&global_wallet_args.account);
+			let a = arg_parse!(parse_process_invoice_args(&args, min_conf, !test_mode));
 			command::process_invoice(
 				wallet,
 				km,
@@ -948,7 +1141,8 @@ where
 			)
 		}
 		("info", Some(args)) => {
-			let a = arg_parse!(parse_info_args(&args));
+			let min_conf = wallet_config.display_minimum_confirmations(&global_wallet_args.account);
+			let a = arg_parse!(parse_info_args(&args, min_conf));
 			command::info(
 				wallet,
 				km,
@@ -957,6 +1151,23 @@ where
 				wallet_config.dark_background_color_scheme.unwrap_or(true),
 			)
 		}
+		("stats", Some(_)) => command::stats(wallet, km),
+		("reconcile", Some(args)) => {
+			let a = arg_parse!(parse_reconcile_args(&args));
+			command::reconcile(wallet, km, &global_wallet_args, a)
+		}
+		("check_recipient", Some(args)) => {
+			let a = arg_parse!(parse_check_recipient_args(&args));
+			command::check_recipient(wallet, km, a)
+		}
+		("view_bundle", Some(args)) => {
+			let a = arg_parse!(parse_view_bundle_args(&args));
+			command::view_bundle(wallet, km, a)
+		}
+		("backup", Some(args)) => {
+			let a = arg_parse!(parse_backup_args(&args));
+			command::backup(wallet, km, &wallet_config, a)
+		}
 		("outputs", Some(_)) => command::outputs(
 			wallet,
 			km,
@@ -981,7 +1192,10 @@ where
 			let a = arg_parse!(parse_cancel_args(&args));
 			command::cancel(wallet, km, a)
 		}
-		("restore", Some(_)) => command::restore(wallet, km),
+		("restore", Some(args)) => {
+			let a = arg_parse!(parse_restore_args(&args));
+			command::restore(wallet, km, a)
+		}
 		("check", Some(args)) => {
 			let a = arg_parse!(parse_check_args(&args));
 			command::check_repair(wallet, km, a)
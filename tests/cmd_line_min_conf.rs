@@ -0,0 +1,159 @@
+// Copyright 2019 The Grin Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test the confirmations configured for the command line, per account
+#[macro_use]
+extern crate clap;
+
+#[macro_use]
+extern crate log;
+
+extern crate grin_wallet;
+
+use grin_wallet::cmd::wallet_args;
+use grin_wallet_config::{AccountConfirmations, WalletConfig};
+use grin_wallet_impls::test_framework::{self, LocalWalletClient, WalletProxy};
+
+use clap::App;
+use std::collections::BTreeMap;
+use std::thread;
+use std::time::Duration;
+
+use grin_wallet_impls::DefaultLCProvider;
+use grin_wallet_util::grin_keychain::ExtKeychain;
+
+mod common;
+use common::{clean_output_dir, execute_command, initial_setup_wallet, instantiate_wallet, setup};
+
+/// Run a command with the given configuration rather than the wallet's file
+fn execute_with_config(
+	app: &App,
+	config: &WalletConfig,
+	client: &LocalWalletClient,
+	arg_vec: Vec<&str>,
+) -> Result<String, grin_wallet_controller::Error> {
+	let args = app.clone().get_matches_from(arg_vec);
+	let mut config = config.clone();
+	config.chain_type = None;
+	wallet_args::wallet_command(&args, config, None, client.clone(), true, |_| {})
+}
+
+/// Arguments of a send by file, with the confirmations given if any
+fn send_args<'a>(file_name: &'a str, min_conf: Option<&'a str>) -> Vec<&'a str> {
+	let mut arg_vec = vec!["grin-wallet", "-p", "password", "send", "-m", "file"];
+	if let Some(c) = min_conf {
+		arg_vec.extend(vec!["-c", c]);
+	}
+	arg_vec.extend(vec!["-d", file_name, "1"]);
+	arg_vec
+}
+
+/// min conf tests
+fn min_conf_test_impl(test_dir: &str) -> Result<(), grin_wallet_controller::Error> {
+	setup(test_dir);
+	// Create a new proxy to simulate server and wallet responses
+	let mut wallet_proxy: WalletProxy<
+		DefaultLCProvider<LocalWalletClient, ExtKeychain>,
+		LocalWalletClient,
+		ExtKeychain,
+	> = WalletProxy::new(test_dir);
+	let chain = wallet_proxy.chain.clone();
+
+	let yml = load_yaml!("../src/bin/grin-wallet.yml");
+	let app = App::from_yaml(yml);
+
+	let arg_vec = vec!["grin-wallet", "-p", "password", "init", "-h"];
+	let client1 = LocalWalletClient::new("wallet1", wallet_proxy.tx.clone());
+	execute_command(&app, test_dir, "wallet1", &client1, arg_vec)?;
+
+	let config1 = initial_setup_wallet(test_dir, "wallet1");
+	let (wallet1, mask1_i) =
+		instantiate_wallet(config1.clone(), client1.clone(), "password", "default")?;
+	let mask1 = (&mask1_i).as_ref();
+	wallet_proxy.add_wallet(
+		"wallet1",
+		client1.get_send_instance(),
+		wallet1.clone(),
+		mask1_i.clone(),
+	);
+
+	// Set the wallet proxy listener running
+	thread::spawn(move || {
+		if let Err(e) = wallet_proxy.run() {
+			error!("Wallet Proxy error: {}", e);
+		}
+	});
+
+	let _ = test_framework::award_blocks_to_wallet(&chain, wallet1.clone(), mask1, 10, false);
+
+	// The confirmations of an account take over the wallet's, those it doesn't
+	// set being the wallet's
+	let mut accounts = BTreeMap::new();
+	accounts.insert(
+		"default".to_owned(),
+		AccountConfirmations {
+			display: None,
+			spend: Some(100),
+		},
+	);
+	let config = WalletConfig {
+		display_minimum_confirmations: Some(3),
+		spend_minimum_confirmations: Some(1),
+		account_confirmations: Some(accounts),
+		..config1.clone()
+	};
+	assert_eq!(config.spend_minimum_confirmations("default"), 100);
+	assert_eq!(config.display_minimum_confirmations("default"), 3);
+	assert_eq!(config.spend_minimum_confirmations("other"), 1);
+	assert_eq!(config.display_minimum_confirmations("other"), 3);
+	let unset = WalletConfig {
+		display_minimum_confirmations: None,
+		spend_minimum_confirmations: None,
+		..config1.clone()
+	};
+	assert_eq!(unset.spend_minimum_confirmations("default"), 10);
+	assert_eq!(unset.display_minimum_confirmations("default"), 10);
+
+	// No output of the account has the confirmations configured to spend it
+	let file_name = format!("{}/tx1.part_tx", test_dir);
+	assert!(execute_with_config(&app, &config, &client1, send_args(&file_name, None)).is_err());
+
+	// unless given on the command line
+	execute_with_config(&app, &config, &client1, send_args(&file_name, Some("1")))?;
+
+	// Without the account's, the wallet's confirmations are used
+	let config = WalletConfig {
+		spend_minimum_confirmations: Some(100),
+		..config1.clone()
+	};
+	assert!(execute_with_config(&app, &config, &client1, send_args(&file_name, None)).is_err());
+	let config = WalletConfig {
+		spend_minimum_confirmations: Some(1),
+		..config1.clone()
+	};
+	execute_with_config(&app, &config, &client1, send_args(&file_name, None))?;
+
+	// let logging finish
+	thread::sleep(Duration::from_millis(200));
+	clean_output_dir(test_dir);
+	Ok(())
+}
+
+#[test]
+fn wallet_command_line_min_conf() {
+	let test_dir = "target/test_output/command_line_min_conf";
+	if let Err(e) = min_conf_test_impl(test_dir) {
+		panic!("Libwallet Error: {} - {}", e, e.backtrace().unwrap());
+	}
+}
@@ -250,7 +250,7 @@ pub fn execute_command(
 	let mut config = initial_setup_wallet(test_dir, wallet_name);
 	//unset chain type so it doesn't get reset
 	config.chain_type = None;
-	wallet_args::wallet_command(&args, config.clone(), client.clone(), true, |_| {})
+	wallet_args::wallet_command(&args, config.clone(), None, client.clone(), true, |_| {})
 }
 
 // as above, but without necessarily setting up the wallet
@@ -287,7 +287,7 @@ where
 	wallet_config.chain_type = None;
 	wallet_config.api_secret_path = None;
 	wallet_config.node_api_secret_path = None;
-	wallet_args::wallet_command(&args, wallet_config, client.clone(), true, f)
+	wallet_args::wallet_command(&args, wallet_config, None, client.clone(), true, f)
 }
 
 pub fn post<IN>(url: &Url, api_secret: Option<String>, input: &IN) -> Result<String, api::Error>